@@ -0,0 +1,160 @@
+// Throughput benchmark for the tokenizer hot loop
+// Run with: cargo bench --bench tokenize
+// No benchmark framework is needed: every case is run a few times and the best time is kept.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use tokenizer_rust::intern::Interner;
+use tokenizer_rust::Tokenizer;
+
+/// English prose, repeated to get a few megabytes of text
+const PROSE: &str =
+    "It was the best of times, it was the worst of times, it was the age of wisdom, \
+it was the age of foolishness, it was the epoch of belief, it was the epoch of incredulity. \
+There were a king with a large jaw and a queen with a plain face, on the throne of England; \
+there were a king with a large jaw and a queen with a fair face, on the throne of France. \
+\"Don't worry,\" she said (twice) - and 1,775 people listened!\n";
+
+/// Mixed-script text with many non-ASCII characters
+const MIXED: &str =
+    "Crème brûlée, naïve café! Ελληνικά κείμενα; русский текст. 日本語のテキスト。 ¿Qué tal?\n";
+
+/// Runs `function` several times and returns the fastest run
+fn best_of<F: FnMut()>(runs: usize, mut function: F) -> Duration {
+    (0..runs)
+        .map(|_| {
+            let start = Instant::now();
+            function();
+            start.elapsed()
+        })
+        .min()
+        .unwrap_or_default()
+}
+
+/// Prints the throughput of one case
+fn report(name: &str, text: &str, time: Duration) {
+    let megabytes = text.len() as f64 / 1_000_000.0;
+    println!(
+        "{:<24} {:>8.2} MB in {:>8.2?}  ->  {:>8.1} MB/s",
+        name,
+        megabytes,
+        time,
+        megabytes / time.as_secs_f64()
+    );
+}
+
+/// Measures and prints the throughput of tokenizing one text
+fn bench(name: &str, text: &str) {
+    let tokenizer = Tokenizer::new(String::new());
+    let time = best_of(10, || {
+        black_box(tokenizer.tokenize_text(black_box(text)));
+    });
+    report(name, text, time);
+}
+
+/// Measures and prints the throughput of tokenizing into tokens that borrow their text
+fn bench_borrowed(name: &str, text: &str) {
+    let tokenizer = Tokenizer::new(String::new());
+    let time = best_of(10, || {
+        black_box(tokenizer.tokenize_borrowed(black_box(text)).count());
+    });
+    report(&format!("{} (borrowed)", name), text, time);
+}
+
+/// Measures and prints the throughput of tokenizing a corpus of 64 KiB documents, with owned
+/// and with borrowed tokens
+///
+/// One big text is dominated by the page faults of its multi-megabyte token vector; with
+/// documents the allocator reuses the memory of the previous vector, like in a real corpus.
+fn bench_documents(name: &str, text: &str) {
+    const DOCUMENT_SIZE: usize = 64 * 1024;
+
+    let mut documents = Vec::new();
+    let mut start = 0;
+    while start < text.len() {
+        let mut end = (start + DOCUMENT_SIZE).min(text.len());
+        while !text.is_char_boundary(end) {
+            end += 1;
+        }
+        documents.push(&text[start..end]);
+        start = end;
+    }
+
+    let tokenizer = Tokenizer::new(String::new());
+    let time = best_of(10, || {
+        for document in &documents {
+            black_box(tokenizer.tokenize_text(black_box(document)));
+        }
+    });
+    report(&format!("{} (documents)", name), text, time);
+    let time = best_of(10, || {
+        for document in &documents {
+            black_box(tokenizer.tokenize_borrowed(black_box(document)).count());
+        }
+    });
+    report(&format!("{} (docs, borrowed)", name), text, time);
+}
+
+/// Measures and prints the throughput of counting tokens without creating them
+fn bench_count(name: &str, text: &str) {
+    let tokenizer = Tokenizer::new(String::new());
+    let time = best_of(10, || {
+        black_box(tokenizer.count_tokens(black_box(text)));
+    });
+    report(&format!("{} (count)", name), text, time);
+}
+
+/// Measures and prints the throughput of tokenizing with an interner reused between runs
+/// (like one interner shared by a whole corpus)
+fn bench_interned(name: &str, text: &str) {
+    let tokenizer = Tokenizer::new(String::new());
+    let mut interner = Interner::new();
+    let time = best_of(10, || {
+        black_box(tokenizer.tokenize_interned(black_box(text), &mut interner));
+    });
+    report(&format!("{} (interned)", name), text, time);
+}
+
+/// Measures and prints the throughput of tokenizing a memory-mapped file
+///
+/// Tokens borrow their text from the file, so this measures the scanner alone,
+/// without one String allocation per token.
+#[cfg(feature = "mmap")]
+fn bench_mapped(name: &str, text: &str) {
+    let path = std::env::temp_dir().join(format!("tokenizer_bench_{}.txt", name));
+    std::fs::write(&path, text).expect("cannot write the benchmark file");
+
+    let tokenizer = Tokenizer::new(String::new());
+    let file = tokenizer
+        .tokenize_file(&path)
+        .expect("cannot map the benchmark file");
+    let time = best_of(10, || {
+        black_box(file.tokens().count());
+    });
+    report(&format!("{} (mmap)", name), text, time);
+
+    let _ = std::fs::remove_file(&path);
+}
+
+fn main() {
+    let prose = PROSE.repeat(8_000);
+    let mixed = MIXED.repeat(30_000);
+
+    bench("prose", &prose);
+    bench("mixed", &mixed);
+    bench_borrowed("prose", &prose);
+    bench_borrowed("mixed", &mixed);
+    bench_documents("prose", &prose);
+    bench_documents("mixed", &mixed);
+    bench_interned("prose", &prose);
+    bench_interned("mixed", &mixed);
+    bench_count("prose", &prose);
+    bench_count("mixed", &mixed);
+
+    #[cfg(feature = "mmap")]
+    {
+        bench_mapped("prose", &prose);
+        bench_mapped("mixed", &mixed);
+    }
+}
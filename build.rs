@@ -0,0 +1,58 @@
+// Build script
+// With the `ffi` feature, generates the C header of src/ffi.rs with cbindgen (configured in
+// cbindgen.toml) into OUT_DIR. The header is committed as include/tokenizer_rust.h, so C users
+// don't need cbindgen: the build compares the generated header with it and warns when it is
+// out of date. scripts/update_header.sh copies the generated header over the committed one,
+// and `scripts/update_header.sh --check` (TOKENIZER_RUST_CHECK_HEADER=1) fails the build
+// instead of warning, for CI.
+
+fn main() {
+    #[cfg(feature = "ffi")]
+    {
+        println!("cargo:rerun-if-changed=src/ffi.rs");
+        println!("cargo:rerun-if-changed=cbindgen.toml");
+        println!("cargo:rerun-if-changed=include/tokenizer_rust.h");
+        println!("cargo:rerun-if-env-changed=TOKENIZER_RUST_CHECK_HEADER");
+        let strict = std::env::var_os("TOKENIZER_RUST_CHECK_HEADER").is_some();
+        if let Err(message) = check_header() {
+            if strict {
+                eprintln!("error: {}", message);
+                std::process::exit(1);
+            }
+            println!("cargo:warning={}", message);
+        }
+    }
+    println!("cargo:rerun-if-changed=build.rs");
+}
+
+/// Generates the C header into OUT_DIR and compares it with include/tokenizer_rust.h
+///
+/// # Returns
+/// An error message if the header cannot be generated or the committed one is out of date
+#[cfg(feature = "ffi")]
+fn check_header() -> Result<(), String> {
+    let directory = std::env::var("CARGO_MANIFEST_DIR").map_err(|error| error.to_string())?;
+    let out_dir = std::env::var("OUT_DIR").map_err(|error| error.to_string())?;
+
+    // STEP 1: Generate the header from src/ffi.rs
+    let config = cbindgen::Config::from_file(format!("{}/cbindgen.toml", directory))
+        .map_err(|error| format!("cannot read cbindgen.toml: {}", error))?;
+    let generated = format!("{}/tokenizer_rust.h", out_dir);
+    cbindgen::Builder::new()
+        .with_src(format!("{}/src/ffi.rs", directory))
+        .with_config(config)
+        .generate()
+        .map_err(|error| format!("cannot generate the C header of src/ffi.rs: {}", error))?
+        .write_to_file(&generated);
+
+    // STEP 2: Compare it with the committed header
+    let committed = std::fs::read(format!("{}/include/tokenizer_rust.h", directory));
+    let fresh = std::fs::read(&generated).map_err(|error| format!("{}: {}", generated, error))?;
+    if committed.ok().as_deref() != Some(fresh.as_slice()) {
+        return Err(format!(
+            "include/tokenizer_rust.h is out of date with src/ffi.rs, run scripts/update_header.sh (generated header: {})",
+            generated
+        ));
+    }
+    Ok(())
+}
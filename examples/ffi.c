@@ -0,0 +1,49 @@
+/*
+ * Using the tokenizer from C through include/tokenizer_rust.h
+ *     cargo build --release --features ffi
+ *     cc examples/ffi.c -Iinclude -Ltarget/release -ltokenizer_rust -o ffi
+ *     LD_LIBRARY_PATH=target/release ./ffi [MODEL]
+ * MODEL is a tokenizer saved by `tokenizer build-vocab`, to print the ids of the text too.
+ */
+
+#include <stdio.h>
+#include <string.h>
+
+#include "tokenizer_rust.h"
+
+int main(int argc, char **argv) {
+    const char *text = "Hello, world! Tokenizers are fun.";
+
+    /* Load the saved tokenizer, or use the default rules */
+    TokenizerHandle *tokenizer = argc > 1 ? tokenizer_from_file(argv[1]) : tokenizer_new();
+    if (tokenizer == NULL) {
+        fprintf(stderr, "error: %s\n", tokenizer_last_error());
+        return 1;
+    }
+
+    /* Print every token with its byte offsets in the text */
+    TokenizerTokens tokens;
+    if (tokenizer_tokenize(tokenizer, text, strlen(text), &tokens) != 0) {
+        fprintf(stderr, "error: %s\n", tokenizer_last_error());
+        tokenizer_free(tokenizer);
+        return 1;
+    }
+    for (size_t i = 0; i < tokens.len; i++) {
+        printf("%zu..%zu\t%s\n", tokens.starts[i], tokens.ends[i], tokens.tokens[i]);
+    }
+    tokenizer_tokens_free(tokens);
+
+    /* Print the ids of the text in the vocabulary of the saved tokenizer */
+    if (argc > 1) {
+        TokenizerIds ids;
+        if (tokenizer_encode(tokenizer, text, strlen(text), &ids) == 0) {
+            for (size_t i = 0; i < ids.len; i++) {
+                printf("%u%c", ids.ids[i], i + 1 < ids.len ? ' ' : '\n');
+            }
+            tokenizer_ids_free(ids);
+        }
+    }
+
+    tokenizer_free(tokenizer);
+    return 0;
+}
@@ -0,0 +1,126 @@
+/* C interface of the tokenizer_rust library (build it with `cargo build --release --features ffi`) */
+
+#ifndef TOKENIZER_RUST_H
+#define TOKENIZER_RUST_H
+
+/* Generated by cbindgen from src/ffi.rs: change that file, not this one */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// A tokenizer, opaque to C
+typedef struct TokenizerHandle TokenizerHandle;
+
+// The tokens of a text: their UTF-8 strings and their byte offsets in the text
+typedef struct TokenizerTokens {
+  // The text of every token, as NUL-terminated UTF-8 (a token that contains a NUL byte is
+  // cut before it)
+  char **tokens;
+  // The byte offset where every token starts in the text
+  size_t *starts;
+  // The byte offset where every token ends in the text
+  size_t *ends;
+  // The number of tokens
+  size_t len;
+} TokenizerTokens;
+
+// The vocabulary ids of a text
+typedef struct TokenizerIds {
+  // The ids
+  uint32_t *ids;
+  // The number of ids
+  size_t len;
+} TokenizerIds;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Creates a tokenizer with the default rules and no vocabulary
+//
+// # Returns
+// A tokenizer to free with tokenizer_free()
+struct TokenizerHandle *tokenizer_new(void);
+
+// Loads a tokenizer written by Tokenizer::save() (or `tokenizer build-vocab`), with its special
+// tokens, vocabulary and template
+//
+// # Returns
+// A tokenizer to free with tokenizer_free(), or NULL if the file can't be read or is not a
+// tokenizer (see tokenizer_last_error())
+//
+// # Safety
+// `path` must be NULL or a NUL-terminated string.
+struct TokenizerHandle *tokenizer_from_file(const char *path);
+
+// Frees a tokenizer (NULL is ignored)
+//
+// # Safety
+// `tokenizer` must be NULL or a tokenizer from tokenizer_new() or tokenizer_from_file()
+// that was not freed yet.
+void tokenizer_free(struct TokenizerHandle *tokenizer);
+
+// Tokenizes a text
+//
+// # Arguments
+// * `tokenizer` - The tokenizer
+// * `text` - The UTF-8 bytes of the text
+// * `length` - The number of bytes of the text
+// * `tokens` - Where the tokens are written, to free with tokenizer_tokens_free()
+//
+// # Returns
+// 0, or -1 if an argument is NULL or the text is not UTF-8 (see tokenizer_last_error())
+//
+// # Safety
+// `tokenizer` must be a live tokenizer, `text` must point to `length` readable bytes (or be
+// NULL with a length of 0), and `tokens` must point to a writable TokenizerTokens.
+int32_t tokenizer_tokenize(const struct TokenizerHandle *tokenizer,
+                           const char *text,
+                           size_t length,
+                           struct TokenizerTokens *tokens);
+
+// Frees the tokens returned by tokenizer_tokenize()
+//
+// # Safety
+// `tokens` must come from tokenizer_tokenize() and not be freed yet.
+void tokenizer_tokens_free(struct TokenizerTokens tokens);
+
+// Encodes a text into the ids of the tokenizer's vocabulary, with the special tokens of its
+// template
+//
+// # Arguments
+// * `tokenizer` - The tokenizer
+// * `text` - The UTF-8 bytes of the text
+// * `length` - The number of bytes of the text
+// * `ids` - Where the ids are written, to free with tokenizer_ids_free()
+//
+// # Returns
+// 0, or -1 if an argument is NULL or the text is not UTF-8 (see tokenizer_last_error())
+//
+// # Safety
+// `tokenizer` must be a live tokenizer, `text` must point to `length` readable bytes (or be
+// NULL with a length of 0), and `ids` must point to a writable TokenizerIds.
+int32_t tokenizer_encode(const struct TokenizerHandle *tokenizer,
+                         const char *text,
+                         size_t length,
+                         struct TokenizerIds *ids);
+
+// Frees the ids returned by tokenizer_encode()
+//
+// # Safety
+// `ids` must come from tokenizer_encode() and not be freed yet.
+void tokenizer_ids_free(struct TokenizerIds ids);
+
+// Returns the message of the last failure on this thread, or NULL if nothing failed
+//
+// The message stays valid until the next failure on the same thread.
+const char *tokenizer_last_error(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* TOKENIZER_RUST_H */
@@ -0,0 +1,19 @@
+#!/bin/sh
+# Updates include/tokenizer_rust.h, the committed C header of src/ffi.rs
+# build.rs generates the header into OUT_DIR when the crate is built with the `ffi` feature;
+# this script copies the newest generated header over the committed one.
+#     scripts/update_header.sh            regenerate and copy the header
+#     scripts/update_header.sh --check    fail if the committed header is out of date (for CI)
+set -e
+cd "$(dirname "$0")/.."
+
+if [ "$1" = "--check" ]; then
+    TOKENIZER_RUST_CHECK_HEADER=1 cargo build --features ffi
+    exit 0
+fi
+
+cargo build --features ffi
+target_dir="${CARGO_TARGET_DIR:-target}"
+generated=$(ls -t "$target_dir"/debug/build/Tokenizer-Rust-*/out/tokenizer_rust.h | head -n 1)
+cp "$generated" include/tokenizer_rust.h
+echo "include/tokenizer_rust.h updated from $generated"
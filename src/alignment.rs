@@ -0,0 +1,119 @@
+// Alignment between text spans and token indices
+// Annotations made by other tools (named entities, error spans, highlights) come as
+// character or byte offsets into the text. To use them with this tokenizer they have to be
+// projected onto token indices, and labels predicted per token have to go back to text
+// spans. Token offsets are byte offsets, so character offsets go through a table of the
+// byte offset of every character.
+
+use crate::token::Token;
+
+/// Maps spans of a text to the tokens that cover them, and back
+///
+/// Spans and token ranges are (start, end) pairs with an exclusive end.
+#[derive(Debug, Clone)]
+pub struct TokenAlignment {
+    /// The (start, end) byte offsets of every token, in text order
+    tokens: Vec<(usize, usize)>,
+    /// The byte offset of every character, followed by the length of the text
+    char_offsets: Vec<usize>,
+}
+
+impl TokenAlignment {
+    /// Builds the alignment of a text and its tokens
+    ///
+    /// # Arguments
+    /// * `text` - The text that was tokenized
+    /// * `tokens` - Its tokens, as returned by tokenize_text()
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::alignment::TokenAlignment;
+    ///
+    /// let text = "Café Müller opened in 1999.";
+    /// let tokens = Tokenizer::new(String::new()).tokenize_text(text);
+    /// let alignment = TokenAlignment::new(text, &tokens);
+    ///
+    /// // An entity annotated by character offsets: "Café Müller" is characters 0 to 11
+    /// assert_eq!(alignment.char_span_to_tokens(0, 11), Some((0, 2)));
+    /// // A span cutting into a token covers the whole token
+    /// assert_eq!(alignment.char_span_to_tokens(22, 24), Some((4, 5)));
+    /// // And back: token 4 ("1999") is characters 22 to 26, bytes 24 to 28
+    /// assert_eq!(alignment.tokens_to_char_span(4, 5), Some((22, 26)));
+    /// assert_eq!(alignment.tokens_to_byte_span(4, 5), Some((24, 28)));
+    /// ```
+    pub fn new(text: &str, tokens: &[Token]) -> Self {
+        let mut char_offsets: Vec<usize> = text.char_indices().map(|(offset, _)| offset).collect();
+        char_offsets.push(text.len());
+
+        TokenAlignment {
+            tokens: tokens
+                .iter()
+                .map(|token| (token.start, token.end))
+                .collect(),
+            char_offsets,
+        }
+    }
+
+    /// Returns the number of tokens
+    pub fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    /// Returns true if the text has no tokens
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// Returns the range of tokens that overlap a byte span of the text
+    ///
+    /// # Returns
+    /// The (first, end) token indices, or None if no token overlaps the span (an empty
+    /// span, or one that only covers whitespace)
+    pub fn byte_span_to_tokens(&self, start: usize, end: usize) -> Option<(usize, usize)> {
+        let first = self
+            .tokens
+            .partition_point(|&(_, token_end)| token_end <= start);
+        let last = self
+            .tokens
+            .partition_point(|&(token_start, _)| token_start < end);
+        (first < last).then_some((first, last))
+    }
+
+    /// Returns the range of tokens that overlap a character span of the text
+    ///
+    /// # Returns
+    /// The (first, end) token indices, or None if no token overlaps the span or the span
+    /// is outside the text
+    pub fn char_span_to_tokens(&self, start: usize, end: usize) -> Option<(usize, usize)> {
+        self.byte_span_to_tokens(self.char_to_byte(start)?, self.char_to_byte(end)?)
+    }
+
+    /// Returns the byte span of a range of tokens, from the start of the first to the end
+    /// of the last one (None for an empty or out of bounds range)
+    pub fn tokens_to_byte_span(&self, first: usize, end: usize) -> Option<(usize, usize)> {
+        if first >= end || end > self.tokens.len() {
+            return None;
+        }
+        Some((self.tokens[first].0, self.tokens[end - 1].1))
+    }
+
+    /// Returns the character span of a range of tokens (None for an empty or out of
+    /// bounds range)
+    pub fn tokens_to_char_span(&self, first: usize, end: usize) -> Option<(usize, usize)> {
+        let (start, end) = self.tokens_to_byte_span(first, end)?;
+        Some((self.byte_to_char(start)?, self.byte_to_char(end)?))
+    }
+
+    /// Returns the byte offset of a character offset (the length of the text for the
+    /// character count, None past it)
+    pub fn char_to_byte(&self, char_offset: usize) -> Option<usize> {
+        self.char_offsets.get(char_offset).copied()
+    }
+
+    /// Returns the character offset of a byte offset (None if the byte offset is inside
+    /// a character or past the end of the text)
+    pub fn byte_to_char(&self, byte_offset: usize) -> Option<usize> {
+        self.char_offsets.binary_search(&byte_offset).ok()
+    }
+}
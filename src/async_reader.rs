@@ -0,0 +1,105 @@
+// Async streaming tokenization (only compiled with the `tokio` feature)
+// The same chunked tokenization as the stream module, but reading from a tokio AsyncBufRead
+// and yielding the tokens as a Stream, so an async ingestion service never blocks its runtime
+// waiting for input. Tokenizing one chunk is quick and done inline.
+
+use std::io;
+use std::ops::Deref;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures_core::Stream;
+use tokio::io::AsyncBufRead;
+
+use crate::stream::Chunker;
+use crate::token::Token;
+use crate::Tokenizer;
+
+/// A Stream over the tokens of an async reader, created by Tokenizer::tokenize_async()
+///
+/// `T` is anything that gives access to a Tokenizer: a `&Tokenizer`, or an `Arc<Tokenizer>`
+/// when the stream has to be moved into a spawned task.
+/// Token offsets are byte offsets from the start of the stream. Reading errors and
+/// invalid UTF-8 come out as `Err` items, after which the stream ends.
+pub struct AsyncTokenStream<T, R> {
+    /// The tokenizer whose settings are used
+    tokenizer: T,
+    /// Where the text comes from
+    reader: R,
+    /// The bytes and tokens waiting to be returned
+    chunker: Chunker,
+    /// true once the end of the input (or an error) was reached
+    finished: bool,
+}
+
+impl<T, R> AsyncTokenStream<T, R>
+where
+    T: Deref<Target = Tokenizer>,
+    R: AsyncBufRead + Unpin,
+{
+    /// Creates a token stream
+    ///
+    /// # Arguments
+    /// * `tokenizer` - The tokenizer to use, for example an `Arc<Tokenizer>`
+    /// * `reader` - Where the text comes from
+    pub fn new(tokenizer: T, reader: R) -> Self {
+        AsyncTokenStream {
+            tokenizer,
+            reader,
+            chunker: Chunker::default(),
+            finished: false,
+        }
+    }
+}
+
+impl<T, R> Stream for AsyncTokenStream<T, R>
+where
+    T: Deref<Target = Tokenizer> + Unpin,
+    R: AsyncBufRead + Unpin,
+{
+    type Item = io::Result<Token>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            // STEP 1: Hand out the tokens that are ready
+            if let Some(token) = this.chunker.pending.pop_front() {
+                return Poll::Ready(Some(Ok(token)));
+            }
+            if this.finished {
+                return Poll::Ready(None);
+            }
+
+            // STEP 2: Wait for more input
+            let bytes = match Pin::new(&mut this.reader).poll_fill_buf(cx) {
+                Poll::Ready(Ok(bytes)) => bytes,
+                Poll::Ready(Err(error)) => return Poll::Ready(Some(Err(this.fail(error)))),
+                Poll::Pending => return Poll::Pending,
+            };
+
+            // STEP 3: Tokenize what can be tokenized (everything at the end of the input)
+            let length = bytes.len();
+            let result = if length == 0 {
+                this.finished = true;
+                this.chunker.finish(&this.tokenizer)
+            } else {
+                this.chunker.push(&this.tokenizer, bytes)
+            };
+            Pin::new(&mut this.reader).consume(length);
+
+            if let Err(error) = result {
+                return Poll::Ready(Some(Err(this.fail(error))));
+            }
+        }
+    }
+}
+
+impl<T, R> AsyncTokenStream<T, R> {
+    /// Ends the stream after an error
+    fn fail(&mut self, error: io::Error) -> io::Error {
+        self.finished = true;
+        self.chunker.clear();
+        error
+    }
+}
@@ -0,0 +1,250 @@
+// Bidirectional (bidi) text metadata
+// Text that mixes left-to-right scripts (English) with right-to-left scripts (Hebrew, Arabic)
+// is stored in "logical" order but displayed in a different "visual" order.
+// This module gives every token an embedding level, like the Unicode Bidirectional Algorithm does,
+// so renderers and analysts can work out the display order of the tokens.
+//
+// This is a simplified, token-level version of the algorithm: explicit embedding characters
+// (LRE, RLO, isolates, ...) are not supported, and the direction of a token is decided by its
+// first strong character.
+
+use crate::token::Token;
+use crate::tokenizer::Tokenizer;
+
+/// The writing direction of a single token
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Left-to-right letters (Latin, Greek, Cyrillic, CJK, ...)
+    LeftToRight,
+    /// Right-to-left letters (Hebrew, Arabic, Syriac, Thaana, N'Ko)
+    RightToLeft,
+    /// Digits: they are always written left-to-right, but they don't change the direction around them
+    Number,
+    /// Punctuation and symbols: they take the direction of the text around them
+    Neutral,
+}
+
+/// A token annotated with its bidi metadata
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BidiToken {
+    /// The token itself (text, kind and offsets in logical order)
+    pub token: Token,
+    /// The direction of the token after neutrals and numbers have been resolved
+    pub direction: Direction,
+    /// The embedding level: even levels are displayed left-to-right, odd levels right-to-left
+    pub level: u8,
+}
+
+/// Returns true if the character belongs to a right-to-left script
+fn is_right_to_left(character: char) -> bool {
+    matches!(character as u32,
+        0x0590..=0x08FF   // Hebrew, Arabic, Syriac, Thaana, N'Ko, Samaritan, Mandaic, Arabic Extended
+        | 0xFB1D..=0xFDFF // Hebrew and Arabic presentation forms A
+        | 0xFE70..=0xFEFF // Arabic presentation forms B
+        | 0x10800..=0x10FFF // Historic right-to-left scripts
+        | 0x1E800..=0x1EFFF // Adlam, Arabic mathematical symbols, ...
+    )
+}
+
+/// Works out the direction of a token from its characters (before resolving neutrals)
+fn token_direction(text: &str) -> Direction {
+    // The first "strong" character (a letter) decides the direction
+    for character in text.chars() {
+        if is_right_to_left(character) {
+            return Direction::RightToLeft;
+        }
+        if character.is_alphabetic() {
+            return Direction::LeftToRight;
+        }
+    }
+
+    // No letters at all: it is either a number or a neutral token
+    if text.chars().any(|c| c.is_numeric()) {
+        Direction::Number
+    } else {
+        Direction::Neutral
+    }
+}
+
+/// Returns the base (paragraph) embedding level of a text
+///
+/// The level is 1 (right-to-left) if the first letter of the text belongs to a
+/// right-to-left script, and 0 (left-to-right) otherwise.
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::bidi::paragraph_level;
+///
+/// assert_eq!(paragraph_level("Hello שלום"), 0);
+/// assert_eq!(paragraph_level("שלום Hello"), 1);
+/// ```
+pub fn paragraph_level(text: &str) -> u8 {
+    for character in text.chars() {
+        if is_right_to_left(character) {
+            return 1;
+        }
+        if character.is_alphabetic() {
+            return 0;
+        }
+    }
+    0
+}
+
+/// Tokenizes a text and annotates every token with its bidi direction and embedding level
+///
+/// The tokens are returned in logical order (the order they are stored in the text).
+/// Use visual_order() to find the order in which they are displayed.
+///
+/// # Arguments
+/// * `text` - The text to tokenize and annotate
+///
+/// # Returns
+/// One BidiToken per token
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::bidi::annotate;
+///
+/// let tokens = annotate("I said שלום עולם today");
+/// let levels: Vec<u8> = tokens.iter().map(|t| t.level).collect();
+/// // the Hebrew words are embedded at level 1 inside a left-to-right paragraph
+/// assert_eq!(levels, vec![0, 0, 1, 1, 0]);
+/// ```
+pub fn annotate(text: &str) -> Vec<BidiToken> {
+    let base_level = paragraph_level(text);
+    let base_direction = if base_level == 1 {
+        Direction::RightToLeft
+    } else {
+        Direction::LeftToRight
+    };
+
+    let tokens = Tokenizer::new(text.to_string()).tokenize_with_offsets();
+    let mut directions: Vec<Direction> = tokens.iter().map(|t| token_direction(&t.text)).collect();
+
+    // STEP 1: Numbers that follow left-to-right text behave like left-to-right text
+    let mut last_strong = base_direction;
+    for direction in directions.iter_mut() {
+        match *direction {
+            Direction::LeftToRight | Direction::RightToLeft => last_strong = *direction,
+            Direction::Number if last_strong == Direction::LeftToRight => {
+                *direction = Direction::LeftToRight
+            }
+            _ => {}
+        }
+    }
+
+    // STEP 2: Neutral tokens take the direction of the text around them
+    // If the text on both sides has the same direction, the neutral takes it,
+    // otherwise it takes the paragraph direction (numbers count as right-to-left here)
+    let strong_of = |direction: Direction| match direction {
+        Direction::Number => Some(Direction::RightToLeft),
+        Direction::Neutral => None,
+        other => Some(other),
+    };
+    let mut index = 0;
+    while index < directions.len() {
+        if directions[index] != Direction::Neutral {
+            index += 1;
+            continue;
+        }
+
+        // Find the whole run of neutral tokens
+        let run_start = index;
+        while index < directions.len() && directions[index] == Direction::Neutral {
+            index += 1;
+        }
+
+        let before = if run_start == 0 {
+            base_direction
+        } else {
+            strong_of(directions[run_start - 1]).unwrap_or(base_direction)
+        };
+        let after = if index == directions.len() {
+            base_direction
+        } else {
+            strong_of(directions[index]).unwrap_or(base_direction)
+        };
+        let resolved = if before == after {
+            before
+        } else {
+            base_direction
+        };
+
+        for direction in &mut directions[run_start..index] {
+            *direction = resolved;
+        }
+    }
+
+    // STEP 3: Turn the resolved directions into embedding levels
+    tokens
+        .into_iter()
+        .zip(directions)
+        .map(|(token, direction)| {
+            let level = match (base_level, direction) {
+                (0, Direction::RightToLeft) => 1,
+                (0, Direction::Number) => 2,
+                (0, _) => 0,
+                (_, Direction::LeftToRight) => 2,
+                (_, Direction::Number) => 2,
+                (_, _) => 1,
+            };
+            BidiToken {
+                token,
+                direction,
+                level,
+            }
+        })
+        .collect()
+}
+
+/// Computes the visual (display) order of annotated tokens
+///
+/// Starting from the highest level, every run of tokens at that level or higher
+/// is reversed, down to the lowest odd level (rule L2 of the Unicode Bidirectional Algorithm).
+///
+/// # Arguments
+/// * `tokens` - Tokens annotated by annotate(), in logical order
+///
+/// # Returns
+/// The logical indices of the tokens, in the order they appear on screen from left to right
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::bidi::{annotate, visual_order};
+///
+/// let tokens = annotate("I said שלום עולם today");
+/// // the two Hebrew words are displayed in reverse order
+/// assert_eq!(visual_order(&tokens), vec![0, 1, 3, 2, 4]);
+/// ```
+pub fn visual_order(tokens: &[BidiToken]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..tokens.len()).collect();
+
+    let highest = tokens.iter().map(|t| t.level).max().unwrap_or(0);
+    let lowest_odd = tokens
+        .iter()
+        .map(|t| t.level)
+        .filter(|level| level % 2 == 1)
+        .min()
+        .unwrap_or(highest + 1);
+
+    let mut level = highest;
+    while level >= lowest_odd && level > 0 {
+        let mut index = 0;
+        while index < order.len() {
+            if tokens[order[index]].level < level {
+                index += 1;
+                continue;
+            }
+
+            // Reverse the run of tokens at this level or higher
+            let run_start = index;
+            while index < order.len() && tokens[order[index]].level >= level {
+                index += 1;
+            }
+            order[run_start..index].reverse();
+        }
+        level -= 1;
+    }
+
+    order
+}
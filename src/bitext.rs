@@ -0,0 +1,614 @@
+// Parallel corpora (bitext) for machine translation
+// A bitext is a list of sentence pairs: line N of the source file translates line N of the
+// target file (or both sides sit on one line, separated by a tab). The two sides are often
+// tokenized differently (identifiers on the code side, another language's abbreviations),
+// so each side gets its own Tokenizer. Pairs are filtered jointly: a pair goes away as a
+// whole when one side is empty or when the lengths of the sides are too far apart, which
+// is the usual sign of a misalignment. Plausibility checks catch the other common failures:
+// numbers that do not match on both sides, and "translations" that are a copy of the source.
+// Inline placeholders (tags, format arguments) can be kept as single tokens on both sides
+// and must then be the same on both sides.
+// A PairWriter sends the kept pairs to one output and the dropped ones, with the reason,
+// to another, so a cleaning run can be reviewed.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+
+use crate::placeholders::{self, PlaceholderMismatch};
+use crate::token::{Token, TokenKind};
+use crate::Tokenizer;
+
+/// Why a sentence pair was dropped by a PairFilter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PairRejection {
+    /// The source side has no tokens
+    EmptySource,
+    /// The target side has no tokens
+    EmptyTarget,
+    /// One side has more tokens than the maximum
+    TooLong,
+    /// One side is too long compared to the other one (in characters)
+    LengthRatio,
+    /// The ratio of target tokens to source tokens is out of bounds
+    TokenRatio,
+    /// The numbers of the two sides are too different
+    NumberMismatch,
+    /// The target is a copy of the source
+    Untranslated,
+    /// The two sides do not have the same placeholders
+    PlaceholderMismatch,
+}
+
+impl fmt::Display for PairRejection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let reason = match self {
+            PairRejection::EmptySource => "empty source",
+            PairRejection::EmptyTarget => "empty target",
+            PairRejection::TooLong => "too long",
+            PairRejection::LengthRatio => "length ratio",
+            PairRejection::TokenRatio => "token ratio",
+            PairRejection::NumberMismatch => "number mismatch",
+            PairRejection::Untranslated => "untranslated",
+            PairRejection::PlaceholderMismatch => "placeholder mismatch",
+        };
+        write!(f, "{}", reason)
+    }
+}
+
+/// One aligned sentence pair with the tokens of both sides
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SentencePair {
+    /// The line number of the pair in the input (starting at 1)
+    pub line: usize,
+    /// The source sentence
+    pub source: String,
+    /// The target sentence
+    pub target: String,
+    /// The tokens of the source sentence
+    pub source_tokens: Vec<Token>,
+    /// The tokens of the target sentence
+    pub target_tokens: Vec<Token>,
+}
+
+impl SentencePair {
+    /// Compares the placeholders of the two sides (see Bitext::with_placeholders())
+    ///
+    /// # Returns
+    /// None if both sides have the same placeholders, the differences otherwise
+    pub fn placeholder_mismatch(&self) -> Option<PlaceholderMismatch> {
+        placeholders::compare_placeholders(&self.source_tokens, &self.target_tokens)
+    }
+}
+
+/// Keeps or drops sentence pairs by looking at both sides together
+///
+/// Pairs with an empty side are always dropped, the other checks are off by default.
+#[derive(Debug, Clone)]
+pub struct PairFilter {
+    /// Maximum number of tokens on each side
+    max_tokens: usize,
+    /// Maximum ratio between the character lengths of the longer and the shorter side
+    max_length_ratio: f64,
+    /// Accepted range of target tokens per source token
+    token_ratio: (f64, f64),
+    /// Minimum share of numbers found on both sides, among the numbers of the pair
+    min_number_overlap: f64,
+    /// Drop pairs whose two sides have the same tokens
+    drop_untranslated: bool,
+    /// Drop pairs whose two sides do not have the same placeholders
+    check_placeholders: bool,
+}
+
+impl Default for PairFilter {
+    fn default() -> Self {
+        PairFilter {
+            max_tokens: usize::MAX,
+            max_length_ratio: f64::INFINITY,
+            token_ratio: (0.0, f64::INFINITY),
+            min_number_overlap: 0.0,
+            drop_untranslated: false,
+            check_placeholders: false,
+        }
+    }
+}
+
+impl PairFilter {
+    /// Creates a filter that only drops pairs with an empty side
+    pub fn new() -> Self {
+        PairFilter::default()
+    }
+
+    /// Drops pairs where a side has more than `max` tokens
+    pub fn with_max_tokens(mut self, max: usize) -> Self {
+        self.max_tokens = max;
+        self
+    }
+
+    /// Drops pairs where one side has more than `ratio` times the characters of the other
+    ///
+    /// Translations rarely differ in length by more than 2 or 3 times, so a larger ratio
+    /// usually means the pair is misaligned.
+    pub fn with_max_length_ratio(mut self, ratio: f64) -> Self {
+        self.max_length_ratio = ratio.max(1.0);
+        self
+    }
+
+    /// Keeps only pairs with `min` to `max` target tokens per source token
+    ///
+    /// Unlike the length ratio, the bounds are not symmetric: a language pair where the
+    /// target usually needs more tokens (English to German compounds split by the tokenizer,
+    /// for example) can use bounds like 0.8 to 2.5.
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::bitext::{Bitext, PairFilter, PairRejection};
+    ///
+    /// let bitext = Bitext::new(Tokenizer::new(String::new()), Tokenizer::new(String::new()));
+    /// let filter = PairFilter::new().with_token_ratio(0.5, 2.0);
+    /// // 4 source tokens, more than twice as many target tokens
+    /// let pair = bitext.pair(1, "Close the door.", "Pourriez-vous fermer la porte , s'il vous plaît ?");
+    /// assert_eq!(filter.check(&pair), Err(PairRejection::TokenRatio));
+    /// ```
+    pub fn with_token_ratio(mut self, min: f64, max: f64) -> Self {
+        self.token_ratio = (min.max(0.0), max);
+        self
+    }
+
+    /// Keeps only pairs where at least `ratio` (0.0 to 1.0) of the numbers appear on both
+    /// sides
+    ///
+    /// Numbers are the Number tokens, compared as written, and the share is the number of
+    /// matched numbers over the number of numbers of the side that has more. Pairs without
+    /// any number pass. A translation keeps its numbers, so a mismatch usually means the
+    /// target belongs to another source line.
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::bitext::{Bitext, PairFilter, PairRejection};
+    ///
+    /// let bitext = Bitext::new(Tokenizer::new(String::new()), Tokenizer::new(String::new()));
+    /// let filter = PairFilter::new().with_min_number_overlap(1.0);
+    /// // "3e" is a word, not the number 3
+    /// let ordinal = bitext.pair(1, "Room 12 on floor 3.", "Salle 12 au 3e étage.");
+    /// assert_eq!(filter.check(&ordinal), Err(PairRejection::NumberMismatch));
+    /// let shifted = bitext.pair(2, "Call 911 now.", "Appelez le 112.");
+    /// assert_eq!(filter.check(&shifted), Err(PairRejection::NumberMismatch));
+    /// let fine = bitext.pair(3, "In 2024, 40 people came.", "En 2024, 40 personnes sont venues.");
+    /// assert_eq!(filter.check(&fine), Ok(()));
+    /// ```
+    pub fn with_min_number_overlap(mut self, ratio: f64) -> Self {
+        self.min_number_overlap = ratio.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Drops pairs whose two sides have the same tokens (ignoring case), which are copies
+    /// of the source left untranslated
+    pub fn with_untranslated_dropped(mut self, enabled: bool) -> Self {
+        self.drop_untranslated = enabled;
+        self
+    }
+
+    /// Drops pairs whose two sides do not have the same placeholders (in any order)
+    ///
+    /// Placeholders are only found in pairs read by a Bitext with placeholders enabled.
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::bitext::{Bitext, PairFilter, PairRejection};
+    ///
+    /// let bitext = Bitext::new(Tokenizer::new(String::new()), Tokenizer::new(String::new()))
+    ///     .with_placeholders(true);
+    /// let filter = PairFilter::new().with_placeholder_check(true);
+    ///
+    /// let pair = bitext.pair(1, r#"Click <g id="1">here</g>."#, r#"Cliquez <g id="1">ici."#);
+    /// assert_eq!(filter.check(&pair), Err(PairRejection::PlaceholderMismatch));
+    /// assert_eq!(pair.placeholder_mismatch().unwrap().missing, vec!["</g>"]);
+    /// ```
+    pub fn with_placeholder_check(mut self, enabled: bool) -> Self {
+        self.check_placeholders = enabled;
+        self
+    }
+
+    /// Checks a sentence pair
+    ///
+    /// # Returns
+    /// Ok(()) if the pair is kept, or the first check it fails, in this order: empty
+    /// sides, token counts, length ratio, token ratio, numbers, untranslated copy,
+    /// placeholders
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::bitext::{Bitext, PairFilter, PairRejection};
+    ///
+    /// let bitext = Bitext::new(Tokenizer::new(String::new()), Tokenizer::new(String::new()));
+    /// let filter = PairFilter::new().with_max_length_ratio(2.0);
+    ///
+    /// let good = bitext.pair(1, "The cat sleeps.", "Le chat dort.");
+    /// assert_eq!(filter.check(&good), Ok(()));
+    /// let empty = bitext.pair(2, "Thank you!", "  ");
+    /// assert_eq!(filter.check(&empty), Err(PairRejection::EmptyTarget));
+    /// let shifted = bitext.pair(3, "Yes.", "Le chat dort toute la journée au soleil.");
+    /// assert_eq!(filter.check(&shifted), Err(PairRejection::LengthRatio));
+    /// ```
+    pub fn check(&self, pair: &SentencePair) -> Result<(), PairRejection> {
+        // STEP 1: Both sides must have tokens
+        if pair.source_tokens.is_empty() {
+            return Err(PairRejection::EmptySource);
+        }
+        if pair.target_tokens.is_empty() {
+            return Err(PairRejection::EmptyTarget);
+        }
+
+        // STEP 2: Token counts
+        if pair.source_tokens.len() > self.max_tokens || pair.target_tokens.len() > self.max_tokens
+        {
+            return Err(PairRejection::TooLong);
+        }
+
+        // STEP 3: Length ratio, in characters so it does not depend on the tokenizers
+        let source_length = pair.source.trim().chars().count() as f64;
+        let target_length = pair.target.trim().chars().count() as f64;
+        if source_length.max(target_length)
+            > self.max_length_ratio * source_length.min(target_length)
+        {
+            return Err(PairRejection::LengthRatio);
+        }
+
+        // STEP 4: Target tokens per source token
+        let ratio = pair.target_tokens.len() as f64 / pair.source_tokens.len() as f64;
+        if ratio < self.token_ratio.0 || ratio > self.token_ratio.1 {
+            return Err(PairRejection::TokenRatio);
+        }
+
+        // STEP 5: Alignment plausibility: the numbers are kept and the text is translated
+        if self.min_number_overlap > 0.0 && number_overlap(pair) < self.min_number_overlap {
+            return Err(PairRejection::NumberMismatch);
+        }
+        if self.drop_untranslated
+            && pair.source_tokens.len() == pair.target_tokens.len()
+            && pair
+                .source_tokens
+                .iter()
+                .zip(&pair.target_tokens)
+                .all(|(source, target)| source.text.to_lowercase() == target.text.to_lowercase())
+        {
+            return Err(PairRejection::Untranslated);
+        }
+        if self.check_placeholders && pair.placeholder_mismatch().is_some() {
+            return Err(PairRejection::PlaceholderMismatch);
+        }
+
+        Ok(())
+    }
+
+    /// Returns true if the pair passes every check
+    pub fn keeps(&self, pair: &SentencePair) -> bool {
+        self.check(pair).is_ok()
+    }
+}
+
+/// Reads and tokenizes sentence pairs, with one tokenizer per side
+pub struct Bitext {
+    /// Tokenizes the source sentences
+    source: Tokenizer,
+    /// Tokenizes the target sentences
+    target: Tokenizer,
+    /// Keep inline placeholders as single tokens
+    placeholders: bool,
+}
+
+impl Bitext {
+    /// Creates a bitext reader
+    ///
+    /// # Arguments
+    /// * `source` - The tokenizer of the source side
+    /// * `target` - The tokenizer of the target side (it can have other settings)
+    pub fn new(source: Tokenizer, target: Tokenizer) -> Self {
+        Bitext {
+            source,
+            target,
+            placeholders: false,
+        }
+    }
+
+    /// Keeps inline placeholders (`<g id="1">`, `{0}`, `%s`, see the placeholders module)
+    /// as single Special tokens on both sides
+    pub fn with_placeholders(mut self, enabled: bool) -> Self {
+        self.placeholders = enabled;
+        self
+    }
+
+    /// Tokenizes both sides of one pair
+    pub fn pair(&self, line: usize, source: &str, target: &str) -> SentencePair {
+        SentencePair {
+            line,
+            source: source.to_string(),
+            target: target.to_string(),
+            source_tokens: self.tokenize(&self.source, source),
+            target_tokens: self.tokenize(&self.target, target),
+        }
+    }
+
+    /// Tokenizes one side, with or without placeholders
+    fn tokenize(&self, tokenizer: &Tokenizer, text: &str) -> Vec<Token> {
+        if self.placeholders {
+            placeholders::tokenize_with_placeholders(tokenizer, text)
+        } else {
+            tokenizer.tokenize_text(text)
+        }
+    }
+
+    /// Reads the pairs of two aligned files: line N of `source` goes with line N of `target`
+    ///
+    /// # Returns
+    /// The pairs in order. A reading error, or files with a different number of lines
+    /// (InvalidData), give one error and end the iteration.
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::bitext::{Bitext, PairFilter};
+    ///
+    /// let bitext = Bitext::new(Tokenizer::new(String::new()), Tokenizer::new(String::new()));
+    /// let source = "Good morning.\nSee you!\n";
+    /// let target = "Bonjour.\n\n";
+    ///
+    /// let filter = PairFilter::new();
+    /// let kept: Vec<String> = bitext
+    ///     .read_parallel(source.as_bytes(), target.as_bytes())
+    ///     .map(|pair| pair.unwrap())
+    ///     .filter(|pair| filter.keeps(pair))
+    ///     .map(|pair| pair.target)
+    ///     .collect();
+    /// assert_eq!(kept, vec!["Bonjour."]);
+    ///
+    /// // Misaligned files
+    /// let mut pairs = bitext.read_parallel("a\nb\n".as_bytes(), "a\n".as_bytes());
+    /// assert!(pairs.next().unwrap().is_ok());
+    /// assert!(pairs.next().unwrap().is_err());
+    /// assert!(pairs.next().is_none());
+    /// ```
+    pub fn read_parallel<'b, S, T>(
+        &'b self,
+        source: S,
+        target: T,
+    ) -> impl Iterator<Item = io::Result<SentencePair>> + 'b
+    where
+        S: BufRead + 'b,
+        T: BufRead + 'b,
+    {
+        let mut source = source.lines();
+        let mut target = target.lines();
+        let mut line = 0;
+        let mut done = false;
+        std::iter::from_fn(move || {
+            if done {
+                return None;
+            }
+            line += 1;
+            let result = match (source.next(), target.next()) {
+                (None, None) => return None,
+                (Some(source), Some(target)) => source
+                    .and_then(|source| Ok((source, target?)))
+                    .map(|(source, target)| self.pair(line, &source, &target)),
+                (Some(_), None) | (None, Some(_)) => Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "the source and target have a different number of lines (line {})",
+                        line
+                    ),
+                )),
+            };
+            done = result.is_err();
+            Some(result)
+        })
+    }
+
+    /// Reads pairs written as "source<TAB>target", one per line
+    ///
+    /// # Returns
+    /// The pairs in order. A reading error, or a line without a tab (InvalidData), gives
+    /// one error and ends the iteration.
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::bitext::Bitext;
+    ///
+    /// let bitext = Bitext::new(Tokenizer::new(String::new()), Tokenizer::new(String::new()));
+    /// let pairs: Vec<_> = bitext
+    ///     .read_tsv("Thanks!\tMerci !\n".as_bytes())
+    ///     .collect::<Result<_, _>>()
+    ///     .unwrap();
+    /// assert_eq!(pairs[0].source_tokens.len(), 2);
+    /// assert_eq!(pairs[0].target, "Merci !");
+    /// ```
+    pub fn read_tsv<'b, R: BufRead + 'b>(
+        &'b self,
+        reader: R,
+    ) -> impl Iterator<Item = io::Result<SentencePair>> + 'b {
+        let mut lines = reader.lines().enumerate();
+        let mut done = false;
+        std::iter::from_fn(move || {
+            if done {
+                return None;
+            }
+            let (index, line) = lines.next()?;
+            let result = line.and_then(|line| match line.split_once('\t') {
+                Some((source, target)) => Ok(self.pair(index + 1, source, target)),
+                None => Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("line {} has no tab between source and target", index + 1),
+                )),
+            });
+            done = result.is_err();
+            Some(result)
+        })
+    }
+}
+
+/// Returns the share of the numbers of a pair found on both sides (1.0 without numbers)
+fn number_overlap(pair: &SentencePair) -> f64 {
+    let numbers = |tokens: &[Token]| {
+        let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+        for token in tokens
+            .iter()
+            .filter(|token| token.kind == TokenKind::Number)
+        {
+            *counts.entry(token.text.clone()).or_insert(0) += 1;
+        }
+        counts
+    };
+    let source = numbers(&pair.source_tokens);
+    let target = numbers(&pair.target_tokens);
+
+    let source_count: usize = source.values().sum();
+    let target_count: usize = target.values().sum();
+    if source_count.max(target_count) == 0 {
+        return 1.0;
+    }
+    let shared: usize = source
+        .iter()
+        .map(|(number, &count)| count.min(target.get(number).copied().unwrap_or(0)))
+        .sum();
+    shared as f64 / source_count.max(target_count) as f64
+}
+
+/// How many pairs a cleaning run kept, and why it dropped the others
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FilterSummary {
+    /// Number of pairs read
+    pub total: usize,
+    /// Number of pairs kept
+    pub kept: usize,
+    /// Number of dropped pairs per reason
+    pub rejections: BTreeMap<PairRejection, usize>,
+}
+
+impl fmt::Display for FilterSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "kept {} of {} pairs", self.kept, self.total)?;
+        for (rejection, count) in &self.rejections {
+            writeln!(f, "  {}: {}", rejection, count)?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes the pairs a PairFilter keeps to one output and the dropped ones to another
+///
+/// Kept pairs are written as "source<TAB>target", dropped ones as
+/// "source<TAB>target<TAB>reason", one pair per line. A placeholder mismatch also lists
+/// the placeholders missing from or added to the target.
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::Tokenizer;
+/// use tokenizer_rust::bitext::{Bitext, PairFilter, PairWriter};
+///
+/// let bitext = Bitext::new(Tokenizer::new(String::new()), Tokenizer::new(String::new()));
+/// let filter = PairFilter::new().with_untranslated_dropped(true);
+/// let mut writer = PairWriter::new(Vec::new(), Vec::new()).with_tokenized(true);
+/// for pair in bitext.read_tsv("Hello!\tBonjour !\nOK\tok\n".as_bytes()) {
+///     writer.write(&filter, &pair.unwrap()).unwrap();
+/// }
+///
+/// assert_eq!(writer.summary().kept, 1);
+/// let (accepted, rejected) = writer.into_inner();
+/// assert_eq!(String::from_utf8(accepted).unwrap(), "Hello !\tBonjour !\n");
+/// assert_eq!(String::from_utf8(rejected).unwrap(), "OK\tok\tuntranslated\n");
+/// ```
+#[derive(Debug)]
+pub struct PairWriter<A: Write, R: Write> {
+    /// Receives the kept pairs
+    accepted: A,
+    /// Receives the dropped pairs
+    rejected: R,
+    /// Write the tokens joined with spaces instead of the original text
+    tokenized: bool,
+    /// The counts so far
+    summary: FilterSummary,
+}
+
+impl<A: Write, R: Write> PairWriter<A, R> {
+    /// Creates a writer sending kept pairs to `accepted` and dropped ones to `rejected`
+    /// (use io::sink() to throw them away)
+    pub fn new(accepted: A, rejected: R) -> Self {
+        PairWriter {
+            accepted,
+            rejected,
+            tokenized: false,
+            summary: FilterSummary::default(),
+        }
+    }
+
+    /// Writes the tokens of each side joined with single spaces instead of the original text
+    pub fn with_tokenized(mut self, enabled: bool) -> Self {
+        self.tokenized = enabled;
+        self
+    }
+
+    /// Checks a pair with the filter and writes it to the matching output
+    ///
+    /// # Returns
+    /// Whether the pair was kept, or the writing error
+    pub fn write(&mut self, filter: &PairFilter, pair: &SentencePair) -> io::Result<bool> {
+        let source = self.side(&pair.source, &pair.source_tokens);
+        let target = self.side(&pair.target, &pair.target_tokens);
+        self.summary.total += 1;
+        match filter.check(pair) {
+            Ok(()) => {
+                self.summary.kept += 1;
+                writeln!(self.accepted, "{}\t{}", source, target)?;
+                Ok(true)
+            }
+            Err(rejection) => {
+                *self.summary.rejections.entry(rejection).or_insert(0) += 1;
+                let reason = match rejection {
+                    PairRejection::PlaceholderMismatch => format!(
+                        "{} ({})",
+                        rejection,
+                        pair.placeholder_mismatch().unwrap_or_default()
+                    ),
+                    _ => rejection.to_string(),
+                };
+                writeln!(self.rejected, "{}\t{}\t{}", source, target, reason)?;
+                Ok(false)
+            }
+        }
+    }
+
+    /// Returns the counts of the pairs written so far
+    pub fn summary(&self) -> &FilterSummary {
+        &self.summary
+    }
+
+    /// Flushes both outputs
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.accepted.flush()?;
+        self.rejected.flush()
+    }
+
+    /// Returns the two outputs (accepted, rejected)
+    pub fn into_inner(self) -> (A, R) {
+        (self.accepted, self.rejected)
+    }
+
+    /// Returns the text written for one side of a pair
+    fn side(&self, text: &str, tokens: &[Token]) -> String {
+        if self.tokenized {
+            tokens
+                .iter()
+                .map(|token| token.text.as_str())
+                .collect::<Vec<_>>()
+                .join(" ")
+        } else {
+            text.trim().to_string()
+        }
+    }
+}
@@ -0,0 +1,135 @@
+// Chunking: splitting long documents into pieces that fit a token budget
+// Retrieval-augmented generation (RAG) stores documents as chunks small enough for the
+// context of a language model. Tokenizer::chunk() cuts a document into windows of at most
+// `max_tokens` tokens, where neighbouring windows can share some tokens so that a sentence
+// cut at the border of one chunk is still complete in the next one.
+// Tokenizer::chunk_sentences() packs whole sentences instead, so chunks don't end in the
+// middle of a sentence; only a sentence longer than the budget is cut into windows.
+// Every chunk is one contiguous piece of the original text (with its original whitespace),
+// so it can be shown, stored or detokenized without losing anything in between.
+
+use crate::encode_options::window_starts;
+use crate::token::Token;
+
+/// One piece of a chunked document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chunk {
+    /// The text of the chunk, exactly as in the document (from its first to its last token)
+    pub text: String,
+    /// Byte offset of the chunk in the document
+    pub start: usize,
+    /// Byte offset of the end of the chunk in the document (exclusive)
+    pub end: usize,
+    /// Index of the first token of the chunk among the tokens of the document
+    pub first_token: usize,
+    /// Number of tokens of the chunk
+    pub token_count: usize,
+}
+
+/// Cuts the tokens of a text into windows of at most `max_tokens` tokens
+///
+/// Neighbouring windows share `overlap` tokens (at most `max_tokens - 1`, so the
+/// windows always move forward).
+pub(crate) fn chunk_tokens(
+    text: &str,
+    tokens: &[Token],
+    max_tokens: usize,
+    overlap: usize,
+) -> Vec<Chunk> {
+    if tokens.is_empty() || max_tokens == 0 {
+        return Vec::new();
+    }
+
+    window_starts(tokens.len(), max_tokens, overlap)
+        .into_iter()
+        .map(|first| {
+            let last = (first + max_tokens).min(tokens.len()) - 1;
+            make_chunk(text, tokens, first, last)
+        })
+        .collect()
+}
+
+/// Packs whole sentences into chunks of at most `max_tokens` tokens
+///
+/// `sentences` are the (start, end) byte ranges of the sentences of the text. Every token
+/// belongs to the sentence it starts in (tokens between two sentences to the first one).
+/// Neighbouring chunks share the whole sentences at the end of the first chunk that fit
+/// in `overlap` tokens. A sentence longer than `max_tokens` gets chunks of its own, cut
+/// like chunk_tokens() does.
+pub(crate) fn chunk_sentences(
+    text: &str,
+    tokens: &[Token],
+    sentences: &[(usize, usize)],
+    max_tokens: usize,
+    overlap: usize,
+) -> Vec<Chunk> {
+    if tokens.is_empty() || max_tokens == 0 {
+        return Vec::new();
+    }
+
+    // STEP 1: The token range [first, end) of every sentence
+    let mut boundaries = vec![0];
+    for &(start, _) in sentences.iter().skip(1) {
+        boundaries.push(tokens.partition_point(|token| token.start < start));
+    }
+    boundaries.push(tokens.len());
+    let units: Vec<(usize, usize)> = boundaries
+        .windows(2)
+        .map(|range| (range[0], range[1]))
+        .filter(|(first, end)| first < end)
+        .collect();
+
+    // STEP 2: Pack as many sentences as fit into every chunk
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < units.len() {
+        let first = units[start].0;
+        let mut end = start;
+        while end < units.len() && units[end].1 - first <= max_tokens {
+            end += 1;
+        }
+
+        if end == start {
+            // The sentence alone is over the budget: fall back to token windows
+            let (first, last) = units[start];
+            for mut chunk in chunk_tokens(text, &tokens[first..last], max_tokens, overlap) {
+                chunk.first_token += first;
+                chunks.push(chunk);
+            }
+            start += 1;
+            continue;
+        }
+
+        chunks.push(make_chunk(text, tokens, first, units[end - 1].1 - 1));
+        if end == units.len() {
+            break;
+        }
+
+        // STEP 3: Start the next chunk with the last sentences that fit in the overlap,
+        // but never so many that the next sentence doesn't fit anymore
+        let last_token = units[end - 1].1;
+        let mut next = end;
+        while next > start + 1 && last_token - units[next - 1].0 <= overlap {
+            next -= 1;
+        }
+        while next < end && units[end].1 - units[next].0 > max_tokens {
+            next += 1;
+        }
+        start = next;
+    }
+
+    chunks
+}
+
+/// Builds the chunk going from tokens[first] to tokens[last] (both included)
+fn make_chunk(text: &str, tokens: &[Token], first: usize, last: usize) -> Chunk {
+    let start = tokens[first].start;
+    let end = tokens[last].end;
+    Chunk {
+        text: text[start..end].to_string(),
+        start,
+        end,
+        first_token: first,
+        token_count: last - first + 1,
+    }
+}
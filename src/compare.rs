@@ -0,0 +1,207 @@
+// Comparing two tokenizer configurations on the same corpus
+// Before deploying a config change (new special tokens, identifier detection, ...) it helps
+// to see what it does to real data: how many more or fewer tokens there are, how many tokens
+// a word becomes on average (the "fertility"), and which sentences are tokenized differently.
+
+use std::fmt;
+
+use crate::Tokenizer;
+
+/// Token counts of one configuration over a corpus
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CorpusStats {
+    /// Number of sentences tokenized
+    pub sentences: usize,
+    /// Number of whitespace-separated words in the sentences
+    pub words: usize,
+    /// Number of tokens produced
+    pub tokens: usize,
+}
+
+impl CorpusStats {
+    /// Returns the average number of tokens per sentence (0 for an empty corpus)
+    pub fn tokens_per_sentence(&self) -> f64 {
+        ratio(self.tokens, self.sentences)
+    }
+
+    /// Returns the fertility: the average number of tokens per word (0 without words)
+    ///
+    /// A fertility of 1.0 means every word is one token; punctuation and split words
+    /// make it higher, merged identifiers make it lower.
+    pub fn fertility(&self) -> f64 {
+        ratio(self.tokens, self.words)
+    }
+}
+
+/// A sentence that the two configurations tokenize differently
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    /// Position of the sentence in the corpus
+    pub index: usize,
+    /// The sentence
+    pub sentence: String,
+    /// The tokens of the first configuration
+    pub first: Vec<String>,
+    /// The tokens of the second configuration
+    pub second: Vec<String>,
+}
+
+/// The result of compare(): statistics of both configurations and example differences
+#[derive(Debug, Clone, PartialEq)]
+pub struct Comparison {
+    /// Statistics of the first configuration
+    pub first: CorpusStats,
+    /// Statistics of the second configuration
+    pub second: CorpusStats,
+    /// How many sentences are tokenized differently
+    pub diverging_count: usize,
+    /// The first diverging sentences (at most the requested number of examples)
+    pub diverging: Vec<Divergence>,
+}
+
+/// Tokenizes a corpus with two configurations and reports the differences
+///
+/// # Arguments
+/// * `first` - The current configuration
+/// * `second` - The configuration to evaluate
+/// * `sentences` - The corpus, one sentence per item
+/// * `max_examples` - How many diverging sentences to keep as examples
+///
+/// # Returns
+/// The statistics of both configurations and the diverging sentences
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::Tokenizer;
+/// use tokenizer_rust::compare::compare;
+/// use tokenizer_rust::identifiers::IdentifierDetector;
+///
+/// let current = Tokenizer::new(String::new());
+/// let candidate = Tokenizer::new(String::new()).with_identifiers(IdentifierDetector::new());
+/// let corpus = ["Pay to DE89 3704 0044 0532 0130 00 today.", "Thanks a lot!"];
+///
+/// let report = compare(&current, &candidate, &corpus, 10);
+/// assert_eq!(report.first.tokens, 14);
+/// assert_eq!(report.second.tokens, 9);
+/// assert_eq!(report.diverging_count, 1);
+/// assert_eq!(report.diverging[0].second[2], "DE89 3704 0044 0532 0130 00");
+/// println!("{}", report);
+/// ```
+pub fn compare(
+    first: &Tokenizer,
+    second: &Tokenizer,
+    sentences: &[&str],
+    max_examples: usize,
+) -> Comparison {
+    let mut report = Comparison {
+        first: CorpusStats::default(),
+        second: CorpusStats::default(),
+        diverging_count: 0,
+        diverging: Vec::new(),
+    };
+
+    for (index, sentence) in sentences.iter().enumerate() {
+        // STEP 1: Tokenize the sentence with both configurations
+        let first_tokens = token_texts(first, sentence);
+        let second_tokens = token_texts(second, sentence);
+
+        // STEP 2: Add the sentence to the statistics
+        let words = sentence.split_whitespace().count();
+        for (stats, tokens) in [
+            (&mut report.first, &first_tokens),
+            (&mut report.second, &second_tokens),
+        ] {
+            stats.sentences += 1;
+            stats.words += words;
+            stats.tokens += tokens.len();
+        }
+
+        // STEP 3: Keep the sentence as an example if the tokens differ
+        if first_tokens != second_tokens {
+            report.diverging_count += 1;
+            if report.diverging.len() < max_examples {
+                report.diverging.push(Divergence {
+                    index,
+                    sentence: sentence.to_string(),
+                    first: first_tokens,
+                    second: second_tokens,
+                });
+            }
+        }
+    }
+
+    report
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<22} {:>10} {:>10} {:>9}",
+            "", "first", "second", "change"
+        )?;
+        writeln!(
+            f,
+            "{:<22} {:>10} {:>10} {:>9}",
+            "tokens",
+            self.first.tokens,
+            self.second.tokens,
+            change(self.first.tokens as f64, self.second.tokens as f64)
+        )?;
+        for (name, first, second) in [
+            (
+                "tokens per sentence",
+                self.first.tokens_per_sentence(),
+                self.second.tokens_per_sentence(),
+            ),
+            ("fertility", self.first.fertility(), self.second.fertility()),
+        ] {
+            writeln!(
+                f,
+                "{:<22} {:>10.2} {:>10.2} {:>9}",
+                name,
+                first,
+                second,
+                change(first, second)
+            )?;
+        }
+
+        writeln!(
+            f,
+            "diverging sentences: {} of {}",
+            self.diverging_count, self.first.sentences
+        )?;
+        for divergence in &self.diverging {
+            writeln!(f, "#{}: {:?}", divergence.index, divergence.sentence)?;
+            writeln!(f, "  first:  {:?}", divergence.first)?;
+            writeln!(f, "  second: {:?}", divergence.second)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the token texts of one sentence
+fn token_texts(tokenizer: &Tokenizer, sentence: &str) -> Vec<String> {
+    tokenizer
+        .tokenize_text(sentence)
+        .into_iter()
+        .map(|token| token.text)
+        .collect()
+}
+
+/// Divides two counts, giving 0 when there is nothing to divide by
+fn ratio(count: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        count as f64 / total as f64
+    }
+}
+
+/// Formats the relative change from `before` to `after`, like "+12.5%"
+fn change(before: f64, after: f64) -> String {
+    if before == 0.0 {
+        return "-".to_string();
+    }
+    format!("{:+.1}%", (after - before) / before * 100.0)
+}
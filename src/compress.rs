@@ -0,0 +1,287 @@
+// Transparent compression of corpus files
+// Most corpora are stored compressed, and decompressing them to disk first doubles the
+// space they take. The readers and writers here pick the compression from the extension of
+// the file: ".gz" is gzip (with the `gzip` feature, which `cli` turns on), ".zst" is zstd (with
+// the `zstd` feature, which builds the C library), anything else is read and written as is.
+// A compressed file opened without its feature gives an error naming the feature, never
+// the compressed bytes as text.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// The compression of a file, from its extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Compression {
+    /// Not compressed
+    None,
+    /// gzip (".gz")
+    Gzip,
+    /// zstd (".zst")
+    Zstd,
+}
+
+impl Compression {
+    /// Returns the compression a path's extension asks for
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::compress::Compression;
+    ///
+    /// assert_eq!(Compression::from_path("corpus.jsonl.gz"), Compression::Gzip);
+    /// assert_eq!(Compression::from_path("corpus.txt.zst"), Compression::Zstd);
+    /// assert_eq!(Compression::from_path("corpus.txt"), Compression::None);
+    /// ```
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Self {
+        match path
+            .as_ref()
+            .extension()
+            .and_then(|extension| extension.to_str())
+        {
+            Some("gz") => Compression::Gzip,
+            Some("zst") => Compression::Zstd,
+            _ => Compression::None,
+        }
+    }
+
+    /// Returns the extension of the compression, without the dot ("" when not compressed)
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Compression::None => "",
+            Compression::Gzip => "gz",
+            Compression::Zstd => "zst",
+        }
+    }
+
+    /// Returns the name of the cargo feature that reads and writes this compression
+    #[cfg(not(all(feature = "gzip", feature = "zstd")))]
+    fn feature(&self) -> &'static str {
+        match self {
+            Compression::None => "",
+            Compression::Gzip => "gzip",
+            Compression::Zstd => "zstd",
+        }
+    }
+
+    /// Returns the error of a compression whose feature is off
+    #[cfg(not(all(feature = "gzip", feature = "zstd")))]
+    fn unsupported(&self) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!(
+                ".{} files need the `{}` feature",
+                self.extension(),
+                self.feature()
+            ),
+        )
+    }
+}
+
+/// Returns a path without its compression extension ("a.txt.gz" -> "a.txt")
+///
+/// # Example
+/// ```
+/// use std::path::Path;
+/// use tokenizer_rust::compress::strip_compression;
+///
+/// assert_eq!(strip_compression("news/a.txt.zst"), Path::new("news/a.txt"));
+/// assert_eq!(strip_compression("news/a.txt"), Path::new("news/a.txt"));
+/// ```
+pub fn strip_compression<P: AsRef<Path>>(path: P) -> PathBuf {
+    let path = path.as_ref();
+    match Compression::from_path(path) {
+        Compression::None => path.to_path_buf(),
+        _ => path.with_extension(""),
+    }
+}
+
+/// Opens a file for reading, decompressing it on the fly if its extension says so
+///
+/// gzip files made of several members (pigz, bgzip, `cat a.gz b.gz`) are read to the end
+/// of the last one.
+///
+/// # Example
+/// ```
+/// use std::io::{BufRead, Write};
+/// use tokenizer_rust::compress;
+///
+/// # #[cfg(feature = "gzip")]
+/// # {
+/// let path = std::env::temp_dir().join("tokenizer_compress_open_doctest.txt.gz");
+/// let mut writer = compress::create(&path).unwrap();
+/// writeln!(writer, "first line\nsecond line").unwrap();
+/// writer.finish().unwrap();
+///
+/// let lines: Vec<String> = compress::open(&path).unwrap().lines().map(Result::unwrap).collect();
+/// assert_eq!(lines, vec!["first line", "second line"]);
+/// # std::fs::remove_file(&path).unwrap();
+/// # }
+/// ```
+pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Box<dyn BufRead + Send>> {
+    let path = path.as_ref();
+    let compression = Compression::from_path(path);
+    let file = File::open(path)?;
+    match compression {
+        Compression::None => Ok(Box::new(BufReader::new(file))),
+        Compression::Gzip => gzip_reader(file),
+        Compression::Zstd => zstd_reader(file),
+    }
+}
+
+/// Reads a whole file to a string, decompressing it if its extension says so
+pub fn read_to_string<P: AsRef<Path>>(path: P) -> io::Result<String> {
+    let mut text = String::new();
+    open(path)?.read_to_string(&mut text)?;
+    Ok(text)
+}
+
+/// Creates (or truncates) a file for writing, compressing what is written if its extension
+/// says so
+///
+/// # Returns
+/// A writer to end with finish(), which reports the errors of the end of the compressed
+/// stream (dropping the writer ends it too, but ignores them)
+pub fn create<P: AsRef<Path>>(path: P) -> io::Result<CompressedWriter> {
+    let path = path.as_ref();
+    let encoder = match Compression::from_path(path) {
+        Compression::None => Encoder::Plain(BufWriter::new(File::create(path)?)),
+        Compression::Gzip => gzip_encoder(path)?,
+        Compression::Zstd => zstd_encoder(path)?,
+    };
+    Ok(CompressedWriter {
+        encoder: Some(encoder),
+    })
+}
+
+/// A file being written, compressed or not (see create())
+pub struct CompressedWriter {
+    /// The compressor (None once the stream is ended)
+    encoder: Option<Encoder>,
+}
+
+impl CompressedWriter {
+    /// Ends the compressed stream and flushes the file
+    pub fn finish(mut self) -> io::Result<()> {
+        match self.encoder.take() {
+            Some(encoder) => encoder.finish(),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the compressor (only finish() and drop() take it)
+    fn encoder(&mut self) -> &mut Encoder {
+        self.encoder
+            .as_mut()
+            .expect("the encoder is only taken when the writer is consumed")
+    }
+}
+
+impl Write for CompressedWriter {
+    fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        match self.encoder() {
+            Encoder::Plain(writer) => writer.write(buffer),
+            #[cfg(feature = "gzip")]
+            Encoder::Gzip(writer) => writer.write(buffer),
+            #[cfg(feature = "zstd")]
+            Encoder::Zstd(writer) => writer.write(buffer),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.encoder() {
+            Encoder::Plain(writer) => writer.flush(),
+            #[cfg(feature = "gzip")]
+            Encoder::Gzip(writer) => writer.flush(),
+            #[cfg(feature = "zstd")]
+            Encoder::Zstd(writer) => writer.flush(),
+        }
+    }
+}
+
+impl Drop for CompressedWriter {
+    fn drop(&mut self) {
+        if let Some(encoder) = self.encoder.take() {
+            let _ = encoder.finish();
+        }
+    }
+}
+
+/// The compressor of a CompressedWriter
+enum Encoder {
+    /// A file written as is
+    Plain(BufWriter<File>),
+    /// A gzip stream
+    #[cfg(feature = "gzip")]
+    Gzip(flate2::write::GzEncoder<BufWriter<File>>),
+    /// A zstd stream
+    #[cfg(feature = "zstd")]
+    Zstd(zstd::stream::write::Encoder<'static, BufWriter<File>>),
+}
+
+impl Encoder {
+    /// Ends the compressed stream and flushes the file
+    fn finish(self) -> io::Result<()> {
+        match self {
+            Encoder::Plain(mut writer) => writer.flush(),
+            #[cfg(feature = "gzip")]
+            Encoder::Gzip(writer) => writer.finish()?.flush(),
+            #[cfg(feature = "zstd")]
+            Encoder::Zstd(writer) => writer.finish()?.flush(),
+        }
+    }
+}
+
+/// Decompresses a gzip file (every member of it)
+#[cfg(feature = "gzip")]
+fn gzip_reader(file: File) -> io::Result<Box<dyn BufRead + Send>> {
+    let decoder = flate2::read::MultiGzDecoder::new(BufReader::new(file));
+    Ok(Box::new(BufReader::new(decoder)))
+}
+
+/// Without the `gzip` feature, gzip files can't be read
+#[cfg(not(feature = "gzip"))]
+fn gzip_reader(_file: File) -> io::Result<Box<dyn BufRead + Send>> {
+    Err(Compression::Gzip.unsupported())
+}
+
+/// Decompresses a zstd file (every frame of it)
+#[cfg(feature = "zstd")]
+fn zstd_reader(file: File) -> io::Result<Box<dyn BufRead + Send>> {
+    Ok(Box::new(BufReader::new(zstd::stream::read::Decoder::new(
+        file,
+    )?)))
+}
+
+/// Without the `zstd` feature, zstd files can't be read
+#[cfg(not(feature = "zstd"))]
+fn zstd_reader(_file: File) -> io::Result<Box<dyn BufRead + Send>> {
+    Err(Compression::Zstd.unsupported())
+}
+
+/// Compresses a file with gzip at the default level
+#[cfg(feature = "gzip")]
+fn gzip_encoder(path: &Path) -> io::Result<Encoder> {
+    Ok(Encoder::Gzip(flate2::write::GzEncoder::new(
+        BufWriter::new(File::create(path)?),
+        flate2::Compression::default(),
+    )))
+}
+
+/// Without the `gzip` feature, gzip files can't be written (and the file isn't created)
+#[cfg(not(feature = "gzip"))]
+fn gzip_encoder(_path: &Path) -> io::Result<Encoder> {
+    Err(Compression::Gzip.unsupported())
+}
+
+/// Compresses a file with zstd at the default level
+#[cfg(feature = "zstd")]
+fn zstd_encoder(path: &Path) -> io::Result<Encoder> {
+    let file = BufWriter::new(File::create(path)?);
+    Ok(Encoder::Zstd(zstd::stream::write::Encoder::new(file, 0)?))
+}
+
+/// Without the `zstd` feature, zstd files can't be written (and the file isn't created)
+#[cfg(not(feature = "zstd"))]
+fn zstd_encoder(_path: &Path) -> io::Result<Encoder> {
+    Err(Compression::Zstd.unsupported())
+}
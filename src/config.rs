@@ -0,0 +1,478 @@
+// Tokenizer configuration files in JSON
+// Tokenizer::save() writes every setting of a Tokenizer as one JSON document, so other
+// programs (Python, jq, a config service) can read and write it without this crate:
+//     {"format": "tokenizer-config", "version": 1, "rules_version": 1, ...}
+// The keys follow the builder methods of Tokenizer: structure_tokens, capacity_hint,
+// estimated_capacity, special_tokens, added_tokens, identifiers, filters,
+// sentence_filter, vocabulary (in id order) and encoder. The filters and the sentence
+// filter are written with the settings of their Manifest step, one "key value" string per
+// setting. The pipeline hash of the Manifest of the same Tokenizer is written too, to
+// compare setups at a glance, but it is not checked on load: editing the file is allowed.
+
+use crate::encode_options::{Padding, PaddingSide, PaddingStrategy, TruncationStrategy};
+use crate::encoder::{EncoderConfig, Template, UnknownPolicy};
+use crate::filter::SentenceFilter;
+use crate::identifiers::IdentifierDetector;
+use crate::json::{Json, JsonCursor};
+use crate::manifest::{describe_template, Manifest, ManifestStep, RULES_VERSION};
+use crate::output::json_string;
+use crate::special::SpecialTokens;
+use crate::subword::{SubwordKind, SubwordModel};
+use crate::token_filter::FilterPipeline;
+use crate::vocab::Vocabulary;
+use crate::Tokenizer;
+
+/// The value of the "format" key of every configuration file
+const FORMAT: &str = "tokenizer-config";
+
+/// The version of the configuration file format
+const FORMAT_VERSION: u32 = 1;
+
+/// Writes the configuration of a Tokenizer as a JSON document (identifier validators and
+/// custom token filters, which are Rust code, are left out: Tokenizer::to_json() checks)
+pub(crate) fn to_json(tokenizer: &Tokenizer) -> String {
+    let strings = |values: &mut dyn Iterator<Item = &str>| {
+        let values: Vec<String> = values.map(json_string).collect();
+        if values.is_empty() {
+            "[]".to_string()
+        } else {
+            format!("[\n    {}\n  ]", values.join(",\n    "))
+        }
+    };
+    let optional = |value: Option<usize>| value.map_or("null".to_string(), |n| n.to_string());
+
+    let identifiers = match tokenizer.identifiers() {
+        Some(detector) => format!("{{\"min_length\": {}}}", detector.min_length()),
+        None => "null".to_string(),
+    };
+    let filters: Vec<String> = tokenizer
+        .filters()
+        .steps()
+        .iter()
+        .filter(|step| step.name != "custom_filter")
+        .map(|step| {
+            // The first line is the position, which is the place in the array
+            format!(
+                "{{\"name\": {}, \"settings\": {}}}",
+                json_string(&step.name),
+                settings_json(&step.lines[1..])
+            )
+        })
+        .collect();
+    let filters = if filters.is_empty() {
+        "[]".to_string()
+    } else {
+        format!("[\n    {}\n  ]", filters.join(",\n    "))
+    };
+    let sentence_filter = match tokenizer.sentence_filter() {
+        Some(filter) => format!(
+            "{{\"settings\": {}}}",
+            settings_json(&filter.describe().lines)
+        ),
+        None => "null".to_string(),
+    };
+    let fields = [
+        ("format", json_string(FORMAT)),
+        ("version", FORMAT_VERSION.to_string()),
+        ("rules_version", RULES_VERSION.to_string()),
+        (
+            "pipeline_hash",
+            json_string(&format!(
+                "{:016x}",
+                Manifest::from_tokenizer(tokenizer).hash()
+            )),
+        ),
+        ("structure_tokens", tokenizer.structure_tokens().to_string()),
+        ("capacity_hint", optional(tokenizer.capacity_hint())),
+        (
+            "estimated_capacity",
+            tokenizer.estimated_capacity().to_string(),
+        ),
+        (
+            "special_tokens",
+            strings(&mut tokenizer.special_tokens().iter()),
+        ),
+        ("added_tokens", strings(&mut tokenizer.added_tokens())),
+        ("identifiers", identifiers),
+        ("filters", filters),
+        ("sentence_filter", sentence_filter),
+        (
+            "vocabulary",
+            strings(&mut tokenizer.vocabulary().iter().map(|(_, token)| token)),
+        ),
+        ("encoder", encoder_json(tokenizer.encoder_config())),
+    ];
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|(key, value)| format!("  {}: {}", json_string(key), value))
+        .collect();
+    format!("{{\n{}\n}}\n", fields.join(",\n"))
+}
+
+/// Writes strings (the "key value" lines of a Manifest step, subword pieces, ...) as a
+/// one-line JSON array
+fn settings_json(lines: &[String]) -> String {
+    let lines: Vec<String> = lines.iter().map(|line| json_string(line)).collect();
+    format!("[{}]", lines.join(", "))
+}
+
+/// Writes the encoding settings as a JSON object
+fn encoder_json(config: &EncoderConfig) -> String {
+    let mut fields = Vec::new();
+    let (unknown, unknown_token) = match config.unknown_policy() {
+        UnknownPolicy::Skip => ("skip", None),
+        UnknownPolicy::ByteFallback => ("byte_fallback", None),
+        UnknownPolicy::UnknownToken(token) => ("token", Some(token.as_str())),
+    };
+    fields.push(("unknown", json_string(unknown)));
+    if let Some(token) = unknown_token {
+        fields.push(("unknown_token", json_string(token)));
+    }
+    let template = match config.template() {
+        Some(template) => format!(
+            "{{\"single\": {}, \"pair\": {}}}",
+            json_string(&describe_template(template.single())),
+            json_string(&describe_template(template.pair()))
+        ),
+        None => "null".to_string(),
+    };
+    fields.push(("template", template));
+    fields.push((
+        "max_length",
+        config
+            .max_length()
+            .map_or("null".to_string(), |length| length.to_string()),
+    ));
+    let truncation = match config.truncation_strategy() {
+        TruncationStrategy::LongestFirst => "longest_first",
+        TruncationStrategy::OnlyFirst => "only_first",
+        TruncationStrategy::OnlySecond => "only_second",
+    };
+    fields.push(("truncation", json_string(truncation)));
+    let padding = match config.padding() {
+        Some(padding) => {
+            let strategy = match padding.strategy() {
+                PaddingStrategy::Fixed(length) => format!("\"fixed\", \"length\": {}", length),
+                PaddingStrategy::BatchLongest => "\"batch_longest\"".to_string(),
+            };
+            let side = match padding.side() {
+                PaddingSide::Right => "right",
+                PaddingSide::Left => "left",
+            };
+            format!(
+                "{{\"strategy\": {}, \"side\": {}, \"pad_token\": {}, \"pad_type_id\": {}}}",
+                strategy,
+                json_string(side),
+                json_string(padding.pad_token()),
+                padding.pad_type_id()
+            )
+        }
+        None => "null".to_string(),
+    };
+    fields.push(("padding", padding));
+    fields.push((
+        "overflowing_stride",
+        config
+            .overflowing_stride()
+            .map_or("null".to_string(), |stride| stride.to_string()),
+    ));
+    let subwords = match config.subwords() {
+        Some(model) => {
+            let merges: Vec<String> = model
+                .merges()
+                .iter()
+                .map(|(left, right)| format!("{} {}", left, right))
+                .collect();
+            format!(
+                "{{\"model\": {}, \"pieces\": {}, \"merges\": {}}}",
+                json_string(model.kind().name()),
+                settings_json(model.pieces()),
+                settings_json(&merges)
+            )
+        }
+        None => "null".to_string(),
+    };
+    fields.push(("subwords", subwords));
+
+    let fields: Vec<String> = fields
+        .iter()
+        .map(|(key, value)| format!("    {}: {}", json_string(key), value))
+        .collect();
+    format!("{{\n{}\n  }}", fields.join(",\n"))
+}
+
+/// Rebuilds a Tokenizer (without text) from a document written by to_json()
+///
+/// # Returns
+/// The Tokenizer, or the reason the document cannot be read
+pub(crate) fn from_json(text: &str) -> Result<Tokenizer, String> {
+    let Json::Object(mut fields) = JsonCursor::new(text).document()? else {
+        return Err("the configuration is not a JSON object".to_string());
+    };
+
+    // STEP 1: Check that the file is a configuration this version can read
+    if string(take(&mut fields, "format"))?.as_deref() != Some(FORMAT) {
+        return Err(format!(
+            "not a tokenizer configuration (no \"format\": \"{}\")",
+            FORMAT
+        ));
+    }
+    match number(take(&mut fields, "version"), "version")? {
+        Some(version) if version as u32 == FORMAT_VERSION => {}
+        version => {
+            return Err(format!(
+                "unsupported configuration version {:?} (this version reads {})",
+                version, FORMAT_VERSION
+            ))
+        }
+    }
+    match number(take(&mut fields, "rules_version"), "rules_version")? {
+        Some(version) if version as u32 == RULES_VERSION => {}
+        Some(version) => {
+            return Err(format!(
+                "the file was written with tokenization rules version {} (this version has {})",
+                version, RULES_VERSION
+            ))
+        }
+        None => return Err("the file has no tokenization rules version".to_string()),
+    }
+
+    // STEP 2: The vocabulary goes first so every token keeps its id
+    let mut vocab = Vocabulary::new();
+    for token in strings(take(&mut fields, "vocabulary"), "vocabulary")? {
+        vocab.add_token(&token);
+    }
+    let mut special_tokens = SpecialTokens::new();
+    for token in strings(take(&mut fields, "special_tokens"), "special_tokens")? {
+        special_tokens.register(&token);
+    }
+    let structure_tokens = boolean(take(&mut fields, "structure_tokens"), "structure_tokens")?;
+    let mut tokenizer = Tokenizer::new(String::new())
+        .with_vocabulary(vocab)
+        .with_special_tokens(special_tokens)
+        .with_structure_tokens(structure_tokens);
+    tokenizer.add_tokens(strings(take(&mut fields, "added_tokens"), "added_tokens")?);
+
+    // STEP 3: The other builder settings
+    match take(&mut fields, "identifiers") {
+        None | Some(Json::Scalar(_)) => {}
+        Some(Json::Object(mut identifiers)) => {
+            let mut detector = IdentifierDetector::new();
+            if let Some(length) = number(take(&mut identifiers, "min_length"), "min_length")? {
+                detector = detector.with_min_length(length);
+            }
+            tokenizer = tokenizer.with_identifiers(detector);
+        }
+        Some(_) => return Err("\"identifiers\" is not an object".to_string()),
+    }
+    if let Some(hint) = number(take(&mut fields, "capacity_hint"), "capacity_hint")? {
+        tokenizer = tokenizer.with_capacity_hint(hint);
+    }
+    let estimated = boolean(
+        take(&mut fields, "estimated_capacity"),
+        "estimated_capacity",
+    )?;
+    tokenizer = tokenizer.with_estimated_capacity(estimated);
+    tokenizer = tokenizer.with_filters(parse_filters(take(&mut fields, "filters"))?);
+    match take(&mut fields, "sentence_filter") {
+        None | Some(Json::Scalar(_)) => {}
+        Some(Json::Object(mut filter)) => {
+            let mut step = ManifestStep::new("sentence_filter");
+            step.lines = strings(take(&mut filter, "settings"), "settings")?;
+            let filter = SentenceFilter::from_step(&step)
+                .ok_or("the settings of \"sentence_filter\" cannot be read")?;
+            tokenizer = tokenizer.with_sentence_filter(filter);
+        }
+        Some(_) => return Err("\"sentence_filter\" is not an object".to_string()),
+    }
+    let encoder = match take(&mut fields, "encoder") {
+        Some(Json::Object(encoder)) => parse_encoder(encoder)?,
+        None | Some(Json::Scalar(_)) => EncoderConfig::new(),
+        Some(_) => return Err("\"encoder\" is not an object".to_string()),
+    };
+    Ok(tokenizer.with_encoder_config(encoder))
+}
+
+/// Rebuilds the token filters from their array of {"name", "settings"} objects
+fn parse_filters(value: Option<Json>) -> Result<FilterPipeline, String> {
+    if is_null(&value) {
+        return Ok(FilterPipeline::new());
+    }
+    let Some(Json::Array(items)) = value else {
+        return Err("\"filters\" is not an array".to_string());
+    };
+    let mut steps = Vec::new();
+    for (position, item) in items.into_iter().enumerate() {
+        let Json::Object(mut filter) = item else {
+            return Err("\"filters\" has a value that is not an object".to_string());
+        };
+        let name = string(take(&mut filter, "name"))?.ok_or("a filter has no \"name\"")?;
+        let mut step = ManifestStep::new(&name);
+        step.push("position", position);
+        step.lines
+            .extend(strings(take(&mut filter, "settings"), "settings")?);
+        steps.push(step);
+    }
+    let steps: Vec<&ManifestStep> = steps.iter().collect();
+    FilterPipeline::from_steps(&steps)
+        .ok_or_else(|| "a filter is unknown or its settings cannot be read".to_string())
+}
+
+/// Rebuilds the encoding settings from their object
+fn parse_encoder(mut fields: Vec<(String, Json)>) -> Result<EncoderConfig, String> {
+    let mut config = EncoderConfig::new();
+
+    let policy = match string(take(&mut fields, "unknown"))?.as_deref() {
+        None | Some("skip") => UnknownPolicy::Skip,
+        Some("byte_fallback") => UnknownPolicy::ByteFallback,
+        Some("token") => UnknownPolicy::UnknownToken(
+            string(take(&mut fields, "unknown_token"))?
+                .ok_or("\"unknown\": \"token\" needs an \"unknown_token\"")?,
+        ),
+        Some(other) => return Err(format!("unknown \"unknown\" policy {:?}", other)),
+    };
+    config = config.with_unknown_policy(policy);
+
+    match take(&mut fields, "template") {
+        None | Some(Json::Scalar(_)) => {}
+        Some(Json::Object(mut template)) => {
+            let single = string(take(&mut template, "single"))?;
+            let pair = string(take(&mut template, "pair"))?;
+            let (Some(single), Some(pair)) = (single, pair) else {
+                return Err("\"template\" needs \"single\" and \"pair\"".to_string());
+            };
+            config = config.with_template(Template::new(&single, &pair));
+        }
+        Some(_) => return Err("\"template\" is not an object".to_string()),
+    }
+    if let Some(length) = number(take(&mut fields, "max_length"), "max_length")? {
+        config = config.with_max_length(length);
+    }
+    let truncation = match string(take(&mut fields, "truncation"))?.as_deref() {
+        None | Some("longest_first") => TruncationStrategy::LongestFirst,
+        Some("only_first") => TruncationStrategy::OnlyFirst,
+        Some("only_second") => TruncationStrategy::OnlySecond,
+        Some(other) => return Err(format!("unknown truncation {:?}", other)),
+    };
+    config = config.with_truncation_strategy(truncation);
+
+    match take(&mut fields, "padding") {
+        None | Some(Json::Scalar(_)) => {}
+        Some(Json::Object(mut padding)) => {
+            let strategy = match string(take(&mut padding, "strategy"))?.as_deref() {
+                Some("batch_longest") => PaddingStrategy::BatchLongest,
+                Some("fixed") => PaddingStrategy::Fixed(
+                    number(take(&mut padding, "length"), "length")?
+                        .ok_or("fixed padding needs a \"length\"")?,
+                ),
+                other => return Err(format!("unknown padding strategy {:?}", other)),
+            };
+            let side = match string(take(&mut padding, "side"))?.as_deref() {
+                None | Some("right") => PaddingSide::Right,
+                Some("left") => PaddingSide::Left,
+                Some(other) => return Err(format!("unknown padding side {:?}", other)),
+            };
+            let mut settings = Padding::new(strategy).with_side(side);
+            if let Some(token) = string(take(&mut padding, "pad_token"))? {
+                settings = settings.with_pad_token(&token);
+            }
+            if let Some(type_id) = number(take(&mut padding, "pad_type_id"), "pad_type_id")? {
+                settings = settings.with_pad_type_id(type_id as u32);
+            }
+            config = config.with_padding(settings);
+        }
+        Some(_) => return Err("\"padding\" is not an object".to_string()),
+    }
+    if let Some(stride) = number(
+        take(&mut fields, "overflowing_stride"),
+        "overflowing_stride",
+    )? {
+        config = config.with_overflowing(stride);
+    }
+    match take(&mut fields, "subwords") {
+        None | Some(Json::Scalar(_)) => {}
+        Some(Json::Object(mut subwords)) => {
+            let kind = string(take(&mut subwords, "model"))?;
+            let kind = kind
+                .as_deref()
+                .and_then(SubwordKind::from_name)
+                .ok_or_else(|| format!("unknown subword model {:?}", kind))?;
+            let merges = strings(take(&mut subwords, "merges"), "merges")?
+                .into_iter()
+                .map(|merge| match merge.split_once(' ') {
+                    Some((left, right)) => Ok((left.to_string(), right.to_string())),
+                    None => Err(format!("the merge {:?} is not two pieces", merge)),
+                })
+                .collect::<Result<Vec<_>, String>>()?;
+            let pieces = strings(take(&mut subwords, "pieces"), "pieces")?;
+            config = config.with_subwords(SubwordModel::from_parts(kind, pieces, merges));
+        }
+        Some(_) => return Err("\"subwords\" is not an object".to_string()),
+    }
+
+    Ok(config)
+}
+
+/// Removes a field from a JSON object and returns its value
+fn take(fields: &mut Vec<(String, Json)>, name: &str) -> Option<Json> {
+    let index = fields.iter().position(|(key, _)| key == name)?;
+    Some(fields.swap_remove(index).1)
+}
+
+/// Reads an optional string (a missing value and null are None)
+fn string(value: Option<Json>) -> Result<Option<String>, String> {
+    match value {
+        Some(Json::String(text)) => Ok(Some(text)),
+        None | Some(Json::Scalar(_)) if is_null(&value) => Ok(None),
+        _ => Err("expected a string".to_string()),
+    }
+}
+
+/// Reads an optional true or false (a missing value is false)
+fn boolean(value: Option<Json>, name: &str) -> Result<bool, String> {
+    match value {
+        None => Ok(false),
+        Some(Json::Scalar(text)) if text == "true" || text == "false" => Ok(text == "true"),
+        Some(_) => Err(format!("\"{}\" is not true or false", name)),
+    }
+}
+
+/// Reads an optional non-negative integer (a missing value and null are None)
+fn number(value: Option<Json>, name: &str) -> Result<Option<usize>, String> {
+    if is_null(&value) {
+        return Ok(None);
+    }
+    match value {
+        Some(Json::Scalar(text)) => text
+            .parse()
+            .map(Some)
+            .map_err(|_| format!("\"{}\" is not a non-negative integer", name)),
+        _ => Err(format!("\"{}\" is not a number", name)),
+    }
+}
+
+/// Reads an optional array of strings (a missing value and null are empty)
+fn strings(value: Option<Json>, name: &str) -> Result<Vec<String>, String> {
+    if is_null(&value) {
+        return Ok(Vec::new());
+    }
+    let Some(Json::Array(items)) = value else {
+        return Err(format!("\"{}\" is not an array", name));
+    };
+    items
+        .into_iter()
+        .map(|item| match item {
+            Json::String(text) => Ok(text),
+            _ => Err(format!("\"{}\" has a value that is not a string", name)),
+        })
+        .collect()
+}
+
+/// Returns true for a missing value or null
+fn is_null(value: &Option<Json>) -> bool {
+    match value {
+        None => true,
+        Some(Json::Scalar(text)) => text == "null",
+        Some(_) => false,
+    }
+}
@@ -0,0 +1,220 @@
+// Reading and writing tokenized documents in the formats other tools exchange
+// Every format stores a sequence of documents (or sentences), each a list of tokens:
+// - jsonl: one OutputRecord per line, {"schema_version":1,"tokens":["Hello",",","world"]}
+// - conll: CoNLL-U, one token per line (ID and FORM columns, the others "_"),
+//   a blank line after each sentence, "#" comment lines ignored
+// - lines: one token per line, a blank line after each document
+// - text: one document per line, as detokenized text (tokenized again when read)
+// A DocumentReader turns any of them into OutputRecords, and convert() writes them to any
+// OutputWriter (the writers of the same formats, CSV or Arrow are in the output module).
+// The block formats (conll, lines) can't hold an empty document: it is not written.
+
+use std::io::{self, BufRead};
+
+use crate::output::{OutputRecord, OutputWriter};
+use crate::Tokenizer;
+
+/// A format of tokenized documents
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Format {
+    /// One OutputRecord (JSON object) per line
+    Jsonl,
+    /// CoNLL-U: one token per line, sentences separated by blank lines
+    Conll,
+    /// One token per line, documents separated by blank lines
+    Lines,
+    /// One detokenized document per line
+    Text,
+}
+
+impl Format {
+    /// Every format, in the order of the usage text
+    pub const ALL: [Format; 4] = [Format::Jsonl, Format::Conll, Format::Lines, Format::Text];
+
+    /// Returns the format with this name ("jsonl", "conll", "lines" or "text")
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::convert::Format;
+    ///
+    /// assert_eq!(Format::from_name("CoNLL"), Some(Format::Conll));
+    /// assert_eq!(Format::from_name("xml"), None);
+    /// ```
+    pub fn from_name(name: &str) -> Option<Format> {
+        Format::ALL
+            .into_iter()
+            .find(|format| format.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Returns the name of the format, as accepted by from_name()
+    pub fn name(&self) -> &'static str {
+        match self {
+            Format::Jsonl => "jsonl",
+            Format::Conll => "conll",
+            Format::Lines => "lines",
+            Format::Text => "text",
+        }
+    }
+}
+
+/// Reads the documents of a reader in one format, as OutputRecords
+///
+/// Records read from text keep the text and the token offsets, records read from JSONL are
+/// the ones written; the other formats only have the tokens.
+///
+/// Malformed input (broken JSON, a CoNLL line without a FORM column) is an InvalidData
+/// error naming the line.
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::convert::{DocumentReader, Format};
+/// use tokenizer_rust::output::OutputRecord;
+///
+/// let conll = "# sent_id = 1\n1\tHello\t_\t_\t_\t_\t_\t_\t_\t_\n2\t!\t_\t_\t_\t_\t_\t_\t_\t_\n\n";
+/// let documents: Vec<OutputRecord> = DocumentReader::new(conll.as_bytes(), Format::Conll)
+///     .collect::<Result<_, _>>()
+///     .unwrap();
+/// assert_eq!(documents, vec![OutputRecord::new(vec!["Hello".to_string(), "!".to_string()])]);
+/// ```
+pub struct DocumentReader<R> {
+    /// Where the documents are read from
+    reader: R,
+    /// The format of the input
+    format: Format,
+    /// Tokenizes the documents of the text format
+    tokenizer: Tokenizer,
+    /// Number of the last line read, for error messages
+    line: usize,
+}
+
+impl<R: BufRead> DocumentReader<R> {
+    /// Creates a reader of documents in `format`
+    pub fn new(reader: R, format: Format) -> Self {
+        DocumentReader {
+            reader,
+            format,
+            tokenizer: Tokenizer::new(String::new()),
+            line: 0,
+        }
+    }
+
+    /// Reads the next line without its line ending, None at the end of the input
+    fn next_line(&mut self) -> Option<io::Result<String>> {
+        let mut line = String::new();
+        match self.reader.read_line(&mut line) {
+            Ok(0) => None,
+            Ok(_) => {
+                self.line += 1;
+                let end = line.trim_end_matches(['\n', '\r']).len();
+                line.truncate(end);
+                Some(Ok(line))
+            }
+            Err(error) => Some(Err(error)),
+        }
+    }
+
+    /// Returns an InvalidData error for the current line
+    fn invalid(&self, message: &str) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("line {}: {}", self.line, message),
+        )
+    }
+
+    /// Reads the tokens of one document of a block format (conll or lines)
+    fn read_block(&mut self) -> Option<io::Result<OutputRecord>> {
+        let mut tokens = Vec::new();
+        while let Some(line) = self.next_line() {
+            let line = match line {
+                Ok(line) => line,
+                Err(error) => return Some(Err(error)),
+            };
+            if line.trim().is_empty() {
+                // A blank line ends the document (several blank lines are one separator)
+                if tokens.is_empty() {
+                    continue;
+                }
+                return Some(Ok(OutputRecord::new(tokens)));
+            }
+            if self.format == Format::Lines {
+                tokens.push(line);
+                continue;
+            }
+
+            // CoNLL-U: skip comments, multiword ranges ("1-2") and empty nodes ("1.1")
+            if line.starts_with('#') {
+                continue;
+            }
+            let mut columns = line.split('\t');
+            let id = columns.next().unwrap_or_default();
+            match columns.next() {
+                Some(_) if id.contains(['-', '.']) => {}
+                Some(form) => tokens.push(form.to_string()),
+                None => return Some(Err(self.invalid("a CoNLL token line needs a FORM column"))),
+            }
+        }
+        (!tokens.is_empty()).then(|| Ok(OutputRecord::new(tokens)))
+    }
+}
+
+impl<R: BufRead> Iterator for DocumentReader<R> {
+    type Item = io::Result<OutputRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.format {
+            Format::Conll | Format::Lines => self.read_block(),
+            Format::Text => Some(self.next_line()?.map(|line| {
+                OutputRecord::from_tokens(&line, &self.tokenizer.tokenize_text(&line))
+            })),
+            Format::Jsonl => loop {
+                let line = match self.next_line()? {
+                    Ok(line) => line,
+                    Err(error) => return Some(Err(error)),
+                };
+                if line.trim().is_empty() {
+                    continue;
+                }
+                return Some(
+                    OutputRecord::from_json(&line)
+                        .map_err(|error| self.invalid(&error.to_string())),
+                );
+            },
+        }
+    }
+}
+
+/// Converts every document of a reader in one format into records written to `output`
+///
+/// The output is not finished, so that several inputs can go to the same output: call
+/// finish() after the last one.
+///
+/// # Returns
+/// The number of documents converted
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::convert::{convert, Format};
+/// use tokenizer_rust::output::{ConllWriter, OutputWriter, TextWriter};
+///
+/// let text = "Hello, world!\nSee you.\n";
+/// let mut conll = ConllWriter::new(Vec::new());
+/// assert_eq!(convert(text.as_bytes(), Format::Text, &mut conll).unwrap(), 2);
+/// conll.finish().unwrap();
+///
+/// // And back: the detokenized text is the original one
+/// let mut back = TextWriter::new(Vec::new());
+/// convert(conll.into_inner().as_slice(), Format::Conll, &mut back).unwrap();
+/// assert_eq!(String::from_utf8(back.into_inner()).unwrap(), text);
+/// ```
+pub fn convert<R: BufRead, O: OutputWriter + ?Sized>(
+    reader: R,
+    from: Format,
+    output: &mut O,
+) -> io::Result<usize> {
+    let mut documents = 0;
+    for record in DocumentReader::new(reader, from) {
+        output.write_record(&record?)?;
+        documents += 1;
+    }
+    Ok(documents)
+}
@@ -0,0 +1,430 @@
+// Corpus tokenization: tokenize every file of a directory (or a list of files)
+// A corpus is collected from a directory tree, from a glob pattern like
+// "corpus/**/*.txt", or from a list of files, and can be narrowed down with include and
+// exclude patterns ("*.txt", "drafts/**").
+// Files are tokenized in parallel when the `parallel` feature is on, and every result is
+// handed to a callback as soon as it is ready, so nothing has to hold the whole corpus in
+// memory. A file that cannot be read is reported in the summary and the run goes on.
+// Files ending with .gz or .zst are decompressed as they are read (see the compress module).
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::compress;
+use crate::token::Token;
+use crate::Tokenizer;
+
+/// A file (or directory) that could not be read, and why
+#[derive(Debug)]
+pub struct FileError {
+    /// The file or directory
+    pub path: PathBuf,
+    /// What went wrong
+    pub error: io::Error,
+}
+
+/// What happened during Corpus::tokenize()
+#[derive(Debug, Default)]
+pub struct CorpusSummary {
+    /// Number of files tokenized successfully
+    pub files: usize,
+    /// Total number of tokens of those files
+    pub tokens: usize,
+    /// The files that could not be read
+    pub errors: Vec<FileError>,
+}
+
+/// A list of files to tokenize
+#[derive(Debug, Default)]
+pub struct Corpus {
+    /// The files, in the order they were found
+    files: Vec<PathBuf>,
+    /// The directories that could not be listed while looking for files
+    walk_errors: Vec<FileError>,
+    /// The directory the files were collected from (from_dir() and from_glob())
+    root: Option<PathBuf>,
+}
+
+impl Corpus {
+    /// Collects every file of a directory and its subdirectories, sorted by path
+    ///
+    /// Symbolic links to directories are not followed, so links cannot create loops.
+    /// Subdirectories that cannot be listed are skipped and returned by walk_errors().
+    ///
+    /// # Arguments
+    /// * `directory` - The root directory of the corpus
+    ///
+    /// # Returns
+    /// The corpus, or an error if the root directory itself cannot be listed
+    pub fn from_dir<P: AsRef<Path>>(directory: P) -> io::Result<Self> {
+        let mut corpus = Corpus::default();
+
+        // The root must be readable, anything below it is reported per directory
+        let root = directory.as_ref();
+        let entries = fs::read_dir(root)?;
+        corpus.walk(root, entries);
+
+        corpus.files.sort();
+        corpus.root = Some(root.to_path_buf());
+        Ok(corpus)
+    }
+
+    /// Collects the files whose path matches a glob pattern, sorted by path
+    ///
+    /// The directory before the first wildcard is walked like in from_dir(), and the paths
+    /// of its files are matched against the whole pattern (see glob_match()). A pattern
+    /// without wildcards is a single file.
+    ///
+    /// # Arguments
+    /// * `pattern` - A pattern like "corpus/*.txt" or "corpus/**/*.jsonl"
+    ///
+    /// # Returns
+    /// The corpus, or an error if the directory before the first wildcard cannot be listed
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::corpus::Corpus;
+    ///
+    /// let directory = std::env::temp_dir().join("tokenizer_glob_doctest");
+    /// std::fs::create_dir_all(directory.join("news/2024")).unwrap();
+    /// std::fs::write(directory.join("news/a.txt"), "A.").unwrap();
+    /// std::fs::write(directory.join("news/2024/b.txt"), "B.").unwrap();
+    /// std::fs::write(directory.join("news/2024/b.json"), "{}").unwrap();
+    ///
+    /// let pattern = format!("{}/news/**/*.txt", directory.display());
+    /// let corpus = Corpus::from_glob(&pattern).unwrap();
+    /// assert_eq!(corpus.files().len(), 2);
+    /// assert_eq!(corpus.root(), Some(directory.join("news").as_path()));
+    ///
+    /// let pattern = format!("{}/news/*.txt", directory.display());
+    /// assert_eq!(Corpus::from_glob(&pattern).unwrap().files(), [directory.join("news/a.txt")]);
+    /// # std::fs::remove_dir_all(&directory).unwrap();
+    /// ```
+    pub fn from_glob(pattern: &str) -> io::Result<Self> {
+        let Some(wildcard) = pattern.find(['*', '?', '[']) else {
+            return Ok(Corpus::from_paths([pattern]));
+        };
+
+        // STEP 1: Walk the directory before the first wildcard ("" is the current one)
+        let root = match pattern[..wildcard].rfind('/') {
+            Some(slash) => &pattern[..=slash],
+            None => "",
+        };
+        let mut corpus = Corpus::default();
+        let directory = if root.is_empty() {
+            Path::new(".")
+        } else {
+            Path::new(root)
+        };
+        corpus.walk(directory, fs::read_dir(directory)?);
+
+        // STEP 2: Keep the files whose path matches, written the way the pattern is
+        corpus.files = corpus
+            .files
+            .into_iter()
+            .map(|path| match (root.is_empty(), path.strip_prefix(".")) {
+                (true, Ok(relative)) => relative.to_path_buf(),
+                _ => path,
+            })
+            .filter(|path| glob_match(pattern, &path.to_string_lossy()))
+            .collect();
+        corpus.files.sort();
+        corpus.root = Some(PathBuf::from(root));
+        Ok(corpus)
+    }
+
+    /// Uses the given files as the corpus, in the given order
+    pub fn from_paths<I, P>(paths: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<PathBuf>,
+    {
+        Corpus {
+            files: paths.into_iter().map(Into::into).collect(),
+            walk_errors: Vec::new(),
+            root: None,
+        }
+    }
+
+    /// Keeps only the files that match at least one of the patterns
+    ///
+    /// A pattern without '/' is matched against the file name ("*.txt"), any other against
+    /// the path below the root of the corpus ("2024/**"), or the whole path without a root.
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::corpus::Corpus;
+    ///
+    /// let corpus = Corpus::from_paths(["a.txt", "b.md", "drafts/c.txt"])
+    ///     .with_include(["*.txt"])
+    ///     .with_exclude(["drafts/**"]);
+    /// assert_eq!(corpus.files(), [std::path::PathBuf::from("a.txt")]);
+    /// ```
+    pub fn with_include<I, S>(mut self, patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let patterns: Vec<S> = patterns.into_iter().collect();
+        if patterns.is_empty() {
+            return self;
+        }
+        let root = self.root.as_deref();
+        self.files.retain(|path| {
+            patterns
+                .iter()
+                .any(|pattern| matches_file(pattern.as_ref(), path, root))
+        });
+        self
+    }
+
+    /// Drops the files that match any of the patterns (written like in with_include())
+    pub fn with_exclude<I, S>(mut self, patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let patterns: Vec<S> = patterns.into_iter().collect();
+        let root = self.root.as_deref();
+        self.files.retain(|path| {
+            !patterns
+                .iter()
+                .any(|pattern| matches_file(pattern.as_ref(), path, root))
+        });
+        self
+    }
+
+    /// Returns the files of the corpus
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    /// Returns the directory the files were collected from, if any
+    ///
+    /// Every file path starts with it, so strip_prefix() gives the path of a file within
+    /// the corpus ("" for a glob pattern that starts with a wildcard).
+    pub fn root(&self) -> Option<&Path> {
+        self.root.as_deref()
+    }
+
+    /// Returns the subdirectories that could not be listed by from_dir()
+    pub fn walk_errors(&self) -> &[FileError] {
+        &self.walk_errors
+    }
+
+    /// Tokenizes every file and hands each result to a callback
+    ///
+    /// With the `parallel` feature the files are spread over all CPU cores and the callback
+    /// is called from several threads, in no particular order. To collect the results,
+    /// use a Mutex or send them through a channel.
+    ///
+    /// # Arguments
+    /// * `tokenizer` - The tokenizer whose settings are used
+    /// * `on_file` - Called with the path and the tokens of every file that was read
+    ///
+    /// # Returns
+    /// How many files and tokens were processed, and which files could not be read
+    ///
+    /// # Example
+    /// ```
+    /// use std::sync::Mutex;
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::corpus::Corpus;
+    ///
+    /// let directory = std::env::temp_dir().join("tokenizer_corpus_doctest");
+    /// std::fs::create_dir_all(directory.join("chapter")).unwrap();
+    /// std::fs::write(directory.join("a.txt"), "Hello, world!").unwrap();
+    /// std::fs::write(directory.join("chapter/b.txt"), "One more file.").unwrap();
+    /// std::fs::write(directory.join("broken.txt"), [0xff, 0xfe]).unwrap();
+    ///
+    /// let corpus = Corpus::from_dir(&directory).unwrap();
+    /// let counts = Mutex::new(Vec::new());
+    /// let summary = corpus.tokenize(&Tokenizer::new(String::new()), |path, tokens| {
+    ///     counts.lock().unwrap().push((path.file_name().unwrap().to_owned(), tokens.len()));
+    /// });
+    ///
+    /// assert_eq!(summary.files, 2);
+    /// assert_eq!(summary.tokens, 8);
+    /// assert_eq!(summary.errors.len(), 1);
+    /// assert!(summary.errors[0].path.ends_with("broken.txt"));
+    ///
+    /// let mut counts = counts.into_inner().unwrap();
+    /// counts.sort();
+    /// assert_eq!(counts, vec![("a.txt".into(), 4), ("b.txt".into(), 4)]);
+    /// # std::fs::remove_dir_all(&directory).unwrap();
+    /// ```
+    pub fn tokenize<F>(&self, tokenizer: &Tokenizer, on_file: F) -> CorpusSummary
+    where
+        F: Fn(&Path, Vec<Token>) + Sync,
+    {
+        // STEP 1: Tokenize every file, keeping only the token count or the error
+        let results = map_files(&self.files, |path| {
+            let text = compress::read_to_string(path)?;
+            let tokens = tokenizer.tokenize_text(&text);
+            let count = tokens.len();
+            on_file(path, tokens);
+            Ok(count)
+        });
+
+        // STEP 2: Sum everything up
+        let mut summary = CorpusSummary::default();
+        for (path, result) in self.files.iter().zip(results) {
+            match result {
+                Ok(count) => {
+                    summary.files += 1;
+                    summary.tokens += count;
+                }
+                Err(error) => summary.errors.push(FileError {
+                    path: path.clone(),
+                    error,
+                }),
+            }
+        }
+
+        summary
+    }
+
+    /// Adds the files of a directory listing, going down into subdirectories
+    fn walk(&mut self, directory: &Path, entries: fs::ReadDir) {
+        for entry in entries {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(error) => {
+                    self.walk_errors.push(FileError {
+                        path: directory.to_path_buf(),
+                        error,
+                    });
+                    continue;
+                }
+            };
+
+            let path = entry.path();
+            // file_type() does not follow symbolic links
+            match entry.file_type() {
+                Ok(kind) if kind.is_dir() => match fs::read_dir(&path) {
+                    Ok(entries) => self.walk(&path, entries),
+                    Err(error) => self.walk_errors.push(FileError { path, error }),
+                },
+                Ok(kind) if kind.is_file() => self.files.push(path),
+                // A link is used if it points to a file
+                Ok(kind) if kind.is_symlink() && path.is_file() => self.files.push(path),
+                Ok(_) => {}
+                Err(error) => self.walk_errors.push(FileError { path, error }),
+            }
+        }
+    }
+}
+
+/// Runs a function on every file, in parallel when the `parallel` feature is on
+#[cfg(feature = "parallel")]
+fn map_files<F>(files: &[PathBuf], function: F) -> Vec<io::Result<usize>>
+where
+    F: Fn(&Path) -> io::Result<usize> + Sync,
+{
+    use rayon::prelude::*;
+
+    // collect() on an indexed parallel iterator keeps the original order
+    files.par_iter().map(|path| function(path)).collect()
+}
+
+/// Runs a function on every file, in parallel when the `parallel` feature is on
+#[cfg(not(feature = "parallel"))]
+fn map_files<F>(files: &[PathBuf], function: F) -> Vec<io::Result<usize>>
+where
+    F: Fn(&Path) -> io::Result<usize>,
+{
+    files.iter().map(|path| function(path)).collect()
+}
+
+/// Returns true if a path matches a glob pattern
+///
+/// `*` matches any characters but '/', `?` one character but '/', `[abc]`, `[a-z]` and
+/// `[!abc]` one character of (or not of) a set, and `**/` any number of directories.
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::corpus::glob_match;
+///
+/// assert!(glob_match("corpus/*.txt", "corpus/a.txt"));
+/// assert!(!glob_match("corpus/*.txt", "corpus/news/a.txt"));
+/// assert!(glob_match("corpus/**/*.txt", "corpus/a.txt"));
+/// assert!(glob_match("corpus/**/*.txt", "corpus/news/2024/a.txt"));
+/// assert!(glob_match("part-[0-9][0-9].jsonl", "part-07.jsonl"));
+/// assert!(!glob_match("[!.]*", ".hidden"));
+/// ```
+pub fn glob_match(pattern: &str, path: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let path: Vec<char> = path.chars().collect();
+    match_from(&pattern, &path)
+}
+
+/// Matches the rest of a pattern against the rest of a path
+fn match_from(pattern: &[char], path: &[char]) -> bool {
+    match pattern {
+        [] => path.is_empty(),
+        // "**/" is zero or more whole directories, a final "**" anything left
+        ['*', '*', '/', rest @ ..] => (0..=path.len())
+            .filter(|&start| start == 0 || path[start - 1] == '/')
+            .any(|start| match_from(rest, &path[start..])),
+        ['*', '*'] => true,
+        ['*', rest @ ..] => {
+            let segment = path.iter().position(|&c| c == '/').unwrap_or(path.len());
+            (0..=segment).any(|start| match_from(rest, &path[start..]))
+        }
+        ['?', rest @ ..] => match path {
+            [c, tail @ ..] if *c != '/' => match_from(rest, tail),
+            _ => false,
+        },
+        ['[', rest @ ..] => match (parse_class(rest), path) {
+            (Some((matches, after)), [c, tail @ ..]) => {
+                *c != '/' && matches(*c) && match_from(after, tail)
+            }
+            // A '[' without its ']' is a plain character
+            (None, ['[', tail @ ..]) => match_from(rest, tail),
+            _ => false,
+        },
+        [literal, rest @ ..] => match path {
+            [c, tail @ ..] if c == literal => match_from(rest, tail),
+            _ => false,
+        },
+    }
+}
+
+/// Parses a character class after its '[', returning a matcher and the pattern after ']'
+fn parse_class(pattern: &[char]) -> Option<(impl Fn(char) -> bool + '_, &[char])> {
+    let (negated, body) = match pattern {
+        ['!', body @ ..] => (true, body),
+        body => (false, body),
+    };
+    // A ']' right after the '[' (or '[!') is part of the set
+    let end = body.iter().skip(1).position(|&c| c == ']')? + 1;
+    let set = &body[..end];
+    let matches = move |c: char| {
+        let mut index = 0;
+        let mut found = false;
+        while index < set.len() {
+            if index + 2 < set.len() && set[index + 1] == '-' {
+                found |= set[index] <= c && c <= set[index + 2];
+                index += 3;
+            } else {
+                found |= set[index] == c;
+                index += 1;
+            }
+        }
+        found != negated
+    };
+    Some((matches, &body[end + 1..]))
+}
+
+/// Returns true if a file matches an include or exclude pattern (see Corpus::with_include())
+fn matches_file(pattern: &str, path: &Path, root: Option<&Path>) -> bool {
+    if !pattern.contains('/') {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        return glob_match(pattern, &name);
+    }
+    let relative = root
+        .and_then(|root| path.strip_prefix(root).ok())
+        .unwrap_or(path);
+    glob_match(pattern, &relative.to_string_lossy())
+}
@@ -0,0 +1,170 @@
+// Graphemes, words, sentences and tokens of a text, counted the same way everywhere
+// Apps usually need these four numbers together (a character counter, a word count, a
+// token budget) and end up computing each one with its own definition. Tokenizer::counts()
+// returns all of them with the definitions used in the rest of the crate:
+// - graphemes: user-perceived characters ("é" written as e + accent, a flag or a family emoji is one)
+// - words: Word tokens (numbers, punctuation and identifiers are not words), as in readability
+// - sentences: the sentences of the default SentenceSplitter
+// - tokens: the tokens of tokenize_text()
+// The grapheme rules are the main ones of Unicode extended grapheme clusters (UAX #29):
+// combining marks, joiners, emoji modifiers, regional indicator pairs, Hangul syllables,
+// Devanagari conjuncts and CR LF. This covers the scripts the crate knows without a table of
+// every Unicode character.
+
+/// The sizes of a text in graphemes, words, sentences and tokens
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::Tokenizer;
+///
+/// let counts = Tokenizer::new(String::new()).counts("Café au lait? Oui, 2 cafés. 👍🏽");
+/// assert_eq!(counts.graphemes, 29);
+/// assert_eq!(counts.words, 5);
+/// assert_eq!(counts.sentences, 3);
+/// assert_eq!(counts.tokens, 11);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Counts {
+    /// Number of user-perceived characters (see count_graphemes())
+    pub graphemes: usize,
+    /// Number of Word tokens
+    pub words: usize,
+    /// Number of sentences
+    pub sentences: usize,
+    /// Number of tokens
+    pub tokens: usize,
+}
+
+/// Counts the user-perceived characters (grapheme clusters) of a text
+///
+/// A character starts a new grapheme unless it extends the previous one: a combining mark,
+/// a variation selector or an emoji skin tone, the character after a zero-width joiner in an
+/// emoji sequence, the second regional indicator of a flag, the next jamo of a Hangul
+/// syllable, the consonant after a Devanagari virama, or the LF of CR LF.
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::counts::count_graphemes;
+///
+/// assert_eq!(count_graphemes("e\u{301}te\u{301}"), 3);
+/// assert_eq!(count_graphemes("👨‍👩‍👧"), 1);
+/// assert_eq!(count_graphemes("🇫🇷🇩🇪"), 2);
+/// assert_eq!(count_graphemes("नमस्ते"), 3);
+/// assert_eq!(count_graphemes("\u{1100}\u{1161}\u{11A8}"), 1);
+/// assert_eq!(count_graphemes("a\r\nb"), 3);
+/// ```
+pub fn count_graphemes(text: &str) -> usize {
+    let mut count = 0;
+    let mut previous: Option<char> = None;
+    // The number of regional indicators in a row (a flag is a pair of them)
+    let mut regional_run = 0;
+
+    for character in text.chars() {
+        let joined = previous.is_some_and(|previous| extends(previous, character, regional_run));
+        if !joined {
+            count += 1;
+        }
+
+        if is_regional_indicator(character) {
+            regional_run += 1;
+        } else {
+            regional_run = 0;
+        }
+        previous = Some(character);
+    }
+
+    count
+}
+
+/// Returns true if `character` belongs to the same grapheme as the `previous` one
+fn extends(previous: char, character: char, regional_run: usize) -> bool {
+    match (previous, character) {
+        ('\r', '\n') => true,
+        (_, '\r' | '\n') | ('\r' | '\n', _) => false,
+        _ if is_extend(character) => true,
+        ('\u{200D}', _) => is_pictographic(character),
+        _ if is_regional_indicator(previous) && is_regional_indicator(character) => {
+            regional_run % 2 == 1
+        }
+        // Devanagari consonant after a virama (a conjunct like "स्त")
+        ('\u{94D}', '\u{915}'..='\u{939}') => true,
+        _ => hangul_extends(previous, character),
+    }
+}
+
+/// Returns true for characters that never start a grapheme (marks, joiners, modifiers)
+fn is_extend(character: char) -> bool {
+    matches!(
+        character as u32,
+        0x300..=0x36F
+            | 0x483..=0x489
+            | 0x591..=0x5BD
+            | 0x5BF
+            | 0x5C1..=0x5C2
+            | 0x5C4..=0x5C5
+            | 0x5C7
+            | 0x610..=0x61A
+            | 0x64B..=0x65F
+            | 0x670
+            | 0x6D6..=0x6DC
+            | 0x6DF..=0x6E4
+            | 0x6E7..=0x6E8
+            | 0x6EA..=0x6ED
+            | 0x900..=0x903
+            | 0x93A..=0x93C
+            | 0x93E..=0x94F
+            | 0x951..=0x957
+            | 0x962..=0x963
+            | 0xE31
+            | 0xE34..=0xE3A
+            | 0xE47..=0xE4E
+            | 0x1AB0..=0x1AFF
+            | 0x1DC0..=0x1DFF
+            | 0x200C..=0x200D
+            | 0x20D0..=0x20FF
+            | 0x302A..=0x302F
+            | 0x3099..=0x309A
+            | 0xFE00..=0xFE0F
+            | 0xFE20..=0xFE2F
+            | 0x1F3FB..=0x1F3FF
+            | 0xE0020..=0xE007F
+            | 0xE0100..=0xE01EF
+    )
+}
+
+/// Returns true for emoji and pictographs (what a zero-width joiner can join)
+fn is_pictographic(character: char) -> bool {
+    matches!(character as u32, 0x2600..=0x27BF | 0x1F000..=0x1FAFF)
+}
+
+/// Returns true for the regional indicator letters that make flags
+fn is_regional_indicator(character: char) -> bool {
+    matches!(character as u32, 0x1F1E6..=0x1F1FF)
+}
+
+/// Returns true if two Hangul characters are parts of one syllable
+///
+/// A syllable is a leading consonant (L), a vowel (V) and an optional trailing consonant (T),
+/// written as jamo or as a precomposed LV or LVT syllable.
+fn hangul_extends(previous: char, character: char) -> bool {
+    let (previous, character) = (hangul_type(previous), hangul_type(character));
+    matches!(
+        (previous, character),
+        (Some('L'), Some('L' | 'V' | 'v' | 't'))
+            | (Some('V' | 'v'), Some('V' | 'T'))
+            | (Some('T' | 't'), Some('T'))
+    )
+}
+
+/// Returns the Hangul syllable type of a character: 'L', 'V', 'T', 'v' (LV) or 't' (LVT)
+fn hangul_type(character: char) -> Option<char> {
+    match character as u32 {
+        0x1100..=0x115F | 0xA960..=0xA97C => Some('L'),
+        0x1160..=0x11A7 | 0xD7B0..=0xD7C6 => Some('V'),
+        0x11A8..=0x11FF | 0xD7CB..=0xD7FB => Some('T'),
+        code @ 0xAC00..=0xD7A3 if (code - 0xAC00) % 28 == 0 => Some('v'),
+        0xAC00..=0xD7A3 => Some('t'),
+        _ => None,
+    }
+}
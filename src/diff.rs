@@ -0,0 +1,234 @@
+// Token-level diff between two texts
+// Character diffs of prose are hard to read ("c[-a-]{+o+}t"), line diffs are too coarse for
+// paragraphs. Diffing tokens gives what a redlining tool needs: whole words inserted,
+// deleted or substituted, with the byte spans of every change in both texts.
+// The alignment is a longest common subsequence of the two token lists, computed with
+// Hirschberg's algorithm so memory stays linear even for long documents.
+
+use crate::token::Token;
+use crate::Tokenizer;
+
+/// What a diff operation does
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffKind {
+    /// The tokens are the same in both texts
+    Equal,
+    /// The tokens only exist in the new text
+    Insert,
+    /// The tokens only exist in the old text
+    Delete,
+    /// The old tokens were replaced by the new ones
+    Substitute,
+}
+
+/// One operation of a diff: a run of tokens and what happened to it
+///
+/// Spans are (start, end) byte ranges, from the first to the last token of the run.
+/// The side without tokens (the old text of an Insert, the new text of a Delete) gets an
+/// empty span at the end of the token before the change.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffOp {
+    /// What happened
+    pub kind: DiffKind,
+    /// Byte span in the old text
+    pub old: (usize, usize),
+    /// Byte span in the new text
+    pub new: (usize, usize),
+    /// Range of token indices in the old text (start, end exclusive)
+    pub old_tokens: (usize, usize),
+    /// Range of token indices in the new text (start, end exclusive)
+    pub new_tokens: (usize, usize),
+}
+
+/// Computes the token diff between two texts
+///
+/// Both texts are tokenized with the given Tokenizer and tokens are compared by their
+/// text, so changes in whitespace alone are not reported.
+///
+/// # Arguments
+/// * `tokenizer` - The tokenizer whose settings are used
+/// * `old` - The original text
+/// * `new` - The changed text
+///
+/// # Returns
+/// The operations in text order, covering every token of both texts
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::Tokenizer;
+/// use tokenizer_rust::diff::{diff, DiffKind};
+///
+/// let old = "The buyer shall pay within 30 days.";
+/// let new = "The buyer must pay within  60 days, net.";
+/// let ops = diff(&Tokenizer::new(String::new()), old, new);
+///
+/// let changes: Vec<(DiffKind, &str, &str)> = ops
+///     .iter()
+///     .filter(|op| op.kind != DiffKind::Equal)
+///     .map(|op| (op.kind, &old[op.old.0..op.old.1], &new[op.new.0..op.new.1]))
+///     .collect();
+/// assert_eq!(changes, vec![
+///     (DiffKind::Substitute, "shall", "must"),
+///     (DiffKind::Substitute, "30", "60"),
+///     (DiffKind::Insert, "", ", net"),
+/// ]);
+/// ```
+pub fn diff(tokenizer: &Tokenizer, old: &str, new: &str) -> Vec<DiffOp> {
+    let old_tokens = tokenizer.tokenize_text(old);
+    let new_tokens = tokenizer.tokenize_text(new);
+    let old_texts: Vec<&str> = old_tokens.iter().map(|token| token.text.as_str()).collect();
+    let new_texts: Vec<&str> = new_tokens.iter().map(|token| token.text.as_str()).collect();
+
+    // STEP 1: Align the tokens (pairs of equal tokens, in order)
+    let mut pairs = Vec::new();
+    align(&old_texts, &new_texts, 0, 0, &mut pairs);
+
+    // STEP 2: Turn the alignment into runs of equal and changed tokens
+    let old_span = |first: usize, end: usize| span(&old_tokens, first, end);
+    let new_span = |first: usize, end: usize| span(&new_tokens, first, end);
+    let mut ops: Vec<DiffOp> = Vec::new();
+    let mut old_position = 0;
+    let mut new_position = 0;
+    for (old_index, new_index) in pairs
+        .into_iter()
+        .chain(std::iter::once((old_tokens.len(), new_tokens.len())))
+    {
+        // The tokens between two aligned pairs changed
+        let kind = match (old_index > old_position, new_index > new_position) {
+            (true, true) => Some(DiffKind::Substitute),
+            (true, false) => Some(DiffKind::Delete),
+            (false, true) => Some(DiffKind::Insert),
+            (false, false) => None,
+        };
+        if let Some(kind) = kind {
+            ops.push(DiffOp {
+                kind,
+                old: old_span(old_position, old_index),
+                new: new_span(new_position, new_index),
+                old_tokens: (old_position, old_index),
+                new_tokens: (new_position, new_index),
+            });
+        }
+        if old_index == old_tokens.len() {
+            break;
+        }
+
+        // The aligned pair itself extends the current equal run, or starts a new one
+        match ops.last_mut() {
+            Some(op) if op.kind == DiffKind::Equal => {
+                op.old = (op.old.0, old_tokens[old_index].end);
+                op.new = (op.new.0, new_tokens[new_index].end);
+                op.old_tokens.1 += 1;
+                op.new_tokens.1 += 1;
+            }
+            _ => ops.push(DiffOp {
+                kind: DiffKind::Equal,
+                old: old_span(old_index, old_index + 1),
+                new: new_span(new_index, new_index + 1),
+                old_tokens: (old_index, old_index + 1),
+                new_tokens: (new_index, new_index + 1),
+            }),
+        }
+        old_position = old_index + 1;
+        new_position = new_index + 1;
+    }
+
+    ops
+}
+
+/// Returns the byte span of tokens[first..end], or an empty span after tokens[first - 1]
+fn span(tokens: &[Token], first: usize, end: usize) -> (usize, usize) {
+    if first < end {
+        (tokens[first].start, tokens[end - 1].end)
+    } else {
+        let position = first
+            .checked_sub(1)
+            .map_or(0, |previous| tokens[previous].end);
+        (position, position)
+    }
+}
+
+/// Finds a longest common subsequence of `old` and `new` (Hirschberg's algorithm)
+///
+/// The aligned (old index, new index) pairs are pushed in order, shifted by the offsets.
+fn align(
+    old: &[&str],
+    new: &[&str],
+    old_offset: usize,
+    new_offset: usize,
+    pairs: &mut Vec<(usize, usize)>,
+) {
+    // STEP 1: Equal tokens at both ends are aligned right away (most edits are small)
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    pairs.extend((0..prefix).map(|index| (old_offset + index, new_offset + index)));
+    let (old, new) = (&old[prefix..], &new[prefix..]);
+    let (old_offset, new_offset) = (old_offset + prefix, new_offset + prefix);
+
+    let suffix = old
+        .iter()
+        .rev()
+        .zip(new.iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (middle_old, middle_new) = (&old[..old.len() - suffix], &new[..new.len() - suffix]);
+
+    // STEP 2: Split the old tokens in half, and the new ones where the LCS is longest
+    if middle_old.len() == 1 {
+        if let Some(index) = middle_new.iter().position(|token| *token == middle_old[0]) {
+            pairs.push((old_offset, new_offset + index));
+        }
+    } else if !middle_old.is_empty() && !middle_new.is_empty() {
+        let half = middle_old.len() / 2;
+        let forward = lcs_lengths(middle_old[..half].iter(), middle_new.iter());
+        let backward = lcs_lengths(middle_old[half..].iter().rev(), middle_new.iter().rev());
+
+        let length = middle_new.len();
+        let split = (0..=length)
+            .max_by_key(|&split| {
+                (
+                    forward[split] + backward[length - split],
+                    usize::MAX - split,
+                )
+            })
+            .unwrap_or(0);
+        align(
+            &middle_old[..half],
+            &middle_new[..split],
+            old_offset,
+            new_offset,
+            pairs,
+        );
+        align(
+            &middle_old[half..],
+            &middle_new[split..],
+            old_offset + half,
+            new_offset + split,
+            pairs,
+        );
+    }
+
+    let (old_end, new_end) = (old_offset + middle_old.len(), new_offset + middle_new.len());
+    pairs.extend((0..suffix).map(|index| (old_end + index, new_end + index)));
+}
+
+/// Returns the LCS length of `old` and every prefix of `new` (index = prefix length)
+fn lcs_lengths<'a, I, J>(old: I, new: J) -> Vec<usize>
+where
+    I: Iterator<Item = &'a &'a str>,
+    J: Iterator<Item = &'a &'a str> + Clone,
+{
+    let length = new.clone().count();
+    let mut previous = vec![0; length + 1];
+    let mut current = vec![0; length + 1];
+    for old_token in old {
+        for (index, new_token) in new.clone().enumerate() {
+            current[index + 1] = if old_token == new_token {
+                previous[index] + 1
+            } else {
+                previous[index + 1].max(current[index])
+            };
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous
+}
@@ -0,0 +1,520 @@
+// Round-trip-safe token editing
+// detokenize() rebuilds text with its own spacing rules, so "Hello\nworld" comes back as
+// "Hello world". EditableText keeps the exact whitespace after every token instead, so a text
+// can be edited token by token and rendered back without touching anything that wasn't edited.
+
+use crate::tokenizer::Tokenizer;
+
+/// One token of an EditableText together with the whitespace that follows it
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Piece {
+    /// The token text
+    text: String,
+    /// The exact whitespace between this token and the next one (or the end of the text)
+    whitespace_after: String,
+}
+
+/// A tokenized text that can be edited token by token and rendered back exactly
+///
+/// Without edits, render() returns the original text byte for byte.
+/// Whitespace next to an edit is kept when the spacing rules of detokenize() want a space
+/// there (a single space is used if there was none), and dropped when they don't.
+/// Whitespace anywhere else is never touched.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EditableText {
+    /// Whitespace before the first token
+    leading: String,
+    /// The tokens, each with the whitespace that follows it
+    pieces: Vec<Piece>,
+}
+
+impl EditableText {
+    /// Tokenizes a text with the default tokenizer settings
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::edit::EditableText;
+    ///
+    /// let text = EditableText::new("  Hello,\n world!  ");
+    /// assert_eq!(text.len(), 4);
+    /// assert_eq!(text.render(), "  Hello,\n world!  ");
+    /// ```
+    pub fn new(text: &str) -> Self {
+        EditableText::from_tokenizer(&Tokenizer::new(text.to_string()))
+    }
+
+    /// Uses the text and the settings (special tokens, identifiers, ...) of a Tokenizer
+    pub fn from_tokenizer(tokenizer: &Tokenizer) -> Self {
+        let text = tokenizer.original_text();
+        let tokens = tokenizer.tokenize_with_offsets();
+
+        let leading_end = tokens.first().map_or(text.len(), |token| token.start);
+        let mut pieces = Vec::with_capacity(tokens.len());
+        for (index, token) in tokens.iter().enumerate() {
+            // The whitespace after a token runs until the next token starts
+            let next_start = tokens.get(index + 1).map_or(text.len(), |next| next.start);
+            pieces.push(Piece {
+                text: token.text.clone(),
+                whitespace_after: text[token.end..next_start].to_string(),
+            });
+        }
+
+        EditableText {
+            leading: text[..leading_end].to_string(),
+            pieces,
+        }
+    }
+
+    /// Returns the number of tokens
+    pub fn len(&self) -> usize {
+        self.pieces.len()
+    }
+
+    /// Returns true if there are no tokens
+    pub fn is_empty(&self) -> bool {
+        self.pieces.is_empty()
+    }
+
+    /// Returns the token at an index, or None if the index is out of range
+    pub fn token(&self, index: usize) -> Option<&str> {
+        self.pieces.get(index).map(|piece| piece.text.as_str())
+    }
+
+    /// Returns all tokens, in order
+    pub fn tokens(&self) -> Vec<String> {
+        self.pieces.iter().map(|piece| piece.text.clone()).collect()
+    }
+
+    /// Replaces the token at `index` with `new_text`
+    ///
+    /// Replacing a token with an empty (or whitespace-only) text removes it, like remove_token().
+    ///
+    /// # Panics
+    /// Panics if `index` is out of range
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::edit::EditableText;
+    ///
+    /// let mut text = EditableText::new("I like\tcolour  TV.");
+    /// text.replace_token(2, "color");
+    /// assert_eq!(text.render(), "I like\tcolor  TV.");
+    /// ```
+    pub fn replace_token(&mut self, index: usize, new_text: &str) {
+        if new_text.trim().is_empty() {
+            self.remove_token(index);
+            return;
+        }
+
+        self.pieces[index].text = new_text.to_string();
+        self.fix_gap_before(index);
+        self.fix_gap_after(index);
+    }
+
+    /// Inserts a new token before the token at `index` (use len() to append at the end)
+    ///
+    /// Inserting an empty (or whitespace-only) text does nothing.
+    ///
+    /// # Panics
+    /// Panics if `index` is greater than len()
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::edit::EditableText;
+    ///
+    /// let mut text = EditableText::new("Hello\nworld");
+    /// text.insert_token(1, ",");
+    /// text.insert_token(3, "!");
+    /// assert_eq!(text.render(), "Hello,\nworld!");
+    /// ```
+    pub fn insert_token(&mut self, index: usize, new_text: &str) {
+        assert!(
+            index <= self.pieces.len(),
+            "insertion index {} is out of range",
+            index
+        );
+        if new_text.trim().is_empty() {
+            return;
+        }
+
+        // The new token takes over the whitespace that was before the next token,
+        // and a fresh gap is created between the previous token and the new one
+        let whitespace_after = if index > 0 {
+            std::mem::take(&mut self.pieces[index - 1].whitespace_after)
+        } else {
+            String::new()
+        };
+
+        self.pieces.insert(
+            index,
+            Piece {
+                text: new_text.to_string(),
+                whitespace_after,
+            },
+        );
+        self.fix_gap_before(index);
+        self.fix_gap_after(index);
+    }
+
+    /// Removes the token at `index` and returns it
+    ///
+    /// # Panics
+    /// Panics if `index` is out of range
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::edit::EditableText;
+    ///
+    /// let mut text = EditableText::new("This is  really\ngood.");
+    /// text.remove_token(2);
+    /// assert_eq!(text.render(), "This is\ngood.");
+    /// ```
+    pub fn remove_token(&mut self, index: usize) -> String {
+        let removed = self.pieces.remove(index);
+
+        // The whitespace that was before the next token stays there
+        if index > 0 {
+            let was_last = index == self.pieces.len();
+            if !removed.whitespace_after.is_empty() || was_last {
+                self.pieces[index - 1].whitespace_after = removed.whitespace_after;
+            }
+            self.fix_gap_after(index - 1);
+        }
+
+        removed.text
+    }
+
+    /// Replaces the tokens `start..end` with the tokens of `new_text`
+    ///
+    /// `new_text` can hold several tokens: the whitespace between them is kept as written,
+    /// its leading and trailing whitespace is not. The whitespace after the span stays
+    /// after the new tokens. An empty span (`start == end`) inserts before `start`, and an
+    /// empty (or whitespace-only) `new_text` removes the span.
+    ///
+    /// # Panics
+    /// Panics if `start > end` or `end` is greater than len()
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::edit::EditableText;
+    ///
+    /// let mut text = EditableText::new("We met in New York,\ttwice.");
+    /// text.replace_span(3, 5, "the Big Apple");
+    /// assert_eq!(text.render(), "We met in the Big Apple,\ttwice.");
+    ///
+    /// text.replace_span(7, 7, "at least");
+    /// assert_eq!(text.render(), "We met in the Big Apple, at least\ttwice.");
+    /// ```
+    pub fn replace_span(&mut self, start: usize, end: usize, new_text: &str) {
+        assert!(
+            start <= end && end <= self.pieces.len(),
+            "token span {}..{} is out of range",
+            start,
+            end
+        );
+
+        let mut new_pieces = EditableText::new(new_text).pieces;
+        if new_pieces.is_empty() {
+            self.remove_span(start, end);
+            return;
+        }
+
+        // The last new token takes over the whitespace after the span (or, for an
+        // insertion, the whitespace before the token the new ones are inserted before)
+        let whitespace_after = if end > start {
+            std::mem::take(&mut self.pieces[end - 1].whitespace_after)
+        } else if start > 0 {
+            std::mem::take(&mut self.pieces[start - 1].whitespace_after)
+        } else {
+            String::new()
+        };
+        let count = new_pieces.len();
+        new_pieces[count - 1].whitespace_after = whitespace_after;
+
+        self.pieces.splice(start..end, new_pieces);
+        self.fix_gap_before(start);
+        self.fix_gap_after(start + count - 1);
+    }
+
+    /// Removes the tokens `start..end` and returns them
+    ///
+    /// # Panics
+    /// Panics if `start > end` or `end` is greater than len()
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::edit::EditableText;
+    ///
+    /// let mut text = EditableText::new("It is, in fact,\nfine.");
+    /// assert_eq!(text.remove_span(2, 6), vec![",", "in", "fact", ","]);
+    /// assert_eq!(text.render(), "It is\nfine.");
+    /// ```
+    pub fn remove_span(&mut self, start: usize, end: usize) -> Vec<String> {
+        assert!(
+            start <= end && end <= self.pieces.len(),
+            "token span {}..{} is out of range",
+            start,
+            end
+        );
+        (start..end).map(|_| self.remove_token(start)).collect()
+    }
+
+    /// Returns the token spans (start, end) where the tokens of `phrase` occur, in order
+    /// and without overlaps
+    ///
+    /// An empty phrase matches nothing.
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::edit::EditableText;
+    ///
+    /// let text = EditableText::new("New York, New  York! New Jersey.");
+    /// assert_eq!(text.find("New York"), vec![(0, 2), (3, 5)]);
+    /// ```
+    pub fn find(&self, phrase: &str) -> Vec<(usize, usize)> {
+        let phrase = EditableText::new(phrase).tokens();
+        let mut spans = Vec::new();
+        if phrase.is_empty() {
+            return spans;
+        }
+
+        let mut start = 0;
+        while start + phrase.len() <= self.pieces.len() {
+            let end = start + phrase.len();
+            let matches = self.pieces[start..end]
+                .iter()
+                .zip(&phrase)
+                .all(|(piece, token)| piece.text == *token);
+            if matches {
+                spans.push((start, end));
+                start = end;
+            } else {
+                start += 1;
+            }
+        }
+        spans
+    }
+
+    /// Replaces every occurrence of the tokens of `from` with the tokens of `to`
+    ///
+    /// Unlike replace_all(), both sides can hold several tokens, and matching is done on
+    /// tokens, not characters: "cat" does not match inside "concatenate", and any
+    /// whitespace between the tokens of a match is accepted.
+    ///
+    /// # Returns
+    /// The number of replaced occurrences
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::edit::EditableText;
+    ///
+    /// let mut text = EditableText::new("Click log in,\nthen log  in again.");
+    /// assert_eq!(text.replace_phrase("log in", "sign in"), 2);
+    /// assert_eq!(text.render(), "Click sign in,\nthen sign in again.");
+    /// ```
+    pub fn replace_phrase(&mut self, from: &str, to: &str) -> usize {
+        let spans = self.find(from);
+        // Replace from the end so the spans still to replace keep their indices
+        for &(start, end) in spans.iter().rev() {
+            self.replace_span(start, end, to);
+        }
+        spans.len()
+    }
+
+    /// Replaces every token equal to `from` with `to`, keeping the whitespace around it
+    ///
+    /// An empty `from` matches nothing, and an empty `to` removes the matching tokens.
+    ///
+    /// # Returns
+    /// The number of replaced tokens
+    pub fn replace_all(&mut self, from: &str, to: &str) -> usize {
+        self.replace_matching(
+            |token| !from.is_empty() && token == from,
+            |_| to.to_string(),
+        )
+    }
+
+    /// Replaces every token equal to `from` ignoring case, with the casing of the original token
+    ///
+    /// An empty `from` matches nothing, and an empty `to` removes the matching tokens.
+    ///
+    /// # Returns
+    /// The number of replaced tokens
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::edit::EditableText;
+    ///
+    /// let mut text = EditableText::new("COLOUR and colour.\nColour!");
+    /// assert_eq!(text.replace_all_ignore_case("colour", "color"), 3);
+    /// assert_eq!(text.render(), "COLOR and color.\nColor!");
+    /// ```
+    pub fn replace_all_ignore_case(&mut self, from: &str, to: &str) -> usize {
+        let from = from.to_lowercase();
+        self.replace_matching(
+            |token| !from.is_empty() && token.to_lowercase() == from,
+            |token| match_case(token, to),
+        )
+    }
+
+    /// Replaces every token accepted by `matches` with the text built by `replacement`
+    fn replace_matching<M, R>(&mut self, matches: M, replacement: R) -> usize
+    where
+        M: Fn(&str) -> bool,
+        R: Fn(&str) -> String,
+    {
+        let mut count = 0;
+
+        // Walk backwards so removing a token doesn't shift the ones still to visit
+        for index in (0..self.pieces.len()).rev() {
+            if matches(&self.pieces[index].text) {
+                let new_text = replacement(&self.pieces[index].text);
+                self.replace_token(index, &new_text);
+                count += 1;
+            }
+        }
+        count
+    }
+
+    /// Renders the text back, with the exact whitespace of the original
+    pub fn render(&self) -> String {
+        let mut result = self.leading.clone();
+        for piece in &self.pieces {
+            result.push_str(&piece.text);
+            result.push_str(&piece.whitespace_after);
+        }
+        result
+    }
+
+    /// Fixes the gap between the token at `index` and the one before it
+    fn fix_gap_before(&mut self, index: usize) {
+        if index > 0 {
+            self.fix_gap_after(index - 1);
+        }
+    }
+
+    /// Fixes the gap between the token at `index` and the one after it
+    ///
+    /// The gap keeps its whitespace if detokenize() would put a space there (a single
+    /// space is used if the gap was empty), and becomes empty otherwise.
+    fn fix_gap_after(&mut self, index: usize) {
+        if index + 1 >= self.pieces.len() {
+            return;
+        }
+
+        let needs_space =
+            Tokenizer::needs_space(&self.pieces[index].text, &self.pieces[index + 1].text);
+        let gap = &mut self.pieces[index].whitespace_after;
+        if !needs_space {
+            gap.clear();
+        } else if gap.is_empty() {
+            gap.push(' ');
+        }
+    }
+}
+
+/// Replaces every token equal to `from` with `to`
+///
+/// Only whole tokens match: replacing "colour" leaves "colours" alone.
+/// An empty `from` matches nothing. The result can be passed to detokenize()
+/// like any other token list (an empty `to` simply disappears there).
+///
+/// # Arguments
+/// * `tokens` - The tokens to search
+/// * `from` - The token to replace
+/// * `to` - The replacement
+///
+/// # Returns
+/// The tokens with every match replaced
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::Tokenizer;
+/// use tokenizer_rust::edit::replace_all;
+///
+/// let tokenizer = Tokenizer::new("The colour of colours.".to_string());
+/// let tokens = replace_all(&tokenizer.tokenize(), "colour", "color");
+/// assert_eq!(tokenizer.detokenize(&tokens), "The color of colours.");
+/// ```
+pub fn replace_all(tokens: &[String], from: &str, to: &str) -> Vec<String> {
+    tokens
+        .iter()
+        .map(|token| {
+            if !from.is_empty() && token == from {
+                to.to_string()
+            } else {
+                token.clone()
+            }
+        })
+        .collect()
+}
+
+/// Replaces every token equal to `from` ignoring case, with the casing of the original token
+///
+/// "COLOUR" becomes "COLOR", "Colour" becomes "Color" and "colour" becomes "color".
+/// See match_case() for the exact rules. An empty `from` matches nothing.
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::edit::replace_all_ignore_case;
+///
+/// let tokens: Vec<String> = ["Colour", "vs", "COLOUR"].iter().map(|t| t.to_string()).collect();
+/// assert_eq!(replace_all_ignore_case(&tokens, "colour", "color"), vec!["Color", "vs", "COLOR"]);
+/// ```
+pub fn replace_all_ignore_case(tokens: &[String], from: &str, to: &str) -> Vec<String> {
+    let from = from.to_lowercase();
+    tokens
+        .iter()
+        .map(|token| {
+            if !from.is_empty() && token.to_lowercase() == from {
+                match_case(token, to)
+            } else {
+                token.clone()
+            }
+        })
+        .collect()
+}
+
+/// Gives a replacement the casing pattern of the token it replaces
+///
+/// * all uppercase ("COLOUR") -> the replacement in uppercase
+/// * capitalized ("Colour") -> the replacement capitalized
+/// * all lowercase ("colour") -> the replacement in lowercase
+/// * anything else ("cOlOuR") -> the replacement unchanged
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::edit::match_case;
+///
+/// assert_eq!(match_case("Colour", "color"), "Color");
+/// assert_eq!(match_case("COLOUR", "color"), "COLOR");
+/// assert_eq!(match_case("cOlOuR", "color"), "color");
+/// ```
+pub fn match_case(original: &str, replacement: &str) -> String {
+    let letters: Vec<char> = original.chars().filter(|c| c.is_alphabetic()).collect();
+    if letters.is_empty() {
+        return replacement.to_string();
+    }
+
+    let all_upper = letters.iter().all(|c| c.is_uppercase());
+    let all_lower = letters.iter().all(|c| c.is_lowercase());
+    let capitalized = letters[0].is_uppercase() && letters[1..].iter().all(|c| c.is_lowercase());
+
+    // A one-letter token like "I" counts as capitalized, not as uppercase
+    if capitalized {
+        let mut chars = replacement.chars();
+        match chars.next() {
+            Some(first) => first
+                .to_uppercase()
+                .chain(chars.flat_map(|c| c.to_lowercase()))
+                .collect(),
+            None => String::new(),
+        }
+    } else if all_upper {
+        replacement.to_uppercase()
+    } else if all_lower {
+        replacement.to_lowercase()
+    } else {
+        replacement.to_string()
+    }
+}
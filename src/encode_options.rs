@@ -0,0 +1,293 @@
+// Encode options: truncation and padding
+// Models read fixed-size inputs: long texts are truncated to a maximum number of tokens,
+// and short ones are padded with a padding token that the attention mask tells the model to ignore.
+
+use crate::encoding::Encoding;
+
+/// Truncation and padding settings used by encode() and encode_pair()
+///
+/// Usually set through EncoderConfig::with_max_length(), with_truncation_strategy()
+/// and with_padding(), or all at once with EncoderConfig::with_options().
+#[derive(Debug, Clone, Default)]
+pub struct EncodeOptions {
+    /// The maximum number of tokens of an encoding, template tokens included (no limit by default)
+    max_length: Option<usize>,
+    /// Which sequence of a pair is shortened to respect the maximum length
+    truncation_strategy: TruncationStrategy,
+    /// How encodings are padded (no padding by default)
+    padding: Option<Padding>,
+    /// If set, tokens cut off by truncation are kept as extra windows overlapping by this many tokens
+    overflowing_stride: Option<usize>,
+}
+
+impl EncodeOptions {
+    /// Creates the default options (no truncation, no padding)
+    pub fn new() -> Self {
+        EncodeOptions::default()
+    }
+
+    /// Sets the maximum number of tokens of an encoding (template tokens included)
+    pub fn with_max_length(mut self, max_length: usize) -> Self {
+        self.max_length = Some(max_length);
+        self
+    }
+
+    /// Returns the maximum number of tokens of an encoding, if there is one
+    pub fn max_length(&self) -> Option<usize> {
+        self.max_length
+    }
+
+    /// Sets which sequence of a pair is shortened to respect the maximum length
+    pub fn with_truncation_strategy(mut self, strategy: TruncationStrategy) -> Self {
+        self.truncation_strategy = strategy;
+        self
+    }
+
+    /// Returns the truncation strategy
+    pub fn truncation_strategy(&self) -> TruncationStrategy {
+        self.truncation_strategy
+    }
+
+    /// Enables padding
+    pub fn with_padding(mut self, padding: Padding) -> Self {
+        self.padding = Some(padding);
+        self
+    }
+
+    /// Returns the padding settings, if padding is enabled
+    pub fn padding(&self) -> Option<&Padding> {
+        self.padding.as_ref()
+    }
+
+    /// Keeps the tokens cut off by truncation as extra windows instead of dropping them
+    ///
+    /// Each window starts `stride` tokens before the end of the previous one, so a word
+    /// near a window border is seen with context in at least one of them.
+    pub fn with_overflowing(mut self, stride: usize) -> Self {
+        self.overflowing_stride = Some(stride);
+        self
+    }
+
+    /// Returns the overlap between windows, if overflowing windows are enabled
+    pub fn overflowing_stride(&self) -> Option<usize> {
+        self.overflowing_stride
+    }
+}
+
+/// Computes where the windows over a sequence start
+///
+/// # Arguments
+/// * `length` - The number of tokens of the sequence
+/// * `window` - The number of tokens of one window
+/// * `stride` - How many tokens two neighbouring windows share
+///
+/// # Returns
+/// The start position of every window (the last window ends at the end of the sequence)
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::encode_options::window_starts;
+///
+/// // windows [0..4], [2..6], [4..8], [6..9]
+/// assert_eq!(window_starts(9, 4, 2), vec![0, 2, 4, 6]);
+/// ```
+pub fn window_starts(length: usize, window: usize, stride: usize) -> Vec<usize> {
+    // A stride as large as the window would never move forward
+    let step = window.saturating_sub(stride).max(1);
+
+    let mut starts = vec![0];
+    let mut start = 0;
+    while start + window < length {
+        start += step;
+        starts.push(start);
+    }
+    starts
+}
+
+/// Which sequence loses tokens when an encoded pair is longer than the maximum length
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TruncationStrategy {
+    /// Remove tokens from the longer sequence first, one at a time (the default)
+    #[default]
+    LongestFirst,
+    /// Only shorten the first sequence (the question stays complete, the context is cut)
+    OnlyFirst,
+    /// Only shorten the second sequence
+    OnlySecond,
+}
+
+impl TruncationStrategy {
+    /// Computes how many tokens of each sequence are kept
+    ///
+    /// If the sequence a strategy is allowed to shorten is too short to make room, the other
+    /// sequence is shortened too, so the result always fits in `budget`.
+    /// A single sequence (`second` is 0) is always truncated, whatever the strategy.
+    ///
+    /// # Arguments
+    /// * `first` - The number of tokens of the first sequence
+    /// * `second` - The number of tokens of the second sequence
+    /// * `budget` - The number of tokens both sequences may use together
+    ///
+    /// # Returns
+    /// The number of tokens kept from the first and from the second sequence
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::encode_options::TruncationStrategy;
+    ///
+    /// assert_eq!(TruncationStrategy::LongestFirst.lengths(10, 4, 8), (4, 4));
+    /// assert_eq!(TruncationStrategy::OnlyFirst.lengths(10, 4, 8), (4, 4));
+    /// assert_eq!(TruncationStrategy::OnlySecond.lengths(10, 4, 8), (8, 0));
+    /// ```
+    pub fn lengths(&self, first: usize, second: usize, budget: usize) -> (usize, usize) {
+        if first + second <= budget {
+            return (first, second);
+        }
+
+        match self {
+            TruncationStrategy::LongestFirst => {
+                // Shorten the longer sequence until both have the same length,
+                // then share what is left (the first sequence gets the odd token)
+                let shorter = first.min(second);
+                if budget <= 2 * shorter {
+                    let half = budget / 2;
+                    (budget - half, half)
+                } else if first > second {
+                    (budget - second, second)
+                } else {
+                    (first, budget - first)
+                }
+            }
+            TruncationStrategy::OnlyFirst => {
+                let kept_second = second.min(budget);
+                (budget - kept_second, kept_second)
+            }
+            TruncationStrategy::OnlySecond if second > 0 => {
+                let kept_first = first.min(budget);
+                (kept_first, budget - kept_first)
+            }
+            TruncationStrategy::OnlySecond => (budget, 0),
+        }
+    }
+}
+
+/// Which length encodings are padded to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaddingStrategy {
+    /// Pad every encoding to this number of tokens (longer encodings are left alone)
+    Fixed(usize),
+    /// Pad every encoding of a batch to the length of the longest one
+    BatchLongest,
+}
+
+/// Which side of an encoding the padding tokens go to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PaddingSide {
+    /// After the tokens (the default, used by BERT-style models)
+    #[default]
+    Right,
+    /// Before the tokens (used by many decoder-only models)
+    Left,
+}
+
+/// Padding settings
+///
+/// Padding tokens get the attention mask 0 (the model ignores them), the special-token
+/// mask 1 and the empty offsets (0, 0).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Padding {
+    /// Which length encodings are padded to
+    strategy: PaddingStrategy,
+    /// Which side the padding tokens go to
+    side: PaddingSide,
+    /// The padding token ("[PAD]" by default)
+    pad_token: String,
+    /// The type id of the padding tokens (0 by default)
+    pad_type_id: u32,
+}
+
+impl Padding {
+    /// Creates padding settings with the given strategy, padding on the right with "[PAD]"
+    pub fn new(strategy: PaddingStrategy) -> Self {
+        Padding {
+            strategy,
+            side: PaddingSide::Right,
+            pad_token: "[PAD]".to_string(),
+            pad_type_id: 0,
+        }
+    }
+
+    /// Sets which side the padding tokens go to
+    pub fn with_side(mut self, side: PaddingSide) -> Self {
+        self.side = side;
+        self
+    }
+
+    /// Sets the padding token, for example "<pad>" for RoBERTa
+    pub fn with_pad_token(mut self, pad_token: &str) -> Self {
+        self.pad_token = pad_token.to_string();
+        self
+    }
+
+    /// Sets the type id of the padding tokens
+    pub fn with_pad_type_id(mut self, pad_type_id: u32) -> Self {
+        self.pad_type_id = pad_type_id;
+        self
+    }
+
+    /// Returns the padding strategy
+    pub fn strategy(&self) -> PaddingStrategy {
+        self.strategy
+    }
+
+    /// Returns which side the padding tokens go to
+    pub fn side(&self) -> PaddingSide {
+        self.side
+    }
+
+    /// Returns the padding token
+    pub fn pad_token(&self) -> &str {
+        &self.pad_token
+    }
+
+    /// Returns the type id of the padding tokens
+    pub fn pad_type_id(&self) -> u32 {
+        self.pad_type_id
+    }
+
+    /// Pads a batch of encodings
+    ///
+    /// # Arguments
+    /// * `encodings` - The encodings to pad (all of them are padded to the same target length)
+    /// * `pad_id` - The vocabulary id of the padding token
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::encode_options::{Padding, PaddingSide, PaddingStrategy};
+    /// use tokenizer_rust::encoding::Encoding;
+    ///
+    /// let mut short = Encoding::new();
+    /// short.push(5, "Hi", (0, 2), false);
+    /// let mut long = Encoding::new();
+    /// long.push(6, "Hello", (0, 5), false);
+    /// long.push(7, "there", (6, 11), false);
+    ///
+    /// let mut batch = vec![short, long];
+    /// Padding::new(PaddingStrategy::BatchLongest)
+    ///     .with_side(PaddingSide::Left)
+    ///     .pad(&mut batch, 0);
+    /// assert_eq!(batch[0].tokens, vec!["[PAD]", "Hi"]);
+    /// assert_eq!(batch[0].attention_mask, vec![0, 1]);
+    /// assert_eq!(batch[1].attention_mask, vec![1, 1]);
+    /// ```
+    pub fn pad(&self, encodings: &mut [Encoding], pad_id: u32) {
+        let target = match self.strategy {
+            PaddingStrategy::Fixed(length) => length,
+            PaddingStrategy::BatchLongest => encodings.iter().map(Encoding::len).max().unwrap_or(0),
+        };
+
+        for encoding in encodings.iter_mut() {
+            encoding.pad(target, pad_id, &self.pad_token, self.pad_type_id, self.side);
+        }
+    }
+}
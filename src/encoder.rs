@@ -0,0 +1,379 @@
+// Encoder configuration: the settings that control how tokens are turned into ids
+
+use crate::encode_options::{EncodeOptions, Padding};
+use crate::subword::SubwordModel;
+
+// Kept here too so existing `encoder::TruncationStrategy` imports still work
+pub use crate::encode_options::TruncationStrategy;
+
+/// What encode() does with a token that is not in the vocabulary
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum UnknownPolicy {
+    /// Drop the token (the default, kept for backwards compatibility)
+    #[default]
+    Skip,
+    /// Replace the token with the given unknown token, for example "[UNK]"
+    UnknownToken(String),
+    /// Split the token into its UTF-8 bytes, each encoded as a "<0xE2>"-style piece
+    /// so decode() can rebuild the exact original token
+    ByteFallback,
+}
+
+/// Settings used by Tokenizer::encode() and Tokenizer::decode()
+#[derive(Debug, Clone, Default)]
+pub struct EncoderConfig {
+    /// What to do with tokens that are not in the vocabulary
+    unknown_policy: UnknownPolicy,
+    /// The template adding special tokens around encoded sequences (none by default)
+    template: Option<Template>,
+    /// Truncation and padding settings
+    options: EncodeOptions,
+    /// Optional subword model splitting the tokens that are not in the vocabulary
+    subwords: Option<SubwordModel>,
+}
+
+impl EncoderConfig {
+    /// Creates the default configuration (unknown tokens are skipped)
+    pub fn new() -> Self {
+        EncoderConfig::default()
+    }
+
+    /// Sets what encode() does with tokens that are not in the vocabulary
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::encoder::{EncoderConfig, UnknownPolicy};
+    ///
+    /// let config = EncoderConfig::new().with_unknown_policy(UnknownPolicy::UnknownToken("[UNK]".to_string()));
+    /// ```
+    pub fn with_unknown_policy(mut self, policy: UnknownPolicy) -> Self {
+        self.unknown_policy = policy;
+        self
+    }
+
+    /// Returns the unknown-token policy
+    pub fn unknown_policy(&self) -> &UnknownPolicy {
+        &self.unknown_policy
+    }
+
+    /// Sets the template used to add special tokens around encoded sequences
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::encoder::{EncoderConfig, Template};
+    ///
+    /// let config = EncoderConfig::new().with_template(Template::bert());
+    /// ```
+    pub fn with_template(mut self, template: Template) -> Self {
+        self.template = Some(template);
+        self
+    }
+
+    /// Returns the template, if one is set
+    pub fn template(&self) -> Option<&Template> {
+        self.template.as_ref()
+    }
+
+    /// Sets the subword model that splits tokens which are not in the vocabulary
+    ///
+    /// A token that is not in the vocabulary is split into pieces of the model, and only
+    /// follows the unknown-token policy if the model cannot split it. The pieces of the
+    /// model are added to the vocabulary, and decode() glues the pieces starting with "##"
+    /// back onto the piece before them.
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::encoder::EncoderConfig;
+    /// use tokenizer_rust::stats::FrequencyTable;
+    /// use tokenizer_rust::subword::{SubwordKind, SubwordModel};
+    ///
+    /// let mut frequencies = FrequencyTable::new();
+    /// frequencies.add_texts(&Tokenizer::new(String::new()), &["the lower low, the newest new"]);
+    /// let model = SubwordModel::train(SubwordKind::Bpe, &frequencies, 1, 30);
+    /// let tokenizer =
+    ///     Tokenizer::new(String::new()).with_encoder_config(EncoderConfig::new().with_subwords(model));
+    ///
+    /// let encoding = tokenizer.encode_batch(&["the newer"]).remove(0);
+    /// assert_eq!(encoding.tokens, vec!["the", "new", "##er"]);
+    /// assert_eq!(encoding.offsets, vec![(0, 3), (4, 7), (7, 9)]);
+    /// assert_eq!(tokenizer.decode(&encoding.ids), "the newer");
+    ///
+    /// // The model is saved with the tokenizer
+    /// let loaded = Tokenizer::from_json(&tokenizer.to_json().unwrap()).unwrap();
+    /// assert_eq!(loaded.encode_batch(&["the newer"])[0].ids, encoding.ids);
+    /// ```
+    pub fn with_subwords(mut self, model: SubwordModel) -> Self {
+        self.subwords = Some(model);
+        self
+    }
+
+    /// Returns the subword model, if one is set
+    pub fn subwords(&self) -> Option<&SubwordModel> {
+        self.subwords.as_ref()
+    }
+
+    /// Sets the maximum number of tokens of an encoding (template tokens included)
+    ///
+    /// Template tokens are never removed: if `max_length` is smaller than the template,
+    /// the encoding only contains the template tokens.
+    pub fn with_max_length(mut self, max_length: usize) -> Self {
+        self.options = self.options.with_max_length(max_length);
+        self
+    }
+
+    /// Returns the maximum number of tokens of an encoding, if there is one
+    pub fn max_length(&self) -> Option<usize> {
+        self.options.max_length()
+    }
+
+    /// Sets which sequence of a pair is shortened to respect the maximum length
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::encoder::{EncoderConfig, TruncationStrategy};
+    ///
+    /// let config = EncoderConfig::new()
+    ///     .with_max_length(512)
+    ///     .with_truncation_strategy(TruncationStrategy::OnlySecond);
+    /// ```
+    pub fn with_truncation_strategy(mut self, strategy: TruncationStrategy) -> Self {
+        self.options = self.options.with_truncation_strategy(strategy);
+        self
+    }
+
+    /// Returns the truncation strategy
+    pub fn truncation_strategy(&self) -> TruncationStrategy {
+        self.options.truncation_strategy()
+    }
+
+    /// Sets how encodings are padded (no padding by default)
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::encoder::EncoderConfig;
+    /// use tokenizer_rust::encode_options::{Padding, PaddingStrategy};
+    ///
+    /// let config = EncoderConfig::new().with_padding(Padding::new(PaddingStrategy::Fixed(128)));
+    /// ```
+    pub fn with_padding(mut self, padding: Padding) -> Self {
+        self.options = self.options.with_padding(padding);
+        self
+    }
+
+    /// Returns the padding settings, if padding is enabled
+    pub fn padding(&self) -> Option<&Padding> {
+        self.options.padding()
+    }
+
+    /// Keeps the tokens cut off by truncation as extra windows overlapping by `stride` tokens
+    ///
+    /// The windows are returned in the `overflowing` field of the encoding.
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::encoder::EncoderConfig;
+    /// use tokenizer_rust::vocab::Vocabulary;
+    ///
+    /// let tokens = Tokenizer::new("a b c d e".to_string()).tokenize();
+    /// let tokenizer = Tokenizer::new("a b c d e".to_string())
+    ///     .with_vocabulary(Vocabulary::from_tokens(&tokens))
+    ///     .with_encoder_config(EncoderConfig::new().with_max_length(3).with_overflowing(1));
+    /// let encoding = tokenizer.encode();
+    /// assert_eq!(encoding.tokens, vec!["a", "b", "c"]);
+    /// assert_eq!(encoding.overflowing[0].tokens, vec!["c", "d", "e"]);
+    /// ```
+    pub fn with_overflowing(mut self, stride: usize) -> Self {
+        self.options = self.options.with_overflowing(stride);
+        self
+    }
+
+    /// Returns the overlap between windows, if overflowing windows are enabled
+    pub fn overflowing_stride(&self) -> Option<usize> {
+        self.options.overflowing_stride()
+    }
+
+    /// Replaces all truncation and padding settings at once
+    pub fn with_options(mut self, options: EncodeOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Returns the truncation and padding settings
+    pub fn options(&self) -> &EncodeOptions {
+        &self.options
+    }
+
+    /// Returns how many tokens the template adds to a single sequence or to a pair
+    pub(crate) fn template_length(&self, pair: bool) -> usize {
+        self.template.as_ref().map_or(0, |template| {
+            let pieces = if pair {
+                template.pair()
+            } else {
+                template.single()
+            };
+            pieces
+                .iter()
+                .filter(|piece| matches!(piece, TemplatePiece::Special(..)))
+                .count()
+        })
+    }
+
+    /// Returns the tokens this configuration needs to find in the vocabulary
+    ///
+    /// That is the unknown token (or the 256 byte pieces when byte fallback is used),
+    /// the special tokens of the template, the padding token and the subword pieces.
+    pub(crate) fn reserved_tokens(&self) -> Vec<String> {
+        let mut tokens: Vec<String> = match &self.unknown_policy {
+            UnknownPolicy::Skip => Vec::new(),
+            UnknownPolicy::UnknownToken(token) => vec![token.clone()],
+            UnknownPolicy::ByteFallback => (0..=255).map(byte_piece).collect(),
+        };
+
+        if let Some(template) = &self.template {
+            tokens.extend(template.special_tokens().map(|token| token.to_string()));
+        }
+        if let Some(padding) = self.padding() {
+            tokens.push(padding.pad_token().to_string());
+        }
+        if let Some(model) = &self.subwords {
+            tokens.extend(model.pieces().iter().cloned());
+        }
+
+        tokens
+    }
+}
+
+/// One piece of a template
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplatePiece {
+    /// The first sequence (`$A`), with the type id given to its tokens
+    SequenceA(u32),
+    /// The second sequence (`$B`), with the type id given to its tokens
+    SequenceB(u32),
+    /// A special token like [CLS], with its type id
+    Special(String, u32),
+}
+
+/// Describes where special tokens go around one sequence or a pair of sequences
+///
+/// Templates are written as space-separated pieces: `$A` and `$B` stand for the two
+/// sequences, anything else is a special token. A piece can end with `:n` to give its
+/// tokens the type id `n` (0 by default). For example BERT uses
+/// `[CLS] $A [SEP]` for single sequences and `[CLS] $A [SEP] $B:1 [SEP]:1` for pairs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    /// The pieces used when encoding one sequence
+    single: Vec<TemplatePiece>,
+    /// The pieces used when encoding a pair of sequences
+    pair: Vec<TemplatePiece>,
+}
+
+impl Template {
+    /// Creates a template from its single-sequence and pair descriptions
+    ///
+    /// # Arguments
+    /// * `single` - The template for one sequence, like "[CLS] $A [SEP]"
+    /// * `pair` - The template for a pair, like "[CLS] $A [SEP] $B:1 [SEP]:1"
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::encoder::{Template, TemplatePiece};
+    ///
+    /// let template = Template::new("<s> $A </s>", "<s> $A </s> </s> $B </s>");
+    /// assert_eq!(template.single()[1], TemplatePiece::SequenceA(0));
+    /// ```
+    pub fn new(single: &str, pair: &str) -> Self {
+        Template {
+            single: parse_template(single),
+            pair: parse_template(pair),
+        }
+    }
+
+    /// The template used by BERT: `[CLS] $A [SEP]` and `[CLS] $A [SEP] $B:1 [SEP]:1`
+    pub fn bert() -> Self {
+        Template::new("[CLS] $A [SEP]", "[CLS] $A [SEP] $B:1 [SEP]:1")
+    }
+
+    /// The template used by RoBERTa: `<s> $A </s>` and `<s> $A </s> </s> $B </s>`
+    pub fn roberta() -> Self {
+        Template::new("<s> $A </s>", "<s> $A </s> </s> $B </s>")
+    }
+
+    /// Returns the pieces used when encoding one sequence
+    pub fn single(&self) -> &[TemplatePiece] {
+        &self.single
+    }
+
+    /// Returns the pieces used when encoding a pair of sequences
+    pub fn pair(&self) -> &[TemplatePiece] {
+        &self.pair
+    }
+
+    /// Iterates over the special tokens used by the template
+    pub fn special_tokens(&self) -> impl Iterator<Item = &str> {
+        self.single
+            .iter()
+            .chain(self.pair.iter())
+            .filter_map(|piece| match piece {
+                TemplatePiece::Special(token, _) => Some(token.as_str()),
+                _ => None,
+            })
+    }
+}
+
+/// Reads a template description like "[CLS] $A [SEP] $B:1 [SEP]:1"
+fn parse_template(description: &str) -> Vec<TemplatePiece> {
+    description
+        .split_whitespace()
+        .map(|piece| {
+            // An optional ":n" suffix gives the type id
+            let (name, type_id) = match piece.rsplit_once(':') {
+                Some((name, number)) if !name.is_empty() => match number.parse() {
+                    Ok(type_id) => (name, type_id),
+                    Err(_) => (piece, 0),
+                },
+                _ => (piece, 0),
+            };
+
+            match name {
+                "$A" => TemplatePiece::SequenceA(type_id),
+                "$B" => TemplatePiece::SequenceB(type_id),
+                token => TemplatePiece::Special(token.to_string(), type_id),
+            }
+        })
+        .collect()
+}
+
+/// Returns the byte-fallback piece for one byte
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::encoder::byte_piece;
+///
+/// assert_eq!(byte_piece(0xE2), "<0xE2>");
+/// ```
+pub fn byte_piece(byte: u8) -> String {
+    format!("<0x{:02X}>", byte)
+}
+
+/// Reads the byte out of a byte-fallback piece like "<0xE2>"
+///
+/// # Returns
+/// The byte, or None if the token is not a byte piece
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::encoder::parse_byte_piece;
+///
+/// assert_eq!(parse_byte_piece("<0xE2>"), Some(0xE2));
+/// assert_eq!(parse_byte_piece("hello"), None);
+/// ```
+pub fn parse_byte_piece(token: &str) -> Option<u8> {
+    let hex = token.strip_prefix("<0x")?.strip_suffix('>')?;
+    if hex.len() != 2 {
+        return None;
+    }
+    u8::from_str_radix(hex, 16).ok()
+}
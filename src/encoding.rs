@@ -0,0 +1,198 @@
+// The Encoding type: everything a model needs from one call to encode()
+
+use crate::encode_options::PaddingSide;
+
+/// The result of encoding a text
+///
+/// All vectors have the same length: position `i` in each of them describes the same token.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Encoding {
+    /// The vocabulary id of every token
+    pub ids: Vec<u32>,
+    /// The token strings (byte-fallback pieces appear as "<0xE2>" and unknown tokens as the unknown token)
+    pub tokens: Vec<String>,
+    /// The (start, end) byte offsets of every token in the original text
+    pub offsets: Vec<(usize, usize)>,
+    /// Which sequence every token belongs to (0 for the first text, 1 for the second one in a pair)
+    pub type_ids: Vec<u32>,
+    /// 1 for tokens the model should look at, 0 for padding
+    pub attention_mask: Vec<u32>,
+    /// 1 for special tokens like [CLS] or <s>, 0 for normal tokens
+    pub special_tokens_mask: Vec<u32>,
+    /// The other windows of a text that was longer than the maximum length
+    /// (only filled when overflowing windows are enabled in the encode options)
+    pub overflowing: Vec<Encoding>,
+}
+
+impl Encoding {
+    /// Creates an empty encoding
+    pub fn new() -> Self {
+        Encoding::default()
+    }
+
+    /// Creates an empty encoding with room for `capacity` tokens
+    pub fn with_capacity(capacity: usize) -> Self {
+        Encoding {
+            ids: Vec::with_capacity(capacity),
+            tokens: Vec::with_capacity(capacity),
+            offsets: Vec::with_capacity(capacity),
+            type_ids: Vec::with_capacity(capacity),
+            attention_mask: Vec::with_capacity(capacity),
+            special_tokens_mask: Vec::with_capacity(capacity),
+            overflowing: Vec::new(),
+        }
+    }
+
+    /// Returns the number of tokens in the encoding
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    /// Returns true if the encoding has no tokens
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Adds one token at the end of the encoding
+    ///
+    /// # Arguments
+    /// * `id` - The vocabulary id of the token
+    /// * `token` - The token string
+    /// * `offsets` - The (start, end) byte offsets of the token in the original text
+    /// * `special` - true if this is a special token
+    pub fn push(&mut self, id: u32, token: &str, offsets: (usize, usize), special: bool) {
+        self.ids.push(id);
+        self.tokens.push(token.to_string());
+        self.offsets.push(offsets);
+        self.type_ids.push(0);
+        self.attention_mask.push(1);
+        self.special_tokens_mask.push(u32::from(special));
+    }
+
+    /// Adds all tokens of another encoding at the end of this one
+    ///
+    /// # Arguments
+    /// * `other` - The encoding to append (takes ownership)
+    /// * `type_id` - The type id given to every appended token
+    pub fn append(&mut self, other: Encoding, type_id: u32) {
+        self.type_ids
+            .extend(std::iter::repeat_n(type_id, other.len()));
+        self.ids.extend(other.ids);
+        self.tokens.extend(other.tokens);
+        self.offsets.extend(other.offsets);
+        self.attention_mask.extend(other.attention_mask);
+        self.special_tokens_mask.extend(other.special_tokens_mask);
+    }
+
+    /// Keeps only the first `length` tokens (all vectors are shortened together)
+    pub fn truncate(&mut self, length: usize) {
+        self.ids.truncate(length);
+        self.tokens.truncate(length);
+        self.offsets.truncate(length);
+        self.type_ids.truncate(length);
+        self.attention_mask.truncate(length);
+        self.special_tokens_mask.truncate(length);
+    }
+
+    /// Copies the tokens from `start` to `end` (excluded) into a new encoding
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::encoding::Encoding;
+    ///
+    /// let mut encoding = Encoding::new();
+    /// encoding.push(1, "a", (0, 1), false);
+    /// encoding.push(2, "b", (2, 3), false);
+    /// assert_eq!(encoding.slice(1, 2).tokens, vec!["b"]);
+    /// ```
+    pub fn slice(&self, start: usize, end: usize) -> Encoding {
+        Encoding {
+            ids: self.ids[start..end].to_vec(),
+            tokens: self.tokens[start..end].to_vec(),
+            offsets: self.offsets[start..end].to_vec(),
+            type_ids: self.type_ids[start..end].to_vec(),
+            attention_mask: self.attention_mask[start..end].to_vec(),
+            special_tokens_mask: self.special_tokens_mask[start..end].to_vec(),
+            overflowing: Vec::new(),
+        }
+    }
+
+    /// Adds padding tokens until the encoding has `length` tokens
+    ///
+    /// Padding tokens get the attention mask 0, the special-token mask 1 and the offsets (0, 0).
+    /// Nothing happens if the encoding already has `length` tokens or more.
+    ///
+    /// # Arguments
+    /// * `length` - The length to pad to
+    /// * `pad_id` - The vocabulary id of the padding token
+    /// * `pad_token` - The padding token string
+    /// * `pad_type_id` - The type id given to the padding tokens
+    /// * `side` - Whether the padding goes before or after the tokens
+    pub fn pad(
+        &mut self,
+        length: usize,
+        pad_id: u32,
+        pad_token: &str,
+        pad_type_id: u32,
+        side: PaddingSide,
+    ) {
+        if self.len() >= length {
+            return;
+        }
+        let count = length - self.len();
+
+        // Right padding extends the vectors, left padding inserts at the front
+        let position = match side {
+            PaddingSide::Right => self.len(),
+            PaddingSide::Left => 0,
+        };
+        self.ids
+            .splice(position..position, std::iter::repeat_n(pad_id, count));
+        self.tokens.splice(
+            position..position,
+            std::iter::repeat_n(pad_token.to_string(), count),
+        );
+        self.offsets
+            .splice(position..position, std::iter::repeat_n((0, 0), count));
+        self.type_ids
+            .splice(position..position, std::iter::repeat_n(pad_type_id, count));
+        self.attention_mask
+            .splice(position..position, std::iter::repeat_n(0, count));
+        self.special_tokens_mask
+            .splice(position..position, std::iter::repeat_n(1, count));
+    }
+}
+
+/// The cached encoding of a prefix shared by many texts (see Tokenizer::encode_prefix())
+///
+/// Only the part of the prefix before its last safe cut point is kept encoded; the rest is
+/// kept as text and encoded together with every suffix.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EncodedPrefix {
+    /// The encoding of the prefix up to the cut point (without template)
+    pub(crate) encoding: Encoding,
+    /// true if the last token before the cut point was encoded byte by byte
+    pub(crate) ends_with_bytes: bool,
+    /// The byte offset of the cut point in the prefix
+    pub(crate) cut: usize,
+    /// The prefix text after the cut point
+    pub(crate) tail: String,
+}
+
+impl EncodedPrefix {
+    /// Returns the encoding of the cached part of the prefix
+    pub fn encoding(&self) -> &Encoding {
+        &self.encoding
+    }
+
+    /// Returns the length in bytes of the cached part of the prefix
+    pub fn cached_len(&self) -> usize {
+        self.cut
+    }
+
+    /// Returns the end of the prefix that is encoded again with every suffix
+    pub fn tail(&self) -> &str {
+        &self.tail
+    }
+}
@@ -0,0 +1,431 @@
+// External vocabularies: comparing a local vocabulary with the one of a published model
+// Before fine-tuning a model on new data, it is worth checking which words of the corpus
+// the model's vocabulary already knows. This module reads the vocabulary files models are
+// shipped with (Hugging Face vocab.json / tokenizer.json, BERT vocab.txt, tiktoken files),
+// keeps the entries that are whole words, and reports the overlap with a local vocabulary
+// together with the most frequent corpus tokens the model does not know: those are the
+// best candidates for Tokenizer::add_tokens().
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::json::{Json, JsonCursor};
+use crate::vocab::Vocabulary;
+
+/// The file formats external vocabularies come in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VocabFormat {
+    /// A Hugging Face vocab.json (`{"token": id}`) or tokenizer.json (BPE, WordPiece or Unigram)
+    HuggingFace,
+    /// A tiktoken file: one base64-encoded token and its rank per line
+    Tiktoken,
+    /// One token per line, like the vocab.txt of BERT
+    WordList,
+}
+
+impl VocabFormat {
+    /// Guesses the format of a vocabulary file from its content
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::external_vocab::VocabFormat;
+    ///
+    /// assert_eq!(VocabFormat::detect("{\"hello\": 0}"), VocabFormat::HuggingFace);
+    /// assert_eq!(VocabFormat::detect("aGVsbG8= 0\nIHdvcmxk 1\n"), VocabFormat::Tiktoken);
+    /// assert_eq!(VocabFormat::detect("[PAD]\nhello\n##ing\n"), VocabFormat::WordList);
+    /// ```
+    pub fn detect(content: &str) -> Self {
+        if content.trim_start().starts_with('{') {
+            return VocabFormat::HuggingFace;
+        }
+
+        // Every line of a tiktoken file is "<base64> <rank>"
+        let mut lines = content.lines().filter(|line| !line.is_empty()).peekable();
+        let is_tiktoken = lines.peek().is_some()
+            && lines.all(|line| {
+                line.split_once(' ').is_some_and(|(token, rank)| {
+                    decode_base64(token).is_some() && rank.parse::<u64>().is_ok()
+                })
+            });
+        if is_tiktoken {
+            VocabFormat::Tiktoken
+        } else {
+            VocabFormat::WordList
+        }
+    }
+}
+
+/// The whole-word entries of the vocabulary of an external model
+///
+/// Entries are compared with the tokens of this crate, which are whole words, so only
+/// entries that can stand for a whole word are kept, without their word markers:
+/// "Ġhello" (byte-level BPE), "▁hello" (SentencePiece) and " hello" (tiktoken) all
+/// become "hello". WordPiece continuations like "##ing" are dropped, and so are entries
+/// that are not valid UTF-8 on their own (parts of a multi-byte character).
+#[derive(Debug, Clone)]
+pub struct ExternalVocab {
+    /// The format the vocabulary was read from
+    format: VocabFormat,
+    /// Number of entries in the file
+    entries: usize,
+    /// The whole-word entries, without their word markers
+    words: HashSet<String>,
+}
+
+impl ExternalVocab {
+    /// Reads an external vocabulary from a string
+    ///
+    /// # Arguments
+    /// * `content` - The content of the vocabulary file
+    /// * `format` - The format of the file (see VocabFormat::detect())
+    ///
+    /// # Returns
+    /// The vocabulary, or None if the content is not valid for the format
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::external_vocab::{ExternalVocab, VocabFormat};
+    ///
+    /// let json = r#"{"hello": 0, "Ġworld": 1, "Ã©": 2, "Ã": 3}"#;
+    /// let vocab = ExternalVocab::parse(json, VocabFormat::HuggingFace).unwrap();
+    /// assert_eq!(vocab.entries(), 4);
+    /// assert!(vocab.contains("world"));
+    /// // Byte-level entries are decoded: "Ã©" is "é", a lone "Ã" is half a character
+    /// assert!(vocab.contains("é"));
+    /// assert_eq!(vocab.len(), 3);
+    ///
+    /// // Too deeply nested documents are rejected, like malformed ones
+    /// let nested = format!(r#"{{"model": {}}}"#, "[".repeat(100_000));
+    /// assert!(ExternalVocab::parse(&nested, VocabFormat::HuggingFace).is_none());
+    /// ```
+    pub fn parse(content: &str, format: VocabFormat) -> Option<Self> {
+        // STEP 1: Read the raw entries of the file
+        let entries: Vec<String> = match format {
+            VocabFormat::HuggingFace => {
+                let entries = hugging_face_entries(JsonCursor::new(content).document().ok()?)?;
+                // Byte-level BPE vocabularies write a space as "Ġ" and every byte as a character
+                if entries.iter().any(|entry| entry.starts_with('Ġ')) {
+                    entries
+                        .iter()
+                        .map(|entry| decode_byte_level(entry))
+                        .collect()
+                } else {
+                    entries
+                }
+            }
+            VocabFormat::Tiktoken => {
+                let mut entries = Vec::new();
+                for line in content.lines().filter(|line| !line.is_empty()) {
+                    let (token, _rank) = line.split_once(' ')?;
+                    // Tokens that are only part of a character are counted but not kept
+                    entries.push(String::from_utf8(decode_base64(token)?).unwrap_or_default());
+                }
+                entries
+            }
+            VocabFormat::WordList => content
+                .lines()
+                .map(|line| line.trim_end_matches('\r'))
+                .filter(|line| !line.is_empty())
+                .map(str::to_string)
+                .collect(),
+        };
+
+        // STEP 2: Keep the entries that can be whole words, without their word markers
+        let words = entries
+            .iter()
+            .filter_map(|entry| whole_word(entry, format))
+            .map(str::to_string)
+            .collect();
+
+        Some(ExternalVocab {
+            format,
+            entries: entries.len(),
+            words,
+        })
+    }
+
+    /// Reads an external vocabulary file, detecting its format from the content
+    ///
+    /// # Returns
+    /// The vocabulary, or an `InvalidData` error if the file cannot be parsed
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let content = fs::read_to_string(path)?;
+        ExternalVocab::parse(&content, VocabFormat::detect(&content)).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a Hugging Face, tiktoken or word list vocabulary",
+            )
+        })
+    }
+
+    /// Returns the format the vocabulary was read from
+    pub fn format(&self) -> VocabFormat {
+        self.format
+    }
+
+    /// Returns the number of entries of the file (whole words or not)
+    pub fn entries(&self) -> usize {
+        self.entries
+    }
+
+    /// Returns the number of distinct whole words
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    /// Returns true if the vocabulary has no whole words
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// Returns true if the word is one entry of the vocabulary
+    pub fn contains(&self, word: &str) -> bool {
+        self.words.contains(word)
+    }
+
+    /// Returns the whole words, in no particular order
+    pub fn words(&self) -> impl Iterator<Item = &str> {
+        self.words.iter().map(String::as_str)
+    }
+}
+
+/// How a local vocabulary and a corpus relate to an external vocabulary
+#[derive(Debug, Clone, PartialEq)]
+pub struct VocabOverlap {
+    /// Number of tokens of the local vocabulary
+    pub local: usize,
+    /// Number of whole words of the external vocabulary
+    pub external: usize,
+    /// Number of local tokens that are also external words
+    pub shared: usize,
+    /// Number of token occurrences in the corpus
+    pub corpus_tokens: usize,
+    /// Number of those occurrences the external vocabulary knows
+    pub covered_tokens: usize,
+    /// The most frequent corpus tokens the external vocabulary doesn't know, with
+    /// their number of occurrences (most frequent first)
+    pub missing: Vec<(String, usize)>,
+}
+
+impl VocabOverlap {
+    /// Returns the number of local tokens the external vocabulary doesn't have
+    pub fn local_only(&self) -> usize {
+        self.local - self.shared
+    }
+
+    /// Returns the share of corpus token occurrences the external vocabulary knows (0 to 1)
+    pub fn coverage(&self) -> f64 {
+        if self.corpus_tokens == 0 {
+            0.0
+        } else {
+            self.covered_tokens as f64 / self.corpus_tokens as f64
+        }
+    }
+}
+
+/// Compares a local vocabulary and a corpus with an external vocabulary
+///
+/// # Arguments
+/// * `local` - The vocabulary built for the corpus
+/// * `external` - The vocabulary of the model
+/// * `corpus` - The tokens of the corpus, used to rank the missing tokens by frequency
+/// * `max_missing` - How many missing tokens to report
+///
+/// # Returns
+/// The overlap of both vocabularies and the most frequent missing tokens
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::Tokenizer;
+/// use tokenizer_rust::external_vocab::{overlap, ExternalVocab, VocabFormat};
+/// use tokenizer_rust::vocab::Vocabulary;
+///
+/// let corpus = Tokenizer::new("The tokamak heats the plasma. The tokamak is big.".to_string()).tokenize();
+/// let local = Vocabulary::from_tokens(&corpus);
+/// let model = ExternalVocab::parse("[UNK]\nThe\nthe\nheats\nis\nbig\n.\n##ma\n", VocabFormat::WordList).unwrap();
+///
+/// let report = overlap(&local, &model, &corpus, 10);
+/// assert_eq!((report.local, report.shared, report.local_only()), (8, 6, 2));
+/// assert_eq!(report.missing, vec![("tokamak".to_string(), 2), ("plasma".to_string(), 1)]);
+/// assert_eq!(report.coverage(), 8.0 / 11.0);
+/// println!("{}", report);
+/// ```
+pub fn overlap(
+    local: &Vocabulary,
+    external: &ExternalVocab,
+    corpus: &[String],
+    max_missing: usize,
+) -> VocabOverlap {
+    // STEP 1: Compare the two vocabularies
+    let shared = local
+        .iter()
+        .filter(|(_, token)| external.contains(token))
+        .count();
+
+    // STEP 2: Count the corpus tokens the external vocabulary doesn't know
+    let mut missing: HashMap<&str, usize> = HashMap::new();
+    let mut covered_tokens = 0;
+    for token in corpus.iter().filter(|token| !token.is_empty()) {
+        if external.contains(token) {
+            covered_tokens += 1;
+        } else {
+            *missing.entry(token).or_insert(0) += 1;
+        }
+    }
+
+    // STEP 3: Most frequent first, ties in alphabetical order so the report is stable
+    let mut missing: Vec<(String, usize)> = missing
+        .into_iter()
+        .map(|(token, count)| (token.to_string(), count))
+        .collect();
+    missing.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    missing.truncate(max_missing);
+
+    VocabOverlap {
+        local: local.len(),
+        external: external.len(),
+        shared,
+        corpus_tokens: corpus.iter().filter(|token| !token.is_empty()).count(),
+        covered_tokens,
+        missing,
+    }
+}
+
+impl fmt::Display for VocabOverlap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "local vocabulary:    {:>10}", self.local)?;
+        writeln!(f, "external vocabulary: {:>10}", self.external)?;
+        writeln!(f, "shared:              {:>10}", self.shared)?;
+        writeln!(f, "local only:          {:>10}", self.local_only())?;
+        writeln!(f, "corpus coverage:     {:>9.1}%", self.coverage() * 100.0)?;
+        if !self.missing.is_empty() {
+            writeln!(f, "most frequent missing tokens:")?;
+            for (token, count) in &self.missing {
+                writeln!(f, "  {:>8}  {}", count, token)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the word an entry stands for, or None if it cannot be a whole word
+fn whole_word(entry: &str, format: VocabFormat) -> Option<&str> {
+    // WordPiece continuations only ever follow another piece
+    if format != VocabFormat::Tiktoken && entry.starts_with("##") && entry.len() > 2 {
+        return None;
+    }
+    // SentencePiece and byte-level BPE mark the start of a word with "▁" or a space
+    let word = entry
+        .strip_prefix('▁')
+        .or_else(|| entry.strip_prefix(' '))
+        .unwrap_or(entry);
+    (!word.is_empty() && !word.contains(char::is_whitespace)).then_some(word)
+}
+
+/// Returns the raw entries of a Hugging Face vocab.json or tokenizer.json
+fn hugging_face_entries(document: Json) -> Option<Vec<String>> {
+    let Json::Object(mut fields) = document else {
+        return None;
+    };
+
+    // vocab.json: the whole document is {"token": id}
+    let Some(Json::Object(mut model)) = take_field(&mut fields, "model") else {
+        return Some(fields.into_iter().map(|(token, _)| token).collect());
+    };
+
+    // tokenizer.json: BPE and WordPiece have {"token": id}, Unigram has [["token", score]]
+    let mut entries: Vec<String> = match take_field(&mut model, "vocab")? {
+        Json::Object(vocab) => vocab.into_iter().map(|(token, _)| token).collect(),
+        Json::Array(vocab) => vocab
+            .into_iter()
+            .filter_map(|entry| match entry {
+                Json::Array(pair) => match pair.into_iter().next() {
+                    Some(Json::String(token)) => Some(token),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect(),
+        _ => return None,
+    };
+
+    // Added tokens ([CLS], <|endoftext|>, ...) are part of the vocabulary too
+    if let Some(Json::Array(added)) = take_field(&mut fields, "added_tokens") {
+        for token in added {
+            if let Json::Object(mut token) = token {
+                if let Some(Json::String(content)) = take_field(&mut token, "content") {
+                    entries.push(content);
+                }
+            }
+        }
+    }
+
+    Some(entries)
+}
+
+/// Turns a byte-level BPE entry back into text
+///
+/// Entries with characters outside the byte alphabet (added tokens) are kept as they are,
+/// entries that are not valid UTF-8 once decoded become empty.
+fn decode_byte_level(entry: &str) -> String {
+    let bytes: Option<Vec<u8>> = entry.chars().map(byte_of_character).collect();
+    match bytes {
+        Some(bytes) => String::from_utf8(bytes).unwrap_or_default(),
+        None => entry.to_string(),
+    }
+}
+
+/// Returns the byte a character stands for in the GPT-2 byte-level alphabet
+///
+/// Printable bytes stand for themselves, the others (control characters, space, ...)
+/// are numbered in byte order from U+0100.
+fn byte_of_character(character: char) -> Option<u8> {
+    let is_printable = |byte: u32| matches!(byte, 33..=126 | 161..=172 | 174..=255);
+
+    let code = character as u32;
+    if is_printable(code) {
+        return Some(code as u8);
+    }
+    let index = code.checked_sub(256)? as usize;
+    (0..=255u8)
+        .filter(|&byte| !is_printable(byte as u32))
+        .nth(index)
+}
+
+/// Decodes standard base64 (with or without "=" padding)
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let text = text.trim_end_matches('=');
+    let mut bytes = Vec::with_capacity(text.len() * 3 / 4);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+
+    for character in text.bytes() {
+        let value = match character {
+            b'A'..=b'Z' => character - b'A',
+            b'a'..=b'z' => character - b'a' + 26,
+            b'0'..=b'9' => character - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None,
+        };
+        buffer = (buffer << 6) | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+
+    // A single leftover character cannot encode a byte
+    (bits < 6 && !text.is_empty()).then_some(bytes)
+}
+
+/// Removes a field from a JSON object and returns its value
+fn take_field(fields: &mut Vec<(String, Json)>, name: &str) -> Option<Json> {
+    let index = fields.iter().position(|(key, _)| key == name)?;
+    Some(fields.swap_remove(index).1)
+}
@@ -0,0 +1,306 @@
+// C bindings (only compiled with the `ffi` feature)
+// C, C++ and every language with a C FFI (Go, Swift, C#, Java through JNA, ...) can embed the
+// tokenizer through these functions. The header, include/tokenizer_rust.h, is generated from
+// this file by cbindgen when the crate is built with the feature, and the library is the
+// cdylib (libtokenizer_rust.so, .dylib or .dll):
+//     cargo build --release --features ffi
+//     cc examples/ffi.c -Iinclude -Ltarget/release -ltokenizer_rust
+// The rules of the interface, so that it stays stable:
+// - A tokenizer is an opaque TokenizerHandle, created by tokenizer_new() or loaded with
+//   tokenizer_from_file(), used from any thread, and freed by tokenizer_free().
+// - Texts are UTF-8 bytes with a length (they don't need a NUL at the end), and byte offsets
+//   point into them.
+// - Functions that can fail return NULL, or -1 instead of 0 (without writing their result),
+//   and tokenizer_last_error() tells why.
+// - Every result owns its memory and has its own free function, which also accepts a zeroed
+//   result.
+
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::path::Path;
+use std::ptr;
+
+use crate::Tokenizer;
+
+thread_local! {
+    /// The message of the last failure on this thread
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Records the message of a failure for tokenizer_last_error()
+fn set_error(message: String) {
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|error| *error.borrow_mut() = Some(message));
+}
+
+/// A tokenizer, opaque to C
+pub struct TokenizerHandle {
+    /// The Rust tokenizer
+    tokenizer: Tokenizer,
+}
+
+/// The tokens of a text: their UTF-8 strings and their byte offsets in the text
+#[repr(C)]
+pub struct TokenizerTokens {
+    /// The text of every token, as NUL-terminated UTF-8 (a token that contains a NUL byte is
+    /// cut before it)
+    pub tokens: *mut *mut c_char,
+    /// The byte offset where every token starts in the text
+    pub starts: *mut usize,
+    /// The byte offset where every token ends in the text
+    pub ends: *mut usize,
+    /// The number of tokens
+    pub len: usize,
+}
+
+/// The vocabulary ids of a text
+#[repr(C)]
+pub struct TokenizerIds {
+    /// The ids
+    pub ids: *mut u32,
+    /// The number of ids
+    pub len: usize,
+}
+
+/// Creates a tokenizer with the default rules and no vocabulary
+///
+/// # Returns
+/// A tokenizer to free with tokenizer_free()
+#[no_mangle]
+pub extern "C" fn tokenizer_new() -> *mut TokenizerHandle {
+    Box::into_raw(Box::new(TokenizerHandle {
+        tokenizer: Tokenizer::new(String::new()),
+    }))
+}
+
+/// Loads a tokenizer written by Tokenizer::save() (or `tokenizer build-vocab`), with its special
+/// tokens, vocabulary and template
+///
+/// # Returns
+/// A tokenizer to free with tokenizer_free(), or NULL if the file can't be read or is not a
+/// tokenizer (see tokenizer_last_error())
+///
+/// # Safety
+/// `path` must be NULL or a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn tokenizer_from_file(path: *const c_char) -> *mut TokenizerHandle {
+    if path.is_null() {
+        set_error("the path is NULL".to_string());
+        return ptr::null_mut();
+    }
+    // SAFETY: the caller passes a NUL-terminated string
+    let path = unsafe { CStr::from_ptr(path) }.to_string_lossy();
+    match Tokenizer::from_file(Path::new(path.as_ref())) {
+        Ok(tokenizer) => Box::into_raw(Box::new(TokenizerHandle { tokenizer })),
+        Err(error) => {
+            set_error(format!("{}: {}", path, error));
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Frees a tokenizer (NULL is ignored)
+///
+/// # Safety
+/// `tokenizer` must be NULL or a tokenizer from tokenizer_new() or tokenizer_from_file()
+/// that was not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn tokenizer_free(tokenizer: *mut TokenizerHandle) {
+    if !tokenizer.is_null() {
+        // SAFETY: the pointer comes from Box::into_raw() and is freed only once
+        drop(unsafe { Box::from_raw(tokenizer) });
+    }
+}
+
+/// Tokenizes a text
+///
+/// # Arguments
+/// * `tokenizer` - The tokenizer
+/// * `text` - The UTF-8 bytes of the text
+/// * `length` - The number of bytes of the text
+/// * `tokens` - Where the tokens are written, to free with tokenizer_tokens_free()
+///
+/// # Returns
+/// 0, or -1 if an argument is NULL or the text is not UTF-8 (see tokenizer_last_error())
+///
+/// # Safety
+/// `tokenizer` must be a live tokenizer, `text` must point to `length` readable bytes (or be
+/// NULL with a length of 0), and `tokens` must point to a writable TokenizerTokens.
+#[no_mangle]
+pub unsafe extern "C" fn tokenizer_tokenize(
+    tokenizer: *const TokenizerHandle,
+    text: *const c_char,
+    length: usize,
+    tokens: *mut TokenizerTokens,
+) -> i32 {
+    // SAFETY: the caller passes a live tokenizer, `length` readable bytes and a result
+    let Some((tokenizer, text, result)) = (unsafe { arguments(tokenizer, text, length, tokens) })
+    else {
+        return -1;
+    };
+
+    let tokens = tokenizer.tokenize_text(text);
+    let len = tokens.len();
+    let (mut texts, mut starts, mut ends) = (
+        Vec::with_capacity(len),
+        Vec::with_capacity(len),
+        Vec::with_capacity(len),
+    );
+    for token in tokens {
+        let mut bytes = token.text.into_bytes();
+        if let Some(nul) = bytes.iter().position(|&byte| byte == 0) {
+            bytes.truncate(nul);
+        }
+        texts.push(CString::new(bytes).unwrap_or_default().into_raw());
+        starts.push(token.start);
+        ends.push(token.end);
+    }
+    *result = TokenizerTokens {
+        tokens: into_raw(texts),
+        starts: into_raw(starts),
+        ends: into_raw(ends),
+        len,
+    };
+    0
+}
+
+/// Frees the tokens returned by tokenizer_tokenize()
+///
+/// # Safety
+/// `tokens` must come from tokenizer_tokenize() and not be freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn tokenizer_tokens_free(tokens: TokenizerTokens) {
+    // SAFETY: the arrays come from into_raw() with `len` items, and every string from
+    // CString::into_raw()
+    unsafe {
+        if let Some(texts) = from_raw(tokens.tokens, tokens.len) {
+            for text in texts.iter() {
+                drop(CString::from_raw(*text));
+            }
+        }
+        from_raw(tokens.starts, tokens.len);
+        from_raw(tokens.ends, tokens.len);
+    }
+}
+
+/// Encodes a text into the ids of the tokenizer's vocabulary, with the special tokens of its
+/// template
+///
+/// # Arguments
+/// * `tokenizer` - The tokenizer
+/// * `text` - The UTF-8 bytes of the text
+/// * `length` - The number of bytes of the text
+/// * `ids` - Where the ids are written, to free with tokenizer_ids_free()
+///
+/// # Returns
+/// 0, or -1 if an argument is NULL or the text is not UTF-8 (see tokenizer_last_error())
+///
+/// # Safety
+/// `tokenizer` must be a live tokenizer, `text` must point to `length` readable bytes (or be
+/// NULL with a length of 0), and `ids` must point to a writable TokenizerIds.
+#[no_mangle]
+pub unsafe extern "C" fn tokenizer_encode(
+    tokenizer: *const TokenizerHandle,
+    text: *const c_char,
+    length: usize,
+    ids: *mut TokenizerIds,
+) -> i32 {
+    // SAFETY: the caller passes a live tokenizer, `length` readable bytes and a result
+    let Some((tokenizer, text, result)) = (unsafe { arguments(tokenizer, text, length, ids) })
+    else {
+        return -1;
+    };
+
+    let ids = tokenizer
+        .encode_batch(&[text])
+        .pop()
+        .map(|encoding| encoding.ids)
+        .unwrap_or_default();
+    *result = TokenizerIds {
+        len: ids.len(),
+        ids: into_raw(ids),
+    };
+    0
+}
+
+/// Frees the ids returned by tokenizer_encode()
+///
+/// # Safety
+/// `ids` must come from tokenizer_encode() and not be freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn tokenizer_ids_free(ids: TokenizerIds) {
+    // SAFETY: the array comes from into_raw() with `len` items
+    unsafe {
+        from_raw(ids.ids, ids.len);
+    }
+}
+
+/// Returns the message of the last failure on this thread, or NULL if nothing failed
+///
+/// The message stays valid until the next failure on the same thread.
+#[no_mangle]
+pub extern "C" fn tokenizer_last_error() -> *const c_char {
+    LAST_ERROR.with(|error| {
+        error
+            .borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+}
+
+/// Checks the tokenizer, text and result arguments of a call
+///
+/// # Returns
+/// The tokenizer, the text and the result, or None after recording the failure
+///
+/// # Safety
+/// As for tokenizer_tokenize().
+unsafe fn arguments<'a, T>(
+    tokenizer: *const TokenizerHandle,
+    text: *const c_char,
+    length: usize,
+    result: *mut T,
+) -> Option<(&'a Tokenizer, &'a str, &'a mut T)> {
+    if tokenizer.is_null() || result.is_null() {
+        set_error("the tokenizer or the result is NULL".to_string());
+        return None;
+    }
+    let bytes = if length == 0 {
+        &[][..]
+    } else if text.is_null() {
+        set_error("the text is NULL".to_string());
+        return None;
+    } else {
+        // SAFETY: the caller passes `length` readable bytes
+        unsafe { std::slice::from_raw_parts(text.cast::<u8>(), length) }
+    };
+    let text = match std::str::from_utf8(bytes) {
+        Ok(text) => text,
+        Err(error) => {
+            set_error(format!("the text is not UTF-8: {}", error));
+            return None;
+        }
+    };
+    // SAFETY: the caller passes a live tokenizer and a writable result
+    Some(unsafe { (&(*tokenizer).tokenizer, text, &mut *result) })
+}
+
+/// Hands the items of a Vec to C (NULL when there are none)
+fn into_raw<T>(items: Vec<T>) -> *mut T {
+    if items.is_empty() {
+        return ptr::null_mut();
+    }
+    Box::into_raw(items.into_boxed_slice()).cast::<T>()
+}
+
+/// Takes back the items handed to C by into_raw()
+///
+/// # Safety
+/// `items` must be NULL or come from into_raw() with `len` items.
+unsafe fn from_raw<T>(items: *mut T, len: usize) -> Option<Box<[T]>> {
+    if items.is_null() {
+        return None;
+    }
+    // SAFETY: into_raw() leaked a boxed slice of `len` items at this address
+    Some(unsafe { Box::from_raw(ptr::slice_from_raw_parts_mut(items, len)) })
+}
@@ -0,0 +1,404 @@
+// Sentence filtering by token criteria
+// Text scraped from the web is full of sentences that should not go into a training set:
+// menu items ("Home | About"), tables of numbers, one-word fragments, walls of symbols,
+// text in other languages. The usual cleaning step keeps a sentence only if its tokens
+// pass a few simple checks: a token count range, a minimum ratio of alphabetic tokens,
+// the writing system of its letters, its language, and patterns that tokens must or must
+// not match.
+
+use std::fmt;
+
+use crate::language::{detect_language, Language};
+use crate::manifest::ManifestStep;
+use crate::metrics::Script;
+use crate::sentences::SentenceSplitter;
+use crate::token::{Token, TokenKind};
+use crate::Tokenizer;
+
+/// Why a sentence was dropped by a SentenceFilter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Rejection {
+    /// Fewer tokens than the minimum
+    TooShort,
+    /// More tokens than the maximum
+    TooLong,
+    /// Not enough alphabetic tokens
+    NotAlphabetic,
+    /// The letters are not in one of the accepted scripts
+    Script,
+    /// The sentence is not in one of the accepted languages
+    Language,
+    /// No token matches the patterns a sentence must contain
+    MissingPattern,
+    /// A token matches a pattern that drops the sentence
+    DroppedPattern,
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let reason = match self {
+            Rejection::TooShort => "too short",
+            Rejection::TooLong => "too long",
+            Rejection::NotAlphabetic => "not alphabetic",
+            Rejection::Script => "script",
+            Rejection::Language => "language",
+            Rejection::MissingPattern => "missing pattern",
+            Rejection::DroppedPattern => "dropped pattern",
+        };
+        write!(f, "{}", reason)
+    }
+}
+
+/// Keeps or drops sentences depending on their tokens
+///
+/// Every check is off by default, so `SentenceFilter::new()` keeps everything.
+#[derive(Debug, Clone)]
+pub struct SentenceFilter {
+    /// Minimum number of tokens (punctuation included)
+    min_tokens: usize,
+    /// Maximum number of tokens (punctuation included)
+    max_tokens: usize,
+    /// Minimum share of alphabetic tokens among the non-punctuation tokens
+    min_alphabetic_ratio: f64,
+    /// Accepted scripts (empty means any)
+    scripts: Vec<Script>,
+    /// Accepted languages (empty means any)
+    languages: Vec<Language>,
+    /// A sentence is kept only if one of its tokens matches each of these patterns
+    #[cfg(feature = "regex")]
+    required: Vec<regex::Regex>,
+    /// A sentence is dropped if one of its tokens matches one of these patterns
+    #[cfg(feature = "regex")]
+    forbidden: Vec<regex::Regex>,
+}
+
+impl Default for SentenceFilter {
+    fn default() -> Self {
+        SentenceFilter {
+            min_tokens: 0,
+            max_tokens: usize::MAX,
+            min_alphabetic_ratio: 0.0,
+            scripts: Vec::new(),
+            languages: Vec::new(),
+            #[cfg(feature = "regex")]
+            required: Vec::new(),
+            #[cfg(feature = "regex")]
+            forbidden: Vec::new(),
+        }
+    }
+}
+
+impl SentenceFilter {
+    /// Creates a filter that keeps every sentence
+    pub fn new() -> Self {
+        SentenceFilter::default()
+    }
+
+    /// Keeps only sentences with `min` to `max` tokens (both included)
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::filter::{Rejection, SentenceFilter};
+    ///
+    /// let tokenizer = Tokenizer::new(String::new());
+    /// let filter = SentenceFilter::new().with_token_range(3, 6);
+    /// assert_eq!(filter.check(&tokenizer.tokenize_text("Home")), Err(Rejection::TooShort));
+    /// assert_eq!(filter.check(&tokenizer.tokenize_text("It works fine.")), Ok(()));
+    /// ```
+    pub fn with_token_range(mut self, min: usize, max: usize) -> Self {
+        self.min_tokens = min;
+        self.max_tokens = max;
+        self
+    }
+
+    /// Keeps only sentences where at least `ratio` (0.0 to 1.0) of the tokens other than
+    /// punctuation are made of letters only
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::filter::{Rejection, SentenceFilter};
+    ///
+    /// let tokenizer = Tokenizer::new(String::new());
+    /// let filter = SentenceFilter::new().with_min_alphabetic_ratio(0.8);
+    /// // Punctuation does not count: 3 alphabetic tokens out of 3
+    /// assert_eq!(filter.check(&tokenizer.tokenize_text("Well, it works!")), Ok(()));
+    /// // 1 alphabetic token out of 4
+    /// assert_eq!(
+    ///     filter.check(&tokenizer.tokenize_text("Total 12 34 56")),
+    ///     Err(Rejection::NotAlphabetic)
+    /// );
+    /// ```
+    pub fn with_min_alphabetic_ratio(mut self, ratio: f64) -> Self {
+        self.min_alphabetic_ratio = ratio.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Keeps only sentences whose letters are mostly in one of the given scripts
+    ///
+    /// The script tells languages apart only roughly (English and French are both Latin),
+    /// but it catches most of the text in the wrong language in a scraped corpus.
+    /// Sentences without letters have the script `Common`.
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::filter::{Rejection, SentenceFilter};
+    /// use tokenizer_rust::metrics::Script;
+    ///
+    /// let tokenizer = Tokenizer::new(String::new());
+    /// let filter = SentenceFilter::new().with_scripts([Script::Latin]);
+    /// assert_eq!(filter.check(&tokenizer.tokenize_text("Bonjour à tous.")), Ok(()));
+    /// assert_eq!(filter.check(&tokenizer.tokenize_text("Привет всем.")), Err(Rejection::Script));
+    /// ```
+    pub fn with_scripts<I: IntoIterator<Item = Script>>(mut self, scripts: I) -> Self {
+        self.scripts.extend(scripts);
+        self
+    }
+
+    /// Keeps only sentences detect_language() finds to be in one of the given languages
+    ///
+    /// Unlike the script, this tells apart languages written alike (English and French).
+    /// Sentences without letters have no language and are dropped.
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::filter::{Rejection, SentenceFilter};
+    /// use tokenizer_rust::language::Language;
+    ///
+    /// let tokenizer = Tokenizer::new(String::new());
+    /// let filter = SentenceFilter::new().with_languages([Language::English]);
+    /// let english = tokenizer.tokenize_text("The river floods the valley every spring.");
+    /// let french = tokenizer.tokenize_text("La rivière inonde la vallée chaque printemps.");
+    /// assert_eq!(filter.check(&english), Ok(()));
+    /// assert_eq!(filter.check(&french), Err(Rejection::Language));
+    /// ```
+    pub fn with_languages<I: IntoIterator<Item = Language>>(mut self, languages: I) -> Self {
+        self.languages.extend(languages);
+        self
+    }
+
+    /// Keeps only sentences where at least one token matches the pattern
+    ///
+    /// Patterns are matched against every token text on its own: anchor them with `^` and
+    /// `$` to match whole tokens.
+    ///
+    /// # Example
+    /// ```
+    /// use regex::Regex;
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::filter::{Rejection, SentenceFilter};
+    ///
+    /// let tokenizer = Tokenizer::new(String::new());
+    /// let filter = SentenceFilter::new().with_required_pattern(Regex::new("^[A-Z]").unwrap());
+    /// assert_eq!(filter.check(&tokenizer.tokenize_text("Paris is big.")), Ok(()));
+    /// assert_eq!(
+    ///     filter.check(&tokenizer.tokenize_text("click here")),
+    ///     Err(Rejection::MissingPattern)
+    /// );
+    /// ```
+    #[cfg(feature = "regex")]
+    pub fn with_required_pattern(mut self, pattern: regex::Regex) -> Self {
+        self.required.push(pattern);
+        self
+    }
+
+    /// Drops sentences where a token matches the pattern
+    ///
+    /// # Example
+    /// ```
+    /// use regex::Regex;
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::filter::{Rejection, SentenceFilter};
+    ///
+    /// let tokenizer = Tokenizer::new(String::new());
+    /// let filter = SentenceFilter::new()
+    ///     .with_forbidden_pattern(Regex::new("(?i)^(cookies?|javascript)$").unwrap());
+    /// assert_eq!(
+    ///     filter.check(&tokenizer.tokenize_text("Please enable JavaScript.")),
+    ///     Err(Rejection::DroppedPattern)
+    /// );
+    /// ```
+    #[cfg(feature = "regex")]
+    pub fn with_forbidden_pattern(mut self, pattern: regex::Regex) -> Self {
+        self.forbidden.push(pattern);
+        self
+    }
+
+    /// Checks the tokens of one sentence
+    ///
+    /// # Returns
+    /// Ok(()) if the sentence is kept, or the first check it fails, in this order: token
+    /// count, alphabetic ratio, script, language, patterns
+    pub fn check(&self, tokens: &[Token]) -> Result<(), Rejection> {
+        // STEP 1: Token count
+        if tokens.len() < self.min_tokens {
+            return Err(Rejection::TooShort);
+        }
+        if tokens.len() > self.max_tokens {
+            return Err(Rejection::TooLong);
+        }
+
+        // STEP 2: Alphabetic ratio (a sentence of punctuation only has a ratio of 0)
+        if self.min_alphabetic_ratio > 0.0 {
+            let content: Vec<&Token> = tokens
+                .iter()
+                .filter(|token| {
+                    !matches!(
+                        token.kind,
+                        TokenKind::Punctuation | TokenKind::LineBreak | TokenKind::ParagraphBreak
+                    )
+                })
+                .collect();
+            let alphabetic = content
+                .iter()
+                .filter(|token| token.text.chars().all(char::is_alphabetic))
+                .count();
+            let ratio = if content.is_empty() {
+                0.0
+            } else {
+                alphabetic as f64 / content.len() as f64
+            };
+            if ratio < self.min_alphabetic_ratio {
+                return Err(Rejection::NotAlphabetic);
+            }
+        }
+
+        // STEP 3: Script of the letters of the whole sentence
+        if !self.scripts.is_empty() {
+            let letters: String = tokens.iter().map(|token| token.text.as_str()).collect();
+            if !self.scripts.contains(&Script::of_word(&letters)) {
+                return Err(Rejection::Script);
+            }
+        }
+
+        // STEP 4: Language of the whole sentence
+        if !self.languages.is_empty() {
+            let words: Vec<&str> = tokens.iter().map(|token| token.text.as_str()).collect();
+            let language = detect_language(&words.join(" "));
+            if !language.is_some_and(|language| self.languages.contains(&language)) {
+                return Err(Rejection::Language);
+            }
+        }
+
+        // STEP 5: Patterns over the tokens
+        #[cfg(feature = "regex")]
+        {
+            let matches =
+                |pattern: &regex::Regex| tokens.iter().any(|token| pattern.is_match(&token.text));
+            if !self.required.iter().all(matches) {
+                return Err(Rejection::MissingPattern);
+            }
+            if self.forbidden.iter().any(matches) {
+                return Err(Rejection::DroppedPattern);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Describes the checks of the filter as a manifest step
+    ///
+    /// Only the checks that are on are written, and the patterns keep the order they were
+    /// added in, so changing any check or pattern changes the hash of the step.
+    pub fn describe(&self) -> ManifestStep {
+        let mut step = ManifestStep::new("sentence_filter");
+        step.push("min_tokens", self.min_tokens);
+        if self.max_tokens != usize::MAX {
+            step.push("max_tokens", self.max_tokens);
+        }
+        if self.min_alphabetic_ratio > 0.0 {
+            step.push("min_alphabetic_ratio", self.min_alphabetic_ratio);
+        }
+        for script in &self.scripts {
+            step.push("script", script.name());
+        }
+        for language in &self.languages {
+            step.push("language", language.code());
+        }
+        #[cfg(feature = "regex")]
+        {
+            for pattern in &self.required {
+                step.push("required", crate::manifest::escape(pattern.as_str()));
+            }
+            for pattern in &self.forbidden {
+                step.push("forbidden", crate::manifest::escape(pattern.as_str()));
+            }
+        }
+        step
+    }
+
+    /// Rebuilds a filter from the step written by describe()
+    ///
+    /// # Returns
+    /// None if the step is not valid, or if it has patterns and the crate was built
+    /// without the `regex` feature
+    pub(crate) fn from_step(step: &ManifestStep) -> Option<Self> {
+        let mut filter = SentenceFilter::new();
+        filter.min_tokens = step.value("min_tokens")?.parse().ok()?;
+        if let Some(max_tokens) = step.value("max_tokens") {
+            filter.max_tokens = max_tokens.parse().ok()?;
+        }
+        if let Some(ratio) = step.value("min_alphabetic_ratio") {
+            filter = filter.with_min_alphabetic_ratio(ratio.parse().ok()?);
+        }
+        for script in step.values("script") {
+            filter.scripts.push(Script::from_name(&script)?);
+        }
+        for language in step.values("language") {
+            filter.languages.push(Language::from_code(&language)?);
+        }
+        #[cfg(feature = "regex")]
+        {
+            for pattern in step.values("required") {
+                filter.required.push(regex::Regex::new(&pattern).ok()?);
+            }
+            for pattern in step.values("forbidden") {
+                filter.forbidden.push(regex::Regex::new(&pattern).ok()?);
+            }
+        }
+        #[cfg(not(feature = "regex"))]
+        if !step.values("required").is_empty() || !step.values("forbidden").is_empty() {
+            return None;
+        }
+        Some(filter)
+    }
+
+    /// Returns true if the sentence passes every check
+    pub fn keeps(&self, tokens: &[Token]) -> bool {
+        self.check(tokens).is_ok()
+    }
+
+    /// Splits a text into sentences and returns the ones the filter keeps
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::filter::SentenceFilter;
+    /// use tokenizer_rust::sentences::SentenceSplitter;
+    ///
+    /// let tokenizer = Tokenizer::new(String::new());
+    /// let filter = SentenceFilter::new()
+    ///     .with_token_range(3, 50)
+    ///     .with_min_alphabetic_ratio(0.7);
+    /// let page = "Home. The river floods every spring. 12 34 56 78! Thanks for reading.";
+    /// assert_eq!(
+    ///     filter.filter(&tokenizer, &SentenceSplitter::new(), page),
+    ///     vec!["The river floods every spring.", "Thanks for reading."]
+    /// );
+    /// ```
+    pub fn filter<'t>(
+        &self,
+        tokenizer: &Tokenizer,
+        splitter: &SentenceSplitter,
+        text: &'t str,
+    ) -> Vec<&'t str> {
+        splitter
+            .sentences(text)
+            .into_iter()
+            .filter(|sentence| self.keeps(&tokenizer.tokenize_text(sentence)))
+            .collect()
+    }
+}
@@ -0,0 +1,172 @@
+// Bag-of-words vectors with the hashing trick
+// A HashingVectorizer turns a document into a sparse vector of fixed size without a
+// vocabulary: the index of a token is its hash modulo the number of dimensions. Nothing is
+// learned or stored, so documents can be vectorized one at a time from a stream, in any
+// process, and always get the same indices. Two tokens can share an index (a collision);
+// with signed hashing, half of the tokens count negatively so that collisions tend to
+// cancel out instead of always adding up.
+// The hash is 64-bit FNV-1a (the one of the manifests), which is the same on every machine,
+// followed by the MurmurHash3 finalizer: the low bits of FNV-1a are never influenced by its
+// high bits, so without mixing the index modulo a power of two would collide too often.
+
+use std::collections::BTreeMap;
+
+use crate::manifest::{fnv1a, FNV_OFFSET};
+use crate::token::{Token, TokenKind};
+
+/// Maps documents to sparse vectors of a fixed number of dimensions by hashing their tokens
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::Tokenizer;
+/// use tokenizer_rust::hashing::HashingVectorizer;
+///
+/// let vectorizer = HashingVectorizer::new(1 << 10);
+/// let tokens = Tokenizer::new(String::new()).tokenize_text("to be or not to be");
+///
+/// // (index, value) pairs sorted by index: "to" and "be" count twice
+/// let vector = vectorizer.vector_tokens(&tokens);
+/// assert_eq!(vector.len(), 4);
+/// assert_eq!(vector.iter().map(|(_, value)| value.abs()).sum::<f64>(), 6.0);
+///
+/// // The index and sign of a token never change
+/// let (index, sign) = vectorizer.index("be");
+/// assert!(index < 1 << 10);
+/// assert!(vector.contains(&(index, 2.0 * sign)));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HashingVectorizer {
+    /// Size of the vectors
+    dimensions: u32,
+    /// Give half of the tokens a negative sign
+    signed: bool,
+    /// Hash "The" and "the" separately
+    case_sensitive: bool,
+    /// Scale the vectors to a length of 1
+    normalize: bool,
+}
+
+impl Default for HashingVectorizer {
+    /// 2^20 dimensions, signed, case-sensitive and without normalization
+    fn default() -> Self {
+        HashingVectorizer::new(1 << 20)
+    }
+}
+
+impl HashingVectorizer {
+    /// Creates a signed, case-sensitive vectorizer of raw counts
+    ///
+    /// # Arguments
+    /// * `dimensions` - The size of the vectors (more dimensions, fewer collisions)
+    ///
+    /// # Panics
+    /// If `dimensions` is 0
+    pub fn new(dimensions: u32) -> Self {
+        assert!(
+            dimensions > 0,
+            "a HashingVectorizer needs at least one dimension"
+        );
+        HashingVectorizer {
+            dimensions,
+            signed: true,
+            case_sensitive: true,
+            normalize: false,
+        }
+    }
+
+    /// Sets whether the sign of a token comes from its hash (the default) or is always +1
+    pub fn with_signed(mut self, enabled: bool) -> Self {
+        self.signed = enabled;
+        self
+    }
+
+    /// Sets whether tokens that differ only by case get different indices (the default)
+    pub fn with_case_sensitive(mut self, enabled: bool) -> Self {
+        self.case_sensitive = enabled;
+        self
+    }
+
+    /// Sets whether vectors are scaled to a (Euclidean) length of 1
+    pub fn with_normalization(mut self, enabled: bool) -> Self {
+        self.normalize = enabled;
+        self
+    }
+
+    /// Returns the size of the vectors
+    pub fn dimensions(&self) -> u32 {
+        self.dimensions
+    }
+
+    /// Returns the index of a token and its sign (+1.0 or -1.0, always +1.0 unsigned)
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::hashing::HashingVectorizer;
+    ///
+    /// let vectorizer = HashingVectorizer::new(100).with_case_sensitive(false);
+    /// assert_eq!(vectorizer.index("Rust"), vectorizer.index("rust"));
+    /// assert_eq!(vectorizer.with_signed(false).index("rust").1, 1.0);
+    /// ```
+    pub fn index(&self, token: &str) -> (u32, f64) {
+        let hash = mix(if self.case_sensitive {
+            fnv1a(FNV_OFFSET, token.as_bytes())
+        } else {
+            fnv1a(FNV_OFFSET, token.to_lowercase().as_bytes())
+        });
+        // The index comes from the low bits, the sign from the highest one
+        let index = (hash % u64::from(self.dimensions)) as u32;
+        let sign = if self.signed && hash >> 63 == 1 {
+            -1.0
+        } else {
+            1.0
+        };
+        (index, sign)
+    }
+
+    /// Returns the vector of a document as (index, value) pairs sorted by index
+    ///
+    /// Every token adds its sign to its index. Indices whose value is 0 (tokens of
+    /// opposite signs that collided) are left out.
+    pub fn vector<S: AsRef<str>>(&self, tokens: &[S]) -> Vec<(u32, f64)> {
+        // STEP 1: Add up the signs of the tokens at their indices
+        let mut values: BTreeMap<u32, f64> = BTreeMap::new();
+        for token in tokens {
+            let (index, sign) = self.index(token.as_ref());
+            *values.entry(index).or_insert(0.0) += sign;
+        }
+        let mut vector: Vec<(u32, f64)> = values
+            .into_iter()
+            .filter(|&(_, value)| value != 0.0)
+            .collect();
+
+        // STEP 2: Scale to a length of 1
+        let length = vector
+            .iter()
+            .map(|(_, value)| value * value)
+            .sum::<f64>()
+            .sqrt();
+        if self.normalize && length > 0.0 {
+            vector.iter_mut().for_each(|(_, value)| *value /= length);
+        }
+        vector
+    }
+
+    /// Returns the vector of tokenized text (line and paragraph breaks are skipped)
+    pub fn vector_tokens(&self, tokens: &[Token]) -> Vec<(u32, f64)> {
+        let texts: Vec<&str> = tokens
+            .iter()
+            .filter(|token| !matches!(token.kind, TokenKind::LineBreak | TokenKind::ParagraphBreak))
+            .map(|token| token.text.as_str())
+            .collect();
+        self.vector(&texts)
+    }
+}
+
+/// Spreads every bit of a hash over all the others (the MurmurHash3 64-bit finalizer)
+fn mix(mut hash: u64) -> u64 {
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    hash ^ (hash >> 33)
+}
@@ -0,0 +1,237 @@
+// Highlighting query terms in the original text of a document
+// A search result page shows the document with the matched words marked ("<mark>pay</mark>").
+// The document is tokenized with the same settings as the query and every token equal to a
+// query term gives its byte range in the original text, so the text itself is never
+// rewritten: the caller wraps the ranges in whatever markup it needs.
+// colorize_kinds() uses the same offsets to show how a text was tokenized in a terminal:
+// every token is colored by its kind, and the text between tokens is copied as is.
+
+use std::collections::HashSet;
+
+use crate::token::{Token, TokenKind};
+use crate::Tokenizer;
+
+/// Finds the byte ranges of query terms in documents
+///
+/// Like the search index, only words, numbers and identifiers are terms: punctuation in
+/// the query is ignored.
+pub struct Highlighter {
+    /// The tokenizer used for the query and the documents
+    tokenizer: Tokenizer,
+    /// The query terms, as written in the query
+    terms: Vec<String>,
+    /// Compare terms with their case (false by default)
+    case_sensitive: bool,
+    /// Merge the ranges of matched tokens separated only by whitespace (false by default)
+    merge_adjacent: bool,
+}
+
+impl Highlighter {
+    /// Creates a highlighter for the terms of a query
+    ///
+    /// # Arguments
+    /// * `tokenizer` - The tokenizer whose settings are used for the query and the documents
+    /// * `query` - The query, split into terms by the tokenizer
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::highlight::Highlighter;
+    ///
+    /// let highlighter = Highlighter::new(Tokenizer::new(String::new()), "late payment");
+    /// let text = "Payment is LATE: pay now.";
+    /// assert_eq!(highlighter.ranges(text), vec![(0, 7), (11, 15)]);
+    /// assert_eq!(
+    ///     highlighter.mark(text, "<mark>", "</mark>"),
+    ///     "<mark>Payment</mark> is <mark>LATE</mark>: pay now."
+    /// );
+    /// ```
+    pub fn new(tokenizer: Tokenizer, query: &str) -> Self {
+        let terms = tokenizer
+            .tokenize_text(query)
+            .into_iter()
+            .filter(is_term)
+            .map(|token| token.text)
+            .collect();
+        Highlighter {
+            tokenizer,
+            terms,
+            case_sensitive: false,
+            merge_adjacent: false,
+        }
+    }
+
+    /// Compares terms with their case ("Apple" no longer matches "apple")
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::highlight::Highlighter;
+    ///
+    /// let highlighter = Highlighter::new(Tokenizer::new(String::new()), "Apple")
+    ///     .with_case_sensitive(true);
+    /// assert_eq!(highlighter.ranges("apple and Apple"), vec![(10, 15)]);
+    /// ```
+    pub fn with_case_sensitive(mut self, enabled: bool) -> Self {
+        self.case_sensitive = enabled;
+        self
+    }
+
+    /// Merges matched tokens separated only by whitespace into one range, so
+    /// "New York" is marked once instead of word by word
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::highlight::Highlighter;
+    ///
+    /// let highlighter = Highlighter::new(Tokenizer::new(String::new()), "new york")
+    ///     .with_merge_adjacent(true);
+    /// let text = "New  York, new jersey";
+    /// assert_eq!(highlighter.mark(text, "[", "]"), "[New  York], [new] jersey");
+    /// ```
+    pub fn with_merge_adjacent(mut self, enabled: bool) -> Self {
+        self.merge_adjacent = enabled;
+        self
+    }
+
+    /// Returns the query terms, as written in the query
+    pub fn terms(&self) -> &[String] {
+        &self.terms
+    }
+
+    /// Returns the (start, end) byte ranges of the query terms in a document, in text order
+    pub fn ranges(&self, document: &str) -> Vec<(usize, usize)> {
+        let terms: HashSet<String> = self.terms.iter().map(|term| self.key(term)).collect();
+        let mut ranges: Vec<(usize, usize)> = Vec::new();
+        if terms.is_empty() {
+            return ranges;
+        }
+
+        for token in self.tokenizer.tokenize_text(document) {
+            if !is_term(&token) || !terms.contains(&self.key(&token.text)) {
+                continue;
+            }
+            match ranges.last_mut() {
+                Some(last)
+                    if self.merge_adjacent && document[last.1..token.start].trim().is_empty() =>
+                {
+                    last.1 = token.end;
+                }
+                _ => ranges.push((token.start, token.end)),
+            }
+        }
+        ranges
+    }
+
+    /// Returns the document with every match wrapped in `open` and `close`
+    ///
+    /// The rest of the text is copied as is: escape it first if it goes into HTML (and
+    /// compute the ranges on the escaped text).
+    pub fn mark(&self, document: &str, open: &str, close: &str) -> String {
+        let mut marked = String::with_capacity(document.len());
+        let mut position = 0;
+        for (start, end) in self.ranges(document) {
+            marked.push_str(&document[position..start]);
+            marked.push_str(open);
+            marked.push_str(&document[start..end]);
+            marked.push_str(close);
+            position = end;
+        }
+        marked.push_str(&document[position..]);
+        marked
+    }
+
+    /// Returns the form a term is compared in
+    fn key(&self, term: &str) -> String {
+        if self.case_sensitive {
+            term.to_string()
+        } else {
+            term.to_lowercase()
+        }
+    }
+}
+
+/// Returns true for the tokens that can match a query term
+fn is_term(token: &Token) -> bool {
+    matches!(
+        token.kind,
+        TokenKind::Word | TokenKind::Number | TokenKind::Identifier
+    )
+}
+
+/// ANSI escape that resets the color and the underline
+const RESET: &str = "\x1b[0m";
+
+/// Returns the ANSI escape (SGR) colorize_kinds() uses for a token kind
+///
+/// Words are green, numbers yellow, punctuation magenta, special tokens bold red and
+/// identifiers cyan. Line and paragraph breaks are whitespace and keep no color ("").
+pub fn kind_color(kind: TokenKind) -> &'static str {
+    match kind {
+        TokenKind::Word => "\x1b[32m",
+        TokenKind::Number => "\x1b[33m",
+        TokenKind::Punctuation => "\x1b[35m",
+        TokenKind::Special => "\x1b[1;31m",
+        TokenKind::Identifier => "\x1b[36m",
+        TokenKind::LineBreak | TokenKind::ParagraphBreak => "",
+    }
+}
+
+/// Returns the original text with every token colored by its kind, for a terminal
+///
+/// A token that starts right where the previous one ends is underlined when the previous
+/// one is not (and the other way around), so that "can't" shows where it was split.
+///
+/// # Arguments
+/// * `text` - The original text
+/// * `tokens` - Its tokens, with their offsets (from tokenize_text())
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::Tokenizer;
+/// use tokenizer_rust::highlight::colorize_kinds;
+///
+/// let text = "Pay 42 now!";
+/// let tokens = Tokenizer::new(String::new()).tokenize_text(text);
+/// let colored = colorize_kinds(text, &tokens);
+/// assert_eq!(
+///     colored,
+///     "\x1b[32mPay\x1b[0m \x1b[33m42\x1b[0m \x1b[32mnow\x1b[0m\x1b[35;4m!\x1b[0m"
+/// );
+/// ```
+pub fn colorize_kinds(text: &str, tokens: &[Token]) -> String {
+    let mut colored = String::with_capacity(text.len() * 2);
+    let mut position = 0;
+    let mut underlined = false;
+    for token in tokens {
+        // Offsets that go back or out of the text can't be shown
+        if token.start < position || token.end > text.len() || token.start > token.end {
+            continue;
+        }
+        let color = kind_color(token.kind);
+        colored.push_str(&text[position..token.start]);
+        if color.is_empty() {
+            colored.push_str(&text[token.start..token.end]);
+            position = token.end;
+            underlined = false;
+            continue;
+        }
+
+        // STEP 1: Underline every other token of a run without whitespace between them
+        underlined = token.start == position && position > 0 && !underlined;
+        colored.push_str(color);
+        if underlined {
+            // "\x1b[32m" + underline is "\x1b[32;4m"
+            colored.pop();
+            colored.push_str(";4m");
+        }
+
+        // STEP 2: The token itself, as written in the text
+        colored.push_str(&text[token.start..token.end]);
+        colored.push_str(RESET);
+        position = token.end;
+    }
+    colored.push_str(&text[position..]);
+    colored
+}
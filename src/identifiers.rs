@@ -0,0 +1,235 @@
+// Identifier detection: order numbers, IBANs, tracking codes, ...
+// These long codes mix letters and digits and must stay in one piece, otherwise text analytics
+// on customer-support messages counts "DE89", "3704" and "0044" as three unrelated tokens.
+
+use std::fmt;
+use std::sync::Arc;
+
+use crate::token::{Token, TokenKind};
+
+/// A checksum validation hook
+///
+/// It receives the compact form of a candidate identifier (see compact()) and returns
+/// true if the identifier is valid.
+pub type Validator = Arc<dyn Fn(&str) -> bool + Send + Sync>;
+
+/// Recognizes identifiers in a token stream and turns them into `TokenKind::Identifier` tokens
+///
+/// A token is an identifier candidate when its compact form:
+/// - is at least `min_length` characters long,
+/// - only contains ASCII letters and digits,
+/// - contains at least one digit,
+/// - and contains at least one letter, or was written with hyphens ("100-2345-678").
+///
+/// Plain numbers like "1000000" are never identifiers, so quantities and identifiers don't get confused.
+/// IBAN-style codes written in groups ("DE89 3704 0044 0532 0130 00") are merged into one token.
+///
+/// If validators are registered, a candidate is only accepted when at least one of them says it is valid.
+#[derive(Clone)]
+pub struct IdentifierDetector {
+    /// Minimum number of letters and digits in an identifier
+    min_length: usize,
+    /// Checksum hooks (an empty list means "accept every candidate")
+    validators: Vec<Validator>,
+}
+
+impl Default for IdentifierDetector {
+    fn default() -> Self {
+        IdentifierDetector {
+            min_length: 6,
+            validators: Vec::new(),
+        }
+    }
+}
+
+impl fmt::Debug for IdentifierDetector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IdentifierDetector")
+            .field("min_length", &self.min_length)
+            .field("validators", &self.validators.len())
+            .finish()
+    }
+}
+
+impl IdentifierDetector {
+    /// Creates a detector with the default settings (minimum length 6, no validators)
+    pub fn new() -> Self {
+        IdentifierDetector::default()
+    }
+
+    /// Sets the minimum number of letters and digits an identifier must have
+    pub fn with_min_length(mut self, min_length: usize) -> Self {
+        self.min_length = min_length;
+        self
+    }
+
+    /// Returns the minimum number of letters and digits an identifier must have
+    pub fn min_length(&self) -> usize {
+        self.min_length
+    }
+
+    /// Returns the number of checksum validation hooks
+    pub fn validator_count(&self) -> usize {
+        self.validators.len()
+    }
+
+    /// Adds a checksum validation hook
+    ///
+    /// # Arguments
+    /// * `validator` - A function receiving the compact identifier and returning true if it is valid
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::identifiers::IdentifierDetector;
+    ///
+    /// // Only accept order numbers starting with "ORD"
+    /// let detector = IdentifierDetector::new().with_validator(|id| id.starts_with("ORD"));
+    /// assert!(detector.is_identifier("ORD-2024-7781"));
+    /// assert!(!detector.is_identifier("XYZ-2024-7781"));
+    /// ```
+    pub fn with_validator<F>(mut self, validator: F) -> Self
+    where
+        F: Fn(&str) -> bool + Send + Sync + 'static,
+    {
+        self.validators.push(Arc::new(validator));
+        self
+    }
+
+    /// Returns true if the text has the shape of an identifier (validators are not run)
+    pub fn is_candidate(&self, text: &str) -> bool {
+        let compact_form = compact(text);
+
+        if compact_form.len() < self.min_length
+            || !compact_form.chars().all(|c| c.is_ascii_alphanumeric())
+        {
+            return false;
+        }
+
+        let has_digit = compact_form.chars().any(|c| c.is_ascii_digit());
+        let has_letter = compact_form.chars().any(|c| c.is_ascii_alphabetic());
+
+        has_digit && (has_letter || text.contains('-'))
+    }
+
+    /// Returns true if the text has the shape of an identifier and passes the validators
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::identifiers::IdentifierDetector;
+    ///
+    /// let detector = IdentifierDetector::new();
+    /// assert!(detector.is_identifier("1Z999AA10123456784"));
+    /// assert!(!detector.is_identifier("1000000"));
+    /// ```
+    pub fn is_identifier(&self, text: &str) -> bool {
+        if !self.is_candidate(text) {
+            return false;
+        }
+
+        let compact_form = compact(text);
+        self.validators.is_empty()
+            || self
+                .validators
+                .iter()
+                .any(|validate| validate(&compact_form))
+    }
+
+    /// Marks the identifiers in a list of tokens
+    ///
+    /// Identifier tokens get the kind `TokenKind::Identifier`. Grouped IBAN-style codes are
+    /// merged into a single token whose text is the original slice (spaces included).
+    ///
+    /// # Arguments
+    /// * `text` - The original text the tokens come from
+    /// * `tokens` - The tokens to look at
+    ///
+    /// # Returns
+    /// The tokens with identifiers marked (and merged where needed)
+    pub fn apply(&self, text: &str, tokens: Vec<Token>) -> Vec<Token> {
+        let mut result: Vec<Token> = Vec::with_capacity(tokens.len());
+        let mut index = 0;
+
+        while index < tokens.len() {
+            let token = &tokens[index];
+
+            // First try to merge a grouped code like "DE89 3704 0044 0532 0130 00"
+            if is_group_head(&token.text) {
+                let mut last = index;
+                while last + 1 < tokens.len()
+                    && &text[tokens[last].end..tokens[last + 1].start] == " "
+                    && is_group(&tokens[last + 1].text)
+                {
+                    last += 1;
+                }
+
+                if last > index {
+                    let start = token.start;
+                    let end = tokens[last].end;
+                    let merged = &text[start..end];
+                    let length = compact(merged).len();
+
+                    // IBANs are between 15 and 34 characters long
+                    if (15..=34).contains(&length) && self.is_identifier(merged) {
+                        result.push(Token {
+                            text: merged.to_string(),
+                            kind: TokenKind::Identifier,
+                            start,
+                            end,
+                        });
+                        index = last + 1;
+                        continue;
+                    }
+                }
+            }
+
+            // Otherwise look at the token on its own
+            let mut token = token.clone();
+            if token.kind != TokenKind::Special && self.is_identifier(&token.text) {
+                token.kind = TokenKind::Identifier;
+            }
+            result.push(token);
+            index += 1;
+        }
+
+        result
+    }
+}
+
+/// Returns the compact form of an identifier: spaces and hyphens removed, letters in uppercase
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::identifiers::compact;
+///
+/// assert_eq!(compact("de89 3704-0044"), "DE8937040044");
+/// ```
+pub fn compact(text: &str) -> String {
+    text.chars()
+        .filter(|c| *c != ' ' && *c != '-')
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+/// Returns true if a single space between two words could be inside a grouped code like
+/// "DE89 3704 0044" (apply() merges such groups into one Identifier token)
+pub(crate) fn may_join_groups(before: &str, after: &str) -> bool {
+    is_group(before) && is_group(after)
+}
+
+/// Returns true for the first group of an IBAN-style code: two uppercase letters and two digits
+fn is_group_head(text: &str) -> bool {
+    let bytes = text.as_bytes();
+    bytes.len() == 4
+        && bytes[0].is_ascii_uppercase()
+        && bytes[1].is_ascii_uppercase()
+        && bytes[2].is_ascii_digit()
+        && bytes[3].is_ascii_digit()
+}
+
+/// Returns true for a following group: one to four uppercase letters or digits
+fn is_group(text: &str) -> bool {
+    (1..=4).contains(&text.len())
+        && text
+            .chars()
+            .all(|c| c.is_ascii_digit() || c.is_ascii_uppercase())
+}
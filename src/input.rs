@@ -0,0 +1,415 @@
+// Sources of documents to tokenize
+// The InputSource trait is the counterpart of OutputWriter: anything that hands out
+// documents one at a time. The crate reads lines of files or stdin, whole files, a string
+// field of JSONL records and the bodies of HTTP responses, and a user implementation can
+// read from anywhere else (S3, a message queue) and still feed the CLI code paths and
+// Tokenizer::write_source().
+// Every document has a name ("corpus.txt:12", a path or a URL) that says where it came from.
+
+use std::collections::VecDeque;
+use std::io::{self, BufRead, Read, Write};
+use std::net::TcpStream;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::compress;
+use crate::output;
+
+/// One document read from an InputSource
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputDocument {
+    /// Where the document comes from ("corpus.txt:12", a path or a URL)
+    pub name: String,
+    /// The text of the document
+    pub text: String,
+}
+
+/// A source of documents
+///
+/// # Example
+/// ```
+/// use std::collections::VecDeque;
+/// use std::io;
+/// use tokenizer_rust::Tokenizer;
+/// use tokenizer_rust::input::{InputDocument, InputSource};
+/// use tokenizer_rust::output::{JsonlWriter, OutputWriter};
+///
+/// // Documents that arrive on a queue
+/// struct QueueSource(VecDeque<String>);
+///
+/// impl InputSource for QueueSource {
+///     fn next_document(&mut self) -> io::Result<Option<InputDocument>> {
+///         Ok(self.0.pop_front().map(|text| InputDocument { name: "queue".to_string(), text }))
+///     }
+/// }
+///
+/// let mut source = QueueSource(VecDeque::from(["Hi!".to_string(), "Bye.".to_string()]));
+/// let mut jsonl = JsonlWriter::new(Vec::new());
+/// let written = Tokenizer::new(String::new()).write_source(&mut source, &mut jsonl).unwrap();
+/// jsonl.finish().unwrap();
+/// assert_eq!(written, 2);
+/// ```
+pub trait InputSource {
+    /// Reads the next document
+    ///
+    /// # Returns
+    /// The document, None when the source has no more documents, or the error of the
+    /// underlying reader
+    fn next_document(&mut self) -> io::Result<Option<InputDocument>>;
+}
+
+impl<T: InputSource + ?Sized> InputSource for &mut T {
+    fn next_document(&mut self) -> io::Result<Option<InputDocument>> {
+        (**self).next_document()
+    }
+}
+
+impl<T: InputSource + ?Sized> InputSource for Box<T> {
+    fn next_document(&mut self) -> io::Result<Option<InputDocument>> {
+        (**self).next_document()
+    }
+}
+
+/// One document per non-blank line of a reader (a file or stdin)
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::input::{InputSource, LineSource};
+///
+/// let mut source = LineSource::new("first\n\nsecond\n".as_bytes(), "notes.txt");
+/// let document = source.next_document().unwrap().unwrap();
+/// assert_eq!((document.name.as_str(), document.text.as_str()), ("notes.txt:1", "first"));
+/// assert_eq!(source.next_document().unwrap().unwrap().name, "notes.txt:3");
+/// assert_eq!(source.next_document().unwrap(), None);
+/// ```
+pub struct LineSource<R> {
+    /// The lines
+    reader: R,
+    /// The name of the reader, before the line numbers
+    name: String,
+    /// Number of the last line read
+    line: usize,
+}
+
+impl<R: BufRead> LineSource<R> {
+    /// Creates a source of the lines of a reader, named `name`
+    pub fn new(reader: R, name: &str) -> Self {
+        LineSource {
+            reader,
+            name: name.to_string(),
+            line: 0,
+        }
+    }
+
+    /// Reads the next line without its line ending, None at the end of the reader
+    fn next_line(&mut self) -> io::Result<Option<String>> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        self.line += 1;
+        let end = line.trim_end_matches(['\n', '\r']).len();
+        line.truncate(end);
+        Ok(Some(line))
+    }
+}
+
+impl LineSource<Box<dyn BufRead>> {
+    /// Opens a file ("-" is stdin) as a source of its lines, decompressing .gz and .zst
+    /// files (see compress::open())
+    pub fn open(path: &str) -> io::Result<Self> {
+        if path == "-" {
+            return Ok(LineSource::new(Box::new(io::stdin().lock()), "stdin"));
+        }
+        let reader = compress::open(path)
+            .map_err(|error| io::Error::new(error.kind(), format!("{}: {}", path, error)))?;
+        Ok(LineSource::new(reader, path))
+    }
+}
+
+impl<R: BufRead> InputSource for LineSource<R> {
+    fn next_document(&mut self) -> io::Result<Option<InputDocument>> {
+        while let Some(line) = self.next_line()? {
+            if !line.trim().is_empty() {
+                return Ok(Some(InputDocument {
+                    name: format!("{}:{}", self.name, self.line),
+                    text: line,
+                }));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// One document per file, with the whole content of the file (decompressed if it is a .gz or
+/// .zst file)
+pub struct FileSource {
+    /// The files not read yet
+    paths: VecDeque<PathBuf>,
+}
+
+impl FileSource {
+    /// Creates a source of the given files, read in order
+    pub fn new<I, P>(paths: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<PathBuf>,
+    {
+        FileSource {
+            paths: paths.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl InputSource for FileSource {
+    fn next_document(&mut self) -> io::Result<Option<InputDocument>> {
+        let Some(path) = self.paths.pop_front() else {
+            return Ok(None);
+        };
+        let name = path.display().to_string();
+        let text = compress::read_to_string(&path)
+            .map_err(|error| io::Error::new(error.kind(), format!("{}: {}", name, error)))?;
+        Ok(Some(InputDocument { name, text }))
+    }
+}
+
+/// One document per JSONL record: the string field at a path of keys ("text", or
+/// "meta.body" for {"meta": {"body": "..."}})
+///
+/// A record without the field, malformed JSON or a field that is not a string is an
+/// InvalidData error naming the line.
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::input::{InputSource, JsonlFieldSource};
+///
+/// let jsonl = "{\"id\": 1, \"meta\": {\"body\": \"Hello\"}}\n{\"meta\": {\"body\": \"Bye\"}}\n";
+/// let mut source = JsonlFieldSource::new(jsonl.as_bytes(), "dump.jsonl", "meta.body");
+/// assert_eq!(source.next_document().unwrap().unwrap().text, "Hello");
+/// assert_eq!(source.next_document().unwrap().unwrap().name, "dump.jsonl:2");
+///
+/// let mut missing = JsonlFieldSource::new("{\"title\": \"x\"}\n".as_bytes(), "dump.jsonl", "text");
+/// assert!(missing.next_document().is_err());
+/// ```
+pub struct JsonlFieldSource<R> {
+    /// The records, one per line
+    lines: LineSource<R>,
+    /// The keys leading to the field
+    path: Vec<String>,
+}
+
+impl<R: BufRead> JsonlFieldSource<R> {
+    /// Creates a source of the `field` of every record of a reader named `name`
+    pub fn new(reader: R, name: &str, field: &str) -> Self {
+        JsonlFieldSource {
+            lines: LineSource::new(reader, name),
+            path: field.split('.').map(str::to_string).collect(),
+        }
+    }
+}
+
+impl JsonlFieldSource<Box<dyn BufRead>> {
+    /// Opens a JSONL file ("-" is stdin) as a source of one field of its records
+    pub fn open(path: &str, field: &str) -> io::Result<Self> {
+        let lines = LineSource::open(path)?;
+        Ok(JsonlFieldSource {
+            lines,
+            path: field.split('.').map(str::to_string).collect(),
+        })
+    }
+}
+
+impl<R: BufRead> InputSource for JsonlFieldSource<R> {
+    fn next_document(&mut self) -> io::Result<Option<InputDocument>> {
+        let Some(record) = self.lines.next_document()? else {
+            return Ok(None);
+        };
+        let path: Vec<&str> = self.path.iter().map(String::as_str).collect();
+        let invalid = |message: String| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: {}", record.name, message),
+            )
+        };
+        let text = output::json_string_field(&record.text, &path)
+            .map_err(invalid)?
+            .ok_or_else(|| invalid(format!("no \"{}\" field", self.path.join("."))))?;
+        Ok(Some(InputDocument {
+            name: record.name.clone(),
+            text,
+        }))
+    }
+}
+
+/// The largest HTTP response HttpSource reads by default (64 MiB)
+pub const DEFAULT_MAX_RESPONSE_BYTES: usize = 64 * 1024 * 1024;
+
+/// One document per URL: the body of the response to a GET request
+///
+/// Only plain `http://` URLs are supported (no TLS, no redirects): a response that is not
+/// 2xx is an error. Bodies must be UTF-8 text. A response larger than the maximum size
+/// (DEFAULT_MAX_RESPONSE_BYTES unless changed with with_max_size()) is an error too, and is
+/// not read past that size, so a misbehaving server cannot fill the memory.
+pub struct HttpSource {
+    /// The URLs not fetched yet
+    urls: VecDeque<String>,
+    /// Connect, read and write timeout
+    timeout: Duration,
+    /// The largest response read, headers included
+    max_size: usize,
+}
+
+impl HttpSource {
+    /// Creates a source of the bodies of the given URLs, fetched in order
+    pub fn new<I, S>(urls: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        HttpSource {
+            urls: urls.into_iter().map(Into::into).collect(),
+            timeout: Duration::from_secs(30),
+            max_size: DEFAULT_MAX_RESPONSE_BYTES,
+        }
+    }
+
+    /// Sets the connect, read and write timeout (30 seconds by default)
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets the largest response read, headers included (DEFAULT_MAX_RESPONSE_BYTES by
+    /// default)
+    ///
+    /// # Example
+    /// ```
+    /// use std::io::{BufRead, BufReader, Write};
+    /// use tokenizer_rust::input::{HttpSource, InputSource};
+    ///
+    /// // A server that answers with a 1 MB body
+    /// let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    /// let url = format!("http://{}/big", listener.local_addr().unwrap());
+    /// std::thread::spawn(move || {
+    ///     let (mut stream, _) = listener.accept().unwrap();
+    ///     let mut request = BufReader::new(stream.try_clone().unwrap());
+    ///     let mut line = String::new();
+    ///     while request.read_line(&mut line).unwrap() > 2 {
+    ///         line.clear();
+    ///     }
+    ///     let body = "word ".repeat(200_000);
+    ///     let _ = write!(stream, "HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n{}", body.len(), body);
+    /// });
+    ///
+    /// let error = HttpSource::new([url]).with_max_size(64 * 1024).next_document().unwrap_err();
+    /// assert!(error.to_string().contains("larger than 65536 bytes"));
+    /// ```
+    pub fn with_max_size(mut self, bytes: usize) -> Self {
+        self.max_size = bytes;
+        self
+    }
+
+    /// Fetches the body of one URL
+    fn fetch(&self, url: &str) -> io::Result<String> {
+        // STEP 1: Split the URL into host, port and path
+        let invalid = |message: &str| {
+            io::Error::new(io::ErrorKind::InvalidInput, format!("{}: {}", url, message))
+        };
+        let rest = url
+            .strip_prefix("http://")
+            .ok_or_else(|| invalid("only http:// URLs are supported"))?;
+        let (authority, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+        let path = if path.is_empty() { "/" } else { path };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse().map_err(|_| invalid("invalid port"))?),
+            None => (authority, 80),
+        };
+
+        // STEP 2: Send the request and read the whole response (the server closes), but not
+        // more than the maximum size
+        let address = std::net::ToSocketAddrs::to_socket_addrs(&(host, port))?
+            .next()
+            .ok_or_else(|| invalid("unknown host"))?;
+        let mut stream = TcpStream::connect_timeout(&address, self.timeout)?;
+        stream.set_read_timeout(Some(self.timeout))?;
+        stream.set_write_timeout(Some(self.timeout))?;
+        let request = format!(
+            "GET {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\nAccept-Encoding: identity\r\n\r\n",
+            path, authority
+        );
+        stream.write_all(request.as_bytes())?;
+        let mut response = Vec::new();
+        stream
+            .take(self.max_size as u64 + 1)
+            .read_to_end(&mut response)?;
+        if response.len() > self.max_size {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "{}: the response is larger than {} bytes",
+                    url, self.max_size
+                ),
+            ));
+        }
+
+        // STEP 3: Check the status and decode the body
+        let malformed = || invalid("malformed HTTP response");
+        let header_end = response
+            .windows(4)
+            .position(|window| window == b"\r\n\r\n")
+            .ok_or_else(malformed)?;
+        let head = String::from_utf8_lossy(&response[..header_end]);
+        let mut lines = head.split("\r\n");
+        let status = lines.next().unwrap_or_default();
+        let code = status.split(' ').nth(1).ok_or_else(malformed)?;
+        if !code.starts_with('2') {
+            return Err(io::Error::other(format!("{}: {}", url, status)));
+        }
+        let chunked = lines.any(|line| {
+            line.split_once(':').is_some_and(|(name, value)| {
+                name.trim().eq_ignore_ascii_case("transfer-encoding")
+                    && value.trim().eq_ignore_ascii_case("chunked")
+            })
+        });
+        let body = &response[header_end + 4..];
+        let body = if chunked {
+            decode_chunked(body).ok_or_else(malformed)?
+        } else {
+            body.to_vec()
+        };
+        String::from_utf8(body).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: the body is not UTF-8", url),
+            )
+        })
+    }
+}
+
+impl InputSource for HttpSource {
+    fn next_document(&mut self) -> io::Result<Option<InputDocument>> {
+        let Some(url) = self.urls.pop_front() else {
+            return Ok(None);
+        };
+        let text = self.fetch(&url)?;
+        Ok(Some(InputDocument { name: url, text }))
+    }
+}
+
+/// Joins the chunks of a "Transfer-Encoding: chunked" body, None if it is malformed
+fn decode_chunked(mut body: &[u8]) -> Option<Vec<u8>> {
+    let mut decoded = Vec::new();
+    loop {
+        // Every chunk is "<hex size>[;extensions]\r\n<data>\r\n", the last one has size 0
+        let line_end = body.windows(2).position(|window| window == b"\r\n")?;
+        let size_line = std::str::from_utf8(&body[..line_end]).ok()?;
+        let size_text = size_line.split(';').next()?.trim();
+        let size = usize::from_str_radix(size_text, 16).ok()?;
+        body = &body[line_end + 2..];
+        if size == 0 {
+            return Some(decoded);
+        }
+        decoded.extend_from_slice(body.get(..size)?);
+        body = body.get(size + 2..)?;
+    }
+}
@@ -0,0 +1,75 @@
+// String interning: one shared string per distinct token
+// A large corpus has millions of tokens but only a few hundred thousand distinct ones, and
+// the most frequent ("the", ",", ".") make up a big share of them. An Interner keeps one
+// Arc<str> per distinct token and hands out clones of it, so every repeated token costs
+// a reference count increment instead of a new allocation.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// A set of shared token strings
+///
+/// The interner is passed to Tokenizer::tokenize_interned() rather than stored in the
+/// Tokenizer, so a Tokenizer can still be shared between threads without locking: every
+/// thread can keep its own interner, or one interner can be reused for a whole corpus.
+#[derive(Debug, Clone, Default)]
+pub struct Interner {
+    /// Every distinct string seen so far
+    strings: HashSet<Arc<str>>,
+}
+
+impl Interner {
+    /// Creates an empty interner
+    pub fn new() -> Self {
+        Interner::default()
+    }
+
+    /// Returns the shared copy of a string, creating it the first time the string is seen
+    ///
+    /// # Arguments
+    /// * `text` - The string to intern
+    ///
+    /// # Returns
+    /// A shared string equal to `text`
+    ///
+    /// # Example
+    /// ```
+    /// use std::sync::Arc;
+    /// use tokenizer_rust::intern::Interner;
+    ///
+    /// let mut interner = Interner::new();
+    /// let first = interner.intern("the");
+    /// let second = interner.intern("the");
+    /// assert!(Arc::ptr_eq(&first, &second));
+    /// assert_eq!(interner.len(), 1);
+    /// ```
+    pub fn intern(&mut self, text: &str) -> Arc<str> {
+        if let Some(shared) = self.strings.get(text) {
+            return Arc::clone(shared);
+        }
+
+        let shared: Arc<str> = Arc::from(text);
+        self.strings.insert(Arc::clone(&shared));
+        shared
+    }
+
+    /// Returns the shared copy of a string, if it was interned before
+    pub fn get(&self, text: &str) -> Option<Arc<str>> {
+        self.strings.get(text).cloned()
+    }
+
+    /// Returns the number of distinct strings
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    /// Returns true if no string was interned yet
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+
+    /// Forgets every string (tokens already handed out keep their text)
+    pub fn clear(&mut self) {
+        self.strings.clear();
+    }
+}
@@ -0,0 +1,307 @@
+// A small JSON reader shared by the crate
+// The crate reads JSON in three places, the tokenized records of output.rs, the vocabulary
+// files of external models in external_vocab.rs and the saved configurations of config.rs,
+// and has no JSON dependency for any of them. JsonCursor walks a JSON text one value at a
+// time: records are read key by key without building a tree, the files are read into a
+// Json tree. Arrays and objects can only be nested MAX_JSON_DEPTH levels deep, so hostile
+// input gives an error instead of overflowing the stack.
+
+/// How deep arrays and objects can be nested (records need 2 levels, vocabulary files 4)
+pub(crate) const MAX_JSON_DEPTH: usize = 64;
+
+/// A JSON value, as far as the crate needs it
+pub(crate) enum Json {
+    /// A number, true, false or null, as written in the text
+    Scalar(String),
+    /// A string
+    String(String),
+    /// An array
+    Array(Vec<Json>),
+    /// An object, with its fields in file order
+    Object(Vec<(String, Json)>),
+}
+
+/// A position in a JSON text
+pub(crate) struct JsonCursor<'a> {
+    /// The JSON text
+    text: &'a str,
+    /// Byte offset of the next character
+    position: usize,
+}
+
+impl<'a> JsonCursor<'a> {
+    /// Starts at the beginning of a JSON text
+    pub(crate) fn new(text: &'a str) -> Self {
+        JsonCursor { text, position: 0 }
+    }
+
+    /// Fails unless only whitespace is left
+    pub(crate) fn finish(&mut self) -> Result<(), String> {
+        self.skip_whitespace();
+        if self.position < self.text.len() {
+            return Err("unexpected text after the JSON value".to_string());
+        }
+        Ok(())
+    }
+
+    /// Reads the whole text as one value
+    pub(crate) fn document(&mut self) -> Result<Json, String> {
+        let value = self.value(0)?;
+        self.finish()?;
+        Ok(value)
+    }
+
+    /// Skips spaces, tabs and line breaks
+    pub(crate) fn skip_whitespace(&mut self) {
+        let rest = &self.text[self.position..];
+        self.position += rest.len() - rest.trim_start_matches([' ', '\t', '\n', '\r']).len();
+    }
+
+    /// Returns the next non-whitespace character without consuming it
+    pub(crate) fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.text[self.position..].chars().next()
+    }
+
+    /// Consumes `expected` if it is the next character
+    pub(crate) fn eat(&mut self, expected: char) -> bool {
+        let found = self.peek() == Some(expected);
+        if found {
+            self.position += expected.len_utf8();
+        }
+        found
+    }
+
+    /// Consumes `expected`, or fails
+    pub(crate) fn expect(&mut self, expected: char) -> Result<(), String> {
+        if self.eat(expected) {
+            Ok(())
+        } else {
+            Err(format!("expected '{}' at byte {}", expected, self.position))
+        }
+    }
+
+    /// Reads a string, resolving its escapes
+    pub(crate) fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut value = String::new();
+        loop {
+            // Copy everything up to the next quote or escape at once
+            let rest = &self.text[self.position..];
+            let special = rest.find(['"', '\\']).ok_or("unterminated JSON string")?;
+            value.push_str(&rest[..special]);
+            self.position += special + 1;
+            if rest.as_bytes()[special] == b'"' {
+                return Ok(value);
+            }
+
+            let escape = self.text[self.position..]
+                .chars()
+                .next()
+                .ok_or("unterminated JSON string")?;
+            self.position += escape.len_utf8();
+            let escaped = match escape {
+                'n' => '\n',
+                't' => '\t',
+                'r' => '\r',
+                'b' => '\u{8}',
+                'f' => '\u{c}',
+                'u' => {
+                    let high = self.hex_unit().ok_or("invalid \\u escape")?;
+                    // A UTF-16 surrogate pair is written as two escapes
+                    let code = if (0xD800..0xDC00).contains(&high) {
+                        let low = self.text[self.position..]
+                            .starts_with("\\u")
+                            .then(|| {
+                                self.position += 2;
+                                self.hex_unit()
+                            })
+                            .flatten()
+                            .filter(|low| (0xDC00..0xE000).contains(low))
+                            .ok_or("unpaired surrogate in \\u escape")?;
+                        0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+                    } else {
+                        high
+                    };
+                    char::from_u32(code).ok_or("invalid \\u escape")?
+                }
+                c @ ('"' | '\\' | '/') => c,
+                _ => return Err("invalid escape in a JSON string".to_string()),
+            };
+            value.push(escaped);
+        }
+    }
+
+    /// Reads the 4 hexadecimal digits of a \u escape
+    fn hex_unit(&mut self) -> Option<u32> {
+        let digits = self.text.get(self.position..self.position + 4)?;
+        if !digits.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+            return None;
+        }
+        self.position += 4;
+        u32::from_str_radix(digits, 16).ok()
+    }
+
+    /// Reads a non-negative integer
+    pub(crate) fn unsigned(&mut self) -> Result<usize, String> {
+        self.skip_whitespace();
+        let rest = &self.text[self.position..];
+        let length = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        let value = rest[..length]
+            .parse()
+            .map_err(|_| format!("expected a non-negative integer at byte {}", self.position))?;
+        self.position += length;
+        Ok(value)
+    }
+
+    /// Reads a number, true, false or null, and returns it as written
+    fn scalar(&mut self) -> Result<&'a str, String> {
+        // They run until a delimiter
+        let rest = &self.text[self.position..];
+        let length = rest
+            .find([',', '}', ']', ' ', '\t', '\n', '\r'])
+            .unwrap_or(rest.len());
+        let literal = &rest[..length];
+        let valid = matches!(literal, "true" | "false" | "null")
+            || (length > 0 && literal.parse::<f64>().is_ok());
+        if !valid {
+            return Err(format!("invalid JSON value at byte {}", self.position));
+        }
+        self.position += length;
+        Ok(literal)
+    }
+
+    /// Consumes the opening bracket of an array or object found `depth` levels deep
+    ///
+    /// # Returns
+    /// False if the array or object is empty (its closing bracket is consumed too)
+    fn open(&mut self, open: char, depth: usize) -> Result<bool, String> {
+        if depth >= MAX_JSON_DEPTH {
+            return Err(format!("JSON nested deeper than {} levels", MAX_JSON_DEPTH));
+        }
+        self.expect(open)?;
+        Ok(!self.eat(if open == '{' { '}' } else { ']' }))
+    }
+
+    /// Reads the "," between two items (returns true) or the closing bracket (returns false)
+    fn separator(&mut self, close: char) -> Result<bool, String> {
+        if self.eat(close) {
+            return Ok(false);
+        }
+        self.expect(',')?;
+        Ok(true)
+    }
+
+    /// Reads any JSON value, found `depth` arrays or objects deep
+    pub(crate) fn value(&mut self, depth: usize) -> Result<Json, String> {
+        match self.peek() {
+            Some('"') => self.string().map(Json::String),
+            Some('{') => {
+                let mut fields = Vec::new();
+                if self.open('{', depth)? {
+                    loop {
+                        let key = self.string()?;
+                        self.expect(':')?;
+                        fields.push((key, self.value(depth + 1)?));
+                        if !self.separator('}')? {
+                            break;
+                        }
+                    }
+                }
+                Ok(Json::Object(fields))
+            }
+            Some('[') => {
+                let mut items = Vec::new();
+                if self.open('[', depth)? {
+                    loop {
+                        items.push(self.value(depth + 1)?);
+                        if !self.separator(']')? {
+                            break;
+                        }
+                    }
+                }
+                Ok(Json::Array(items))
+            }
+            Some(_) => self
+                .scalar()
+                .map(|literal| Json::Scalar(literal.to_string())),
+            None => Err("unexpected end of the JSON text".to_string()),
+        }
+    }
+
+    /// Skips any JSON value, found `depth` arrays or objects deep (without building it)
+    pub(crate) fn skip_value(&mut self, depth: usize) -> Result<(), String> {
+        match self.peek() {
+            Some('"') => self.string().map(drop),
+            Some(open @ ('{' | '[')) => {
+                let close = if open == '{' { '}' } else { ']' };
+                if self.open(open, depth)? {
+                    loop {
+                        if open == '{' {
+                            self.string()?;
+                            self.expect(':')?;
+                        }
+                        self.skip_value(depth + 1)?;
+                        if !self.separator(close)? {
+                            break;
+                        }
+                    }
+                }
+                Ok(())
+            }
+            Some(_) => self.scalar().map(drop),
+            None => Err("unexpected end of the JSON text".to_string()),
+        }
+    }
+
+    /// Reads an array of [start, end] pairs
+    pub(crate) fn span_array(&mut self) -> Result<Vec<(usize, usize)>, String> {
+        let mut spans = Vec::new();
+        if self.open('[', 0)? {
+            loop {
+                self.expect('[')?;
+                let start = self.unsigned()?;
+                self.expect(',')?;
+                let end = self.unsigned()?;
+                self.expect(']')?;
+                spans.push((start, end));
+                if !self.separator(']')? {
+                    break;
+                }
+            }
+        }
+        Ok(spans)
+    }
+
+    /// Reads an array of token ids
+    pub(crate) fn id_array(&mut self) -> Result<Vec<u32>, String> {
+        let mut ids = Vec::new();
+        if self.open('[', 0)? {
+            loop {
+                let position = self.position;
+                let id = self.unsigned()?;
+                ids.push(
+                    u32::try_from(id).map_err(|_| format!("id too large at byte {}", position))?,
+                );
+                if !self.separator(']')? {
+                    break;
+                }
+            }
+        }
+        Ok(ids)
+    }
+
+    /// Reads an array of strings
+    pub(crate) fn string_array(&mut self) -> Result<Vec<String>, String> {
+        let mut values = Vec::new();
+        if self.open('[', 0)? {
+            loop {
+                values.push(self.string()?);
+                if !self.separator(']')? {
+                    break;
+                }
+            }
+        }
+        Ok(values)
+    }
+}
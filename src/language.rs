@@ -0,0 +1,640 @@
+// Lightweight language identification
+// Many tokenization rules depend on the language: the abbreviations that don't end a
+// sentence, the elided articles of French and Italian ("l'homme", "dell'anno"). The language
+// of a text is guessed in two steps:
+// - the writing system of its letters settles it for the languages with a script of their
+//   own (Greek, Korean, Thai, ...), with Kana telling Japanese from Chinese,
+// - Latin and Cyrillic texts are compared with a character n-gram profile of every language
+//   written in that script (the 300 most frequent 1- to 3-grams of a sample text, the
+//   method of Cavnar and Trenkle, 1994): the closest ranking wins.
+// This needs no model file and works from a sentence on. Short or mixed texts can be
+// misjudged, so callers get the scores too.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::OnceLock;
+
+use crate::manifest::ManifestStep;
+use crate::metrics::Script;
+use crate::token::{Token, TokenKind};
+use crate::token_filter::TokenFilter;
+
+/// Number of n-grams kept in a profile
+const PROFILE_SIZE: usize = 300;
+
+/// A language detect_language() can recognize
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Language {
+    /// English
+    English,
+    /// French
+    French,
+    /// German
+    German,
+    /// Spanish
+    Spanish,
+    /// Italian
+    Italian,
+    /// Portuguese
+    Portuguese,
+    /// Dutch
+    Dutch,
+    /// Swedish
+    Swedish,
+    /// Danish
+    Danish,
+    /// Finnish
+    Finnish,
+    /// Polish
+    Polish,
+    /// Czech
+    Czech,
+    /// Romanian
+    Romanian,
+    /// Hungarian
+    Hungarian,
+    /// Turkish
+    Turkish,
+    /// Indonesian
+    Indonesian,
+    /// Russian
+    Russian,
+    /// Ukrainian
+    Ukrainian,
+    /// Greek
+    Greek,
+    /// Arabic (any text in Arabic script)
+    Arabic,
+    /// Hebrew
+    Hebrew,
+    /// Hindi (any text in Devanagari)
+    Hindi,
+    /// Thai
+    Thai,
+    /// Korean
+    Korean,
+    /// Japanese (Han characters with Kana)
+    Japanese,
+    /// Chinese (Han characters without Kana)
+    Chinese,
+}
+
+impl Language {
+    /// Every language, in the order of the enum
+    pub const ALL: [Language; 26] = [
+        Language::English,
+        Language::French,
+        Language::German,
+        Language::Spanish,
+        Language::Italian,
+        Language::Portuguese,
+        Language::Dutch,
+        Language::Swedish,
+        Language::Danish,
+        Language::Finnish,
+        Language::Polish,
+        Language::Czech,
+        Language::Romanian,
+        Language::Hungarian,
+        Language::Turkish,
+        Language::Indonesian,
+        Language::Russian,
+        Language::Ukrainian,
+        Language::Greek,
+        Language::Arabic,
+        Language::Hebrew,
+        Language::Hindi,
+        Language::Thai,
+        Language::Korean,
+        Language::Japanese,
+        Language::Chinese,
+    ];
+
+    /// Returns the ISO 639-1 code of the language ("en", "fr", ...)
+    pub fn code(&self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::French => "fr",
+            Language::German => "de",
+            Language::Spanish => "es",
+            Language::Italian => "it",
+            Language::Portuguese => "pt",
+            Language::Dutch => "nl",
+            Language::Swedish => "sv",
+            Language::Danish => "da",
+            Language::Finnish => "fi",
+            Language::Polish => "pl",
+            Language::Czech => "cs",
+            Language::Romanian => "ro",
+            Language::Hungarian => "hu",
+            Language::Turkish => "tr",
+            Language::Indonesian => "id",
+            Language::Russian => "ru",
+            Language::Ukrainian => "uk",
+            Language::Greek => "el",
+            Language::Arabic => "ar",
+            Language::Hebrew => "he",
+            Language::Hindi => "hi",
+            Language::Thai => "th",
+            Language::Korean => "ko",
+            Language::Japanese => "ja",
+            Language::Chinese => "zh",
+        }
+    }
+
+    /// Finds a language by its ISO 639-1 code, in any case
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::language::Language;
+    ///
+    /// assert_eq!(Language::from_code("DE"), Some(Language::German));
+    /// assert_eq!(Language::from_code("xx"), None);
+    /// ```
+    pub fn from_code(code: &str) -> Option<Language> {
+        Language::ALL
+            .into_iter()
+            .find(|language| language.code().eq_ignore_ascii_case(code.trim()))
+    }
+
+    /// Returns the script the language is written in (Han for Japanese, with Kana)
+    pub fn script(&self) -> Script {
+        match self {
+            Language::Russian | Language::Ukrainian => Script::Cyrillic,
+            Language::Greek => Script::Greek,
+            Language::Arabic => Script::Arabic,
+            Language::Hebrew => Script::Hebrew,
+            Language::Hindi => Script::Devanagari,
+            Language::Thai => Script::Thai,
+            Language::Korean => Script::Hangul,
+            Language::Japanese | Language::Chinese => Script::Han,
+            _ => Script::Latin,
+        }
+    }
+
+    /// Returns the abbreviations of the language that don't end a sentence (lowercase,
+    /// without the final period), for SentenceSplitter::for_language()
+    ///
+    /// Languages without a list of their own share the English one of SentenceSplitter.
+    pub fn abbreviations(&self) -> &'static [&'static str] {
+        match self {
+            Language::French => &[
+                "m", "mm", "mme", "mmes", "mlle", "dr", "pr", "me", "st", "ste", "cf", "etc",
+                "env", "p", "pp", "art", "av", "bd", "chap", "n°", "tél", "vol", "éd", "janv",
+                "févr", "avr", "juil", "sept", "oct", "nov", "déc",
+            ],
+            Language::German => &[
+                "hr", "hrn", "fr", "dr", "prof", "str", "nr", "bzw", "ca", "vgl", "usw", "z.b",
+                "d.h", "u.a", "s", "bd", "abs", "evtl", "ggf", "inkl", "zzgl", "geb", "jan", "feb",
+                "febr", "jun", "jul", "aug", "sep", "sept", "okt", "nov", "dez",
+            ],
+            Language::Spanish => &[
+                "sr", "sra", "srta", "dr", "dra", "ud", "uds", "d", "dña", "etc", "pág", "núm",
+                "art", "av", "avda", "tel", "ej", "p.ej", "aprox", "ene", "feb", "abr", "jun",
+                "jul", "ago", "sept", "oct", "nov", "dic",
+            ],
+            Language::Italian => &[
+                "sig", "sigg", "sig.ra", "dott", "dott.ssa", "prof", "ing", "avv", "on", "ecc",
+                "pag", "art", "n", "tel", "es", "ca", "gen", "feb", "apr", "giu", "lug", "ago",
+                "sett", "ott", "nov", "dic",
+            ],
+            Language::Portuguese => &[
+                "sr", "sra", "srta", "dr", "dra", "prof", "av", "pág", "art", "n", "nº", "tel",
+                "etc", "ex", "aprox", "jan", "fev", "abr", "mai", "jun", "jul", "ago", "set",
+                "out", "nov", "dez",
+            ],
+            Language::Dutch => &[
+                "dhr", "mevr", "mw", "dr", "prof", "ir", "drs", "mr", "nr", "blz", "bijv", "bv",
+                "enz", "ca", "d.w.z", "o.a", "m.b.t", "jan", "feb", "apr", "jun", "jul", "aug",
+                "sep", "okt", "nov", "dec",
+            ],
+            _ => &[],
+        }
+    }
+
+    /// Returns the elided words of the language, with their apostrophe ("l'", "qu'"):
+    /// ElisionFilter splits them from the word they are glued to
+    pub fn elisions(&self) -> &'static [&'static str] {
+        match self {
+            Language::French => &[
+                "l'", "d'", "j'", "m'", "n'", "s'", "t'", "c'", "qu'", "jusqu'", "lorsqu'",
+                "puisqu'", "quoiqu'",
+            ],
+            Language::Italian => &[
+                "l'", "un'", "d'", "c'", "v'", "s'", "m'", "t'", "dell'", "dall'", "nell'",
+                "sull'", "all'", "quest'", "quell'", "anch'", "com'", "dov'",
+            ],
+            _ => &[],
+        }
+    }
+}
+
+/// Guesses the language of a text
+///
+/// # Returns
+/// The most likely language, or None if the text has no letters
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::language::{detect_language, Language};
+///
+/// assert_eq!(detect_language("The quick brown fox jumps over the lazy dog."), Some(Language::English));
+/// assert_eq!(detect_language("Où est la gare ? Je voudrais acheter un billet."), Some(Language::French));
+/// assert_eq!(detect_language("Ich habe heute keine Zeit, weil ich arbeiten muss."), Some(Language::German));
+/// assert_eq!(detect_language("Мы пошли домой после работы."), Some(Language::Russian));
+/// assert_eq!(detect_language("東京に行きたいです。"), Some(Language::Japanese));
+/// assert_eq!(detect_language("1234 !?"), None);
+/// ```
+pub fn detect_language(text: &str) -> Option<Language> {
+    language_scores(text).first().map(|&(language, _)| language)
+}
+
+/// Scores the languages a text could be in, best first
+///
+/// Only the languages written in the script of the text are scored. A score is between 0
+/// and 1: 1 is a text ranking its n-grams exactly like the profile of the language. The
+/// languages identified by their script alone get a score of 1.
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::language::{language_scores, Language};
+///
+/// let scores = language_scores("Questa è una frase scritta in italiano.");
+/// assert_eq!(scores[0].0, Language::Italian);
+/// assert!(scores[0].1 > scores[1].1);
+/// ```
+pub fn language_scores(text: &str) -> Vec<(Language, f64)> {
+    // STEP 1: Find the script most letters are written in
+    let mut scripts: BTreeMap<Script, usize> = BTreeMap::new();
+    for character in text.chars() {
+        let script = Script::of(character);
+        if script != Script::Common {
+            *scripts.entry(script).or_insert(0) += 1;
+        }
+    }
+    // On a tie the first script in the enum order wins, so the result is deterministic
+    let Some((&script, _)) = scripts.iter().rev().max_by_key(|&(_, &count)| count) else {
+        return Vec::new();
+    };
+
+    // STEP 2: The scripts of a single language decide on their own
+    let only = match script {
+        // Japanese mixes Han with Kana, Chinese has no Kana
+        Script::Han | Script::Kana if scripts.contains_key(&Script::Kana) => Language::Japanese,
+        Script::Han => Language::Chinese,
+        Script::Hangul => Language::Korean,
+        Script::Greek => Language::Greek,
+        Script::Arabic => Language::Arabic,
+        Script::Hebrew => Language::Hebrew,
+        Script::Devanagari => Language::Hindi,
+        Script::Thai => Language::Thai,
+        Script::Latin | Script::Cyrillic => return profile_scores(text, script),
+        _ => return Vec::new(),
+    };
+    vec![(only, 1.0)]
+}
+
+/// Scores the languages of one script by comparing n-gram rankings (Cavnar and Trenkle)
+fn profile_scores(text: &str, script: Script) -> Vec<(Language, f64)> {
+    let document = ngram_ranks(text);
+    if document.is_empty() {
+        return Vec::new();
+    }
+
+    // Every n-gram adds how far its rank is from its rank in the language profile
+    // (the maximum when the language doesn't have it)
+    let worst = (document.len() * PROFILE_SIZE) as f64;
+    let mut scores: Vec<(Language, f64)> = profiles()
+        .iter()
+        .filter(|(language, _)| language.script() == script)
+        .map(|(language, profile)| {
+            let distance: usize = document
+                .iter()
+                .map(|(ngram, &rank)| {
+                    profile.get(ngram).map_or(PROFILE_SIZE, |&other| {
+                        rank.abs_diff(other).min(PROFILE_SIZE)
+                    })
+                })
+                .sum();
+            (*language, 1.0 - distance as f64 / worst)
+        })
+        .collect();
+    scores.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    scores
+}
+
+/// Returns the rank of the PROFILE_SIZE most frequent 1- to 3-grams of a text
+///
+/// Words are lowercased and padded with a space on both sides, so "_th" and "he_" tell
+/// where words start and end. Ties are ranked in lexicographic order.
+fn ngram_ranks(text: &str) -> HashMap<String, usize> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    let lowercase = text.to_lowercase();
+    for word in lowercase.split(|c: char| !c.is_alphabetic()) {
+        if word.is_empty() {
+            continue;
+        }
+        let padded: Vec<char> = std::iter::once(' ')
+            .chain(word.chars())
+            .chain(std::iter::once(' '))
+            .collect();
+        for n in 1..=3 {
+            for ngram in padded.windows(n) {
+                // A lone space says nothing about the language
+                if n == 1 && ngram[0] == ' ' {
+                    continue;
+                }
+                *counts.entry(ngram.iter().collect()).or_insert(0) += 1;
+            }
+        }
+    }
+
+    let mut ranked: Vec<(String, usize)> = counts.into_iter().collect();
+    ranked.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    ranked
+        .into_iter()
+        .take(PROFILE_SIZE)
+        .enumerate()
+        .map(|(rank, (ngram, _))| (ngram, rank))
+        .collect()
+}
+
+/// The n-gram profiles of the Latin and Cyrillic languages, built once from SAMPLES
+fn profiles() -> &'static [(Language, HashMap<String, usize>)] {
+    static PROFILES: OnceLock<Vec<(Language, HashMap<String, usize>)>> = OnceLock::new();
+    PROFILES.get_or_init(|| {
+        SAMPLES
+            .iter()
+            .map(|&(language, sample)| (language, ngram_ranks(sample)))
+            .collect()
+    })
+}
+
+/// Splits elided words from the word they are glued to ("l'homme" -> "l'" + "homme")
+///
+/// The tokenizer keeps apostrophes inside words, which is right for "don't" but leaves
+/// French and Italian articles attached to every noun. Both parts keep their offsets in
+/// the original text.
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::Tokenizer;
+/// use tokenizer_rust::language::{detect_language, ElisionFilter};
+/// use tokenizer_rust::token_filter::TokenFilter;
+///
+/// let text = "L'homme qu'il a vu";
+/// let language = detect_language(text).unwrap();
+/// let tokens = ElisionFilter::new(language).apply(Tokenizer::new(String::new()).tokenize_text(text));
+/// let texts: Vec<&str> = tokens.iter().map(|token| token.text.as_str()).collect();
+/// assert_eq!(texts, vec!["L'", "homme", "qu'", "il", "a", "vu"]);
+/// assert_eq!(&text[tokens[1].start..tokens[1].end], "homme");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ElisionFilter {
+    /// The language whose elisions are split
+    language: Language,
+}
+
+impl ElisionFilter {
+    /// Creates a filter for the elisions of a language (it changes nothing for languages
+    /// without elisions)
+    pub fn new(language: Language) -> Self {
+        ElisionFilter { language }
+    }
+
+    /// Returns the length in bytes of the elided word at the start of a word, if any
+    fn elision_length(&self, word: &str) -> Option<usize> {
+        let (apostrophe, _) = word.char_indices().find(|&(_, c)| c == '\'')?;
+        let prefix = &word[..=apostrophe];
+        let elided = self
+            .language
+            .elisions()
+            .iter()
+            .any(|elision| elision.eq_ignore_ascii_case(prefix));
+        // Something must follow the apostrophe ("l'" alone stays as it is)
+        (elided && prefix.len() < word.len()).then_some(prefix.len())
+    }
+
+    /// Rebuilds a filter from the step written by describe()
+    pub(crate) fn from_step(step: &ManifestStep) -> Option<Self> {
+        Some(ElisionFilter::new(Language::from_code(
+            &step.value("language")?,
+        )?))
+    }
+}
+
+impl TokenFilter for ElisionFilter {
+    fn apply(&self, tokens: Vec<Token>) -> Vec<Token> {
+        let mut result = Vec::with_capacity(tokens.len());
+        for token in tokens {
+            let length = match token.kind {
+                TokenKind::Word => self.elision_length(&token.text),
+                _ => None,
+            };
+            let Some(length) = length else {
+                result.push(token);
+                continue;
+            };
+            let (elided, rest) = token.text.split_at(length);
+            result.push(Token {
+                text: elided.to_string(),
+                kind: TokenKind::Word,
+                start: token.start,
+                end: token.start + length,
+            });
+            result.push(Token {
+                text: rest.to_string(),
+                kind: TokenKind::of(rest),
+                start: token.start + length,
+                end: token.end,
+            });
+        }
+        result
+    }
+
+    fn describe(&self) -> Option<ManifestStep> {
+        let mut step = ManifestStep::new("elision");
+        step.push("language", self.language.code());
+        Some(step)
+    }
+}
+
+/// The sample texts the n-gram profiles are built from (the first article of the Universal
+/// Declaration of Human Rights, followed by everyday sentences)
+const SAMPLES: &[(Language, &str)] = &[
+    (
+        Language::English,
+        "All human beings are born free and equal in dignity and rights. They are endowed \
+         with reason and conscience and should act towards one another in a spirit of \
+         brotherhood. The weather was nice this morning, so we walked to the market and \
+         bought some bread, cheese and fruit for the children. What do you think about the \
+         new book that she wrote last year? It is one of the best stories I have ever read, \
+         and I would like to read it again. There is nothing which would be more useful than \
+         this, when the people of the town have their own things to do.",
+    ),
+    (
+        Language::French,
+        "Tous les êtres humains naissent libres et égaux en dignité et en droits. Ils sont \
+         doués de raison et de conscience et doivent agir les uns envers les autres dans un \
+         esprit de fraternité. Il faisait beau ce matin, alors nous sommes allés au marché \
+         pour acheter du pain, du fromage et des fruits pour les enfants. Qu'est-ce que tu \
+         penses du nouveau livre qu'elle a écrit l'année dernière ? C'est l'une des \
+         meilleures histoires que j'ai jamais lues, et je voudrais la relire. Il n'y a rien \
+         de plus utile que cela, quand les gens de la ville ont leurs propres choses à faire.",
+    ),
+    (
+        Language::German,
+        "Alle Menschen sind frei und gleich an Würde und Rechten geboren. Sie sind mit \
+         Vernunft und Gewissen begabt und sollen einander im Geist der Brüderlichkeit \
+         begegnen. Heute Morgen war das Wetter schön, deshalb sind wir zum Markt gegangen \
+         und haben Brot, Käse und Obst für die Kinder gekauft. Was hältst du von dem neuen \
+         Buch, das sie letztes Jahr geschrieben hat? Es ist eine der besten Geschichten, die \
+         ich je gelesen habe, und ich möchte es noch einmal lesen. Es gibt nichts, was \
+         nützlicher wäre, wenn die Leute in der Stadt ihre eigenen Sachen zu tun haben.",
+    ),
+    (
+        Language::Spanish,
+        "Todos los seres humanos nacen libres e iguales en dignidad y derechos y, dotados \
+         como están de razón y conciencia, deben comportarse fraternalmente los unos con los \
+         otros. Esta mañana hacía buen tiempo, así que fuimos al mercado y compramos pan, \
+         queso y fruta para los niños. ¿Qué piensas del nuevo libro que ella escribió el año \
+         pasado? Es una de las mejores historias que he leído nunca, y me gustaría leerlo \
+         otra vez. No hay nada que sea más útil que esto, cuando la gente de la ciudad tiene \
+         sus propias cosas que hacer.",
+    ),
+    (
+        Language::Italian,
+        "Tutti gli esseri umani nascono liberi ed eguali in dignità e diritti. Essi sono \
+         dotati di ragione e di coscienza e devono agire gli uni verso gli altri in spirito \
+         di fratellanza. Stamattina faceva bel tempo, quindi siamo andati al mercato e \
+         abbiamo comprato pane, formaggio e frutta per i bambini. Che cosa pensi del nuovo \
+         libro che lei ha scritto l'anno scorso? È una delle storie più belle che abbia mai \
+         letto, e vorrei leggerlo di nuovo. Non c'è niente che sia più utile di questo, \
+         quando la gente della città ha le proprie cose da fare.",
+    ),
+    (
+        Language::Portuguese,
+        "Todos os seres humanos nascem livres e iguais em dignidade e em direitos. Dotados \
+         de razão e de consciência, devem agir uns para com os outros em espírito de \
+         fraternidade. Hoje de manhã o tempo estava bom, então fomos ao mercado e compramos \
+         pão, queijo e frutas para as crianças. O que você acha do novo livro que ela \
+         escreveu no ano passado? É uma das melhores histórias que já li, e eu gostaria de \
+         lê-lo outra vez. Não há nada que seja mais útil do que isso, quando as pessoas da \
+         cidade têm as suas próprias coisas para fazer.",
+    ),
+    (
+        Language::Dutch,
+        "Alle mensen worden vrij en gelijk in waardigheid en rechten geboren. Zij zijn \
+         begiftigd met verstand en geweten, en behoren zich jegens elkander in een geest van \
+         broederschap te gedragen. Vanochtend was het mooi weer, dus zijn we naar de markt \
+         gelopen en hebben we brood, kaas en fruit voor de kinderen gekocht. Wat vind je van \
+         het nieuwe boek dat zij vorig jaar heeft geschreven? Het is een van de beste \
+         verhalen die ik ooit heb gelezen, en ik zou het graag nog een keer lezen. Er is \
+         niets dat nuttiger zou zijn, als de mensen in de stad hun eigen dingen te doen hebben.",
+    ),
+    (
+        Language::Swedish,
+        "Alla människor är födda fria och lika i värde och rättigheter. De har utrustats med \
+         förnuft och samvete och bör handla gentemot varandra i en anda av broderskap. I \
+         morse var vädret fint, så vi gick till torget och köpte bröd, ost och frukt till \
+         barnen. Vad tycker du om den nya boken som hon skrev förra året? Det är en av de \
+         bästa berättelser jag någonsin har läst, och jag skulle vilja läsa den igen. Det \
+         finns ingenting som vore mer användbart, när människorna i staden har sina egna \
+         saker att göra.",
+    ),
+    (
+        Language::Danish,
+        "Alle mennesker er født frie og lige i værdighed og rettigheder. De er udstyret med \
+         fornuft og samvittighed, og de bør handle mod hverandre i en broderskabets ånd. I \
+         morges var vejret godt, så vi gik ned til torvet og købte brød, ost og frugt til \
+         børnene. Hvad synes du om den nye bog, som hun skrev sidste år? Det er en af de \
+         bedste historier, jeg nogensinde har læst, og jeg vil gerne læse den igen. Der er \
+         ikke noget, som ville være mere nyttigt, når folk i byen har deres egne ting at lave.",
+    ),
+    (
+        Language::Finnish,
+        "Kaikki ihmiset syntyvät vapaina ja tasavertaisina arvoltaan ja oikeuksiltaan. \
+         Heille on annettu järki ja omatunto, ja heidän on toimittava toisiaan kohtaan \
+         veljeyden hengessä. Tänä aamuna sää oli kaunis, joten kävelimme torille ja ostimme \
+         leipää, juustoa ja hedelmiä lapsille. Mitä mieltä olet uudesta kirjasta, jonka hän \
+         kirjoitti viime vuonna? Se on yksi parhaista tarinoista, joita olen koskaan \
+         lukenut, ja haluaisin lukea sen uudelleen. Mikään ei olisi hyödyllisempää, kun \
+         kaupungin ihmisillä on omat asiansa tehtävänä.",
+    ),
+    (
+        Language::Polish,
+        "Wszyscy ludzie rodzą się wolni i równi pod względem swej godności i swych praw. Są \
+         oni obdarzeni rozumem i sumieniem i powinni postępować wobec innych w duchu \
+         braterstwa. Dziś rano była ładna pogoda, więc poszliśmy na targ i kupiliśmy chleb, \
+         ser i owoce dla dzieci. Co myślisz o nowej książce, którą ona napisała w zeszłym \
+         roku? To jedna z najlepszych historii, jakie kiedykolwiek czytałem, i chciałbym \
+         przeczytać ją jeszcze raz. Nie ma nic bardziej przydatnego, kiedy ludzie w mieście \
+         mają swoje własne sprawy do załatwienia.",
+    ),
+    (
+        Language::Czech,
+        "Všichni lidé rodí se svobodní a sobě rovní co do důstojnosti a práv. Jsou nadáni \
+         rozumem a svědomím a mají spolu jednat v duchu bratrství. Dnes ráno bylo hezké \
+         počasí, tak jsme šli na trh a koupili jsme chléb, sýr a ovoce pro děti. Co si \
+         myslíš o nové knize, kterou loni napsala? Je to jeden z nejlepších příběhů, jaké \
+         jsem kdy četl, a rád bych si ho přečetl znovu. Není nic užitečnějšího, když lidé ve \
+         městě mají své vlastní věci na práci.",
+    ),
+    (
+        Language::Romanian,
+        "Toate ființele umane se nasc libere și egale în demnitate și în drepturi. Ele sunt \
+         înzestrate cu rațiune și conștiință și trebuie să se comporte unele față de altele \
+         în spiritul fraternității. În această dimineață vremea a fost frumoasă, așa că am \
+         mers la piață și am cumpărat pâine, brânză și fructe pentru copii. Ce crezi despre \
+         noua carte pe care a scris-o anul trecut? Este una dintre cele mai bune povești pe \
+         care le-am citit vreodată și aș vrea să o citesc din nou. Nu există nimic mai util, \
+         când oamenii din oraș au propriile lor lucruri de făcut.",
+    ),
+    (
+        Language::Hungarian,
+        "Minden emberi lény szabadon születik és egyenlő méltósága és joga van. Az emberek, \
+         ésszel és lelkiismerettel bírván, egymással szemben testvéri szellemben kell hogy \
+         viseltessenek. Ma reggel szép idő volt, ezért elmentünk a piacra, és kenyeret, \
+         sajtot és gyümölcsöt vettünk a gyerekeknek. Mit gondolsz az új könyvről, amelyet \
+         tavaly írt? Ez az egyik legjobb történet, amelyet valaha olvastam, és szeretném \
+         újra elolvasni. Nincs ennél hasznosabb dolog, amikor a város lakóinak megvannak a \
+         saját tennivalóik.",
+    ),
+    (
+        Language::Turkish,
+        "Bütün insanlar hür, haysiyet ve haklar bakımından eşit doğarlar. Akıl ve vicdana \
+         sahiptirler ve birbirlerine karşı kardeşlik zihniyeti ile hareket etmelidirler. Bu \
+         sabah hava güzeldi, bu yüzden pazara yürüdük ve çocuklar için ekmek, peynir ve \
+         meyve aldık. Geçen yıl yazdığı yeni kitap hakkında ne düşünüyorsun? Şimdiye kadar \
+         okuduğum en iyi hikâyelerden biri ve onu tekrar okumak istiyorum. Şehirdeki \
+         insanların kendi yapacak işleri olduğunda, bundan daha yararlı bir şey yoktur.",
+    ),
+    (
+        Language::Indonesian,
+        "Semua orang dilahirkan merdeka dan mempunyai martabat dan hak-hak yang sama. Mereka \
+         dikaruniai akal dan hati nurani dan hendaknya bergaul satu sama lain dalam semangat \
+         persaudaraan. Pagi ini cuacanya cerah, jadi kami berjalan ke pasar dan membeli \
+         roti, keju, dan buah untuk anak-anak. Apa pendapatmu tentang buku baru yang dia \
+         tulis tahun lalu? Itu adalah salah satu cerita terbaik yang pernah saya baca, dan \
+         saya ingin membacanya lagi. Tidak ada yang lebih berguna daripada ini, ketika orang \
+         di kota itu mempunyai urusan mereka sendiri.",
+    ),
+    (
+        Language::Russian,
+        "Все люди рождаются свободными и равными в своем достоинстве и правах. Они наделены \
+         разумом и совестью и должны поступать в отношении друг друга в духе братства. \
+         Сегодня утром была хорошая погода, поэтому мы пошли на рынок и купили хлеб, сыр и \
+         фрукты для детей. Что ты думаешь о новой книге, которую она написала в прошлом \
+         году? Это одна из лучших историй, которые я когда-либо читал, и я хотел бы \
+         прочитать её ещё раз. Нет ничего более полезного, когда у людей в городе есть свои \
+         собственные дела.",
+    ),
+    (
+        Language::Ukrainian,
+        "Усі люди народжуються вільними і рівними у своїй гідності та правах. Вони наділені \
+         розумом і совістю і повинні діяти у відношенні один до одного в дусі братерства. \
+         Сьогодні вранці була гарна погода, тому ми пішли на ринок і купили хліб, сир і \
+         фрукти для дітей. Що ти думаєш про нову книжку, яку вона написала минулого року? \
+         Це одна з найкращих історій, які я коли-небудь читав, і я хотів би прочитати її ще \
+         раз. Немає нічого кориснішого, коли люди в місті мають свої власні справи.",
+    ),
+];
@@ -0,0 +1,289 @@
+// Dictionary-based lemmatization
+// A lemmatizer maps inflected forms to their dictionary form ("went" -> "go", "mice" ->
+// "mouse"), which a stemmer can't do since it only strips suffixes. The forms come from a
+// lemma dictionary, a TSV file with one "form<TAB>lemma" line per form and an optional
+// third column with a part-of-speech tag, for forms whose lemma depends on it ("saw" is
+// "see" as a verb and "saw" as a noun).
+// As a TokenFilter, the Lemmatizer replaces the text of Word tokens by their lemma and keeps
+// their offsets. lemmatize() and lemmatize_tagged() keep the original tokens next to their
+// lemmas instead.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+use crate::manifest::{escape, ManifestStep};
+use crate::token::{Token, TokenKind};
+use crate::token_filter::TokenFilter;
+
+/// A token with its lemma
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LemmaToken {
+    /// The original token (text, kind and offsets unchanged)
+    pub token: Token,
+    /// The lemma of the token (its text if the dictionary does not know it)
+    pub lemma: String,
+    /// true if the lemma comes from the dictionary
+    pub found: bool,
+}
+
+/// Looks up the lemmas of word forms in a dictionary
+///
+/// Forms are compared without case ("Went" finds the entry of "went") unless the
+/// lemmatizer is case-sensitive.
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::Tokenizer;
+/// use tokenizer_rust::lemma::Lemmatizer;
+/// use tokenizer_rust::token_filter::TokenFilter;
+///
+/// let dictionary = "went\tgo\nmice\tmouse\nsaw\tsee\tVERB\nsaw\tsaw\tNOUN\n";
+/// let lemmatizer = Lemmatizer::new().read_from(dictionary.as_bytes()).unwrap();
+///
+/// let text = "Mice went home";
+/// let tokens = lemmatizer.apply(Tokenizer::new(String::new()).tokenize_text(text));
+/// let lemmas: Vec<&str> = tokens.iter().map(|token| token.text.as_str()).collect();
+/// assert_eq!(lemmas, vec!["mouse", "go", "home"]);
+/// assert_eq!(&text[tokens[0].start..tokens[0].end], "Mice");
+///
+/// // The part of speech picks between the entries of a form
+/// assert_eq!(lemmatizer.lemma("saw", Some("VERB")), Some("see"));
+/// assert_eq!(lemmatizer.lemma("saw", Some("NOUN")), Some("saw"));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Lemmatizer {
+    /// The entries of every form: (part of speech, lemma), in the order they were added
+    entries: HashMap<String, Vec<(Option<String>, String)>>,
+    /// If true, forms are only found as written (otherwise they are stored lowercased)
+    case_sensitive: bool,
+}
+
+impl Lemmatizer {
+    /// Creates a lemmatizer with an empty dictionary
+    pub fn new() -> Self {
+        Lemmatizer::default()
+    }
+
+    /// Sets whether forms are only found as written (false by default)
+    ///
+    /// Set this before adding entries: they are stored lowercased when case is ignored.
+    pub fn with_case_sensitive(mut self, case_sensitive: bool) -> Self {
+        self.case_sensitive = case_sensitive;
+        self
+    }
+
+    /// Adds the lemma of a form, for any part of speech
+    pub fn with_entry(mut self, form: &str, lemma: &str) -> Self {
+        self.add(form, None, lemma);
+        self
+    }
+
+    /// Adds the lemma of a form used as one part of speech
+    pub fn with_tagged_entry(mut self, form: &str, pos: &str, lemma: &str) -> Self {
+        self.add(form, Some(pos), lemma);
+        self
+    }
+
+    /// Adds the entries of a lemma dictionary
+    ///
+    /// Every line is "form<TAB>lemma" or "form<TAB>lemma<TAB>POS". Blank lines and lines
+    /// starting with "#" are ignored.
+    ///
+    /// # Arguments
+    /// * `reader` - Where to read the dictionary from
+    ///
+    /// # Returns
+    /// The lemmatizer, or an `InvalidData` error naming the first line without a lemma
+    pub fn read_from<R: BufRead>(mut self, reader: R) -> io::Result<Self> {
+        for (number, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.split('\t').map(str::trim);
+            match (fields.next(), fields.next(), fields.next()) {
+                (Some(form), Some(lemma), pos) if !form.is_empty() && !lemma.is_empty() => {
+                    self.add(form, pos.filter(|pos| !pos.is_empty()), lemma);
+                }
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("line {}: expected form<TAB>lemma[<TAB>POS]", number + 1),
+                    ))
+                }
+            }
+        }
+        Ok(self)
+    }
+
+    /// Adds the entries of a lemma dictionary file (see read_from() for the format)
+    pub fn load<P: AsRef<Path>>(self, path: P) -> io::Result<Self> {
+        self.read_from(BufReader::new(File::open(path)?))
+    }
+
+    /// Returns the number of distinct forms in the dictionary
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if the dictionary is empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the lemma of a form
+    ///
+    /// With a part of speech, the entry for that tag is used if there is one. Otherwise
+    /// (or without a tag) the entry without tag is used, and else the first one added.
+    ///
+    /// # Returns
+    /// The lemma, or None if the form is not in the dictionary
+    pub fn lemma(&self, form: &str, pos: Option<&str>) -> Option<&str> {
+        let entries = if self.case_sensitive {
+            self.entries.get(form)
+        } else {
+            self.entries.get(&form.to_lowercase())
+        }?;
+
+        let tagged =
+            pos.and_then(|pos| entries.iter().find(|(tag, _)| tag.as_deref() == Some(pos)));
+        let (_, lemma) = tagged
+            .or_else(|| entries.iter().find(|(tag, _)| tag.is_none()))
+            .or_else(|| entries.first())?;
+        Some(lemma)
+    }
+
+    /// Returns the lemma of every token, next to the original token
+    ///
+    /// Only Word tokens are looked up; the lemma of the others is their text.
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::lemma::Lemmatizer;
+    ///
+    /// let lemmatizer = Lemmatizer::new().with_entry("mice", "mouse");
+    /// let tokens = Tokenizer::new(String::new()).tokenize_text("Three mice");
+    /// let lemmas = lemmatizer.lemmatize(&tokens);
+    /// assert_eq!(lemmas[1].lemma, "mouse");
+    /// assert_eq!(lemmas[1].token.text, "mice");
+    /// assert!(lemmas[1].found && !lemmas[0].found);
+    /// ```
+    pub fn lemmatize(&self, tokens: &[Token]) -> Vec<LemmaToken> {
+        self.lemmatize_with(tokens, |_| None)
+    }
+
+    /// Returns the lemma of every token, using the part of speech of every token
+    ///
+    /// # Arguments
+    /// * `tokens` - The tokens
+    /// * `tags` - The part of speech of every token (from a tagger)
+    ///
+    /// # Panics
+    /// If there are not as many tags as tokens
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::lemma::Lemmatizer;
+    ///
+    /// let lemmatizer = Lemmatizer::new()
+    ///     .with_tagged_entry("saw", "VERB", "see")
+    ///     .with_tagged_entry("saw", "NOUN", "saw");
+    /// let tokens = Tokenizer::new(String::new()).tokenize_text("I saw a saw");
+    /// let lemmas = lemmatizer.lemmatize_tagged(&tokens, &["PRON", "VERB", "DET", "NOUN"]);
+    /// assert_eq!(lemmas[1].lemma, "see");
+    /// assert_eq!(lemmas[3].lemma, "saw");
+    /// ```
+    pub fn lemmatize_tagged<S: AsRef<str>>(&self, tokens: &[Token], tags: &[S]) -> Vec<LemmaToken> {
+        assert_eq!(tags.len(), tokens.len(), "one tag per token is needed");
+        self.lemmatize_with(tokens, |index| Some(tags[index].as_ref()))
+    }
+
+    /// Returns the lemma of every token, with the part of speech given by `pos` for each index
+    fn lemmatize_with<'t, F>(&self, tokens: &[Token], pos: F) -> Vec<LemmaToken>
+    where
+        F: Fn(usize) -> Option<&'t str>,
+    {
+        tokens
+            .iter()
+            .enumerate()
+            .map(|(index, token)| {
+                let lemma = if token.kind == TokenKind::Word {
+                    self.lemma(&token.text, pos(index))
+                } else {
+                    None
+                };
+                LemmaToken {
+                    token: token.clone(),
+                    lemma: lemma.unwrap_or(&token.text).to_string(),
+                    found: lemma.is_some(),
+                }
+            })
+            .collect()
+    }
+
+    /// Rebuilds a lemmatizer from the step written by describe()
+    pub(crate) fn from_step(step: &ManifestStep) -> Option<Self> {
+        let case_sensitive = step.value("case_sensitive")?.parse().ok()?;
+        let mut lemmatizer = Lemmatizer::new().with_case_sensitive(case_sensitive);
+        let (mut form, mut pos) = (None, None);
+        for (key, value) in step.entries() {
+            match key {
+                "form" => form = Some(value),
+                "pos" => pos = Some(value),
+                "lemma" => lemmatizer.add(&form.take()?, pos.take().as_deref(), &value),
+                _ => {}
+            }
+        }
+        Some(lemmatizer)
+    }
+
+    /// Adds one entry (forms are stored lowercased unless case-sensitive)
+    fn add(&mut self, form: &str, pos: Option<&str>, lemma: &str) {
+        let form = if self.case_sensitive {
+            form.to_string()
+        } else {
+            form.to_lowercase()
+        };
+        self.entries
+            .entry(form)
+            .or_default()
+            .push((pos.map(str::to_string), lemma.to_string()));
+    }
+}
+
+impl TokenFilter for Lemmatizer {
+    fn apply(&self, mut tokens: Vec<Token>) -> Vec<Token> {
+        for token in &mut tokens {
+            if token.kind != TokenKind::Word {
+                continue;
+            }
+            if let Some(lemma) = self.lemma(&token.text, None) {
+                token.text = lemma.to_string();
+            }
+        }
+        tokens
+    }
+
+    /// A "form", an optional "pos" and a "lemma" line per entry, the forms in sorted order
+    /// and the entries of a form in the order they were added
+    fn describe(&self) -> Option<ManifestStep> {
+        let mut step = ManifestStep::new("lemmatizer");
+        step.push("case_sensitive", self.case_sensitive);
+        let mut forms: Vec<&String> = self.entries.keys().collect();
+        forms.sort_unstable();
+        for form in forms {
+            for (pos, lemma) in &self.entries[form] {
+                step.push("form", escape(form));
+                if let Some(pos) = pos {
+                    step.push("pos", escape(pos));
+                }
+                step.push("lemma", escape(lemma));
+            }
+        }
+        Some(step)
+    }
+}
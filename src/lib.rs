@@ -0,0 +1,198 @@
+// This is the library root of the tokenizer crate
+// It declares every module so they can be used from main.rs and from other projects
+
+// The core tokenizer (tokenize / detokenize / statistics)
+pub mod tokenizer;
+
+// Streaming tokenization of readers that don't fit in memory
+pub mod stream;
+
+// Async streaming tokenization of tokio readers (with the `tokio` feature)
+#[cfg(feature = "tokio")]
+pub mod async_reader;
+
+// Tokenization of memory-mapped files (with the `mmap` feature, on by default)
+#[cfg(feature = "mmap")]
+pub mod mmap;
+
+// Feeding one tokenization pass to several consumers (stats, indexing, encoding)
+pub mod tee;
+
+// The Token type (text, kind and byte offsets), its borrowed version TokenRef (from
+// tokenize_borrowed() and memory-mapped files) and InternedToken
+pub mod token;
+
+// String interning, so repeated tokens share one allocation
+pub mod intern;
+
+// Tokenizing every file of a directory in parallel, with per-file error reporting
+pub mod corpus;
+
+// Paragraphs, lines and the LineBreak / ParagraphBreak structure tokens
+pub mod structure;
+
+// Splitting long documents into overlapping chunks that fit a token budget
+pub mod chunk;
+
+// Splitting text into sentences (abbreviation, initial and decimal aware)
+pub mod sentences;
+
+// Mapping character / byte spans of the text to token indices and back
+pub mod alignment;
+
+// Special tokens like [CLS] or <s> that are never split
+pub mod special;
+
+// Detection of order numbers, IBANs and other identifiers
+pub mod identifiers;
+
+// Masking of credit card numbers and IBANs (with checksum validation)
+pub mod pii;
+
+// Mapping tokens to numeric ids and back
+pub mod vocab;
+
+// Encoder settings (unknown-token policy, byte fallback, templates)
+pub mod encoder;
+
+// Truncation and padding options used when encoding
+pub mod encode_options;
+
+// BPE and WordPiece models learned from word counts, splitting unknown words into pieces
+pub mod subword;
+
+// The Encoding result (ids, tokens, offsets and masks)
+pub mod encoding;
+
+// Token-level diffs between two texts (insertions, deletions, substitutions with spans)
+pub mod diff;
+
+// Patches describing how a reconstruction differs from the original text
+pub mod patch;
+
+// Breaking text into display lines for subtitles and UI rendering
+pub mod subtitles;
+
+// Bidirectional text metadata (embedding levels and display order)
+pub mod bidi;
+
+// Filling {name} slots of text templates
+pub mod slots;
+
+// Token-level editing (keeping the original whitespace) and find-and-replace
+pub mod edit;
+
+// Reading the vocabularies of published models and comparing them with a local one
+pub mod external_vocab;
+
+// A positional inverted index over documents, with query-time synonyms
+pub mod search;
+
+// Byte ranges of query terms in documents, for marking search hits
+pub mod highlight;
+
+// Pipeline manifests with content hashes, to reproduce a Tokenizer setup from one file
+pub mod manifest;
+
+// Fertility and bytes-per-token of a vocabulary, overall and per script
+pub mod metrics;
+
+// Comparing the token statistics of two tokenizer configurations on one corpus
+pub mod compare;
+
+// Seeded reservoir sampling of documents or sentences from a corpus stream
+pub mod sample;
+
+// Keeping or dropping sentences by token count, alphabetic ratio, script, language or patterns
+pub mod filter;
+
+// Noise heuristics of sentences (punctuation, digit, uppercase and repeated-token ratios)
+pub mod quality;
+
+// N-grams and skip-grams of token sequences, and character n-grams of words
+pub mod ngrams;
+
+// Parallel corpora: aligned sentence pairs tokenized per side and filtered jointly
+pub mod bitext;
+
+// Inline placeholders of translation units (tags, {0}, %s) kept as single tokens
+pub mod placeholders;
+
+// Fixed-size sparse bag-of-words vectors with the hashing trick (no vocabulary)
+pub mod hashing;
+
+// Sources of documents (lines, files, JSONL fields, HTTP bodies) behind the InputSource trait
+pub mod input;
+
+// The small bounded-depth JSON reader behind the output records, the vocabulary files and
+// the saved configurations
+mod json;
+
+// The JSON configuration files written by Tokenizer::save() and read by Tokenizer::from_file()
+mod config;
+
+// The schema-versioned JSON record of a tokenized document and the OutputWriter sinks
+pub mod output;
+
+// Reading and writing tokenized documents as JSONL, CoNLL-U, one token per line or text
+pub mod convert;
+
+// Token frequency tables (case-insensitive if wanted, mergeable across workers) and TF-IDF
+pub mod stats;
+
+// Post-tokenization filters behind the TokenFilter trait, and pipelines of them
+pub mod token_filter;
+
+// Stopword lists (built-in English or loaded from a file) to remove or mask stopwords
+pub mod stopwords;
+
+// Per-kind token length limits with a truncation marker (the original span is kept)
+pub mod truncate;
+
+// The Porter stemmer as a filter that keeps the offsets of the original words
+pub mod stem;
+
+// Dictionary lemmatization (form to lemma TSV files, optionally by part of speech)
+pub mod lemma;
+
+// Language identification from the script and character n-gram profiles, and elisions
+pub mod language;
+
+// Readability scores (Flesch, SMOG, Gunning fog, ...) with a syllable estimator
+pub mod readability;
+
+// Grapheme, word, sentence and token counts with one definition for the whole crate
+pub mod counts;
+
+// JSONL corpus shards rotated by size or document count, with a manifest (gzip with `gzip`)
+pub mod shard;
+
+// Resume manifests: the finished input files of a long run, with checksums of their content
+pub mod resume;
+
+// Pipeline::preprocess(): read, clean, tokenize, build a vocabulary, encode and shard a corpus
+pub mod pipeline;
+
+// Progress of a run over many files: files done, tokens per second and estimated time left
+pub mod progress;
+
+// Reading and writing .gz and .zst files by their extension (with the `gzip` and `zstd` features)
+pub mod compress;
+
+// Penn Treebank token conventions ("do n't", -LRB-, `` and '') as a filter
+pub mod ptb;
+
+// JavaScript bindings through wasm-bindgen (with the `wasm` feature)
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+// C functions and the opaque TokenizerHandle behind the generated header (with the `ffi` feature)
+#[cfg(feature = "ffi")]
+pub mod ffi;
+
+// The `tokenizer_rust` Python module through PyO3 (with the `python` feature)
+#[cfg(feature = "python")]
+pub mod python;
+
+// Re-export the main type so users can write `use tokenizer_rust::Tokenizer;`
+pub use tokenizer::Tokenizer;
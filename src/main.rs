@@ -1,139 +1,1857 @@
-// This is the entry point of our program
-// It imports the tokenizer module and shows how to use it
+// This is the entry point of the `tokenizer` command line tool
+// Without a subcommand it tokenizes files (or stdin) and prints the tokens to stdout, as
+// JSON Lines or as text, so that it fits in a shell pipeline. The other subcommands turn
+// tokens back into text (detokenize), count tokens for a budget (count), build and inspect
+// the vocabulary of a saved tokenizer (build-vocab, vocab), try the options of the tokenizer on
+// lines typed one by one (repl), and sample, filter, clean and convert corpora. Input and
+// output files ending with .gz or .zst are (de)compressed on the fly. The tour of the
+// library that used to be printed here is in examples/demo.rs (`cargo run --example demo`).
 
-// Declare that we have a module called 'tokenizer' in tokenizer.rs
-mod tokenizer;
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
 
-// Import the Tokenizer struct from our tokenizer module
-use tokenizer::Tokenizer;
+use clap::{Args, Parser, Subcommand, ValueEnum};
 
-fn main() {
-    // Print a welcoming header
-    println!("╔════════════════════════════════════════╗");
-    println!("║     Rust Tokenizer - Main Program      ║");
-    println!("╚════════════════════════════════════════╝\n");
+use tokenizer_rust::bitext::{Bitext, PairFilter, PairWriter, SentencePair};
+use tokenizer_rust::compress::{self, strip_compression, Compression};
+use tokenizer_rust::convert::{self, Format};
+use tokenizer_rust::corpus::Corpus;
+use tokenizer_rust::counts::Counts;
+use tokenizer_rust::encoder::{EncoderConfig, Template};
+use tokenizer_rust::external_vocab::{overlap, ExternalVocab};
+use tokenizer_rust::filter::SentenceFilter;
+use tokenizer_rust::highlight::{colorize_kinds, kind_color};
+use tokenizer_rust::input::{HttpSource, InputDocument, InputSource, JsonlFieldSource, LineSource};
+use tokenizer_rust::language::Language;
+use tokenizer_rust::metrics::Script;
+use tokenizer_rust::output::{
+    ConllWriter, CsvColumn, CsvQuoting, CsvWriter, OutputFormat, OutputRecord, OutputWriter,
+};
+use tokenizer_rust::progress::Progress;
+use tokenizer_rust::ptb::PennTreebank;
+use tokenizer_rust::resume::{FileStatus, Fingerprint, ResumeManifest};
+use tokenizer_rust::sample::Reservoir;
+use tokenizer_rust::sentences::SentenceSplitter;
+use tokenizer_rust::special::SpecialTokens;
+use tokenizer_rust::stats::FrequencyTable;
+use tokenizer_rust::stem::PorterStemmer;
+use tokenizer_rust::stopwords::StopwordFilter;
+use tokenizer_rust::subword::{SubwordKind, SubwordModel};
+use tokenizer_rust::token::TokenKind;
+use tokenizer_rust::token_filter::{FilterPipeline, Lowercase, TokenFilter};
+use tokenizer_rust::Tokenizer;
+
+/// Tokenize text, and sample, filter, clean and convert corpora
+///
+/// Without a subcommand, tokenizes the given files (or stdin) like `tokenizer tokenize`.
+#[derive(Parser)]
+#[command(name = "tokenizer", version, args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    tokenize: TokenizeArgs,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print the tokens of every document (the default command)
+    Tokenize(TokenizeArgs),
+    /// Turn tokens (or token ids) back into text
+    Detokenize(DetokenizeArgs),
+    /// Count the tokens, words, sentences and characters of every document
+    Count(CountArgs),
+    /// Build the vocabulary of a tokenizer from a corpus (whole words, or the pieces of a BPE
+    /// or WordPiece model) and save the tokenizer
+    BuildVocab(BuildVocabArgs),
+    /// Print the vocabulary of a tokenizer, or compare it with the one of a published model
+    Vocab(VocabArgs),
+    /// Tokenize the lines typed on stdin, with commands to change the options as you go
+    Repl(ReplArgs),
+    /// Print a seeded random sample of the documents or sentences
+    Sample(SampleArgs),
+    /// Print the sentences that pass every check
+    Filter(FilterArgs),
+    /// Keep the sentence pairs of a parallel corpus that pass the filter
+    Bitext(BitextArgs),
+    /// Convert tokenized documents from one format to another
+    Convert(ConvertArgs),
+}
+
+/// What is tokenized as one document
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Mode {
+    /// Every line (or JSONL field, or URL body)
+    Lines,
+    /// Every sentence of every line
+    Sentences,
+    /// Every input as a whole, with its line breaks as tokens
+    Document,
+}
+
+/// The special tokens and template of a model family
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Family {
+    /// [CLS] text [SEP], with [PAD], [UNK] and [MASK]
+    Bert,
+    /// <s> text </s>, with <pad>, <unk> and <mask>
+    Roberta,
+}
+
+/// The subword models `build-vocab` learns
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum SubwordArg {
+    /// Byte pair encoding: the most frequent pairs of pieces are merged
+    Bpe,
+    /// WordPiece, as in BERT: pairs are merged by likelihood, words split longest piece first
+    Wordpiece,
+}
+
+/// How tokens are written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum TokenFormat {
+    /// One JSON object per document, with the tokens, their kinds and their offsets
+    Json,
+    /// One line per document, the tokens separated by spaces
+    Text,
+}
+
+/// The inputs of a command, and which files of its directories and patterns are read
+#[derive(Args)]
+struct InputArgs {
+    /// Input files, directories, glob patterns ("corpus/**/*.txt") or http:// URLs ("-" or
+    /// none for stdin); .gz and .zst files are decompressed
+    #[arg(value_name = "INPUT")]
+    files: Vec<String>,
+
+    /// Only read the files of directories and patterns that match one of these patterns
+    /// ("*.txt", "2024/**")
+    #[arg(long, value_name = "GLOB")]
+    include: Vec<String>,
+
+    /// Skip the files of directories and patterns that match one of these patterns
+    #[arg(long, value_name = "GLOB")]
+    exclude: Vec<String>,
+}
+
+/// How the input files of a command are processed
+#[derive(Args)]
+struct JobArgs {
+    /// Number of files processed at once (default: one per CPU core)
+    #[arg(short = 'j', long, value_name = "N")]
+    jobs: Option<usize>,
+
+    /// Show the files done, the tokens per second and the time left on stderr
+    #[arg(long)]
+    progress: bool,
+}
+
+#[derive(Args)]
+struct TokenizeArgs {
+    /// What is tokenized as one document
+    #[arg(long, value_enum, default_value_t = Mode::Lines)]
+    mode: Mode,
+
+    /// How the tokens are printed
+    #[arg(long, value_enum, default_value_t = TokenFormat::Json)]
+    output: TokenFormat,
+
+    /// Print the text of every document with its tokens colored by kind instead (words
+    /// green, numbers yellow, punctuation magenta, special tokens red, identifiers cyan)
+    #[arg(long, conflicts_with_all = ["output", "output_dir"])]
+    highlight: bool,
+
+    /// Keep the special tokens of a model family whole ([CLS], <s>, ...)
+    #[arg(long, value_enum, value_name = "FAMILY")]
+    special_tokens: Option<Family>,
+
+    /// Read this string field of JSONL records instead of lines ("text", "meta.body")
+    #[arg(long, value_name = "NAME")]
+    field: Option<String>,
+
+    /// Write one file per input file in this directory (same path below the directory or
+    /// pattern it was found in, with a .jsonl or .txt extension, compressed like the input)
+    /// instead of stdout
+    #[arg(long, value_name = "DIR")]
+    output_dir: Option<String>,
+
+    #[command(flatten)]
+    jobs: JobArgs,
+
+    #[command(flatten)]
+    input: InputArgs,
+}
+
+#[derive(Args)]
+struct DetokenizeArgs {
+    /// How the tokens are written
+    #[arg(long = "input", value_enum, default_value_t = TokenFormat::Json)]
+    input_format: TokenFormat,
 
-    // EXAMPLE 1: Simple sentence tokenization
-    println!("📌 EXAMPLE 1: Simple Tokenization");
-    println!("─────────────────────────────────────\n");
+    /// Decode token ids with this tokenizer (written by `build-vocab`): the ids of the JSON
+    /// records, or space-separated ids with --input text
+    #[arg(long, value_name = "MODEL")]
+    model: Option<String>,
 
-    // Create a sample sentence
-    let sentence1 = "Hello, world! How are you?";
-    println!("Original sentence: \"{}\"", sentence1);
+    #[command(flatten)]
+    input: InputArgs,
+}
+
+#[derive(Args)]
+struct CountArgs {
+    /// What is counted as one document
+    #[arg(long, value_enum, default_value_t = Mode::Lines)]
+    mode: Mode,
+
+    /// Also count the ids this tokenizer (written by `build-vocab`) encodes every document into
+    #[arg(long, value_name = "MODEL")]
+    model: Option<String>,
+
+    /// Print the counts of every document as a TSV row, and the totals on stderr
+    #[arg(long)]
+    per_document: bool,
+
+    /// Read this string field of JSONL records instead of lines
+    #[arg(long, value_name = "NAME")]
+    field: Option<String>,
+
+    #[command(flatten)]
+    jobs: JobArgs,
+
+    #[command(flatten)]
+    input: InputArgs,
+}
 
-    // Create a new Tokenizer instance with our sentence
-    // The Tokenizer::new() function takes ownership of the string
-    let tokenizer1 = Tokenizer::new(sentence1.to_string());
+#[derive(Args)]
+struct BuildVocabArgs {
+    /// Write the tokenizer with its vocabulary to this file
+    #[arg(long, value_name = "MODEL")]
+    output: String,
 
-    // Get the tokens (this breaks the sentence into pieces)
-    let tokens1 = tokenizer1.tokenize();
-    println!("Tokens: {:?}", tokens1);
+    /// Leave the tokens seen fewer times out of the vocabulary
+    #[arg(long, value_name = "N", default_value_t = 1)]
+    min_count: usize,
 
-    // Reconstruct the original sentence from tokens
-    // detokenize() uses smart rules to handle spacing with punctuation
-    let reconstructed1 = tokenizer1.detokenize(&tokens1);
-    println!("Reconstructed: \"{}\"", reconstructed1);
-    println!("Match original? {}\n", reconstructed1 == sentence1);
+    /// Add at most this many tokens to the vocabulary (the most frequent ones), or with
+    /// --model learn this many pieces (8000 by default)
+    #[arg(long, value_name = "N")]
+    max_vocabulary: Option<usize>,
 
-    // EXAMPLE 2: Complex sentence with various punctuation
-    println!("📌 EXAMPLE 2: Complex Punctuation");
-    println!("─────────────────────────────────────\n");
+    /// Learn a subword model instead of whole words: words that are not in the vocabulary
+    /// are then encoded as pieces
+    #[arg(long, value_enum, value_name = "MODEL")]
+    model: Option<SubwordArg>,
 
-    let sentence2 = "Mr. Smith said, \"Don't worry!\"";
-    println!("Original sentence: \"{}\"", sentence2);
+    /// Start from the special tokens and template of a model family
+    #[arg(long, value_enum, value_name = "FAMILY")]
+    special_tokens: Option<Family>,
 
-    let tokenizer2 = Tokenizer::new(sentence2.to_string());
-    let tokens2 = tokenizer2.tokenize();
-    println!("Tokens: {:?}", tokens2);
+    /// Read this string field of JSONL records instead of lines
+    #[arg(long, value_name = "NAME")]
+    field: Option<String>,
 
-    let reconstructed2 = tokenizer2.detokenize(&tokens2);
-    println!("Reconstructed: \"{}\"", reconstructed2);
-    println!("Match original? {}\n", reconstructed2 == sentence2);
+    #[command(flatten)]
+    jobs: JobArgs,
 
-    // EXAMPLE 3: Question with multiple punctuation marks
-    println!("📌 EXAMPLE 3: Questions and Exclamations");
-    println!("─────────────────────────────────────\n");
+    #[command(flatten)]
+    input: InputArgs,
+}
+
+#[derive(Args)]
+struct VocabArgs {
+    /// A tokenizer written by `build-vocab`, or with --external the vocabulary file of a model
+    #[arg(value_name = "FILE")]
+    file: String,
+
+    /// Read FILE as the vocabulary of a published model (vocab.json, tokenizer.json,
+    /// vocab.txt or a tiktoken file) and print its whole words
+    #[arg(long)]
+    external: bool,
+
+    /// Compare the vocabulary with the one of a published model
+    #[arg(long, value_name = "FILE", conflicts_with = "external")]
+    compare: Option<String>,
 
-    let sentence3 = "Really? Yes! Absolutely!!!";
-    println!("Original sentence: \"{}\"", sentence3);
+    /// Corpus files whose tokens give the coverage and the missing tokens of --compare
+    #[arg(long, value_name = "FILE", requires = "compare")]
+    corpus: Vec<String>,
 
-    let tokenizer3 = Tokenizer::new(sentence3.to_string());
-    let tokens3 = tokenizer3.tokenize();
-    println!("Tokens: {:?}", tokens3);
+    /// Number of missing tokens in the --compare report
+    #[arg(long, value_name = "N", default_value_t = 20)]
+    top: usize,
+}
+
+#[derive(Args)]
+struct ReplArgs {
+    /// Show the ids of the tokens in the vocabulary of this tokenizer (written by `build-vocab`)
+    #[arg(long, value_name = "MODEL")]
+    model: Option<String>,
+
+    /// Start with the special tokens of a model family
+    #[arg(long, value_enum, value_name = "FAMILY")]
+    special_tokens: Option<Family>,
+}
 
-    let reconstructed3 = tokenizer3.detokenize(&tokens3);
-    println!("Reconstructed: \"{}\"", reconstructed3);
-    println!("Match original? {}\n", reconstructed3 == sentence3);
+#[derive(Args)]
+struct SampleArgs {
+    /// Number of documents or sentences in the sample
+    #[arg(short = 'n', long, default_value_t = 10)]
+    count: usize,
 
-    // EXAMPLE 4: Token statistics
-    println!("📌 EXAMPLE 4: Token Statistics");
-    println!("─────────────────────────────────────\n");
+    /// The seed of the random choice (the same seed prints the same sample)
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
 
-    let sentence4 = "Tokenization is the process of breaking text into tokens.";
-    println!("Original sentence: \"{}\"", sentence4);
+    /// Sample sentences instead of documents
+    #[arg(long)]
+    sentences: bool,
 
-    let tokenizer4 = Tokenizer::new(sentence4.to_string());
-    let tokens4 = tokenizer4.tokenize();
+    /// Read this string field of JSONL records instead of lines
+    #[arg(long, value_name = "NAME")]
+    field: Option<String>,
+
+    #[command(flatten)]
+    input: InputArgs,
+}
+
+#[derive(Args)]
+struct FilterArgs {
+    /// Drop the sentences with fewer tokens
+    #[arg(long, value_name = "N", default_value_t = 0)]
+    min_tokens: usize,
+
+    /// Drop the sentences with more tokens
+    #[arg(long, value_name = "N", default_value_t = usize::MAX, hide_default_value = true)]
+    max_tokens: usize,
+
+    /// Drop the sentences with a smaller share of alphabetic tokens
+    #[arg(long, value_name = "RATIO")]
+    min_alpha: Option<f64>,
+
+    /// Keep only the sentences written in these scripts (latin, cyrillic, ...)
+    #[arg(long, value_name = "NAME", value_parser = script)]
+    script: Vec<Script>,
+
+    /// Keep only the sentences detected to be in these languages (ISO 639-1 codes: en, fr, ...)
+    #[arg(long, value_name = "CODE", value_parser = language)]
+    language: Vec<Language>,
+
+    /// Keep only the sentences with a token matching this regular expression
+    #[cfg(feature = "regex")]
+    #[arg(long, value_name = "REGEX")]
+    require: Vec<regex::Regex>,
+
+    /// Drop the sentences with a token matching this regular expression
+    #[cfg(feature = "regex")]
+    #[arg(long, value_name = "REGEX")]
+    forbid: Vec<regex::Regex>,
+
+    /// Print the dropped sentences instead of the kept ones
+    #[arg(long)]
+    invert: bool,
+
+    /// Read this string field of JSONL records instead of lines
+    #[arg(long, value_name = "NAME")]
+    field: Option<String>,
+
+    /// Write the sentences to this file instead of stdout (compressed if it ends with .gz
+    /// or .zst)
+    #[arg(long, value_name = "FILE")]
+    output: Option<String>,
+
+    /// Record the finished input files here, and skip them in the next run
+    #[arg(long, value_name = "MANIFEST", requires = "output")]
+    resume: Option<String>,
+
+    #[command(flatten)]
+    input: InputArgs,
+}
 
-    // Print token count
-    println!("Total tokens: {}", tokens4.len());
+#[derive(Args)]
+struct BitextArgs {
+    /// Drop the pairs with a side of more tokens
+    #[arg(long, value_name = "N")]
+    max_tokens: Option<usize>,
 
-    // Count only word tokens (excluding punctuation)
-    // This shows how to filter tokens based on their type
-    let word_tokens: Vec<_> = tokens4
+    /// Drop the pairs with a side this many times longer than the other
+    #[arg(long, value_name = "RATIO")]
+    max_ratio: Option<f64>,
+
+    /// Bounds of the target tokens per source token
+    #[arg(long, value_name = "MIN:MAX", value_parser = ratio_bounds)]
+    token_ratio: Option<(f64, f64)>,
+
+    /// Drop the pairs that share a smaller share of their numbers
+    #[arg(long, value_name = "RATIO")]
+    min_number_overlap: Option<f64>,
+
+    /// Drop the pairs whose target copies the source
+    #[arg(long)]
+    drop_untranslated: bool,
+
+    /// Keep inline tags and format arguments whole, and drop the pairs that differ in them
+    #[arg(long)]
+    placeholders: bool,
+
+    /// Write the dropped pairs, with the reason, to this file (compressed if it ends with
+    /// .gz or .zst)
+    #[arg(long, value_name = "FILE")]
+    rejected: Option<String>,
+
+    /// Print the kept pairs tokenized
+    #[arg(long)]
+    tokenized: bool,
+
+    /// Read the pairs from one tab-separated file
+    #[arg(long, value_name = "FILE", conflicts_with = "files")]
+    tsv: Option<String>,
+
+    /// The SOURCE and TARGET files, line N translating line N
+    #[arg(value_names = ["SOURCE", "TARGET"], num_args = 2)]
+    files: Vec<String>,
+}
+
+#[derive(Args)]
+struct ConvertArgs {
+    /// The input format: jsonl, conll, lines or text
+    #[arg(long, value_name = "FORMAT", value_parser = format)]
+    from: Format,
+
+    /// The output format: jsonl, conll, lines, text, csv, tsv (and arrow with that feature)
+    #[arg(long, value_name = "FORMAT", value_parser = output_format)]
+    to: OutputFormat,
+
+    /// The csv and tsv columns: doc_id, token_index, token, kind, start, end, id
+    #[arg(long, value_name = "NAME,...", value_parser = csv_column, value_delimiter = ',')]
+    columns: Option<Vec<CsvColumn>>,
+
+    /// The csv and tsv quoting: necessary, always, non-numeric or never
+    #[arg(long, value_name = "STYLE", value_parser = csv_quoting)]
+    quoting: Option<CsvQuoting>,
+
+    /// Split every document of the conll output into sentences
+    #[arg(long)]
+    sentences: bool,
+
+    #[command(flatten)]
+    input: InputArgs,
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let result = match cli.command {
+        None => tokenize(cli.tokenize),
+        Some(Command::Tokenize(args)) => tokenize(args),
+        Some(Command::Detokenize(args)) => detokenize(args),
+        Some(Command::Count(args)) => count(args),
+        Some(Command::BuildVocab(args)) => build_vocab(args),
+        Some(Command::Vocab(args)) => vocab(args),
+        Some(Command::Repl(args)) => repl(args),
+        Some(Command::Sample(args)) => sample(args),
+        Some(Command::Filter(args)) => filter(args),
+        Some(Command::Bitext(args)) => bitext(args),
+        Some(Command::Convert(args)) => convert(args),
+    };
+    if let Err(error) = result {
+        // A closed pipe (`tokenizer big.txt | head`) is not an error
+        if error.kind() == io::ErrorKind::BrokenPipe {
+            return;
+        }
+        eprintln!("error: {}", error);
+        std::process::exit(1);
+    }
+}
+
+/// The `tokenize` command: prints the tokens of every document (line, sentence or whole
+/// input) of the given inputs, or of stdin, as JSON Lines or as text, or writes them to one
+/// file per input file with --output-dir
+fn tokenize(args: TokenizeArgs) -> io::Result<()> {
+    // STEP 1: Keep the line breaks as tokens when a whole input is one document
+    let tokenizer = with_family(Tokenizer::new(String::new()), args.special_tokens)
+        .with_structure_tokens(args.mode == Mode::Document);
+    let tokenize_file = |file: &str, writer: &mut dyn Write, progress: &FileProgress| {
+        let (mut documents, mut tokens) = (0, 0);
+        for_each_document(
+            args.mode,
+            args.field.as_deref(),
+            vec![file.to_string()],
+            progress,
+            |text| {
+                let document = tokenizer.tokenize_text(text);
+                documents += 1;
+                tokens += document.len();
+                progress.tokenized(document.len());
+                if args.highlight {
+                    write!(writer, "{}", colorize_kinds(text, &document))?;
+                    // A whole document already ends with its line break
+                    if !text.ends_with('\n') {
+                        writeln!(writer)?;
+                    }
+                    return Ok(());
+                }
+                let record = OutputRecord::from_tokens(text, &document);
+                match args.output {
+                    TokenFormat::Json => writeln!(writer, "{}", record.to_json()),
+                    TokenFormat::Text => write_text(writer, &record),
+                }
+            },
+        )?;
+        Ok((documents, tokens))
+    };
+    if args.highlight {
+        let legend = [
+            TokenKind::Word,
+            TokenKind::Number,
+            TokenKind::Punctuation,
+            TokenKind::Special,
+            TokenKind::Identifier,
+        ]
+        .map(|kind| format!("{}{}\x1b[0m", kind_color(kind), kind.name()));
+        eprintln!("{}", legend.join(" "));
+    }
+
+    // STEP 2: Print the tokens of every document, as one stream in input order
+    let Some(directory) = &args.output_dir else {
+        let mut stdout = io::BufWriter::new(io::stdout().lock());
+        let documents: usize = run_files(
+            inputs(args.input)?,
+            &args.jobs,
+            &mut stdout,
+            |_, file, writer, progress| tokenize_file(file, writer, progress),
+        )?
+        .into_iter()
+        .sum();
+        stdout.flush()?;
+        eprintln!("tokenized {} documents", documents);
+        return Ok(());
+    };
+
+    // STEP 3: Or write them to one file per input file, under the output directory
+    let extension = match args.output {
+        TokenFormat::Json => "jsonl",
+        TokenFormat::Text => "txt",
+    };
+    let (mut files, mut outputs) = (Vec::new(), Vec::new());
+    for (file, relative) in expand_inputs(&args.input)? {
+        if file == "-" || file.starts_with("http://") {
+            return Err(invalid_input(format!(
+                "--output-dir needs files, not {}",
+                file
+            )));
+        }
+        // A compressed input gives an output compressed the same way ("a.txt.gz" -> "a.jsonl.gz")
+        let compression = Compression::from_path(&relative);
+        let mut path = Path::new(directory)
+            .join(strip_compression(&relative))
+            .with_extension(extension);
+        if compression != Compression::None {
+            path = path.with_extension(format!("{}.{}", extension, compression.extension()));
+        }
+        if outputs.contains(&path) {
+            return Err(invalid_input(format!(
+                "two inputs would be written to {}",
+                path.display()
+            )));
+        }
+        files.push(file);
+        outputs.push(path);
+    }
+    let documents: usize = run_files(
+        files,
+        &args.jobs,
+        &mut io::sink(),
+        |index, file, _, progress| {
+            let path = &outputs[index];
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let located = |error: io::Error| {
+                io::Error::new(error.kind(), format!("{}: {}", path.display(), error))
+            };
+            let mut writer = compress::create(path).map_err(located)?;
+            let counts = tokenize_file(file, &mut writer, progress)?;
+            writer.finish().map_err(located)?;
+            Ok(counts)
+        },
+    )?
+    .into_iter()
+    .sum();
+    eprintln!(
+        "tokenized {} documents into {} files in {}",
+        documents,
+        outputs.len(),
+        directory
+    );
+    Ok(())
+}
+
+/// Runs `work` on every input file and returns its results in input order
+///
+/// With more than one job the files are spread over that many threads: each one sends the
+/// output of its file in chunks, and the chunks are copied to `output` in input order. A job
+/// whose file is not the one being written waits once CHUNKS_IN_FLIGHT chunks are queued, so
+/// memory stays bounded whatever the size of the files. With one job (or one file, like stdin) `work` writes straight to
+/// `output`. `work` gets the index of the file, its name and the writer, and returns its
+/// result and the number of tokens of the file for --progress.
+fn run_files<T, F>(
+    files: Vec<String>,
+    jobs: &JobArgs,
+    output: &mut dyn Write,
+    work: F,
+) -> io::Result<Vec<T>>
+where
+    T: Send,
+    F: Fn(usize, &str, &mut dyn Write, &FileProgress) -> io::Result<(T, usize)> + Sync,
+{
+    // STEP 1: Size the run (stdin and URLs count as empty files for the time left)
+    let sizes: Vec<u64> = files
         .iter()
-        .filter(|t| !t.chars().all(|c| !c.is_alphanumeric())) // Keep tokens with letters/numbers
+        .map(|file| fs::metadata(file).map_or(0, |metadata| metadata.len()))
         .collect();
+    let progress = jobs
+        .progress
+        .then(|| Progress::new(files.len(), sizes.iter().sum()));
+    let threads = jobs
+        .jobs
+        .unwrap_or_else(|| thread::available_parallelism().map_or(1, usize::from))
+        .clamp(1, files.len().max(1));
+    let finished = AtomicBool::new(false);
+    let (next, stop) = (AtomicUsize::new(0), AtomicBool::new(false));
 
-    println!("Word tokens: {}", word_tokens.len());
-    println!("Punctuation tokens: {}", tokens4.len() - word_tokens.len());
-    println!("Tokens breakdown: {:?}\n", tokens4);
+    thread::scope(|scope| {
+        if let Some(progress) = &progress {
+            scope.spawn(|| draw_progress(progress, &finished));
+        }
 
-    // EXAMPLE 5: Interactive-style demonstration
-    println!("📌 EXAMPLE 5: Custom Sentence");
-    println!("─────────────────────────────────────\n");
+        // STEP 2: One job works through the files in order, writing straight to the output
+        let result = if threads == 1 {
+            let mut results = Vec::with_capacity(files.len());
+            let mut outcome = Ok(());
+            for (index, file) in files.iter().enumerate() {
+                let file_progress = FileProgress::new(progress.as_ref(), sizes[index]);
+                match work(index, file, output, &file_progress) {
+                    Ok((value, tokens)) => {
+                        file_progress.done(tokens);
+                        results.push(value);
+                    }
+                    Err(error) => {
+                        outcome = Err(error);
+                        break;
+                    }
+                }
+            }
+            outcome.map(|()| results)
+        } else {
+            // STEP 3: Several jobs take the next file in turn and stream its output back in
+            // chunks, through one bounded channel per file
+            let (files_sender, files_receiver) = mpsc::channel();
+            for _ in 0..threads {
+                let files_sender = files_sender.clone();
+                let (files, sizes, work) = (&files, &sizes, &work);
+                let (next, stop, progress) = (&next, &stop, &progress);
+                scope.spawn(move || {
+                    while !stop.load(Ordering::Relaxed) {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(file) = files.get(index) else {
+                            break;
+                        };
+                        let (sender, receiver) = mpsc::sync_channel(CHUNKS_IN_FLIGHT);
+                        if files_sender.send((index, receiver)).is_err() {
+                            break;
+                        }
+                        let mut writer = ChunkWriter {
+                            sender,
+                            buffer: Vec::new(),
+                        };
+                        let file_progress = FileProgress::new(progress.as_ref(), sizes[index]);
+                        let result = work(index, file, &mut writer, &file_progress).and_then(
+                            |(value, tokens)| {
+                                writer.send_buffer()?;
+                                file_progress.done(tokens);
+                                Ok(value)
+                            },
+                        );
+                        // The writer side is gone after an error: nothing is waiting for this
+                        let _ = writer.sender.send(OutputPiece::Done(result));
+                    }
+                });
+            }
+            drop(files_sender);
+
+            // The files are written in input order, each one as its chunks arrive. A file
+            // further on can only buffer CHUNKS_IN_FLIGHT chunks before its job waits.
+            let mut started = BTreeMap::new();
+            let mut results = Vec::with_capacity(files.len());
+            let mut outcome = Ok(());
+            'files: for index in 0..files.len() {
+                let receiver = loop {
+                    if let Some(receiver) = started.remove(&index) {
+                        break receiver;
+                    }
+                    match files_receiver.recv() {
+                        Ok((started_index, receiver)) => {
+                            started.insert(started_index, receiver);
+                        }
+                        Err(_) => {
+                            outcome = Err(io::Error::other("a job stopped before its file"));
+                            break 'files;
+                        }
+                    }
+                };
+                let written = loop {
+                    match receiver.recv() {
+                        Ok(OutputPiece::Chunk(chunk)) => {
+                            if let Err(error) = output.write_all(&chunk) {
+                                break Err(error);
+                            }
+                        }
+                        Ok(OutputPiece::Done(result)) => break result,
+                        Err(_) => break Err(io::Error::other("a job stopped during its file")),
+                    }
+                };
+                match written {
+                    Ok(value) => results.push(value),
+                    Err(error) => {
+                        stop.store(true, Ordering::Relaxed);
+                        outcome = Err(error);
+                        break;
+                    }
+                }
+            }
+            outcome.map(|()| results)
+        };
+        finished.store(true, Ordering::Relaxed);
+        result
+    })
+}
 
-    let sentence5 = "This Rust project is awesome, isn't it? (Not really)";
-    println!("Original sentence: \"{}\"", sentence5);
+/// How many bytes a job collects before sending them as one chunk of output
+const OUTPUT_CHUNK_BYTES: usize = 64 * 1024;
 
-    let tokenizer5 = Tokenizer::new(sentence5.to_string());
-    let tokens5 = tokenizer5.tokenize();
+/// How many chunks of one file can wait to be written before its job stops to wait
+const CHUNKS_IN_FLIGHT: usize = 16;
 
-    println!("\nToken breakdown:");
-    // Iterate through each token and show its index
-    // This is useful for understanding token positions
-    for (index, token) in tokens5.iter().enumerate() {
-        // Determine if this is a word or punctuation
-        let token_type = if token.chars().all(|c| !c.is_alphanumeric()) {
-            "PUNCTUATION"
+/// What a job sends about the file it works on
+enum OutputPiece<T> {
+    /// The next part of the output of the file
+    Chunk(Vec<u8>),
+    /// The end of the file, with the result of the work
+    Done(io::Result<T>),
+}
+
+/// The writer a job writes the output of a file to, sending it on in chunks
+struct ChunkWriter<T> {
+    /// Where the chunks go (blocks while CHUNKS_IN_FLIGHT chunks are waiting)
+    sender: mpsc::SyncSender<OutputPiece<T>>,
+    /// The bytes of the next chunk
+    buffer: Vec<u8>,
+}
+
+impl<T> ChunkWriter<T> {
+    /// Sends the bytes collected so far
+    fn send_buffer(&mut self) -> io::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let chunk = std::mem::take(&mut self.buffer);
+        self.sender
+            .send(OutputPiece::Chunk(chunk))
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "the output was closed"))
+    }
+}
+
+impl<T> Write for ChunkWriter<T> {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(bytes);
+        if self.buffer.len() >= OUTPUT_CHUNK_BYTES {
+            self.send_buffer()?;
+        }
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        // The chunks are flushed with the output they are copied to
+        Ok(())
+    }
+}
+
+/// How far a job is into the file it works on, reported to the progress line (if any) as
+/// the documents are read, so a long file moves the line before it is finished
+struct FileProgress<'a> {
+    /// The counters of the run, without --progress
+    progress: Option<&'a Progress>,
+    /// The size of the file (0 when unknown), which the bytes reported never exceed
+    size: u64,
+    /// The bytes reported so far
+    reported: Cell<u64>,
+    /// The tokens reported so far
+    tokens: Cell<usize>,
+}
+
+impl<'a> FileProgress<'a> {
+    /// Starts a file of `size` bytes
+    fn new(progress: Option<&'a Progress>, size: u64) -> Self {
+        FileProgress {
+            progress,
+            size,
+            reported: Cell::new(0),
+            tokens: Cell::new(0),
+        }
+    }
+
+    /// Reports `bytes` more bytes of the file as read (decompressed bytes can add up to
+    /// more than the size, which stops the progress of the file at its size)
+    fn read(&self, bytes: u64) {
+        let Some(progress) = self.progress else {
+            return;
+        };
+        let reported = self.reported.get();
+        let now = reported.saturating_add(bytes).min(self.size);
+        progress.advance(now - reported, 0);
+        self.reported.set(now);
+    }
+
+    /// Reports `tokens` more tokens found in the file
+    fn tokenized(&self, tokens: usize) {
+        if let Some(progress) = self.progress {
+            progress.advance(0, tokens);
+            self.tokens.set(self.tokens.get() + tokens);
+        }
+    }
+
+    /// Reports the file as finished with `tokens` tokens in all, with the bytes and tokens
+    /// not reported yet
+    fn done(&self, tokens: usize) {
+        if let Some(progress) = self.progress {
+            progress.file_done(
+                self.size - self.reported.get(),
+                tokens.saturating_sub(self.tokens.get()),
+            );
+        }
+    }
+}
+
+/// Redraws the progress line on stderr until the run is finished, then leaves it there
+fn draw_progress(progress: &Progress, finished: &AtomicBool) {
+    let mut last_draw = Instant::now();
+    while !finished.load(Ordering::Relaxed) {
+        thread::sleep(Duration::from_millis(50));
+        if last_draw.elapsed() >= Duration::from_millis(250) {
+            eprint!("\r{}\x1b[K", progress.report());
+            last_draw = Instant::now();
+        }
+    }
+    eprintln!("\r{}\x1b[K", progress.report());
+}
+
+/// Writes the tokens of a record on one line, separated by spaces (line break tokens are
+/// written as they are, and end the record when they come last)
+fn write_text<W: Write + ?Sized>(writer: &mut W, record: &OutputRecord) -> io::Result<()> {
+    let kinds = record.kinds.as_deref().unwrap_or_default();
+    let mut line_start = true;
+    for (index, token) in record.tokens.iter().enumerate() {
+        match kinds.get(index) {
+            Some(TokenKind::LineBreak | TokenKind::ParagraphBreak) => {
+                write!(writer, "{}", token)?;
+                line_start = true;
+            }
+            _ => {
+                if !line_start {
+                    write!(writer, " ")?;
+                }
+                write!(writer, "{}", token)?;
+                line_start = false;
+            }
+        }
+    }
+    if line_start && !record.tokens.is_empty() {
+        return Ok(());
+    }
+    writeln!(writer)
+}
+
+/// Reads the documents of every input (lines, sentences or whole inputs, see Mode) and
+/// hands their texts over in order (see inputs() for the list of inputs), reporting the
+/// bytes of every document read to `progress`
+fn for_each_document<F>(
+    mode: Mode,
+    field: Option<&str>,
+    files: Vec<String>,
+    progress: &FileProgress,
+    mut on_document: F,
+) -> io::Result<()>
+where
+    F: FnMut(&str) -> io::Result<()>,
+{
+    let splitter = SentenceSplitter::new();
+    for file in files {
+        let mut source = match mode {
+            Mode::Document if field.is_none() && !file.starts_with("http://") => {
+                Box::new(WholeInput { file: Some(file) })
+            }
+            _ => open_source(&file, field)?,
+        };
+        while let Some(document) = source.next_document()? {
+            progress.read(document.text.len() as u64);
+            match mode {
+                Mode::Sentences => {
+                    for sentence in splitter.sentences(&document.text) {
+                        on_document(sentence)?;
+                    }
+                }
+                Mode::Lines | Mode::Document => on_document(&document.text)?,
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The whole content of an input file (or stdin) as one document
+struct WholeInput {
+    /// The input, until it is read
+    file: Option<String>,
+}
+
+impl InputSource for WholeInput {
+    fn next_document(&mut self) -> io::Result<Option<InputDocument>> {
+        let Some(name) = self.file.take() else {
+            return Ok(None);
+        };
+        let mut text = String::new();
+        if name == "-" {
+            io::stdin().read_to_string(&mut text)?;
         } else {
-            "WORD"
+            text = compress::read_to_string(&name)
+                .map_err(|error| io::Error::new(error.kind(), format!("{}: {}", name, error)))?;
+        }
+        Ok(Some(InputDocument { name, text }))
+    }
+}
+
+/// The `detokenize` command: prints every tokenized document of the given files (or stdin)
+/// as text, one per line
+fn detokenize(args: DetokenizeArgs) -> io::Result<()> {
+    // STEP 1: Load the tokenizer that encoded the ids
+    let tokenizer = match &args.model {
+        Some(model) => load_model(model)?,
+        None => Tokenizer::new(String::new()),
+    };
+
+    // STEP 2: Read the tokens (or ids) of every line and print them as text
+    let mut stdout = io::BufWriter::new(io::stdout().lock());
+    let mut documents = 0;
+    for file in inputs(args.input)? {
+        let located =
+            |error: io::Error| io::Error::new(error.kind(), format!("{}: {}", file, error));
+        for line in open_input(&file)?.lines() {
+            let line = line?;
+            let text = match (args.input_format, &args.model) {
+                (TokenFormat::Json, _) if line.trim().is_empty() => continue,
+                (TokenFormat::Json, model) => {
+                    let record = OutputRecord::from_json(&line).map_err(located)?;
+                    match (model, &record.ids) {
+                        (Some(_), Some(ids)) => tokenizer.decode_skip_special(ids),
+                        _ => tokenizer.detokenize(&record.tokens),
+                    }
+                }
+                (TokenFormat::Text, Some(_)) => {
+                    let ids = line
+                        .split_whitespace()
+                        .map(|id| id.parse::<u32>())
+                        .collect::<Result<Vec<_>, _>>()
+                        .map_err(|_| located(invalid_data(format!("invalid ids: {}", line))))?;
+                    tokenizer.decode_skip_special(&ids)
+                }
+                (TokenFormat::Text, None) => {
+                    let tokens: Vec<String> = line.split_whitespace().map(String::from).collect();
+                    tokenizer.detokenize(&tokens)
+                }
+            };
+            writeln!(stdout, "{}", text)?;
+            documents += 1;
+        }
+    }
+    stdout.flush()?;
+    eprintln!("detokenized {} documents", documents);
+    Ok(())
+}
+
+/// The `count` command: prints the number of documents, tokens, words, sentences and
+/// graphemes of the given inputs (or stdin), and the number of ids a tokenizer encodes them
+/// into, to check a corpus against a token budget
+fn count(args: CountArgs) -> io::Result<()> {
+    // STEP 1: Load the tokenizer whose ids are counted
+    let tokenizer = match &args.model {
+        Some(model) => load_model(model)?,
+        None => Tokenizer::new(String::new()),
+    }
+    .with_structure_tokens(args.mode == Mode::Document);
+
+    // STEP 2: Count every document, printing its counts with --per-document
+    let mut stdout = io::BufWriter::new(io::stdout().lock());
+    let results = run_files(
+        inputs(args.input)?,
+        &args.jobs,
+        &mut stdout,
+        |_, file, writer, progress| {
+            let mut total = Counts::default();
+            let (mut documents, mut ids) = (0, 0);
+            for_each_document(
+                args.mode,
+                args.field.as_deref(),
+                vec![file.to_string()],
+                progress,
+                |text| {
+                    let counts = tokenizer.counts(text);
+                    let encoded = match &args.model {
+                        Some(_) => tokenizer.encode_batch(&[text])[0].ids.len(),
+                        None => 0,
+                    };
+                    documents += 1;
+                    total.tokens += counts.tokens;
+                    progress.tokenized(counts.tokens);
+                    total.words += counts.words;
+                    total.sentences += counts.sentences;
+                    total.graphemes += counts.graphemes;
+                    ids += encoded;
+                    if !args.per_document {
+                        return Ok(());
+                    }
+                    write!(
+                        writer,
+                        "{}\t{}\t{}\t{}",
+                        counts.tokens, counts.words, counts.sentences, counts.graphemes
+                    )?;
+                    match &args.model {
+                        Some(_) => writeln!(writer, "\t{}", encoded),
+                        None => writeln!(writer),
+                    }
+                },
+            )?;
+            Ok(((total, documents, ids), total.tokens))
+        },
+    )?;
+    stdout.flush()?;
+    let mut total = Counts::default();
+    let (mut documents, mut ids) = (0, 0);
+    for (counts, file_documents, file_ids) in results {
+        documents += file_documents;
+        total.tokens += counts.tokens;
+        total.words += counts.words;
+        total.sentences += counts.sentences;
+        total.graphemes += counts.graphemes;
+        ids += file_ids;
+    }
+
+    // STEP 3: Print the totals (on stderr after the rows of --per-document)
+    let mut report = format!(
+        "documents: {:>12}\ntokens:    {:>12}\nwords:     {:>12}\nsentences: {:>12}\ngraphemes: {:>12}\n",
+        documents, total.tokens, total.words, total.sentences, total.graphemes
+    );
+    if args.model.is_some() {
+        report.push_str(&format!("ids:       {:>12}\n", ids));
+    }
+    if args.per_document {
+        eprint!("{}", report);
+    } else {
+        print!("{}", report);
+    }
+    Ok(())
+}
+
+/// How many pieces `build-vocab --model` learns without --max-vocabulary
+const DEFAULT_SUBWORD_PIECES: usize = 8000;
+
+/// The `build-vocab` command: counts the tokens of a corpus, adds the frequent ones to the
+/// vocabulary (or with --model learns BPE or WordPiece pieces from them) and saves the
+/// tokenizer (see Tokenizer::save()) for `count`, `detokenize` and `vocab`, or for
+/// Tokenizer::from_file() in a program
+fn build_vocab(args: BuildVocabArgs) -> io::Result<()> {
+    // STEP 1: Start from the special tokens of the model family
+    let tokenizer = with_family(Tokenizer::new(String::new()), args.special_tokens);
+
+    // STEP 2: Count the tokens of every document, one table per file
+    let tables = run_files(
+        inputs(args.input)?,
+        &args.jobs,
+        &mut io::sink(),
+        |_, file, _, progress| {
+            let mut table = FrequencyTable::new();
+            let mut tokens = 0;
+            for_each_document(
+                Mode::Lines,
+                args.field.as_deref(),
+                vec![file.to_string()],
+                progress,
+                |text| {
+                    let document = tokenizer.tokenize_text(text);
+                    tokens += document.len();
+                    progress.tokenized(document.len());
+                    table.add_tokens(&document);
+                    Ok(())
+                },
+            )?;
+            Ok((table, tokens))
+        },
+    )?;
+    let mut frequencies = FrequencyTable::new();
+    for table in tables {
+        frequencies.merge(table);
+    }
+
+    // STEP 3: Build the vocabulary (the pieces of a subword model are added to it by the
+    // encoder configuration) and save the tokenizer
+    let min_count = args.min_count.max(1);
+    let tokenizer = match args.model {
+        None => {
+            let mut vocabulary = tokenizer.vocabulary().clone();
+            vocabulary.add_frequent(&frequencies, min_count, args.max_vocabulary);
+            tokenizer.with_vocabulary(vocabulary)
+        }
+        Some(model) => {
+            let kind = match model {
+                SubwordArg::Bpe => SubwordKind::Bpe,
+                SubwordArg::Wordpiece => SubwordKind::WordPiece,
+            };
+            let pieces = args.max_vocabulary.unwrap_or(DEFAULT_SUBWORD_PIECES);
+            let model = SubwordModel::train(kind, &frequencies, min_count, pieces);
+            let config = tokenizer.encoder_config().clone().with_subwords(model);
+            tokenizer.with_encoder_config(config)
+        }
+    };
+    tokenizer
+        .save(&args.output)
+        .map_err(|error| io::Error::new(error.kind(), format!("{}: {}", args.output, error)))?;
+    eprintln!(
+        "built a vocabulary of {} tokens from {} documents, saved to {}",
+        tokenizer.vocabulary().len(),
+        frequencies.documents(),
+        args.output
+    );
+    Ok(())
+}
+
+/// The `vocab` command: prints the tokens of a saved tokenizer with their ids, the whole
+/// words of a published model's vocabulary, or how the two compare on a corpus
+fn vocab(args: VocabArgs) -> io::Result<()> {
+    let mut stdout = io::BufWriter::new(io::stdout().lock());
+
+    // A published model's vocabulary: its whole words, in alphabetical order
+    if args.external {
+        let external = load_external(&args.file)?;
+        let mut words: Vec<&str> = external.words().collect();
+        words.sort_unstable();
+        for word in words {
+            writeln!(stdout, "{}", word)?;
+        }
+        stdout.flush()?;
+        eprintln!(
+            "{} whole words of {} entries",
+            external.len(),
+            external.entries()
+        );
+        return Ok(());
+    }
+
+    // A saved tokenizer: "id<TAB>token" lines, or the overlap with a published model
+    let tokenizer = load_model(&args.file)?;
+    match &args.compare {
+        Some(file) => {
+            let external = load_external(file)?;
+            let mut corpus = Vec::new();
+            if !args.corpus.is_empty() {
+                let untracked = FileProgress::new(None, 0);
+                for_each_document(Mode::Lines, None, args.corpus, &untracked, |text| {
+                    corpus.extend(
+                        tokenizer
+                            .tokenize_text(text)
+                            .into_iter()
+                            .map(|token| token.text),
+                    );
+                    Ok(())
+                })?;
+            }
+            let report = overlap(tokenizer.vocabulary(), &external, &corpus, args.top);
+            write!(stdout, "{}", report)?;
+        }
+        None => {
+            for (id, token) in tokenizer.vocabulary().iter() {
+                writeln!(stdout, "{}\t{}", id, token)?;
+            }
+        }
+    }
+    stdout.flush()
+}
+
+/// The commands of the `repl` command, printed by :help
+const REPL_HELP: &str = "\
+Type a text to print its tokens (index, text, kind and byte offsets), or a command:
+  :mode lines|sentences|ptb    tokenize every line as a whole, sentence by sentence, or
+                               sentence by sentence with Penn Treebank tokens (\"do n't\")
+  :lowercase on|off            lowercase the tokens
+  :stem on|off                 replace the words by their Porter stem
+  :stopwords on|off            drop the English stopwords
+  :special bert|roberta|none   keep the special tokens of a model family whole
+  :highlight on|off            also print the line with its tokens colored by kind
+  :model FILE|none             also print the ids a tokenizer written by `build-vocab`
+                               encodes the line into (template included)
+  :options                     print the current options
+  :help                        print this help
+  :quit                        leave (or end of input)";
+
+/// How the `repl` command tokenizes a line
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum ReplMode {
+    /// The line as a whole
+    #[default]
+    Lines,
+    /// Every sentence of the line on its own
+    Sentences,
+    /// Every sentence on its own, with the tokens of the Penn Treebank (see PennTreebank)
+    Ptb,
+}
+
+/// The options of the `repl` command, changed by its commands
+#[derive(Debug, Clone, Default)]
+struct ReplOptions {
+    /// How lines are tokenized
+    mode: ReplMode,
+    /// Lowercase the tokens
+    lowercase: bool,
+    /// Stem the words
+    stem: bool,
+    /// Drop the stopwords
+    stopwords: bool,
+    /// Print the line colored by kind before its tokens
+    highlight: bool,
+    /// The special tokens of a model family
+    family: Option<Family>,
+    /// The tokenizer whose ids are shown
+    model: Option<String>,
+}
+
+impl ReplOptions {
+    /// Builds the tokenizer these options ask for
+    fn tokenizer(&self) -> io::Result<Tokenizer> {
+        let tokenizer = match &self.model {
+            Some(model) => load_model(model)?,
+            None => Tokenizer::new(String::new()),
+        };
+        Ok(with_family(tokenizer, self.family))
+    }
+
+    /// Builds the filters applied to the tokens of every line
+    fn filters(&self) -> FilterPipeline {
+        let mut filters = FilterPipeline::new();
+        // The contractions are split before the other filters see them
+        if self.mode == ReplMode::Ptb {
+            filters = filters.with_filter(PennTreebank);
+        }
+        if self.stopwords {
+            filters = filters.with_filter(StopwordFilter::english());
+        }
+        if self.lowercase {
+            filters = filters.with_filter(Lowercase);
+        }
+        if self.stem {
+            filters = filters.with_filter(PorterStemmer::new());
+        }
+        filters
+    }
+
+    /// Applies a `:name value` command (without its colon)
+    ///
+    /// # Returns
+    /// An error message for an unknown command or value
+    fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        let switch = |value: &str| match value {
+            "on" => Ok(true),
+            "off" => Ok(false),
+            _ => Err(format!(":{} takes on or off, not {:?}", name, value)),
+        };
+        match name {
+            "mode" => {
+                self.mode = match value {
+                    "lines" => ReplMode::Lines,
+                    "sentences" => ReplMode::Sentences,
+                    "ptb" => ReplMode::Ptb,
+                    _ => {
+                        return Err(format!(
+                            "unknown mode {:?} (lines, sentences or ptb)",
+                            value
+                        ))
+                    }
+                }
+            }
+            "lowercase" => self.lowercase = switch(value)?,
+            "stem" => self.stem = switch(value)?,
+            "stopwords" => self.stopwords = switch(value)?,
+            "highlight" => self.highlight = switch(value)?,
+            "special" => {
+                self.family = match value {
+                    "none" => None,
+                    name => Some(Family::from_str(name, true).map_err(|_| {
+                        format!("unknown model family {:?} (bert, roberta or none)", name)
+                    })?),
+                }
+            }
+            "model" => {
+                self.model = match value {
+                    "" => return Err(":model takes a file or none".to_string()),
+                    "none" => None,
+                    file => Some(file.to_string()),
+                }
+            }
+            _ => return Err(format!("unknown command :{} (:help lists them)", name)),
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for ReplOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let switch = |on: bool| if on { "on" } else { "off" };
+        write!(
+            f,
+            "mode {}  lowercase {}  stem {}  stopwords {}  special {}  highlight {}  model {}",
+            match self.mode {
+                ReplMode::Lines => "lines",
+                ReplMode::Sentences => "sentences",
+                ReplMode::Ptb => "ptb",
+            },
+            switch(self.lowercase),
+            switch(self.stem),
+            switch(self.stopwords),
+            match self.family {
+                Some(Family::Bert) => "bert",
+                Some(Family::Roberta) => "roberta",
+                None => "none",
+            },
+            switch(self.highlight),
+            self.model.as_deref().unwrap_or("none")
+        )
+    }
+}
+
+/// The `repl` command: tokenizes every line typed on stdin and prints its tokens, with
+/// commands (":lowercase on") that change the options between lines
+fn repl(args: ReplArgs) -> io::Result<()> {
+    // STEP 1: Build the tokenizer of the starting options
+    let mut options = ReplOptions {
+        family: args.special_tokens,
+        model: args.model,
+        ..ReplOptions::default()
+    };
+    let mut tokenizer = options.tokenizer()?;
+    let mut filters = options.filters();
+    let splitter = SentenceSplitter::new();
+
+    // STEP 2: Only prompt when a person is typing (not for a file piped in)
+    let interactive = io::stdin().is_terminal();
+    let mut stdout = io::stdout().lock();
+    if interactive {
+        writeln!(stdout, "{}\n:help lists the commands", options)?;
+    }
+    let mut line = String::new();
+    let mut stdin = io::stdin().lock();
+    loop {
+        if interactive {
+            write!(stdout, "> ")?;
+            stdout.flush()?;
+        }
+        line.clear();
+        if stdin.read_line(&mut line)? == 0 {
+            break;
+        }
+        let text = line.trim_end_matches(['\n', '\r']);
+
+        // STEP 3: Apply a command, keeping the previous options if the new ones fail
+        if let Some(command) = text.strip_prefix(':') {
+            let (name, value) = command
+                .trim()
+                .split_once(' ')
+                .unwrap_or((command.trim(), ""));
+            match name {
+                "quit" | "q" => break,
+                "help" => writeln!(stdout, "{}", REPL_HELP)?,
+                "options" => writeln!(stdout, "{}", options)?,
+                _ => {
+                    let mut changed = options.clone();
+                    let result = changed
+                        .set(name, value.trim())
+                        .and_then(|()| changed.tokenizer().map_err(|error| error.to_string()));
+                    match result {
+                        Ok(changed_tokenizer) => {
+                            tokenizer = changed_tokenizer;
+                            filters = changed.filters();
+                            options = changed;
+                            writeln!(stdout, "{}", options)?;
+                        }
+                        Err(message) => writeln!(stdout, "error: {}", message)?,
+                    }
+                }
+            }
+            continue;
+        }
+
+        // STEP 4: Print the tokens of the line (or of each of its sentences)
+        let spans = if options.mode != ReplMode::Lines {
+            splitter.split(text)
+        } else {
+            vec![(0, text.len())]
+        };
+        let mut tokens = Vec::new();
+        for (start, end) in spans {
+            tokens.extend(tokenizer.tokenize_text(&text[start..end]).into_iter().map(
+                |mut token| {
+                    token.start += start;
+                    token.end += start;
+                    token
+                },
+            ));
+        }
+        let tokens = filters.apply(tokens);
+        if options.highlight {
+            writeln!(stdout, "{}", colorize_kinds(text, &tokens))?;
+        }
+        let width = tokens
+            .iter()
+            .map(|token| token.text.chars().count())
+            .max()
+            .unwrap_or(0);
+        for (index, token) in tokens.iter().enumerate() {
+            writeln!(
+                stdout,
+                "{:>3}  {:<width$}  {:<11}  {}..{}",
+                index,
+                token.text,
+                token.kind.name(),
+                token.start,
+                token.end,
+                width = width
+            )?;
+        }
+
+        // The ids are those of the encoder on the line itself (the filters only change what
+        // is shown above), with the template and byte fallback pieces of the model
+        if options.model.is_some() {
+            let encoding = &tokenizer.encode_batch(&[text])[0];
+            let ids: Vec<String> = encoding.ids.iter().map(u32::to_string).collect();
+            writeln!(stdout, "ids  {}", ids.join(" "))?;
+            writeln!(stdout, "     {}", encoding.tokens.join(" "))?;
+        }
+    }
+    Ok(())
+}
+
+/// The `sample` command: prints a seeded random sample of the documents (lines, JSONL
+/// fields or URL bodies) or sentences of the given inputs, or of stdin when none is given
+fn sample(args: SampleArgs) -> io::Result<()> {
+    // STEP 1: Stream every input through one reservoir
+    let splitter = SentenceSplitter::new();
+    let mut reservoir = Reservoir::new(args.count, args.seed);
+    for file in inputs(args.input)? {
+        let mut source = open_source(&file, args.field.as_deref())?;
+        while let Some(document) = source.next_document()? {
+            if args.sentences {
+                for sentence in splitter.sentences(&document.text) {
+                    reservoir.push(sentence.to_string());
+                }
+            } else {
+                reservoir.push(document.text);
+            }
+        }
+    }
+
+    // STEP 2: Print the sample, in corpus order
+    let seen = reservoir.seen();
+    let mut stdout = io::BufWriter::new(io::stdout().lock());
+    for item in reservoir.into_sample() {
+        writeln!(stdout, "{}", item)?;
+    }
+    stdout.flush()?;
+    eprintln!("sampled {} of {}", args.count.min(seen), seen);
+    Ok(())
+}
+
+/// The `filter` command: splits the documents of the given inputs (or stdin) into sentences
+/// and prints the ones that pass every check, one per line (the dropped ones with --invert)
+///
+/// With --resume, every finished input file is recorded in the manifest, and a new run with
+/// the same manifest skips them and continues the --output file where the last run stopped.
+fn filter(args: FilterArgs) -> io::Result<()> {
+    // STEP 1: Set the filter up from the options
+    let mut filter = SentenceFilter::new()
+        .with_token_range(args.min_tokens, args.max_tokens)
+        .with_scripts(args.script)
+        .with_languages(args.language);
+    if let Some(ratio) = args.min_alpha {
+        filter = filter.with_min_alphabetic_ratio(ratio);
+    }
+    #[cfg(feature = "regex")]
+    {
+        for pattern in args.require {
+            filter = filter.with_required_pattern(pattern);
+        }
+        for pattern in args.forbid {
+            filter = filter.with_forbidden_pattern(pattern);
+        }
+    }
+
+    // STEP 2: Open the output, cut back to the end of the last finished file when resuming
+    // (a compressed stream can't be cut back, so a compressed output is always a new file)
+    let compressed = args
+        .output
+        .as_deref()
+        .is_some_and(|file| Compression::from_path(file) != Compression::None);
+    if compressed && args.resume.is_some() {
+        return Err(invalid_input(
+            "--resume can't append to a compressed --output".to_string(),
+        ));
+    }
+    let mut manifest = args
+        .resume
+        .as_deref()
+        .map(ResumeManifest::open)
+        .transpose()?;
+    let start = manifest.as_ref().map_or(0, ResumeManifest::output_end);
+    let mut output: Box<dyn Write> = match &args.output {
+        Some(file) if compressed => Box::new(
+            compress::create(file)
+                .map_err(|error| io::Error::new(error.kind(), format!("{}: {}", file, error)))?,
+        ),
+        Some(file) => {
+            let opened = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(file)
+                .map_err(|error| io::Error::new(error.kind(), format!("{}: {}", file, error)))?;
+            if opened.metadata()?.len() < start {
+                return Err(invalid_input(format!(
+                    "{} is shorter than the manifest says, delete the manifest to start over",
+                    file
+                )));
+            }
+            opened.set_len(start)?;
+            Box::new(io::BufWriter::new(opened))
+        }
+        None => Box::new(io::BufWriter::new(io::stdout().lock())),
+    };
+
+    // STEP 3: Check every sentence of every line, skipping the files a past run finished
+    let tokenizer = Tokenizer::new(String::new());
+    let splitter = SentenceSplitter::new();
+    let (mut kept, mut total, mut skipped) = (0, 0, 0);
+    let mut written = start;
+    for file in inputs(args.input)? {
+        let fingerprint = match &manifest {
+            Some(manifest) => {
+                if file == "-" || file.starts_with("http://") {
+                    return Err(invalid_input(format!("--resume needs files, not {}", file)));
+                }
+                let fingerprint = Fingerprint::of(&file).map_err(|error| {
+                    io::Error::new(error.kind(), format!("{}: {}", file, error))
+                })?;
+                match manifest.status(&file, &fingerprint) {
+                    FileStatus::Pending => Some(fingerprint),
+                    FileStatus::Done => {
+                        skipped += 1;
+                        continue;
+                    }
+                    FileStatus::Changed => {
+                        return Err(invalid_input(format!(
+                            "{} changed since it was filtered, delete the manifest to start over",
+                            file
+                        )))
+                    }
+                }
+            }
+            None => None,
         };
-        println!("  [{}] {} (type: {})", index, token, token_type);
+
+        let mut source = open_source(&file, args.field.as_deref())?;
+        let mut sentences = 0;
+        while let Some(document) = source.next_document()? {
+            for sentence in splitter.sentences(&document.text) {
+                sentences += 1;
+                let keep = filter.keeps(&tokenizer.tokenize_text(sentence));
+                kept += usize::from(keep);
+                if keep != args.invert {
+                    writeln!(output, "{}", sentence)?;
+                    written += sentence.len() as u64 + 1;
+                }
+            }
+        }
+        total += sentences;
+
+        // The output must be on disk before the file is recorded as finished
+        if let (Some(manifest), Some(fingerprint)) = (&mut manifest, fingerprint) {
+            output.flush()?;
+            manifest.mark_done(&file, fingerprint, sentences, written)?;
+        }
+    }
+    output.flush()?;
+    if skipped > 0 {
+        eprintln!("skipped {} files finished by a previous run", skipped);
     }
+    eprintln!("kept {} of {} sentences", kept, total);
+    Ok(())
+}
 
-    let reconstructed5 = tokenizer5.detokenize(&tokens5);
-    println!("\nReconstructed: \"{}\"", reconstructed5);
-    println!("Perfect match? {}\n", reconstructed5 == sentence5);
+/// The `bitext` command: reads aligned sentence pairs (two files, or one tab-separated
+/// file), prints the pairs that pass the filter as "source<TAB>target" and writes the
+/// others, with the reason, to the --rejected file
+fn bitext(args: BitextArgs) -> io::Result<()> {
+    // STEP 1: Set the filter up from the options
+    let mut filter = PairFilter::new()
+        .with_untranslated_dropped(args.drop_untranslated)
+        .with_placeholder_check(args.placeholders);
+    if let Some(max) = args.max_tokens {
+        filter = filter.with_max_tokens(max);
+    }
+    if let Some(ratio) = args.max_ratio {
+        filter = filter.with_max_length_ratio(ratio);
+    }
+    if let Some((min, max)) = args.token_ratio {
+        filter = filter.with_token_ratio(min, max);
+    }
+    if let Some(ratio) = args.min_number_overlap {
+        filter = filter.with_min_number_overlap(ratio);
+    }
 
-    // EXAMPLE 6: Edge case - sentence with extra spaces
-    println!("📌 EXAMPLE 6: Extra Spaces Handling");
-    println!("─────────────────────────────────────\n");
+    // STEP 2: Read the pairs, from two aligned files or one tab-separated file
+    let bitext = Bitext::new(Tokenizer::new(String::new()), Tokenizer::new(String::new()))
+        .with_placeholders(args.placeholders);
+    let pairs: Box<dyn Iterator<Item = io::Result<SentencePair>>> =
+        match (args.tsv, args.files.as_slice()) {
+            (Some(file), []) => Box::new(bitext.read_tsv(open_input(&file)?)),
+            (None, [source, target]) if source == "-" && target == "-" => {
+                return Err(invalid_input(
+                    "the SOURCE and the TARGET cannot both be stdin (-)".to_string(),
+                ))
+            }
+            (None, [source, target]) => {
+                Box::new(bitext.read_parallel(open_input(source)?, open_input(target)?))
+            }
+            _ => {
+                return Err(invalid_input(
+                    "give a SOURCE and a TARGET file, or one --tsv file".to_string(),
+                ))
+            }
+        };
 
-    // Note: Multiple spaces get collapsed into single space during tokenization
-    let sentence6 = "This  has   multiple    spaces.";
-    println!("Original sentence: \"{}\"", sentence6);
+    // STEP 3: Print the kept pairs, and write the dropped ones with their reason
+    let rejected: Box<dyn Write> = match args.rejected {
+        Some(file) => Box::new(
+            compress::create(&file)
+                .map_err(|error| io::Error::new(error.kind(), format!("{}: {}", file, error)))?,
+        ),
+        None => Box::new(io::sink()),
+    };
+    let mut writer = PairWriter::new(io::BufWriter::new(io::stdout().lock()), rejected)
+        .with_tokenized(args.tokenized);
+    for pair in pairs {
+        writer.write(&filter, &pair?)?;
+    }
+    writer.flush()?;
+    eprint!("{}", writer.summary());
+    Ok(())
+}
 
-    let tokenizer6 = Tokenizer::new(sentence6.to_string());
-    let tokens6 = tokenizer6.tokenize();
-    println!("Tokens: {:?}", tokens6);
+/// The `convert` command: reads tokenized documents in one format from the given files (or
+/// stdin) and prints them in another
+fn convert(args: ConvertArgs) -> io::Result<()> {
+    // STEP 1: Set the writer of the output format up
+    let to = args.to;
+    let stdout = io::BufWriter::new(io::stdout().lock());
+    let mut output: Box<dyn OutputWriter> = match to {
+        OutputFormat::Csv | OutputFormat::Tsv => {
+            let delimiter = if to == OutputFormat::Tsv { '\t' } else { ',' };
+            let csv = CsvWriter::new(stdout)
+                .with_delimiter(delimiter)
+                .with_quoting(args.quoting.unwrap_or_default());
+            match &args.columns {
+                Some(columns) => Box::new(csv.with_columns(columns)),
+                None => Box::new(csv),
+            }
+        }
+        _ if args.columns.is_some() || args.quoting.is_some() => {
+            return Err(invalid_input(
+                "--columns and --quoting are only for csv and tsv".to_string(),
+            ))
+        }
+        OutputFormat::Conll if args.sentences => {
+            Box::new(ConllWriter::new(stdout).with_sentences(SentenceSplitter::new()))
+        }
+        _ if args.sentences => {
+            return Err(invalid_input("--sentences is only for conll".to_string()))
+        }
+        _ => to.writer(stdout)?,
+    };
 
-    let reconstructed6 = tokenizer6.detokenize(&tokens6);
-    println!("Reconstructed: \"{}\"", reconstructed6);
-    println!("Note: Extra spaces are normalized to single spaces\n");
+    // STEP 2: Convert every input, in order
+    let mut documents = 0;
+    for file in inputs(args.input)? {
+        documents += convert::convert(open_input(&file)?, args.from, &mut output)
+            .map_err(|error| io::Error::new(error.kind(), format!("{}: {}", file, error)))?;
+    }
+    output.finish()?;
+    eprintln!("converted {} documents", documents);
+    Ok(())
+}
+
+/// Adds the special tokens and the template of a model family to a tokenizer
+fn with_family(tokenizer: Tokenizer, family: Option<Family>) -> Tokenizer {
+    match family {
+        Some(Family::Bert) => tokenizer
+            .with_special_tokens(SpecialTokens::bert())
+            .with_encoder_config(EncoderConfig::new().with_template(Template::bert())),
+        Some(Family::Roberta) => tokenizer
+            .with_special_tokens(SpecialTokens::roberta())
+            .with_encoder_config(EncoderConfig::new().with_template(Template::roberta())),
+        None => tokenizer,
+    }
+}
+
+/// Loads a tokenizer saved by `build-vocab`, naming the file in the error
+fn load_model(file: &str) -> io::Result<Tokenizer> {
+    Tokenizer::from_file(file)
+        .map_err(|error| io::Error::new(error.kind(), format!("{}: {}", file, error)))
+}
+
+/// Loads the vocabulary file of a published model, naming the file in the error
+fn load_external(file: &str) -> io::Result<ExternalVocab> {
+    ExternalVocab::load(file)
+        .map_err(|error| io::Error::new(error.kind(), format!("{}: {}", file, error)))
+}
 
-    // Print final summary
-    println!("╔════════════════════════════════════════╗");
-    println!("║        Tokenizer Examples Complete     ║");
-    println!("╚════════════════════════════════════════╝");
+/// Returns an InvalidData error (a malformed input)
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Returns an InvalidInput error (a bad command line)
+fn invalid_input(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+/// Parses a --script value
+fn script(name: &str) -> Result<Script, String> {
+    Script::from_name(name).ok_or_else(|| format!("unknown script: {}", name))
+}
+
+/// Parses a --language value
+fn language(code: &str) -> Result<Language, String> {
+    Language::from_code(code).ok_or_else(|| format!("unknown language code: {}", code))
+}
+
+/// Parses a --from value
+fn format(name: &str) -> Result<Format, String> {
+    Format::from_name(name).ok_or_else(|| format!("unknown input format: {}", name))
+}
+
+/// Parses a --to value
+fn output_format(name: &str) -> Result<OutputFormat, String> {
+    OutputFormat::from_name(name).ok_or_else(|| format!("unknown output format: {}", name))
+}
+
+/// Parses one name of a --columns list
+fn csv_column(name: &str) -> Result<CsvColumn, String> {
+    CsvColumn::from_name(name.trim()).ok_or_else(|| format!("unknown column: {}", name))
+}
+
+/// Parses a --quoting value
+fn csv_quoting(name: &str) -> Result<CsvQuoting, String> {
+    CsvQuoting::from_name(name).ok_or_else(|| format!("unknown quoting: {}", name))
+}
+
+/// Parses MIN:MAX bounds ("0.5:2")
+fn ratio_bounds(bounds: &str) -> Result<(f64, f64), String> {
+    bounds
+        .split_once(':')
+        .and_then(|(min, max)| Some((min.parse().ok()?, max.parse().ok()?)))
+        .ok_or_else(|| format!("invalid MIN:MAX bounds: {}", bounds))
+}
+
+/// Returns the input files of a command (see expand_inputs())
+fn inputs(args: InputArgs) -> io::Result<Vec<String>> {
+    Ok(expand_inputs(&args)?
+        .into_iter()
+        .map(|(file, _)| file)
+        .collect())
+}
+
+/// Returns the input files of a command, with their paths below the directory or pattern
+/// they were found in (their names for the files given as they are)
+///
+/// Directories are read recursively and glob patterns expanded, keeping the files that pass
+/// --include and --exclude, in path order. Stdin ("-") is the input when none is given, and
+/// stdin and URLs are kept as they are.
+fn expand_inputs(args: &InputArgs) -> io::Result<Vec<(String, PathBuf)>> {
+    let stdin = ["-".to_string()];
+    let files = if args.files.is_empty() {
+        &stdin[..]
+    } else {
+        &args.files[..]
+    };
+
+    let mut expanded = Vec::new();
+    for file in files {
+        let path = Path::new(file);
+        let corpus = if path.is_dir() {
+            Corpus::from_dir(path)
+        } else if file.contains(['*', '?', '[']) && !path.exists() {
+            Corpus::from_glob(file)
+        } else {
+            let name = path.file_name().map_or_else(|| file.into(), PathBuf::from);
+            expanded.push((file.clone(), name));
+            continue;
+        };
+        let corpus = corpus
+            .map_err(|error| io::Error::new(error.kind(), format!("{}: {}", file, error)))?
+            .with_include(&args.include)
+            .with_exclude(&args.exclude);
+        for error in corpus.walk_errors() {
+            eprintln!("warning: {}: {}", error.path.display(), error.error);
+        }
+        if corpus.files().is_empty() {
+            eprintln!("warning: no files to read in {}", file);
+        }
+        let root = corpus.root().unwrap_or(Path::new(""));
+        for path in corpus.files() {
+            let relative = path.strip_prefix(root).unwrap_or(path);
+            expanded.push((path.to_string_lossy().into_owned(), relative.to_path_buf()));
+        }
+    }
+    Ok(expanded)
+}
+
+/// Opens an input as a source of documents: the body of an http:// URL, the `field` of the
+/// JSONL records of a file, or the lines of a file ("-" is stdin)
+fn open_source(file: &str, field: Option<&str>) -> io::Result<Box<dyn InputSource>> {
+    if file.starts_with("http://") {
+        return Ok(Box::new(HttpSource::new([file])));
+    }
+    Ok(match field {
+        Some(field) => Box::new(JsonlFieldSource::open(file, field)?),
+        None => Box::new(LineSource::open(file)?),
+    })
+}
+
+/// Opens an input file for reading lines ("-" is stdin), decompressing .gz and .zst files
+fn open_input(file: &str) -> io::Result<Box<dyn BufRead>> {
+    if file == "-" {
+        return Ok(Box::new(io::stdin().lock()));
+    }
+    let opened = compress::open(file)
+        .map_err(|error| io::Error::new(error.kind(), format!("{}: {}", file, error)))?;
+    Ok(opened)
 }
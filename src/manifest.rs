@@ -0,0 +1,658 @@
+// Pipeline manifests: the whole processing configuration of a Tokenizer in one file
+// A manifest lists every processing step in the order it is applied (tokenization rules,
+// special tokens, added tokens, identifier detection, the token filters with their
+// position in the FilterPipeline, the sentence filter, vocabulary, encoding settings) with
+// all of its content and a content hash. Saving the manifest next to a paper's results
+// lets anyone rebuild the exact same Tokenizer and filters, and the hashes show at a
+// glance which step differs between two setups.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::encode_options::{Padding, PaddingSide, PaddingStrategy, TruncationStrategy};
+use crate::encoder::{EncoderConfig, Template, TemplatePiece, UnknownPolicy};
+use crate::filter::SentenceFilter;
+use crate::identifiers::IdentifierDetector;
+use crate::special::SpecialTokens;
+use crate::subword::{SubwordKind, SubwordModel};
+use crate::token_filter::FilterPipeline;
+use crate::vocab::Vocabulary;
+use crate::Tokenizer;
+
+/// The version of the manifest file format
+const FORMAT_VERSION: u32 = 1;
+
+/// The version of the tokenization rules (bumped whenever scan_spans() changes its output)
+pub const RULES_VERSION: u32 = 1;
+
+/// One processing step of a manifest
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManifestStep {
+    /// The name of the step, like "special_tokens" or "encoder"
+    pub name: String,
+    /// The settings of the step, one "key value" line each, in a fixed order
+    pub lines: Vec<String>,
+}
+
+impl ManifestStep {
+    /// Creates an empty step
+    pub(crate) fn new(name: &str) -> Self {
+        ManifestStep {
+            name: name.to_string(),
+            lines: Vec::new(),
+        }
+    }
+
+    /// Adds one "key value" line (text values go through escape() first)
+    pub(crate) fn push(&mut self, key: &str, value: impl fmt::Display) {
+        self.lines.push(format!("{} {}", key, value));
+    }
+
+    /// Returns the content hash of the step (64-bit FNV-1a of its name and lines)
+    ///
+    /// The hash only depends on the content, so it is the same on every machine and
+    /// with every Rust version.
+    pub fn hash(&self) -> u64 {
+        let mut hash = fnv1a(FNV_OFFSET, self.name.as_bytes());
+        for line in &self.lines {
+            hash = fnv1a(hash, b"\n");
+            hash = fnv1a(hash, line.as_bytes());
+        }
+        hash
+    }
+
+    /// Iterates over the (key, value) pairs of the step, with the values unescaped
+    pub(crate) fn entries(&self) -> impl Iterator<Item = (&str, String)> {
+        self.lines.iter().map(|line| {
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            (key, unescape(value))
+        })
+    }
+
+    /// Returns the value of the first line with the given key
+    pub(crate) fn value(&self, key: &str) -> Option<String> {
+        self.entries()
+            .find(|(name, _)| *name == key)
+            .map(|(_, value)| value)
+    }
+
+    /// Returns the values of every line with the given key, in order
+    pub(crate) fn values(&self, key: &str) -> Vec<String> {
+        self.entries()
+            .filter(|(name, _)| *name == key)
+            .map(|(_, value)| value)
+            .collect()
+    }
+}
+
+/// The complete, ordered description of a Tokenizer's processing pipeline
+///
+/// The text of the Tokenizer is not part of the manifest, only how it is processed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Manifest {
+    /// The processing steps, in the order they are applied
+    steps: Vec<ManifestStep>,
+}
+
+impl Manifest {
+    /// Describes the pipeline of a Tokenizer
+    ///
+    /// # Arguments
+    /// * `tokenizer` - The configured Tokenizer
+    ///
+    /// # Returns
+    /// The manifest, with one step per processing stage
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::encoder::{EncoderConfig, Template};
+    /// use tokenizer_rust::manifest::Manifest;
+    ///
+    /// let tokenizer = Tokenizer::new(String::new())
+    ///     .with_encoder_config(EncoderConfig::new().with_template(Template::bert()).with_max_length(128));
+    /// let manifest = Manifest::from_tokenizer(&tokenizer);
+    ///
+    /// let names: Vec<&str> = manifest.steps().iter().map(|step| step.name.as_str()).collect();
+    /// assert_eq!(names, vec!["tokenize", "special_tokens", "added_tokens", "identifiers", "vocabulary", "encoder"]);
+    /// ```
+    pub fn from_tokenizer(tokenizer: &Tokenizer) -> Self {
+        let mut steps = Vec::new();
+
+        // STEP 1: The tokenization rules themselves
+        let mut rules = ManifestStep::new("tokenize");
+        rules.push("rules", RULES_VERSION);
+        rules.push("structure_tokens", tokenizer.structure_tokens());
+        steps.push(rules);
+
+        // STEP 2: The tokens that are never split
+        let mut special = ManifestStep::new("special_tokens");
+        for token in tokenizer.special_tokens().iter() {
+            special.push("token", escape(token));
+        }
+        steps.push(special);
+
+        let mut added = ManifestStep::new("added_tokens");
+        for token in tokenizer.added_tokens() {
+            added.push("token", escape(token));
+        }
+        steps.push(added);
+
+        // STEP 3: Identifier detection
+        let mut identifiers = ManifestStep::new("identifiers");
+        match tokenizer.identifiers() {
+            Some(detector) => {
+                identifiers.push("enabled", true);
+                identifiers.push("min_length", detector.min_length());
+                identifiers.push("validators", detector.validator_count());
+            }
+            None => identifiers.push("enabled", false),
+        }
+        steps.push(identifiers);
+
+        // STEP 4: The token filters, with their positions, and the sentence filter
+        steps.extend(tokenizer.filters().steps());
+        if let Some(filter) = tokenizer.sentence_filter() {
+            steps.push(filter.describe());
+        }
+
+        // STEP 5: The vocabulary, in id order
+        let mut vocabulary = ManifestStep::new("vocabulary");
+        for (_, token) in tokenizer.vocabulary().iter() {
+            vocabulary.push("token", escape(token));
+        }
+        steps.push(vocabulary);
+
+        // STEP 6: The encoding settings
+        steps.push(encoder_step(tokenizer.encoder_config()));
+
+        Manifest { steps }
+    }
+
+    /// Returns the processing steps, in the order they are applied
+    pub fn steps(&self) -> &[ManifestStep] {
+        &self.steps
+    }
+
+    /// Adds the token filters applied after tokenization, one step per filter with its
+    /// position in the pipeline (replacing the filters already in the manifest)
+    ///
+    /// A filter that cannot describe itself (see TokenFilter::describe()) gets a
+    /// "custom_filter" step, and the manifest is then not reproducible.
+    ///
+    /// # Arguments
+    /// * `filters` - The filters, in the order they are applied
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::filter::SentenceFilter;
+    /// use tokenizer_rust::manifest::Manifest;
+    /// use tokenizer_rust::stem::PorterStemmer;
+    /// use tokenizer_rust::stopwords::StopwordFilter;
+    /// use tokenizer_rust::token_filter::{FilterPipeline, Lowercase, TokenFilter};
+    ///
+    /// let tokenizer = Tokenizer::new(String::new());
+    /// let pipeline = |stopwords: &[&str]| {
+    ///     FilterPipeline::new()
+    ///         .with_filter(Lowercase)
+    ///         .with_filter(StopwordFilter::new().with_words(stopwords))
+    ///         .with_filter(PorterStemmer::new())
+    /// };
+    /// let manifest = Manifest::from_tokenizer(&tokenizer).with_filters(&pipeline(&["the", "a"]));
+    /// let names: Vec<&str> = manifest.steps().iter().map(|step| step.name.as_str()).collect();
+    /// assert_eq!(names[4..7], ["lowercase", "stopwords", "porter_stemmer"]);
+    ///
+    /// // Another stopword list, or the same filters in another order, is another pipeline
+    /// let other_words = Manifest::from_tokenizer(&tokenizer).with_filters(&pipeline(&["the", "an"]));
+    /// assert_ne!(other_words.hash(), manifest.hash());
+    /// let reordered = FilterPipeline::new()
+    ///     .with_filter(StopwordFilter::new().with_words(["the", "a"]))
+    ///     .with_filter(Lowercase)
+    ///     .with_filter(PorterStemmer::new());
+    /// assert_ne!(Manifest::from_tokenizer(&tokenizer).with_filters(&reordered).hash(), manifest.hash());
+    ///
+    /// // And the filters are rebuilt from the manifest
+    /// let rebuilt = Manifest::parse(&manifest.to_string()).unwrap().to_filters().unwrap();
+    /// let tokens = rebuilt.apply(tokenizer.tokenize_text("The Runners"));
+    /// assert_eq!(tokens[0].text, "runner");
+    /// ```
+    pub fn with_filters(mut self, filters: &FilterPipeline) -> Self {
+        self.steps.retain(|step| !is_filter_step(step));
+        let at = self.filter_position();
+        self.steps.splice(at..at, filters.steps());
+        self
+    }
+
+    /// Adds the sentence filter that keeps or drops sentences by their tokens (replacing the
+    /// one already in the manifest)
+    ///
+    /// # Example
+    /// ```
+    /// # #[cfg(feature = "regex")]
+    /// # {
+    /// use regex::Regex;
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::filter::SentenceFilter;
+    /// use tokenizer_rust::manifest::Manifest;
+    ///
+    /// let tokenizer = Tokenizer::new(String::new());
+    /// let filter = |pattern: &str| {
+    ///     SentenceFilter::new()
+    ///         .with_token_range(3, 50)
+    ///         .with_forbidden_pattern(Regex::new(pattern).unwrap())
+    /// };
+    /// let manifest = Manifest::from_tokenizer(&tokenizer).with_sentence_filter(&filter("(?i)^cookies?$"));
+    ///
+    /// // Another regex is another pipeline
+    /// let other = Manifest::from_tokenizer(&tokenizer).with_sentence_filter(&filter("(?i)^javascript$"));
+    /// assert_ne!(other.hash(), manifest.hash());
+    ///
+    /// let rebuilt = Manifest::parse(&manifest.to_string()).unwrap().to_sentence_filter().unwrap();
+    /// assert!(!rebuilt.keeps(&tokenizer.tokenize_text("We use cookies here.")));
+    /// assert!(rebuilt.keeps(&tokenizer.tokenize_text("A plain sentence.")));
+    /// # }
+    /// ```
+    pub fn with_sentence_filter(mut self, filter: &SentenceFilter) -> Self {
+        self.steps.retain(|step| step.name != "sentence_filter");
+        let at = self.filter_position();
+        let after_filters = at
+            + self.steps[at..]
+                .iter()
+                .take_while(|step| is_filter_step(step))
+                .count();
+        self.steps.insert(after_filters, filter.describe());
+        self
+    }
+
+    /// Rebuilds the token filters of the manifest (an empty pipeline if there are none)
+    ///
+    /// # Returns
+    /// The filters in their order, or None if one cannot be rebuilt (a custom filter, or
+    /// a setting that cannot be read)
+    pub fn to_filters(&self) -> Option<FilterPipeline> {
+        let steps: Vec<&ManifestStep> = self
+            .steps
+            .iter()
+            .filter(|step| is_filter_step(step))
+            .collect();
+        FilterPipeline::from_steps(&steps)
+    }
+
+    /// Rebuilds the sentence filter of the manifest
+    ///
+    /// # Returns
+    /// The filter, or None if the manifest has none or if it cannot be rebuilt (a pattern
+    /// without the `regex` feature)
+    pub fn to_sentence_filter(&self) -> Option<SentenceFilter> {
+        SentenceFilter::from_step(self.step("sentence_filter")?)
+    }
+
+    /// Returns where the filter steps go: after identifier detection, before the vocabulary
+    fn filter_position(&self) -> usize {
+        self.steps
+            .iter()
+            .position(|step| step.name == "identifiers")
+            .map_or(self.steps.len(), |index| index + 1)
+    }
+
+    /// Returns the hash of the whole pipeline (computed from the hashes of the steps)
+    ///
+    /// Two Tokenizers with the same pipeline hash process text identically.
+    pub fn hash(&self) -> u64 {
+        self.steps.iter().fold(FNV_OFFSET, |hash, step| {
+            fnv1a(hash, &step.hash().to_le_bytes())
+        })
+    }
+
+    /// Returns the version of the tokenization rules the manifest was written with
+    pub fn rules_version(&self) -> Option<u32> {
+        self.step("tokenize")?.value("rules")?.parse().ok()
+    }
+
+    /// Returns false if the pipeline uses code that cannot be written to a file
+    ///
+    /// Identifier validators are Rust closures: the manifest records how many there are,
+    /// but to_tokenizer() cannot recreate them. The same goes for custom token filters,
+    /// which to_filters() cannot recreate.
+    pub fn is_reproducible(&self) -> bool {
+        let validators = self
+            .step("identifiers")
+            .and_then(|step| step.value("validators"))
+            .is_none_or(|count| count == "0");
+        validators && self.step("custom_filter").is_none()
+    }
+
+    /// Rebuilds a Tokenizer (without text) from the manifest
+    ///
+    /// # Returns
+    /// The Tokenizer, or None if a setting cannot be read
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::manifest::Manifest;
+    /// use tokenizer_rust::special::SpecialTokens;
+    ///
+    /// let mut tokenizer = Tokenizer::new(String::new()).with_special_tokens(SpecialTokens::roberta());
+    /// tokenizer.add_tokens(["<tool_call>"]);
+    /// let manifest = Manifest::from_tokenizer(&tokenizer);
+    ///
+    /// let rebuilt = manifest.to_tokenizer().unwrap();
+    /// assert_eq!(Manifest::from_tokenizer(&rebuilt).hash(), manifest.hash());
+    /// assert_eq!(rebuilt.tokenize_text("<s>Call <tool_call></s>").len(), 4);
+    /// ```
+    pub fn to_tokenizer(&self) -> Option<Tokenizer> {
+        // The vocabulary goes first so every token keeps its id
+        let mut vocab = Vocabulary::new();
+        for token in self.step("vocabulary")?.values("token") {
+            vocab.add_token(&token);
+        }
+
+        let mut special_tokens = SpecialTokens::new();
+        for token in self.step("special_tokens")?.values("token") {
+            special_tokens.register(&token);
+        }
+
+        let structure_tokens = self.step("tokenize")?.value("structure_tokens")?;
+        let mut tokenizer = Tokenizer::new(String::new())
+            .with_vocabulary(vocab)
+            .with_special_tokens(special_tokens)
+            .with_structure_tokens(structure_tokens.parse().ok()?);
+        tokenizer.add_tokens(self.step("added_tokens")?.values("token"));
+
+        let identifiers = self.step("identifiers")?;
+        if identifiers.value("enabled")?.parse::<bool>().ok()? {
+            let min_length = identifiers.value("min_length")?.parse().ok()?;
+            tokenizer =
+                tokenizer.with_identifiers(IdentifierDetector::new().with_min_length(min_length));
+        }
+
+        // Custom filters are Rust code, left out like identifier validators
+        let filters: Vec<&ManifestStep> = self
+            .steps
+            .iter()
+            .filter(|step| is_filter_step(step) && step.name != "custom_filter")
+            .collect();
+        tokenizer = tokenizer.with_filters(FilterPipeline::from_steps(&filters)?);
+        if let Some(step) = self.step("sentence_filter") {
+            tokenizer = tokenizer.with_sentence_filter(SentenceFilter::from_step(step)?);
+        }
+
+        Some(tokenizer.with_encoder_config(parse_encoder(self.step("encoder")?)?))
+    }
+
+    /// Reads a manifest written with `to_string()` (or save())
+    ///
+    /// # Returns
+    /// The manifest, or None if the text is not a manifest or if a hash does not match
+    /// the content (the file was edited or damaged)
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::manifest::Manifest;
+    ///
+    /// let manifest = Manifest::from_tokenizer(&Tokenizer::new(String::new()));
+    /// let text = manifest.to_string();
+    /// assert_eq!(Manifest::parse(&text), Some(manifest));
+    /// assert_eq!(Manifest::parse(&text.replace("rules 1", "rules 2")), None);
+    /// ```
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut lines = text.lines();
+        if lines.next()? != format!("tokenizer-manifest {}", FORMAT_VERSION) {
+            return None;
+        }
+        let pipeline_hash = parse_hash(lines.next()?.strip_prefix("pipeline ")?)?;
+
+        // Every "step <name> <hash>" line starts a new step
+        let mut steps: Vec<(ManifestStep, u64)> = Vec::new();
+        for line in lines {
+            if let Some(header) = line.strip_prefix("step ") {
+                let (name, hash) = header.split_once(' ')?;
+                steps.push((ManifestStep::new(name), parse_hash(hash)?));
+            } else {
+                steps.last_mut()?.0.lines.push(line.to_string());
+            }
+        }
+
+        if steps.iter().any(|(step, hash)| step.hash() != *hash) {
+            return None;
+        }
+        let manifest = Manifest {
+            steps: steps.into_iter().map(|(step, _)| step).collect(),
+        };
+        if manifest.hash() != pipeline_hash {
+            return None;
+        }
+
+        Some(manifest)
+    }
+
+    /// Writes the manifest to a file
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+
+    /// Reads a manifest from a file
+    ///
+    /// # Returns
+    /// The manifest, or an `InvalidData` error if the file is not a valid manifest
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        Manifest::parse(&text).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a tokenizer manifest, or its hashes do not match",
+            )
+        })
+    }
+
+    /// Returns the step with the given name
+    fn step(&self, name: &str) -> Option<&ManifestStep> {
+        self.steps.iter().find(|step| step.name == name)
+    }
+}
+
+impl fmt::Display for Manifest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "tokenizer-manifest {}", FORMAT_VERSION)?;
+        writeln!(f, "pipeline {:016x}", self.hash())?;
+        for step in &self.steps {
+            writeln!(f, "step {} {:016x}", step.name, step.hash())?;
+            for line in &step.lines {
+                writeln!(f, "{}", line)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Describes the encoding settings
+fn encoder_step(config: &EncoderConfig) -> ManifestStep {
+    let mut step = ManifestStep::new("encoder");
+
+    match config.unknown_policy() {
+        UnknownPolicy::Skip => step.push("unknown", "skip"),
+        UnknownPolicy::ByteFallback => step.push("unknown", "byte_fallback"),
+        UnknownPolicy::UnknownToken(token) => step.push("unknown_token", escape(token)),
+    }
+
+    if let Some(template) = config.template() {
+        step.push("template_single", describe_template(template.single()));
+        step.push("template_pair", describe_template(template.pair()));
+    }
+    if let Some(max_length) = config.max_length() {
+        step.push("max_length", max_length);
+    }
+    let truncation = match config.truncation_strategy() {
+        TruncationStrategy::LongestFirst => "longest_first",
+        TruncationStrategy::OnlyFirst => "only_first",
+        TruncationStrategy::OnlySecond => "only_second",
+    };
+    step.push("truncation", truncation);
+
+    if let Some(padding) = config.padding() {
+        match padding.strategy() {
+            PaddingStrategy::Fixed(length) => step.push("padding_fixed", length),
+            PaddingStrategy::BatchLongest => step.push("padding", "batch_longest"),
+        }
+        let side = match padding.side() {
+            PaddingSide::Right => "right",
+            PaddingSide::Left => "left",
+        };
+        step.push("padding_side", side);
+        step.push("pad_token", escape(padding.pad_token()));
+        step.push("pad_type_id", padding.pad_type_id());
+    }
+    if let Some(stride) = config.overflowing_stride() {
+        step.push("overflowing_stride", stride);
+    }
+    if let Some(model) = config.subwords() {
+        step.push("subwords", model.kind().name());
+        for piece in model.pieces() {
+            step.push("piece", escape(piece));
+        }
+        for (left, right) in model.merges() {
+            step.push("merge", escape(&format!("{} {}", left, right)));
+        }
+    }
+
+    step
+}
+
+/// Rebuilds the encoding settings from their step
+fn parse_encoder(step: &ManifestStep) -> Option<EncoderConfig> {
+    let mut config = EncoderConfig::new();
+
+    let policy = match (step.value("unknown"), step.value("unknown_token")) {
+        (_, Some(token)) => UnknownPolicy::UnknownToken(token),
+        (Some(policy), None) if policy == "byte_fallback" => UnknownPolicy::ByteFallback,
+        (Some(policy), None) if policy == "skip" => UnknownPolicy::Skip,
+        _ => return None,
+    };
+    config = config.with_unknown_policy(policy);
+
+    if let (Some(single), Some(pair)) = (step.value("template_single"), step.value("template_pair"))
+    {
+        config = config.with_template(Template::new(&single, &pair));
+    }
+    if let Some(max_length) = step.value("max_length") {
+        config = config.with_max_length(max_length.parse().ok()?);
+    }
+    let truncation = match step.value("truncation")?.as_str() {
+        "longest_first" => TruncationStrategy::LongestFirst,
+        "only_first" => TruncationStrategy::OnlyFirst,
+        "only_second" => TruncationStrategy::OnlySecond,
+        _ => return None,
+    };
+    config = config.with_truncation_strategy(truncation);
+
+    let strategy = match (step.value("padding_fixed"), step.value("padding")) {
+        (Some(length), _) => Some(PaddingStrategy::Fixed(length.parse().ok()?)),
+        (None, Some(padding)) if padding == "batch_longest" => Some(PaddingStrategy::BatchLongest),
+        (None, None) => None,
+        _ => return None,
+    };
+    if let Some(strategy) = strategy {
+        let side = match step.value("padding_side")?.as_str() {
+            "right" => PaddingSide::Right,
+            "left" => PaddingSide::Left,
+            _ => return None,
+        };
+        let padding = Padding::new(strategy)
+            .with_side(side)
+            .with_pad_token(&step.value("pad_token")?)
+            .with_pad_type_id(step.value("pad_type_id")?.parse().ok()?);
+        config = config.with_padding(padding);
+    }
+    if let Some(stride) = step.value("overflowing_stride") {
+        config = config.with_overflowing(stride.parse().ok()?);
+    }
+    if let Some(kind) = step.value("subwords") {
+        let merges = step
+            .values("merge")
+            .iter()
+            .map(|merge| {
+                let (left, right) = merge.split_once(' ')?;
+                Some((left.to_string(), right.to_string()))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        let model =
+            SubwordModel::from_parts(SubwordKind::from_name(&kind)?, step.values("piece"), merges);
+        config = config.with_subwords(model);
+    }
+
+    Some(config)
+}
+
+/// Writes template pieces back as a description like "[CLS]:0 $A:0 [SEP]:0"
+///
+/// Type ids are always written, so special tokens containing ':' are read back correctly.
+pub(crate) fn describe_template(pieces: &[TemplatePiece]) -> String {
+    pieces
+        .iter()
+        .map(|piece| match piece {
+            TemplatePiece::SequenceA(type_id) => format!("$A:{}", type_id),
+            TemplatePiece::SequenceB(type_id) => format!("$B:{}", type_id),
+            TemplatePiece::Special(token, type_id) => format!("{}:{}", token, type_id),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The FNV-1a 64-bit offset basis
+pub(crate) const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// Continues a 64-bit FNV-1a hash with some bytes
+pub(crate) fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    for &byte in bytes {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
+
+/// Reads a 16-digit hexadecimal hash
+fn parse_hash(text: &str) -> Option<u64> {
+    if text.len() != 16 {
+        return None;
+    }
+    u64::from_str_radix(text, 16).ok()
+}
+
+/// Returns true for the step of a token filter (its first line is its position)
+fn is_filter_step(step: &ManifestStep) -> bool {
+    step.lines
+        .first()
+        .is_some_and(|line| line.starts_with("position "))
+}
+
+/// Escapes the characters that would break the line-based format (backslash, newlines)
+pub(crate) fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+/// Undoes escape()
+fn unescape(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut characters = value.chars();
+    while let Some(character) = characters.next() {
+        if character != '\\' {
+            result.push(character);
+            continue;
+        }
+        match characters.next() {
+            Some('n') => result.push('\n'),
+            Some('r') => result.push('\r'),
+            Some(other) => result.push(other),
+            None => result.push('\\'),
+        }
+    }
+    result
+}
@@ -0,0 +1,319 @@
+// Compression metrics of a vocabulary on a corpus
+// How well a vocabulary fits a corpus is usually measured with two numbers:
+// - fertility: how many encoded tokens a word becomes (1.0 is perfect, byte fallback makes it explode)
+// - bytes per token: how much text one token carries (higher means better compression)
+// Both are also reported per writing system (script), because a vocabulary trained on English
+// text can look fine overall while splitting every Greek or Cyrillic word into bytes.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::Tokenizer;
+
+/// The writing system of a word
+///
+/// Only the scripts that matter most for vocabulary comparisons are told apart,
+/// every other alphabet is `Other`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Script {
+    /// Latin letters ("hello", "café")
+    Latin,
+    /// Greek letters
+    Greek,
+    /// Cyrillic letters
+    Cyrillic,
+    /// Hebrew letters
+    Hebrew,
+    /// Arabic letters
+    Arabic,
+    /// Devanagari (Hindi, Marathi, ...)
+    Devanagari,
+    /// Thai
+    Thai,
+    /// Korean Hangul
+    Hangul,
+    /// Japanese Hiragana and Katakana
+    Kana,
+    /// Chinese characters (also used in Japanese)
+    Han,
+    /// Any other alphabet
+    Other,
+    /// No letters at all (numbers, punctuation, symbols)
+    Common,
+}
+
+impl Script {
+    /// Returns the script of one character
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::metrics::Script;
+    ///
+    /// assert_eq!(Script::of('é'), Script::Latin);
+    /// assert_eq!(Script::of('ж'), Script::Cyrillic);
+    /// assert_eq!(Script::of('中'), Script::Han);
+    /// assert_eq!(Script::of('7'), Script::Common);
+    /// ```
+    pub fn of(character: char) -> Self {
+        match character as u32 {
+            0x41..=0x5A | 0x61..=0x7A | 0xC0..=0x24F | 0x1E00..=0x1EFF => Script::Latin,
+            0x370..=0x3FF | 0x1F00..=0x1FFF => Script::Greek,
+            0x400..=0x52F => Script::Cyrillic,
+            0x590..=0x5FF => Script::Hebrew,
+            0x600..=0x6FF | 0x750..=0x77F | 0xFB50..=0xFDFF | 0xFE70..=0xFEFF => Script::Arabic,
+            0x900..=0x97F => Script::Devanagari,
+            0xE00..=0xE7F => Script::Thai,
+            0x1100..=0x11FF | 0x3130..=0x318F | 0xAC00..=0xD7AF => Script::Hangul,
+            0x3040..=0x30FF => Script::Kana,
+            0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0x20000..=0x2FFFF => Script::Han,
+            _ if character.is_alphabetic() => Script::Other,
+            _ => Script::Common,
+        }
+    }
+
+    /// Returns the script of a word: the script most of its letters belong to
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::metrics::Script;
+    ///
+    /// assert_eq!(Script::of_word("naïve"), Script::Latin);
+    /// assert_eq!(Script::of_word("(2024)"), Script::Common);
+    /// ```
+    pub fn of_word(word: &str) -> Self {
+        let mut counts: BTreeMap<Script, usize> = BTreeMap::new();
+        for character in word.chars() {
+            let script = Script::of(character);
+            if script != Script::Common {
+                *counts.entry(script).or_insert(0) += 1;
+            }
+        }
+
+        // On a tie the first script in the enum order wins, so the result is deterministic
+        let mut best = Script::Common;
+        let mut best_count = 0;
+        for (script, count) in counts {
+            if count > best_count {
+                best = script;
+                best_count = count;
+            }
+        }
+        best
+    }
+
+    /// Returns the name of the script in lowercase, as from_name() reads it
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::metrics::Script;
+    ///
+    /// assert_eq!(Script::Devanagari.name(), "devanagari");
+    /// assert_eq!(Script::from_name(Script::Han.name()), Some(Script::Han));
+    /// ```
+    pub fn name(&self) -> &'static str {
+        match self {
+            Script::Latin => "latin",
+            Script::Greek => "greek",
+            Script::Cyrillic => "cyrillic",
+            Script::Hebrew => "hebrew",
+            Script::Arabic => "arabic",
+            Script::Devanagari => "devanagari",
+            Script::Thai => "thai",
+            Script::Hangul => "hangul",
+            Script::Kana => "kana",
+            Script::Han => "han",
+            Script::Other => "other",
+            Script::Common => "common",
+        }
+    }
+
+    /// Finds a script by its name, in any case ("latin", "Cyrillic", "HAN")
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::metrics::Script;
+    ///
+    /// assert_eq!(Script::from_name("cyrillic"), Some(Script::Cyrillic));
+    /// assert_eq!(Script::from_name("klingon"), None);
+    /// ```
+    pub fn from_name(name: &str) -> Option<Self> {
+        let script = match name.trim().to_lowercase().as_str() {
+            "latin" => Script::Latin,
+            "greek" => Script::Greek,
+            "cyrillic" => Script::Cyrillic,
+            "hebrew" => Script::Hebrew,
+            "arabic" => Script::Arabic,
+            "devanagari" => Script::Devanagari,
+            "thai" => Script::Thai,
+            "hangul" => Script::Hangul,
+            "kana" => Script::Kana,
+            "han" => Script::Han,
+            "other" => Script::Other,
+            "common" => Script::Common,
+            _ => return None,
+        };
+        Some(script)
+    }
+}
+
+/// Word, byte and token counts of one part of a corpus
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CompressionStats {
+    /// Number of whitespace-separated words
+    pub words: usize,
+    /// Number of UTF-8 bytes of those words
+    pub bytes: usize,
+    /// Number of encoded tokens (ids) of those words
+    pub tokens: usize,
+}
+
+impl CompressionStats {
+    /// Returns the fertility: the average number of tokens per word (0 without words)
+    pub fn fertility(&self) -> f64 {
+        if self.words == 0 {
+            0.0
+        } else {
+            self.tokens as f64 / self.words as f64
+        }
+    }
+
+    /// Returns the average number of bytes per token (0 without tokens)
+    pub fn bytes_per_token(&self) -> f64 {
+        if self.tokens == 0 {
+            0.0
+        } else {
+            self.bytes as f64 / self.tokens as f64
+        }
+    }
+
+    /// Adds the counts of one word
+    fn add(&mut self, bytes: usize, tokens: usize) {
+        self.words += 1;
+        self.bytes += bytes;
+        self.tokens += tokens;
+    }
+}
+
+/// Compression metrics of a corpus, overall and per script
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CompressionReport {
+    /// The whole corpus
+    pub total: CompressionStats,
+    /// The words of every script that appears in the corpus
+    pub by_script: BTreeMap<Script, CompressionStats>,
+}
+
+/// Measures how well the vocabulary of a Tokenizer compresses a corpus
+///
+/// Every text is encoded with encode_text() (no template, no truncation), so the counts
+/// depend on the vocabulary and on the unknown-token policy: with byte fallback an unknown
+/// word costs one token per byte, with `Skip` it costs nothing. Encoded tokens are counted
+/// for the whitespace-separated word they come from. Scripts written without spaces
+/// (Chinese, Japanese, Thai) count a whole phrase as one word.
+///
+/// # Arguments
+/// * `tokenizer` - The tokenizer with the vocabulary to evaluate
+/// * `texts` - The corpus
+///
+/// # Returns
+/// The fertility and bytes per token of the whole corpus and of every script
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::Tokenizer;
+/// use tokenizer_rust::encoder::{EncoderConfig, UnknownPolicy};
+/// use tokenizer_rust::metrics::{measure, Script};
+/// use tokenizer_rust::vocab::Vocabulary;
+///
+/// let english = Tokenizer::new("the cat sat .".to_string()).tokenize();
+/// let tokenizer = Tokenizer::new(String::new())
+///     .with_vocabulary(Vocabulary::from_tokens(&english))
+///     .with_encoder_config(EncoderConfig::new().with_unknown_policy(UnknownPolicy::ByteFallback));
+///
+/// let report = measure(&tokenizer, &["the cat sat.", "ο γάτος"]);
+/// assert_eq!(report.by_script[&Script::Latin].fertility(), 4.0 / 3.0);
+/// // Greek is unknown: 2 + 10 bytes, plus one separator between the two byte-encoded words
+/// assert_eq!(report.by_script[&Script::Greek].tokens, 13);
+/// assert!(report.by_script[&Script::Greek].bytes_per_token() < 1.0);
+/// ```
+pub fn measure(tokenizer: &Tokenizer, texts: &[&str]) -> CompressionReport {
+    let mut report = CompressionReport::default();
+
+    for text in texts {
+        // STEP 1: Find the words and encode the text
+        let words = word_spans(text);
+        let encoding = tokenizer.encode_text(text);
+
+        // STEP 2: Count the tokens of every word (by the start offset of the token)
+        let mut tokens_per_word = vec![0; words.len()];
+        for &(start, _) in &encoding.offsets {
+            let word = words.partition_point(|&(_, end)| end <= start);
+            if let Some(count) = tokens_per_word.get_mut(word) {
+                *count += 1;
+            }
+        }
+
+        // STEP 3: Add every word to the total and to its script
+        for (&(start, end), &tokens) in words.iter().zip(&tokens_per_word) {
+            let word = &text[start..end];
+            report.total.add(word.len(), tokens);
+            report
+                .by_script
+                .entry(Script::of_word(word))
+                .or_default()
+                .add(word.len(), tokens);
+        }
+    }
+
+    report
+}
+
+impl fmt::Display for CompressionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<12} {:>10} {:>10} {:>10} {:>16}",
+            "script", "words", "tokens", "fertility", "bytes per token"
+        )?;
+        let rows = self
+            .by_script
+            .iter()
+            .map(|(script, stats)| (format!("{:?}", script), stats))
+            .chain(std::iter::once(("total".to_string(), &self.total)));
+        for (name, stats) in rows {
+            writeln!(
+                f,
+                "{:<12} {:>10} {:>10} {:>10.2} {:>16.2}",
+                name,
+                stats.words,
+                stats.tokens,
+                stats.fertility(),
+                stats.bytes_per_token()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the (start, end) byte ranges of the whitespace-separated words of a text
+fn word_spans(text: &str) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut start = None;
+    for (position, character) in text.char_indices() {
+        match (character.is_whitespace(), start) {
+            (true, Some(word_start)) => {
+                spans.push((word_start, position));
+                start = None;
+            }
+            (false, None) => start = Some(position),
+            _ => {}
+        }
+    }
+    if let Some(word_start) = start {
+        spans.push((word_start, text.len()));
+    }
+    spans
+}
@@ -0,0 +1,159 @@
+// Memory-mapped file tokenization (only compiled with the `mmap` feature, on by default)
+// The file is mapped into memory instead of being read into a String: the operating system
+// loads pages on demand and can drop them again, so files larger than RAM work too.
+// Tokens borrow their text straight from the mapping, nothing is copied.
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+use memmap2::{Advice, Mmap};
+
+use crate::stream::{safe_cut, MAX_PENDING_BYTES};
+use crate::token::{TokenKind, TokenRef};
+use crate::tokenizer::scan_spans;
+use crate::Tokenizer;
+
+/// How many bytes are tokenized at a time
+const CHUNK_SIZE: usize = 1024 * 1024;
+
+/// A memory-mapped file ready to be tokenized, created by Tokenizer::tokenize_file()
+pub struct MappedText<'t> {
+    /// The tokenizer whose settings are used
+    tokenizer: &'t Tokenizer,
+    /// The mapped file
+    map: Mmap,
+}
+
+impl<'t> MappedText<'t> {
+    /// Maps a file into memory
+    ///
+    /// # Arguments
+    /// * `tokenizer` - The tokenizer whose settings are used
+    /// * `path` - The file to map
+    pub fn open<P: AsRef<Path>>(tokenizer: &'t Tokenizer, path: P) -> io::Result<Self> {
+        let file = File::open(path)?;
+
+        // SAFETY: the mapping is read-only. If another process truncates or rewrites the file
+        // while it is mapped, reads may fail or see the new content; this is the usual
+        // trade-off of memory mapping and the reason the file is only ever read sequentially.
+        let map = unsafe { Mmap::map(&file)? };
+
+        // The file is read from start to end, so the OS can read ahead and drop pages behind us
+        // (this is only a hint, so failing to give it is not an error)
+        let _ = map.advise(Advice::Sequential);
+
+        Ok(MappedText { tokenizer, map })
+    }
+
+    /// Returns the size of the file in bytes
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns true if the file is empty
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Iterates over the tokens of the file
+    ///
+    /// The file is tokenized one chunk at a time, as the iterator advances.
+    /// Invalid UTF-8 comes out as an `Err` item, after which the iterator stops.
+    pub fn tokens(&self) -> MappedTokens<'_> {
+        MappedTokens {
+            tokenizer: self.tokenizer,
+            bytes: &self.map,
+            position: 0,
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+/// An iterator over the borrowed tokens of a memory-mapped file
+pub struct MappedTokens<'a> {
+    /// The tokenizer whose settings are used
+    tokenizer: &'a Tokenizer,
+    /// The whole file
+    bytes: &'a [u8],
+    /// Where the next chunk starts
+    position: usize,
+    /// Tokens of the current chunk that were not returned yet
+    pending: VecDeque<TokenRef<'a>>,
+}
+
+impl<'a> MappedTokens<'a> {
+    /// Tokenizes the next chunk of the file
+    fn next_chunk(&mut self) -> io::Result<()> {
+        let rest = &self.bytes[self.position..];
+        let length = chunk_length(rest, self.tokenizer);
+
+        let chunk = std::str::from_utf8(&rest[..length])
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+        let offset = self.position;
+        self.position += length;
+
+        if self.tokenizer.is_plain() {
+            // Fast path: without special tokens or identifiers the scanner alone gives the
+            // tokens, so not a single String is allocated
+            let pending = &mut self.pending;
+            scan_spans(chunk, |start, end, kind: TokenKind| {
+                pending.push_back(TokenRef {
+                    text: &chunk[start..end],
+                    kind,
+                    start: offset + start,
+                    end: offset + end,
+                });
+            });
+        } else {
+            for token in self.tokenizer.tokenize_text(chunk) {
+                self.pending.push_back(TokenRef {
+                    text: &chunk[token.start..token.end],
+                    kind: token.kind,
+                    start: offset + token.start,
+                    end: offset + token.end,
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> Iterator for MappedTokens<'a> {
+    type Item = io::Result<TokenRef<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pending.is_empty() && self.position < self.bytes.len() {
+            if let Err(error) = self.next_chunk() {
+                // Stop after the first error
+                self.position = self.bytes.len();
+                return Some(Err(error));
+            }
+        }
+
+        self.pending.pop_front().map(Ok)
+    }
+}
+
+/// Decides how many bytes of the rest of the file are tokenized next
+///
+/// A chunk is about CHUNK_SIZE bytes and ends at a safe cut point (see stream::safe_cut()).
+/// If there is none, the chunk grows until one is found or the file ends.
+fn chunk_length(rest: &[u8], tokenizer: &Tokenizer) -> usize {
+    let mut size = CHUNK_SIZE;
+    loop {
+        if size >= rest.len() {
+            return rest.len();
+        }
+        if let Some(cut) = safe_cut(
+            &rest[..size],
+            size >= MAX_PENDING_BYTES,
+            &tokenizer.protected_tokens(),
+        ) {
+            return cut;
+        }
+        size *= 2;
+    }
+}
@@ -0,0 +1,359 @@
+// N-grams of token sequences
+// Feature extraction, language models and phrase mining all look at runs of n consecutive
+// tokens: bigrams ("new york"), trigrams ("out of the"), ... The functions here work on token
+// slices, and NGrams is an iterator adapter that does the same over any token iterator
+// (a streaming tokenizer included) while keeping only the last n tokens in memory.
+// Padding adds n - 1 boundary markers on each side, so the first and last tokens also
+// appear at every position of an n-gram ("<s> the", "end </s>").
+// Skip-grams relax n-grams for embedding training: the n tokens keep their order but up to
+// k tokens between them can be skipped ("insurgents ... ongoing").
+// Character n-grams work inside tokens, fastText-style: the word is wrapped in "<" and ">"
+// so prefixes and suffixes ("<wo", "rd>") differ from the same letters inside a word.
+
+use std::collections::VecDeque;
+
+use crate::token::{Token, TokenKind};
+
+/// Returns the n-grams of a token sequence, each joined with single spaces
+///
+/// n = 0 or a sequence shorter than n gives no n-grams.
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::ngrams::ngrams;
+///
+/// let tokens = ["the", "cat", "sat", "down"];
+/// assert_eq!(ngrams(&tokens, 2), vec!["the cat", "cat sat", "sat down"]);
+/// assert_eq!(ngrams(&tokens, 4), vec!["the cat sat down"]);
+/// assert!(ngrams(&tokens, 5).is_empty());
+/// ```
+pub fn ngrams<S: AsRef<str>>(tokens: &[S], n: usize) -> Vec<String> {
+    NGrams::new(tokens.iter().map(AsRef::as_ref), n)
+        .map(|gram| gram.join(" "))
+        .collect()
+}
+
+/// Returns the n-grams of a token sequence padded with n - 1 boundary markers on each side,
+/// each joined with single spaces
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::ngrams::padded_ngrams;
+///
+/// let tokens = ["the", "cat"];
+/// assert_eq!(
+///     padded_ngrams(&tokens, 3, "<s>", "</s>"),
+///     vec!["<s> <s> the", "<s> the cat", "the cat </s>", "cat </s> </s>"]
+/// );
+/// ```
+pub fn padded_ngrams<S: AsRef<str>>(tokens: &[S], n: usize, start: &str, end: &str) -> Vec<String> {
+    NGrams::new(tokens.iter().map(AsRef::as_ref), n)
+        .with_padding(start, end)
+        .map(|gram| gram.join(" "))
+        .collect()
+}
+
+/// Returns the n-grams of a slice as views into it, without copying any token
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::Tokenizer;
+/// use tokenizer_rust::ngrams::ngram_slices;
+///
+/// let tokens = Tokenizer::new(String::new()).tokenize_text("to be or not");
+/// let spans: Vec<(usize, usize)> = ngram_slices(&tokens, 3)
+///     .map(|gram| (gram[0].start, gram[2].end))
+///     .collect();
+/// assert_eq!(spans, vec![(0, 8), (3, 12)]);
+/// ```
+pub fn ngram_slices<T>(tokens: &[T], n: usize) -> impl Iterator<Item = &[T]> {
+    // slice::windows() panics on 0, which gives no n-grams here
+    tokens.windows(n.max(1)).filter(move |_| n > 0)
+}
+
+/// An iterator adapter yielding the n-grams of any token iterator
+///
+/// Every n-gram is a Vec of n cloned tokens. Only the last n tokens are kept in memory,
+/// so it also works on the streaming tokenizers.
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::Tokenizer;
+/// use tokenizer_rust::ngrams::NGrams;
+///
+/// let tokenizer = Tokenizer::new(String::new());
+/// let tokens = tokenizer
+///     .tokenize_reader("Hello,\nworld!".as_bytes())
+///     .map(|token| token.unwrap().text);
+///
+/// let bigrams: Vec<Vec<String>> = NGrams::new(tokens, 2).collect();
+/// assert_eq!(bigrams.len(), 3);
+/// assert_eq!(bigrams[1], vec![",", "world"]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct NGrams<I: Iterator> {
+    /// The tokens not read yet
+    tokens: I,
+    /// The size of the n-grams
+    n: usize,
+    /// The last tokens read (at most n)
+    window: VecDeque<I::Item>,
+    /// The end marker, when padding
+    end_marker: Option<I::Item>,
+    /// How many end markers are still to be added
+    end_markers_left: usize,
+}
+
+impl<I> NGrams<I>
+where
+    I: Iterator,
+    I::Item: Clone,
+{
+    /// Creates the adapter for n-grams of size `n` (n = 0 gives no n-grams)
+    pub fn new(tokens: I, n: usize) -> Self {
+        NGrams {
+            tokens,
+            n,
+            window: VecDeque::with_capacity(n),
+            end_marker: None,
+            end_markers_left: 0,
+        }
+    }
+
+    /// Pads the sequence with n - 1 `start` markers before the first token and n - 1
+    /// `end` markers after the last one
+    ///
+    /// An empty sequence then gives the n-grams made of markers only (one for bigrams).
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::ngrams::NGrams;
+    ///
+    /// let bigrams: Vec<Vec<&str>> = NGrams::new(["hi"].into_iter(), 2)
+    ///     .with_padding("<s>", "</s>")
+    ///     .collect();
+    /// assert_eq!(bigrams, vec![vec!["<s>", "hi"], vec!["hi", "</s>"]]);
+    /// ```
+    pub fn with_padding(mut self, start: I::Item, end: I::Item) -> Self {
+        let markers = self.n.saturating_sub(1);
+        self.window.clear();
+        self.window.extend(std::iter::repeat_n(start, markers));
+        self.end_marker = Some(end);
+        self.end_markers_left = markers;
+        self
+    }
+}
+
+impl<I> Iterator for NGrams<I>
+where
+    I: Iterator,
+    I::Item: Clone,
+{
+    type Item = Vec<I::Item>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.n == 0 {
+            return None;
+        }
+
+        loop {
+            // The next token, or an end marker once the tokens run out
+            let token = match self.tokens.next() {
+                Some(token) => token,
+                None => match &self.end_marker {
+                    Some(marker) if self.end_markers_left > 0 => {
+                        self.end_markers_left -= 1;
+                        marker.clone()
+                    }
+                    _ => return None,
+                },
+            };
+
+            if self.window.len() == self.n {
+                self.window.pop_front();
+            }
+            self.window.push_back(token);
+            if self.window.len() == self.n {
+                return Some(self.window.iter().cloned().collect());
+            }
+        }
+    }
+}
+
+/// Returns the skip-grams of a token sequence: n tokens in order, with at most k tokens
+/// skipped in total between them
+///
+/// The plain n-grams (nothing skipped) are included. Skip-grams are produced one at a time,
+/// grouped by first token, without building the whole list.
+///
+/// # Arguments
+/// * `tokens` - The token sequence
+/// * `n` - The number of tokens of every skip-gram (0 gives none)
+/// * `k` - The maximum number of skipped tokens
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::ngrams::skipgrams;
+///
+/// let tokens = ["insurgents", "killed", "in", "ongoing", "fighting"];
+/// let join = |gram: Vec<&&str>| gram.into_iter().copied().collect::<Vec<_>>().join(" ");
+///
+/// let pairs: Vec<String> = skipgrams(&tokens, 2, 2).map(join).collect();
+/// assert_eq!(pairs, vec![
+///     "insurgents killed", "insurgents in", "insurgents ongoing",
+///     "killed in", "killed ongoing", "killed fighting",
+///     "in ongoing", "in fighting",
+///     "ongoing fighting",
+/// ]);
+///
+/// // 2-skip-trigrams starting with the first token
+/// let first: Vec<String> = skipgrams(&tokens, 3, 2)
+///     .take_while(|gram| *gram[0] == "insurgents")
+///     .map(join)
+///     .collect();
+/// assert_eq!(first, vec![
+///     "insurgents killed in", "insurgents killed ongoing", "insurgents killed fighting",
+///     "insurgents in ongoing", "insurgents in fighting", "insurgents ongoing fighting",
+/// ]);
+/// ```
+pub fn skipgrams<T>(tokens: &[T], n: usize, k: usize) -> SkipGrams<'_, T> {
+    SkipGrams {
+        tokens,
+        n,
+        k,
+        indices: Vec::new(),
+    }
+}
+
+/// The iterator returned by skipgrams(), yielding each skip-gram as references to its tokens
+#[derive(Debug, Clone)]
+pub struct SkipGrams<'a, T> {
+    /// The token sequence
+    tokens: &'a [T],
+    /// The number of tokens of every skip-gram
+    n: usize,
+    /// The maximum number of skipped tokens
+    k: usize,
+    /// The token indices of the last skip-gram (empty before the first one)
+    indices: Vec<usize>,
+}
+
+impl<'a, T> Iterator for SkipGrams<'a, T> {
+    type Item = Vec<&'a T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (n, length) = (self.n, self.tokens.len());
+        if n == 0 || n > length {
+            return None;
+        }
+
+        if self.indices.is_empty() {
+            // The first skip-gram is the first n-gram
+            self.indices = (0..n).collect();
+        } else if !self.advance() {
+            // Every skip-gram of this first token was given: start at the next token
+            let first = self.indices[0] + 1;
+            if first + n > length {
+                return None;
+            }
+            self.indices = (first..first + n).collect();
+        }
+
+        Some(
+            self.indices
+                .iter()
+                .map(|&index| &self.tokens[index])
+                .collect(),
+        )
+    }
+}
+
+impl<T> SkipGrams<'_, T> {
+    /// Moves to the next skip-gram with the same first token (false if there is none)
+    ///
+    /// The indices after the first one go through every combination that ends at most
+    /// n - 1 + k tokens after the first, in lexicographic order.
+    fn advance(&mut self) -> bool {
+        let n = self.n;
+        let last = (self.indices[0] + n - 1 + self.k).min(self.tokens.len() - 1);
+        for position in (1..n).rev() {
+            // The highest index this position can take while leaving room for the next ones
+            if self.indices[position] < last - (n - 1 - position) {
+                self.indices[position] += 1;
+                for next in position + 1..n {
+                    self.indices[next] = self.indices[next - 1] + 1;
+                }
+                return true;
+            }
+        }
+        false
+    }
+}
+
+/// Returns the character n-grams of a word, for every n from `min_n` to `max_n`
+///
+/// With `markers`, the word is first wrapped in "<" and ">" like in fastText, and the
+/// markers alone are not n-grams. N-grams are counted in characters, not bytes, and are
+/// given shortest first, then in text order.
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::ngrams::char_ngrams;
+///
+/// assert_eq!(char_ngrams("word", 3, 3, true), vec!["<wo", "wor", "ord", "rd>"]);
+/// assert_eq!(char_ngrams("été", 2, 3, false), vec!["ét", "té", "été"]);
+/// assert_eq!(char_ngrams("a", 1, 2, true), vec!["a", "<a", "a>"]);
+/// ```
+pub fn char_ngrams(word: &str, min_n: usize, max_n: usize, markers: bool) -> Vec<String> {
+    let mut characters: Vec<char> = word.chars().collect();
+    if markers {
+        characters.insert(0, '<');
+        characters.push('>');
+    }
+
+    let mut grams = Vec::new();
+    for n in min_n.max(1)..=max_n.min(characters.len()) {
+        for gram in characters.windows(n) {
+            // The markers alone ("<", ">") say nothing about the word
+            if markers && n == 1 && (gram[0] == '<' || gram[0] == '>') {
+                continue;
+            }
+            grams.push(gram.iter().collect());
+        }
+    }
+    grams
+}
+
+/// Returns the character n-grams of every word token, as char_ngrams() does
+///
+/// The result has one entry per token, so it can be zipped with the tokens: numbers,
+/// punctuation and the other non-word tokens get no n-grams.
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::Tokenizer;
+/// use tokenizer_rust::ngrams::word_char_ngrams;
+///
+/// let tokens = Tokenizer::new(String::new()).tokenize_text("Go 2 it!");
+/// let grams = word_char_ngrams(&tokens, 3, 4, true);
+/// assert_eq!(grams.len(), 4);
+/// assert_eq!(grams[0], vec!["<Go", "Go>", "<Go>"]);
+/// assert!(grams[1].is_empty() && grams[3].is_empty());
+/// ```
+pub fn word_char_ngrams(
+    tokens: &[Token],
+    min_n: usize,
+    max_n: usize,
+    markers: bool,
+) -> Vec<Vec<String>> {
+    tokens
+        .iter()
+        .map(|token| {
+            if token.kind == TokenKind::Word {
+                char_ngrams(&token.text, min_n, max_n, markers)
+            } else {
+                Vec::new()
+            }
+        })
+        .collect()
+}
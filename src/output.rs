@@ -0,0 +1,1243 @@
+// The JSON record written for every tokenized document, and its schema version
+// JSON and JSONL outputs are read by other programs, so their shape is a contract: every
+// record carries the schema_version it was written with, and OutputRecord is the Rust side
+// of that contract. The crate writes and reads the records itself (no JSON dependency):
+// records are read key by key with the JsonCursor of json.rs, and unknown keys are skipped
+// so optional keys can be added within a schema version without breaking older readers.
+// The OutputWriter trait is the sink every output format implements (JSONL, CoNLL-U, CSV or
+// TSV, tokens per line, text, Arrow), so users can add their own without changing the crate.
+
+use std::borrow::Cow;
+use std::io::{self, Write};
+
+use crate::encoding::Encoding;
+use crate::json::{JsonCursor, MAX_JSON_DEPTH};
+use crate::sentences::SentenceSplitter;
+use crate::token::{Token, TokenKind};
+use crate::Tokenizer;
+
+/// The schema version of the records this crate writes
+///
+/// It changes when a key is removed, renamed or changes meaning. Adding an optional key
+/// does not change it.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// One tokenized document of the JSON outputs (one record per line in JSONL)
+///
+/// The keys of schema version 1, written in this order:
+/// - `schema_version` (integer, required): the SCHEMA_VERSION of the writer
+/// - `text` (string, optional): the original text of the document
+/// - `tokens` (array of strings, required): the tokens, in order
+/// - `kinds` (array of strings, optional): the TokenKind name of every token ("word", "number", ...)
+/// - `offsets` (array of `[start, end]` byte offsets into `text`, optional): one per token
+/// - `ids` (array of integers, optional): the vocabulary id of every token, for encoded documents
+///
+/// Records of a newer schema version than SCHEMA_VERSION are rejected when read, other
+/// unknown keys are ignored.
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::Tokenizer;
+/// use tokenizer_rust::output::{OutputRecord, SCHEMA_VERSION};
+///
+/// let text = "Hi, you";
+/// let record = OutputRecord::from_tokens(text, &Tokenizer::new(String::new()).tokenize_text(text));
+/// let json = record.to_json();
+/// assert_eq!(
+///     json,
+///     r#"{"schema_version":1,"text":"Hi, you","tokens":["Hi",",","you"],"kinds":["word","punctuation","word"],"offsets":[[0,2],[2,3],[4,7]]}"#
+/// );
+///
+/// let read = OutputRecord::from_json(&json).unwrap();
+/// assert_eq!(read, record);
+/// assert_eq!(read.schema_version, SCHEMA_VERSION);
+///
+/// // Only the tokens and the version are required
+/// let minimal = OutputRecord::from_json(r#"{"schema_version": 1, "tokens": ["a"], "lang": "en"}"#);
+/// assert_eq!(minimal.unwrap(), OutputRecord::new(vec!["a".to_string()]));
+/// assert!(OutputRecord::from_json(r#"{"schema_version": 2, "tokens": []}"#).is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutputRecord {
+    /// The schema version the record was written with
+    pub schema_version: u32,
+    /// The original text of the document, if known
+    pub text: Option<String>,
+    /// The tokens of the document
+    pub tokens: Vec<String>,
+    /// The kinds of the tokens, if known
+    pub kinds: Option<Vec<TokenKind>>,
+    /// The (start, end) byte offsets of the tokens in `text`, if known
+    pub offsets: Option<Vec<(usize, usize)>>,
+    /// The vocabulary ids of the tokens, if the document was encoded
+    pub ids: Option<Vec<u32>>,
+}
+
+impl OutputRecord {
+    /// Creates a record with only tokens (no text or offsets)
+    pub fn new(tokens: Vec<String>) -> Self {
+        OutputRecord {
+            schema_version: SCHEMA_VERSION,
+            text: None,
+            tokens,
+            kinds: None,
+            offsets: None,
+            ids: None,
+        }
+    }
+
+    /// Creates a record of a text and its tokens, with their kinds and offsets
+    pub fn from_tokens(text: &str, tokens: &[Token]) -> Self {
+        OutputRecord {
+            schema_version: SCHEMA_VERSION,
+            text: Some(text.to_string()),
+            tokens: tokens.iter().map(|token| token.text.clone()).collect(),
+            kinds: Some(tokens.iter().map(|token| token.kind).collect()),
+            offsets: Some(
+                tokens
+                    .iter()
+                    .map(|token| (token.start, token.end))
+                    .collect(),
+            ),
+            ids: None,
+        }
+    }
+
+    /// Creates a record of an encoded text: its tokens, their offsets and their ids
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::output::OutputRecord;
+    /// use tokenizer_rust::vocab::Vocabulary;
+    ///
+    /// let vocab = Vocabulary::from_tokens(&["Hi".to_string(), "!".to_string()]);
+    /// let tokenizer = Tokenizer::new(String::new()).with_vocabulary(vocab);
+    /// let record = OutputRecord::from_encoding("Hi!", &tokenizer.encode_text("Hi!"));
+    /// assert_eq!(
+    ///     record.to_json(),
+    ///     r#"{"schema_version":1,"text":"Hi!","tokens":["Hi","!"],"offsets":[[0,2],[2,3]],"ids":[0,1]}"#
+    /// );
+    /// assert_eq!(OutputRecord::from_json(&record.to_json()).unwrap(), record);
+    /// ```
+    pub fn from_encoding(text: &str, encoding: &Encoding) -> Self {
+        OutputRecord {
+            schema_version: SCHEMA_VERSION,
+            text: Some(text.to_string()),
+            tokens: encoding.tokens.clone(),
+            kinds: None,
+            offsets: Some(encoding.offsets.clone()),
+            ids: Some(encoding.ids.clone()),
+        }
+    }
+
+    /// Writes the record as one line of JSON (without the line ending)
+    pub fn to_json(&self) -> String {
+        let mut json = format!("{{\"schema_version\":{}", self.schema_version);
+        if let Some(text) = &self.text {
+            json.push_str(&format!(",\"text\":{}", json_string(text)));
+        }
+        let tokens: Vec<String> = self.tokens.iter().map(|token| json_string(token)).collect();
+        json.push_str(&format!(",\"tokens\":[{}]", tokens.join(",")));
+        if let Some(kinds) = &self.kinds {
+            let kinds: Vec<String> = kinds.iter().map(|kind| json_string(kind.name())).collect();
+            json.push_str(&format!(",\"kinds\":[{}]", kinds.join(",")));
+        }
+        if let Some(offsets) = &self.offsets {
+            let offsets: Vec<String> = offsets
+                .iter()
+                .map(|(start, end)| format!("[{},{}]", start, end))
+                .collect();
+            json.push_str(&format!(",\"offsets\":[{}]", offsets.join(",")));
+        }
+        if let Some(ids) = &self.ids {
+            let ids: Vec<String> = ids.iter().map(u32::to_string).collect();
+            json.push_str(&format!(",\"ids\":[{}]", ids.join(",")));
+        }
+        json.push('}');
+        json
+    }
+
+    /// Reads a record from one JSON object
+    ///
+    /// # Returns
+    /// The record, or an InvalidData error if the JSON is malformed, a required key is
+    /// missing, the schema version is newer than SCHEMA_VERSION, a kind is unknown or
+    /// the kinds, offsets or ids don't match the tokens
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::output::OutputRecord;
+    ///
+    /// let record = OutputRecord::from_json(r#"{"schema_version":1,"tokens":["a"],"extra":[[1]]}"#).unwrap();
+    /// assert_eq!(record.tokens, vec!["a"]);
+    ///
+    /// // Deeply nested values are an error, not a stack overflow
+    /// let nested = format!(r#"{{"schema_version":1,"tokens":[],"x":{}}}"#, "[".repeat(200_000));
+    /// assert!(OutputRecord::from_json(&nested).is_err());
+    /// ```
+    pub fn from_json(json: &str) -> io::Result<Self> {
+        read_record(json).map_err(|message| io::Error::new(io::ErrorKind::InvalidData, message))
+    }
+}
+
+/// A sink of tokenized documents
+///
+/// The crate writes records as JSONL, CoNLL-U, CSV, one token per line, text and (with the
+/// `arrow` feature) Arrow IPC. Implementing the trait sends them anywhere else (a message
+/// queue, a database) from the same code: the `convert` command and
+/// Tokenizer::write_batch() accept any OutputWriter.
+///
+/// # Example
+/// ```
+/// use std::io;
+/// use tokenizer_rust::Tokenizer;
+/// use tokenizer_rust::output::{OutputRecord, OutputWriter};
+///
+/// // A sink that only counts tokens
+/// #[derive(Default)]
+/// struct TokenCounter {
+///     tokens: usize,
+/// }
+///
+/// impl OutputWriter for TokenCounter {
+///     fn write_record(&mut self, record: &OutputRecord) -> io::Result<()> {
+///         self.tokens += record.tokens.len();
+///         Ok(())
+///     }
+///
+///     fn finish(&mut self) -> io::Result<()> {
+///         Ok(())
+///     }
+/// }
+///
+/// let mut counter = TokenCounter::default();
+/// let tokenizer = Tokenizer::new(String::new());
+/// tokenizer.write_batch(&["Hello, world!", "Bye."], &mut counter).unwrap();
+/// counter.finish().unwrap();
+/// assert_eq!(counter.tokens, 6);
+/// ```
+pub trait OutputWriter {
+    /// Writes one document
+    fn write_record(&mut self, record: &OutputRecord) -> io::Result<()>;
+
+    /// Writes everything still buffered (and the footer of formats that have one); called
+    /// once, after the last record
+    fn finish(&mut self) -> io::Result<()>;
+}
+
+impl<T: OutputWriter + ?Sized> OutputWriter for &mut T {
+    fn write_record(&mut self, record: &OutputRecord) -> io::Result<()> {
+        (**self).write_record(record)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        (**self).finish()
+    }
+}
+
+impl<T: OutputWriter + ?Sized> OutputWriter for Box<T> {
+    fn write_record(&mut self, record: &OutputRecord) -> io::Result<()> {
+        (**self).write_record(record)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        (**self).finish()
+    }
+}
+
+/// Writes one record per line as JSON (see OutputRecord for the keys)
+pub struct JsonlWriter<W> {
+    /// Where the records are written
+    writer: W,
+}
+
+impl<W: Write> JsonlWriter<W> {
+    /// Creates a JSONL writer
+    pub fn new(writer: W) -> Self {
+        JsonlWriter { writer }
+    }
+
+    /// Returns the underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> OutputWriter for JsonlWriter<W> {
+    fn write_record(&mut self, record: &OutputRecord) -> io::Result<()> {
+        writeln!(self.writer, "{}", record.to_json())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Writes records as CoNLL-U sentences: a "# text = ..." comment when the text is known,
+/// then one line per token with its ID and FORM (the other columns are "_", and MISC is
+/// "SpaceAfter=No" when the offsets show no space after the token), then a blank line
+///
+/// Records without tokens are not written. With with_sentences(), every record is split
+/// into sentences, each written as its own CoNLL-U sentence with a "# sent_id" comment: the
+/// vertical format sequence-labeling tools read.
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::Tokenizer;
+/// use tokenizer_rust::output::{ConllWriter, OutputRecord, OutputWriter};
+///
+/// let text = "Hi, you";
+/// let tokens = Tokenizer::new(String::new()).tokenize_text(text);
+/// let mut conll = ConllWriter::new(Vec::new());
+/// conll.write_record(&OutputRecord::from_tokens(text, &tokens)).unwrap();
+/// assert_eq!(
+///     String::from_utf8(conll.into_inner()).unwrap(),
+///     "# text = Hi, you\n\
+///      1\tHi\t_\t_\t_\t_\t_\t_\t_\tSpaceAfter=No\n\
+///      2\t,\t_\t_\t_\t_\t_\t_\t_\t_\n\
+///      3\tyou\t_\t_\t_\t_\t_\t_\t_\t_\n\n"
+/// );
+/// ```
+pub struct ConllWriter<W> {
+    /// Where the sentences are written
+    writer: W,
+    /// Splits every record into sentences
+    splitter: Option<SentenceSplitter>,
+    /// Number of records written, the first part of the sentence ids
+    documents: usize,
+}
+
+impl<W: Write> ConllWriter<W> {
+    /// Creates a CoNLL-U writer
+    pub fn new(writer: W) -> Self {
+        ConllWriter {
+            writer,
+            splitter: None,
+            documents: 0,
+        }
+    }
+
+    /// Splits every record into sentences with a SentenceSplitter
+    ///
+    /// The sentences are found in the text of the record and its tokens are assigned to
+    /// them by their offsets, so records without a text or offsets stay one sentence.
+    /// Every sentence gets a "# sent_id = <record>-<sentence>" comment (both counted from 1).
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::output::{ConllWriter, OutputRecord, OutputWriter};
+    /// use tokenizer_rust::sentences::SentenceSplitter;
+    ///
+    /// let text = "Dr. Who came. He saw!";
+    /// let tokens = Tokenizer::new(String::new()).tokenize_text(text);
+    /// let mut conll = ConllWriter::new(Vec::new()).with_sentences(SentenceSplitter::new());
+    /// conll.write_record(&OutputRecord::from_tokens(text, &tokens)).unwrap();
+    /// let output = String::from_utf8(conll.into_inner()).unwrap();
+    /// let blocks: Vec<&str> = output.split("\n\n").collect();
+    /// assert_eq!(blocks.len(), 3);
+    /// assert!(blocks[0].starts_with("# sent_id = 1-1\n# text = Dr. Who came.\n1\tDr\t"));
+    /// assert!(blocks[1].starts_with("# sent_id = 1-2\n# text = He saw!\n1\tHe\t"));
+    /// assert_eq!(blocks[1].lines().count(), 5);
+    /// ```
+    pub fn with_sentences(mut self, splitter: SentenceSplitter) -> Self {
+        self.splitter = Some(splitter);
+        self
+    }
+
+    /// Returns the underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// Writes one sentence: its comments, one line per token, then a blank line
+    fn write_sentence(
+        &mut self,
+        sent_id: Option<String>,
+        text: Option<&str>,
+        tokens: &[String],
+        offsets: &[(usize, usize)],
+    ) -> io::Result<()> {
+        if let Some(sent_id) = sent_id {
+            writeln!(self.writer, "# sent_id = {}", sent_id)?;
+        }
+        if let Some(text) = text {
+            let text: Vec<&str> = text.split_whitespace().collect();
+            writeln!(self.writer, "# text = {}", text.join(" "))?;
+        }
+        for (index, token) in tokens.iter().enumerate() {
+            // No space after the token if the next one starts where it ends
+            let joined = match (offsets.get(index), offsets.get(index + 1)) {
+                (Some(&(_, end)), Some(&(next, _))) => end == next,
+                _ => false,
+            };
+            let misc = if joined { "SpaceAfter=No" } else { "_" };
+            writeln!(
+                self.writer,
+                "{}\t{}{}\t{}",
+                index + 1,
+                token,
+                "\t_".repeat(7),
+                misc
+            )?;
+        }
+        writeln!(self.writer)
+    }
+}
+
+impl<W: Write> OutputWriter for ConllWriter<W> {
+    fn write_record(&mut self, record: &OutputRecord) -> io::Result<()> {
+        if record.tokens.is_empty() {
+            return Ok(());
+        }
+        self.documents += 1;
+        let offsets = record.offsets.as_deref().unwrap_or_default();
+
+        // STEP 1: Without a splitter (or the text and offsets), the record is one sentence
+        let (Some(splitter), Some(text), Some(_)) = (&self.splitter, &record.text, &record.offsets)
+        else {
+            return self.write_sentence(None, record.text.as_deref(), &record.tokens, offsets);
+        };
+
+        // STEP 2: Every token goes to the last sentence starting at or before it
+        let spans = splitter.split(text);
+        let mut sentence = 0;
+        let mut first_token = 0;
+        let mut groups = Vec::new();
+        for (index, &(start, _)) in offsets.iter().enumerate() {
+            while sentence + 1 < spans.len() && spans[sentence + 1].0 <= start {
+                if index > first_token {
+                    groups.push((sentence, first_token..index));
+                }
+                sentence += 1;
+                first_token = index;
+            }
+        }
+        groups.push((sentence, first_token..offsets.len()));
+
+        // STEP 3: Write every sentence that has tokens
+        for (number, (sentence, tokens)) in groups.into_iter().enumerate() {
+            let span = spans.get(sentence).map(|&(start, end)| &text[start..end]);
+            self.write_sentence(
+                Some(format!("{}-{}", self.documents, number + 1)),
+                span.or(Some(text)),
+                &record.tokens[tokens.clone()],
+                &offsets[tokens],
+            )?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// A column of the rows of a CsvWriter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CsvColumn {
+    /// The position of the record in the output ("doc_id")
+    DocId,
+    /// The position of the token in its record ("token_index")
+    TokenIndex,
+    /// The token ("token")
+    Token,
+    /// The TokenKind name of the token, empty when unknown ("kind")
+    Kind,
+    /// The byte offset where the token starts, empty when unknown ("start")
+    Start,
+    /// The byte offset where the token ends, empty when unknown ("end")
+    End,
+    /// The vocabulary id of the token, empty when the record is not encoded ("id")
+    Id,
+}
+
+impl CsvColumn {
+    /// Every column
+    pub const ALL: [CsvColumn; 7] = [
+        CsvColumn::DocId,
+        CsvColumn::TokenIndex,
+        CsvColumn::Token,
+        CsvColumn::Kind,
+        CsvColumn::Start,
+        CsvColumn::End,
+        CsvColumn::Id,
+    ];
+
+    /// The columns of a new CsvWriter: doc_id, token_index, token, kind, start and end
+    pub const DEFAULT: [CsvColumn; 6] = [
+        CsvColumn::DocId,
+        CsvColumn::TokenIndex,
+        CsvColumn::Token,
+        CsvColumn::Kind,
+        CsvColumn::Start,
+        CsvColumn::End,
+    ];
+
+    /// Returns the column with this name, as written in the header row
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::output::CsvColumn;
+    ///
+    /// assert_eq!(CsvColumn::from_name("token_index"), Some(CsvColumn::TokenIndex));
+    /// assert_eq!(CsvColumn::from_name("lemma"), None);
+    /// ```
+    pub fn from_name(name: &str) -> Option<CsvColumn> {
+        CsvColumn::ALL
+            .into_iter()
+            .find(|column| column.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Returns the name of the column, as written in the header row
+    pub fn name(&self) -> &'static str {
+        match self {
+            CsvColumn::DocId => "doc_id",
+            CsvColumn::TokenIndex => "token_index",
+            CsvColumn::Token => "token",
+            CsvColumn::Kind => "kind",
+            CsvColumn::Start => "start",
+            CsvColumn::End => "end",
+            CsvColumn::Id => "id",
+        }
+    }
+}
+
+/// When a CsvWriter puts a field between quotes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum CsvQuoting {
+    /// Only the fields that contain the delimiter, a quote or a line break (RFC 4180)
+    #[default]
+    Necessary,
+    /// Every field
+    Always,
+    /// Every field that is not a number (empty fields are not quoted either)
+    NonNumeric,
+    /// No field: backslashes, tabs, line breaks and the delimiter are escaped with a backslash
+    /// ("\t", "\n", "\r", "\\"), like the text format of PostgreSQL COPY
+    Never,
+}
+
+impl CsvQuoting {
+    /// Every quoting style
+    pub const ALL: [CsvQuoting; 4] = [
+        CsvQuoting::Necessary,
+        CsvQuoting::Always,
+        CsvQuoting::NonNumeric,
+        CsvQuoting::Never,
+    ];
+
+    /// Returns the style with this name ("necessary", "always", "non-numeric" or "never")
+    pub fn from_name(name: &str) -> Option<CsvQuoting> {
+        CsvQuoting::ALL
+            .into_iter()
+            .find(|quoting| quoting.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Returns the name of the style, as accepted by from_name()
+    pub fn name(&self) -> &'static str {
+        match self {
+            CsvQuoting::Necessary => "necessary",
+            CsvQuoting::Always => "always",
+            CsvQuoting::NonNumeric => "non-numeric",
+            CsvQuoting::Never => "never",
+        }
+    }
+
+    /// Writes a field with this quoting style
+    fn quote<'f>(&self, field: &'f str, delimiter: char, numeric: bool) -> Cow<'f, str> {
+        match self {
+            CsvQuoting::Necessary => csv_field(field, delimiter),
+            CsvQuoting::NonNumeric if numeric || field.is_empty() => csv_field(field, delimiter),
+            CsvQuoting::Always | CsvQuoting::NonNumeric => {
+                Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+            }
+            CsvQuoting::Never if field.contains([delimiter, '\\', '\t', '\n', '\r']) => {
+                let mut escaped = String::with_capacity(field.len() + 2);
+                for character in field.chars() {
+                    match character {
+                        '\\' => escaped.push_str("\\\\"),
+                        '\n' => escaped.push_str("\\n"),
+                        '\r' => escaped.push_str("\\r"),
+                        '\t' => escaped.push_str("\\t"),
+                        c if c == delimiter => {
+                            escaped.push('\\');
+                            escaped.push(c);
+                        }
+                        c => escaped.push(c),
+                    }
+                }
+                Cow::Owned(escaped)
+            }
+            CsvQuoting::Never => Cow::Borrowed(field),
+        }
+    }
+}
+
+/// Writes one CSV row per token, after a header row: by default doc_id, token_index, token,
+/// kind, start and end (the kind and offsets are empty when unknown)
+///
+/// The columns, the delimiter and the quoting are configurable, so the rows load straight
+/// into pandas (`pd.read_csv`) or a SQL table. By default, fields are quoted when they
+/// contain the delimiter, a quote or a line break.
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::Tokenizer;
+/// use tokenizer_rust::output::{CsvColumn, CsvQuoting, CsvWriter, OutputRecord, OutputWriter};
+///
+/// let text = "He said \"no\"";
+/// let tokens = Tokenizer::new(String::new()).tokenize_text(text);
+/// let mut csv = CsvWriter::new(Vec::new());
+/// csv.write_record(&OutputRecord::from_tokens(text, &tokens)).unwrap();
+/// csv.write_record(&OutputRecord::new(vec!["a,b".to_string()])).unwrap();
+/// let csv = String::from_utf8(csv.into_inner()).unwrap();
+/// assert_eq!(
+///     csv.lines().collect::<Vec<_>>(),
+///     vec![
+///         "doc_id,token_index,token,kind,start,end",
+///         "0,0,He,word,0,2",
+///         "0,1,said,word,3,7",
+///         "0,2,\"\"\"\",punctuation,8,9",
+///         "0,3,no,word,9,11",
+///         "0,4,\"\"\"\",punctuation,11,12",
+///         "1,0,\"a,b\",,,",
+///     ]
+/// );
+///
+/// // A TSV file of the tokens and their kinds, without quotes
+/// let text = "a\tb";
+/// let tokens = Tokenizer::new(String::new()).tokenize_text(text);
+/// let mut tsv = CsvWriter::new(Vec::new())
+///     .with_delimiter('\t')
+///     .with_columns(&[CsvColumn::Token, CsvColumn::Kind])
+///     .with_quoting(CsvQuoting::Never);
+/// tsv.write_record(&OutputRecord::from_tokens(text, &tokens)).unwrap();
+/// tsv.write_record(&OutputRecord::new(vec!["x\ty".to_string()])).unwrap();
+/// assert_eq!(
+///     String::from_utf8(tsv.into_inner()).unwrap(),
+///     "token\tkind\na\tword\nb\tword\nx\\ty\t\n"
+/// );
+/// ```
+pub struct CsvWriter<W> {
+    /// Where the rows are written
+    writer: W,
+    /// The field separator
+    delimiter: char,
+    /// The columns of every row
+    columns: Vec<CsvColumn>,
+    /// When fields are quoted
+    quoting: CsvQuoting,
+    /// Write the header row
+    header: bool,
+    /// Number of records written, the doc_id column
+    documents: usize,
+    /// Whether the header row was written
+    header_written: bool,
+}
+
+impl<W: Write> CsvWriter<W> {
+    /// Creates a comma-separated writer with the default columns
+    pub fn new(writer: W) -> Self {
+        CsvWriter {
+            writer,
+            delimiter: ',',
+            columns: CsvColumn::DEFAULT.to_vec(),
+            quoting: CsvQuoting::default(),
+            header: true,
+            documents: 0,
+            header_written: false,
+        }
+    }
+
+    /// Sets the field separator ('\t' for TSV)
+    pub fn with_delimiter(mut self, delimiter: char) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Sets the columns of every row, in order
+    pub fn with_columns(mut self, columns: &[CsvColumn]) -> Self {
+        self.columns = columns.to_vec();
+        self
+    }
+
+    /// Sets when fields are quoted
+    pub fn with_quoting(mut self, quoting: CsvQuoting) -> Self {
+        self.quoting = quoting;
+        self
+    }
+
+    /// Writes the header row or not (on by default)
+    pub fn with_header(mut self, enabled: bool) -> Self {
+        self.header = enabled;
+        self
+    }
+
+    /// Returns the columns of every row
+    pub fn columns(&self) -> &[CsvColumn] {
+        &self.columns
+    }
+
+    /// Returns the underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// Writes one row of (field, is numeric) pairs, quoting the fields that need it
+    fn write_row(&mut self, fields: &[(&str, bool)]) -> io::Result<()> {
+        let delimiter = self.delimiter.to_string();
+        let fields: Vec<Cow<str>> = fields
+            .iter()
+            .map(|&(field, numeric)| self.quoting.quote(field, self.delimiter, numeric))
+            .collect();
+        writeln!(self.writer, "{}", fields.join(&delimiter))
+    }
+
+    /// Writes the header row before the first row
+    fn write_header(&mut self) -> io::Result<()> {
+        if self.header && !self.header_written {
+            self.header_written = true;
+            let names: Vec<(&str, bool)> = self
+                .columns
+                .iter()
+                .map(|column| (column.name(), false))
+                .collect();
+            self.write_row(&names)?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> OutputWriter for CsvWriter<W> {
+    fn write_record(&mut self, record: &OutputRecord) -> io::Result<()> {
+        self.write_header()?;
+        let document = self.documents.to_string();
+        self.documents += 1;
+        for index in 0..record.tokens.len() {
+            let fields: Vec<(Cow<str>, bool)> = self
+                .columns
+                .iter()
+                .map(|&column| csv_column(column, record, index, &document))
+                .collect();
+            let fields: Vec<(&str, bool)> = fields
+                .iter()
+                .map(|(field, numeric)| (field.as_ref(), *numeric))
+                .collect();
+            self.write_row(&fields)?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        // An output without records still has its header
+        self.write_header()?;
+        self.writer.flush()
+    }
+}
+
+/// Returns the field of a column for the token at `index` of a record, and whether it is a number
+fn csv_column<'r>(
+    column: CsvColumn,
+    record: &'r OutputRecord,
+    index: usize,
+    document: &'r str,
+) -> (Cow<'r, str>, bool) {
+    let offsets = record
+        .offsets
+        .as_ref()
+        .and_then(|offsets| offsets.get(index));
+    let number = |value: Option<String>| (Cow::Owned(value.unwrap_or_default()), true);
+    match column {
+        CsvColumn::DocId => (Cow::Borrowed(document), true),
+        CsvColumn::TokenIndex => number(Some(index.to_string())),
+        CsvColumn::Token => (Cow::Borrowed(record.tokens[index].as_str()), false),
+        CsvColumn::Kind => {
+            let kind = record.kinds.as_ref().and_then(|kinds| kinds.get(index));
+            (Cow::Borrowed(kind.map_or("", TokenKind::name)), false)
+        }
+        CsvColumn::Start => number(offsets.map(|(start, _)| start.to_string())),
+        CsvColumn::End => number(offsets.map(|(_, end)| end.to_string())),
+        CsvColumn::Id => number(
+            record
+                .ids
+                .as_ref()
+                .and_then(|ids| ids.get(index))
+                .map(u32::to_string),
+        ),
+    }
+}
+
+/// Writes one token per line, with a blank line after each record
+///
+/// Records without tokens are not written (a blank line separates records).
+pub struct LinesWriter<W> {
+    /// Where the tokens are written
+    writer: W,
+}
+
+impl<W: Write> LinesWriter<W> {
+    /// Creates a token-per-line writer
+    pub fn new(writer: W) -> Self {
+        LinesWriter { writer }
+    }
+
+    /// Returns the underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> OutputWriter for LinesWriter<W> {
+    fn write_record(&mut self, record: &OutputRecord) -> io::Result<()> {
+        if record.tokens.is_empty() {
+            return Ok(());
+        }
+        for token in &record.tokens {
+            writeln!(self.writer, "{}", token)?;
+        }
+        writeln!(self.writer)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Writes every record as one line of detokenized text
+pub struct TextWriter<W> {
+    /// Where the lines are written
+    writer: W,
+    /// Detokenizes the records
+    tokenizer: Tokenizer,
+}
+
+impl<W: Write> TextWriter<W> {
+    /// Creates a text writer
+    pub fn new(writer: W) -> Self {
+        TextWriter {
+            writer,
+            tokenizer: Tokenizer::new(String::new()),
+        }
+    }
+
+    /// Returns the underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> OutputWriter for TextWriter<W> {
+    fn write_record(&mut self, record: &OutputRecord) -> io::Result<()> {
+        writeln!(self.writer, "{}", self.tokenizer.detokenize(&record.tokens))
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Writes records as an Arrow IPC file (readable with pyarrow, pandas or polars), one row
+/// per record with the columns schema_version, text, tokens and offsets
+///
+/// Rows are buffered and written in batches of 1024; finish() writes the last batch and the
+/// file footer.
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::Tokenizer;
+/// use tokenizer_rust::output::{ArrowWriter, OutputRecord, OutputWriter};
+///
+/// let text = "Hi, you";
+/// let tokens = Tokenizer::new(String::new()).tokenize_text(text);
+/// let mut arrow = ArrowWriter::new(Vec::new()).unwrap();
+/// arrow.write_record(&OutputRecord::from_tokens(text, &tokens)).unwrap();
+/// arrow.write_record(&OutputRecord::new(vec!["a".to_string()])).unwrap();
+/// let file = arrow.into_inner().unwrap();
+/// assert!(file.starts_with(b"ARROW1"));
+/// ```
+#[cfg(feature = "arrow")]
+pub struct ArrowWriter<W: Write> {
+    /// The IPC file writer
+    writer: arrow_ipc::writer::FileWriter<W>,
+    /// The columns of the rows not written yet
+    columns: ArrowColumns,
+}
+
+/// The column builders of an ArrowWriter
+#[cfg(feature = "arrow")]
+struct ArrowColumns {
+    /// Rows in the builders
+    rows: usize,
+    schema_version: arrow_array::builder::UInt32Builder,
+    text: arrow_array::builder::StringBuilder,
+    tokens: arrow_array::builder::ListBuilder<arrow_array::builder::StringBuilder>,
+    offsets: arrow_array::builder::ListBuilder<
+        arrow_array::builder::FixedSizeListBuilder<arrow_array::builder::UInt64Builder>,
+    >,
+}
+
+#[cfg(feature = "arrow")]
+impl<W: Write> ArrowWriter<W> {
+    /// Rows per record batch
+    const BATCH_ROWS: usize = 1024;
+
+    /// Creates an Arrow writer (the file header is written at once)
+    pub fn new(writer: W) -> io::Result<Self> {
+        let writer = arrow_ipc::writer::FileWriter::try_new(writer, &Self::schema())
+            .map_err(io::Error::other)?;
+        Ok(ArrowWriter {
+            writer,
+            columns: ArrowColumns::new(),
+        })
+    }
+
+    /// Finishes the file and returns the underlying writer
+    pub fn into_inner(mut self) -> io::Result<W> {
+        self.finish()?;
+        self.writer.into_inner().map_err(io::Error::other)
+    }
+
+    /// The schema of the rows
+    fn schema() -> arrow_schema::Schema {
+        use arrow_schema::{DataType, Field, Schema};
+
+        let pair = DataType::FixedSizeList(Field::new_list_field(DataType::UInt64, true).into(), 2);
+        Schema::new(vec![
+            Field::new("schema_version", DataType::UInt32, false),
+            Field::new("text", DataType::Utf8, true),
+            Field::new_list("tokens", Field::new_list_field(DataType::Utf8, true), false),
+            Field::new_list("offsets", Field::new_list_field(pair, true), true),
+        ])
+    }
+
+    /// Writes the buffered rows as one record batch
+    fn write_batch(&mut self) -> io::Result<()> {
+        use std::sync::Arc;
+
+        if self.columns.rows == 0 {
+            return Ok(());
+        }
+        let columns = std::mem::replace(&mut self.columns, ArrowColumns::new());
+        let schema = Arc::new(Self::schema());
+        let batch = arrow_array::RecordBatch::try_new(schema, columns.into_arrays())
+            .map_err(io::Error::other)?;
+        self.writer.write(&batch).map_err(io::Error::other)
+    }
+}
+
+#[cfg(feature = "arrow")]
+impl ArrowColumns {
+    /// Creates empty builders
+    fn new() -> Self {
+        use arrow_array::builder::{
+            FixedSizeListBuilder, ListBuilder, StringBuilder, UInt64Builder,
+        };
+
+        ArrowColumns {
+            rows: 0,
+            schema_version: Default::default(),
+            text: StringBuilder::new(),
+            tokens: ListBuilder::new(StringBuilder::new()),
+            offsets: ListBuilder::new(FixedSizeListBuilder::new(UInt64Builder::new(), 2)),
+        }
+    }
+
+    /// Adds the row of a record
+    fn push(&mut self, record: &OutputRecord) {
+        self.rows += 1;
+        self.schema_version.append_value(record.schema_version);
+        self.text.append_option(record.text.as_deref());
+        for token in &record.tokens {
+            self.tokens.values().append_value(token);
+        }
+        self.tokens.append(true);
+        match &record.offsets {
+            Some(offsets) => {
+                for &(start, end) in offsets {
+                    let pair = self.offsets.values();
+                    pair.values().append_value(start as u64);
+                    pair.values().append_value(end as u64);
+                    pair.append(true);
+                }
+                self.offsets.append(true);
+            }
+            None => self.offsets.append(false),
+        }
+    }
+
+    /// Turns the builders into the arrays of a batch, in the order of the schema
+    fn into_arrays(mut self) -> Vec<arrow_array::ArrayRef> {
+        use std::sync::Arc;
+
+        vec![
+            Arc::new(self.schema_version.finish()),
+            Arc::new(self.text.finish()),
+            Arc::new(self.tokens.finish()),
+            Arc::new(self.offsets.finish()),
+        ]
+    }
+}
+
+#[cfg(feature = "arrow")]
+impl<W: Write> OutputWriter for ArrowWriter<W> {
+    fn write_record(&mut self, record: &OutputRecord) -> io::Result<()> {
+        self.columns.push(record);
+        if self.columns.rows >= Self::BATCH_ROWS {
+            self.write_batch()?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.write_batch()?;
+        self.writer.finish().map_err(io::Error::other)
+    }
+}
+
+/// The output formats of OutputFormat::writer()
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OutputFormat {
+    /// JsonlWriter
+    Jsonl,
+    /// ConllWriter
+    Conll,
+    /// CsvWriter
+    Csv,
+    /// CsvWriter with tabs between the fields
+    Tsv,
+    /// LinesWriter
+    Lines,
+    /// TextWriter
+    Text,
+    /// ArrowWriter (with the `arrow` feature)
+    #[cfg(feature = "arrow")]
+    Arrow,
+}
+
+impl OutputFormat {
+    /// Every output format
+    pub const ALL: &'static [OutputFormat] = &[
+        OutputFormat::Jsonl,
+        OutputFormat::Conll,
+        OutputFormat::Csv,
+        OutputFormat::Tsv,
+        OutputFormat::Lines,
+        OutputFormat::Text,
+        #[cfg(feature = "arrow")]
+        OutputFormat::Arrow,
+    ];
+
+    /// Returns the format with this name ("jsonl", "conll", "csv", "tsv", "lines", "text" or
+    /// "arrow")
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::output::OutputFormat;
+    ///
+    /// assert_eq!(OutputFormat::from_name("csv"), Some(OutputFormat::Csv));
+    /// assert_eq!(OutputFormat::from_name("xml"), None);
+    /// ```
+    pub fn from_name(name: &str) -> Option<OutputFormat> {
+        OutputFormat::ALL
+            .iter()
+            .copied()
+            .find(|format| format.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Returns the name of the format, as accepted by from_name()
+    pub fn name(&self) -> &'static str {
+        match self {
+            OutputFormat::Jsonl => "jsonl",
+            OutputFormat::Conll => "conll",
+            OutputFormat::Csv => "csv",
+            OutputFormat::Tsv => "tsv",
+            OutputFormat::Lines => "lines",
+            OutputFormat::Text => "text",
+            #[cfg(feature = "arrow")]
+            OutputFormat::Arrow => "arrow",
+        }
+    }
+
+    /// Creates a writer of this format
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::output::{OutputFormat, OutputRecord};
+    ///
+    /// let mut output = Vec::new();
+    /// let mut writer = OutputFormat::Lines.writer(&mut output).unwrap();
+    /// writer.write_record(&OutputRecord::new(vec!["a".to_string(), "b".to_string()])).unwrap();
+    /// writer.finish().unwrap();
+    /// drop(writer);
+    /// assert_eq!(output, b"a\nb\n\n");
+    /// ```
+    pub fn writer<'w, W: Write + 'w>(&self, writer: W) -> io::Result<Box<dyn OutputWriter + 'w>> {
+        Ok(match self {
+            OutputFormat::Jsonl => Box::new(JsonlWriter::new(writer)),
+            OutputFormat::Conll => Box::new(ConllWriter::new(writer)),
+            OutputFormat::Csv => Box::new(CsvWriter::new(writer)),
+            OutputFormat::Tsv => Box::new(CsvWriter::new(writer).with_delimiter('\t')),
+            OutputFormat::Lines => Box::new(LinesWriter::new(writer)),
+            OutputFormat::Text => Box::new(TextWriter::new(writer)),
+            #[cfg(feature = "arrow")]
+            OutputFormat::Arrow => Box::new(ArrowWriter::new(writer)?),
+        })
+    }
+}
+
+/// Reads the keys of a record, with the reason when it is not one
+fn read_record(line: &str) -> Result<OutputRecord, String> {
+    let mut json = JsonCursor::new(line);
+    let (mut schema_version, mut text, mut tokens, mut offsets) = (None, None, None, None);
+    let (mut kinds, mut ids) = (None, None);
+
+    // STEP 1: Walk the keys of the object
+    json.expect('{')?;
+    if !json.eat('}') {
+        loop {
+            let key = json.string()?;
+            json.expect(':')?;
+            match key.as_str() {
+                "schema_version" => schema_version = Some(json.unsigned()?),
+                "text" => text = Some(json.string()?),
+                "tokens" => tokens = Some(json.string_array()?),
+                "kinds" => kinds = Some(json.string_array()?),
+                "offsets" => offsets = Some(json.span_array()?),
+                "ids" => ids = Some(json.id_array()?),
+                _ => json.skip_value(1)?,
+            }
+            if json.eat('}') {
+                break;
+            }
+            json.expect(',')?;
+        }
+    }
+    json.finish()?;
+
+    // STEP 2: Check the contract
+    let schema_version = schema_version.ok_or("the record has no \"schema_version\"")?;
+    if schema_version == 0 || schema_version > SCHEMA_VERSION as usize {
+        return Err(format!(
+            "unsupported schema_version {} (this version reads up to {})",
+            schema_version, SCHEMA_VERSION
+        ));
+    }
+    let tokens: Vec<String> = tokens.ok_or("the record has no \"tokens\" array")?;
+    let kinds = kinds
+        .map(|kinds: Vec<String>| {
+            kinds
+                .iter()
+                .map(|name| {
+                    TokenKind::from_name(name)
+                        .ok_or_else(|| format!("unknown token kind {:?}", name))
+                })
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .transpose()?;
+    if kinds
+        .as_ref()
+        .is_some_and(|kinds: &Vec<_>| kinds.len() != tokens.len())
+    {
+        return Err("the record does not have one kind per token".to_string());
+    }
+    if offsets
+        .as_ref()
+        .is_some_and(|offsets: &Vec<_>| offsets.len() != tokens.len())
+    {
+        return Err("the record does not have one offset per token".to_string());
+    }
+    if ids
+        .as_ref()
+        .is_some_and(|ids: &Vec<_>| ids.len() != tokens.len())
+    {
+        return Err("the record does not have one id per token".to_string());
+    }
+    Ok(OutputRecord {
+        schema_version: schema_version as u32,
+        text,
+        tokens,
+        kinds,
+        offsets,
+        ids,
+    })
+}
+
+/// Reads the string at a path of keys ("meta", "text") in a JSON object
+///
+/// # Returns
+/// The string, None if a key of the path is missing, or the reason the JSON is malformed or
+/// the value is not a string
+pub(crate) fn json_string_field(line: &str, path: &[&str]) -> Result<Option<String>, String> {
+    let mut json = JsonCursor::new(line);
+    let found = find_string_field(&mut json, path, 0)?;
+    if found.is_none() {
+        json.finish()?;
+    }
+    Ok(found)
+}
+
+/// Walks an object (nested `depth` levels deep) until the value at `path`, which must be a string
+fn find_string_field(
+    json: &mut JsonCursor,
+    path: &[&str],
+    depth: usize,
+) -> Result<Option<String>, String> {
+    if depth >= MAX_JSON_DEPTH {
+        return Err(format!("JSON nested deeper than {} levels", MAX_JSON_DEPTH));
+    }
+    json.expect('{')?;
+    if json.eat('}') {
+        return Ok(None);
+    }
+    loop {
+        let key = json.string()?;
+        json.expect(':')?;
+        match path {
+            [last] if key == *last => {
+                if json.peek() != Some('"') {
+                    return Err(format!("\"{}\" is not a string", key));
+                }
+                return json.string().map(Some);
+            }
+            [first, rest @ ..] if key == *first && json.peek() == Some('{') => {
+                // Later keys can't hold the value any more: stop at the first match
+                return find_string_field(json, rest, depth + 1);
+            }
+            _ => json.skip_value(depth + 1)?,
+        }
+        if json.eat('}') {
+            return Ok(None);
+        }
+        json.expect(',')?;
+    }
+}
+
+/// Quotes a CSV field if it contains the delimiter, a quote or a line break
+pub(crate) fn csv_field(field: &str, delimiter: char) -> Cow<'_, str> {
+    if field.contains([delimiter, '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(field)
+    }
+}
+
+/// Quotes a string for JSON
+pub(crate) fn json_string(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for character in text.chars() {
+        match character {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
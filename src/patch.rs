@@ -0,0 +1,259 @@
+// Patches between the original text and its detokenized reconstruction
+// detokenize() is not always lossless: "This  has   spaces" comes back as "This has spaces",
+// and newlines become spaces. A Patch lists exactly what changed, so a reconstruction
+// can be audited, or corrected back into the original text downstream.
+
+use std::io::{self, Write};
+
+/// One edit operation of a patch
+///
+/// Offsets are byte offsets in the original text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatchOp {
+    /// The original text had `text` at `start`, but the reconstruction doesn't
+    Delete {
+        /// Byte offset of the deleted text in the original
+        start: usize,
+        /// The deleted text
+        text: String,
+    },
+    /// The reconstruction has `text` where the original has nothing, at `start`
+    Insert {
+        /// Byte offset in the original where the text is inserted
+        start: usize,
+        /// The inserted text
+        text: String,
+    },
+}
+
+/// A list of edit operations that turns an original text into its reconstruction
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Patch {
+    /// The operations, sorted by position in the original text
+    pub ops: Vec<PatchOp>,
+}
+
+impl Patch {
+    /// Computes the patch that turns `original` into `reconstructed`
+    ///
+    /// The tokenizer never changes the visible characters of a text, only the whitespace
+    /// between them, so the two texts are aligned on their non-whitespace characters and
+    /// every run of whitespace is compared separately. This is fast (a single pass) and
+    /// gives small patches. If the visible characters differ too, the differing middle part
+    /// is simply deleted and re-inserted.
+    ///
+    /// # Arguments
+    /// * `original` - The original text
+    /// * `reconstructed` - The text produced by detokenize()
+    ///
+    /// # Returns
+    /// The patch (empty if both texts are equal)
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::patch::{Patch, PatchOp};
+    ///
+    /// let patch = Patch::between("This  has\nspaces", "This has spaces");
+    /// assert_eq!(patch.ops, vec![
+    ///     PatchOp::Delete { start: 5, text: " ".to_string() },
+    ///     PatchOp::Delete { start: 9, text: "\n".to_string() },
+    ///     PatchOp::Insert { start: 10, text: " ".to_string() },
+    /// ]);
+    /// ```
+    pub fn between(original: &str, reconstructed: &str) -> Self {
+        let mut patch = Patch::default();
+
+        let visible = |text: &str| {
+            text.chars()
+                .filter(|c| !c.is_whitespace())
+                .collect::<String>()
+        };
+        if visible(original) != visible(reconstructed) {
+            patch.push_replacement(original, reconstructed, 0);
+            return patch;
+        }
+
+        // Walk both texts, alternating between whitespace runs and visible characters
+        let mut original_position = 0;
+        let mut reconstructed_position = 0;
+        loop {
+            let original_gap = whitespace_run(&original[original_position..]);
+            let reconstructed_gap = whitespace_run(&reconstructed[reconstructed_position..]);
+            patch.push_replacement(
+                &original[original_position..original_position + original_gap],
+                &reconstructed[reconstructed_position..reconstructed_position + reconstructed_gap],
+                original_position,
+            );
+            original_position += original_gap;
+            reconstructed_position += reconstructed_gap;
+
+            // The next visible character is the same on both sides
+            match original[original_position..].chars().next() {
+                Some(character) => {
+                    original_position += character.len_utf8();
+                    reconstructed_position += character.len_utf8();
+                }
+                None => break,
+            }
+        }
+
+        patch
+    }
+
+    /// Returns true if the patch has no operations (the texts are equal)
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    /// Applies the patch to the original text
+    ///
+    /// # Returns
+    /// The reconstructed text
+    pub fn apply(&self, original: &str) -> String {
+        let mut result = String::with_capacity(original.len());
+        let mut last_end = 0;
+
+        for op in &self.ops {
+            match op {
+                PatchOp::Delete { start, text } => {
+                    result.push_str(&original[last_end..*start]);
+                    last_end = start + text.len();
+                }
+                PatchOp::Insert { start, text } => {
+                    result.push_str(&original[last_end..*start]);
+                    result.push_str(text);
+                    last_end = *start;
+                }
+            }
+        }
+        result.push_str(&original[last_end..]);
+
+        result
+    }
+
+    /// Undoes the patch on a reconstructed text
+    ///
+    /// This is how a lossy reconstruction is corrected back into the original text.
+    ///
+    /// # Returns
+    /// The original text
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::patch::Patch;
+    ///
+    /// let original = "Hello ,  world";
+    /// let patch = Patch::between(original, "Hello, world");
+    /// assert_eq!(patch.apply(original), "Hello, world");
+    /// assert_eq!(patch.revert("Hello, world"), original);
+    /// ```
+    pub fn revert(&self, reconstructed: &str) -> String {
+        let mut result = String::with_capacity(reconstructed.len());
+        let mut last_end = 0;
+
+        // The difference between positions in the reconstruction and in the original
+        let mut shift: isize = 0;
+
+        for op in &self.ops {
+            match op {
+                PatchOp::Delete { start, text } => {
+                    let position = (*start as isize + shift) as usize;
+                    result.push_str(&reconstructed[last_end..position]);
+                    result.push_str(text);
+                    last_end = position;
+                    shift -= text.len() as isize;
+                }
+                PatchOp::Insert { start, text } => {
+                    let position = (*start as isize + shift) as usize;
+                    result.push_str(&reconstructed[last_end..position]);
+                    last_end = position + text.len();
+                    shift += text.len() as isize;
+                }
+            }
+        }
+        result.push_str(&reconstructed[last_end..]);
+
+        result
+    }
+
+    /// Writes the patch in a simple line format
+    ///
+    /// Each operation is one tab-separated line: `DEL` or `INS`, the byte offset in the
+    /// original, and the text with `\t`, `\n`, `\r` and `\\` escaped.
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::patch::Patch;
+    ///
+    /// let mut output = Vec::new();
+    /// Patch::between("a\nb", "a b").write_to(&mut output).unwrap();
+    /// assert_eq!(String::from_utf8(output).unwrap(), "DEL\t1\t\\n\nINS\t2\t \n");
+    /// ```
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        for op in &self.ops {
+            let (name, start, text) = match op {
+                PatchOp::Delete { start, text } => ("DEL", start, text),
+                PatchOp::Insert { start, text } => ("INS", start, text),
+            };
+            writeln!(writer, "{}\t{}\t{}", name, start, escape(text))?;
+        }
+        Ok(())
+    }
+
+    /// Adds the operations replacing `old` (found at `offset` in the original) with `new`
+    ///
+    /// The common prefix and suffix of both texts are kept, only the middle is replaced.
+    fn push_replacement(&mut self, old: &str, new: &str, offset: usize) {
+        let prefix: usize = old
+            .chars()
+            .zip(new.chars())
+            .take_while(|(a, b)| a == b)
+            .map(|(a, _)| a.len_utf8())
+            .sum();
+        let suffix: usize = old[prefix..]
+            .chars()
+            .rev()
+            .zip(new[prefix..].chars().rev())
+            .take_while(|(a, b)| a == b)
+            .map(|(a, _)| a.len_utf8())
+            .sum();
+
+        let deleted = &old[prefix..old.len() - suffix];
+        let inserted = &new[prefix..new.len() - suffix];
+
+        if !deleted.is_empty() {
+            self.ops.push(PatchOp::Delete {
+                start: offset + prefix,
+                text: deleted.to_string(),
+            });
+        }
+        if !inserted.is_empty() {
+            self.ops.push(PatchOp::Insert {
+                start: offset + prefix + deleted.len(),
+                text: inserted.to_string(),
+            });
+        }
+    }
+}
+
+/// Returns the length in bytes of the whitespace at the start of a text
+fn whitespace_run(text: &str) -> usize {
+    text.char_indices()
+        .find(|(_, c)| !c.is_whitespace())
+        .map_or(text.len(), |(position, _)| position)
+}
+
+/// Escapes tabs, newlines, carriage returns and backslashes for the patch line format
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for character in text.chars() {
+        match character {
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\\' => escaped.push_str("\\\\"),
+            other => escaped.push(other),
+        }
+    }
+    escaped
+}
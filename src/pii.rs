@@ -0,0 +1,623 @@
+// PII (personally identifiable information) masking
+// Finds credit card numbers and IBANs in text and replaces them with placeholders.
+// Checksums (Luhn for cards, mod-97 for IBANs) are validated, so an arbitrary run of digits
+// like a phone number or a timestamp is not masked by mistake.
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+
+use crate::identifiers::{compact, IdentifierDetector};
+use crate::token::TokenKind;
+use crate::tokenizer::Tokenizer;
+
+/// The categories of PII this module knows about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PiiKind {
+    /// A payment card number (13 to 19 digits)
+    CreditCard,
+    /// An International Bank Account Number
+    Iban,
+}
+
+impl PiiKind {
+    /// Returns the default placeholder used when masking this kind of PII
+    pub fn placeholder(&self) -> &'static str {
+        match self {
+            PiiKind::CreditCard => "[CREDIT_CARD]",
+            PiiKind::Iban => "[IBAN]",
+        }
+    }
+
+    /// Returns the name of this kind, as written in redaction mapping files
+    pub fn name(&self) -> &'static str {
+        match self {
+            PiiKind::CreditCard => "CREDIT_CARD",
+            PiiKind::Iban => "IBAN",
+        }
+    }
+
+    /// Finds the kind with the given name (the opposite of name())
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "CREDIT_CARD" => Some(PiiKind::CreditCard),
+            "IBAN" => Some(PiiKind::Iban),
+            _ => None,
+        }
+    }
+}
+
+/// What a piece of PII is replaced with when masking
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Replacement {
+    /// Replace the whole match with a fixed token, like "[IBAN]" or "<CARD>"
+    Token(String),
+    /// Keep the format of the original: every letter and digit becomes `mask_char`,
+    /// except the last `keep_last` ones, and separators stay in place
+    /// (for example "4111 1111 1111 1111" becomes "XXXX XXXX XXXX 1111")
+    FormatPreserving {
+        /// The character used to hide letters and digits
+        mask_char: char,
+        /// How many letters and digits to leave visible at the end
+        keep_last: usize,
+    },
+}
+
+impl Replacement {
+    /// Computes the replacement text for one match
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::pii::Replacement;
+    ///
+    /// let replacement = Replacement::FormatPreserving { mask_char: 'X', keep_last: 4 };
+    /// assert_eq!(replacement.apply("123-45-6789"), "XXX-XX-6789");
+    /// ```
+    pub fn apply(&self, original: &str) -> String {
+        match self {
+            Replacement::Token(token) => token.clone(),
+            Replacement::FormatPreserving {
+                mask_char,
+                keep_last,
+            } => {
+                let alphanumeric_count = original.chars().filter(|c| c.is_alphanumeric()).count();
+                let mut seen = 0;
+                original
+                    .chars()
+                    .map(|c| {
+                        if !c.is_alphanumeric() {
+                            return c;
+                        }
+                        seen += 1;
+                        if seen + keep_last > alphanumeric_count {
+                            c
+                        } else {
+                            *mask_char
+                        }
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// The information needed to undo a masking: which part of the masked text replaced which original
+///
+/// It can be saved to a mapping file (one tab-separated line per replacement:
+/// start, end, kind, original) and loaded back to restore the original text.
+/// Mapping files contain the unmasked PII, so they must be stored as carefully as the original data.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RedactionMap {
+    /// One entry per replacement, in text order
+    pub entries: Vec<RedactionEntry>,
+}
+
+/// One replacement made while masking
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RedactionEntry {
+    /// What kind of PII was replaced
+    pub kind: PiiKind,
+    /// Byte offset where the replacement starts in the masked text
+    pub start: usize,
+    /// Byte offset just after the replacement ends in the masked text
+    pub end: usize,
+    /// The original text that was replaced
+    pub original: String,
+}
+
+impl RedactionMap {
+    /// Puts the original PII back into a masked text
+    ///
+    /// # Arguments
+    /// * `masked` - The text returned by PiiMasker::mask_reversible()
+    ///
+    /// # Returns
+    /// The original, unmasked text, or an `InvalidData` error if an entry is out of order,
+    /// overlaps the one before it, ends past the text or cuts a character in two (the mapping
+    /// belongs to another text)
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::pii::{PiiKind, RedactionEntry, RedactionMap};
+    ///
+    /// let entry = |start, end| RedactionEntry {
+    ///     kind: PiiKind::Iban,
+    ///     start,
+    ///     end,
+    ///     original: "DE89370400440532013000".to_string(),
+    /// };
+    /// let map = RedactionMap { entries: vec![entry(4, 10)] };
+    /// assert_eq!(map.restore("Pay [IBAN] now").unwrap(), "Pay DE89370400440532013000 now");
+    ///
+    /// // a mapping of another text gives an error instead of a panic
+    /// assert!(map.restore("Pay").is_err());
+    /// assert!(map.restore("Payé [IBAN]").is_err());
+    /// let overlapping = RedactionMap { entries: vec![entry(4, 10), entry(8, 12)] };
+    /// assert!(overlapping.restore("Pay [IBAN] [IBAN]").is_err());
+    /// ```
+    pub fn restore(&self, masked: &str) -> io::Result<String> {
+        let mut result = String::with_capacity(masked.len());
+        let mut last_end = 0;
+
+        for entry in &self.entries {
+            // get() returns None for reversed ranges, ranges past the end and ranges
+            // that don't start and end on character boundaries
+            let (before, _) = masked
+                .get(last_end..entry.start)
+                .zip(masked.get(entry.start..entry.end))
+                .ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!(
+                            "redaction entry {}..{} does not fit the masked text",
+                            entry.start, entry.end
+                        ),
+                    )
+                })?;
+            result.push_str(before);
+            result.push_str(&entry.original);
+            last_end = entry.end;
+        }
+        result.push_str(&masked[last_end..]);
+
+        Ok(result)
+    }
+
+    /// Writes the mapping file (one tab-separated line per replacement)
+    ///
+    /// # Arguments
+    /// * `writer` - Where to write the mapping (a file, a buffer, ...)
+    pub fn write_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        for entry in &self.entries {
+            writeln!(
+                writer,
+                "{}\t{}\t{}\t{}",
+                entry.start,
+                entry.end,
+                entry.kind.name(),
+                entry.original
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Reads a mapping file written by write_to()
+    ///
+    /// # Arguments
+    /// * `reader` - Where to read the mapping from
+    ///
+    /// # Returns
+    /// The mapping, or an `InvalidData` error if a line is malformed, ends before it starts or
+    /// overlaps the line before it (restore() checks the entries against the masked text)
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::pii::RedactionMap;
+    ///
+    /// let mapping = "5\t18\tCREDIT_CARD\t4111 1111 1111 1111\n";
+    /// let map = RedactionMap::read_from(mapping.as_bytes()).unwrap();
+    /// assert_eq!(map.restore("Card [CREDIT_CARD]").unwrap(), "Card 4111 1111 1111 1111");
+    ///
+    /// assert!(RedactionMap::read_from("18\t5\tIBAN\tx\n".as_bytes()).is_err());
+    /// assert!(RedactionMap::read_from("0\t6\tIBAN\tx\n4\t10\tIBAN\ty\n".as_bytes()).is_err());
+    /// ```
+    pub fn read_from<R: BufRead>(reader: R) -> io::Result<Self> {
+        let mut entries: Vec<RedactionEntry> = Vec::new();
+
+        for line in reader.lines() {
+            let line = line?;
+            if line.is_empty() {
+                continue;
+            }
+
+            let invalid = || {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid redaction mapping line: {}", line),
+                )
+            };
+            let mut fields = line.splitn(4, '\t');
+            let start = fields
+                .next()
+                .and_then(|f| f.parse().ok())
+                .ok_or_else(invalid)?;
+            let end = fields
+                .next()
+                .and_then(|f| f.parse().ok())
+                .ok_or_else(invalid)?;
+            let kind = fields
+                .next()
+                .and_then(PiiKind::from_name)
+                .ok_or_else(invalid)?;
+            let original = fields.next().ok_or_else(invalid)?.to_string();
+            // Entries are written in text order and never overlap
+            let previous_end = entries.last().map_or(0, |entry| entry.end);
+            if start > end || start < previous_end {
+                return Err(invalid());
+            }
+
+            entries.push(RedactionEntry {
+                kind,
+                start,
+                end,
+                original,
+            });
+        }
+
+        Ok(RedactionMap { entries })
+    }
+}
+
+/// One piece of PII found in a text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PiiMatch {
+    /// What kind of PII this is
+    pub kind: PiiKind,
+    /// The matched text, exactly as written in the original
+    pub text: String,
+    /// Byte offset where the match starts in the original text
+    pub start: usize,
+    /// Byte offset just after the match ends in the original text
+    pub end: usize,
+}
+
+/// Finds and masks credit card numbers and IBANs
+#[derive(Debug, Clone)]
+pub struct PiiMasker {
+    /// If true, only numbers with a valid checksum are treated as PII
+    validate_checksums: bool,
+    /// What each kind of PII is replaced with (kinds not in the map use their default placeholder)
+    replacements: HashMap<PiiKind, Replacement>,
+    /// If true, mask_reversible() is allowed to produce a mapping back to the original PII
+    reversible: bool,
+}
+
+impl Default for PiiMasker {
+    fn default() -> Self {
+        PiiMasker {
+            validate_checksums: true,
+            replacements: HashMap::new(),
+            reversible: false,
+        }
+    }
+}
+
+impl PiiMasker {
+    /// Creates a masker that validates checksums
+    pub fn new() -> Self {
+        PiiMasker::default()
+    }
+
+    /// Turns checksum validation on or off
+    ///
+    /// Without validation, every run of 13 to 19 digits and every IBAN-shaped code is masked.
+    pub fn with_checksum_validation(mut self, validate: bool) -> Self {
+        self.validate_checksums = validate;
+        self
+    }
+
+    /// Sets what a kind of PII is replaced with
+    ///
+    /// # Arguments
+    /// * `kind` - The PII category
+    /// * `replacement` - The replacement token or format-preserving mask for that category
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::pii::{PiiKind, PiiMasker, Replacement};
+    ///
+    /// let masker = PiiMasker::new()
+    ///     .with_replacement(PiiKind::CreditCard, Replacement::FormatPreserving { mask_char: 'X', keep_last: 4 })
+    ///     .with_replacement(PiiKind::Iban, Replacement::Token("<ACCOUNT>".to_string()));
+    /// let masked = masker.mask("Card 4111-1111-1111-1111 or DE89370400440532013000");
+    /// assert_eq!(masked, "Card XXXX-XXXX-XXXX-1111 or <ACCOUNT>");
+    /// ```
+    pub fn with_replacement(mut self, kind: PiiKind, replacement: Replacement) -> Self {
+        self.replacements.insert(kind, replacement);
+        self
+    }
+
+    /// Authorizes (or forbids) reversible masking with mask_reversible()
+    ///
+    /// It is off by default because the mapping contains the original PII.
+    pub fn with_reversible_mapping(mut self, authorized: bool) -> Self {
+        self.reversible = authorized;
+        self
+    }
+
+    /// Returns the replacement text for one match
+    fn replacement_for(&self, found: &PiiMatch) -> String {
+        match self.replacements.get(&found.kind) {
+            Some(replacement) => replacement.apply(&found.text),
+            None => found.kind.placeholder().to_string(),
+        }
+    }
+
+    /// Finds all credit card numbers and IBANs in a text
+    ///
+    /// Card numbers may be written in one piece, with hyphens, or in space-separated groups.
+    ///
+    /// # Arguments
+    /// * `text` - The text to search
+    ///
+    /// # Returns
+    /// The matches, in the order they appear in the text
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::pii::{PiiKind, PiiMasker};
+    ///
+    /// let matches = PiiMasker::new().find("Card 4111 1111 1111 1111, ref 1234 5678 9012 3456");
+    /// // only the first number has a valid Luhn checksum
+    /// assert_eq!(matches.len(), 1);
+    /// assert_eq!(matches[0].kind, PiiKind::CreditCard);
+    /// assert_eq!(matches[0].text, "4111 1111 1111 1111");
+    /// ```
+    pub fn find(&self, text: &str) -> Vec<PiiMatch> {
+        let tokens = Tokenizer::new(text.to_string())
+            .with_identifiers(IdentifierDetector::new())
+            .tokenize_with_offsets();
+
+        let mut matches = Vec::new();
+        let mut index = 0;
+
+        while index < tokens.len() {
+            let token = &tokens[index];
+
+            match token.kind {
+                TokenKind::Identifier => {
+                    let compact_form = compact(&token.text);
+                    let kind = if looks_like_iban(&compact_form) {
+                        Some(PiiKind::Iban)
+                            .filter(|_| !self.validate_checksums || iban_valid(&compact_form))
+                    } else if is_card_length(&compact_form) {
+                        Some(PiiKind::CreditCard)
+                            .filter(|_| !self.validate_checksums || luhn_valid(&compact_form))
+                    } else {
+                        None
+                    };
+
+                    if let Some(kind) = kind {
+                        matches.push(PiiMatch {
+                            kind,
+                            text: token.text.clone(),
+                            start: token.start,
+                            end: token.end,
+                        });
+                    }
+                    index += 1;
+                }
+                TokenKind::Number => {
+                    // Collect a run of digit groups separated by single spaces ("4111 1111 1111 1111")
+                    let mut last = index;
+                    let mut digit_count = token.text.len();
+                    while last + 1 < tokens.len()
+                        && tokens[last + 1].kind == TokenKind::Number
+                        && &text[tokens[last].end..tokens[last + 1].start] == " "
+                        && digit_count + tokens[last + 1].text.len() <= 19
+                    {
+                        last += 1;
+                        digit_count += tokens[last].text.len();
+                    }
+
+                    // Try the longest valid card number first, then shorter ones
+                    let mut found = None;
+                    for end_index in (index..=last).rev() {
+                        let candidate = compact(&text[token.start..tokens[end_index].end]);
+                        if is_card_length(&candidate)
+                            && (!self.validate_checksums || luhn_valid(&candidate))
+                        {
+                            found = Some(end_index);
+                            break;
+                        }
+                    }
+
+                    match found {
+                        Some(end_index) => {
+                            let start = token.start;
+                            let end = tokens[end_index].end;
+                            matches.push(PiiMatch {
+                                kind: PiiKind::CreditCard,
+                                text: text[start..end].to_string(),
+                                start,
+                                end,
+                            });
+                            index = end_index + 1;
+                        }
+                        None => index += 1,
+                    }
+                }
+                _ => index += 1,
+            }
+        }
+
+        matches
+    }
+
+    /// Replaces every credit card number and IBAN in a text
+    ///
+    /// Each match is replaced according to the replacement configured for its kind
+    /// (by default "[CREDIT_CARD]" and "[IBAN]").
+    ///
+    /// # Arguments
+    /// * `text` - The text to mask
+    ///
+    /// # Returns
+    /// The masked text
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::pii::PiiMasker;
+    ///
+    /// let masked = PiiMasker::new().mask("Pay to GB82 WEST 1234 5698 7654 32 today");
+    /// assert_eq!(masked, "Pay to [IBAN] today");
+    /// ```
+    pub fn mask(&self, text: &str) -> String {
+        self.mask_with_map(text).0
+    }
+
+    /// Masks a text and returns the mapping needed to undo it
+    ///
+    /// This is only allowed when the masker was authorized with with_reversible_mapping(true),
+    /// because the mapping contains the original PII.
+    ///
+    /// # Arguments
+    /// * `text` - The text to mask
+    ///
+    /// # Returns
+    /// The masked text and its mapping, or None if reversible masking is not authorized
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::pii::PiiMasker;
+    ///
+    /// let text = "Card 4111 1111 1111 1111 please";
+    /// let masker = PiiMasker::new().with_reversible_mapping(true);
+    /// let (masked, mapping) = masker.mask_reversible(text).unwrap();
+    /// assert_eq!(masked, "Card [CREDIT_CARD] please");
+    /// assert_eq!(mapping.restore(&masked).unwrap(), text);
+    ///
+    /// // without authorization there is no mapping
+    /// assert!(PiiMasker::new().mask_reversible(text).is_none());
+    /// ```
+    pub fn mask_reversible(&self, text: &str) -> Option<(String, RedactionMap)> {
+        if !self.reversible {
+            return None;
+        }
+        Some(self.mask_with_map(text))
+    }
+
+    /// Masks a text and records every replacement (used by mask() and mask_reversible())
+    fn mask_with_map(&self, text: &str) -> (String, RedactionMap) {
+        let mut result = String::with_capacity(text.len());
+        let mut map = RedactionMap::default();
+        let mut last_end = 0;
+
+        for found in self.find(text) {
+            result.push_str(&text[last_end..found.start]);
+
+            let start = result.len();
+            result.push_str(&self.replacement_for(&found));
+            map.entries.push(RedactionEntry {
+                kind: found.kind,
+                start,
+                end: result.len(),
+                original: found.text,
+            });
+
+            last_end = found.end;
+        }
+        result.push_str(&text[last_end..]);
+
+        (result, map)
+    }
+}
+
+/// Checks a number with the Luhn algorithm (used by payment cards)
+///
+/// Spaces and hyphens are ignored. Any other non-digit character makes the number invalid.
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::pii::luhn_valid;
+///
+/// assert!(luhn_valid("4111-1111-1111-1111"));
+/// assert!(!luhn_valid("4111-1111-1111-1112"));
+/// ```
+pub fn luhn_valid(number: &str) -> bool {
+    let digits = compact(number);
+    if digits.len() < 2 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return false;
+    }
+
+    // Starting from the rightmost digit, double every second digit
+    // and subtract 9 when the result has two digits
+    let sum: u32 = digits
+        .bytes()
+        .rev()
+        .enumerate()
+        .map(|(position, byte)| {
+            let digit = (byte - b'0') as u32;
+            if position % 2 == 1 {
+                let doubled = digit * 2;
+                if doubled > 9 {
+                    doubled - 9
+                } else {
+                    doubled
+                }
+            } else {
+                digit
+            }
+        })
+        .sum();
+
+    sum.is_multiple_of(10)
+}
+
+/// Checks an IBAN with the ISO 13616 mod-97 checksum
+///
+/// Spaces and hyphens are ignored and letters may be lowercase.
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::pii::iban_valid;
+///
+/// assert!(iban_valid("DE89 3704 0044 0532 0130 00"));
+/// assert!(!iban_valid("DE88 3704 0044 0532 0130 00"));
+/// ```
+pub fn iban_valid(iban: &str) -> bool {
+    let compact_form = compact(iban);
+    if !looks_like_iban(&compact_form) {
+        return false;
+    }
+
+    // Move the country code and check digits to the end, turn letters into numbers (A = 10, ..., Z = 35)
+    // and compute the remainder digit by digit so the number never overflows
+    let rearranged = compact_form[4..].chars().chain(compact_form[..4].chars());
+    let mut remainder: u32 = 0;
+    for character in rearranged {
+        let value = character.to_digit(36).unwrap_or(0);
+        remainder = if value >= 10 {
+            (remainder * 100 + value) % 97
+        } else {
+            (remainder * 10 + value) % 97
+        };
+    }
+
+    remainder == 1
+}
+
+/// Returns true if a compact code has the shape of an IBAN
+fn looks_like_iban(compact_form: &str) -> bool {
+    let bytes = compact_form.as_bytes();
+    (15..=34).contains(&bytes.len())
+        && bytes[0].is_ascii_uppercase()
+        && bytes[1].is_ascii_uppercase()
+        && bytes[2].is_ascii_digit()
+        && bytes[3].is_ascii_digit()
+        && bytes.iter().all(|b| b.is_ascii_alphanumeric())
+}
+
+/// Returns true if a compact code is made of 13 to 19 digits, like a payment card number
+fn is_card_length(compact_form: &str) -> bool {
+    (13..=19).contains(&compact_form.len()) && compact_form.chars().all(|c| c.is_ascii_digit())
+}
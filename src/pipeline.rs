@@ -0,0 +1,297 @@
+// Corpus preprocessing in one call: from text files to shards of token ids
+// Turning a raw corpus into model input takes the same steps every time: read the
+// documents, clean them, tokenize them, build a vocabulary from the token counts, encode
+// every document with that vocabulary and write the results in shards. Every step exists
+// on its own in the crate; Pipeline::preprocess() wires them together from one
+// CorpusConfig, so that the whole chain is a dozen lines for a new user.
+// The corpus is read twice (once to count the tokens, once to encode the documents), so
+// only one batch of documents is ever in memory.
+
+use std::io;
+
+use crate::filter::SentenceFilter;
+use crate::input::{InputSource, JsonlFieldSource, LineSource};
+use crate::output::{OutputRecord, OutputWriter};
+use crate::pii::PiiMasker;
+use crate::shard::{ShardInfo, ShardWriter};
+use crate::stats::FrequencyTable;
+use crate::Tokenizer;
+
+/// Documents cleaned and tokenized or encoded at once (enough to keep every core busy)
+const BATCH_DOCUMENTS: usize = 1024;
+
+/// Everything Pipeline::preprocess() needs: the inputs, the cleaning and tokenization
+/// settings, the vocabulary limits and where the shards go
+pub struct CorpusConfig {
+    /// The input files, one document per line
+    inputs: Vec<String>,
+    /// Read this field of JSONL records instead of lines
+    field: Option<String>,
+    /// Masks card numbers and IBANs before tokenization
+    masker: Option<PiiMasker>,
+    /// Drops the documents whose tokens fail its checks
+    filter: Option<SentenceFilter>,
+    /// The tokenization and encoding settings
+    tokenizer: Tokenizer,
+    /// Tokens seen fewer times are left out of the vocabulary
+    min_count: usize,
+    /// The maximum number of tokens added to the vocabulary
+    max_vocabulary: Option<usize>,
+    /// Where the encoded documents are written
+    shards: ShardWriter,
+}
+
+impl CorpusConfig {
+    /// Creates a configuration that reads every line of `inputs` as a document and writes
+    /// the encoded documents with `shards`
+    ///
+    /// By default nothing is cleaned, the default Tokenizer settings are used and every
+    /// token of the corpus goes into the vocabulary.
+    ///
+    /// # Arguments
+    /// * `inputs` - The input files (read twice, so stdin cannot be one)
+    /// * `shards` - The writer of the output shards, with its size limits
+    pub fn new<I, S>(inputs: I, shards: ShardWriter) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        CorpusConfig {
+            inputs: inputs.into_iter().map(Into::into).collect(),
+            field: None,
+            masker: None,
+            filter: None,
+            tokenizer: Tokenizer::new(String::new()),
+            min_count: 1,
+            max_vocabulary: None,
+            shards,
+        }
+    }
+
+    /// Reads the documents from a string field of JSONL records ("text", "meta.body")
+    pub fn with_field(mut self, field: &str) -> Self {
+        self.field = Some(field.to_string());
+        self
+    }
+
+    /// Masks card numbers and IBANs in every document before it is tokenized
+    pub fn with_pii_masking(mut self, masker: PiiMasker) -> Self {
+        self.masker = Some(masker);
+        self
+    }
+
+    /// Drops the documents whose tokens don't pass the filter (without one, the sentence
+    /// filter of the Tokenizer is used, if it has one)
+    pub fn with_filter(mut self, filter: SentenceFilter) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    /// Uses the settings of a Tokenizer (special tokens, template, unknown-token policy)
+    ///
+    /// The tokens of its vocabulary keep their ids, and the tokens of the corpus are
+    /// added after them.
+    pub fn with_tokenizer(mut self, tokenizer: Tokenizer) -> Self {
+        self.tokenizer = tokenizer;
+        self
+    }
+
+    /// Leaves the tokens seen fewer than `count` times out of the vocabulary
+    pub fn with_min_count(mut self, count: usize) -> Self {
+        self.min_count = count.max(1);
+        self
+    }
+
+    /// Adds at most `size` tokens of the corpus to the vocabulary (the most frequent ones)
+    pub fn with_max_vocabulary(mut self, size: usize) -> Self {
+        self.max_vocabulary = Some(size);
+        self
+    }
+
+    /// Returns the input files
+    pub fn inputs(&self) -> &[String] {
+        &self.inputs
+    }
+
+    /// Returns the minimum count of a vocabulary token
+    pub fn min_count(&self) -> usize {
+        self.min_count
+    }
+
+    /// Returns the maximum number of corpus tokens in the vocabulary, if limited
+    pub fn max_vocabulary(&self) -> Option<usize> {
+        self.max_vocabulary
+    }
+}
+
+/// The result of Pipeline::preprocess(): the trained Tokenizer and what was written
+pub struct Pipeline {
+    /// The Tokenizer of the configuration, with the vocabulary built from the corpus
+    pub tokenizer: Tokenizer,
+    /// Number of documents read
+    pub documents: usize,
+    /// Number of documents dropped by the filter
+    pub dropped: usize,
+    /// Number of token ids written
+    pub tokens: usize,
+    /// The shards written
+    pub shards: Vec<ShardInfo>,
+}
+
+impl Pipeline {
+    /// Reads, cleans, tokenizes and encodes a corpus, and writes it as shards of token ids
+    ///
+    /// The first pass cleans and tokenizes every document and counts its tokens, and the
+    /// vocabulary is built from the counts (most frequent first, ties in lexicographic
+    /// order). The second pass cleans the documents again, encodes them with that
+    /// vocabulary and writes one record per document with its text, tokens, offsets and ids.
+    ///
+    /// # Arguments
+    /// * `config` - The inputs and the settings of every step
+    ///
+    /// # Returns
+    /// The trained Tokenizer and the counts of the run, or the first read or write error
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::filter::SentenceFilter;
+    /// use tokenizer_rust::pipeline::{CorpusConfig, Pipeline};
+    /// use tokenizer_rust::shard::ShardWriter;
+    ///
+    /// let directory = std::env::temp_dir().join("tokenizer_pipeline_doctest");
+    /// std::fs::create_dir_all(&directory).unwrap();
+    /// let input = directory.join("corpus.txt");
+    /// std::fs::write(&input, "the cat sat\nthe dog sat down\nok\nthe end\n").unwrap();
+    ///
+    /// let config = CorpusConfig::new(
+    ///     [input.to_str().unwrap()],
+    ///     ShardWriter::new(directory.join("shards"), "corpus").with_max_documents(2),
+    /// )
+    /// .with_filter(SentenceFilter::new().with_token_range(2, 100))
+    /// .with_min_count(2);
+    /// let pipeline = Pipeline::preprocess(config).unwrap();
+    ///
+    /// // "ok" was dropped, and only "the" and "sat" are frequent enough
+    /// assert_eq!((pipeline.documents, pipeline.dropped), (4, 1));
+    /// assert_eq!(pipeline.tokenizer.vocabulary().len(), 2);
+    /// assert_eq!(pipeline.shards.len(), 2);
+    /// let first = std::fs::read_to_string(directory.join("shards/corpus-0001.jsonl")).unwrap();
+    /// assert!(first.starts_with(r#"{"schema_version":1,"text":"the cat sat","tokens":["the","sat"],"#));
+    /// assert!(first.lines().next().unwrap().ends_with(r#""ids":[0,1]}"#));
+    /// # std::fs::remove_dir_all(&directory).unwrap();
+    /// ```
+    pub fn preprocess(config: CorpusConfig) -> io::Result<Pipeline> {
+        let CorpusConfig {
+            inputs,
+            field,
+            masker,
+            filter,
+            tokenizer,
+            min_count,
+            max_vocabulary,
+            mut shards,
+        } = config;
+        if inputs.iter().any(|input| input == "-") {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "the corpus is read twice, so stdin cannot be one of its inputs",
+            ));
+        }
+        let filter = filter.or_else(|| tokenizer.sentence_filter().cloned());
+        let cleaner = Cleaner { masker, filter };
+
+        // STEP 1: Count the tokens of the clean documents
+        let mut frequencies = FrequencyTable::new();
+        let (mut documents, mut dropped) = (0, 0);
+        for_each_batch(&inputs, field.as_deref(), |batch| {
+            let texts = cleaner.clean(&tokenizer, batch);
+            documents += batch.len();
+            dropped += batch.len() - texts.len();
+            let texts: Vec<&str> = texts.iter().map(String::as_str).collect();
+            for tokens in tokenizer.tokenize_batch(&texts) {
+                frequencies.add_tokens(&tokens);
+            }
+            Ok(())
+        })?;
+
+        // STEP 2: Build the vocabulary, after the tokens the Tokenizer already has
+        let mut vocabulary = tokenizer.vocabulary().clone();
+        vocabulary.add_frequent(&frequencies, min_count, max_vocabulary);
+        let tokenizer = tokenizer.with_vocabulary(vocabulary);
+
+        // STEP 3: Encode the clean documents again and write them
+        let mut tokens = 0;
+        for_each_batch(&inputs, field.as_deref(), |batch| {
+            let texts = cleaner.clean(&tokenizer, batch);
+            let texts: Vec<&str> = texts.iter().map(String::as_str).collect();
+            for (text, encoding) in texts.iter().zip(tokenizer.encode_batch(&texts)) {
+                tokens += encoding.ids.len();
+                shards.write_record(&OutputRecord::from_encoding(text, &encoding))?;
+            }
+            Ok(())
+        })?;
+        shards.finish()?;
+
+        Ok(Pipeline {
+            documents,
+            dropped,
+            tokens,
+            shards: shards.shards().to_vec(),
+            tokenizer,
+        })
+    }
+}
+
+/// The cleaning steps of a CorpusConfig
+struct Cleaner {
+    /// Masks card numbers and IBANs
+    masker: Option<PiiMasker>,
+    /// Drops the documents whose tokens fail its checks
+    filter: Option<SentenceFilter>,
+}
+
+impl Cleaner {
+    /// Returns the masked texts of the documents the filter keeps, in order
+    fn clean(&self, tokenizer: &Tokenizer, texts: &[String]) -> Vec<String> {
+        let mut texts: Vec<String> = match &self.masker {
+            Some(masker) => texts.iter().map(|text| masker.mask(text)).collect(),
+            None => texts.to_vec(),
+        };
+        if let Some(filter) = &self.filter {
+            let borrowed: Vec<&str> = texts.iter().map(String::as_str).collect();
+            let kept: Vec<bool> = tokenizer
+                .tokenize_batch(&borrowed)
+                .iter()
+                .map(|tokens| filter.keeps(tokens))
+                .collect();
+            let mut kept = kept.into_iter();
+            texts.retain(|_| kept.next().unwrap_or(false));
+        }
+        texts
+    }
+}
+
+/// Reads the documents of every input in order and hands them over in batches
+fn for_each_batch<F>(inputs: &[String], field: Option<&str>, mut on_batch: F) -> io::Result<()>
+where
+    F: FnMut(&[String]) -> io::Result<()>,
+{
+    let mut batch = Vec::with_capacity(BATCH_DOCUMENTS);
+    for input in inputs {
+        let mut source: Box<dyn InputSource> = match field {
+            Some(field) => Box::new(JsonlFieldSource::open(input, field)?),
+            None => Box::new(LineSource::open(input)?),
+        };
+        while let Some(document) = source.next_document()? {
+            batch.push(document.text);
+            if batch.len() == BATCH_DOCUMENTS {
+                on_batch(&batch)?;
+                batch.clear();
+            }
+        }
+    }
+    if !batch.is_empty() {
+        on_batch(&batch)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,246 @@
+// Inline placeholders of translation units (markup tags, format arguments)
+// Text sent to machine translation carries inline codes that must come out untouched:
+// XLIFF tags (<g id="1">, </g>, <x id="2"/>), format arguments ({0}, {name}) and printf
+// conversions (%s, %1$d). Their exact text varies from one segment to the next, so they
+// can't all be registered as special tokens in advance. They are found by their shape
+// instead, and every placeholder becomes one Special token while the text between them
+// is tokenized normally.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::token::{Token, TokenKind};
+use crate::Tokenizer;
+
+/// Longest tag accepted as a placeholder, in bytes (a "<" that is never closed is text)
+const MAX_TAG_LENGTH: usize = 256;
+
+/// Returns the (start, end) byte ranges of the placeholders of a text, in order
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::placeholders::find_placeholders;
+///
+/// let text = r#"Click <g id="1">here</g> to delete {0} files (%d left)."#;
+/// let found: Vec<&str> = find_placeholders(text)
+///     .into_iter()
+///     .map(|(start, end)| &text[start..end])
+///     .collect();
+/// assert_eq!(found, vec![r#"<g id="1">"#, "</g>", "{0}", "%d"]);
+///
+/// // Comparisons, braces around text and percentages are not placeholders
+/// assert!(find_placeholders("if a < b and {not a name} at 50% off").is_empty());
+/// ```
+pub fn find_placeholders(text: &str) -> Vec<(usize, usize)> {
+    let bytes = text.as_bytes();
+    let mut found = Vec::new();
+    let mut position = 0;
+    while position < bytes.len() {
+        let length = match bytes[position] {
+            b'<' => tag_length(&text[position..]),
+            b'{' => brace_length(&text[position..]),
+            b'%' => printf_length(&text[position..]),
+            _ => None,
+        };
+        match length {
+            Some(length) => {
+                found.push((position, position + length));
+                position += length;
+            }
+            None => position += 1,
+        }
+    }
+    found
+}
+
+/// Returns true if the whole text is one placeholder
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::placeholders::is_placeholder;
+///
+/// assert!(is_placeholder("<x id=\"2\"/>"));
+/// assert!(is_placeholder("%1$s"));
+/// assert!(!is_placeholder("{0} files"));
+/// ```
+pub fn is_placeholder(text: &str) -> bool {
+    find_placeholders(text) == [(0, text.len())]
+}
+
+/// Tokenizes a text, keeping every placeholder as one Special token
+///
+/// The text between placeholders is tokenized with the settings of `tokenizer`, and all
+/// offsets point into `text`.
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::Tokenizer;
+/// use tokenizer_rust::placeholders::tokenize_with_placeholders;
+/// use tokenizer_rust::token::TokenKind;
+///
+/// let text = r#"Delete <b>{count}</b> files?"#;
+/// let tokens = tokenize_with_placeholders(&Tokenizer::new(String::new()), text);
+/// let texts: Vec<&str> = tokens.iter().map(|token| token.text.as_str()).collect();
+/// assert_eq!(texts, vec!["Delete", "<b>", "{count}", "</b>", "files", "?"]);
+/// assert_eq!(tokens[2].kind, TokenKind::Special);
+/// assert_eq!(&text[tokens[2].start..tokens[2].end], "{count}");
+/// ```
+pub fn tokenize_with_placeholders(tokenizer: &Tokenizer, text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut position = 0;
+    let placeholders = find_placeholders(text);
+    let segments = placeholders
+        .iter()
+        .map(|&(start, end)| (start, Some(end)))
+        .chain(std::iter::once((text.len(), None)));
+    for (start, end) in segments {
+        // The text before the placeholder, with its offsets moved into the whole text
+        tokens.extend(
+            tokenizer
+                .tokenize_text(&text[position..start])
+                .into_iter()
+                .map(|token| Token {
+                    start: token.start + position,
+                    end: token.end + position,
+                    ..token
+                }),
+        );
+        if let Some(end) = end {
+            tokens.push(Token {
+                text: text[start..end].to_string(),
+                kind: TokenKind::Special,
+                start,
+                end,
+            });
+            position = end;
+        }
+    }
+    tokens
+}
+
+/// Placeholders that are not the same on the two sides of a translation
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PlaceholderMismatch {
+    /// Placeholders of the source missing from the target (once per missing copy)
+    pub missing: Vec<String>,
+    /// Placeholders of the target that the source does not have (once per extra copy)
+    pub extra: Vec<String>,
+}
+
+impl fmt::Display for PlaceholderMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        if !self.missing.is_empty() {
+            parts.push(format!("missing {}", self.missing.join(" ")));
+        }
+        if !self.extra.is_empty() {
+            parts.push(format!("extra {}", self.extra.join(" ")));
+        }
+        write!(f, "{}", parts.join("; "))
+    }
+}
+
+/// Compares the placeholders of two token lists as multisets (order does not matter,
+/// translations often move them)
+///
+/// # Returns
+/// None if both sides have the same placeholders, the differences otherwise
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::Tokenizer;
+/// use tokenizer_rust::placeholders::{compare_placeholders, tokenize_with_placeholders};
+///
+/// let tokenizer = Tokenizer::new(String::new());
+/// let source = tokenize_with_placeholders(&tokenizer, "{0} of {1} done");
+/// let moved = tokenize_with_placeholders(&tokenizer, "{1} : {0} terminés");
+/// assert_eq!(compare_placeholders(&source, &moved), None);
+///
+/// let broken = tokenize_with_placeholders(&tokenizer, "{0} sur {2} terminés");
+/// let mismatch = compare_placeholders(&source, &broken).unwrap();
+/// assert_eq!(mismatch.missing, vec!["{1}"]);
+/// assert_eq!(mismatch.extra, vec!["{2}"]);
+/// assert_eq!(mismatch.to_string(), "missing {1}; extra {2}");
+/// ```
+pub fn compare_placeholders(source: &[Token], target: &[Token]) -> Option<PlaceholderMismatch> {
+    let source = count_placeholders(source);
+    let target = count_placeholders(target);
+
+    // Every copy one side has more than the other is reported once
+    let surplus = |of: &BTreeMap<&str, usize>, over: &BTreeMap<&str, usize>| -> Vec<String> {
+        of.iter()
+            .flat_map(|(&placeholder, &count)| {
+                let missing = count.saturating_sub(over.get(placeholder).copied().unwrap_or(0));
+                std::iter::repeat_n(placeholder.to_string(), missing)
+            })
+            .collect()
+    };
+    let mismatch = PlaceholderMismatch {
+        missing: surplus(&source, &target),
+        extra: surplus(&target, &source),
+    };
+    if mismatch.missing.is_empty() && mismatch.extra.is_empty() {
+        None
+    } else {
+        Some(mismatch)
+    }
+}
+
+/// Counts the placeholder tokens of a token list
+fn count_placeholders(tokens: &[Token]) -> BTreeMap<&str, usize> {
+    let mut counts = BTreeMap::new();
+    for token in tokens {
+        if token.kind == TokenKind::Special && is_placeholder(&token.text) {
+            *counts.entry(token.text.as_str()).or_insert(0) += 1;
+        }
+    }
+    counts
+}
+
+/// Returns the length of the markup tag at the start of `text`, if there is one:
+/// "<name ...>", "</name>" or "<name .../>", on one line
+fn tag_length(text: &str) -> Option<usize> {
+    let rest = text[1..].strip_prefix('/').unwrap_or(&text[1..]);
+    if !rest.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        return None;
+    }
+    let end = text
+        .bytes()
+        .take(MAX_TAG_LENGTH)
+        .position(|byte| byte == b'>')?;
+    let inside = &text[1..end];
+    let valid = !inside.contains(['<', '\n']) && inside.matches('"').count().is_multiple_of(2);
+    // The name runs until whitespace, "/" or the end of the tag
+    let name_valid = rest[..rest.find([' ', '\t', '/', '>']).unwrap_or(rest.len())]
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | ':' | '.'));
+    (valid && name_valid).then_some(end + 1)
+}
+
+/// Returns the length of the "{0}" or "{name}" argument at the start of `text`, if any
+fn brace_length(text: &str) -> Option<usize> {
+    let name_length = text[1..]
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(text.len() - 1);
+    (name_length > 0 && text[1 + name_length..].starts_with('}')).then_some(name_length + 2)
+}
+
+/// Returns the length of the printf conversion at the start of `text` ("%s", "%1$d"), if any
+fn printf_length(text: &str) -> Option<usize> {
+    let bytes = text.as_bytes();
+    let mut position = 1;
+
+    // An optional argument position: digits followed by "$"
+    let digits = bytes[1..].iter().take_while(|b| b.is_ascii_digit()).count();
+    if digits > 0 && bytes.get(1 + digits) == Some(&b'$') {
+        position += digits + 1;
+    }
+
+    let conversion = *bytes.get(position)?;
+    let is_conversion = b"sdifuxXeEgGcp@".contains(&conversion);
+    // "%s" must not run into a word ("%simple" is not a placeholder)
+    let word_follows = bytes
+        .get(position + 1)
+        .is_some_and(|next| next.is_ascii_alphanumeric());
+    (is_conversion && !word_follows).then_some(position + 1)
+}
@@ -0,0 +1,212 @@
+// Progress of a long run over many files: files done, tokens per second and time left
+// A batch job over a corpus can run for hours, and a silent terminal doesn't tell whether
+// it is ten minutes or a day away from the end. Progress counts the finished files, the
+// bytes read and the tokens from any number of threads (the counters are atomic), and a
+// ProgressReport turns a snapshot of the counters into a one-line status:
+//     12/40 files  3.1MB/9.8MB  1.2M tokens  410.5k tokens/s  ETA 0:16
+// The time left is estimated from the bytes done so far, or from the files done when the
+// sizes of the inputs are unknown (stdin, URLs).
+
+use std::fmt;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// The counters of a run over a known list of files, shared by the threads of the run
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::progress::Progress;
+///
+/// let progress = Progress::new(4, 4_000);
+/// progress.file_done(1_000, 250);
+/// let report = progress.report();
+/// assert_eq!((report.files, report.bytes, report.tokens), (1, 1_000, 250));
+/// assert_eq!(report.fraction_done(), 0.25);
+/// assert!(report.to_string().starts_with("1/4 files"));
+///
+/// // A long file moves the bytes (and the time left) before it is finished
+/// progress.advance(500, 130);
+/// assert_eq!((progress.report().bytes, progress.report().tokens), (1_500, 380));
+/// progress.file_done(500, 120);
+/// assert_eq!(progress.report().files, 2);
+/// ```
+#[derive(Debug)]
+pub struct Progress {
+    /// Number of files of the run
+    total_files: usize,
+    /// Total size of those files in bytes (0 when unknown)
+    total_bytes: u64,
+    /// Number of finished files
+    files: AtomicUsize,
+    /// Bytes read so far, of the finished files and of the files being read
+    bytes: AtomicU64,
+    /// Tokens so far, of the finished files and of the files being read
+    tokens: AtomicUsize,
+    /// When the run started
+    started: Instant,
+}
+
+impl Progress {
+    /// Starts the clock of a run
+    ///
+    /// # Arguments
+    /// * `total_files` - The number of files the run will process
+    /// * `total_bytes` - Their total size in bytes, or 0 if it is unknown
+    pub fn new(total_files: usize, total_bytes: u64) -> Self {
+        Progress {
+            total_files,
+            total_bytes,
+            files: AtomicUsize::new(0),
+            bytes: AtomicU64::new(0),
+            tokens: AtomicUsize::new(0),
+            started: Instant::now(),
+        }
+    }
+
+    /// Records the progress of a file that is not finished yet (called from any thread)
+    ///
+    /// # Arguments
+    /// * `bytes` - The bytes read since the last call for this file
+    /// * `tokens` - The tokens found since the last call for this file
+    pub fn advance(&self, bytes: u64, tokens: usize) {
+        self.bytes.fetch_add(bytes, Ordering::Relaxed);
+        self.tokens.fetch_add(tokens, Ordering::Relaxed);
+    }
+
+    /// Records a finished file (called from any thread)
+    ///
+    /// # Arguments
+    /// * `bytes` - The bytes of the file not recorded with advance() yet (its size if none
+    ///   were)
+    /// * `tokens` - The tokens of the file not recorded with advance() yet
+    pub fn file_done(&self, bytes: u64, tokens: usize) {
+        self.bytes.fetch_add(bytes, Ordering::Relaxed);
+        self.tokens.fetch_add(tokens, Ordering::Relaxed);
+        self.files.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns the counters as they are now
+    pub fn report(&self) -> ProgressReport {
+        ProgressReport {
+            files: self.files.load(Ordering::Relaxed),
+            total_files: self.total_files,
+            bytes: self.bytes.load(Ordering::Relaxed),
+            total_bytes: self.total_bytes,
+            tokens: self.tokens.load(Ordering::Relaxed),
+            elapsed: self.started.elapsed(),
+        }
+    }
+}
+
+/// A snapshot of the counters of a Progress
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProgressReport {
+    /// Number of finished files
+    pub files: usize,
+    /// Number of files of the run
+    pub total_files: usize,
+    /// Bytes read so far
+    pub bytes: u64,
+    /// Total size of the files of the run (0 when unknown)
+    pub total_bytes: u64,
+    /// Tokens so far
+    pub tokens: usize,
+    /// Time since the run started
+    pub elapsed: Duration,
+}
+
+impl ProgressReport {
+    /// Returns the share of the run that is done (0 to 1), by bytes when the sizes are
+    /// known and by files otherwise
+    pub fn fraction_done(&self) -> f64 {
+        if self.total_bytes > 0 {
+            (self.bytes as f64 / self.total_bytes as f64).min(1.0)
+        } else if self.total_files > 0 {
+            self.files as f64 / self.total_files as f64
+        } else {
+            1.0
+        }
+    }
+
+    /// Returns the number of tokens per second since the start
+    pub fn tokens_per_second(&self) -> f64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds > 0.0 {
+            self.tokens as f64 / seconds
+        } else {
+            0.0
+        }
+    }
+
+    /// Returns the estimated time left, or None before anything is done
+    ///
+    /// # Example
+    /// ```
+    /// use std::time::Duration;
+    /// use tokenizer_rust::progress::ProgressReport;
+    ///
+    /// let report = ProgressReport {
+    ///     files: 1,
+    ///     total_files: 4,
+    ///     bytes: 2_500,
+    ///     total_bytes: 10_000,
+    ///     tokens: 600,
+    ///     elapsed: Duration::from_secs(30),
+    /// };
+    /// // A quarter of the bytes took 30 seconds, the other three quarters take 90
+    /// assert_eq!(report.eta(), Some(Duration::from_secs(90)));
+    /// assert_eq!(report.tokens_per_second(), 20.0);
+    /// ```
+    pub fn eta(&self) -> Option<Duration> {
+        let done = self.fraction_done();
+        if done <= 0.0 {
+            return None;
+        }
+        Some(self.elapsed.mul_f64((1.0 - done) / done))
+    }
+}
+
+impl fmt::Display for ProgressReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{} files", self.files, self.total_files)?;
+        if self.total_bytes > 0 {
+            write!(
+                f,
+                "  {}B/{}B",
+                human(self.bytes as f64),
+                human(self.total_bytes as f64)
+            )?;
+        }
+        write!(
+            f,
+            "  {} tokens  {} tokens/s",
+            human(self.tokens as f64),
+            human(self.tokens_per_second())
+        )?;
+        match self.eta() {
+            Some(eta) => write!(f, "  ETA {}", clock(eta)),
+            None => write!(f, "  ETA -"),
+        }
+    }
+}
+
+/// Writes a number with a k, M or G suffix ("410.5k")
+fn human(value: f64) -> String {
+    match value {
+        v if v >= 1e9 => format!("{:.1}G", v / 1e9),
+        v if v >= 1e6 => format!("{:.1}M", v / 1e6),
+        v if v >= 1e3 => format!("{:.1}k", v / 1e3),
+        v => format!("{:.0}", v),
+    }
+}
+
+/// Writes a duration as h:mm:ss, or m:ss under an hour
+fn clock(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{}:{:02}", minutes, seconds)
+    }
+}
@@ -0,0 +1,162 @@
+// Penn Treebank token conventions
+// Taggers and parsers trained on the Penn Treebank expect its tokens: contractions split
+// off their stem ("don't" -> "do" "n't", "she's" -> "she" "'s"), brackets written as
+// -LRB- and -RRB-, and double quotes written as `` when they open and '' when they close.
+// The crate keeps contractions whole and quotes as they are, so PennTreebank is a
+// TokenFilter that rewrites the tokens of the tokenizer into those conventions. The split
+// pieces keep the offsets of their part of the original word, and the renamed brackets and
+// quotes keep the offsets of the original character.
+
+use crate::manifest::ManifestStep;
+use crate::token::{Token, TokenKind};
+use crate::token_filter::TokenFilter;
+
+/// The contractions split off the end of a word, after its apostrophe ("n't" starts one
+/// letter earlier)
+const CLITICS: [&str; 6] = ["s", "m", "d", "re", "ve", "ll"];
+
+/// Words the Penn Treebank splits in two although they have no apostrophe (split after
+/// their 3rd letter)
+const SPLIT_WORDS: [&str; 6] = ["cannot", "gimme", "gonna", "gotta", "lemme", "wanna"];
+
+/// Rewrites tokens into the Penn Treebank conventions
+///
+/// Tokens whose text no longer has the length of their span (changed by an earlier filter)
+/// are only renamed, never split, since their pieces could not be given offsets.
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::Tokenizer;
+/// use tokenizer_rust::ptb::PennTreebank;
+/// use tokenizer_rust::token_filter::TokenFilter;
+///
+/// let text = "She said \"I can't (really) go\"";
+/// let tokens = PennTreebank.apply(Tokenizer::new(String::new()).tokenize_text(text));
+/// let texts: Vec<&str> = tokens.iter().map(|token| token.text.as_str()).collect();
+/// assert_eq!(
+///     texts,
+///     vec!["She", "said", "``", "I", "ca", "n't", "-LRB-", "really", "-RRB-", "go", "''"]
+/// );
+///
+/// // The pieces of "can't" point at their part of it
+/// assert_eq!(&text[tokens[4].start..tokens[4].end], "ca");
+/// assert_eq!(&text[tokens[5].start..tokens[5].end], "n't");
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PennTreebank;
+
+impl TokenFilter for PennTreebank {
+    fn apply(&self, tokens: Vec<Token>) -> Vec<Token> {
+        let mut result = Vec::with_capacity(tokens.len());
+        // A double quote opens at the start, after a space or after an opening bracket
+        let mut opens = true;
+        let mut previous_end = None;
+        for token in tokens {
+            if previous_end.is_some_and(|end| end < token.start) {
+                opens = true;
+            }
+            previous_end = Some(token.end);
+            match (token.kind, token.text.as_str()) {
+                (TokenKind::Punctuation, "\"") => {
+                    let quote = if opens { "``" } else { "''" };
+                    result.push(renamed(token, quote));
+                    opens = false;
+                }
+                (TokenKind::Punctuation, text) => {
+                    let bracket = match text {
+                        "(" => Some("-LRB-"),
+                        ")" => Some("-RRB-"),
+                        "[" => Some("-LSB-"),
+                        "]" => Some("-RSB-"),
+                        "{" => Some("-LCB-"),
+                        "}" => Some("-RCB-"),
+                        _ => None,
+                    };
+                    opens = matches!(text, "(" | "[" | "{");
+                    match bracket {
+                        Some(bracket) => result.push(renamed(token, bracket)),
+                        None => result.push(token),
+                    }
+                }
+                (TokenKind::Word, _) if token.text.len() == token.end - token.start => {
+                    split_word(token, &mut result);
+                    opens = false;
+                }
+                _ => {
+                    result.push(token);
+                    opens = false;
+                }
+            }
+        }
+        result
+    }
+
+    fn describe(&self) -> Option<ManifestStep> {
+        Some(ManifestStep::new("penn_treebank"))
+    }
+}
+
+/// Returns the token with a new text (same kind and offsets)
+fn renamed(token: Token, text: &str) -> Token {
+    Token {
+        text: text.to_string(),
+        ..token
+    }
+}
+
+/// Splits a word into its Penn Treebank pieces: an opening single quote (written `), the
+/// stem, a contraction and a closing single quote, pushing those it has
+fn split_word(token: Token, result: &mut Vec<Token>) {
+    let text = token.text.as_str();
+    let piece = |start: usize, end: usize, text: &str, kind: TokenKind| Token {
+        text: text.to_string(),
+        kind,
+        start: token.start + start,
+        end: token.start + end,
+    };
+
+    // STEP 1: Quotes around the word ("'Hello'" is quoted, "'s" alone is a contraction)
+    let mut start = 0;
+    let mut end = text.len();
+    if text.len() > 1 && text.starts_with('\'') && !is_clitic(&text[1..]) {
+        result.push(piece(0, 1, "`", TokenKind::Punctuation));
+        start = 1;
+    }
+    let closing = end - start > 1 && text.ends_with('\'');
+    if closing {
+        end -= 1;
+    }
+
+    // STEP 2: The contraction at the end of the stem, or the split of a known word
+    let stem = &text[start..end];
+    let lowercase = stem.to_ascii_lowercase();
+    let cut = if lowercase.len() > 3 && lowercase.ends_with("n't") {
+        Some(stem.len() - 3)
+    } else if SPLIT_WORDS.contains(&lowercase.as_str()) {
+        Some(3)
+    } else {
+        stem.rfind('\'')
+            .filter(|&apostrophe| apostrophe > 0 && is_clitic(&lowercase[apostrophe + 1..]))
+    };
+    match cut {
+        Some(cut) => {
+            result.push(piece(start, start + cut, &stem[..cut], TokenKind::Word));
+            result.push(piece(start + cut, end, &stem[cut..], TokenKind::Word));
+        }
+        None if start == 0 && !closing => {
+            result.push(token);
+            return;
+        }
+        None => result.push(piece(start, end, stem, TokenKind::Word)),
+    }
+
+    // STEP 3: The closing quote
+    if closing {
+        result.push(piece(end, end + 1, "'", TokenKind::Punctuation));
+    }
+}
+
+/// Returns true if `text` (after an apostrophe) is a contraction like "s" or "ll"
+fn is_clitic(text: &str) -> bool {
+    CLITICS.contains(&text.to_ascii_lowercase().as_str())
+}
@@ -0,0 +1,191 @@
+// Python bindings (only compiled with the `python` feature)
+// Data scientists work in Python and shouldn't need a Rust toolchain to tokenize a corpus.
+// This module is the `tokenizer_rust` Python extension module, built into a wheel by maturin
+// (see pyproject.toml):
+//     maturin build --release          # target/wheels/tokenizer_rust-*.whl, to pip install
+//     maturin develop --release        # straight into the current virtualenv
+// and used like any Python package:
+//     from tokenizer_rust import Tokenizer
+//     tokenizer = Tokenizer.from_file("model.json")
+//     tokenizer.encode_batch(texts)
+// The batch methods release the GIL while they run (on all CPU cores with the `parallel`
+// feature), so other Python threads keep running. The type hints are in tokenizer_rust.pyi.
+
+use pyo3::exceptions::{PyIOError, PyValueError};
+use pyo3::prelude::*;
+
+use crate::encoder::{EncoderConfig, Template};
+use crate::special::SpecialTokens;
+use crate::token::Token;
+use crate::Tokenizer;
+
+/// A tokenizer: the default rules, the special tokens of a model family, or a tokenizer
+/// saved by `tokenizer build-vocab` (or Tokenizer::save()) with its vocabulary
+#[pyclass(name = "Tokenizer", module = "tokenizer_rust", frozen)]
+pub struct PyTokenizer {
+    /// The Rust tokenizer
+    tokenizer: Tokenizer,
+}
+
+#[pymethods]
+impl PyTokenizer {
+    /// Creates a tokenizer with the default rules, keeping the special tokens of a model
+    /// family ("bert" or "roberta") whole
+    #[new]
+    #[pyo3(signature = (special_tokens = None))]
+    fn new(special_tokens: Option<&str>) -> PyResult<Self> {
+        let tokenizer = Tokenizer::new(String::new());
+        let tokenizer = match special_tokens {
+            None => tokenizer,
+            Some("bert") => tokenizer
+                .with_special_tokens(SpecialTokens::bert())
+                .with_encoder_config(EncoderConfig::new().with_template(Template::bert())),
+            Some("roberta") => tokenizer
+                .with_special_tokens(SpecialTokens::roberta())
+                .with_encoder_config(EncoderConfig::new().with_template(Template::roberta())),
+            Some(family) => {
+                return Err(PyValueError::new_err(format!(
+                    "unknown model family {:?} (bert or roberta)",
+                    family
+                )))
+            }
+        };
+        Ok(PyTokenizer { tokenizer })
+    }
+
+    /// Loads a tokenizer saved by save() or `tokenizer build-vocab`
+    #[staticmethod]
+    fn from_file(path: &str) -> PyResult<Self> {
+        Tokenizer::from_file(path)
+            .map(|tokenizer| PyTokenizer { tokenizer })
+            .map_err(|error| PyIOError::new_err(format!("{}: {}", path, error)))
+    }
+
+    /// Loads a tokenizer from the content of a file written by save()
+    #[staticmethod]
+    fn from_json(text: &str) -> PyResult<Self> {
+        Tokenizer::from_json(text)
+            .map(|tokenizer| PyTokenizer { tokenizer })
+            .map_err(|error| PyValueError::new_err(error.to_string()))
+    }
+
+    /// Loads a tokenizer from a manifest saved by an older version
+    #[staticmethod]
+    fn from_manifest(text: &str) -> PyResult<Self> {
+        Tokenizer::from_manifest(text)
+            .map(|tokenizer| PyTokenizer { tokenizer })
+            .map_err(|error| PyValueError::new_err(error.to_string()))
+    }
+
+    /// Writes the tokenizer to a file, to load it again with from_file()
+    fn save(&self, path: &str) -> PyResult<()> {
+        self.tokenizer
+            .save(path)
+            .map_err(|error| PyIOError::new_err(format!("{}: {}", path, error)))
+    }
+
+    /// Returns the tokens of a text
+    fn tokenize(&self, text: &str) -> Vec<String> {
+        token_texts(self.tokenizer.tokenize_text(text))
+    }
+
+    /// Joins tokens back into text
+    fn detokenize(&self, tokens: Vec<String>) -> String {
+        self.tokenizer.detokenize(&tokens)
+    }
+
+    /// Returns the ids a text is encoded into, with the special tokens of the template
+    fn encode(&self, text: &str) -> Vec<u32> {
+        self.tokenizer
+            .encode_batch(&[text])
+            .pop()
+            .map(|encoding| encoding.ids)
+            .unwrap_or_default()
+    }
+
+    /// Returns the text of a list of ids (without the special tokens unless asked)
+    #[pyo3(signature = (ids, skip_special_tokens = true))]
+    fn decode(&self, ids: Vec<u32>, skip_special_tokens: bool) -> String {
+        self.decode_ids(&ids, skip_special_tokens)
+    }
+
+    /// Returns the number of tokens of a text
+    fn count_tokens(&self, text: &str) -> usize {
+        self.tokenizer.count_tokens(text)
+    }
+
+    /// Returns the tokens of every text, without holding the GIL
+    fn tokenize_batch(&self, py: Python<'_>, texts: Vec<String>) -> Vec<Vec<String>> {
+        py.detach(|| {
+            let texts: Vec<&str> = texts.iter().map(String::as_str).collect();
+            self.tokenizer
+                .tokenize_batch(&texts)
+                .into_iter()
+                .map(token_texts)
+                .collect()
+        })
+    }
+
+    /// Returns the ids of every text (padded if the tokenizer pads batches), without holding
+    /// the GIL
+    fn encode_batch(&self, py: Python<'_>, texts: Vec<String>) -> Vec<Vec<u32>> {
+        py.detach(|| {
+            let texts: Vec<&str> = texts.iter().map(String::as_str).collect();
+            self.tokenizer
+                .encode_batch(&texts)
+                .into_iter()
+                .map(|encoding| encoding.ids)
+                .collect()
+        })
+    }
+
+    /// Returns the text of every list of ids, without holding the GIL
+    #[pyo3(signature = (ids, skip_special_tokens = true))]
+    fn decode_batch(
+        &self,
+        py: Python<'_>,
+        ids: Vec<Vec<u32>>,
+        skip_special_tokens: bool,
+    ) -> Vec<String> {
+        py.detach(|| {
+            ids.iter()
+                .map(|ids| self.decode_ids(ids, skip_special_tokens))
+                .collect()
+        })
+    }
+
+    /// The number of tokens of the vocabulary
+    #[getter]
+    fn vocab_size(&self) -> usize {
+        self.tokenizer.vocabulary().len()
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Tokenizer(vocab_size={})",
+            self.tokenizer.vocabulary().len()
+        )
+    }
+}
+
+impl PyTokenizer {
+    /// Returns the text of a list of ids
+    fn decode_ids(&self, ids: &[u32], skip_special_tokens: bool) -> String {
+        if skip_special_tokens {
+            self.tokenizer.decode_skip_special(ids)
+        } else {
+            self.tokenizer.decode(ids)
+        }
+    }
+}
+
+/// Returns the texts of tokens
+fn token_texts(tokens: Vec<Token>) -> Vec<String> {
+    tokens.into_iter().map(|token| token.text).collect()
+}
+
+/// The `tokenizer_rust` Python module
+#[pymodule]
+fn tokenizer_rust(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyTokenizer>()
+}
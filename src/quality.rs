@@ -0,0 +1,181 @@
+// Noise heuristics of sentences: punctuation, digit, uppercase and repetition ratios
+// Boilerplate and garbage in scraped text have typical shapes: "!!!! >>> ***" is mostly
+// punctuation, tables and logs are mostly digits, banners SHOUT, spam repeats the same words.
+// Each shape is one ratio between 0 and 1, so a corpus cleaner can choose its own thresholds.
+// The ratios are accumulated token by token, so they can be computed during the tokenization
+// pass itself (for example as a TokenTee consumer) instead of walking the text again.
+
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::token::{Token, TokenKind};
+use crate::Tokenizer;
+
+/// Noise ratios of a sentence (or any text), each between 0 and 1 (0 without tokens)
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct QualityScores {
+    /// Number of tokens (line and paragraph breaks excluded)
+    pub tokens: usize,
+    /// Share of the tokens that are punctuation
+    pub punctuation_ratio: f64,
+    /// Share of the characters of the tokens that are digits
+    pub digit_ratio: f64,
+    /// Share of the letters that are uppercase
+    pub uppercase_ratio: f64,
+    /// Share of the words, numbers and identifiers that already appeared earlier in the
+    /// text (compared lowercased)
+    pub repeated_token_ratio: f64,
+}
+
+impl QualityScores {
+    /// Tokenizes a text and returns its scores
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::quality::QualityScores;
+    ///
+    /// let tokenizer = Tokenizer::new(String::new());
+    /// let clean = QualityScores::measure(&tokenizer, "The river floods every spring.");
+    /// assert_eq!(clean.tokens, 6);
+    /// assert!(clean.punctuation_ratio < 0.2 && clean.repeated_token_ratio == 0.0);
+    ///
+    /// let spam = QualityScores::measure(&tokenizer, "BUY NOW buy now BUY NOW!!!");
+    /// assert!(spam.uppercase_ratio > 0.6);
+    /// assert!(spam.repeated_token_ratio > 0.6);
+    /// ```
+    pub fn measure(tokenizer: &Tokenizer, text: &str) -> Self {
+        QualityScores::of(&tokenizer.tokenize_text(text))
+    }
+
+    /// Returns the scores of already tokenized text
+    pub fn of(tokens: &[Token]) -> Self {
+        let mut meter = QualityMeter::new();
+        tokens.iter().for_each(|token| meter.push(token));
+        meter.scores()
+    }
+}
+
+impl fmt::Display for QualityScores {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "tokens {}, punctuation {:.2}, digits {:.2}, uppercase {:.2}, repeated {:.2}",
+            self.tokens,
+            self.punctuation_ratio,
+            self.digit_ratio,
+            self.uppercase_ratio,
+            self.repeated_token_ratio
+        )
+    }
+}
+
+/// Accumulates the counts behind QualityScores, one token at a time
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::Tokenizer;
+/// use tokenizer_rust::quality::QualityMeter;
+/// use tokenizer_rust::tee::TokenTee;
+///
+/// let tokenizer = Tokenizer::new(String::new());
+/// let mut meter = QualityMeter::new();
+/// let mut lengths = Vec::new();
+///
+/// // The scores are computed in the same pass as the other consumers
+/// TokenTee::new()
+///     .with_consumer(|token| meter.push(token))
+///     .with_consumer(|token| lengths.push(token.text.len()))
+///     .run(tokenizer.tokenize_text("Call 555 0199 now!"));
+///
+/// assert_eq!(lengths, vec![4, 3, 4, 3, 1]);
+/// let scores = meter.scores();
+/// assert_eq!(scores.tokens, 5);
+/// assert_eq!(scores.punctuation_ratio, 0.2);
+/// assert_eq!(scores.digit_ratio, 7.0 / 15.0);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct QualityMeter {
+    /// Tokens seen (line and paragraph breaks excluded)
+    tokens: usize,
+    /// Punctuation tokens
+    punctuation: usize,
+    /// Characters of all tokens
+    characters: usize,
+    /// Digit characters
+    digits: usize,
+    /// Letters
+    letters: usize,
+    /// Uppercase letters
+    uppercase: usize,
+    /// Words, numbers and identifiers
+    terms: usize,
+    /// Words, numbers and identifiers seen before
+    repeated: usize,
+    /// The lowercased terms seen so far
+    seen: HashSet<String>,
+}
+
+impl QualityMeter {
+    /// Creates a meter without tokens
+    pub fn new() -> Self {
+        QualityMeter::default()
+    }
+
+    /// Adds one token
+    pub fn push(&mut self, token: &Token) {
+        match token.kind {
+            TokenKind::LineBreak | TokenKind::ParagraphBreak => return,
+            TokenKind::Punctuation => self.punctuation += 1,
+            TokenKind::Word | TokenKind::Number | TokenKind::Identifier => {
+                self.terms += 1;
+                if !self.seen.insert(token.text.to_lowercase()) {
+                    self.repeated += 1;
+                }
+            }
+            TokenKind::Special => {}
+        }
+        self.tokens += 1;
+
+        for character in token.text.chars() {
+            self.characters += 1;
+            if character.is_numeric() {
+                self.digits += 1;
+            } else if character.is_alphabetic() {
+                self.letters += 1;
+                self.uppercase += usize::from(character.is_uppercase());
+            }
+        }
+    }
+
+    /// Returns the scores of the tokens added so far
+    pub fn scores(&self) -> QualityScores {
+        QualityScores {
+            tokens: self.tokens,
+            punctuation_ratio: ratio(self.punctuation, self.tokens),
+            digit_ratio: ratio(self.digits, self.characters),
+            uppercase_ratio: ratio(self.uppercase, self.letters),
+            repeated_token_ratio: ratio(self.repeated, self.terms),
+        }
+    }
+
+    /// Forgets every token, to measure the next sentence
+    pub fn clear(&mut self) {
+        let mut seen = std::mem::take(&mut self.seen);
+        seen.clear();
+        *self = QualityMeter {
+            seen,
+            ..QualityMeter::default()
+        };
+    }
+}
+
+/// Returns part / total, or 0 when total is 0
+fn ratio(part: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 / total as f64
+    }
+}
@@ -0,0 +1,243 @@
+// Readability scores of English text
+// The classic readability formulas only need a few counts: sentences, words, syllables,
+// letters and "polysyllables" (words of three syllables or more). The words come from the
+// tokenizer (Word tokens; numbers and punctuation don't count), the sentences from the
+// SentenceSplitter, and the syllables from an estimator: counting syllables exactly needs a
+// pronunciation dictionary, but counting vowel groups with a few rules for silent endings
+// ("make", "jumped") gets most English words right, which is all the formulas need.
+
+use crate::sentences::SentenceSplitter;
+use crate::token::TokenKind;
+use crate::Tokenizer;
+
+/// The counts of a text and the readability scores computed from them
+///
+/// Every score is 0.0 for a text without words.
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::readability::Readability;
+///
+/// let text = "The cat sat on the mat. It was a sunny day.";
+/// let readability = Readability::analyze(text);
+/// assert_eq!(readability.sentences, 2);
+/// assert_eq!(readability.words, 11);
+/// assert_eq!(readability.syllables, 12);
+/// assert!(readability.flesch_reading_ease() > 100.0);
+///
+/// let text = "Institutional considerations necessitate comprehensive evaluation \
+///             of organizational accountability.";
+/// let readability = Readability::analyze(text);
+/// assert!(readability.flesch_reading_ease() < 0.0);
+/// assert!(readability.flesch_kincaid_grade() > 20.0);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Readability {
+    /// Number of sentences
+    pub sentences: usize,
+    /// Number of words (Word tokens)
+    pub words: usize,
+    /// Estimated number of syllables of all words
+    pub syllables: usize,
+    /// Number of words with three syllables or more
+    pub polysyllables: usize,
+    /// Number of letters in the words
+    pub letters: usize,
+}
+
+impl Readability {
+    /// Counts the sentences, words, syllables and letters of a text
+    ///
+    /// The text is tokenized with the default Tokenizer settings and split into sentences
+    /// with the default SentenceSplitter.
+    ///
+    /// # Arguments
+    /// * `text` - The text to analyze
+    ///
+    /// # Returns
+    /// The counts of the text, from which every score is computed
+    pub fn analyze(text: &str) -> Self {
+        Readability::analyze_with(
+            text,
+            &Tokenizer::new(String::new()),
+            &SentenceSplitter::new(),
+        )
+    }
+
+    /// Counts the sentences, words, syllables and letters of a text with the given splitters
+    ///
+    /// # Arguments
+    /// * `text` - The text to analyze
+    /// * `tokenizer` - Gives the words of the text (its Word tokens)
+    /// * `splitter` - Gives the sentences of the text
+    ///
+    /// # Returns
+    /// The counts of the text
+    pub fn analyze_with(text: &str, tokenizer: &Tokenizer, splitter: &SentenceSplitter) -> Self {
+        let mut readability = Readability {
+            sentences: splitter.split(text).len(),
+            ..Readability::default()
+        };
+
+        for token in tokenizer.tokenize_text(text) {
+            if token.kind != TokenKind::Word {
+                continue;
+            }
+            let syllables = syllables(&token.text);
+            readability.words += 1;
+            readability.syllables += syllables;
+            readability.letters += token.text.chars().filter(|c| c.is_alphabetic()).count();
+            if syllables >= 3 {
+                readability.polysyllables += 1;
+            }
+        }
+
+        readability
+    }
+
+    /// Flesch reading ease: 206.835 - 1.015 × words per sentence - 84.6 × syllables per word
+    ///
+    /// Higher is easier: 90-100 is understood by an 11-year-old, 0-30 by university graduates.
+    pub fn flesch_reading_ease(&self) -> f64 {
+        self.score(|r| 206.835 - 1.015 * r.words_per_sentence() - 84.6 * r.syllables_per_word())
+    }
+
+    /// Flesch-Kincaid grade level: 0.39 × words per sentence + 11.8 × syllables per word - 15.59
+    ///
+    /// The result is a U.S. school grade.
+    pub fn flesch_kincaid_grade(&self) -> f64 {
+        self.score(|r| 0.39 * r.words_per_sentence() + 11.8 * r.syllables_per_word() - 15.59)
+    }
+
+    /// SMOG grade: 1.043 × √(polysyllables × 30 / sentences) + 3.1291
+    ///
+    /// The formula was made for samples of 30 sentences; shorter texts are scaled to 30.
+    pub fn smog_index(&self) -> f64 {
+        self.score(|r| 1.043 * (r.polysyllables as f64 * 30.0 / r.sentence_count()).sqrt() + 3.1291)
+    }
+
+    /// Gunning fog index: 0.4 × (words per sentence + 100 × polysyllables per word)
+    ///
+    /// Every word of three syllables or more counts as a complex word.
+    pub fn gunning_fog(&self) -> f64 {
+        self.score(|r| {
+            0.4 * (r.words_per_sentence() + 100.0 * r.polysyllables as f64 / r.words as f64)
+        })
+    }
+
+    /// Coleman-Liau index: 0.0588 × letters per 100 words - 0.296 × sentences per 100 words - 15.8
+    ///
+    /// It only counts letters, so it doesn't depend on the syllable estimates.
+    pub fn coleman_liau_index(&self) -> f64 {
+        self.score(|r| {
+            let letters = r.letters as f64 * 100.0 / r.words as f64;
+            let sentences = r.sentence_count() * 100.0 / r.words as f64;
+            0.0588 * letters - 0.296 * sentences - 15.8
+        })
+    }
+
+    /// Automated readability index: 4.71 × letters per word + 0.5 × words per sentence - 21.43
+    pub fn automated_readability_index(&self) -> f64 {
+        self.score(|r| {
+            4.71 * (r.letters as f64 / r.words as f64) + 0.5 * r.words_per_sentence() - 21.43
+        })
+    }
+
+    /// Returns the average number of words per sentence (0.0 without words)
+    pub fn words_per_sentence(&self) -> f64 {
+        if self.words == 0 {
+            return 0.0;
+        }
+        self.words as f64 / self.sentence_count()
+    }
+
+    /// Returns the average number of syllables per word (0.0 without words)
+    pub fn syllables_per_word(&self) -> f64 {
+        if self.words == 0 {
+            return 0.0;
+        }
+        self.syllables as f64 / self.words as f64
+    }
+
+    /// Returns the number of sentences, at least 1 (words always belong to a sentence)
+    fn sentence_count(&self) -> f64 {
+        self.sentences.max(1) as f64
+    }
+
+    /// Computes a score, or returns 0.0 for a text without words
+    fn score<F: Fn(&Self) -> f64>(&self, formula: F) -> f64 {
+        if self.words == 0 {
+            return 0.0;
+        }
+        formula(self)
+    }
+}
+
+/// Estimates the number of syllables of a word
+///
+/// Every group of vowels is a syllable ("y" is a vowel except at the start of the word),
+/// minus a silent final "e" ("make", but not "table"), and minus the "e" of "-ed" and "-es"
+/// when it isn't pronounced ("jumped" and "makes", but not "wanted" and "boxes"). Words of
+/// up to three letters have one syllable, and words without letters none.
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::readability::syllables;
+///
+/// let words = ["cat", "make", "table", "jumped", "wanted", "boxes", "beautiful", "readability"];
+/// let counts: Vec<usize> = words.iter().map(|word| syllables(word)).collect();
+/// assert_eq!(counts, vec![1, 1, 2, 1, 2, 2, 3, 5]);
+/// assert_eq!(syllables("42"), 0);
+/// ```
+pub fn syllables(word: &str) -> usize {
+    let letters: Vec<char> = word
+        .chars()
+        .filter(|c| c.is_alphabetic())
+        .flat_map(char::to_lowercase)
+        .collect();
+    if letters.len() <= 3 {
+        return usize::from(!letters.is_empty());
+    }
+
+    // STEP 1: Count the groups of vowels
+    let vowel = |index: usize| is_vowel(letters[index]) || (letters[index] == 'y' && index > 0);
+    let mut count = (0..letters.len())
+        .filter(|&index| vowel(index) && (index == 0 || !vowel(index - 1)))
+        .count();
+
+    // STEP 2: Remove the silent "e" of the endings
+    let length = letters.len();
+    let (last, before) = (letters[length - 1], letters[length - 2]);
+    let third = letters[length - 3];
+    let silent = match (before, last) {
+        // "make", "rose", but "table" and "free" keep their e
+        (_, 'e') => !vowel(length - 2) && (before != 'l' || vowel(length - 3)),
+        // "jumped", but "wanted" and "needed"
+        ('e', 'd') => !matches!(third, 't' | 'd') && !vowel(length - 3),
+        // "makes", but "boxes", "horses" and "judges"
+        ('e', 's') => !matches!(third, 's' | 'x' | 'z' | 'c' | 'g' | 'h') && !vowel(length - 3),
+        _ => false,
+    };
+    if silent && count > 1 {
+        count -= 1;
+    }
+
+    count.max(1)
+}
+
+/// Returns true for the vowels a, e, i, o, u, with or without accent
+fn is_vowel(letter: char) -> bool {
+    matches!(
+        letter,
+        'a' | 'e'
+            | 'i'
+            | 'o'
+            | 'u'
+            | 'à'..='å'
+            | 'è'..='ë'
+            | 'ì'..='ï'
+            | 'ò'..='ö'
+            | 'ù'..='ü'
+    )
+}
@@ -0,0 +1,290 @@
+// Resumable corpus runs: which input files of a long run are already done
+// A run over a big corpus can take hours, and starting it over after a crash or a Ctrl-C
+// wastes all of that. A ResumeManifest records every input file as soon as it is done, with
+// its size and a checksum of its content, so the next run skips those files and picks up at
+// the first one that is missing. A file whose size or checksum differs from the recorded one
+// was changed since it was processed, and is reported as such instead of being skipped.
+// The manifest is a text file with one line per finished file, appended and synced to disk
+// right away: an interruption loses at most the file that was being processed.
+
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::manifest::{fnv1a, FNV_OFFSET};
+
+/// The first line of a resume manifest
+const HEADER: &str = "# tokenizer-rust resume manifest v1";
+
+/// The size and content checksum (64-bit FNV-1a) of a file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fingerprint {
+    /// The size of the file in bytes
+    pub size: u64,
+    /// The checksum of its content
+    pub checksum: u64,
+}
+
+impl Fingerprint {
+    /// Reads a file and computes its fingerprint
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::resume::Fingerprint;
+    ///
+    /// let file = std::env::temp_dir().join("tokenizer_fingerprint_doctest.txt");
+    /// std::fs::write(&file, "Hello, world!").unwrap();
+    /// let before = Fingerprint::of(&file).unwrap();
+    /// assert_eq!(before.size, 13);
+    ///
+    /// std::fs::write(&file, "Hello, World!").unwrap();
+    /// let after = Fingerprint::of(&file).unwrap();
+    /// assert_eq!(after.size, before.size);
+    /// assert_ne!(after.checksum, before.checksum);
+    /// # std::fs::remove_file(&file).unwrap();
+    /// ```
+    pub fn of<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut file = File::open(path)?;
+        let mut buffer = vec![0; 64 * 1024];
+        let mut fingerprint = Fingerprint {
+            size: 0,
+            checksum: FNV_OFFSET,
+        };
+        loop {
+            let read = match file.read(&mut buffer) {
+                Ok(0) => return Ok(fingerprint),
+                Ok(read) => read,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                Err(error) => return Err(error),
+            };
+            fingerprint.size += read as u64;
+            fingerprint.checksum = fnv1a(fingerprint.checksum, &buffer[..read]);
+        }
+    }
+}
+
+/// A finished input file, as recorded in a ResumeManifest
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileEntry {
+    /// The input file, as it was given to the run
+    pub path: PathBuf,
+    /// Its size and checksum when it was processed
+    pub fingerprint: Fingerprint,
+    /// Number of documents (or sentences, records, ...) the run read from it
+    pub documents: usize,
+    /// The size of the run's output after this file was written (0 when it has none)
+    pub output_end: u64,
+}
+
+/// Where an input file stands in a resumed run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileStatus {
+    /// Not processed yet
+    Pending,
+    /// Processed, and unchanged since
+    Done,
+    /// Processed, but its content changed since
+    Changed,
+}
+
+/// The input files a run has finished, kept in a file so that the run can be resumed
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::resume::{FileStatus, Fingerprint, ResumeManifest};
+///
+/// let directory = std::env::temp_dir().join("tokenizer_resume_doctest");
+/// std::fs::create_dir_all(&directory).unwrap();
+/// let (first, second) = (directory.join("a.txt"), directory.join("b.txt"));
+/// std::fs::write(&first, "One file.").unwrap();
+/// std::fs::write(&second, "Another file.").unwrap();
+/// let path = directory.join("run.resume");
+/// # let _ = std::fs::remove_file(&path);
+///
+/// // The first run finishes a.txt, then stops
+/// let mut manifest = ResumeManifest::open(&path).unwrap();
+/// let fingerprint = Fingerprint::of(&first).unwrap();
+/// manifest.mark_done(&first, fingerprint, 1, 0).unwrap();
+/// drop(manifest);
+///
+/// // The next run only has b.txt left
+/// let manifest = ResumeManifest::open(&path).unwrap();
+/// assert_eq!(manifest.status(&first, &Fingerprint::of(&first).unwrap()), FileStatus::Done);
+/// assert_eq!(manifest.status(&second, &Fingerprint::of(&second).unwrap()), FileStatus::Pending);
+///
+/// // Until someone edits a.txt
+/// std::fs::write(&first, "One edited file.").unwrap();
+/// assert_eq!(manifest.status(&first, &Fingerprint::of(&first).unwrap()), FileStatus::Changed);
+/// # std::fs::remove_dir_all(&directory).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct ResumeManifest {
+    /// The finished files, by path
+    entries: HashMap<PathBuf, FileEntry>,
+    /// The manifest file, opened for appending
+    file: File,
+}
+
+impl ResumeManifest {
+    /// Opens a manifest, reading the files it records, or creates it if it doesn't exist
+    ///
+    /// A last line without its newline is a write that was interrupted and is ignored.
+    ///
+    /// # Returns
+    /// The manifest, or an error if it cannot be read or created, or isn't a resume manifest
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let path = path.as_ref();
+        let entries = match File::open(path) {
+            Ok(file) => read_entries(BufReader::new(file))?,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => HashMap::new(),
+            Err(error) => return Err(error),
+        };
+
+        // STEP 1: Start a new manifest with its header, or end a cut line of an old one
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        let length = file.metadata()?.len();
+        if length == 0 {
+            writeln!(file, "{}", HEADER)?;
+        } else if !fs::read(path)?.ends_with(b"\n") {
+            writeln!(file)?;
+        }
+        file.sync_data()?;
+
+        Ok(ResumeManifest { entries, file })
+    }
+
+    /// Returns whether a file is pending, done or changed since it was done
+    ///
+    /// # Arguments
+    /// * `path` - The input file, written the same way as when it was marked done
+    /// * `fingerprint` - Its current fingerprint
+    pub fn status<P: AsRef<Path>>(&self, path: P, fingerprint: &Fingerprint) -> FileStatus {
+        match self.entries.get(path.as_ref()) {
+            None => FileStatus::Pending,
+            Some(entry) if entry.fingerprint == *fingerprint => FileStatus::Done,
+            Some(_) => FileStatus::Changed,
+        }
+    }
+
+    /// Records a file as done, and syncs the manifest to disk
+    ///
+    /// # Arguments
+    /// * `path` - The input file
+    /// * `fingerprint` - Its fingerprint, taken before it was processed
+    /// * `documents` - How many documents were read from it
+    /// * `output_end` - The size of the output once the file is written (0 without output)
+    ///
+    /// # Returns
+    /// An error if the manifest cannot be written or the path contains a line break
+    pub fn mark_done<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        fingerprint: Fingerprint,
+        documents: usize,
+        output_end: u64,
+    ) -> io::Result<()> {
+        let path = path.as_ref();
+        let name = path.to_string_lossy();
+        if name.contains(['\n', '\r']) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("file names with line breaks cannot be resumed: {:?}", name),
+            ));
+        }
+
+        // One write per line, so that a line is either complete or cut at the end
+        let line = format!(
+            "{:016x}\t{}\t{}\t{}\t{}\n",
+            fingerprint.checksum, fingerprint.size, documents, output_end, name
+        );
+        self.file.write_all(line.as_bytes())?;
+        self.file.sync_data()?;
+
+        self.entries.insert(
+            path.to_path_buf(),
+            FileEntry {
+                path: path.to_path_buf(),
+                fingerprint,
+                documents,
+                output_end,
+            },
+        );
+        Ok(())
+    }
+
+    /// Returns the record of a finished file
+    pub fn entry<P: AsRef<Path>>(&self, path: P) -> Option<&FileEntry> {
+        self.entries.get(path.as_ref())
+    }
+
+    /// Returns the size of the output after the last finished file (0 if none is done)
+    ///
+    /// A resumed run truncates its output to this size, which drops what it wrote for the
+    /// file it was processing when it was interrupted.
+    pub fn output_end(&self) -> u64 {
+        self.entries
+            .values()
+            .map(|entry| entry.output_end)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Returns the number of finished files
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true if no file is finished
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Reads the entries of a manifest (the last line recorded for a path wins)
+fn read_entries<R: BufRead>(mut reader: R) -> io::Result<HashMap<PathBuf, FileEntry>> {
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+    let mut entries = HashMap::new();
+    let mut line = String::new();
+    let mut number = 0;
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(entries);
+        }
+        number += 1;
+        let Some(content) = line.strip_suffix('\n') else {
+            // An interrupted write: the file it was recording is not done
+            return Ok(entries);
+        };
+        if number == 1 {
+            if content != HEADER {
+                return Err(invalid("not a resume manifest".to_string()));
+            }
+            continue;
+        }
+        if content.is_empty() {
+            continue;
+        }
+
+        let entry = parse_entry(content)
+            .ok_or_else(|| invalid(format!("invalid resume manifest line {}", number)))?;
+        entries.insert(entry.path.clone(), entry);
+    }
+}
+
+/// Parses a "checksum<TAB>size<TAB>documents<TAB>output_end<TAB>path" line
+fn parse_entry(line: &str) -> Option<FileEntry> {
+    let mut fields = line.splitn(5, '\t');
+    let checksum = u64::from_str_radix(fields.next()?, 16).ok()?;
+    let size = fields.next()?.parse().ok()?;
+    let documents = fields.next()?.parse().ok()?;
+    let output_end = fields.next()?.parse().ok()?;
+    let path = PathBuf::from(fields.next()?);
+    Some(FileEntry {
+        path,
+        fingerprint: Fingerprint { size, checksum },
+        documents,
+        output_end,
+    })
+}
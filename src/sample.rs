@@ -0,0 +1,337 @@
+// Corpus sampling: a seeded random sample of N items from a stream of any length
+// Reservoir sampling keeps only N items in memory, however big the input is, and every item
+// of the stream has the same chance to end up in the sample. The random generator is
+// seeded, so the same seed on the same corpus always gives the same sample.
+// A WeightedReservoir gives every item a chance proportional to a weight instead (its
+// frequency, its length), which is how evaluation sets favour common tokens or long
+// sentences. It uses the same single pass: every item gets a random key u^(1/weight), and
+// the items with the largest keys are the sample (Efraimidis and Spirakis).
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::io::{self, BufRead};
+
+use crate::sentences::SentenceSplitter;
+
+/// A fixed-size uniform random sample of a stream (reservoir sampling)
+#[derive(Debug, Clone)]
+pub struct Reservoir<T> {
+    /// How many items the sample keeps
+    capacity: usize,
+    /// How many items were pushed so far
+    seen: usize,
+    /// The sampled items, with their position in the stream
+    items: Vec<(usize, T)>,
+    /// The seeded random generator
+    random: SplitMix64,
+}
+
+impl<T> Reservoir<T> {
+    /// Creates an empty reservoir keeping `capacity` items
+    ///
+    /// # Arguments
+    /// * `capacity` - The size of the sample
+    /// * `seed` - The seed of the random generator (same seed and input, same sample)
+    pub fn new(capacity: usize, seed: u64) -> Self {
+        Reservoir {
+            capacity,
+            seen: 0,
+            items: Vec::with_capacity(capacity.min(1024)),
+            random: SplitMix64(seed),
+        }
+    }
+
+    /// Offers the next item of the stream to the sample
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::sample::Reservoir;
+    ///
+    /// let mut reservoir = Reservoir::new(3, 42);
+    /// for number in 0..1000 {
+    ///     reservoir.push(number);
+    /// }
+    /// assert_eq!(reservoir.seen(), 1000);
+    ///
+    /// let sample = reservoir.into_sample();
+    /// assert_eq!(sample.len(), 3);
+    /// // The same seed gives the same sample
+    /// let mut again = Reservoir::new(3, 42);
+    /// (0..1000).for_each(|number| again.push(number));
+    /// assert_eq!(again.into_sample(), sample);
+    /// ```
+    pub fn push(&mut self, item: T) {
+        let position = self.seen;
+        self.seen += 1;
+
+        // The first items fill the reservoir, then item number k replaces a random
+        // sampled item with probability capacity / k
+        if self.items.len() < self.capacity {
+            self.items.push((position, item));
+        } else {
+            let slot = self.random.below(self.seen);
+            if slot < self.capacity {
+                self.items[slot] = (position, item);
+            }
+        }
+    }
+
+    /// Returns how many items were offered so far
+    pub fn seen(&self) -> usize {
+        self.seen
+    }
+
+    /// Returns the sample, in the order the items came in the stream
+    pub fn into_sample(mut self) -> Vec<T> {
+        self.items.sort_unstable_by_key(|&(position, _)| position);
+        self.items.into_iter().map(|(_, item)| item).collect()
+    }
+}
+
+impl Reservoir<String> {
+    /// Offers every non-empty line of a reader (one document per line)
+    ///
+    /// # Returns
+    /// An error if the reader fails or the text is not valid UTF-8
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::sample::Reservoir;
+    ///
+    /// let mut reservoir = Reservoir::new(10, 7);
+    /// reservoir.push_lines("first doc\n\nsecond doc\n".as_bytes()).unwrap();
+    /// assert_eq!(reservoir.into_sample(), vec!["first doc", "second doc"]);
+    /// ```
+    pub fn push_lines<R: BufRead>(&mut self, reader: R) -> io::Result<()> {
+        for line in reader.lines() {
+            let line = line?;
+            if !line.trim().is_empty() {
+                self.push(line);
+            }
+        }
+        Ok(())
+    }
+
+    /// Offers every sentence of a reader
+    ///
+    /// The text is read line by line and every line is split into sentences, so only one
+    /// line is in memory at a time and a sentence never spans two lines.
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::sample::Reservoir;
+    /// use tokenizer_rust::sentences::SentenceSplitter;
+    ///
+    /// let mut reservoir = Reservoir::new(10, 7);
+    /// let text = "Dr. Who came. He saw.\nBye!\n";
+    /// reservoir.push_sentences(text.as_bytes(), &SentenceSplitter::new()).unwrap();
+    /// assert_eq!(reservoir.into_sample(), vec!["Dr. Who came.", "He saw.", "Bye!"]);
+    /// ```
+    pub fn push_sentences<R: BufRead>(
+        &mut self,
+        reader: R,
+        splitter: &SentenceSplitter,
+    ) -> io::Result<()> {
+        for line in reader.lines() {
+            let line = line?;
+            for sentence in splitter.sentences(&line) {
+                self.push(sentence.to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A fixed-size random sample of a stream where every item has a chance proportional to
+/// its weight (weighted reservoir sampling, without replacement)
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::sample::WeightedReservoir;
+///
+/// // "heavy" weighs 1000 times more than each of the other items
+/// let mut picked = 0;
+/// for seed in 0..100 {
+///     let mut reservoir = WeightedReservoir::new(1, seed);
+///     for number in 0..10 {
+///         reservoir.push(format!("light {}", number), 1.0);
+///     }
+///     reservoir.push("heavy".to_string(), 1000.0);
+///     if reservoir.into_sample() == vec!["heavy"] {
+///         picked += 1;
+///     }
+/// }
+/// assert!(picked > 95);
+/// ```
+#[derive(Debug, Clone)]
+pub struct WeightedReservoir<T> {
+    /// How many items the sample keeps
+    capacity: usize,
+    /// How many items were pushed so far
+    seen: usize,
+    /// The sampled items, the one with the smallest key on top
+    items: BinaryHeap<Candidate<T>>,
+    /// The seeded random generator
+    random: SplitMix64,
+}
+
+impl<T> WeightedReservoir<T> {
+    /// Creates an empty reservoir keeping `capacity` items
+    ///
+    /// # Arguments
+    /// * `capacity` - The size of the sample
+    /// * `seed` - The seed of the random generator (same seed and input, same sample)
+    pub fn new(capacity: usize, seed: u64) -> Self {
+        WeightedReservoir {
+            capacity,
+            seen: 0,
+            items: BinaryHeap::with_capacity(capacity.min(1024)),
+            random: SplitMix64(seed),
+        }
+    }
+
+    /// Offers the next item of the stream to the sample
+    ///
+    /// # Arguments
+    /// * `item` - The item
+    /// * `weight` - Its weight (items with a weight of zero or less are never sampled)
+    pub fn push(&mut self, item: T, weight: f64) {
+        let position = self.seen;
+        self.seen += 1;
+        if self.capacity == 0 || weight.is_nan() || weight <= 0.0 {
+            return;
+        }
+
+        // ln(u^(1/weight)) keeps the order of the keys without underflowing for big weights
+        let key = self.random.unit().ln() / weight;
+        if self.items.len() < self.capacity {
+            self.items.push(Candidate {
+                key,
+                position,
+                item,
+            });
+        } else if self.items.peek().is_some_and(|smallest| key > smallest.key) {
+            self.items.pop();
+            self.items.push(Candidate {
+                key,
+                position,
+                item,
+            });
+        }
+    }
+
+    /// Returns how many items were offered so far
+    pub fn seen(&self) -> usize {
+        self.seen
+    }
+
+    /// Returns the sample, in the order the items came in the stream
+    pub fn into_sample(self) -> Vec<T> {
+        let mut items = self.items.into_vec();
+        items.sort_unstable_by_key(|candidate| candidate.position);
+        items.into_iter().map(|candidate| candidate.item).collect()
+    }
+}
+
+impl WeightedReservoir<String> {
+    /// Offers every sentence of a reader, with a weight computed from the sentence
+    ///
+    /// The text is read line by line like Reservoir::push_sentences().
+    ///
+    /// # Arguments
+    /// * `reader` - The text
+    /// * `splitter` - Splits every line into sentences
+    /// * `weight` - Gives the weight of a sentence (its length, for example)
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::sample::WeightedReservoir;
+    /// use tokenizer_rust::sentences::SentenceSplitter;
+    ///
+    /// // Longer sentences are more likely to be picked
+    /// let mut reservoir = WeightedReservoir::new(2, 7);
+    /// let text = "Hi. Ok.\nThis sentence is much longer than the others, so it is picked more often.\n";
+    /// reservoir
+    ///     .push_sentences(text.as_bytes(), &SentenceSplitter::new(), |sentence| sentence.len() as f64)
+    ///     .unwrap();
+    /// assert_eq!(reservoir.seen(), 3);
+    /// assert_eq!(reservoir.into_sample().len(), 2);
+    /// ```
+    pub fn push_sentences<R, F>(
+        &mut self,
+        reader: R,
+        splitter: &SentenceSplitter,
+        weight: F,
+    ) -> io::Result<()>
+    where
+        R: BufRead,
+        F: Fn(&str) -> f64,
+    {
+        for line in reader.lines() {
+            let line = line?;
+            for sentence in splitter.sentences(&line) {
+                self.push(sentence.to_string(), weight(sentence));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An item of a WeightedReservoir with its random key
+#[derive(Debug, Clone)]
+struct Candidate<T> {
+    /// The random key (the largest keys are kept)
+    key: f64,
+    /// The position of the item in the stream
+    position: usize,
+    /// The item
+    item: T,
+}
+
+// The heap is ordered so that the smallest key is on top (ties: the latest item first)
+impl<T> Ord for Candidate<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .key
+            .total_cmp(&self.key)
+            .then_with(|| self.position.cmp(&other.position))
+    }
+}
+
+impl<T> PartialOrd for Candidate<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> PartialEq for Candidate<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T> Eq for Candidate<T> {}
+
+/// A small seeded random generator (SplitMix64), good enough for sampling
+#[derive(Debug, Clone)]
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a random number in 0..bound
+    fn below(&mut self, bound: usize) -> usize {
+        ((self.next_u64() as u128 * bound as u128) >> 64) as usize
+    }
+
+    /// Returns a random number in (0, 1]
+    fn unit(&mut self) -> f64 {
+        ((self.next_u64() >> 11) + 1) as f64 / (1u64 << 53) as f64
+    }
+}
@@ -0,0 +1,776 @@
+// Search: a positional inverted index over tokenized documents
+// Every document is tokenized with the Tokenizer of the index, and its words, numbers and
+// identifiers are stored lowercased ("terms") together with the positions where they occur.
+// Queries go through the same Tokenizer, so documents and queries always agree on what a
+// term is. The positions allow phrase ("new york") and proximity (within k terms) queries.
+// Synonym groups ("car", "automobile") are applied when the query is tokenized: a query
+// term matches every term of its group, so new synonyms work on documents that are
+// already indexed.
+// Documents can carry an ID and metadata of the caller, returned with every search hit.
+// Documents can be removed at any time: a removed document only gets a tombstone and is
+// skipped by every query, and its postings are dropped in bulk by compact(), which runs
+// on its own once enough documents were removed.
+
+use std::collections::{BTreeMap, HashMap};
+
+use crate::token::{Token, TokenKind};
+use crate::Tokenizer;
+
+/// The occurrences of one term in one document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Posting {
+    /// The number of the document (in the order documents were added)
+    pub document: usize,
+    /// The positions of the term in the document (0 for the first term, 1 for the next, ...)
+    pub positions: Vec<usize>,
+    /// The (start, end) byte offsets of every occurrence, in the same order as the
+    /// positions (empty unless the index stores offsets, see Index::with_offsets())
+    pub offsets: Vec<(usize, usize)>,
+}
+
+impl Posting {
+    /// Creates a posting without occurrences
+    fn new(document: usize) -> Self {
+        Posting {
+            document,
+            positions: Vec::new(),
+            offsets: Vec::new(),
+        }
+    }
+
+    /// Adds a match starting at `position`, going from the first to the last occurrence
+    fn push_match(
+        &mut self,
+        position: usize,
+        first: Option<(usize, usize)>,
+        last: Option<(usize, usize)>,
+    ) {
+        self.positions.push(position);
+        if let (Some((start, _)), Some((_, end))) = (first, last) {
+            self.offsets.push((start, end));
+        }
+    }
+}
+
+/// One occurrence of a term: its position and its byte offsets (if the index stores them)
+type Occurrence = (usize, Option<(usize, usize)>);
+
+/// Groups of terms that mean the same thing in a query
+///
+/// Terms are compared lowercased, and each term is one token ("automobile", not "motor car").
+/// A term belongs to at most one group: adding a group that shares a term with an existing
+/// one merges the two.
+#[derive(Debug, Clone, Default)]
+pub struct Synonyms {
+    /// The groups, an empty group was merged into a later one
+    groups: Vec<Vec<String>>,
+    /// The group of every term (index in groups)
+    group_of: HashMap<String, usize>,
+}
+
+impl Synonyms {
+    /// Creates an empty set of synonyms
+    pub fn new() -> Self {
+        Synonyms::default()
+    }
+
+    /// Adds a group of synonyms and returns the set (builder style)
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::search::Synonyms;
+    ///
+    /// let synonyms = Synonyms::new()
+    ///     .with_group(["car", "automobile"])
+    ///     .with_group(["Auto", "car"]);
+    /// // Both groups share "car", so they are merged
+    /// assert_eq!(synonyms.len(), 1);
+    /// assert_eq!(synonyms.expand("AUTOMOBILE"), vec!["auto", "automobile", "car"]);
+    /// assert_eq!(synonyms.expand("bike"), vec!["bike"]);
+    /// ```
+    pub fn with_group<I, S>(mut self, terms: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.add_group(terms);
+        self
+    }
+
+    /// Adds a group of synonyms, merging it with the groups that share a term with it
+    ///
+    /// Empty terms are ignored, and a group of less than two terms changes nothing.
+    pub fn add_group<I, S>(&mut self, terms: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut terms: Vec<String> = terms
+            .into_iter()
+            .map(|term| normalize_term(term.as_ref()))
+            .filter(|term| !term.is_empty())
+            .collect();
+        terms.sort();
+        terms.dedup();
+        if terms.len() < 2 {
+            return;
+        }
+
+        // STEP 1: Take the terms of every group this one overlaps with
+        let mut merged = Vec::new();
+        for term in terms {
+            match self.group_of.get(&term) {
+                Some(&group) => merged.append(&mut self.groups[group]),
+                None => merged.push(term),
+            }
+        }
+        merged.sort();
+        merged.dedup();
+
+        // STEP 2: Store the merged group under a new number
+        let group = self.groups.len();
+        for term in &merged {
+            self.group_of.insert(term.clone(), group);
+        }
+        self.groups.push(merged);
+    }
+
+    /// Returns the terms a query term stands for: its whole group (sorted), or only itself
+    pub fn expand(&self, term: &str) -> Vec<String> {
+        let term = normalize_term(term);
+        match self.group_of.get(&term) {
+            Some(&group) => self.groups[group].clone(),
+            None => vec![term],
+        }
+    }
+
+    /// Returns the number of groups
+    pub fn len(&self) -> usize {
+        self.groups.iter().filter(|group| !group.is_empty()).count()
+    }
+
+    /// Returns true if no group was added
+    pub fn is_empty(&self) -> bool {
+        self.group_of.is_empty()
+    }
+}
+
+/// Small key-value data stored with a document and returned with its search hits
+pub type Metadata = BTreeMap<String, String>;
+
+/// A document that matches a query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchHit {
+    /// The number of the document
+    pub document: usize,
+    /// The ID given to upsert_document() (None for documents added with add_document())
+    pub id: Option<String>,
+    /// The metadata stored with the document
+    pub metadata: Metadata,
+    /// How many of the query terms (or one of their synonyms) the document contains
+    pub matched_terms: usize,
+    /// How many times those terms occur in the document
+    pub occurrences: usize,
+}
+
+/// What the index keeps about a document besides its postings
+struct StoredDocument {
+    /// Number of terms
+    length: usize,
+    /// The ID of the caller, if any
+    id: Option<String>,
+    /// The metadata of the caller
+    metadata: Metadata,
+}
+
+/// A positional inverted index: for every term, the documents and positions where it occurs
+pub struct Index {
+    /// Tokenizes documents and queries
+    tokenizer: Tokenizer,
+    /// The postings of every term, sorted by document
+    postings: HashMap<String, Vec<Posting>>,
+    /// Every document by number (None once it is removed)
+    documents: Vec<Option<StoredDocument>>,
+    /// The number of the document with each ID
+    ids: HashMap<String, usize>,
+    /// Number of documents that are not removed
+    live_documents: usize,
+    /// Number of removed documents whose postings are still in the index
+    tombstones: usize,
+    /// compact() runs when tombstones exceed this share of the live documents
+    compaction_ratio: f64,
+    /// If true, postings also keep the byte offsets of every occurrence
+    store_offsets: bool,
+    /// Synonyms applied to queries
+    synonyms: Synonyms,
+}
+
+impl Index {
+    /// Creates an empty index whose documents and queries are tokenized with the given Tokenizer
+    pub fn new(tokenizer: Tokenizer) -> Self {
+        Index {
+            tokenizer,
+            postings: HashMap::new(),
+            documents: Vec::new(),
+            ids: HashMap::new(),
+            live_documents: 0,
+            tombstones: 0,
+            compaction_ratio: 0.25,
+            store_offsets: false,
+            synonyms: Synonyms::new(),
+        }
+    }
+
+    /// Sets when remove_document() compacts the index on its own
+    ///
+    /// The index is compacted when the removed documents still in it are more than
+    /// `ratio` times the remaining documents (0.25 by default). `f64::INFINITY` turns
+    /// automatic compaction off, 0 compacts after every removal.
+    pub fn with_compaction_ratio(mut self, ratio: f64) -> Self {
+        self.compaction_ratio = ratio;
+        self
+    }
+
+    /// Stores the byte offsets of every occurrence in the postings
+    ///
+    /// Snippets and highlights can then be made straight from the postings, without
+    /// tokenizing the documents again. Offsets cost two numbers per occurrence and are off
+    /// by default. This has to be set before documents are added.
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::search::Index;
+    ///
+    /// let text = "Rust is fast. I like rust.";
+    /// let mut index = Index::new(Tokenizer::new(String::new())).with_offsets(true);
+    /// index.add_document(text);
+    ///
+    /// let posting = index.postings("rust").next().unwrap();
+    /// assert_eq!(posting.offsets, vec![(0, 4), (21, 25)]);
+    /// assert_eq!(&text[21..25], "rust");
+    /// // Phrase and proximity matches get the byte range of the whole match
+    /// assert_eq!(index.phrase("like rust")[0].offsets, vec![(16, 25)]);
+    /// ```
+    pub fn with_offsets(mut self, enabled: bool) -> Self {
+        self.store_offsets = enabled;
+        self
+    }
+
+    /// Returns true if postings keep byte offsets
+    pub fn stores_offsets(&self) -> bool {
+        self.store_offsets
+    }
+
+    /// Sets the synonyms applied to queries
+    pub fn with_synonyms(mut self, synonyms: Synonyms) -> Self {
+        self.synonyms = synonyms;
+        self
+    }
+
+    /// Adds a group of synonyms, used by every following search (no re-indexing needed)
+    pub fn add_synonyms<I, S>(&mut self, terms: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.synonyms.add_group(terms);
+    }
+
+    /// Returns the synonyms applied to queries
+    pub fn synonyms(&self) -> &Synonyms {
+        &self.synonyms
+    }
+
+    /// Tokenizes a document and adds its terms to the index
+    ///
+    /// # Returns
+    /// The number of the document (0 for the first one, then 1, 2, ...)
+    pub fn add_document(&mut self, text: &str) -> usize {
+        self.insert(text, None, Metadata::new())
+    }
+
+    /// Adds a document under an ID of the caller, replacing the document with that ID
+    ///
+    /// The ID and the metadata come back with every search hit of the document, so hits
+    /// can be joined back to the caller's data. Replacing a document removes the old
+    /// version (see remove_document()) and gives the new one a new number.
+    ///
+    /// # Arguments
+    /// * `id` - The ID of the document in the caller's data
+    /// * `text` - The text to index
+    /// * `metadata` - Small data to return with the hits (title, URL, ...)
+    ///
+    /// # Returns
+    /// The number of the document
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::search::{Index, Metadata};
+    ///
+    /// let mut index = Index::new(Tokenizer::new(String::new()));
+    /// let mut metadata = Metadata::new();
+    /// metadata.insert("url".to_string(), "/faq#refunds".to_string());
+    /// index.upsert_document("faq-7", "Refunds take two weeks.", metadata.clone());
+    /// index.upsert_document("faq-8", "Shipping is free.", Metadata::new());
+    ///
+    /// let hits = index.search("refunds");
+    /// assert_eq!(hits[0].id.as_deref(), Some("faq-7"));
+    /// assert_eq!(hits[0].metadata["url"], "/faq#refunds");
+    ///
+    /// // Updating a document keeps its ID
+    /// index.upsert_document("faq-7", "Refunds take three days.", metadata);
+    /// assert_eq!(index.search("weeks"), vec![]);
+    /// assert_eq!(index.search("days")[0].id.as_deref(), Some("faq-7"));
+    /// assert_eq!(index.len(), 2);
+    /// ```
+    pub fn upsert_document(&mut self, id: &str, text: &str, metadata: Metadata) -> usize {
+        if let Some(&old) = self.ids.get(id) {
+            self.remove_document(old);
+        }
+        let document = self.insert(text, Some(id.to_string()), metadata);
+        self.ids.insert(id.to_string(), document);
+        document
+    }
+
+    /// Removes the document with an ID of the caller
+    ///
+    /// # Returns
+    /// True if there was a document with this ID
+    pub fn remove_by_id(&mut self, id: &str) -> bool {
+        match self.ids.get(id) {
+            Some(&document) => self.remove_document(document),
+            None => false,
+        }
+    }
+
+    /// Returns the number of the document with an ID of the caller
+    pub fn document_number(&self, id: &str) -> Option<usize> {
+        self.ids.get(id).copied()
+    }
+
+    /// Returns the ID of a document (None if it has none or was removed)
+    pub fn document_id(&self, document: usize) -> Option<&str> {
+        self.stored(document)?.id.as_deref()
+    }
+
+    /// Returns the metadata of a document (None if there is no such document)
+    pub fn metadata(&self, document: usize) -> Option<&Metadata> {
+        self.stored(document).map(|stored| &stored.metadata)
+    }
+
+    /// Indexes a document and stores what the index keeps about it
+    fn insert(&mut self, text: &str, id: Option<String>, metadata: Metadata) -> usize {
+        let document = self.documents.len();
+
+        let terms = self.term_tokens(text);
+        for (position, term) in terms.iter().enumerate() {
+            let postings = self.postings.entry(term.text.clone()).or_default();
+            // Documents are added in order, so this document can only be the last one
+            if postings
+                .last()
+                .is_none_or(|posting| posting.document != document)
+            {
+                postings.push(Posting::new(document));
+            }
+            if let Some(posting) = postings.last_mut() {
+                posting.positions.push(position);
+                if self.store_offsets {
+                    posting.offsets.push((term.start, term.end));
+                }
+            }
+        }
+
+        self.documents.push(Some(StoredDocument {
+            length: terms.len(),
+            id,
+            metadata,
+        }));
+        self.live_documents += 1;
+        document
+    }
+
+    /// Removes a document from the index
+    ///
+    /// The document is skipped by every following query right away. Its postings are only
+    /// dropped by the next compaction, and its number is never given to another document.
+    ///
+    /// # Returns
+    /// True if the document was in the index, false if it was removed already or never added
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::search::Index;
+    ///
+    /// let mut index = Index::new(Tokenizer::new(String::new())).with_compaction_ratio(f64::INFINITY);
+    /// index.add_document("red apples");
+    /// let pears = index.add_document("red pears");
+    ///
+    /// assert!(index.remove_document(pears));
+    /// assert!(!index.remove_document(pears));
+    /// assert_eq!(index.search("red").len(), 1);
+    /// assert_eq!((index.len(), index.tombstones()), (1, 1));
+    ///
+    /// index.compact();
+    /// assert_eq!(index.tombstones(), 0);
+    /// assert_eq!(index.postings("pears").count(), 0);
+    /// // Numbers are never reused
+    /// assert_eq!(index.add_document("green pears"), 2);
+    /// ```
+    pub fn remove_document(&mut self, document: usize) -> bool {
+        let Some(stored) = self.documents.get_mut(document).and_then(Option::take) else {
+            return false;
+        };
+        if let Some(id) = stored.id {
+            self.ids.remove(&id);
+        }
+
+        self.live_documents -= 1;
+        self.tombstones += 1;
+        if self.tombstones as f64 > self.compaction_ratio * self.live_documents as f64 {
+            self.compact();
+        }
+        true
+    }
+
+    /// Drops the postings of all removed documents
+    ///
+    /// This goes over the whole index once, so it is done in bulk instead of on every
+    /// removal. remove_document() calls it on its own (see with_compaction_ratio()).
+    pub fn compact(&mut self) {
+        if self.tombstones == 0 {
+            return;
+        }
+
+        let documents = &self.documents;
+        self.postings.retain(|_, postings| {
+            postings.retain(|posting| documents[posting.document].is_some());
+            !postings.is_empty()
+        });
+        self.tombstones = 0;
+    }
+
+    /// Returns the number of removed documents whose postings were not dropped yet
+    pub fn tombstones(&self) -> usize {
+        self.tombstones
+    }
+
+    /// Returns the number of documents (removed ones don't count)
+    pub fn len(&self) -> usize {
+        self.live_documents
+    }
+
+    /// Returns true if there are no documents
+    pub fn is_empty(&self) -> bool {
+        self.live_documents == 0
+    }
+
+    /// Returns the number of terms of a document (None if there is no such document)
+    pub fn document_length(&self, document: usize) -> Option<usize> {
+        self.stored(document).map(|stored| stored.length)
+    }
+
+    /// Returns the postings of a term (case doesn't matter), sorted by document
+    ///
+    /// Removed documents are skipped.
+    pub fn postings(&self, term: &str) -> impl Iterator<Item = &Posting> {
+        self.postings
+            .get(&normalize_term(term))
+            .into_iter()
+            .flatten()
+            .filter(|posting| self.documents[posting.document].is_some())
+    }
+
+    /// Returns what the index keeps about a document that was not removed
+    fn stored(&self, document: usize) -> Option<&StoredDocument> {
+        self.documents.get(document)?.as_ref()
+    }
+
+    /// Tokenizes a query and expands every term with its synonyms
+    ///
+    /// # Returns
+    /// One entry per query term, with the terms it matches
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::search::Index;
+    ///
+    /// let mut index = Index::new(Tokenizer::new(String::new()));
+    /// index.add_synonyms(["car", "automobile"]);
+    /// assert_eq!(index.expand_query("Red car!"), vec![vec!["red"], vec!["automobile", "car"]]);
+    /// ```
+    pub fn expand_query(&self, query: &str) -> Vec<Vec<String>> {
+        self.term_tokens(query)
+            .iter()
+            .map(|term| self.synonyms.expand(&term.text))
+            .collect()
+    }
+
+    /// Finds the documents that contain at least one term of the query (or a synonym of it)
+    ///
+    /// # Returns
+    /// The matching documents, those with the most query terms first, then those where
+    /// the terms occur most often, then in document order
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::search::Index;
+    ///
+    /// let mut index = Index::new(Tokenizer::new(String::new()));
+    /// index.add_document("The automobile was red.");
+    /// index.add_document("A red bike.");
+    /// index.add_document("Cars, cars and more cars.");
+    ///
+    /// // Synonyms are registered after indexing, without re-indexing anything
+    /// index.add_synonyms(["car", "automobile"]);
+    /// let hits = index.search("red car");
+    /// assert_eq!(hits.iter().map(|hit| hit.document).collect::<Vec<_>>(), vec![0, 1]);
+    /// assert_eq!(hits[0].matched_terms, 2);
+    /// // There is no stemming: "cars" is not the term "car"
+    /// ```
+    pub fn search(&self, query: &str) -> Vec<SearchHit> {
+        // Matched terms and occurrences of every document
+        let mut hits: HashMap<usize, (usize, usize)> = HashMap::new();
+
+        // STEP 1: Collect the documents of every query term (a repeated term counts once)
+        let mut expanded = self.expand_query(query);
+        expanded.sort();
+        expanded.dedup();
+        for alternatives in &expanded {
+            for (document, occurrences) in self.occurrences(alternatives) {
+                let hit = hits.entry(document).or_insert((0, 0));
+                hit.0 += 1;
+                hit.1 += occurrences.len();
+            }
+        }
+
+        // STEP 2: Add what the caller stored with the documents
+        let mut hits: Vec<SearchHit> = hits
+            .into_iter()
+            .filter_map(|(document, (matched_terms, occurrences))| {
+                let stored = self.stored(document)?;
+                Some(SearchHit {
+                    document,
+                    id: stored.id.clone(),
+                    metadata: stored.metadata.clone(),
+                    matched_terms,
+                    occurrences,
+                })
+            })
+            .collect();
+
+        // STEP 3: Best documents first
+        hits.sort_by(|a, b| {
+            b.matched_terms
+                .cmp(&a.matched_terms)
+                .then(b.occurrences.cmp(&a.occurrences))
+                .then(a.document.cmp(&b.document))
+        });
+        hits
+    }
+
+    /// Finds the documents where the terms of a phrase occur next to each other, in order
+    ///
+    /// Positions only count terms, so punctuation between two words doesn't break a phrase.
+    /// Every term of the phrase also matches its synonyms.
+    ///
+    /// # Returns
+    /// One posting per matching document (in document order), with the position of the
+    /// first term of every occurrence of the phrase (and its byte range, if the index
+    /// stores offsets)
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::search::{Index, Posting};
+    ///
+    /// let mut index = Index::new(Tokenizer::new(String::new()));
+    /// index.add_document("New York is not York.");
+    /// index.add_document("York, new and old.");
+    /// index.add_document("I love new york. Really, NEW YORK!");
+    ///
+    /// assert_eq!(
+    ///     index.phrase("new york"),
+    ///     vec![
+    ///         Posting { document: 0, positions: vec![0], offsets: vec![] },
+    ///         Posting { document: 2, positions: vec![2, 5], offsets: vec![] },
+    ///     ]
+    /// );
+    /// ```
+    pub fn phrase(&self, phrase: &str) -> Vec<Posting> {
+        let expanded = self.expand_query(phrase);
+        let Some((first, rest)) = expanded.split_first() else {
+            return Vec::new();
+        };
+
+        // STEP 1: Occurrences of every term of the phrase, per document
+        let following: Vec<BTreeMap<usize, Vec<Occurrence>>> = rest
+            .iter()
+            .map(|alternatives| self.occurrences(alternatives))
+            .collect();
+
+        // STEP 2: Keep the occurrences of the first term that the other terms follow
+        let mut result = Vec::new();
+        for (document, starts) in self.occurrences(first) {
+            let mut posting = Posting::new(document);
+            for (start, start_offsets) in starts {
+                let mut end_offsets = start_offsets;
+                let continues = following.iter().enumerate().all(|(index, occurrences)| {
+                    let next = occurrences.get(&document).and_then(|occurrences| {
+                        let found = occurrences
+                            .binary_search_by_key(&(start + index + 1), |&(position, _)| position)
+                            .ok()?;
+                        Some(occurrences[found])
+                    });
+                    next.map(|(_, offsets)| end_offsets = offsets).is_some()
+                });
+                if continues {
+                    posting.push_match(start, start_offsets, end_offsets);
+                }
+            }
+            if !posting.positions.is_empty() {
+                result.push(posting);
+            }
+        }
+        result
+    }
+
+    /// Finds the documents where all terms of a query occur within `distance` terms
+    ///
+    /// The terms can come in any order: a match is a stretch of the document containing
+    /// every query term, from its first to its last term at most `distance` positions
+    /// apart ("a b" with distance 1 only matches neighbours, distance 3 allows two terms
+    /// in between). Every term also matches its synonyms.
+    ///
+    /// # Returns
+    /// One posting per matching document (in document order), with the start position of
+    /// every shortest stretch that contains all the terms (and its byte range, if the
+    /// index stores offsets)
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::search::Index;
+    ///
+    /// let mut index = Index::new(Tokenizer::new(String::new()));
+    /// index.add_document("The quick brown fox jumps over the lazy dog.");
+    /// index.add_document("A fox. Then a long story, and finally a dog.");
+    ///
+    /// let near: Vec<usize> = index.near("dog fox", 5).iter().map(|posting| posting.document).collect();
+    /// assert_eq!(near, vec![0]);
+    /// assert_eq!(index.near("dog fox", 5)[0].positions, vec![3]);
+    /// assert_eq!(index.near("dog fox", 4), vec![]);
+    /// ```
+    pub fn near(&self, query: &str, distance: usize) -> Vec<Posting> {
+        let mut expanded = self.expand_query(query);
+        expanded.sort();
+        expanded.dedup();
+        if expanded.is_empty() {
+            return Vec::new();
+        }
+
+        // STEP 1: Occurrences of every term, per document
+        let term_occurrences: Vec<BTreeMap<usize, Vec<Occurrence>>> = expanded
+            .iter()
+            .map(|alternatives| self.occurrences(alternatives))
+            .collect();
+
+        let mut result = Vec::new();
+        for &document in term_occurrences[0].keys() {
+            if !term_occurrences
+                .iter()
+                .all(|occurrences| occurrences.contains_key(&document))
+            {
+                continue;
+            }
+
+            // STEP 2: All occurrences of all terms in this document, in document order
+            let mut occurrences: Vec<(Occurrence, usize)> = Vec::new();
+            for (term, term_occurrences) in term_occurrences.iter().enumerate() {
+                occurrences.extend(
+                    term_occurrences[&document]
+                        .iter()
+                        .map(|&occurrence| (occurrence, term)),
+                );
+            }
+            occurrences.sort_unstable();
+
+            // STEP 3: Slide a window over them, shrinking it whenever it holds every term
+            let mut counts = vec![0; expanded.len()];
+            let mut covered = 0;
+            let mut left = 0;
+            let mut posting = Posting::new(document);
+            for &((position, offsets), term) in &occurrences {
+                counts[term] += 1;
+                if counts[term] == 1 {
+                    covered += 1;
+                }
+                while covered == expanded.len() {
+                    let ((left_position, left_offsets), left_term) = occurrences[left];
+                    if position - left_position <= distance
+                        && posting.positions.last() != Some(&left_position)
+                    {
+                        posting.push_match(left_position, left_offsets, offsets);
+                    }
+                    counts[left_term] -= 1;
+                    if counts[left_term] == 0 {
+                        covered -= 1;
+                    }
+                    left += 1;
+                }
+            }
+
+            if !posting.positions.is_empty() {
+                result.push(posting);
+            }
+        }
+        result
+    }
+
+    /// Returns the occurrences of a term or any of its alternatives, per document (sorted)
+    fn occurrences(&self, alternatives: &[String]) -> BTreeMap<usize, Vec<Occurrence>> {
+        let mut documents: BTreeMap<usize, Vec<Occurrence>> = BTreeMap::new();
+        for term in alternatives {
+            for posting in self.postings(term) {
+                let occurrences = documents.entry(posting.document).or_default();
+                for (index, &position) in posting.positions.iter().enumerate() {
+                    occurrences.push((position, posting.offsets.get(index).copied()));
+                }
+            }
+        }
+        if alternatives.len() > 1 {
+            for occurrences in documents.values_mut() {
+                occurrences.sort_unstable();
+            }
+        }
+        documents
+    }
+
+    /// Returns the terms of a text (its words, numbers and identifiers) as tokens with
+    /// lowercased text
+    fn term_tokens(&self, text: &str) -> Vec<Token> {
+        self.tokenizer
+            .tokenize_text(text)
+            .into_iter()
+            .filter(|token| {
+                matches!(
+                    token.kind,
+                    TokenKind::Word | TokenKind::Number | TokenKind::Identifier
+                )
+            })
+            .map(|token| Token {
+                text: normalize_term(&token.text),
+                ..token
+            })
+            .collect()
+    }
+}
+
+/// Turns a token or a user-given term into the form stored in the index
+fn normalize_term(term: &str) -> String {
+    term.trim().to_lowercase()
+}
@@ -0,0 +1,283 @@
+// Sentence segmentation: split a text into sentences before tokenizing it
+// A sentence ends with ".", "!", "?" or "…" followed by whitespace, or with an empty line.
+// A period does not end a sentence after an abbreviation ("Dr. Smith"), after an initial
+// ("J. K. Rowling"), inside a number ("3.14") or when the next word starts in lowercase
+// ("approx. five").
+
+use std::collections::HashSet;
+
+use crate::language::Language;
+use crate::token::Token;
+use crate::Tokenizer;
+
+/// Abbreviations known by default (lowercase, without the final period)
+const DEFAULT_ABBREVIATIONS: &[&str] = &[
+    "mr", "mrs", "ms", "dr", "prof", "sr", "jr", "st", "mt", "vs", "etc", "e.g", "i.e", "cf",
+    "inc", "ltd", "co", "corp", "no", "vol", "fig", "p", "pp", "approx", "dept", "est", "jan",
+    "feb", "mar", "apr", "jun", "jul", "aug", "sep", "sept", "oct", "nov", "dec",
+];
+
+/// Splits texts into sentences
+#[derive(Debug, Clone)]
+pub struct SentenceSplitter {
+    /// Words that are followed by a period without ending the sentence (lowercase, no final period)
+    abbreviations: HashSet<String>,
+}
+
+impl Default for SentenceSplitter {
+    fn default() -> Self {
+        SentenceSplitter {
+            abbreviations: DEFAULT_ABBREVIATIONS
+                .iter()
+                .map(|abbreviation| abbreviation.to_string())
+                .collect(),
+        }
+    }
+}
+
+impl SentenceSplitter {
+    /// Creates a splitter knowing the common English abbreviations ("Dr.", "etc.", "e.g.", ...)
+    pub fn new() -> Self {
+        SentenceSplitter::default()
+    }
+
+    /// Creates a splitter knowing the English abbreviations and those of a language
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::language::detect_language;
+    /// use tokenizer_rust::sentences::SentenceSplitter;
+    ///
+    /// let text = "Wir treffen uns heute mit Hrn. Müller im Büro. Er kommt um zehn Uhr.";
+    /// let splitter = SentenceSplitter::for_language(detect_language(text).unwrap());
+    /// assert_eq!(splitter.sentences(text).len(), 2);
+    /// assert_eq!(SentenceSplitter::new().sentences(text).len(), 3);
+    /// ```
+    pub fn for_language(language: Language) -> Self {
+        language
+            .abbreviations()
+            .iter()
+            .fold(SentenceSplitter::new(), |splitter, abbreviation| {
+                splitter.with_abbreviation(abbreviation)
+            })
+    }
+
+    /// Adds an abbreviation, like "approx." or "Nr." (case and final period don't matter)
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::sentences::SentenceSplitter;
+    ///
+    /// let splitter = SentenceSplitter::new().with_abbreviation("Nr.");
+    /// assert_eq!(splitter.sentences("See Nr. 5 below. Thanks!"), vec!["See Nr. 5 below.", "Thanks!"]);
+    /// ```
+    pub fn with_abbreviation(mut self, abbreviation: &str) -> Self {
+        let abbreviation = abbreviation.trim().trim_end_matches('.').to_lowercase();
+        if !abbreviation.is_empty() {
+            self.abbreviations.insert(abbreviation);
+        }
+        self
+    }
+
+    /// Returns true if the word (without its final period) is a known abbreviation
+    pub fn is_abbreviation(&self, word: &str) -> bool {
+        self.abbreviations
+            .contains(&word.trim_end_matches('.').to_lowercase())
+    }
+
+    /// Finds the sentences of a text
+    ///
+    /// # Arguments
+    /// * `text` - The text to split
+    ///
+    /// # Returns
+    /// The (start, end) byte range of every sentence, without the whitespace around it
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::sentences::SentenceSplitter;
+    ///
+    /// let text = "Dr. Smith paid $3.50 for J. K. Rowling's book. Really? Yes!";
+    /// let spans = SentenceSplitter::new().split(text);
+    /// assert_eq!(spans, vec![(0, 46), (47, 54), (55, 59)]);
+    ///
+    /// // Multi-byte whitespace (ideographic and no-break spaces) before the period
+    /// let text = "Hello\u{3000}world. Next\u{a0}one. Done.";
+    /// assert_eq!(
+    ///     SentenceSplitter::new().sentences(text),
+    ///     vec!["Hello\u{3000}world.", "Next\u{a0}one.", "Done."]
+    /// );
+    /// ```
+    pub fn split(&self, text: &str) -> Vec<(usize, usize)> {
+        let characters: Vec<(usize, char)> = text.char_indices().collect();
+        let mut spans = Vec::new();
+        let mut sentence_start: Option<usize> = None;
+
+        let mut index = 0;
+        while index < characters.len() {
+            let (position, character) = characters[index];
+
+            // STEP 1: Whitespace before a sentence is skipped, an empty line ends one
+            if character.is_whitespace() {
+                if character == '\n' && is_empty_line_after(&characters, index) {
+                    if let Some(start) = sentence_start.take() {
+                        spans.push((start, trim_end(text, start, position)));
+                    }
+                }
+                index += 1;
+                continue;
+            }
+            let start = *sentence_start.get_or_insert(position);
+
+            if !is_terminator(character) {
+                index += 1;
+                continue;
+            }
+
+            // STEP 2: Take the whole run of terminators ("?!", "...") and closing quotes or brackets
+            let mut end = index + 1;
+            while end < characters.len() && is_terminator(characters[end].1) {
+                end += 1;
+            }
+            let run_end = end;
+            while end < characters.len() && is_closer(characters[end].1) {
+                end += 1;
+            }
+
+            // STEP 3: Only whitespace (or the end of the text) can follow a sentence end,
+            // which also keeps numbers like "3.14" and names like "example.com" together
+            let followed_by_space = characters
+                .get(end)
+                .is_none_or(|&(_, next)| next.is_whitespace());
+            if followed_by_space
+                && self.ends_sentence(text, &characters, start, index, run_end, end)
+            {
+                let end_position = characters
+                    .get(end)
+                    .map_or(text.len(), |&(offset, _)| offset);
+                spans.push((start, end_position));
+                sentence_start = None;
+            }
+            index = end;
+        }
+
+        if let Some(start) = sentence_start {
+            spans.push((start, trim_end(text, start, text.len())));
+        }
+        spans
+    }
+
+    /// Splits a text into sentences
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::sentences::SentenceSplitter;
+    ///
+    /// let sentences = SentenceSplitter::new().sentences("It costs approx. five euros. \"Fine!\" he said.");
+    /// assert_eq!(sentences, vec!["It costs approx. five euros.", "\"Fine!\" he said."]);
+    /// ```
+    pub fn sentences<'t>(&self, text: &'t str) -> Vec<&'t str> {
+        self.split(text)
+            .into_iter()
+            .map(|(start, end)| &text[start..end])
+            .collect()
+    }
+
+    /// Splits a text into sentences and tokenizes each of them
+    ///
+    /// Token offsets are relative to the whole text, not to the sentence.
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::sentences::SentenceSplitter;
+    ///
+    /// let tokenizer = Tokenizer::new(String::new());
+    /// let sentences = SentenceSplitter::new().tokenize(&tokenizer, "Hi there. Bye!");
+    /// assert_eq!(sentences.len(), 2);
+    /// assert_eq!(sentences[1][0].text, "Bye");
+    /// assert_eq!(sentences[1][0].start, 10);
+    /// ```
+    pub fn tokenize(&self, tokenizer: &Tokenizer, text: &str) -> Vec<Vec<Token>> {
+        self.split(text)
+            .into_iter()
+            .map(|(start, end)| {
+                let mut tokens = tokenizer.tokenize_text(&text[start..end]);
+                for token in &mut tokens {
+                    token.start += start;
+                    token.end += start;
+                }
+                tokens
+            })
+            .collect()
+    }
+
+    /// Decides if the terminators characters[terminator..run_end] (followed by closing
+    /// quotes or brackets up to `after`) end the sentence
+    fn ends_sentence(
+        &self,
+        text: &str,
+        characters: &[(usize, char)],
+        sentence_start: usize,
+        terminator: usize,
+        run_end: usize,
+        after: usize,
+    ) -> bool {
+        // A lowercase word after the terminators continues the sentence
+        // ("approx. five", "\"Fine!\" he said")
+        let next = characters[after..]
+            .iter()
+            .map(|&(_, character)| character)
+            .find(|character| !character.is_whitespace());
+        if next.is_some_and(char::is_lowercase) {
+            return false;
+        }
+
+        // Otherwise "!", "?" and "…" always end a sentence, and so do several periods
+        if characters[terminator].1 != '.' || run_end - terminator > 1 {
+            return true;
+        }
+
+        // The word before the period, without opening quotes or brackets
+        let period = characters[terminator].0;
+        let word_start = text[sentence_start..period]
+            .char_indices()
+            .rev()
+            .find(|&(_, character)| character.is_whitespace())
+            .map_or(sentence_start, |(space, character)| {
+                sentence_start + space + character.len_utf8()
+            });
+        let word = text[word_start..period].trim_start_matches(|c: char| !c.is_alphanumeric());
+
+        // An abbreviation ("Dr.") or an initial ("J.") doesn't end the sentence
+        let mut letters = word.chars();
+        let is_initial = matches!(
+            (letters.next(), letters.next()),
+            (Some(letter), None) if letter.is_uppercase()
+        );
+        !(is_initial || self.is_abbreviation(word))
+    }
+}
+
+/// Returns true for characters that end a sentence
+fn is_terminator(character: char) -> bool {
+    matches!(character, '.' | '!' | '?' | '…')
+}
+
+/// Returns true for closing quotes and brackets that belong to the sentence before them
+fn is_closer(character: char) -> bool {
+    matches!(character, '"' | '\'' | ')' | ']' | '}' | '”' | '’' | '»')
+}
+
+/// Returns true if the newline at `index` is followed by an empty line
+fn is_empty_line_after(characters: &[(usize, char)], index: usize) -> bool {
+    characters[index + 1..]
+        .iter()
+        .take_while(|(_, character)| character.is_whitespace())
+        .any(|&(_, character)| character == '\n')
+}
+
+/// Returns the end of text[start..end] without its trailing whitespace
+fn trim_end(text: &str, start: usize, end: usize) -> usize {
+    start + text[start..end].trim_end().len()
+}
@@ -0,0 +1,359 @@
+// Corpus shards: a tokenized corpus written as many JSONL files of bounded size
+// A single output file for a huge corpus is hard to copy, to resume and to read in parallel.
+// The ShardWriter starts a new file (corpus-0001.jsonl, corpus-0002.jsonl, ...) whenever the
+// current one holds enough documents or bytes, and writes a manifest listing every shard
+// with its number of documents, tokens and bytes when it is finished. With the `gzip`
+// feature, the shards are compressed (corpus-0001.jsonl.gz) and their size limit applies to
+// the compressed bytes, the size the files have on disk.
+
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::output::{json_string, OutputRecord, OutputWriter};
+
+/// One finished shard of a ShardWriter
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShardInfo {
+    /// The file name of the shard (in the directory of the writer)
+    pub file: String,
+    /// Number of documents in the shard
+    pub documents: usize,
+    /// Number of tokens of those documents
+    pub tokens: usize,
+    /// Size of the file in bytes
+    pub bytes: u64,
+}
+
+/// Writes records as JSONL shards, starting a new shard when the current one is full
+///
+/// The limits are checked before every document, so a shard holds at most `max_documents`
+/// documents, and goes over `max_bytes` by less than one document. With gzip, the compressor
+/// holds back what it hasn't compressed yet: it is flushed after every quarter of the limit
+/// (at most 64 KiB) of uncompressed JSON, so the size on disk never lags behind by more than
+/// that. Without any limit, everything goes to one shard.
+///
+/// finish() closes the last shard and writes the manifest, "<prefix>-manifest.json".
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::Tokenizer;
+/// use tokenizer_rust::output::OutputWriter;
+/// use tokenizer_rust::shard::ShardWriter;
+///
+/// let directory = std::env::temp_dir().join("tokenizer_shard_doctest");
+/// let mut writer = ShardWriter::new(&directory, "corpus").with_max_documents(2);
+/// let tokenizer = Tokenizer::new(String::new());
+/// tokenizer
+///     .write_batch(&["One doc.", "Two docs.", "Three docs here."], &mut writer)
+///     .unwrap();
+/// writer.finish().unwrap();
+///
+/// let shards = writer.shards();
+/// assert_eq!(shards.len(), 2);
+/// assert_eq!((shards[0].file.as_str(), shards[0].documents, shards[0].tokens), ("corpus-0001.jsonl", 2, 6));
+/// assert_eq!((shards[1].file.as_str(), shards[1].documents, shards[1].tokens), ("corpus-0002.jsonl", 1, 4));
+/// assert_eq!(std::fs::read_to_string(directory.join("corpus-0002.jsonl")).unwrap().lines().count(), 1);
+///
+/// let manifest = std::fs::read_to_string(directory.join("corpus-manifest.json")).unwrap();
+/// assert!(manifest.contains("\"file\":\"corpus-0001.jsonl\",\"documents\":2,\"tokens\":6"));
+/// std::fs::remove_dir_all(&directory).unwrap();
+/// ```
+pub struct ShardWriter {
+    /// The directory the shards are written to
+    directory: PathBuf,
+    /// The start of every file name
+    prefix: String,
+    /// Start a new shard after this many documents
+    max_documents: Option<usize>,
+    /// Start a new shard once the file has this many bytes
+    max_bytes: Option<u64>,
+    /// Compress the shards with gzip
+    gzip: bool,
+    /// The shard being written
+    current: Option<OpenShard>,
+    /// The shards already closed
+    shards: Vec<ShardInfo>,
+}
+
+impl ShardWriter {
+    /// Creates a writer of shards named "<prefix>-0001.jsonl", ... in a directory
+    ///
+    /// The directory is created with the first shard.
+    pub fn new<P: AsRef<Path>>(directory: P, prefix: &str) -> Self {
+        ShardWriter {
+            directory: directory.as_ref().to_path_buf(),
+            prefix: prefix.to_string(),
+            max_documents: None,
+            max_bytes: None,
+            gzip: false,
+            current: None,
+            shards: Vec::new(),
+        }
+    }
+
+    /// Starts a new shard after `documents` documents
+    pub fn with_max_documents(mut self, documents: usize) -> Self {
+        self.max_documents = Some(documents.max(1));
+        self
+    }
+
+    /// Starts a new shard once the current file has `bytes` bytes (compressed bytes with gzip)
+    pub fn with_max_bytes(mut self, bytes: u64) -> Self {
+        self.max_bytes = Some(bytes);
+        self
+    }
+
+    /// Compresses the shards with gzip ("<prefix>-0001.jsonl.gz", ...)
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::output::{OutputRecord, OutputWriter};
+    /// use tokenizer_rust::shard::ShardWriter;
+    ///
+    /// let directory = std::env::temp_dir().join("tokenizer_shard_gzip_doctest");
+    /// let mut writer = ShardWriter::new(&directory, "corpus")
+    ///     .with_gzip(true)
+    ///     .with_max_bytes(64 * 1024);
+    /// let record = OutputRecord::new(vec!["the same words again".to_string(); 100]);
+    /// for _ in 0..1000 {
+    ///     writer.write_record(&record).unwrap();
+    /// }
+    /// writer.finish().unwrap();
+    ///
+    /// // 2.5 MB of repetitive JSON compresses into a single small shard
+    /// assert_eq!(writer.shards().len(), 1);
+    /// assert!(writer.shards()[0].bytes < 64 * 1024);
+    /// let shard = std::fs::read(directory.join("corpus-0001.jsonl.gz")).unwrap();
+    /// assert_eq!(&shard[..2], &[0x1f, 0x8b]);
+    /// std::fs::remove_dir_all(&directory).unwrap();
+    /// ```
+    #[cfg(feature = "gzip")]
+    pub fn with_gzip(mut self, enabled: bool) -> Self {
+        self.gzip = enabled;
+        self
+    }
+
+    /// Returns the shards closed so far (all of them after finish())
+    pub fn shards(&self) -> &[ShardInfo] {
+        &self.shards
+    }
+
+    /// Returns the path of the manifest written by finish()
+    pub fn manifest_path(&self) -> PathBuf {
+        self.directory
+            .join(format!("{}-manifest.json", self.prefix))
+    }
+
+    /// Returns true if the current shard can't take another document
+    fn is_full(&self, shard: &OpenShard) -> bool {
+        self.max_documents
+            .is_some_and(|max| shard.info.documents >= max)
+            || self.max_bytes.is_some_and(|max| shard.bytes() >= max)
+    }
+
+    /// Opens the next shard
+    fn open_shard(&self) -> io::Result<OpenShard> {
+        fs::create_dir_all(&self.directory)?;
+        let extension = if self.gzip { "jsonl.gz" } else { "jsonl" };
+        let file = format!("{}-{:04}.{}", self.prefix, self.shards.len() + 1, extension);
+        let output = CountingWriter {
+            inner: BufWriter::new(File::create(self.directory.join(&file))?),
+            bytes: 0,
+        };
+
+        let writer = if self.gzip {
+            compressed(output)
+        } else {
+            ShardFile::Plain(output)
+        };
+        Ok(OpenShard {
+            info: ShardInfo {
+                file,
+                documents: 0,
+                tokens: 0,
+                bytes: 0,
+            },
+            writer,
+            pending: 0,
+        })
+    }
+
+    /// Closes the current shard, if any, and adds it to the list
+    fn close_shard(&mut self) -> io::Result<()> {
+        if let Some(shard) = self.current.take() {
+            self.shards.push(shard.close()?);
+        }
+        Ok(())
+    }
+
+    /// Writes the manifest: every shard and the totals, as one JSON object
+    fn write_manifest(&self) -> io::Result<()> {
+        let mut json = String::from("{\"shards\":[");
+        for (index, shard) in self.shards.iter().enumerate() {
+            if index > 0 {
+                json.push(',');
+            }
+            json.push_str(&format!(
+                "{{\"file\":{},\"documents\":{},\"tokens\":{},\"bytes\":{}}}",
+                json_string(&shard.file),
+                shard.documents,
+                shard.tokens,
+                shard.bytes
+            ));
+        }
+        let documents: usize = self.shards.iter().map(|shard| shard.documents).sum();
+        let tokens: usize = self.shards.iter().map(|shard| shard.tokens).sum();
+        let bytes: u64 = self.shards.iter().map(|shard| shard.bytes).sum();
+        json.push_str(&format!(
+            "],\"documents\":{},\"tokens\":{},\"bytes\":{}}}\n",
+            documents, tokens, bytes
+        ));
+
+        fs::create_dir_all(&self.directory)?;
+        fs::write(self.manifest_path(), json)
+    }
+}
+
+impl OutputWriter for ShardWriter {
+    fn write_record(&mut self, record: &OutputRecord) -> io::Result<()> {
+        // STEP 1: Start a new shard if there is none yet or the current one is full
+        if self
+            .current
+            .as_ref()
+            .is_some_and(|shard| self.is_full(shard))
+        {
+            self.close_shard()?;
+        }
+        let shard = match self.current.take() {
+            Some(shard) => shard,
+            None => self.open_shard()?,
+        };
+        let shard = self.current.insert(shard);
+
+        // STEP 2: Write the record
+        let line = record.to_json();
+        writeln!(shard.writer, "{}", line)?;
+        shard.info.documents += 1;
+        shard.info.tokens += record.tokens.len();
+
+        // STEP 3: Flush the compressor regularly, so the size on disk is known
+        if let (true, Some(max_bytes)) = (self.gzip, self.max_bytes) {
+            shard.pending += line.len() as u64 + 1;
+            if shard.pending >= (max_bytes / 4).clamp(1, 64 * 1024) {
+                shard.writer.flush()?;
+                shard.pending = 0;
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.close_shard()?;
+        self.write_manifest()
+    }
+}
+
+/// The shard being written
+struct OpenShard {
+    /// Its file name and counts so far
+    info: ShardInfo,
+    /// Where its records are written
+    writer: ShardFile,
+    /// Bytes given to the compressor since it was last flushed
+    pending: u64,
+}
+
+impl OpenShard {
+    /// Returns the number of bytes written to the file so far
+    fn bytes(&self) -> u64 {
+        match &self.writer {
+            ShardFile::Plain(writer) => writer.bytes,
+            #[cfg(feature = "gzip")]
+            ShardFile::Gzip(writer) => writer.get_ref().bytes,
+        }
+    }
+
+    /// Writes everything still buffered and returns the final counts of the shard
+    fn close(self) -> io::Result<ShardInfo> {
+        let mut output = self.writer.finish()?;
+        output.flush()?;
+        Ok(ShardInfo {
+            bytes: output.bytes,
+            ..self.info
+        })
+    }
+}
+
+/// The file of a shard, compressed or not
+enum ShardFile {
+    /// A plain JSONL file
+    Plain(CountingWriter<BufWriter<File>>),
+    /// A gzip-compressed JSONL file
+    #[cfg(feature = "gzip")]
+    Gzip(flate2::write::GzEncoder<CountingWriter<BufWriter<File>>>),
+}
+
+impl ShardFile {
+    /// Ends the compressed stream, if any, and returns the file
+    fn finish(self) -> io::Result<CountingWriter<BufWriter<File>>> {
+        match self {
+            ShardFile::Plain(writer) => Ok(writer),
+            #[cfg(feature = "gzip")]
+            ShardFile::Gzip(writer) => writer.finish(),
+        }
+    }
+}
+
+impl Write for ShardFile {
+    fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        match self {
+            ShardFile::Plain(writer) => writer.write(buffer),
+            #[cfg(feature = "gzip")]
+            ShardFile::Gzip(writer) => writer.write(buffer),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            ShardFile::Plain(writer) => writer.flush(),
+            #[cfg(feature = "gzip")]
+            ShardFile::Gzip(writer) => writer.flush(),
+        }
+    }
+}
+
+/// Wraps the file of a gzip shard in the compressor
+#[cfg(feature = "gzip")]
+fn compressed(output: CountingWriter<BufWriter<File>>) -> ShardFile {
+    ShardFile::Gzip(flate2::write::GzEncoder::new(
+        output,
+        flate2::Compression::default(),
+    ))
+}
+
+/// Without the `gzip` feature, shards are never compressed
+#[cfg(not(feature = "gzip"))]
+fn compressed(output: CountingWriter<BufWriter<File>>) -> ShardFile {
+    ShardFile::Plain(output)
+}
+
+/// A writer that counts the bytes going through it
+struct CountingWriter<W> {
+    /// Where the bytes go
+    inner: W,
+    /// Number of bytes written so far
+    bytes: u64,
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buffer)?;
+        self.bytes += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
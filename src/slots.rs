@@ -0,0 +1,132 @@
+// Slot filling for text templates (natural language generation post-processing)
+// A template like "Hello {name}, your order {order} has shipped." is tokenized once,
+// with every {placeholder} kept as a single special token. Filling it splices the tokenized
+// values in and rebuilds the text with detokenize(), so spacing around punctuation is right
+// whatever the values look like.
+
+use std::collections::HashMap;
+
+use crate::special::SpecialTokens;
+use crate::Tokenizer;
+
+/// A tokenized template with `{name}` placeholders
+#[derive(Debug, Clone)]
+pub struct SlotTemplate {
+    /// The tokens of the template, placeholders included (like "{name}")
+    tokens: Vec<String>,
+    /// The placeholders, as special tokens
+    placeholders: SpecialTokens,
+}
+
+impl SlotTemplate {
+    /// Parses and tokenizes a template
+    ///
+    /// A placeholder is a name made of letters, digits and underscores between braces.
+    /// Braces around anything else are normal text.
+    ///
+    /// # Arguments
+    /// * `template` - The template text, like "Hello {name}!"
+    ///
+    /// # Returns
+    /// The tokenized template
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::slots::SlotTemplate;
+    ///
+    /// let template = SlotTemplate::new("Dear {first_name} {last_name},");
+    /// assert_eq!(template.slots(), vec!["first_name", "last_name"]);
+    /// ```
+    pub fn new(template: &str) -> Self {
+        // STEP 1: Find the placeholders and register them as special tokens
+        let mut placeholders = SpecialTokens::new();
+        for placeholder in find_placeholders(template) {
+            placeholders.register(placeholder);
+        }
+
+        // STEP 2: Tokenize the template, the placeholders come out as single tokens
+        let tokens = Tokenizer::new(template.to_string())
+            .with_special_tokens(placeholders.clone())
+            .tokenize();
+
+        SlotTemplate {
+            tokens,
+            placeholders,
+        }
+    }
+
+    /// Returns the names of the slots, in the order they first appear
+    pub fn slots(&self) -> Vec<&str> {
+        self.placeholders.iter().map(slot_name).collect()
+    }
+
+    /// Fills every slot with its value and rebuilds the text
+    ///
+    /// Values are tokenized like any other text, so "Smith & Sons" or "42." are
+    /// spaced correctly next to the surrounding punctuation.
+    ///
+    /// # Arguments
+    /// * `values` - The value of every slot, by slot name
+    ///
+    /// # Returns
+    /// The filled text, or None if a slot has no value
+    ///
+    /// # Example
+    /// ```
+    /// use std::collections::HashMap;
+    /// use tokenizer_rust::slots::SlotTemplate;
+    ///
+    /// let template = SlotTemplate::new("Hello {name}, your order ({order}) has shipped.");
+    /// let values = HashMap::from([("name", "Ann"), ("order", "A-1234")]);
+    /// assert_eq!(
+    ///     template.fill(&values),
+    ///     Some("Hello Ann, your order (A-1234) has shipped.".to_string())
+    /// );
+    /// assert_eq!(template.fill(&HashMap::from([("name", "Ann")])), None);
+    /// ```
+    pub fn fill(&self, values: &HashMap<&str, &str>) -> Option<String> {
+        let mut tokens = Vec::with_capacity(self.tokens.len());
+
+        for token in &self.tokens {
+            if self.placeholders.contains(token) {
+                let value = values.get(slot_name(token))?;
+                tokens.extend(Tokenizer::new(value.to_string()).tokenize());
+            } else {
+                tokens.push(token.clone());
+            }
+        }
+
+        Some(Tokenizer::new(String::new()).detokenize(&tokens))
+    }
+}
+
+/// Returns the name inside a placeholder ("{name}" -> "name")
+fn slot_name(placeholder: &str) -> &str {
+    &placeholder[1..placeholder.len() - 1]
+}
+
+/// Finds every `{name}` placeholder of a template (with its braces)
+fn find_placeholders(template: &str) -> Vec<&str> {
+    let mut placeholders = Vec::new();
+
+    let mut rest = template;
+    let mut offset = 0;
+    while let Some(open) = rest.find('{') {
+        let after_open = &rest[open + 1..];
+        let name_length = after_open
+            .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .unwrap_or(after_open.len());
+
+        // Only "{" + name + "}" is a placeholder
+        if name_length > 0 && after_open[name_length..].starts_with('}') {
+            let start = offset + open;
+            let end = start + name_length + 2;
+            placeholders.push(&template[start..end]);
+        }
+
+        offset += open + 1;
+        rest = &template[offset..];
+    }
+
+    placeholders
+}
@@ -0,0 +1,158 @@
+// Special tokens: markers like [CLS], [SEP], <s> or <pad> that models use for structure
+// They are never split by the tokenizer and always have their own id in the vocabulary
+
+/// A registry of special tokens
+///
+/// Special tokens are matched in the raw text before anything else happens,
+/// so "<s>" in the input becomes one token instead of "<", "s", ">".
+#[derive(Debug, Clone, Default)]
+pub struct SpecialTokens {
+    /// The registered tokens, in registration order
+    tokens: Vec<String>,
+}
+
+impl SpecialTokens {
+    /// Creates an empty registry
+    pub fn new() -> Self {
+        SpecialTokens::default()
+    }
+
+    /// Creates a registry with the special tokens used by BERT-style models
+    ///
+    /// The tokens are: [PAD], [UNK], [CLS], [SEP], [MASK]
+    pub fn bert() -> Self {
+        let mut specials = SpecialTokens::new();
+        for token in ["[PAD]", "[UNK]", "[CLS]", "[SEP]", "[MASK]"] {
+            specials.register(token);
+        }
+        specials
+    }
+
+    /// Creates a registry with the special tokens used by RoBERTa-style models
+    ///
+    /// The tokens are: <s>, <pad>, </s>, <unk>, <mask>
+    pub fn roberta() -> Self {
+        let mut specials = SpecialTokens::new();
+        for token in ["<s>", "<pad>", "</s>", "<unk>", "<mask>"] {
+            specials.register(token);
+        }
+        specials
+    }
+
+    /// Registers a new special token
+    ///
+    /// Registering the same token twice has no effect. Empty strings are ignored.
+    ///
+    /// # Arguments
+    /// * `token` - The special token, exactly as it appears in raw text
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::special::SpecialTokens;
+    ///
+    /// let mut specials = SpecialTokens::new();
+    /// specials.register("<tool>");
+    /// assert!(specials.contains("<tool>"));
+    /// ```
+    pub fn register(&mut self, token: &str) {
+        if !token.is_empty() && !self.contains(token) {
+            self.tokens.push(token.to_string());
+        }
+    }
+
+    /// Returns true if the token is a registered special token
+    pub fn contains(&self, token: &str) -> bool {
+        self.tokens.iter().any(|special| special == token)
+    }
+
+    /// Iterates over the registered special tokens in registration order
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.tokens.iter().map(|token| token.as_str())
+    }
+
+    /// Returns the number of registered special tokens
+    pub fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    /// Returns true if no special token is registered
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+}
+
+/// Finds every protected token (special or added token) in a text
+///
+/// The text is scanned from left to right. When several tokens from the registries start at the
+/// same position, the longest one wins. Matches never overlap.
+///
+/// # Arguments
+/// * `text` - The raw text to search
+/// * `registries` - The registries whose tokens must be found
+///
+/// # Returns
+/// The (start, end) byte ranges of the matches, in order
+pub(crate) fn find_all(text: &str, registries: &[&SpecialTokens]) -> Vec<(usize, usize)> {
+    let mut matches = Vec::new();
+
+    if registries.iter().all(|registry| registry.is_empty()) {
+        return matches;
+    }
+
+    let mut position = 0;
+    while position < text.len() {
+        let rest = &text[position..];
+
+        // Look for the longest protected token starting here
+        let longest = registries
+            .iter()
+            .flat_map(|registry| registry.iter())
+            .filter(|token| rest.starts_with(token))
+            .map(|token| token.len())
+            .max();
+
+        match longest {
+            Some(length) => {
+                matches.push((position, position + length));
+                position += length;
+            }
+            None => {
+                // Move on to the next character (not the next byte, to stay on a char boundary)
+                position += rest.chars().next().map_or(1, |c| c.len_utf8());
+            }
+        }
+    }
+
+    matches
+}
+
+/// Finds where a protected token could start and still run past a cut point
+///
+/// Used before cutting a buffer that is tokenized in pieces: the cut must not go through a
+/// special or added token, including one whose end has not been read yet.
+///
+/// # Arguments
+/// * `buffer` - The bytes read so far
+/// * `cut` - Where the buffer would be cut
+/// * `registries` - The registries whose tokens must stay whole
+///
+/// # Returns
+/// The earliest start of a protected token (or of the beginning of one, at the end of the
+/// buffer) that goes over `cut`, or None if the cut is clear
+pub(crate) fn start_across(
+    buffer: &[u8],
+    cut: usize,
+    registries: &[&SpecialTokens],
+) -> Option<usize> {
+    registries
+        .iter()
+        .flat_map(|registry| registry.iter())
+        .filter_map(|token| {
+            let token = token.as_bytes();
+            (cut.saturating_sub(token.len() - 1)..cut).find(|&start| {
+                let end = buffer.len().min(start + token.len());
+                start + token.len() > cut && token.starts_with(&buffer[start..end])
+            })
+        })
+        .min()
+}
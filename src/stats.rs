@@ -0,0 +1,1108 @@
+// Token frequencies of a corpus
+// A FrequencyTable counts how often every token appears in one or many tokenized documents.
+// Counting can ignore case ("The" and "the" are one entry), and tables built by separate
+// workers over parts of a corpus are merged into the table of the whole corpus, which is
+// how add_texts() counts in parallel with the `parallel` feature.
+// TfIdf builds on the same counting: its document frequencies are a FrequencyTable where
+// every document counts each of its tokens once.
+// Every listing is deterministic, so the outputs of two runs can be diffed: by default the
+// most frequent tokens come first and ties are in lexicographic order, whatever the hashing
+// or the merge order. The table also remembers when every token was first seen, for
+// consumers that want the tokens in insertion order.
+// For corpus linguistics, the table exports its rank/frequency distribution (as values, CSV or
+// JSON) and fits Zipf's law to it: the count of the token of rank r is about C / r^s, with s
+// close to 1 for natural language.
+// TokenStats summarizes one list of tokens: how many of each kind, how many distinct ones
+// and how long they are.
+// A LengthHistogram counts lengths (documents in tokens, tokens in characters) and gives
+// their percentiles: the P99 length of a corpus is a sensible maximum input length.
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::io::{self, Write};
+
+use crate::output::{csv_field, json_string};
+use crate::sample::WeightedReservoir;
+use crate::token::{Token, TokenKind};
+use crate::vocab::Vocabulary;
+use crate::Tokenizer;
+
+/// The order in which FrequencyTable::sorted() lists the tokens
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TokenOrder {
+    /// Most frequent first, ties in lexicographic order
+    #[default]
+    Frequency,
+    /// Lexicographic order of the tokens (byte order of their UTF-8 text)
+    Lexicographic,
+    /// The order in which the tokens were first seen (a merged table lists the tokens of
+    /// the other table after its own)
+    Insertion,
+}
+
+/// How often one token was seen, and when it was seen first
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Entry {
+    /// Number of times the token was seen
+    count: usize,
+    /// Rank of the first time the token was seen, for the insertion order
+    first_seen: usize,
+}
+
+/// One row of the rank/frequency distribution of a FrequencyTable
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RankFrequency<'a> {
+    /// The rank of the token (1 for the most frequent one)
+    pub rank: usize,
+    /// The token
+    pub token: &'a str,
+    /// How often the token was seen
+    pub count: usize,
+    /// The share of all tokens that are this token
+    pub frequency: f64,
+}
+
+/// Zipf's law fitted to a rank/frequency distribution: count ≈ constant / rank^exponent
+///
+/// The fit is a least-squares line through the (ln rank, ln count) points.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ZipfFit {
+    /// The Zipf exponent s (about 1 for natural language)
+    pub exponent: f64,
+    /// The constant C, the count the fit predicts for rank 1
+    pub constant: f64,
+    /// The coefficient of determination of the fit in log-log space (1 is a perfect fit)
+    pub r_squared: f64,
+}
+
+impl ZipfFit {
+    /// Returns the count the fit predicts for a rank
+    pub fn predicted(&self, rank: usize) -> f64 {
+        self.constant / (rank as f64).powf(self.exponent)
+    }
+}
+
+/// How often every token appears in a set of documents
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::Tokenizer;
+/// use tokenizer_rust::stats::FrequencyTable;
+///
+/// let tokenizer = Tokenizer::new(String::new());
+///
+/// // Two workers count their own part of the corpus...
+/// let mut first = FrequencyTable::new().with_case_sensitive(false);
+/// first.add_tokens(&tokenizer.tokenize_text("The cat saw the dog."));
+/// let mut second = FrequencyTable::new().with_case_sensitive(false);
+/// second.add_tokens(&tokenizer.tokenize_text("THE END"));
+///
+/// // ...and their tables are merged
+/// first.merge(second);
+/// assert_eq!(first.count("the"), 3);
+/// assert_eq!(first.count("The"), 3);
+/// assert_eq!((first.total(), first.distinct(), first.documents()), (8, 6, 2));
+/// assert_eq!(first.top(2), vec![("the", 3), (".", 1)]);
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FrequencyTable {
+    /// Number of times every (normalized) token was seen
+    counts: HashMap<String, Entry>,
+    /// The first_seen rank of the next new token
+    next_rank: usize,
+    /// Number of tokens seen
+    total: usize,
+    /// Number of documents added
+    documents: usize,
+    /// Count "The" and "the" separately
+    case_sensitive: bool,
+}
+
+impl Default for FrequencyTable {
+    fn default() -> Self {
+        FrequencyTable {
+            counts: HashMap::new(),
+            next_rank: 0,
+            total: 0,
+            documents: 0,
+            case_sensitive: true,
+        }
+    }
+}
+
+// Two tables are equal when they have the same counts, whatever order the tokens came in
+impl PartialEq for FrequencyTable {
+    fn eq(&self, other: &Self) -> bool {
+        self.total == other.total
+            && self.documents == other.documents
+            && self.case_sensitive == other.case_sensitive
+            && self.counts.len() == other.counts.len()
+            && self.iter_unordered().all(|(token, count)| {
+                other
+                    .counts
+                    .get(token)
+                    .is_some_and(|entry| entry.count == count)
+            })
+    }
+}
+
+impl Eq for FrequencyTable {}
+
+impl FrequencyTable {
+    /// Creates an empty, case-sensitive table
+    pub fn new() -> Self {
+        FrequencyTable::default()
+    }
+
+    /// Sets whether tokens that differ only by case are counted separately (the default)
+    ///
+    /// Without case sensitivity, tokens are counted lowercased and top() returns them
+    /// lowercased.
+    pub fn with_case_sensitive(mut self, enabled: bool) -> Self {
+        self.case_sensitive = enabled;
+        self
+    }
+
+    /// Returns true if tokens that differ only by case are counted separately
+    pub fn case_sensitive(&self) -> bool {
+        self.case_sensitive
+    }
+
+    /// Adds the tokens of one document (line and paragraph breaks are not counted)
+    pub fn add_tokens(&mut self, tokens: &[Token]) {
+        self.documents += 1;
+        for token in tokens {
+            if !matches!(token.kind, TokenKind::LineBreak | TokenKind::ParagraphBreak) {
+                self.add(&token.text, 1);
+            }
+        }
+    }
+
+    /// Adds one document given as token strings (from tokenize() or a token-per-line file)
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::stats::FrequencyTable;
+    ///
+    /// let mut table = FrequencyTable::new();
+    /// table.add_strings(&["to", "be", "or", "not", "to", "be"]);
+    /// assert_eq!(table.top(3), vec![("be", 2), ("to", 2), ("not", 1)]);
+    /// ```
+    pub fn add_strings<S: AsRef<str>>(&mut self, tokens: &[S]) {
+        self.documents += 1;
+        for token in tokens {
+            self.add(token.as_ref(), 1);
+        }
+    }
+
+    /// Tokenizes many documents and adds them, spread over all CPU cores with the
+    /// `parallel` feature (every worker fills its own table, then the tables are merged)
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::stats::FrequencyTable;
+    ///
+    /// let mut table = FrequencyTable::new();
+    /// table.add_texts(&Tokenizer::new(String::new()), &["a b", "b c", "c"]);
+    /// assert_eq!((table.documents(), table.total(), table.count("c")), (3, 5, 2));
+    /// ```
+    #[cfg(feature = "parallel")]
+    pub fn add_texts(&mut self, tokenizer: &Tokenizer, texts: &[&str]) {
+        use rayon::prelude::*;
+
+        let empty = FrequencyTable::new().with_case_sensitive(self.case_sensitive);
+        let counted = texts
+            .par_iter()
+            .fold(
+                || empty.clone(),
+                |mut table, text| {
+                    table.add_tokens(&tokenizer.tokenize_text(text));
+                    table
+                },
+            )
+            .reduce(
+                || empty.clone(),
+                |mut table, other| {
+                    table.merge(other);
+                    table
+                },
+            );
+        self.merge(counted);
+    }
+
+    /// Tokenizes many documents and adds them, spread over all CPU cores with the
+    /// `parallel` feature (every worker fills its own table, then the tables are merged)
+    #[cfg(not(feature = "parallel"))]
+    pub fn add_texts(&mut self, tokenizer: &Tokenizer, texts: &[&str]) {
+        for text in texts {
+            self.add_tokens(&tokenizer.tokenize_text(text));
+        }
+    }
+
+    /// Adds the counts of another table (built by another worker, for example)
+    ///
+    /// The tokens of `other` are normalized like the tokens of this table, so merging a
+    /// case-sensitive table into a case-insensitive one folds its cases together. In
+    /// insertion order, the new tokens of `other` come after the tokens of this table.
+    pub fn merge(&mut self, other: FrequencyTable) {
+        self.documents += other.documents;
+        if self.counts.is_empty() && self.case_sensitive == other.case_sensitive {
+            // Nothing to add to: take the other table as it is
+            self.counts = other.counts;
+            self.next_rank = other.next_rank;
+            self.total += other.total;
+            return;
+        }
+
+        // The ranks of the other table are shifted after ours, which keeps their order
+        let offset = self.next_rank;
+        self.next_rank += other.next_rank;
+        for (token, entry) in other.counts {
+            self.total += entry.count;
+            let first_seen = offset + entry.first_seen;
+            let key = self.normalize(&token).into_owned();
+            self.counts
+                .entry(key)
+                .and_modify(|seen| {
+                    seen.count += entry.count;
+                    // Several cases of the other table may fold into one token
+                    seen.first_seen = seen.first_seen.min(first_seen);
+                })
+                .or_insert(Entry {
+                    count: entry.count,
+                    first_seen,
+                });
+        }
+    }
+
+    /// Returns how often a token was seen (ignoring its case if the table does)
+    pub fn count(&self, token: &str) -> usize {
+        self.counts
+            .get(self.normalize(token).as_ref())
+            .map_or(0, |entry| entry.count)
+    }
+
+    /// Returns the share of all tokens that are this token (0 for an empty table)
+    pub fn frequency(&self, token: &str) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.count(token) as f64 / self.total as f64
+        }
+    }
+
+    /// Returns the `k` most frequent tokens with their counts, most frequent first
+    /// (ties in lexicographic order, so the result doesn't depend on the merge order)
+    pub fn top(&self, k: usize) -> Vec<(&str, usize)> {
+        let mut entries: Vec<(&str, usize)> = self.iter_unordered().collect();
+        // Only the first k entries need to be sorted
+        if k < entries.len() {
+            entries.select_nth_unstable_by(k, by_frequency);
+            entries.truncate(k);
+        }
+        entries.sort_unstable_by(by_frequency);
+        entries
+    }
+
+    /// Returns every token with its count, sorted in the given order
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::stats::{FrequencyTable, TokenOrder};
+    ///
+    /// let mut table = FrequencyTable::new();
+    /// table.add_strings(&["to", "be", "or", "not", "to", "be"]);
+    /// assert_eq!(
+    ///     table.sorted(TokenOrder::Frequency),
+    ///     vec![("be", 2), ("to", 2), ("not", 1), ("or", 1)]
+    /// );
+    /// assert_eq!(
+    ///     table.sorted(TokenOrder::Lexicographic),
+    ///     vec![("be", 2), ("not", 1), ("or", 1), ("to", 2)]
+    /// );
+    /// assert_eq!(
+    ///     table.sorted(TokenOrder::Insertion),
+    ///     vec![("to", 2), ("be", 2), ("or", 1), ("not", 1)]
+    /// );
+    /// ```
+    pub fn sorted(&self, order: TokenOrder) -> Vec<(&str, usize)> {
+        let mut entries: Vec<(&str, &Entry)> = self
+            .counts
+            .iter()
+            .map(|(token, entry)| (token.as_str(), entry))
+            .collect();
+        match order {
+            TokenOrder::Frequency => entries
+                .sort_unstable_by(|a, b| b.1.count.cmp(&a.1.count).then_with(|| a.0.cmp(b.0))),
+            TokenOrder::Lexicographic => entries.sort_unstable_by(|a, b| a.0.cmp(b.0)),
+            TokenOrder::Insertion => entries.sort_unstable_by_key(|(_, entry)| entry.first_seen),
+        }
+        entries
+            .into_iter()
+            .map(|(token, entry)| (token, entry.count))
+            .collect()
+    }
+
+    /// Returns every token with its count, most frequent first (ties in lexicographic
+    /// order, see sorted() for the other orders)
+    pub fn iter(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.sorted(TokenOrder::Frequency).into_iter()
+    }
+
+    /// Returns the rank/frequency distribution: every token with its rank, most frequent first
+    ///
+    /// Tokens with the same count get consecutive ranks, in lexicographic order.
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::stats::FrequencyTable;
+    ///
+    /// let mut table = FrequencyTable::new();
+    /// table.add_strings(&["to", "be", "or", "not", "to", "be"]);
+    /// let ranks = table.rank_frequencies();
+    /// assert_eq!((ranks[0].rank, ranks[0].token, ranks[0].count), (1, "be", 2));
+    /// assert_eq!((ranks[3].rank, ranks[3].token), (4, "or"));
+    /// assert!((ranks[3].frequency - 1.0 / 6.0).abs() < 1e-12);
+    /// ```
+    pub fn rank_frequencies(&self) -> Vec<RankFrequency<'_>> {
+        self.iter()
+            .enumerate()
+            .map(|(index, (token, count))| RankFrequency {
+                rank: index + 1,
+                token,
+                count,
+                frequency: count as f64 / self.total as f64,
+            })
+            .collect()
+    }
+
+    /// Fits Zipf's law to the rank/frequency distribution
+    ///
+    /// # Returns
+    /// The fit, or None with fewer than two distinct tokens
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::stats::FrequencyTable;
+    ///
+    /// // Counts that follow Zipf's law exactly: 60 / rank
+    /// let mut table = FrequencyTable::new();
+    /// for (rank, token) in ["a", "b", "c", "d", "e", "f"].iter().enumerate() {
+    ///     table.add_strings(&vec![*token; 60 / (rank + 1)]);
+    /// }
+    /// let fit = table.zipf_fit().unwrap();
+    /// assert!((fit.exponent - 1.0).abs() < 1e-9);
+    /// assert!((fit.predicted(3) - 20.0).abs() < 1e-9);
+    /// assert!((fit.r_squared - 1.0).abs() < 1e-9);
+    /// ```
+    pub fn zipf_fit(&self) -> Option<ZipfFit> {
+        if self.counts.len() < 2 {
+            return None;
+        }
+
+        // STEP 1: Least squares through the (ln rank, ln count) points
+        let points: Vec<(f64, f64)> = self
+            .iter()
+            .enumerate()
+            .map(|(index, (_, count))| (((index + 1) as f64).ln(), (count as f64).ln()))
+            .collect();
+        let n = points.len() as f64;
+        let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+        let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+        let covariance: f64 = points
+            .iter()
+            .map(|(x, y)| (x - mean_x) * (y - mean_y))
+            .sum();
+        let variance_x: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+        let slope = covariance / variance_x;
+        let intercept = mean_y - slope * mean_x;
+
+        // STEP 2: How much of the variance of ln count the line explains
+        let variance_y: f64 = points.iter().map(|(_, y)| (y - mean_y).powi(2)).sum();
+        let residuals: f64 = points
+            .iter()
+            .map(|(x, y)| (y - (intercept + slope * x)).powi(2))
+            .sum();
+        // All counts equal: the flat line fits them exactly
+        let r_squared = if variance_y > 0.0 {
+            1.0 - residuals / variance_y
+        } else {
+            1.0
+        };
+
+        Some(ZipfFit {
+            exponent: -slope,
+            constant: intercept.exp(),
+            r_squared,
+        })
+    }
+
+    /// Returns a seeded random sample of distinct tokens, each token's chance proportional to
+    /// its count
+    ///
+    /// # Arguments
+    /// * `size` - How many tokens to pick (all of them if the table has fewer)
+    /// * `seed` - The seed of the random generator (same seed and table, same sample)
+    ///
+    /// # Returns
+    /// The sampled tokens with their counts, most frequent first
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::stats::FrequencyTable;
+    ///
+    /// let mut table = FrequencyTable::new();
+    /// table.add_strings(&["the"; 1000]);
+    /// table.add_strings(&["cat", "sat", "mat", "hat"]);
+    ///
+    /// let sample = table.sample(2, 42);
+    /// assert_eq!(sample.len(), 2);
+    /// assert_eq!(sample[0], ("the", 1000));
+    /// assert_eq!(table.sample(2, 42), sample);
+    /// ```
+    pub fn sample(&self, size: usize, seed: u64) -> Vec<(&str, usize)> {
+        // The tokens are offered in a fixed order, so the seed alone decides the sample
+        let mut reservoir = WeightedReservoir::new(size, seed);
+        for (token, count) in self.iter() {
+            reservoir.push((token, count), count as f64);
+        }
+        reservoir.into_sample()
+    }
+
+    /// Writes the rank/frequency distribution as CSV
+    ///
+    /// The header is "rank,token,count,frequency", followed by one row per token, most
+    /// frequent first. Tokens are quoted when needed.
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::stats::FrequencyTable;
+    ///
+    /// let mut table = FrequencyTable::new();
+    /// table.add_strings(&["a", ",", "a", "b"]);
+    /// let mut csv = Vec::new();
+    /// table.write_csv(&mut csv).unwrap();
+    /// assert_eq!(
+    ///     String::from_utf8(csv).unwrap(),
+    ///     "rank,token,count,frequency\n1,a,2,0.5\n2,\",\",1,0.25\n3,b,1,0.25\n"
+    /// );
+    /// ```
+    pub fn write_csv<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "rank,token,count,frequency")?;
+        for row in self.rank_frequencies() {
+            writeln!(
+                writer,
+                "{},{},{},{}",
+                row.rank,
+                csv_field(row.token, ','),
+                row.count,
+                row.frequency
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Writes the rank/frequency distribution and the Zipf fit as one JSON object
+    ///
+    /// The object has the fields "total", "distinct", "documents", "zipf" (an object with
+    /// "exponent", "constant" and "r_squared", or null) and "ranks", the rows of
+    /// rank_frequencies() as objects with "rank", "token", "count" and "frequency".
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::stats::FrequencyTable;
+    ///
+    /// let mut table = FrequencyTable::new();
+    /// table.add_strings(&["a", "a", "b"]);
+    /// let mut json = Vec::new();
+    /// table.write_json(&mut json).unwrap();
+    /// let json = String::from_utf8(json).unwrap();
+    /// assert!(json.starts_with("{\"total\":3,\"distinct\":2,\"documents\":1,\"zipf\":{\"exponent\":1,"));
+    /// assert!(json.ends_with("\"ranks\":[{\"rank\":1,\"token\":\"a\",\"count\":2,\"frequency\":0.6666666666666666},\
+    ///                         {\"rank\":2,\"token\":\"b\",\"count\":1,\"frequency\":0.3333333333333333}]}\n"));
+    /// ```
+    pub fn write_json<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        write!(
+            writer,
+            "{{\"total\":{},\"distinct\":{},\"documents\":{},\"zipf\":",
+            self.total,
+            self.counts.len(),
+            self.documents
+        )?;
+        match self.zipf_fit() {
+            Some(fit) => write!(
+                writer,
+                "{{\"exponent\":{},\"constant\":{},\"r_squared\":{}}}",
+                fit.exponent, fit.constant, fit.r_squared
+            )?,
+            None => write!(writer, "null")?,
+        }
+        write!(writer, ",\"ranks\":[")?;
+        for (index, row) in self.rank_frequencies().iter().enumerate() {
+            if index > 0 {
+                write!(writer, ",")?;
+            }
+            write!(
+                writer,
+                "{{\"rank\":{},\"token\":{},\"count\":{},\"frequency\":{}}}",
+                row.rank,
+                json_string(row.token),
+                row.count,
+                row.frequency
+            )?;
+        }
+        writeln!(writer, "]}}")
+    }
+
+    /// Returns the number of tokens seen
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// Returns the number of different tokens
+    pub fn distinct(&self) -> usize {
+        self.counts.len()
+    }
+
+    /// Returns the number of documents added
+    pub fn documents(&self) -> usize {
+        self.documents
+    }
+
+    /// Returns true if no token was seen
+    pub fn is_empty(&self) -> bool {
+        self.total == 0
+    }
+
+    /// Returns the token as it is counted (lowercased without case sensitivity)
+    fn normalize<'t>(&self, token: &'t str) -> Cow<'t, str> {
+        if self.case_sensitive {
+            Cow::Borrowed(token)
+        } else {
+            Cow::Owned(token.to_lowercase())
+        }
+    }
+
+    /// Returns every token with its count, in the order of the hash map (for counting
+    /// and comparing, where the order doesn't matter)
+    fn iter_unordered(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.counts
+            .iter()
+            .map(|(token, entry)| (token.as_str(), entry.count))
+    }
+
+    /// Counts a token `count` more times
+    fn add(&mut self, token: &str, count: usize) {
+        self.total += count;
+        let key = self.normalize(token);
+        // Only allocate for tokens not seen before
+        match self.counts.get_mut(key.as_ref()) {
+            Some(seen) => seen.count += count,
+            None => {
+                let first_seen = self.next_rank;
+                self.next_rank += 1;
+                self.counts
+                    .insert(key.into_owned(), Entry { count, first_seen });
+            }
+        }
+    }
+}
+
+/// Sorts (token, count) pairs by decreasing count, ties in lexicographic order
+fn by_frequency(a: &(&str, usize), b: &(&str, usize)) -> std::cmp::Ordering {
+    b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0))
+}
+
+/// TF-IDF weights of the tokens of a document collection
+///
+/// Documents are added first (to count in how many documents every token appears), then
+/// any document can be scored against the collection. Every token of the collection gets
+/// an id in the order it is first seen, and vector() returns (id, weight) pairs, so the
+/// result is a sparse vector over vocabulary().
+///
+/// The weights follow the usual definition: the number of times a token appears in the
+/// document (or 1 + ln of it with sublinear tf), times its idf, ln(n / df) + 1 for a token
+/// found in df of the n documents (ln((1 + n) / (1 + df)) + 1 with the default smoothing),
+/// and the vector is scaled to a length of 1.
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::stats::TfIdf;
+///
+/// let mut tfidf = TfIdf::new();
+/// tfidf.add_document(&["the", "cat", "sat"]);
+/// tfidf.add_document(&["the", "dog", "sat"]);
+/// tfidf.add_document(&["the", "cat", "ran"]);
+///
+/// // Rare tokens weigh more than tokens found everywhere
+/// assert!(tfidf.idf("dog").unwrap() > tfidf.idf("cat").unwrap());
+/// assert_eq!(tfidf.idf("the"), Some(1.0));
+/// assert_eq!(tfidf.idf("bird"), None);
+///
+/// // A sparse vector over the ids of the vocabulary ("bird" is not in it)
+/// let vector = tfidf.vector(&["the", "dog", "dog", "bird"]);
+/// let ids: Vec<u32> = vector.iter().map(|&(id, _)| id).collect();
+/// assert_eq!(ids, vec![0, 3]);
+/// let length: f64 = vector.iter().map(|(_, weight)| weight * weight).sum::<f64>().sqrt();
+/// assert!((length - 1.0).abs() < 1e-12);
+///
+/// assert_eq!(tfidf.top_terms(&["the", "cat", "sat"], 1)[0].0, "cat");
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TfIdf {
+    /// Number of documents every token appears in
+    document_frequencies: FrequencyTable,
+    /// The ids of the tokens of the collection
+    vocabulary: Vocabulary,
+    /// Add one document containing every token to the idf
+    smooth_idf: bool,
+    /// Use 1 + ln(count) instead of the count
+    sublinear_tf: bool,
+    /// Scale the vectors to a length of 1
+    normalize: bool,
+}
+
+impl Default for TfIdf {
+    fn default() -> Self {
+        TfIdf {
+            document_frequencies: FrequencyTable::new(),
+            vocabulary: Vocabulary::new(),
+            smooth_idf: true,
+            sublinear_tf: false,
+            normalize: true,
+        }
+    }
+}
+
+impl TfIdf {
+    /// Creates a case-sensitive TfIdf without documents, with smoothed idf, raw counts
+    /// and vectors of length 1
+    pub fn new() -> Self {
+        TfIdf::default()
+    }
+
+    /// Sets whether tokens that differ only by case are different tokens (the default);
+    /// set it before adding documents
+    pub fn with_case_sensitive(mut self, enabled: bool) -> Self {
+        self.document_frequencies.case_sensitive = enabled;
+        self
+    }
+
+    /// Sets whether the idf counts one extra document containing every token, so no
+    /// weight is 0 or infinite (the default)
+    pub fn with_smooth_idf(mut self, enabled: bool) -> Self {
+        self.smooth_idf = enabled;
+        self
+    }
+
+    /// Sets whether a token seen `count` times weighs 1 + ln(count) instead of `count`
+    pub fn with_sublinear_tf(mut self, enabled: bool) -> Self {
+        self.sublinear_tf = enabled;
+        self
+    }
+
+    /// Sets whether vectors are scaled to a (Euclidean) length of 1 (the default)
+    pub fn with_normalization(mut self, enabled: bool) -> Self {
+        self.normalize = enabled;
+        self
+    }
+
+    /// Adds one document of the collection
+    pub fn add_document<S: AsRef<str>>(&mut self, tokens: &[S]) {
+        let table = &mut self.document_frequencies;
+        table.documents += 1;
+        // Ids in the order the tokens are first seen (add_token() keeps known ids), and
+        // every distinct token counted once, in that order too
+        let mut seen: HashSet<Cow<str>> = HashSet::new();
+        for token in tokens {
+            let token = table.normalize(token.as_ref());
+            self.vocabulary.add_token(&token);
+            if !seen.contains(&token) {
+                table.add(&token, 1);
+                seen.insert(token);
+            }
+        }
+    }
+
+    /// Adds one document of the collection (line and paragraph breaks are skipped)
+    pub fn add_tokens(&mut self, tokens: &[Token]) {
+        self.add_document(&token_texts(tokens));
+    }
+
+    /// Returns the number of documents in the collection
+    pub fn documents(&self) -> usize {
+        self.document_frequencies.documents()
+    }
+
+    /// Returns the number of documents a token appears in
+    pub fn document_frequency(&self, token: &str) -> usize {
+        self.document_frequencies.count(token)
+    }
+
+    /// Returns the ids of the tokens of the collection, used by vector()
+    pub fn vocabulary(&self) -> &Vocabulary {
+        &self.vocabulary
+    }
+
+    /// Returns the idf of a token, or None if no document of the collection has it
+    pub fn idf(&self, token: &str) -> Option<f64> {
+        let frequency = self.document_frequency(token);
+        if frequency == 0 {
+            return None;
+        }
+        let (documents, frequency) = (self.documents() as f64, frequency as f64);
+        let idf = if self.smooth_idf {
+            ((1.0 + documents) / (1.0 + frequency)).ln()
+        } else {
+            (documents / frequency).ln()
+        };
+        Some(idf + 1.0)
+    }
+
+    /// Returns the TF-IDF vector of a document as (id, weight) pairs sorted by id
+    ///
+    /// Tokens that are not in the collection have no id and are left out.
+    pub fn vector<S: AsRef<str>>(&self, tokens: &[S]) -> Vec<(u32, f64)> {
+        let mut vector: Vec<(u32, f64)> = self
+            .weights(tokens)
+            .into_iter()
+            .filter_map(|(token, weight)| Some((self.vocabulary.token_to_id(&token)?, weight)))
+            .collect();
+        vector.sort_unstable_by_key(|&(id, _)| id);
+        vector
+    }
+
+    /// Returns the `k` tokens of a document with the highest weights, highest first (ties
+    /// in alphabetical order), as they are counted (lowercased without case sensitivity)
+    pub fn top_terms<S: AsRef<str>>(&self, tokens: &[S], k: usize) -> Vec<(String, f64)> {
+        let mut weights = self.weights(tokens);
+        weights.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        weights.truncate(k);
+        weights
+    }
+
+    /// Returns the weight of every token of a document that is in the collection
+    fn weights<S: AsRef<str>>(&self, tokens: &[S]) -> Vec<(String, f64)> {
+        // STEP 1: Count the tokens of the document
+        let mut counts =
+            FrequencyTable::new().with_case_sensitive(self.document_frequencies.case_sensitive);
+        counts.add_strings(tokens);
+
+        // STEP 2: Weigh the ones the collection knows, in document order (so the length
+        // below is summed in the same order on every run, to the last bit)
+        let mut weights: Vec<(String, f64)> = counts
+            .sorted(TokenOrder::Insertion)
+            .into_iter()
+            .filter_map(|(token, count)| {
+                let tf = if self.sublinear_tf {
+                    1.0 + (count as f64).ln()
+                } else {
+                    count as f64
+                };
+                let idf = self.idf(token)?;
+                Some((token.to_string(), tf * idf))
+            })
+            .collect();
+
+        // STEP 3: Scale to a length of 1
+        let length = weights
+            .iter()
+            .map(|(_, weight)| weight * weight)
+            .sum::<f64>()
+            .sqrt();
+        if self.normalize && length > 0.0 {
+            weights.iter_mut().for_each(|(_, weight)| *weight /= length);
+        }
+        weights
+    }
+}
+
+/// Statistics of a list of tokens (see Tokenizer::analyze_tokens())
+///
+/// Kinds are given by TokenKind::of(), lengths are in characters, and empty tokens are
+/// ignored.
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::stats::TokenStats;
+///
+/// let stats = TokenStats::from_tokens(&["The", "cat", "saw", "3", "cats", "!", "The", "end", "."]);
+/// assert_eq!(stats.total, 9);
+/// assert_eq!((stats.words, stats.numbers, stats.punctuation), (6, 1, 2));
+/// assert_eq!(stats.unique, 8);
+/// assert!((stats.type_token_ratio() - 8.0 / 9.0).abs() < 1e-12);
+/// assert_eq!(stats.median_length, 3.0);
+/// assert_eq!(
+///     stats.to_string(),
+///     "tokens: 9 (8 unique, type-token ratio 0.889)\n\
+///      words: 6, numbers: 1, punctuation: 2, other: 0\n\
+///      length: average 2.44, median 3.0\n"
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TokenStats {
+    /// Number of tokens
+    pub total: usize,
+    /// Number of Word tokens
+    pub words: usize,
+    /// Number of Number tokens
+    pub numbers: usize,
+    /// Number of Punctuation tokens
+    pub punctuation: usize,
+    /// Number of tokens of the other kinds (line and paragraph breaks)
+    pub other: usize,
+    /// Number of distinct tokens (case-sensitive)
+    pub unique: usize,
+    /// Average length of the tokens (0.0 without tokens)
+    pub average_length: f64,
+    /// Median length of the tokens (the mean of the two middle ones for an even count)
+    pub median_length: f64,
+}
+
+impl TokenStats {
+    /// Computes the statistics of a list of tokens
+    ///
+    /// # Arguments
+    /// * `tokens` - The token strings
+    ///
+    /// # Returns
+    /// The statistics (all zero for an empty list)
+    pub fn from_tokens<S: AsRef<str>>(tokens: &[S]) -> Self {
+        // Empty tokens are not counted (tokenize() never produces them)
+        let tokens: Vec<&str> = tokens
+            .iter()
+            .map(AsRef::as_ref)
+            .filter(|token| !token.is_empty())
+            .collect();
+        let mut stats = TokenStats {
+            total: tokens.len(),
+            unique: tokens.iter().collect::<HashSet<_>>().len(),
+            ..TokenStats::default()
+        };
+
+        for token in &tokens {
+            match TokenKind::of(token) {
+                TokenKind::Word => stats.words += 1,
+                TokenKind::Number => stats.numbers += 1,
+                TokenKind::Punctuation => stats.punctuation += 1,
+                _ => stats.other += 1,
+            }
+        }
+
+        let mut lengths: Vec<usize> = tokens.iter().map(|token| token.chars().count()).collect();
+        lengths.sort_unstable();
+        if let Some(&middle) = lengths.get(lengths.len() / 2) {
+            stats.average_length = lengths.iter().sum::<usize>() as f64 / lengths.len() as f64;
+            stats.median_length = if lengths.len().is_multiple_of(2) {
+                (lengths[lengths.len() / 2 - 1] + middle) as f64 / 2.0
+            } else {
+                middle as f64
+            };
+        }
+
+        stats
+    }
+
+    /// Returns the number of distinct tokens divided by the number of tokens (0.0 without tokens)
+    pub fn type_token_ratio(&self) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        self.unique as f64 / self.total as f64
+    }
+}
+
+impl fmt::Display for TokenStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "tokens: {} ({} unique, type-token ratio {:.3})",
+            self.total,
+            self.unique,
+            self.type_token_ratio()
+        )?;
+        writeln!(
+            f,
+            "words: {}, numbers: {}, punctuation: {}, other: {}",
+            self.words, self.numbers, self.punctuation, self.other
+        )?;
+        writeln!(
+            f,
+            "length: average {:.2}, median {:.1}",
+            self.average_length, self.median_length
+        )
+    }
+}
+
+/// How many times every length was seen, with percentiles
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::Tokenizer;
+/// use tokenizer_rust::stats::LengthHistogram;
+///
+/// // Documents measured in tokens
+/// let mut histogram = LengthHistogram::new();
+/// histogram.add_documents(&Tokenizer::new(String::new()), &["Hi!", "Hello there.", "Hi!"]);
+/// assert_eq!(histogram.counts(), vec![(2, 2), (3, 1)]);
+///
+/// // Any lengths (like the lengths of encodings)
+/// let mut histogram = LengthHistogram::new();
+/// (1..=100).for_each(|length| histogram.add(length));
+/// assert_eq!(histogram.percentile(50.0), Some(50));
+/// assert_eq!(histogram.percentile(90.0), Some(90));
+/// assert_eq!(histogram.percentile(99.0), Some(99));
+/// assert_eq!((histogram.min(), histogram.max()), (Some(1), Some(100)));
+/// assert_eq!(histogram.mean(), 50.5);
+/// assert_eq!(histogram.to_string(), "count 100, mean 50.5, min 1, P50 50, P90 90, P99 99, max 100\n");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LengthHistogram {
+    /// Number of times every length was seen
+    counts: BTreeMap<usize, usize>,
+    /// Number of lengths added
+    total: usize,
+    /// Sum of the lengths added
+    sum: usize,
+}
+
+impl LengthHistogram {
+    /// Creates an empty histogram
+    pub fn new() -> Self {
+        LengthHistogram::default()
+    }
+
+    /// Adds one length
+    pub fn add(&mut self, length: usize) {
+        *self.counts.entry(length).or_insert(0) += 1;
+        self.total += 1;
+        self.sum += length;
+    }
+
+    /// Adds the length in tokens of every document (see Tokenizer::count_tokens())
+    pub fn add_documents(&mut self, tokenizer: &Tokenizer, texts: &[&str]) {
+        for text in texts {
+            self.add(tokenizer.count_tokens(text));
+        }
+    }
+
+    /// Adds the length in characters of every token (line and paragraph breaks are skipped)
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::stats::LengthHistogram;
+    ///
+    /// let mut histogram = LengthHistogram::new();
+    /// histogram.add_tokens(&Tokenizer::new(String::new()).tokenize_text("Un café, s'il vous plaît."));
+    /// assert_eq!(histogram.counts(), vec![(1, 2), (2, 1), (4, 3), (5, 1)]);
+    /// ```
+    pub fn add_tokens(&mut self, tokens: &[Token]) {
+        for token in token_texts(tokens) {
+            self.add(token.chars().count());
+        }
+    }
+
+    /// Adds the lengths of another histogram
+    pub fn merge(&mut self, other: &LengthHistogram) {
+        for (&length, &count) in &other.counts {
+            *self.counts.entry(length).or_insert(0) += count;
+        }
+        self.total += other.total;
+        self.sum += other.sum;
+    }
+
+    /// Returns every length seen with its count, shortest first
+    pub fn counts(&self) -> Vec<(usize, usize)> {
+        self.counts
+            .iter()
+            .map(|(&length, &count)| (length, count))
+            .collect()
+    }
+
+    /// Returns the number of lengths added
+    pub fn len(&self) -> usize {
+        self.total
+    }
+
+    /// Returns true if no length was added
+    pub fn is_empty(&self) -> bool {
+        self.total == 0
+    }
+
+    /// Returns the smallest length (None if empty)
+    pub fn min(&self) -> Option<usize> {
+        self.counts.keys().next().copied()
+    }
+
+    /// Returns the largest length (None if empty)
+    pub fn max(&self) -> Option<usize> {
+        self.counts.keys().next_back().copied()
+    }
+
+    /// Returns the average length (0.0 if empty)
+    pub fn mean(&self) -> f64 {
+        if self.total == 0 {
+            return 0.0;
+        }
+        self.sum as f64 / self.total as f64
+    }
+
+    /// Returns the length that `percent` percent of the lengths are less than or equal to
+    ///
+    /// This is the nearest-rank percentile: always one of the lengths seen.
+    ///
+    /// # Arguments
+    /// * `percent` - The percentile, from 0 to 100 (clamped)
+    ///
+    /// # Returns
+    /// The percentile, or None if the histogram is empty
+    pub fn percentile(&self, percent: f64) -> Option<usize> {
+        if self.total == 0 {
+            return None;
+        }
+        let rank = ((percent.clamp(0.0, 100.0) / 100.0 * self.total as f64).ceil() as usize)
+            .clamp(1, self.total);
+        let mut seen = 0;
+        self.counts.iter().find_map(|(&length, &count)| {
+            seen += count;
+            (seen >= rank).then_some(length)
+        })
+    }
+}
+
+impl fmt::Display for LengthHistogram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let show = |value: Option<usize>| value.map_or("-".to_string(), |value| value.to_string());
+        writeln!(
+            f,
+            "count {}, mean {:.1}, min {}, P50 {}, P90 {}, P99 {}, max {}",
+            self.total,
+            self.mean(),
+            show(self.min()),
+            show(self.percentile(50.0)),
+            show(self.percentile(90.0)),
+            show(self.percentile(99.0)),
+            show(self.max())
+        )
+    }
+}
+
+/// Returns the texts of the tokens, without line and paragraph breaks
+fn token_texts(tokens: &[Token]) -> Vec<&str> {
+    tokens
+        .iter()
+        .filter(|token| !matches!(token.kind, TokenKind::LineBreak | TokenKind::ParagraphBreak))
+        .map(|token| token.text.as_str())
+        .collect()
+}
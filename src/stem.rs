@@ -0,0 +1,342 @@
+// Stemming with the Porter algorithm
+// Search indexing wants "connect", "connected", "connecting" and "connections" to be one
+// term. The Porter stemmer strips English suffixes in five steps of rules, each rule only
+// applying when enough of the word is left (its "measure", the number of vowel-consonant
+// sequences). This is the reference version of the algorithm published by Martin Porter
+// (with his later changes: "-bli" to "-ble" and "-logi" to "-log"), so the stems are the
+// ones other Porter implementations give.
+// The PorterStemmer filter replaces the text of word tokens by their stem and leaves the
+// offsets alone: a hit on the stem can still be highlighted on the original word.
+
+use crate::manifest::ManifestStep;
+use crate::token::{Token, TokenKind};
+use crate::token_filter::TokenFilter;
+
+/// Replaces the text of every Word token by its Porter stem, keeping the offsets
+///
+/// Words are lowercased first. Only words made of ASCII letters are stemmed (the algorithm
+/// is for English), the others are just lowercased.
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::Tokenizer;
+/// use tokenizer_rust::stem::PorterStemmer;
+/// use tokenizer_rust::token_filter::TokenFilter;
+///
+/// let text = "Connecting the connections";
+/// let tokens = PorterStemmer::new().apply(Tokenizer::new(String::new()).tokenize_text(text));
+/// let stems: Vec<&str> = tokens.iter().map(|token| token.text.as_str()).collect();
+/// assert_eq!(stems, vec!["connect", "the", "connect"]);
+///
+/// // The offsets still point at the original words
+/// assert_eq!(&text[tokens[2].start..tokens[2].end], "connections");
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PorterStemmer;
+
+impl PorterStemmer {
+    /// Creates a stemmer
+    pub fn new() -> Self {
+        PorterStemmer
+    }
+
+    /// Returns the stem of a word (lowercased)
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::stem::PorterStemmer;
+    ///
+    /// let stemmer = PorterStemmer::new();
+    /// let words = ["caresses", "ponies", "agreed", "Hopping", "happy", "relational",
+    ///              "generalization", "filing", "rational", "café"];
+    /// let stems: Vec<String> = words.iter().map(|word| stemmer.stem(word)).collect();
+    /// assert_eq!(stems, vec!["caress", "poni", "agre", "hop", "happi", "relat",
+    ///                        "gener", "file", "ration", "café"]);
+    /// ```
+    pub fn stem(&self, word: &str) -> String {
+        let word = word.to_lowercase();
+        if word.len() <= 2 || !word.bytes().all(|byte| byte.is_ascii_lowercase()) {
+            return word;
+        }
+
+        let mut stemmer = Stemming {
+            b: word.into_bytes(),
+            k: 0,
+            j: 0,
+        };
+        stemmer.k = stemmer.b.len() as isize - 1;
+        stemmer.step1ab();
+        if stemmer.k > 0 {
+            stemmer.step1c();
+            stemmer.step2();
+            stemmer.step3();
+            stemmer.step4();
+            stemmer.step5();
+        }
+        stemmer.b.truncate((stemmer.k + 1) as usize);
+        // Only ASCII letters were ever written
+        String::from_utf8(stemmer.b).unwrap_or_default()
+    }
+}
+
+impl TokenFilter for PorterStemmer {
+    fn apply(&self, mut tokens: Vec<Token>) -> Vec<Token> {
+        for token in &mut tokens {
+            if token.kind == TokenKind::Word {
+                token.text = self.stem(&token.text);
+            }
+        }
+        tokens
+    }
+
+    fn describe(&self) -> Option<ManifestStep> {
+        Some(ManifestStep::new("porter_stemmer"))
+    }
+}
+
+/// The state of the stemming of one word, as in the reference implementation
+struct Stemming {
+    /// The word (only b[0..=k] is still part of it)
+    b: Vec<u8>,
+    /// The index of the last letter of the word
+    k: isize,
+    /// The index of the last letter before the suffix found by ends() (-1 for the whole word)
+    j: isize,
+}
+
+impl Stemming {
+    /// Returns the letter at an index
+    fn at(&self, i: isize) -> u8 {
+        self.b[i as usize]
+    }
+
+    /// Returns true if the letter at i is a consonant ("y" is one after a vowel or first)
+    fn cons(&self, i: isize) -> bool {
+        match self.at(i) {
+            b'a' | b'e' | b'i' | b'o' | b'u' => false,
+            b'y' => i == 0 || !self.cons(i - 1),
+            _ => true,
+        }
+    }
+
+    /// Returns the measure of b[0..=j]: the number of vowel-consonant sequences
+    fn m(&self) -> usize {
+        let mut n = 0;
+        let mut i = 0;
+        // Skip the leading consonants
+        loop {
+            if i > self.j {
+                return n;
+            }
+            if !self.cons(i) {
+                break;
+            }
+            i += 1;
+        }
+        i += 1;
+        loop {
+            // A run of vowels...
+            loop {
+                if i > self.j {
+                    return n;
+                }
+                if self.cons(i) {
+                    break;
+                }
+                i += 1;
+            }
+            i += 1;
+            n += 1;
+            // ...followed by a run of consonants
+            loop {
+                if i > self.j {
+                    return n;
+                }
+                if !self.cons(i) {
+                    break;
+                }
+                i += 1;
+            }
+            i += 1;
+        }
+    }
+
+    /// Returns true if b[0..=j] contains a vowel
+    fn vowel_in_stem(&self) -> bool {
+        (0..=self.j).any(|i| !self.cons(i))
+    }
+
+    /// Returns true if b[j-1..=j] is a double consonant
+    fn double_consonant(&self, j: isize) -> bool {
+        j >= 1 && self.at(j) == self.at(j - 1) && self.cons(j)
+    }
+
+    /// Returns true if b[i-2..=i] is consonant-vowel-consonant and the last consonant is
+    /// not w, x or y ("hop" but not "snow"): the end of a short word like "hope" without e
+    fn cvc(&self, i: isize) -> bool {
+        i >= 2
+            && self.cons(i)
+            && !self.cons(i - 1)
+            && self.cons(i - 2)
+            && !matches!(self.at(i), b'w' | b'x' | b'y')
+    }
+
+    /// Returns true if b[0..=k] ends with the suffix, and then points j before it
+    fn ends(&mut self, suffix: &str) -> bool {
+        let length = suffix.len() as isize;
+        if length > self.k + 1 || !self.b[..=self.k as usize].ends_with(suffix.as_bytes()) {
+            return false;
+        }
+        self.j = self.k - length;
+        true
+    }
+
+    /// Replaces b[j+1..=k] by the text
+    fn set_to(&mut self, text: &str) {
+        let start = (self.j + 1) as usize;
+        self.b.truncate(start);
+        self.b.extend_from_slice(text.as_bytes());
+        self.k = self.j + text.len() as isize;
+    }
+
+    /// Replaces the suffix found by ends() by the text if the measure before it is positive
+    fn replace(&mut self, text: &str) {
+        if self.m() > 0 {
+            self.set_to(text);
+        }
+    }
+
+    /// Replaces the first suffix of the list the word ends with (if the measure allows it)
+    fn replace_first(&mut self, rules: &[(&str, &str)]) {
+        for (suffix, text) in rules {
+            if self.ends(suffix) {
+                self.replace(text);
+                return;
+            }
+        }
+    }
+
+    /// Removes plurals and -ed or -ing ("caresses" -> "caress", "meetings" -> "meet")
+    fn step1ab(&mut self) {
+        if self.at(self.k) == b's' {
+            if self.ends("sses") {
+                self.k -= 2;
+            } else if self.ends("ies") {
+                self.set_to("i");
+            } else if self.at(self.k - 1) != b's' {
+                self.k -= 1;
+            }
+        }
+        if self.ends("eed") {
+            if self.m() > 0 {
+                self.k -= 1;
+            }
+        } else if (self.ends("ed") || self.ends("ing")) && self.vowel_in_stem() {
+            self.k = self.j;
+            if self.ends("at") {
+                self.set_to("ate");
+            } else if self.ends("bl") {
+                self.set_to("ble");
+            } else if self.ends("iz") {
+                self.set_to("ize");
+            } else if self.double_consonant(self.k) {
+                // "hopping" -> "hop", but "falling" -> "fall"
+                if !matches!(self.at(self.k), b'l' | b's' | b'z') {
+                    self.k -= 1;
+                }
+            } else if self.m() == 1 && self.cvc(self.k) {
+                self.set_to("e");
+            }
+        }
+    }
+
+    /// Turns a final y into i when there is another vowel ("happy" -> "happi")
+    fn step1c(&mut self) {
+        if self.ends("y") && self.vowel_in_stem() {
+            let k = self.k as usize;
+            self.b[k] = b'i';
+        }
+    }
+
+    /// Maps double suffixes to single ones ("-ization" -> "-ize")
+    fn step2(&mut self) {
+        let rules: &[(&str, &str)] = match self.at(self.k - 1) {
+            b'a' => &[("ational", "ate"), ("tional", "tion")],
+            b'c' => &[("enci", "ence"), ("anci", "ance")],
+            b'e' => &[("izer", "ize")],
+            b'l' => &[
+                ("bli", "ble"),
+                ("alli", "al"),
+                ("entli", "ent"),
+                ("eli", "e"),
+                ("ousli", "ous"),
+            ],
+            b'o' => &[("ization", "ize"), ("ation", "ate"), ("ator", "ate")],
+            b's' => &[
+                ("alism", "al"),
+                ("iveness", "ive"),
+                ("fulness", "ful"),
+                ("ousness", "ous"),
+            ],
+            b't' => &[("aliti", "al"), ("iviti", "ive"), ("biliti", "ble")],
+            b'g' => &[("logi", "log")],
+            _ => &[],
+        };
+        self.replace_first(rules);
+    }
+
+    /// Removes or simplifies -ic-, -full, -ness and the like ("-icate" -> "-ic")
+    fn step3(&mut self) {
+        let rules: &[(&str, &str)] = match self.at(self.k) {
+            b'e' => &[("icate", "ic"), ("ative", ""), ("alize", "al")],
+            b'i' => &[("iciti", "ic")],
+            b'l' => &[("ical", "ic"), ("ful", "")],
+            b's' => &[("ness", "")],
+            _ => &[],
+        };
+        self.replace_first(rules);
+    }
+
+    /// Removes -ant, -ence and the like from words that are long enough (measure over 1)
+    fn step4(&mut self) {
+        let suffixes: &[&str] = match self.at(self.k - 1) {
+            b'a' => &["al"],
+            b'c' => &["ance", "ence"],
+            b'e' => &["er"],
+            b'i' => &["ic"],
+            b'l' => &["able", "ible"],
+            b'n' => &["ant", "ement", "ment", "ent"],
+            b'o' => &["ion", "ou"],
+            b's' => &["ism"],
+            b't' => &["ate", "iti"],
+            b'u' => &["ous"],
+            b'v' => &["ive"],
+            b'z' => &["ize"],
+            _ => &[],
+        };
+        let Some(&suffix) = suffixes.iter().find(|suffix| self.ends(suffix)) else {
+            return;
+        };
+        // "-ion" is only removed after s or t ("adoption" -> "adopt", not "opinion")
+        if suffix == "ion" && !(self.j >= 0 && matches!(self.at(self.j), b's' | b't')) {
+            return;
+        }
+        if self.m() > 1 {
+            self.k = self.j;
+        }
+    }
+
+    /// Removes a final -e and turns -ll into -l on long enough words
+    fn step5(&mut self) {
+        self.j = self.k;
+        if self.at(self.k) == b'e' {
+            let m = self.m();
+            if m > 1 || (m == 1 && !self.cvc(self.k - 1)) {
+                self.k -= 1;
+            }
+        }
+        if self.at(self.k) == b'l' && self.double_consonant(self.k) && self.m() > 1 {
+            self.k -= 1;
+        }
+    }
+}
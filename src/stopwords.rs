@@ -0,0 +1,210 @@
+// Stopword filtering
+// Function words ("the", "of", "and") are the most frequent tokens of any text and carry
+// little meaning, so keyword extraction, search indexing and bag-of-words features usually
+// drop them. A StopwordFilter holds one list (the built-in English one, a custom file, or
+// both) and either removes the stopwords from a token list or returns a mask of them.
+// Only Word tokens are compared: numbers, punctuation and special tokens are always kept.
+
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+use crate::manifest::{escape, ManifestStep};
+use crate::token::{Token, TokenKind};
+use crate::token_filter::TokenFilter;
+
+/// The built-in English stopwords, separated by whitespace (the NLTK list, with the
+/// contractions the tokenizer keeps whole like "don't", and the pieces left when a
+/// typographic apostrophe splits them)
+pub const ENGLISH: &str = "\
+    i me my myself we our ours ourselves you you're you've you'll you'd your yours yourself \
+    yourselves he him his himself she she's her hers herself it it's its itself they them \
+    their theirs themselves what which who whom this that that'll these those am is are was \
+    were be been being have has had having do does did doing a an the and but if or because as \
+    until while of at by for with about against between into through during before after above \
+    below to from up down in out on off over under again further then once here there when \
+    where why how all any both each few more most other some such no nor not only own same so \
+    than too very s t can will just don don't should should've now d ll m o re ve y ain aren \
+    aren't couldn couldn't didn didn't doesn doesn't hadn hadn't hasn hasn't haven haven't isn \
+    isn't ma mightn mightn't mustn mustn't needn needn't shan shan't shouldn shouldn't wasn \
+    wasn't weren weren't won won't wouldn wouldn't";
+
+/// Removes stopwords from token lists, or marks them
+///
+/// Comparisons ignore case by default ("The" is a stopword like "the").
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::Tokenizer;
+/// use tokenizer_rust::stopwords::StopwordFilter;
+/// use tokenizer_rust::token_filter::TokenFilter;
+///
+/// let text = "The cat sat on the mat, didn't it?";
+/// let tokens = Tokenizer::new(String::new()).tokenize_text(text);
+/// let filter = StopwordFilter::english();
+///
+/// // Either the tokens that are kept (with their offsets in the text)...
+/// let kept = filter.apply(tokens.clone());
+/// let texts: Vec<&str> = kept.iter().map(|token| token.text.as_str()).collect();
+/// assert_eq!(texts, vec!["cat", "sat", "mat", ",", "?"]);
+/// assert_eq!(&text[kept[2].start..kept[2].end], "mat");
+///
+/// // ...or a mask over all of them (true = kept)
+/// let mask = filter.mask(&tokens);
+/// assert_eq!(mask, vec![false, true, true, false, false, true, true, false, false, true]);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct StopwordFilter {
+    /// The stopwords (lowercased unless case_sensitive)
+    words: HashSet<String>,
+    /// If true, "The" and "the" are different words
+    case_sensitive: bool,
+}
+
+impl StopwordFilter {
+    /// Creates a filter without any stopword (add some with with_words() or read_from())
+    pub fn new() -> Self {
+        StopwordFilter::default()
+    }
+
+    /// Creates a filter with the built-in English stopwords (see ENGLISH)
+    pub fn english() -> Self {
+        StopwordFilter::new().with_words(ENGLISH.split_whitespace())
+    }
+
+    /// Adds stopwords to the list
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::stopwords::StopwordFilter;
+    ///
+    /// let filter = StopwordFilter::english().with_words(["via", "etc"]);
+    /// assert!(filter.is_stopword("Via"));
+    /// assert!(filter.is_stopword("the"));
+    /// assert_eq!(filter.len(), 181);
+    /// assert!(!filter.is_stopword("cat"));
+    /// ```
+    pub fn with_words<I, S>(mut self, words: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        for word in words {
+            let word = self.normalize(word.as_ref()).into_owned();
+            self.words.insert(word);
+        }
+        self
+    }
+
+    /// Sets whether the case of a word matters (false by default)
+    ///
+    /// Words already in the list are lowercased when case is ignored, so set this first
+    /// to keep a case-sensitive list like ["US", "IT"] as it is.
+    pub fn with_case_sensitive(mut self, case_sensitive: bool) -> Self {
+        self.case_sensitive = case_sensitive;
+        if !case_sensitive {
+            self.words = self.words.iter().map(|word| word.to_lowercase()).collect();
+        }
+        self
+    }
+
+    /// Adds the stopwords of a list: one word per line, blank lines and lines starting
+    /// with "#" are ignored
+    ///
+    /// # Arguments
+    /// * `reader` - Where to read the list from
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::stopwords::StopwordFilter;
+    ///
+    /// let list = "# French\nle\nla\n\nles\n";
+    /// let filter = StopwordFilter::new().read_from(list.as_bytes()).unwrap();
+    /// assert_eq!(filter.len(), 3);
+    /// assert!(filter.is_stopword("Les"));
+    /// ```
+    pub fn read_from<R: BufRead>(self, reader: R) -> io::Result<Self> {
+        let mut words = Vec::new();
+        for line in reader.lines() {
+            let line = line?;
+            let word = line.trim();
+            if !word.is_empty() && !word.starts_with('#') {
+                words.push(word.to_string());
+            }
+        }
+        Ok(self.with_words(words))
+    }
+
+    /// Adds the stopwords of a list file (see read_from() for the format)
+    pub fn load<P: AsRef<Path>>(self, path: P) -> io::Result<Self> {
+        self.read_from(BufReader::new(File::open(path)?))
+    }
+
+    /// Returns true if the word is a stopword
+    pub fn is_stopword(&self, word: &str) -> bool {
+        self.words.contains(self.normalize(word).as_ref())
+    }
+
+    /// Returns true if the token is a stopword (only Word tokens can be)
+    pub fn is_stopword_token(&self, token: &Token) -> bool {
+        token.kind == TokenKind::Word && self.is_stopword(&token.text)
+    }
+
+    /// Returns one flag per token: true for the tokens that are kept, false for stopwords
+    pub fn mask(&self, tokens: &[Token]) -> Vec<bool> {
+        tokens
+            .iter()
+            .map(|token| !self.is_stopword_token(token))
+            .collect()
+    }
+
+    /// Returns the number of stopwords in the list
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    /// Returns true if the list has no stopwords
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    /// Rebuilds a filter from the step written by describe()
+    pub(crate) fn from_step(step: &ManifestStep) -> Option<Self> {
+        let case_sensitive = step.value("case_sensitive")?.parse().ok()?;
+        Some(
+            StopwordFilter::new()
+                .with_case_sensitive(case_sensitive)
+                .with_words(step.values("word")),
+        )
+    }
+
+    /// Returns the word as it is stored (lowercased without case sensitivity)
+    fn normalize<'t>(&self, word: &'t str) -> Cow<'t, str> {
+        if self.case_sensitive || !word.chars().any(char::is_uppercase) {
+            Cow::Borrowed(word)
+        } else {
+            Cow::Owned(word.to_lowercase())
+        }
+    }
+}
+
+impl TokenFilter for StopwordFilter {
+    fn apply(&self, mut tokens: Vec<Token>) -> Vec<Token> {
+        tokens.retain(|token| !self.is_stopword_token(token));
+        tokens
+    }
+
+    /// The words are written in sorted order, so the same list always has the same hash
+    fn describe(&self) -> Option<ManifestStep> {
+        let mut step = ManifestStep::new("stopwords");
+        step.push("case_sensitive", self.case_sensitive);
+        let mut words: Vec<&String> = self.words.iter().collect();
+        words.sort_unstable();
+        for word in words {
+            step.push("word", escape(word));
+        }
+        Some(step)
+    }
+}
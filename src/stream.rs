@@ -0,0 +1,336 @@
+// Streaming tokenization: tokenize input that is too big to load into a String
+// The input is read chunk by chunk. Every chunk is only tokenized up to a safe cut point
+// (whitespace), and the rest waits for the next chunk, so tokens spanning two chunks
+// come out exactly as if the whole input had been read at once.
+// Gigantic lines without any whitespace (minified JSON or HTML) are cut after punctuation,
+// so they are streamed too instead of being buffered whole.
+
+use std::collections::VecDeque;
+use std::io::{self, Read};
+
+use crate::special::{self, SpecialTokens};
+use crate::token::{DocumentToken, Token};
+use crate::Tokenizer;
+
+/// How many bytes are read from the input at a time by default
+const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// How many bytes may wait for a newline or a double space before the buffer is cut at any
+/// whitespace or punctuation
+pub(crate) const MAX_PENDING_BYTES: usize = 1024 * 1024;
+
+/// The buffering shared by the blocking TokenReader and the async token stream
+///
+/// Bytes are pushed in as they arrive, and tokens come out once it is safe to cut.
+#[derive(Debug, Default)]
+pub(crate) struct Chunker {
+    /// Bytes received but not tokenized yet
+    buffer: Vec<u8>,
+    /// Byte offset of buffer[0] in the stream
+    buffer_start: usize,
+    /// Tokens ready to be returned
+    pub(crate) pending: VecDeque<Token>,
+}
+
+impl Chunker {
+    /// Adds bytes from the input and tokenizes everything before the last safe cut point
+    pub(crate) fn push(&mut self, tokenizer: &Tokenizer, bytes: &[u8]) -> io::Result<()> {
+        self.buffer.extend_from_slice(bytes);
+        let any_boundary = self.buffer.len() >= MAX_PENDING_BYTES;
+        match safe_cut(&self.buffer, any_boundary, &tokenizer.protected_tokens()) {
+            Some(cut) => self.tokenize_buffer(tokenizer, cut),
+            None => Ok(()),
+        }
+    }
+
+    /// Tokenizes everything left at the end of the input
+    pub(crate) fn finish(&mut self, tokenizer: &Tokenizer) -> io::Result<()> {
+        let length = self.buffer.len();
+        self.tokenize_buffer(tokenizer, length)
+    }
+
+    /// Drops everything still waiting (after an error)
+    pub(crate) fn clear(&mut self) {
+        self.buffer.clear();
+        self.pending.clear();
+    }
+
+    /// Tokenizes the first `length` bytes of the buffer and removes them from it
+    fn tokenize_buffer(&mut self, tokenizer: &Tokenizer, length: usize) -> io::Result<()> {
+        let text = std::str::from_utf8(&self.buffer[..length])
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+
+        for mut token in tokenizer.tokenize_text(text) {
+            token.start += self.buffer_start;
+            token.end += self.buffer_start;
+            self.pending.push_back(token);
+        }
+
+        self.buffer.drain(..length);
+        self.buffer_start += length;
+        Ok(())
+    }
+}
+
+/// An iterator over the tokens of a reader, created by Tokenizer::tokenize_reader()
+///
+/// Token offsets are byte offsets from the start of the stream.
+/// Reading errors and invalid UTF-8 are returned as `io::Error`s, after which the iterator stops.
+///
+/// The input does not need line breaks: a single line of hundreds of megabytes is cut at
+/// whitespace or punctuation once 1 MiB of it is waiting, so memory stays bounded.
+///
+/// # Example
+/// ```
+/// use std::io::Read;
+/// use tokenizer_rust::Tokenizer;
+///
+/// // A minified JSON array of 4 MB on one line, without a single space
+/// let items: Vec<String> = (0..100_000)
+///     .map(|id| format!(r#"{{"id":{id},"name":"item-{id}","tags":["a","b"]}}"#))
+///     .collect();
+/// let json = format!("[{}]", items.join(","));
+/// assert!(json.len() > 4_000_000 && !json.contains(char::is_whitespace));
+///
+/// // The streamed tokens are the tokens of the whole line
+/// let tokenizer = Tokenizer::new(String::new());
+/// let streamed: Vec<_> = tokenizer
+///     .tokenize_reader(json.as_bytes())
+///     .collect::<Result<_, _>>()
+///     .unwrap();
+/// assert_eq!(streamed, tokenizer.tokenize_text(&json));
+///
+/// // And the first token comes out long before the whole line was read
+/// struct Counted<'a>(&'a [u8], usize);
+/// impl Read for Counted<'_> {
+///     fn read(&mut self, buffer: &mut [u8]) -> std::io::Result<usize> {
+///         let read = self.0.read(buffer)?;
+///         self.1 += read;
+///         Ok(read)
+///     }
+/// }
+/// let mut input = Counted(json.as_bytes(), 0);
+/// let first = tokenizer.tokenize_reader(&mut input).next().unwrap().unwrap();
+/// assert_eq!(first.text, "[");
+/// assert!(input.1 < 2_000_000);
+/// ```
+pub struct TokenReader<'a, R> {
+    /// The tokenizer whose settings are used
+    tokenizer: &'a Tokenizer,
+    /// Where the text comes from
+    reader: R,
+    /// How many bytes are read at a time
+    chunk_size: usize,
+    /// The bytes and tokens waiting to be returned
+    chunker: Chunker,
+    /// true once the end of the input (or an error) was reached
+    finished: bool,
+}
+
+impl<'a, R: Read> TokenReader<'a, R> {
+    /// Creates a token reader (use Tokenizer::tokenize_reader() instead)
+    pub fn new(tokenizer: &'a Tokenizer, reader: R) -> Self {
+        TokenReader {
+            tokenizer,
+            reader,
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            chunker: Chunker::default(),
+            finished: false,
+        }
+    }
+
+    /// Sets how many bytes are read at a time (64 KiB by default, at least 1)
+    pub fn with_chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size.max(1);
+        self
+    }
+
+    /// Reads chunks until some tokens are ready or the input ends
+    fn fill_pending(&mut self) -> io::Result<()> {
+        let mut chunk = vec![0; self.chunk_size];
+
+        while self.chunker.pending.is_empty() && !self.finished {
+            let read = match self.reader.read(&mut chunk) {
+                Ok(read) => read,
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => continue,
+                Err(error) => return Err(error),
+            };
+
+            // At the end of the input everything left is tokenized,
+            // otherwise only the part before the last safe cut point
+            if read == 0 {
+                self.finished = true;
+                self.chunker.finish(self.tokenizer)?;
+            } else {
+                self.chunker.push(self.tokenizer, &chunk[..read])?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<R: Read> Iterator for TokenReader<'_, R> {
+    type Item = io::Result<Token>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.chunker.pending.is_empty() {
+            if let Err(error) = self.fill_pending() {
+                // Stop after the first error
+                self.finished = true;
+                self.chunker.clear();
+                return Some(Err(error));
+            }
+        }
+
+        self.chunker.pending.pop_front().map(Ok)
+    }
+}
+
+/// Finds where a buffer can be cut without splitting a token
+///
+/// The cut goes right before the whitespace around the last newline, or else after the last
+/// run of two or more whitespace bytes: identifiers like IBANs contain single spaces
+/// ("DE89 3704 ..."), but never newlines or double spaces. Keeping the newlines of a blank
+/// line together also keeps ParagraphBreak tokens whole. Only when the buffer gets too big
+/// (a gigantic line) is it cut after any whitespace byte, which could split such an identifier,
+/// or after a punctuation byte: punctuation is always a token of its own, so minified text
+/// without any whitespace can be cut too. A cut point inside a special or added token
+/// ("<|endoftext|>" has punctuation, a token could have spaces), or inside the start of one at
+/// the end of the buffer, is never used: the cut moves to an earlier cut point instead.
+///
+/// # Arguments
+/// * `buffer` - The bytes waiting to be tokenized
+/// * `any_boundary` - If true, a single whitespace or punctuation byte is a valid cut point too
+/// * `protected` - The special and added tokens that must not be split
+///
+/// # Returns
+/// The length of the part that can be tokenized now, or None if there is no safe cut point
+pub(crate) fn safe_cut(
+    buffer: &[u8],
+    any_boundary: bool,
+    protected: &[&SpecialTokens],
+) -> Option<usize> {
+    let mut end = buffer.len();
+    while let Some(cut) = boundary_cut(&buffer[..end], any_boundary) {
+        match special::start_across(buffer, cut, protected) {
+            Some(start) => end = start,
+            None => return Some(cut),
+        }
+    }
+    None
+}
+
+/// The last cut point of a buffer by whitespace and punctuation alone (see safe_cut())
+fn boundary_cut(buffer: &[u8], any_boundary: bool) -> Option<usize> {
+    // Only the text after the last newline run is searched for the other cut points
+    let mut searched = 0;
+    if let Some(position) = buffer.iter().rposition(|&byte| byte == b'\n') {
+        let run_start = buffer[..position]
+            .iter()
+            .rposition(|byte| !byte.is_ascii_whitespace())
+            .map_or(0, |before| before + 1);
+        if run_start > 0 {
+            return Some(run_start);
+        }
+
+        // The newline run starts the buffer, so it may not be complete yet
+        match buffer[position..]
+            .iter()
+            .position(|byte| !byte.is_ascii_whitespace())
+        {
+            Some(text_start) => searched = position + text_start,
+            None => return any_boundary.then_some(buffer.len()),
+        }
+    }
+    let rest = &buffer[searched..];
+
+    let double = rest
+        .windows(2)
+        .rposition(|pair| pair[0].is_ascii_whitespace() && pair[1].is_ascii_whitespace());
+    if let Some(position) = double {
+        return Some(searched + position + 2);
+    }
+    if !any_boundary {
+        return None;
+    }
+
+    // Apostrophes and hyphens are not boundaries: they join words ("don't", "e-mail")
+    rest.iter()
+        .rposition(|&byte| {
+            byte.is_ascii_whitespace()
+                || (byte.is_ascii_punctuation() && byte != b'\'' && byte != b'-')
+        })
+        .map(|position| searched + position + 1)
+}
+
+/// An iterator over the tokens of several readers, one document each, created by
+/// Tokenizer::tokenize_readers()
+///
+/// Every token carries the index of its reader, and its offsets start at 0 in every
+/// reader. The readers are opened one after the other (an iterator of files is only
+/// opened as it is reached). After an error the iterator stops.
+pub struct DocumentTokenReader<'a, I: Iterator> {
+    /// The tokenizer whose settings are used
+    tokenizer: &'a Tokenizer,
+    /// The readers not started yet
+    readers: I,
+    /// The reader being read, with its index
+    current: Option<(usize, TokenReader<'a, I::Item>)>,
+    /// Index of the next reader
+    next_document: usize,
+    /// true after an error
+    failed: bool,
+}
+
+impl<'a, I> DocumentTokenReader<'a, I>
+where
+    I: Iterator,
+    I::Item: Read,
+{
+    /// Creates a token reader over several readers (use Tokenizer::tokenize_readers() instead)
+    pub fn new<T: IntoIterator<IntoIter = I>>(tokenizer: &'a Tokenizer, readers: T) -> Self {
+        DocumentTokenReader {
+            tokenizer,
+            readers: readers.into_iter(),
+            current: None,
+            next_document: 0,
+            failed: false,
+        }
+    }
+}
+
+impl<I> Iterator for DocumentTokenReader<'_, I>
+where
+    I: Iterator,
+    I::Item: Read,
+{
+    type Item = io::Result<DocumentToken>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.failed {
+            // Start the next reader when the current one is done
+            let Some((document, reader)) = &mut self.current else {
+                let reader = self.readers.next()?;
+                self.current = Some((self.next_document, TokenReader::new(self.tokenizer, reader)));
+                self.next_document += 1;
+                continue;
+            };
+
+            match reader.next() {
+                Some(Ok(token)) => {
+                    return Some(Ok(DocumentToken {
+                        document: *document,
+                        token,
+                    }))
+                }
+                Some(Err(error)) => {
+                    self.failed = true;
+                    return Some(Err(error));
+                }
+                None => self.current = None,
+            }
+        }
+        None
+    }
+}
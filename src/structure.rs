@@ -0,0 +1,144 @@
+// Document structure: paragraphs and lines
+// Tokenization normally drops all whitespace, so a document comes back from detokenize() as
+// one long line. This module splits documents into paragraphs (separated by blank lines) and
+// lines, and creates the LineBreak / ParagraphBreak tokens the Tokenizer emits when
+// structure tokens are enabled, so the layout survives a tokenize/detokenize round trip.
+
+use crate::token::{Token, TokenKind};
+
+/// Finds the paragraphs of a text (separated by one or more blank lines)
+///
+/// # Arguments
+/// * `text` - The document
+///
+/// # Returns
+/// The (start, end) byte range of every paragraph, without the whitespace around it
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::structure::paragraph_spans;
+///
+/// let text = "Title\n\nFirst paragraph,\nsecond line.\n \n\nLast one.\n";
+/// let paragraphs: Vec<&str> = paragraph_spans(text).into_iter().map(|(start, end)| &text[start..end]).collect();
+/// assert_eq!(paragraphs, vec!["Title", "First paragraph,\nsecond line.", "Last one."]);
+/// ```
+pub fn paragraph_spans(text: &str) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut paragraph: Option<(usize, usize)> = None;
+
+    for (start, end) in line_spans(text) {
+        let line = &text[start..end];
+        if line.trim().is_empty() {
+            // A blank line ends the current paragraph
+            spans.extend(paragraph.take());
+            continue;
+        }
+
+        // Leading whitespace of the first line and trailing whitespace of the last one are dropped
+        let line_start = start + (line.len() - line.trim_start().len());
+        let line_end = start + line.trim_end().len();
+        paragraph = match paragraph {
+            Some((paragraph_start, _)) => Some((paragraph_start, line_end)),
+            None => Some((line_start, line_end)),
+        };
+    }
+
+    spans.extend(paragraph);
+    spans
+}
+
+/// Finds the lines of a text
+///
+/// Lines end with "\n" or "\r\n", which are not part of the line. Empty lines are kept,
+/// and a final line break does not start an extra empty line (like `str::lines()`).
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::structure::line_spans;
+///
+/// assert_eq!(line_spans("one\r\ntwo\n\nthree\n"), vec![(0, 3), (5, 8), (9, 9), (10, 15)]);
+/// ```
+pub fn line_spans(text: &str) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut start = 0;
+
+    while start < text.len() {
+        let (end, next) = match text[start..].find('\n') {
+            Some(newline) => (start + newline, start + newline + 1),
+            None => (text.len(), text.len()),
+        };
+        // "\r\n" line endings: the "\r" is not part of the line either
+        let end = if text[start..end].ends_with('\r') {
+            end - 1
+        } else {
+            end
+        };
+        spans.push((start, end));
+        start = next;
+    }
+
+    spans
+}
+
+/// Returns what a structure token stands for, or None if the token is not one
+///
+/// A structure token is made only of whitespace and contains at least one newline:
+/// one newline is a line break ("\n"), more is a paragraph break ("\n\n").
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::structure::structure_break;
+///
+/// assert_eq!(structure_break("\r\n"), Some("\n"));
+/// assert_eq!(structure_break("\n  \n"), Some("\n\n"));
+/// assert_eq!(structure_break(" "), None);
+/// assert_eq!(structure_break("word"), None);
+/// ```
+pub fn structure_break(token: &str) -> Option<&'static str> {
+    if !token.chars().all(char::is_whitespace) {
+        return None;
+    }
+    match token.matches('\n').count() {
+        0 => None,
+        1 => Some("\n"),
+        _ => Some("\n\n"),
+    }
+}
+
+/// Adds LineBreak and ParagraphBreak tokens for the newlines between the tokens of a text
+///
+/// Every stretch of whitespace containing newlines becomes one token, going from its first
+/// to its last newline: a LineBreak for one newline, a ParagraphBreak for more.
+pub(crate) fn insert_breaks(text: &str, tokens: Vec<Token>) -> Vec<Token> {
+    let mut result = Vec::with_capacity(tokens.len() + tokens.len() / 8);
+
+    let mut gap_start = 0;
+    for token in tokens {
+        push_break(text, gap_start, token.start, &mut result);
+        gap_start = token.end;
+        result.push(token);
+    }
+    push_break(text, gap_start, text.len(), &mut result);
+
+    result
+}
+
+/// Pushes the break token of the gap text[start..end], if the gap contains newlines
+fn push_break(text: &str, start: usize, end: usize, tokens: &mut Vec<Token>) {
+    let gap = &text[start..end];
+    let (Some(first), Some(last)) = (gap.find('\n'), gap.rfind('\n')) else {
+        return;
+    };
+
+    let kind = if first == last {
+        TokenKind::LineBreak
+    } else {
+        TokenKind::ParagraphBreak
+    };
+    tokens.push(Token {
+        text: gap[first..=last].to_string(),
+        kind,
+        start: start + first,
+        end: start + last + 1,
+    });
+}
@@ -0,0 +1,107 @@
+// Subtitle line breaking
+// Splits text into display lines that fit a character budget, only breaking between tokens
+// (never inside a word, and never between a word and the punctuation attached to it)
+
+use crate::tokenizer::Tokenizer;
+
+/// Breaks text into display lines of at most `max_chars` characters
+///
+/// Lines are only broken where detokenize() would put a space, so "world," or "(Not"
+/// always stay together on one line. The function first finds the smallest number of
+/// lines the text needs, then spreads the words over those lines so that the line
+/// lengths are as even as possible (a long line followed by a one-word line looks bad
+/// on screen).
+///
+/// A single unit that is longer than `max_chars` on its own (a very long word, for example)
+/// is put on a line by itself instead of being cut.
+///
+/// # Arguments
+/// * `text` - The text to break into lines
+/// * `max_chars` - The maximum number of characters per line
+///
+/// # Returns
+/// The display lines, in order, each one rebuilt with detokenize()
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::subtitles::break_lines;
+///
+/// let lines = break_lines("I told you, we never go there at night!", 24);
+/// // lines will be: ["I told you, we never", "go there at night!"]
+/// assert_eq!(lines, vec!["I told you, we never", "go there at night!"]);
+/// ```
+pub fn break_lines(text: &str, max_chars: usize) -> Vec<String> {
+    let tokenizer = Tokenizer::new(text.to_string());
+    let tokens = tokenizer.tokenize();
+
+    if tokens.is_empty() {
+        return Vec::new();
+    }
+
+    // STEP 1: Group tokens into "units" that must stay on the same line
+    // A new unit starts at every place where detokenize() would put a space
+    // Each unit is stored as (first token index, last token index + 1, length in characters)
+    let mut units: Vec<(usize, usize, usize)> = Vec::new();
+    let mut unit_start = 0;
+    let mut unit_length = tokens[0].chars().count();
+
+    for index in 1..tokens.len() {
+        if Tokenizer::needs_space(&tokens[index - 1], &tokens[index]) {
+            units.push((unit_start, index, unit_length));
+            unit_start = index;
+            unit_length = 0;
+        }
+        unit_length += tokens[index].chars().count();
+    }
+    units.push((unit_start, tokens.len(), unit_length));
+
+    // STEP 2: Find the best way to cut the units into lines
+    // best[i] holds the cheapest way to lay out the first i units as (number of lines, cost)
+    // Comparing the tuples puts "fewer lines" first and "more even lines" second
+    // The cost of a line is its squared empty space, so uneven layouts cost more
+    let mut best: Vec<(usize, u64)> = vec![(usize::MAX, u64::MAX); units.len() + 1];
+    let mut previous_break: Vec<usize> = vec![0; units.len() + 1];
+    best[0] = (0, 0);
+
+    for end in 1..=units.len() {
+        // Try every possible first unit for the line that ends with unit `end - 1`
+        let mut line_length = 0;
+        for start in (0..end).rev() {
+            // Add the unit, plus the space that separates it from the next unit on the line
+            line_length += units[start].2;
+            if start < end - 1 {
+                line_length += 1;
+            }
+
+            // Stop once the line is too long (unless it holds a single oversized unit)
+            if line_length > max_chars && start < end - 1 {
+                break;
+            }
+
+            if best[start].0 == usize::MAX {
+                continue;
+            }
+
+            let slack = max_chars.saturating_sub(line_length) as u64;
+            let candidate = (best[start].0 + 1, best[start].1 + slack * slack);
+            if candidate < best[end] {
+                best[end] = candidate;
+                previous_break[end] = start;
+            }
+        }
+    }
+
+    // STEP 3: Walk the chosen breaks backwards to collect the lines
+    let mut lines: Vec<String> = Vec::new();
+    let mut end = units.len();
+    while end > 0 {
+        let start = previous_break[end];
+        let first_token = units[start].0;
+        let last_token = units[end - 1].1;
+        lines.push(tokenizer.detokenize(&tokens[first_token..last_token]));
+        end = start;
+    }
+    lines.reverse();
+
+    lines
+}
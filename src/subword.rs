@@ -0,0 +1,420 @@
+// Subword models learned from the word counts of a corpus
+// A word-level vocabulary has no id for a word it never saw. A subword model splits such a
+// word into pieces it knows instead. Both models here start from the characters of the
+// words and merge the pair of neighbouring pieces that scores best, again and again, until
+// there are as many pieces as asked for:
+// - BPE merges the most frequent pair, and splits a word by replaying its merges in order
+// - WordPiece merges the pair that is frequent compared to its two pieces (count of the
+//   pair divided by the counts of the pieces), and splits a word greedily, longest known
+//   piece first
+// Pieces that continue a word start with "##" ("token" "##izer"), as in BERT, so decode()
+// can glue them back onto the piece before them.
+
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+
+use crate::stats::FrequencyTable;
+
+/// The prefix of the pieces that continue a word
+pub const CONTINUATION_PREFIX: &str = "##";
+
+/// How a subword model is learned and how it splits words
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SubwordKind {
+    /// Byte pair encoding: the most frequent pair is merged, words are split by its merges
+    Bpe,
+    /// WordPiece: the pair with the best likelihood score is merged, words are split
+    /// longest piece first
+    WordPiece,
+}
+
+impl SubwordKind {
+    /// Returns the name of the kind, as written in manifests and saved tokenizers
+    pub fn name(&self) -> &'static str {
+        match self {
+            SubwordKind::Bpe => "bpe",
+            SubwordKind::WordPiece => "wordpiece",
+        }
+    }
+
+    /// Finds a kind by its name ("bpe" or "wordpiece")
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "bpe" => Some(SubwordKind::Bpe),
+            "wordpiece" => Some(SubwordKind::WordPiece),
+            _ => None,
+        }
+    }
+}
+
+/// The pieces (and for BPE the merges) of a subword model
+///
+/// Use it with EncoderConfig::with_subwords(): encode() then splits the tokens that are not
+/// in the vocabulary into pieces.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubwordModel {
+    /// How the model was learned
+    kind: SubwordKind,
+    /// Every piece, the characters first and then the merged pieces in the order they were
+    /// learned
+    pieces: Vec<String>,
+    /// The merges of a BPE model, in the order they were learned (empty for WordPiece)
+    merges: Vec<(String, String)>,
+    /// The pieces, for lookups
+    known: HashSet<String>,
+    /// The position of every merge in `merges`
+    ranks: HashMap<(String, String), usize>,
+}
+
+impl SubwordModel {
+    /// Learns a model from the word counts of a corpus
+    ///
+    /// Words containing whitespace (grouped identifiers) are left out. The characters of
+    /// the words are always pieces, so a corpus with more distinct characters than
+    /// `vocab_size` gives a model without any merge.
+    ///
+    /// # Arguments
+    /// * `kind` - BPE or WordPiece
+    /// * `frequencies` - The word counts of the corpus
+    /// * `min_count` - Words seen fewer times are left out
+    /// * `vocab_size` - How many pieces to learn, characters included
+    ///
+    /// # Returns
+    /// The model (it has fewer pieces when there is nothing left to merge)
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::stats::FrequencyTable;
+    /// use tokenizer_rust::subword::{SubwordKind, SubwordModel};
+    ///
+    /// let mut frequencies = FrequencyTable::new();
+    /// for (word, count) in [("low", 5), ("lower", 2), ("newest", 6), ("widest", 3)] {
+    ///     frequencies.add_strings(&vec![word; count]);
+    /// }
+    /// let model = SubwordModel::train(SubwordKind::Bpe, &frequencies, 1, 20);
+    /// assert_eq!(model.pieces().len(), 20);
+    /// assert_eq!(model.merges()[0], ("##e".to_string(), "##s".to_string()));
+    ///
+    /// // A word of the corpus, and one it never had
+    /// assert_eq!(model.split("newest").unwrap(), vec!["newest"]);
+    /// assert_eq!(model.split("lowest").unwrap(), vec!["low", "##est"]);
+    /// // A character that was never seen cannot be split
+    /// assert_eq!(model.split("lowz"), None);
+    /// ```
+    pub fn train(
+        kind: SubwordKind,
+        frequencies: &FrequencyTable,
+        min_count: usize,
+        vocab_size: usize,
+    ) -> Self {
+        // STEP 1: Split every word into its characters
+        let mut trainer = Trainer::default();
+        for (word, count) in frequencies.iter() {
+            if count < min_count || word.is_empty() || word.chars().any(char::is_whitespace) {
+                continue;
+            }
+            let symbols = initial_pieces(word)
+                .into_iter()
+                .map(|piece| trainer.intern(piece))
+                .collect();
+            trainer.words.push((symbols, count));
+        }
+        for index in 0..trainer.words.len() {
+            trainer.count_word(index, true);
+        }
+
+        // STEP 2: The characters are the first pieces (sorted, so the model does not
+        // depend on the order of the table)
+        let mut pieces = trainer.symbols.clone();
+        pieces.sort_unstable();
+        let mut known: HashSet<String> = pieces.iter().cloned().collect();
+
+        // STEP 3: Merge the best pair until there are enough pieces or nothing to merge
+        let mut merges = Vec::new();
+        while pieces.len() < vocab_size {
+            let Some(pair) = trainer.best_pair(kind) else {
+                break;
+            };
+            let (left, right) = (
+                trainer.symbols[pair.0 as usize].clone(),
+                trainer.symbols[pair.1 as usize].clone(),
+            );
+            let merged = trainer.merge(pair);
+            let merged = trainer.symbols[merged as usize].clone();
+            if known.insert(merged.clone()) {
+                pieces.push(merged);
+            }
+            merges.push((left, right));
+        }
+        if kind == SubwordKind::WordPiece {
+            merges.clear();
+        }
+        SubwordModel::from_parts(kind, pieces, merges)
+    }
+
+    /// Creates a model from its pieces and merges (as written by a manifest or a saved
+    /// tokenizer)
+    pub(crate) fn from_parts(
+        kind: SubwordKind,
+        pieces: Vec<String>,
+        merges: Vec<(String, String)>,
+    ) -> Self {
+        let known = pieces.iter().cloned().collect();
+        let ranks = merges
+            .iter()
+            .enumerate()
+            .map(|(rank, merge)| (merge.clone(), rank))
+            .collect();
+        SubwordModel {
+            kind,
+            pieces,
+            merges,
+            known,
+            ranks,
+        }
+    }
+
+    /// Returns how the model was learned
+    pub fn kind(&self) -> SubwordKind {
+        self.kind
+    }
+
+    /// Returns every piece, the characters first and then the learned pieces in order
+    pub fn pieces(&self) -> &[String] {
+        &self.pieces
+    }
+
+    /// Returns the merges of a BPE model in the order they are applied (empty for WordPiece)
+    pub fn merges(&self) -> &[(String, String)] {
+        &self.merges
+    }
+
+    /// Splits a word into pieces of the model
+    ///
+    /// # Returns
+    /// The pieces, the ones after the first starting with "##", or None if the word has a
+    /// part no piece covers
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::stats::FrequencyTable;
+    /// use tokenizer_rust::subword::{SubwordKind, SubwordModel};
+    ///
+    /// let mut frequencies = FrequencyTable::new();
+    /// for (word, count) in [("play", 6), ("played", 3), ("playing", 4), ("sing", 5), ("sings", 2)] {
+    ///     frequencies.add_strings(&vec![word; count]);
+    /// }
+    /// let model = SubwordModel::train(SubwordKind::WordPiece, &frequencies, 1, 30);
+    /// assert_eq!(model.split("playing").unwrap(), vec!["playing"]);
+    /// assert_eq!(model.split("singing").unwrap(), vec!["sing", "##ing"]);
+    /// assert_eq!(model.split("plays").unwrap(), vec!["play", "##s"]);
+    /// ```
+    pub fn split(&self, word: &str) -> Option<Vec<String>> {
+        match self.kind {
+            SubwordKind::Bpe => self.split_bpe(word),
+            SubwordKind::WordPiece => self.split_wordpiece(word),
+        }
+    }
+
+    /// Replays the merges on the characters of a word, the earliest learned merge first
+    fn split_bpe(&self, word: &str) -> Option<Vec<String>> {
+        let mut pieces = initial_pieces(word);
+        loop {
+            let best = pieces
+                .windows(2)
+                .filter_map(|pair| self.ranks.get(&(pair[0].clone(), pair[1].clone())))
+                .min();
+            let Some(&rank) = best else {
+                break;
+            };
+            let (left, right) = &self.merges[rank];
+            let mut merged = Vec::with_capacity(pieces.len());
+            let mut index = 0;
+            while index < pieces.len() {
+                if index + 1 < pieces.len() && &pieces[index] == left && &pieces[index + 1] == right
+                {
+                    merged.push(join(left, right));
+                    index += 2;
+                } else {
+                    merged.push(std::mem::take(&mut pieces[index]));
+                    index += 1;
+                }
+            }
+            pieces = merged;
+        }
+        pieces
+            .iter()
+            .all(|piece| self.known.contains(piece))
+            .then_some(pieces)
+    }
+
+    /// Takes the longest known piece at the start of what is left of a word, again and again
+    fn split_wordpiece(&self, word: &str) -> Option<Vec<String>> {
+        let mut pieces = Vec::new();
+        let mut start = 0;
+        while start < word.len() {
+            let prefix = if start == 0 { "" } else { CONTINUATION_PREFIX };
+            let piece = word[start..]
+                .char_indices()
+                .map(|(offset, character)| start + offset + character.len_utf8())
+                .rev()
+                .map(|end| (end, format!("{}{}", prefix, &word[start..end])))
+                .find(|(_, piece)| self.known.contains(piece));
+            let (end, piece) = piece?;
+            pieces.push(piece);
+            start = end;
+        }
+        Some(pieces)
+    }
+}
+
+/// The pieces of a word before any merge: its first character, then "##" and each of the
+/// others
+fn initial_pieces(word: &str) -> Vec<String> {
+    word.chars()
+        .enumerate()
+        .map(|(index, character)| match index {
+            0 => character.to_string(),
+            _ => format!("{}{}", CONTINUATION_PREFIX, character),
+        })
+        .collect()
+}
+
+/// Merges two neighbouring pieces ("low" and "##er" give "lower")
+fn join(left: &str, right: &str) -> String {
+    let right = right.strip_prefix(CONTINUATION_PREFIX).unwrap_or(right);
+    format!("{}{}", left, right)
+}
+
+/// The state of training: the words as symbol ids, and the counts of their pairs
+#[derive(Default)]
+struct Trainer {
+    /// The text of every symbol, by id
+    symbols: Vec<String>,
+    /// The id of every symbol
+    ids: HashMap<String, u32>,
+    /// Every word as its symbols, with its count
+    words: Vec<(Vec<u32>, usize)>,
+    /// How often every pair of neighbouring symbols occurs (weighted by the word counts)
+    pairs: HashMap<(u32, u32), usize>,
+    /// How often every symbol occurs, by id (weighted by the word counts)
+    symbol_counts: Vec<usize>,
+    /// The words a pair occurs in (indices may be repeated or out of date)
+    pair_words: HashMap<(u32, u32), Vec<usize>>,
+}
+
+impl Trainer {
+    /// Returns the id of a symbol, giving it one if it is new
+    fn intern(&mut self, symbol: String) -> u32 {
+        if let Some(&id) = self.ids.get(&symbol) {
+            return id;
+        }
+        let id = self.symbols.len() as u32;
+        self.ids.insert(symbol.clone(), id);
+        self.symbols.push(symbol);
+        self.symbol_counts.push(0);
+        id
+    }
+
+    /// Adds the pairs and symbols of one word to the counts, or removes them
+    fn count_word(&mut self, index: usize, add: bool) {
+        let (symbols, count) = &self.words[index];
+        for &symbol in symbols {
+            let total = &mut self.symbol_counts[symbol as usize];
+            *total = if add { *total + count } else { *total - count };
+        }
+        for pair in symbols.windows(2) {
+            let pair = (pair[0], pair[1]);
+            if add {
+                *self.pairs.entry(pair).or_insert(0) += count;
+                self.pair_words.entry(pair).or_default().push(index);
+            } else if let Some(total) = self.pairs.get_mut(&pair) {
+                *total -= count;
+                if *total == 0 {
+                    self.pairs.remove(&pair);
+                }
+            }
+        }
+    }
+
+    /// Returns the pair to merge next, or None if no word has two symbols left
+    ///
+    /// Ties go to the pair whose texts come first, so training is deterministic.
+    fn best_pair(&self, kind: SubwordKind) -> Option<(u32, u32)> {
+        let mut best: Option<((u32, u32), usize)> = None;
+        for (&pair, &count) in &self.pairs {
+            let better = match best {
+                None => true,
+                Some((best_pair, best_count)) => {
+                    let order = match kind {
+                        SubwordKind::Bpe => count.cmp(&best_count),
+                        // count / (left * right), compared without division
+                        SubwordKind::WordPiece => {
+                            let score = count as u128 * self.weight(best_pair);
+                            score.cmp(&(best_count as u128 * self.weight(pair)))
+                        }
+                    };
+                    order.then_with(|| self.texts(best_pair).cmp(&self.texts(pair)))
+                        == Ordering::Greater
+                }
+            };
+            if better {
+                best = Some((pair, count));
+            }
+        }
+        best.map(|(pair, _)| pair)
+    }
+
+    /// Returns the product of the counts of the two symbols of a pair
+    fn weight(&self, (left, right): (u32, u32)) -> u128 {
+        self.symbol_counts[left as usize] as u128 * self.symbol_counts[right as usize] as u128
+    }
+
+    /// Returns the texts of the two symbols of a pair
+    fn texts(&self, (left, right): (u32, u32)) -> (&str, &str) {
+        (&self.symbols[left as usize], &self.symbols[right as usize])
+    }
+
+    /// Merges a pair in every word it occurs in
+    ///
+    /// # Returns
+    /// The id of the merged symbol
+    fn merge(&mut self, pair: (u32, u32)) -> u32 {
+        let merged = join(
+            &self.symbols[pair.0 as usize],
+            &self.symbols[pair.1 as usize],
+        );
+        let merged = self.intern(merged);
+
+        let mut indices = self.pair_words.remove(&pair).unwrap_or_default();
+        indices.sort_unstable();
+        indices.dedup();
+        for index in indices {
+            let symbols = &self.words[index].0;
+            if !symbols
+                .windows(2)
+                .any(|window| (window[0], window[1]) == pair)
+            {
+                continue;
+            }
+            self.count_word(index, false);
+            let symbols = &mut self.words[index].0;
+            let mut result = Vec::with_capacity(symbols.len());
+            let mut position = 0;
+            while position < symbols.len() {
+                if position + 1 < symbols.len()
+                    && (symbols[position], symbols[position + 1]) == pair
+                {
+                    result.push(merged);
+                    position += 2;
+                } else {
+                    result.push(symbols[position]);
+                    position += 1;
+                }
+            }
+            *symbols = result;
+            self.count_word(index, true);
+        }
+        merged
+    }
+}
@@ -0,0 +1,142 @@
+// Feeding one tokenization pass to several consumers
+// Statistics, indexing and encoding often all need the same tokens. Instead of tokenizing
+// the text once per consumer, or cloning every token into each of them, a TokenTee hands
+// every token by reference to a list of callbacks, one after the other.
+
+use std::fmt;
+use std::io;
+
+use crate::token::Token;
+
+/// A consumer of tokens, see TokenTee::with_consumer()
+type Consumer<'a> = Box<dyn FnMut(&Token) + 'a>;
+
+/// Sends every token of one tokenization pass to several consumers
+///
+/// Consumers are closures that receive each token by reference, in order. They can borrow
+/// local state mutably (counters, a vocabulary, a vector of ids): the borrows end when the
+/// tee is dropped.
+#[derive(Default)]
+pub struct TokenTee<'a> {
+    /// The consumers, called in the order they were added
+    consumers: Vec<Consumer<'a>>,
+}
+
+impl<'a> TokenTee<'a> {
+    /// Creates a tee without consumers
+    pub fn new() -> Self {
+        TokenTee::default()
+    }
+
+    /// Adds a consumer that is called with every token
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::tee::TokenTee;
+    /// use tokenizer_rust::token::TokenKind;
+    /// use tokenizer_rust::vocab::Vocabulary;
+    ///
+    /// let tokenizer = Tokenizer::new(String::new());
+    /// let mut vocab = Vocabulary::new();
+    /// let mut words = 0;
+    ///
+    /// // One tokenization pass, two consumers
+    /// let count = TokenTee::new()
+    ///     .with_consumer(|token| {
+    ///         vocab.add_token(&token.text);
+    ///     })
+    ///     .with_consumer(|token| words += usize::from(token.kind == TokenKind::Word))
+    ///     .run(tokenizer.tokenize_text("the cat saw the dog."));
+    ///
+    /// assert_eq!(count, 6);
+    /// assert_eq!(words, 5);
+    /// assert_eq!(vocab.len(), 5);
+    /// ```
+    pub fn with_consumer<F: FnMut(&Token) + 'a>(mut self, consumer: F) -> Self {
+        self.consumers.push(Box::new(consumer));
+        self
+    }
+
+    /// Returns the number of consumers
+    pub fn len(&self) -> usize {
+        self.consumers.len()
+    }
+
+    /// Returns true if there are no consumers
+    pub fn is_empty(&self) -> bool {
+        self.consumers.is_empty()
+    }
+
+    /// Sends one token to every consumer
+    pub fn feed(&mut self, token: &Token) {
+        for consumer in &mut self.consumers {
+            consumer(token);
+        }
+    }
+
+    /// Sends every token of an iterator to every consumer
+    ///
+    /// # Arguments
+    /// * `tokens` - The tokens, for example from Tokenizer::tokenize_text()
+    ///
+    /// # Returns
+    /// The number of tokens sent
+    pub fn run<I: IntoIterator<Item = Token>>(&mut self, tokens: I) -> usize {
+        let mut count = 0;
+        for token in tokens {
+            self.feed(&token);
+            count += 1;
+        }
+        count
+    }
+
+    /// Sends every token of a streaming tokenizer to every consumer
+    ///
+    /// Stops at the first error, after the tokens before it were sent.
+    ///
+    /// # Arguments
+    /// * `tokens` - The tokens, for example from Tokenizer::tokenize_reader()
+    ///
+    /// # Returns
+    /// The number of tokens sent, or the first reading error
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::tee::TokenTee;
+    ///
+    /// let tokenizer = Tokenizer::new(String::new());
+    /// let mut texts = Vec::new();
+    /// let mut bytes = 0;
+    ///
+    /// let count = TokenTee::new()
+    ///     .with_consumer(|token| texts.push(token.text.clone()))
+    ///     .with_consumer(|token| bytes += token.end - token.start)
+    ///     .run_reader(tokenizer.tokenize_reader("Hello,\nworld!".as_bytes()))
+    ///     .unwrap();
+    ///
+    /// assert_eq!(count, 4);
+    /// assert_eq!(texts, vec!["Hello", ",", "world", "!"]);
+    /// assert_eq!(bytes, 12);
+    /// ```
+    pub fn run_reader<I: IntoIterator<Item = io::Result<Token>>>(
+        &mut self,
+        tokens: I,
+    ) -> io::Result<usize> {
+        let mut count = 0;
+        for token in tokens {
+            self.feed(&token?);
+            count += 1;
+        }
+        Ok(count)
+    }
+}
+
+impl fmt::Debug for TokenTee<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TokenTee")
+            .field("consumers", &self.consumers.len())
+            .finish()
+    }
+}
@@ -0,0 +1,404 @@
+// The Token type: one token together with where it came from in the original text
+
+use std::sync::Arc;
+
+use crate::structure::structure_break;
+
+/// The different kinds of tokens the tokenizer produces
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TokenKind {
+    /// A word like "Hello" or "don't" (letters, possibly mixed with digits, apostrophes and hyphens)
+    Word,
+    /// A token made only of digits like "2024"
+    Number,
+    /// A single punctuation or symbol character like "," or "!"
+    Punctuation,
+    /// A registered special token like "[CLS]" or "<s>" (see the special module)
+    Special,
+    /// A long code mixing letters and digits like an order number or an IBAN (see the identifiers module)
+    Identifier,
+    /// A single newline, only emitted when structure tokens are enabled (see the structure module)
+    LineBreak,
+    /// Two or more newlines (a blank line), only emitted when structure tokens are enabled
+    ParagraphBreak,
+}
+
+impl TokenKind {
+    /// Every kind, in declaration order
+    pub const ALL: [TokenKind; 7] = [
+        TokenKind::Word,
+        TokenKind::Number,
+        TokenKind::Punctuation,
+        TokenKind::Special,
+        TokenKind::Identifier,
+        TokenKind::LineBreak,
+        TokenKind::ParagraphBreak,
+    ];
+
+    /// Returns the name of the kind, as written in the JSON outputs ("word", "line_break")
+    pub fn name(&self) -> &'static str {
+        match self {
+            TokenKind::Word => "word",
+            TokenKind::Number => "number",
+            TokenKind::Punctuation => "punctuation",
+            TokenKind::Special => "special",
+            TokenKind::Identifier => "identifier",
+            TokenKind::LineBreak => "line_break",
+            TokenKind::ParagraphBreak => "paragraph_break",
+        }
+    }
+
+    /// Returns the kind with this name, as returned by name()
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::token::TokenKind;
+    ///
+    /// assert_eq!(TokenKind::from_name("punctuation"), Some(TokenKind::Punctuation));
+    /// assert_eq!(TokenKind::from_name(TokenKind::LineBreak.name()), Some(TokenKind::LineBreak));
+    /// assert_eq!(TokenKind::from_name("emoji"), None);
+    /// ```
+    pub fn from_name(name: &str) -> Option<TokenKind> {
+        TokenKind::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+
+    /// Works out the kind of a token from its text
+    ///
+    /// # Arguments
+    /// * `text` - The token text
+    ///
+    /// # Returns
+    /// LineBreak or ParagraphBreak for whitespace containing newlines, Number if every
+    /// character is a digit, Word if it contains a letter or digit, Punctuation otherwise
+    /// (an empty text is Punctuation too)
+    pub fn of(text: &str) -> Self {
+        if let Some(line_break) = structure_break(text) {
+            if line_break == "\n" {
+                TokenKind::LineBreak
+            } else {
+                TokenKind::ParagraphBreak
+            }
+        } else if !text.is_empty() && text.chars().all(|c| c.is_numeric()) {
+            TokenKind::Number
+        } else if text.chars().any(|c| c.is_alphanumeric()) {
+            TokenKind::Word
+        } else {
+            TokenKind::Punctuation
+        }
+    }
+}
+
+/// A single token with its kind and its position in the original text
+///
+/// `start` and `end` are byte offsets, so `&original[token.start..token.end]`
+/// gives back exactly the token text.
+///
+/// With the `serde` feature, tokens (like encodings, vocabularies and the statistics types)
+/// can be written and read with any serde format:
+/// ```
+/// # #[cfg(feature = "serde")]
+/// # {
+/// use tokenizer_rust::token::Token;
+///
+/// let token = Token::new("Hello", 0);
+/// let json = serde_json::to_string(&token).unwrap();
+/// assert_eq!(json, r#"{"text":"Hello","kind":"Word","start":0,"end":5}"#);
+/// assert_eq!(serde_json::from_str::<Token>(&json).unwrap(), token);
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Token {
+    /// The token text
+    pub text: String,
+    /// What kind of token this is
+    pub kind: TokenKind,
+    /// Byte offset where the token starts in the original text
+    pub start: usize,
+    /// Byte offset just after the token ends in the original text
+    pub end: usize,
+}
+
+impl Token {
+    /// Creates a token from its text and its starting byte offset
+    ///
+    /// The kind and the end offset are computed from the text.
+    ///
+    /// # Arguments
+    /// * `text` - The token text
+    /// * `start` - Byte offset of the token in the original text
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::token::{Token, TokenKind};
+    ///
+    /// let token = Token::new("world", 7);
+    /// assert_eq!(token.kind, TokenKind::Word);
+    /// assert_eq!(token.end, 12);
+    /// ```
+    pub fn new(text: &str, start: usize) -> Self {
+        Token {
+            text: text.to_string(),
+            kind: TokenKind::of(text),
+            start,
+            end: start + text.len(),
+        }
+    }
+
+    /// Returns the orthographic shape of the token: "X" for an uppercase letter, "x" for a
+    /// lowercase one, "d" for a digit, other characters as they are, and runs of the same
+    /// shape cut after 4 characters
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::token::Token;
+    ///
+    /// assert_eq!(Token::new("Apple", 0).shape(), "Xxxxx");
+    /// assert_eq!(Token::new("Internationalization", 0).shape(), "Xxxxx");
+    /// assert_eq!(Token::new("2024", 0).shape(), "dddd");
+    /// assert_eq!(Token::new("e.g", 0).shape(), "x.x");
+    /// assert_eq!(Token::new("COVID-19", 0).shape(), "XXXX-dd");
+    /// ```
+    pub fn shape(&self) -> String {
+        let mut shape = String::with_capacity(self.text.len());
+        let mut last = None;
+        let mut run = 0;
+        for character in self.text.chars() {
+            let class = if character.is_numeric() {
+                'd'
+            } else if character.is_uppercase() {
+                'X'
+            } else if character.is_alphabetic() {
+                'x'
+            } else {
+                character
+            };
+            run = if last == Some(class) { run + 1 } else { 1 };
+            last = Some(class);
+            if run <= 4 {
+                shape.push(class);
+            }
+        }
+        shape
+    }
+
+    /// Returns the orthographic features of the token (shape, case, digits), as used by
+    /// classical NER feature extractors
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::token::Token;
+    ///
+    /// let features = Token::new("Paris", 0).features();
+    /// assert_eq!(features.shape, "Xxxxx");
+    /// assert!(features.is_title && features.is_alpha && !features.is_upper);
+    ///
+    /// assert!(Token::new("NASA", 0).features().is_upper);
+    /// assert!(Token::new("10,000", 0).features().like_num);
+    /// assert!(Token::new("twelve", 0).features().like_num);
+    /// assert!(!Token::new("3rd", 0).features().is_digit);
+    /// ```
+    pub fn features(&self) -> TokenFeatures {
+        TokenFeatures {
+            shape: self.shape(),
+            is_title: is_title(&self.text),
+            is_upper: has_case(&self.text, char::is_uppercase),
+            is_lower: has_case(&self.text, char::is_lowercase),
+            is_digit: !self.text.is_empty() && self.text.chars().all(char::is_numeric),
+            is_alpha: !self.text.is_empty() && self.text.chars().all(char::is_alphabetic),
+            is_punct: !self.text.is_empty()
+                && self
+                    .text
+                    .chars()
+                    .all(|c| !c.is_alphanumeric() && !c.is_whitespace()),
+            like_num: like_num(&self.text),
+        }
+    }
+}
+
+/// The orthographic features of a token, computed by Token::features()
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TokenFeatures {
+    /// The shape of the token ("Xxxxx", "dddd", "x.x"), see Token::shape()
+    pub shape: String,
+    /// Every word starts with an uppercase letter followed by lowercase ones ("Paris", "New-York")
+    pub is_title: bool,
+    /// It has letters and they are all uppercase ("NASA", "COVID-19")
+    pub is_upper: bool,
+    /// It has letters and they are all lowercase
+    pub is_lower: bool,
+    /// Only digits
+    pub is_digit: bool,
+    /// Only letters
+    pub is_alpha: bool,
+    /// Only punctuation or symbols
+    pub is_punct: bool,
+    /// Looks like a number: digits with separators ("10,000", "3.14", "-5"), a fraction
+    /// ("1/2") or an English number word ("twelve", "million")
+    pub like_num: bool,
+}
+
+/// English number words, for like_num()
+const NUMBER_WORDS: &[&str] = &[
+    "zero",
+    "one",
+    "two",
+    "three",
+    "four",
+    "five",
+    "six",
+    "seven",
+    "eight",
+    "nine",
+    "ten",
+    "eleven",
+    "twelve",
+    "thirteen",
+    "fourteen",
+    "fifteen",
+    "sixteen",
+    "seventeen",
+    "eighteen",
+    "nineteen",
+    "twenty",
+    "thirty",
+    "forty",
+    "fifty",
+    "sixty",
+    "seventy",
+    "eighty",
+    "ninety",
+    "hundred",
+    "thousand",
+    "million",
+    "billion",
+    "trillion",
+];
+
+/// Returns true if the text has a cased letter and all its cased letters pass `case`
+fn has_case(text: &str, case: fn(char) -> bool) -> bool {
+    let mut cased = text
+        .chars()
+        .filter(|c| c.is_uppercase() || c.is_lowercase())
+        .peekable();
+    cased.peek().is_some() && cased.all(case)
+}
+
+/// Returns true if every run of letters starts with an uppercase letter and goes on in
+/// lowercase (like Python's str.istitle())
+fn is_title(text: &str) -> bool {
+    let mut any_cased = false;
+    let mut previous_cased = false;
+    for character in text.chars() {
+        if character.is_uppercase() {
+            if previous_cased {
+                return false;
+            }
+            previous_cased = true;
+            any_cased = true;
+        } else if character.is_lowercase() {
+            if !previous_cased {
+                return false;
+            }
+            previous_cased = true;
+        } else {
+            previous_cased = false;
+        }
+    }
+    any_cased
+}
+
+/// Returns true if the text looks like a number (see TokenFeatures::like_num)
+fn like_num(text: &str) -> bool {
+    let text = text.strip_prefix(['+', '-', '±', '~']).unwrap_or(text);
+    let is_digits = |part: &str| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit());
+
+    // Digits with thousands and decimal separators
+    let digits: String = text.chars().filter(|&c| c != ',' && c != '.').collect();
+    if is_digits(&digits) && text.starts_with(|c: char| c.is_ascii_digit()) {
+        return true;
+    }
+
+    // A fraction
+    if let Some((numerator, denominator)) = text.split_once('/') {
+        if is_digits(numerator) && is_digits(denominator) {
+            return true;
+        }
+    }
+
+    NUMBER_WORDS.contains(&text.to_lowercase().as_str())
+}
+
+/// A token of one of several documents tokenized together, with the index of its document
+///
+/// Produced by Tokenizer::tokenize_documents() and Tokenizer::tokenize_readers(): the
+/// offsets of the token are in its own document, and `document` is the position of that
+/// document in the input (the index of the text, reader or file).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DocumentToken {
+    /// Index of the document the token comes from
+    pub document: usize,
+    /// The token, with offsets in its document
+    pub token: Token,
+}
+
+/// A token that borrows its text instead of owning it
+///
+/// Produced when tokenizing memory-mapped files, where copying every token into
+/// its own String would cost more than the tokenization itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TokenRef<'a> {
+    /// The token text, borrowed from the tokenized text
+    pub text: &'a str,
+    /// What kind of token this is
+    pub kind: TokenKind,
+    /// Byte offset where the token starts in the original text
+    pub start: usize,
+    /// Byte offset just after the token ends in the original text
+    pub end: usize,
+}
+
+impl TokenRef<'_> {
+    /// Copies the token into an owned Token
+    pub fn to_token(&self) -> Token {
+        Token {
+            text: self.text.to_string(),
+            kind: self.kind,
+            start: self.start,
+            end: self.end,
+        }
+    }
+}
+
+/// A token whose text is shared with every other occurrence of the same token
+///
+/// Produced by Tokenizer::tokenize_interned(): all the "the" tokens of a corpus point to
+/// one single string, so cloning or storing them never allocates.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InternedToken {
+    /// The token text, shared through the interner
+    pub text: Arc<str>,
+    /// What kind of token this is
+    pub kind: TokenKind,
+    /// Byte offset where the token starts in the original text
+    pub start: usize,
+    /// Byte offset just after the token ends in the original text
+    pub end: usize,
+}
+
+impl InternedToken {
+    /// Copies the token into an owned Token
+    pub fn to_token(&self) -> Token {
+        Token {
+            text: self.text.to_string(),
+            kind: self.kind,
+            start: self.start,
+            end: self.end,
+        }
+    }
+}
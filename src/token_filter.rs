@@ -0,0 +1,208 @@
+// Post-tokenization filters
+// A TokenFilter takes the tokens of a text and returns the ones to keep, possibly changed:
+// stopword removal drops tokens, normalizers rewrite their text. Every filter keeps the
+// byte offsets of the tokens it returns, so they still point at the original words.
+// The filters of the crate also describe their settings as a manifest step, so a pipeline
+// of them can be written to a manifest and rebuilt from it.
+
+use crate::language::ElisionFilter;
+use crate::lemma::Lemmatizer;
+use crate::manifest::ManifestStep;
+use crate::ptb::PennTreebank;
+use crate::stem::PorterStemmer;
+use crate::stopwords::StopwordFilter;
+use crate::token::{Token, TokenKind};
+use crate::truncate::LengthLimits;
+
+/// A step applied to the tokens of a text after tokenization
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::Tokenizer;
+/// use tokenizer_rust::token::{Token, TokenKind};
+/// use tokenizer_rust::token_filter::TokenFilter;
+///
+/// // A filter that drops punctuation
+/// struct NoPunctuation;
+///
+/// impl TokenFilter for NoPunctuation {
+///     fn apply(&self, tokens: Vec<Token>) -> Vec<Token> {
+///         tokens
+///             .into_iter()
+///             .filter(|token| token.kind != TokenKind::Punctuation)
+///             .collect()
+///     }
+/// }
+///
+/// let tokens = NoPunctuation.apply(Tokenizer::new(String::new()).tokenize_text("Hi, you!"));
+/// let texts: Vec<&str> = tokens.iter().map(|token| token.text.as_str()).collect();
+/// assert_eq!(texts, vec!["Hi", "you"]);
+/// ```
+pub trait TokenFilter {
+    /// Filters the tokens of one text
+    ///
+    /// # Returns
+    /// The tokens that are kept, in their original order
+    fn apply(&self, tokens: Vec<Token>) -> Vec<Token>;
+
+    /// Describes the filter with all of its settings, for manifests and saved tokenizers
+    ///
+    /// # Returns
+    /// The step, or None (the default) for a filter that cannot be written down: a
+    /// manifest with such a filter is not reproducible
+    fn describe(&self) -> Option<ManifestStep> {
+        None
+    }
+}
+
+impl<T: TokenFilter + ?Sized> TokenFilter for &T {
+    fn apply(&self, tokens: Vec<Token>) -> Vec<Token> {
+        (**self).apply(tokens)
+    }
+
+    fn describe(&self) -> Option<ManifestStep> {
+        (**self).describe()
+    }
+}
+
+impl<T: TokenFilter + ?Sized> TokenFilter for Box<T> {
+    fn apply(&self, tokens: Vec<Token>) -> Vec<Token> {
+        (**self).apply(tokens)
+    }
+
+    fn describe(&self) -> Option<ManifestStep> {
+        (**self).describe()
+    }
+}
+
+/// Several filters applied one after the other
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::Tokenizer;
+/// use tokenizer_rust::stem::PorterStemmer;
+/// use tokenizer_rust::stopwords::StopwordFilter;
+/// use tokenizer_rust::token_filter::{FilterPipeline, TokenFilter};
+///
+/// // Index terms: stopwords dropped, the other words stemmed
+/// let pipeline = FilterPipeline::new()
+///     .with_filter(StopwordFilter::english())
+///     .with_filter(PorterStemmer::new());
+/// let tokens = pipeline.apply(Tokenizer::new(String::new()).tokenize_text("The runners are running"));
+/// let terms: Vec<&str> = tokens.iter().map(|token| token.text.as_str()).collect();
+/// assert_eq!(terms, vec!["runner", "run"]);
+/// ```
+#[derive(Default)]
+pub struct FilterPipeline {
+    /// The filters, in the order they are applied
+    filters: Vec<Box<dyn TokenFilter + Send + Sync>>,
+}
+
+impl FilterPipeline {
+    /// Creates a pipeline without any filter (it keeps every token)
+    pub fn new() -> Self {
+        FilterPipeline::default()
+    }
+
+    /// Adds a filter at the end of the pipeline
+    pub fn with_filter<F: TokenFilter + Send + Sync + 'static>(mut self, filter: F) -> Self {
+        self.filters.push(Box::new(filter));
+        self
+    }
+
+    /// Returns the number of filters
+    pub fn len(&self) -> usize {
+        self.filters.len()
+    }
+
+    /// Returns true if the pipeline has no filter
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+
+    /// Describes every filter, in order, with its position in the pipeline as the first
+    /// line (a "custom_filter" step for a filter that cannot describe itself)
+    pub fn steps(&self) -> Vec<ManifestStep> {
+        self.filters
+            .iter()
+            .enumerate()
+            .map(|(position, filter)| {
+                let described = filter
+                    .describe()
+                    .unwrap_or_else(|| ManifestStep::new("custom_filter"));
+                let mut step = ManifestStep::new(&described.name);
+                step.push("position", position);
+                step.lines.extend(described.lines);
+                step
+            })
+            .collect()
+    }
+
+    /// Rebuilds a pipeline from the steps written by steps()
+    ///
+    /// The positions must increase, but may skip the custom filters that were left out.
+    ///
+    /// # Returns
+    /// The pipeline, or None if a step is a custom filter or cannot be read
+    pub(crate) fn from_steps(steps: &[&ManifestStep]) -> Option<Self> {
+        let mut filters: Vec<Box<dyn TokenFilter + Send + Sync>> = Vec::new();
+        let mut next_position = 0;
+        for step in steps {
+            let position: usize = step.value("position")?.parse().ok()?;
+            if position < next_position {
+                return None;
+            }
+            next_position = position + 1;
+            filters.push(match step.name.as_str() {
+                "lowercase" => Box::new(Lowercase),
+                "stopwords" => Box::new(StopwordFilter::from_step(step)?),
+                "porter_stemmer" => Box::new(PorterStemmer::new()),
+                "lemmatizer" => Box::new(Lemmatizer::from_step(step)?),
+                "length_limits" => Box::new(LengthLimits::from_step(step)?),
+                "elision" => Box::new(ElisionFilter::from_step(step)?),
+                "penn_treebank" => Box::new(PennTreebank),
+                _ => return None,
+            });
+        }
+        Some(FilterPipeline { filters })
+    }
+}
+
+impl TokenFilter for FilterPipeline {
+    fn apply(&self, tokens: Vec<Token>) -> Vec<Token> {
+        self.filters
+            .iter()
+            .fold(tokens, |tokens, filter| filter.apply(tokens))
+    }
+}
+
+/// Lowercases the text of every token except the special ones, keeping the offsets
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::Tokenizer;
+/// use tokenizer_rust::token_filter::{Lowercase, TokenFilter};
+///
+/// let text = "Hello NASA";
+/// let tokens = Lowercase.apply(Tokenizer::new(String::new()).tokenize_text(text));
+/// let texts: Vec<&str> = tokens.iter().map(|token| token.text.as_str()).collect();
+/// assert_eq!(texts, vec!["hello", "nasa"]);
+/// assert_eq!(&text[tokens[1].start..tokens[1].end], "NASA");
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Lowercase;
+
+impl TokenFilter for Lowercase {
+    fn apply(&self, mut tokens: Vec<Token>) -> Vec<Token> {
+        for token in &mut tokens {
+            if token.kind != TokenKind::Special {
+                token.text = token.text.to_lowercase();
+            }
+        }
+        tokens
+    }
+
+    fn describe(&self) -> Option<ManifestStep> {
+        Some(ManifestStep::new("lowercase"))
+    }
+}
@@ -1,3 +1,38 @@
+use std::io::Read;
+use std::sync::OnceLock;
+
+#[cfg(feature = "tokio")]
+use crate::async_reader::AsyncTokenStream;
+use crate::chunk::{self, Chunk};
+use crate::counts::{self, Counts};
+use crate::encode_options::{window_starts, TruncationStrategy};
+use crate::encoder::{self, EncoderConfig, TemplatePiece, UnknownPolicy};
+use crate::encoding::{EncodedPrefix, Encoding};
+use crate::filter::SentenceFilter;
+use crate::identifiers::{self, IdentifierDetector};
+use crate::input::InputSource;
+use crate::intern::Interner;
+use crate::manifest::{Manifest, RULES_VERSION};
+#[cfg(feature = "mmap")]
+use crate::mmap::MappedText;
+use crate::output::{OutputRecord, OutputWriter};
+use crate::patch::Patch;
+use crate::sentences::SentenceSplitter;
+use crate::special::{self, SpecialTokens};
+use crate::stats::TokenStats;
+use crate::stream::{self, DocumentTokenReader, TokenReader};
+use crate::structure;
+use crate::subword;
+use crate::token::{DocumentToken, InternedToken, Token, TokenKind, TokenRef};
+use crate::token_filter::{FilterPipeline, TokenFilter};
+use crate::vocab::Vocabulary;
+
+/// How many bytes of a large text count_bounds() tokenizes (texts up to this size are counted exactly)
+const COUNT_BOUNDS_SAMPLE_BYTES: usize = 64 * 1024;
+
+/// How many windows the sample of count_bounds() is spread over
+const COUNT_BOUNDS_WINDOWS: usize = 16;
+
 /// The Tokenizer struct is responsible for breaking text into tokens
 /// and reconstructing text from tokens.
 /// A token is a meaningful unit of text - typically a word or punctuation mark
@@ -5,6 +40,26 @@ pub struct Tokenizer {
     /// The original text that will be tokenized
     /// We store this to preserve the exact original for comparison, to make sure we got the tokenizer process right
     text: String,
+    /// The vocabulary used by encode() and decode() to turn tokens into ids and back
+    vocab: Vocabulary,
+    /// Special tokens like [CLS] or <s> that are never split
+    special_tokens: SpecialTokens,
+    /// Tokens added at runtime with add_tokens(), also never split (but not special)
+    added_tokens: SpecialTokens,
+    /// Optional detector that keeps order numbers, IBANs, etc. in one piece
+    identifiers: Option<IdentifierDetector>,
+    /// Settings for encode() and decode() (unknown-token policy, ...)
+    encoder: EncoderConfig,
+    /// How many tokens a text is expected to have (None = one per 5 bytes, or estimated)
+    capacity_hint: Option<usize>,
+    /// If true and there is no capacity hint, estimate_token_count() sizes result vectors
+    estimated_capacity: bool,
+    /// If true, newlines come out as LineBreak / ParagraphBreak tokens
+    structure_tokens: bool,
+    /// Filters applied by tokenize_filtered() (stopwords, stemming, lowercasing, ...)
+    filters: FilterPipeline,
+    /// Optional filter that keeps or drops the sentences of a corpus by their tokens
+    sentence_filter: Option<SentenceFilter>,
 }
 
 impl Tokenizer {
@@ -18,172 +73,2673 @@ impl Tokenizer {
     ///
     /// # Example
     /// ```
+    /// # use tokenizer_rust::Tokenizer;
     /// let tokenizer = Tokenizer::new("Hello, world!".to_string());
     /// ```
     pub fn new(text: String) -> Self {
-        Tokenizer { text }
+        Tokenizer {
+            text,
+            vocab: Vocabulary::new(),
+            special_tokens: SpecialTokens::new(),
+            added_tokens: SpecialTokens::new(),
+            identifiers: None,
+            encoder: EncoderConfig::new(),
+            capacity_hint: None,
+            estimated_capacity: false,
+            structure_tokens: false,
+            filters: FilterPipeline::new(),
+            sentence_filter: None,
+        }
     }
 
-    /// Tokenizes the stored text into a vector of token strings
+    /// Sets the vocabulary used by encode() and decode()
     ///
-    /// This function:
-    /// 1. Splits the text by whitespace to get word-like units
-    /// 2. Separates punctuation from words
-    /// 3. Returns all tokens as a Vec<String>
+    /// # Arguments
+    /// * `vocab` - The vocabulary to use (takes ownership)
     ///
     /// # Returns
-    /// A vector of tokens (words and punctuation as separate entries)
+    /// The same Tokenizer, now using the given vocabulary
     ///
     /// # Example
     /// ```
-    /// let tokenizer = Tokenizer::new("Hello, world!".to_string());
-    /// let tokens = tokenizer.tokenize();
-    /// // tokens will be: ["Hello", ",", "world", "!"]
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::vocab::Vocabulary;
+    ///
+    /// let tokens = Tokenizer::new("Hello, world!".to_string()).tokenize();
+    /// let tokenizer = Tokenizer::new("Hello, world!".to_string())
+    ///     .with_vocabulary(Vocabulary::from_tokens(&tokens));
     /// ```
-    pub fn tokenize(&self) -> Vec<String> {
-        // Create a mutable vector to store our tokens
-        let mut tokens: Vec<String> = Vec::new();
+    pub fn with_vocabulary(mut self, vocab: Vocabulary) -> Self {
+        self.vocab = vocab;
+        self.reserve_tokens();
+        self
+    }
 
-        // Split the text by whitespace using split_whitespace()
-        // This handles multiple spaces, tabs, newlines, etc. automatically
-        for word_unit in self.text.split_whitespace() {
-            // For each "word" (which might contain punctuation), we need to separate punctuation from the actual word characters
+    /// Sets the special tokens that are never split by tokenization
+    ///
+    /// Every special token is also added to the vocabulary (if it isn't there yet),
+    /// so it always maps to one fixed id.
+    ///
+    /// # Arguments
+    /// * `special_tokens` - The special token registry (takes ownership)
+    ///
+    /// # Returns
+    /// The same Tokenizer, now recognizing the special tokens
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::special::SpecialTokens;
+    ///
+    /// let tokenizer = Tokenizer::new("<s>Hello!</s>".to_string())
+    ///     .with_special_tokens(SpecialTokens::roberta());
+    /// assert_eq!(tokenizer.tokenize(), vec!["<s>", "Hello", "!", "</s>"]);
+    /// ```
+    pub fn with_special_tokens(mut self, special_tokens: SpecialTokens) -> Self {
+        self.special_tokens = special_tokens;
+        self.reserve_tokens();
+        self
+    }
 
-            // We'll process the word character by character
-            let mut current_token = String::new();
+    /// Returns the special tokens recognized by this Tokenizer
+    pub fn special_tokens(&self) -> &SpecialTokens {
+        &self.special_tokens
+    }
 
-            for character in word_unit.chars() {
-                // Check if this character is alphanumeric (letter or digit)
-                if character.is_alphanumeric() || character == '\'' || character == '-' {
-                    // These characters are part of words, so add them to current token (apostrophes and hyphens are often part of words like "don't")
-                    current_token.push(character);
-                } else {
-                    // This character is punctuation
-                    // First, if we've been building a word token, save it
-                    if !current_token.is_empty() {
-                        tokens.push(current_token.clone());
-                        current_token.clear();
-                    }
+    /// Adds new tokens to an already configured Tokenizer
+    ///
+    /// This is useful after a vocabulary has been loaded: domain terms or markers like
+    /// "<tool_call>" get new ids (after the existing ones) and are guaranteed to come out of
+    /// tokenization as single tokens. Unlike special tokens, they are kept by decode_skip_special().
+    ///
+    /// # Arguments
+    /// * `tokens` - The tokens to add
+    ///
+    /// # Returns
+    /// The number of tokens that were not in the vocabulary before
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    ///
+    /// let mut tokenizer = Tokenizer::new("Ask <tool_call> about MyBrandName-X".to_string());
+    /// let added = tokenizer.add_tokens(["<tool_call>", "MyBrandName"]);
+    /// assert_eq!(added, 2);
+    /// assert_eq!(tokenizer.tokenize(), vec!["Ask", "<tool_call>", "about", "MyBrandName", "-X"]);
+    /// ```
+    pub fn add_tokens<I, S>(&mut self, tokens: I) -> usize
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut new_tokens = 0;
 
-                    // Then save the punctuation as its own token
-                    tokens.push(character.to_string());
-                }
+        for token in tokens {
+            let token = token.as_ref();
+            if token.is_empty() {
+                continue;
             }
 
-            // After processing all characters in this word unit, if there's still a token being built, save it
-            if !current_token.is_empty() {
-                tokens.push(current_token);
+            if !self.vocab.contains(token) {
+                new_tokens += 1;
             }
+            self.vocab.add_token(token);
+            self.added_tokens.register(token);
         }
 
-        // Return the complete list of tokens
-        tokens
+        new_tokens
     }
 
-    /// Reconstructs the original text from a list of tokens
+    /// Returns the tokens added with add_tokens(), in the order they were added
+    pub fn added_tokens(&self) -> impl Iterator<Item = &str> {
+        self.added_tokens.iter()
+    }
+
+    /// Returns the registries of the tokens that are never split (special and added tokens)
+    pub(crate) fn protected_tokens(&self) -> [&SpecialTokens; 2] {
+        [&self.special_tokens, &self.added_tokens]
+    }
+
+    /// Turns on identifier detection (order numbers, IBANs, tracking codes)
     ///
-    /// This function uses intelligent spacing rules:
-    /// - Punctuation like . , ! ? ; : ) ] } gets NO space before it
-    /// - Opening brackets ( [ { get NO space after them
-    /// - Other words are separated by spaces
+    /// Detected identifiers come out as single tokens of kind `TokenKind::Identifier`.
     ///
     /// # Arguments
-    /// * `tokens` - A vector of token strings to recombine
+    /// * `detector` - The identifier detector to use (takes ownership)
     ///
     /// # Returns
-    /// A reconstructed string from the tokens
+    /// The same Tokenizer, now detecting identifiers
     ///
     /// # Example
     /// ```
-    /// let tokens = vec!["Hello", ",", "world", "!"];
-    /// let reconstructed = tokenizer.detokenize(&tokens);
-    /// // Result: "Hello, world!"
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::identifiers::IdentifierDetector;
+    ///
+    /// let tokenizer = Tokenizer::new("IBAN: DE89 3704 0044 0532 0130 00, thanks".to_string())
+    ///     .with_identifiers(IdentifierDetector::new());
+    /// assert_eq!(tokenizer.tokenize(), vec!["IBAN", ":", "DE89 3704 0044 0532 0130 00", ",", "thanks"]);
     /// ```
-    pub fn detokenize(&self, tokens: &[String]) -> String {
-        // If there are no tokens, return an empty string
-        if tokens.is_empty() {
-            return String::new();
+    pub fn with_identifiers(mut self, detector: IdentifierDetector) -> Self {
+        self.identifiers = Some(detector);
+        self
+    }
+
+    /// Returns the identifier detector, if identifier detection is enabled
+    pub fn identifiers(&self) -> Option<&IdentifierDetector> {
+        self.identifiers.as_ref()
+    }
+
+    /// Sets the token filters applied by tokenize_filtered()
+    ///
+    /// The filters are part of the configuration (they are saved by save() and written to
+    /// the Manifest), but tokenize_text() and encode() still give the tokens of the text
+    /// itself: only tokenize_filtered() drops or rewrites them.
+    ///
+    /// # Arguments
+    /// * `filters` - The filters, in the order they are applied (takes ownership)
+    ///
+    /// # Returns
+    /// The same Tokenizer, now carrying the filters
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::stem::PorterStemmer;
+    /// use tokenizer_rust::stopwords::StopwordFilter;
+    /// use tokenizer_rust::token_filter::{FilterPipeline, Lowercase};
+    ///
+    /// let tokenizer = Tokenizer::new(String::new()).with_filters(
+    ///     FilterPipeline::new()
+    ///         .with_filter(Lowercase)
+    ///         .with_filter(StopwordFilter::english())
+    ///         .with_filter(PorterStemmer::new()),
+    /// );
+    /// let tokens = tokenizer.tokenize_filtered("The Runners are running");
+    /// let terms: Vec<&str> = tokens.iter().map(|token| token.text.as_str()).collect();
+    /// assert_eq!(terms, vec!["runner", "run"]);
+    /// assert_eq!(tokenizer.tokenize_text("The Runners are running").len(), 4);
+    /// ```
+    pub fn with_filters(mut self, filters: FilterPipeline) -> Self {
+        self.filters = filters;
+        self
+    }
+
+    /// Returns the token filters applied by tokenize_filtered() (empty by default)
+    pub fn filters(&self) -> &FilterPipeline {
+        &self.filters
+    }
+
+    /// Sets the filter that keeps or drops sentences by their tokens
+    ///
+    /// Like the token filters, it is part of the saved configuration. Corpus preprocessing
+    /// (see CorpusConfig) uses it when no filter of its own is set.
+    ///
+    /// # Arguments
+    /// * `filter` - The sentence filter (takes ownership)
+    ///
+    /// # Returns
+    /// The same Tokenizer, now carrying the sentence filter
+    pub fn with_sentence_filter(mut self, filter: SentenceFilter) -> Self {
+        self.sentence_filter = Some(filter);
+        self
+    }
+
+    /// Returns the sentence filter, if one is set
+    pub fn sentence_filter(&self) -> Option<&SentenceFilter> {
+        self.sentence_filter.as_ref()
+    }
+
+    /// Sets the configuration used by encode() and decode()
+    ///
+    /// Tokens the configuration relies on (the unknown token, or the byte pieces for
+    /// byte fallback) are added to the vocabulary if they aren't there yet.
+    ///
+    /// # Arguments
+    /// * `config` - The encoder configuration (takes ownership)
+    ///
+    /// # Returns
+    /// The same Tokenizer, now using the configuration
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::encoder::{EncoderConfig, UnknownPolicy};
+    /// use tokenizer_rust::vocab::Vocabulary;
+    ///
+    /// let vocab = Vocabulary::from_tokens(&["I".to_string(), "love".to_string()]);
+    /// let tokenizer = Tokenizer::new("I love crème brûlée".to_string())
+    ///     .with_vocabulary(vocab)
+    ///     .with_encoder_config(EncoderConfig::new().with_unknown_policy(UnknownPolicy::ByteFallback));
+    /// let encoding = tokenizer.encode();
+    /// // the unknown words are encoded byte by byte, so nothing is lost
+    /// assert_eq!(tokenizer.decode(&encoding.ids), "I love crème brûlée");
+    /// ```
+    pub fn with_encoder_config(mut self, config: EncoderConfig) -> Self {
+        self.encoder = config;
+        self.reserve_tokens();
+        self
+    }
+
+    /// Returns the configuration used by encode() and decode()
+    pub fn encoder_config(&self) -> &EncoderConfig {
+        &self.encoder
+    }
+
+    /// Keeps the layout of the text: newlines come out as structure tokens
+    ///
+    /// A newline between two tokens becomes a `TokenKind::LineBreak` token and a blank line
+    /// a `TokenKind::ParagraphBreak` token. Their text is the whitespace they stand for
+    /// (from the first to the last newline), and detokenize() writes them back as "\n"
+    /// and "\n\n", so paragraphs and lines survive a round trip.
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::token::TokenKind;
+    ///
+    /// let text = "Dear Ann,\n\nThanks for the book!\nBye.";
+    /// let tokenizer = Tokenizer::new(text.to_string()).with_structure_tokens(true);
+    /// let tokens = tokenizer.tokenize_text(text);
+    /// assert_eq!(tokens[3].kind, TokenKind::ParagraphBreak);
+    /// assert_eq!(tokens[9].kind, TokenKind::LineBreak);
+    /// assert_eq!(tokenizer.detokenize(&tokenizer.tokenize()), text);
+    /// ```
+    pub fn with_structure_tokens(mut self, enabled: bool) -> Self {
+        self.structure_tokens = enabled;
+        self
+    }
+
+    /// Returns true if newlines come out as LineBreak / ParagraphBreak tokens
+    pub fn structure_tokens(&self) -> bool {
+        self.structure_tokens
+    }
+
+    /// Tells the Tokenizer how many tokens a text usually has
+    ///
+    /// Result vectors are allocated with this capacity up front. Without a hint they get
+    /// room for one token per 5 bytes of text (the density of English prose), or the result
+    /// of estimate_token_count() with with_estimated_capacity(); a hint is better for known
+    /// workloads (short queries, log lines, fixed-size records, ...).
+    ///
+    /// # Arguments
+    /// * `tokens` - The expected number of tokens per text
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    ///
+    /// // Search queries are short
+    /// let tokenizer = Tokenizer::new(String::new()).with_capacity_hint(8);
+    /// assert_eq!(tokenizer.tokenize_text("cheap flights to Lisbon").len(), 4);
+    /// ```
+    pub fn with_capacity_hint(mut self, tokens: usize) -> Self {
+        self.capacity_hint = Some(tokens);
+        self
+    }
+
+    /// Returns the number of tokens per text given to with_capacity_hint(), if any
+    pub fn capacity_hint(&self) -> Option<usize> {
+        self.capacity_hint
+    }
+
+    /// Sizes result vectors with estimate_token_count() when there is no capacity hint
+    ///
+    /// The estimate reads up to 4 KiB of every text before it is tokenized, which pays off
+    /// when texts are far from one token per 5 bytes (code, tables, CJK text). Off by
+    /// default.
+    ///
+    /// # Arguments
+    /// * `enabled` - Whether to estimate the number of tokens of every text
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    ///
+    /// let tokenizer = Tokenizer::new(String::new()).with_estimated_capacity(true);
+    /// let tokens = tokenizer.tokenize_text("a=1;b=2;c=3");
+    /// assert_eq!(tokens.len(), 11);
+    /// assert!(tokens.capacity() >= 11);
+    /// ```
+    pub fn with_estimated_capacity(mut self, enabled: bool) -> Self {
+        self.estimated_capacity = enabled;
+        self
+    }
+
+    /// Returns true if result vectors are sized with estimate_token_count()
+    pub fn estimated_capacity(&self) -> bool {
+        self.estimated_capacity
+    }
+
+    /// Returns how many tokens to reserve room for before tokenizing a text
+    ///
+    /// The capacity hint if there is one, estimate_token_count() if the caller opted in,
+    /// and otherwise one token per 5 bytes, which costs nothing to compute.
+    fn token_capacity(&self, text: &str) -> usize {
+        match self.capacity_hint {
+            Some(hint) => hint,
+            None if self.estimated_capacity => estimate_token_count(text),
+            None => text.len() / 5,
         }
+    }
 
-        // Create a string to build the result
-        let mut result = String::new();
+    /// Makes sure every token the Tokenizer relies on has an id in the vocabulary
+    ///
+    /// Special tokens, added tokens and the tokens needed by the encoder configuration
+    /// must always be encodable, whatever order the with_...() methods are called in.
+    fn reserve_tokens(&mut self) {
+        // Template tokens like [CLS] and [SEP] are special tokens too, and so is the padding token
+        if let Some(template) = self.encoder.template() {
+            for token in template.special_tokens() {
+                self.special_tokens.register(token);
+            }
+        }
+        if let Some(padding) = self.encoder.padding() {
+            self.special_tokens.register(padding.pad_token());
+        }
 
-        // Iterate through each token with its index
-        for (index, token) in tokens.iter().enumerate() {
-            // On the first token, just add it without any space
-            if index == 0 {
-                result.push_str(token);
-            } else {
-                // For tokens after the first, we need to decide about spacing
+        for token in self.special_tokens.iter().chain(self.added_tokens.iter()) {
+            self.vocab.add_token(token);
+        }
+        for token in self.encoder.reserved_tokens() {
+            self.vocab.add_token(&token);
+        }
+    }
 
-                // These characters should NOT have a space before them because they attach to the previous word
-                let no_space_before = ['.', ',', '!', '?', ';', ':', ')', ']', '}', '"'];
+    /// Returns the vocabulary used by encode() and decode()
+    pub fn vocabulary(&self) -> &Vocabulary {
+        &self.vocab
+    }
 
-                // These characters should NOT have a space after them because the next word attaches to them
-                let no_space_after = ['(', '[', '{', '"'];
+    /// Writes the whole configuration of the Tokenizer to a file, to rebuild it with from_file()
+    ///
+    /// The file is the JSON document of to_json(): the tokenization rules version, the
+    /// special and added tokens, identifier detection, the capacity settings, the vocabulary and
+    /// the encoding settings. The text of the Tokenizer is not saved.
+    ///
+    /// # Returns
+    /// An error if the file cannot be written, or an InvalidInput error if the Tokenizer
+    /// uses identifier validators (Rust closures cannot be written to a file)
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::encoder::{EncoderConfig, Template};
+    /// use tokenizer_rust::special::SpecialTokens;
+    /// use tokenizer_rust::vocab::Vocabulary;
+    ///
+    /// let vocab = Vocabulary::from_tokens(&["hello".to_string(), "world".to_string()]);
+    /// let mut tokenizer = Tokenizer::new(String::new())
+    ///     .with_vocabulary(vocab)
+    ///     .with_special_tokens(SpecialTokens::bert())
+    ///     .with_encoder_config(EncoderConfig::new().with_template(Template::bert()))
+    ///     .with_capacity_hint(16)
+    ///     .with_estimated_capacity(true);
+    /// tokenizer.add_tokens(["<tool_call>"]);
+    ///
+    /// let path = std::env::temp_dir().join("tokenizer_save_doctest.json");
+    /// tokenizer.save(&path).unwrap();
+    /// let loaded = Tokenizer::from_file(&path).unwrap();
+    ///
+    /// let text = "hello <tool_call> world";
+    /// assert_eq!(loaded.encode_batch(&[text]), tokenizer.encode_batch(&[text]));
+    /// assert_eq!(loaded.vocabulary().len(), tokenizer.vocabulary().len());
+    /// assert_eq!(loaded.capacity_hint(), Some(16));
+    /// assert!(loaded.estimated_capacity());
+    /// # std::fs::remove_file(&path).unwrap();
+    /// ```
+    pub fn save<P: AsRef<std::path::Path>>(&self, path: P) -> std::io::Result<()> {
+        std::fs::write(path, self.to_json()?)
+    }
 
-                // Check if the current token starts with a no-space character
-                let first_char = token.chars().next().unwrap_or(' ');
-                let should_add_space = !no_space_before.contains(&first_char);
+    /// Returns the whole configuration of the Tokenizer as a JSON document, like save()
+    ///
+    /// # Returns
+    /// The document, or an InvalidInput error if the Tokenizer uses identifier validators or
+    /// token filters of its own (which cannot describe themselves, see TokenFilter::describe())
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::encoder::{EncoderConfig, Template};
+    /// use tokenizer_rust::encode_options::{Padding, PaddingStrategy};
+    /// use tokenizer_rust::identifiers::IdentifierDetector;
+    ///
+    /// let tokenizer = Tokenizer::new(String::new())
+    ///     .with_identifiers(IdentifierDetector::new().with_min_length(6))
+    ///     .with_structure_tokens(true)
+    ///     .with_encoder_config(
+    ///         EncoderConfig::new()
+    ///             .with_template(Template::bert())
+    ///             .with_max_length(8)
+    ///             .with_padding(Padding::new(PaddingStrategy::Fixed(8))),
+    ///     );
+    /// let json = tokenizer.to_json().unwrap();
+    /// assert!(json.contains("\"format\": \"tokenizer-config\""));
+    ///
+    /// let loaded = Tokenizer::from_json(&json).unwrap();
+    /// assert_eq!(loaded.to_json().unwrap(), json);
+    /// assert_eq!(loaded.identifiers().map(|detector| detector.min_length()), Some(6));
+    /// assert_eq!(loaded.encode_batch(&["a b"]), tokenizer.encode_batch(&["a b"]));
+    ///
+    /// let validated = Tokenizer::new(String::new())
+    ///     .with_identifiers(IdentifierDetector::new().with_validator(|_| true));
+    /// assert!(validated.to_json().is_err());
+    /// ```
+    ///
+    /// The token filters and the sentence filter are saved with all of their settings:
+    /// ```
+    /// # #[cfg(feature = "regex")]
+    /// # {
+    /// use regex::Regex;
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::filter::SentenceFilter;
+    /// use tokenizer_rust::stopwords::StopwordFilter;
+    /// use tokenizer_rust::token::Token;
+    /// use tokenizer_rust::token_filter::{FilterPipeline, Lowercase, TokenFilter};
+    ///
+    /// let tokenizer = Tokenizer::new(String::new())
+    ///     .with_filters(
+    ///         FilterPipeline::new()
+    ///             .with_filter(Lowercase)
+    ///             .with_filter(StopwordFilter::new().with_words(["the", "of"])),
+    ///     )
+    ///     .with_sentence_filter(
+    ///         SentenceFilter::new()
+    ///             .with_token_range(2, 40)
+    ///             .with_forbidden_pattern(Regex::new("(?i)^cookies?$").unwrap()),
+    ///     );
+    /// let json = tokenizer.to_json().unwrap();
+    /// let loaded = Tokenizer::from_json(&json).unwrap();
+    /// assert_eq!(loaded.to_json().unwrap(), json);
+    ///
+    /// let text = "The Book of the Dead";
+    /// assert_eq!(loaded.tokenize_filtered(text), tokenizer.tokenize_filtered(text));
+    /// let filter = loaded.sentence_filter().unwrap();
+    /// assert!(!filter.keeps(&loaded.tokenize_text("We use cookies.")));
+    /// assert!(filter.keeps(&loaded.tokenize_text("We use butter.")));
+    ///
+    /// // A filter that is Rust code cannot be saved
+    /// struct NoOp;
+    /// impl TokenFilter for NoOp {
+    ///     fn apply(&self, tokens: Vec<Token>) -> Vec<Token> {
+    ///         tokens
+    ///     }
+    /// }
+    /// let custom = Tokenizer::new(String::new())
+    ///     .with_filters(FilterPipeline::new().with_filter(NoOp));
+    /// assert!(custom.to_json().is_err());
+    /// # }
+    /// ```
+    pub fn to_json(&self) -> std::io::Result<String> {
+        let invalid = |message: &str| {
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, message.to_string())
+        };
+        if self
+            .identifiers
+            .as_ref()
+            .is_some_and(|detector| detector.validator_count() > 0)
+        {
+            return Err(invalid(
+                "identifier validators are Rust code and cannot be saved to a file",
+            ));
+        }
+        if self
+            .filters
+            .steps()
+            .iter()
+            .any(|step| step.name == "custom_filter")
+        {
+            return Err(invalid(
+                "custom token filters are Rust code and cannot be saved to a file",
+            ));
+        }
+        Ok(crate::config::to_json(self))
+    }
 
-                // Also check if the previous token is a no-space-after character
-                let prev_token = &tokens[index - 1];
-                let prev_last_char = prev_token.chars().last().unwrap_or(' ');
-                let prev_allows_space = !no_space_after.contains(&prev_last_char);
+    /// Creates a Tokenizer (without text) from a JSON document written by to_json() or save()
+    ///
+    /// # Returns
+    /// The Tokenizer, or an InvalidData error if the text is not a tokenizer configuration, a
+    /// setting cannot be read or it was written with other tokenization rules
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    ///
+    /// let json = r#"{"format": "tokenizer-config", "version": 1, "rules_version": 1,
+    ///                "vocabulary": ["hello", "world"], "capacity_hint": 4}"#;
+    /// let tokenizer = Tokenizer::from_json(json).unwrap();
+    /// assert_eq!(tokenizer.encode_batch(&["hello world"])[0].ids, vec![0, 1]);
+    /// assert_eq!(tokenizer.capacity_hint(), Some(4));
+    ///
+    /// assert!(Tokenizer::from_json(r#"{"format": "tokenizer-config", "version": 2}"#).is_err());
+    /// assert!(Tokenizer::from_json("[1, 2]").is_err());
+    /// ```
+    pub fn from_json(text: &str) -> std::io::Result<Tokenizer> {
+        crate::config::from_json(text)
+            .map_err(|message| std::io::Error::new(std::io::ErrorKind::InvalidData, message))
+    }
 
-                // Add space only if both conditions are met
-                if should_add_space && prev_allows_space {
-                    result.push(' ');
-                }
+    /// Creates a Tokenizer (without text) from a file written by save()
+    ///
+    /// Files written by older versions, which saved a Manifest, are read too.
+    ///
+    /// # Returns
+    /// The Tokenizer, or an InvalidData error like from_json() and from_manifest()
+    pub fn from_file<P: AsRef<std::path::Path>>(path: P) -> std::io::Result<Tokenizer> {
+        Tokenizer::from_saved(&std::fs::read_to_string(path)?)
+    }
+
+    /// Creates a Tokenizer from the content of a file written by save(), in JSON or as an
+    /// older Manifest
+    pub(crate) fn from_saved(text: &str) -> std::io::Result<Tokenizer> {
+        if text.starts_with("tokenizer-manifest") {
+            Tokenizer::from_manifest(text)
+        } else {
+            Tokenizer::from_json(text)
+        }
+    }
 
-                // Add the current token to the result
-                result.push_str(token);
+    /// Creates a Tokenizer (without text) from a Manifest, the format save() wrote before it
+    /// wrote JSON
+    ///
+    /// # Returns
+    /// The Tokenizer, or an InvalidData error if the text is not a tokenizer manifest, was
+    /// edited (its hashes don't match) or was written with other tokenization rules
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::manifest::Manifest;
+    /// use tokenizer_rust::vocab::Vocabulary;
+    ///
+    /// let vocab = Vocabulary::from_tokens(&["hello".to_string(), "world".to_string()]);
+    /// let tokenizer = Tokenizer::new(String::new()).with_vocabulary(vocab);
+    /// let path = std::env::temp_dir().join("tokenizer_from_manifest_doctest.manifest");
+    /// Manifest::from_tokenizer(&tokenizer).save(&path).unwrap();
+    ///
+    /// let text = std::fs::read_to_string(&path).unwrap();
+    /// let loaded = Tokenizer::from_manifest(&text).unwrap();
+    /// assert_eq!(loaded.encode_batch(&["hello world"])[0].ids, vec![0, 1]);
+    /// assert!(Tokenizer::from_manifest("not a manifest").is_err());
+    /// # std::fs::remove_file(&path).unwrap();
+    /// ```
+    pub fn from_manifest(text: &str) -> std::io::Result<Tokenizer> {
+        let invalid =
+            |message: String| std::io::Error::new(std::io::ErrorKind::InvalidData, message);
+        let manifest = Manifest::parse(text).ok_or_else(|| {
+            invalid("not a tokenizer manifest, or its hashes do not match".to_string())
+        })?;
+        match manifest.rules_version() {
+            Some(RULES_VERSION) => {}
+            Some(version) => {
+                return Err(invalid(format!(
+                    "the file was written with tokenization rules version {} (this version has {})",
+                    version, RULES_VERSION
+                )))
+            }
+            None => {
+                return Err(invalid(
+                    "the file has no tokenization rules version".to_string(),
+                ))
             }
         }
+        manifest
+            .to_tokenizer()
+            .ok_or_else(|| invalid("a setting of the file cannot be read".to_string()))
+    }
 
-        // Return the reconstructed text
-        result
+    /// Tokenizes the stored text into a vector of token strings
+    ///
+    /// This function:
+    /// 1. Splits the text by whitespace to get word-like units
+    /// 2. Separates punctuation from words
+    /// 3. Returns all tokens as a Vec<String>
+    ///
+    /// # Returns
+    /// A vector of tokens (words and punctuation as separate entries).
+    /// An empty or whitespace-only text gives an empty vector, and tokens are never empty.
+    ///
+    /// # Example
+    /// ```
+    /// # use tokenizer_rust::Tokenizer;
+    /// let tokenizer = Tokenizer::new("Hello, world!".to_string());
+    /// let tokens = tokenizer.tokenize();
+    /// // tokens will be: ["Hello", ",", "world", "!"]
+    ///
+    /// assert!(Tokenizer::new(" \n\t ".to_string()).tokenize().is_empty());
+    /// assert_eq!(Tokenizer::new("?!".to_string()).tokenize(), vec!["?", "!"]);
+    /// ```
+    pub fn tokenize(&self) -> Vec<String> {
+        // The real work happens in tokenize_with_offsets(), here we only keep the token text
+        self.tokenize_with_offsets()
+            .into_iter()
+            .map(|token| token.text)
+            .collect()
     }
 
-    /// Returns the original text that was stored in this Tokenizer
+    /// Tokenizes the stored text and keeps track of where every token comes from
+    ///
+    /// This function:
+    /// 1. Walks through the text character by character
+    /// 2. Whitespace ends the current token and is dropped
+    /// 3. Punctuation ends the current token and becomes a token of its own
     ///
     /// # Returns
-    /// A reference to the original text string
-    pub fn original_text(&self) -> &str {
-        &self.text
+    /// A vector of Token values (text, kind and byte offsets in the original text)
+    ///
+    /// # Example
+    /// ```
+    /// # use tokenizer_rust::Tokenizer;
+    /// let tokenizer = Tokenizer::new("Hello, world!".to_string());
+    /// let tokens = tokenizer.tokenize_with_offsets();
+    /// // tokens[2] is "world", found at bytes 7..12
+    /// assert_eq!((tokens[2].start, tokens[2].end), (7, 12));
+    /// ```
+    pub fn tokenize_with_offsets(&self) -> Vec<Token> {
+        self.tokenize_text(&self.text)
     }
 
-    /// Analyzes and returns statistics about the tokens
+    /// Tokenizes any text with the settings of this Tokenizer
+    ///
+    /// This works exactly like tokenize_with_offsets(), but on the given text instead of
+    /// the stored one, so one configured Tokenizer can be reused for many texts.
     ///
     /// # Arguments
-    /// * `tokens` - A vector of tokens to analyze
+    /// * `text` - The text to tokenize
     ///
     /// # Returns
-    /// A tuple containing:
-    /// - Total number of tokens
-    /// - Number of word tokens (containing alphanumeric characters)
-    /// - Number of punctuation tokens
-    /// - Average token length
-    pub fn analyze_tokens(&self, tokens: &[String]) -> (usize, usize, usize, f64) {
-        let total = tokens.len();
+    /// A vector of Token values (offsets are relative to `text`)
+    ///
+    /// # Example
+    /// ```
+    /// # use tokenizer_rust::Tokenizer;
+    /// let tokenizer = Tokenizer::new(String::new());
+    /// let tokens = tokenizer.tokenize_text("Hi there!");
+    /// assert_eq!(tokens.len(), 3);
+    /// ```
+    pub fn tokenize_text(&self, text: &str) -> Vec<Token> {
+        // Create a mutable vector to store our tokens, pre-sized without reading the text
+        let mut tokens: Vec<Token> = Vec::with_capacity(self.token_capacity(text));
 
-        // Count words by checking if token contains alphanumeric characters
-        let words = tokens
-            .iter()
-            .filter(|t| t.chars().any(|c| c.is_alphanumeric()))
-            .count();
+        // Special and added tokens are matched first, on the raw text, so they are never split
+        // The text between two of them is tokenized normally
+        let mut segment_start = 0;
+        let registries = [&self.special_tokens, &self.added_tokens];
+        for (start, end) in special::find_all(text, &registries) {
+            scan_segment(&text[segment_start..start], segment_start, &mut tokens);
 
-        // Punctuation tokens are everything else
-        let punctuation = total - words;
+            let matched = &text[start..end];
+            let kind = if self.special_tokens.contains(matched) {
+                TokenKind::Special
+            } else {
+                TokenKind::of(matched)
+            };
+            tokens.push(Token {
+                text: matched.to_string(),
+                kind,
+                start,
+                end,
+            });
+            segment_start = end;
+        }
+        scan_segment(&text[segment_start..], segment_start, &mut tokens);
 
-        // Calculate average token length
-        let total_chars: usize = tokens.iter().map(|t| t.len()).sum();
-        let avg_length = if total > 0 {
-            total_chars as f64 / total as f64
-        } else {
-            0.0
-        };
+        // Identifiers (order numbers, IBANs, ...) are marked and merged last
+        if let Some(detector) = &self.identifiers {
+            tokens = detector.apply(text, tokens);
+        }
+
+        // Newlines are added back as tokens last, in the gaps between all the other tokens
+        if self.structure_tokens {
+            tokens = structure::insert_breaks(text, tokens);
+        }
+
+        // Return the complete list of tokens
+        tokens
+    }
+
+    /// Tokenizes a text like tokenize_text(), then applies the token filters
+    ///
+    /// # Arguments
+    /// * `text` - The text to tokenize
+    ///
+    /// # Returns
+    /// The tokens the filters keep, with their offsets in `text`
+    pub fn tokenize_filtered(&self, text: &str) -> Vec<Token> {
+        self.filters.apply(self.tokenize_text(text))
+    }
+
+    /// Counts the tokens of a text without creating them
+    ///
+    /// Gives the same number as `tokenize_text(text).len()`, in a single pass that allocates
+    /// nothing: no Vec, no String per token. Only registered special or added tokens cost
+    /// one small list of their matches. With identifier detection the tokens have to be
+    /// built, since an identifier merges several of them into one.
+    ///
+    /// # Arguments
+    /// * `text` - The text whose tokens are counted
+    ///
+    /// # Returns
+    /// The number of tokens tokenize_text() would return
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::special::SpecialTokens;
+    ///
+    /// let tokenizer = Tokenizer::new(String::new());
+    /// assert_eq!(tokenizer.count_tokens("Hello, world!"), 4);
+    ///
+    /// let tokenizer = Tokenizer::new(String::new())
+    ///     .with_special_tokens(SpecialTokens::bert())
+    ///     .with_structure_tokens(true);
+    /// let text = "[CLS] Hi!\n\nBye. [SEP]";
+    /// assert_eq!(tokenizer.count_tokens(text), tokenizer.tokenize_text(text).len());
+    /// ```
+    pub fn count_tokens(&self, text: &str) -> usize {
+        // Identifiers merge tokens, so only the full tokenizer knows how many are left
+        if self.identifiers.is_some() {
+            return self.tokenize_text(text).len();
+        }
+
+        let mut count = 0;
+        let mut previous_end = 0;
+        let mut count_token = |start: usize, end: usize| {
+            // With structure tokens, a gap containing newlines is one more token
+            if self.structure_tokens && text[previous_end..start].contains('\n') {
+                count += 1;
+            }
+            count += 1;
+            previous_end = end;
+        };
+
+        // Same segments as tokenize_text(): special and added tokens, and the text in between
+        let mut segment_start = 0;
+        let registries = [&self.special_tokens, &self.added_tokens];
+        for (start, end) in special::find_all(text, &registries) {
+            scan_spans(&text[segment_start..start], |token_start, token_end, _| {
+                count_token(segment_start + token_start, segment_start + token_end);
+            });
+            count_token(start, end);
+            segment_start = end;
+        }
+        scan_spans(&text[segment_start..], |token_start, token_end, _| {
+            count_token(segment_start + token_start, segment_start + token_end);
+        });
+
+        if self.structure_tokens && text[previous_end..].contains('\n') {
+            count += 1;
+        }
+        count
+    }
+
+    /// Returns a lower and an upper bound of the number of tokens of a text, without reading
+    /// all of it
+    ///
+    /// Meant for admission control: a payload whose lower bound is over the limit can be
+    /// rejected, and one whose upper bound is under it accepted, before the full encoder runs.
+    /// A few windows spread over the text are tokenized, cut at the end of a whitespace run
+    /// that is not inside a special or added token or a grouped identifier, so their tokens
+    /// are exactly tokens of the whole text. Every token covers at least one byte, so the
+    /// upper bound adds one token per byte that was not read, and is always an upper bound.
+    /// The lower bound adds half the tokens the sparsest window would give the bytes that were
+    /// not read: it only goes over the count if those bytes are more than twice as sparse as
+    /// every window (one huge word among prose). Texts up to 64 KiB are counted exactly.
+    ///
+    /// # Arguments
+    /// * `text` - The text whose tokens are counted
+    ///
+    /// # Returns
+    /// (lower, upper), with count_tokens(text) <= upper
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    ///
+    /// let tokenizer = Tokenizer::new(String::new());
+    /// assert_eq!(tokenizer.count_bounds("Hello, world!"), (4, 4));
+    ///
+    /// let text = "The quick brown fox jumps over the lazy dog.\n".repeat(50_000);
+    /// let (lower, upper) = tokenizer.count_bounds(&text);
+    /// let count = tokenizer.count_tokens(&text);
+    /// assert!(lower <= count && count <= upper);
+    /// assert!(lower > count / 2 && upper < text.len());
+    ///
+    /// // One line of prose without any newline is cut at its spaces
+    /// let line = "Admission control needs a useful bound, even on a single line. ".repeat(2_000);
+    /// let (lower, upper) = tokenizer.count_bounds(&line);
+    /// let count = tokenizer.count_tokens(&line);
+    /// assert!(line.len() > 120_000 && !line.contains('\n'));
+    /// assert!(lower > 0 && lower <= count && count <= upper);
+    /// assert!(lower > count / 2);
+    /// ```
+    pub fn count_bounds(&self, text: &str) -> (usize, usize) {
+        if text.len() <= COUNT_BOUNDS_SAMPLE_BYTES {
+            let count = self.count_tokens(text);
+            return (count, count);
+        }
+
+        let bytes = text.as_bytes();
+        let window = COUNT_BOUNDS_SAMPLE_BYTES / COUNT_BOUNDS_WINDOWS;
+
+        // The last cut point before a position (looking back at most one window)
+        let cut_before =
+            |position: usize| self.bounds_cut(bytes, position.saturating_sub(window), position);
+
+        let mut counted = 0;
+        let mut read = 0;
+        let mut previous_end = 0;
+        let mut sparsest: Option<(usize, usize)> = None;
+        for index in 0..COUNT_BOUNDS_WINDOWS {
+            // STEP 1: Find cut points around a window starting at an even spacing
+            // (the start and the end of the text are cut points too)
+            let position = index * text.len() / COUNT_BOUNDS_WINDOWS;
+            let end = position + window;
+            let start = match position {
+                0 => 0,
+                _ => match cut_before(position) {
+                    Some(start) => start.max(previous_end),
+                    None => continue,
+                },
+            };
+            let end = if end >= text.len() {
+                text.len()
+            } else {
+                match cut_before(end) {
+                    Some(end) if end > start => end,
+                    _ => continue,
+                }
+            };
+
+            // STEP 2: Count the tokens between them exactly
+            let tokens = self.count_tokens(&text[start..end]);
+            counted += tokens;
+            read += end - start;
+            previous_end = end;
+
+            // STEP 3: Keep the density (tokens per byte) of the sparsest window
+            let bytes = end - start;
+            if sparsest.is_none_or(|(sparse_tokens, sparse_bytes)| {
+                tokens * sparse_bytes < sparse_tokens * bytes
+            }) {
+                sparsest = Some((tokens, bytes));
+            }
+        }
+
+        // STEP 4: The bytes that were not read get half the tokens of the sparsest window
+        let unread = text.len() - read;
+        let estimated = sparsest.map_or(0, |(tokens, bytes)| {
+            (unread as u128 * tokens as u128 / (2 * bytes as u128)) as usize
+        });
+        (counted + estimated, counted + unread)
+    }
+
+    /// Finds the last point in bytes[from..position] where a text can be cut without
+    /// splitting a token: the end of a whitespace run (the run stays whole, so structure
+    /// tokens do too) that is not inside a special or added token, nor a single space
+    /// between two groups of an identifier like "DE89 3704 0044"
+    ///
+    /// # Returns
+    /// The cut point, or None if there is none in the range
+    fn bounds_cut(&self, bytes: &[u8], from: usize, position: usize) -> Option<usize> {
+        let protected = self.protected_tokens();
+        let mut end = position;
+        loop {
+            // STEP 1: The end of the last whitespace run before `end`
+            let last_space = from + bytes[from..end].iter().rposition(u8::is_ascii_whitespace)?;
+            let cut = last_space + 1;
+            if bytes.get(cut).is_some_and(u8::is_ascii_whitespace) {
+                end = last_space;
+                continue;
+            }
+
+            // STEP 2: Not inside a special or added token
+            if let Some(start) = special::start_across(bytes, cut, &protected) {
+                if start <= from {
+                    return None;
+                }
+                end = start;
+                continue;
+            }
+
+            // STEP 3: Not between two groups of an identifier (groups have at most 4 bytes,
+            // so 5 bytes on each side of the space tell)
+            if self.identifiers.is_some()
+                && bytes[last_space] == b' '
+                && last_space > 0
+                && !bytes[last_space - 1].is_ascii_whitespace()
+            {
+                let word_start = last_space.saturating_sub(5);
+                let word_start = bytes[word_start..last_space]
+                    .iter()
+                    .rposition(u8::is_ascii_whitespace)
+                    .map_or(word_start, |space| word_start + space + 1);
+                let word_end = bytes.len().min(cut + 5);
+                let word_end = bytes[cut..word_end]
+                    .iter()
+                    .position(u8::is_ascii_whitespace)
+                    .map_or(word_end, |space| cut + space);
+                // Groups are ASCII: a word cut inside a character is not one
+                let before = std::str::from_utf8(&bytes[word_start..last_space]).unwrap_or("");
+                let after = std::str::from_utf8(&bytes[cut..word_end]).unwrap_or("");
+                if identifiers::may_join_groups(before, after) {
+                    end = last_space;
+                    continue;
+                }
+            }
+            return Some(cut);
+        }
+    }
+
+    /// Counts the graphemes, words, sentences and tokens of a text in one call
+    ///
+    /// The definitions are the ones used everywhere in the crate (see the counts module):
+    /// words are Word tokens of this Tokenizer and sentences come from the default
+    /// SentenceSplitter.
+    ///
+    /// # Arguments
+    /// * `text` - The text to count
+    ///
+    /// # Returns
+    /// The four counts of the text
+    pub fn counts(&self, text: &str) -> Counts {
+        let tokens = self.tokenize_text(text);
+        Counts {
+            graphemes: counts::count_graphemes(text),
+            words: tokens
+                .iter()
+                .filter(|token| token.kind == TokenKind::Word)
+                .count(),
+            sentences: SentenceSplitter::new().split(text).len(),
+            tokens: tokens.len(),
+        }
+    }
+
+    /// Tokenizes text like tokenize_text(), sharing the text of repeated tokens
+    ///
+    /// Every distinct token is allocated once in the interner, and every occurrence points
+    /// to that single copy. When the same interner is reused for many texts, a whole corpus
+    /// only allocates one string per distinct token.
+    ///
+    /// # Arguments
+    /// * `text` - The text to tokenize
+    /// * `interner` - Where the shared token strings are kept
+    ///
+    /// # Returns
+    /// The tokens, with shared text
+    ///
+    /// # Example
+    /// ```
+    /// use std::sync::Arc;
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::intern::Interner;
+    ///
+    /// let tokenizer = Tokenizer::new(String::new());
+    /// let mut interner = Interner::new();
+    /// let tokens = tokenizer.tokenize_interned("the cat and the dog", &mut interner);
+    ///
+    /// assert_eq!(&*tokens[3].text, "the");
+    /// assert!(Arc::ptr_eq(&tokens[0].text, &tokens[3].text));
+    /// assert_eq!(interner.len(), 4);
+    /// ```
+    pub fn tokenize_interned(&self, text: &str, interner: &mut Interner) -> Vec<InternedToken> {
+        if !self.is_plain() {
+            // Special tokens and identifiers need the full tokenizer
+            return self
+                .tokenize_text(text)
+                .into_iter()
+                .map(|token| InternedToken {
+                    text: interner.intern(&token.text),
+                    kind: token.kind,
+                    start: token.start,
+                    end: token.end,
+                })
+                .collect();
+        }
+
+        // Fast path: the scanner alone gives the tokens, so no String is allocated at all
+        let mut tokens = Vec::with_capacity(self.token_capacity(text));
+        scan_spans(text, |start, end, kind| {
+            tokens.push(InternedToken {
+                text: interner.intern(&text[start..end]),
+                kind,
+                start,
+                end,
+            });
+        });
+        tokens
+    }
+
+    /// Tokenizes a text into tokens that borrow their text from it, one at a time
+    ///
+    /// Gives the same tokens as tokenize_text(), without a String per token and without a
+    /// Vec of all of them: the tokens of a few KiB of text at a time are kept in a small
+    /// buffer, reused from window to window. This is the fastest way to read the tokens of
+    /// a text that outlives them, several times faster than tokenize_text() on English
+    /// prose (see benches/tokenize.rs). Use to_token() on the tokens that must be kept
+    /// longer than the text. With special tokens, added tokens, identifiers or structure
+    /// tokens the whole text is tokenized first, like tokenize_text() does.
+    ///
+    /// # Arguments
+    /// * `text` - The text to tokenize
+    ///
+    /// # Returns
+    /// An iterator over the tokens, borrowing their text from `text`
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::special::SpecialTokens;
+    ///
+    /// let tokenizer = Tokenizer::new(String::new());
+    /// let words = tokenizer
+    ///     .tokenize_borrowed("Hello, world!")
+    ///     .filter(|token| token.text.len() > 1)
+    ///     .count();
+    /// assert_eq!(words, 2);
+    ///
+    /// // Long texts go through several windows, with the same tokens as tokenize_text()
+    /// let text = "It was the best of times, it was the worst of times.\n".repeat(1_000);
+    /// let tokens: Vec<_> = tokenizer.tokenize_borrowed(&text).map(|token| token.to_token()).collect();
+    /// assert_eq!(tokens, tokenizer.tokenize_text(&text));
+    ///
+    /// let tokenizer = Tokenizer::new(String::new()).with_special_tokens(SpecialTokens::bert());
+    /// let text = "[CLS] Hi [SEP]";
+    /// let tokens: Vec<_> = tokenizer.tokenize_borrowed(text).map(|token| token.to_token()).collect();
+    /// assert_eq!(tokens, tokenizer.tokenize_text(text));
+    /// ```
+    pub fn tokenize_borrowed<'a>(&self, text: &'a str) -> BorrowedTokens<'a> {
+        if self.is_plain() {
+            return BorrowedTokens {
+                text,
+                position: 0,
+                buffer: Vec::new(),
+                next: 0,
+            };
+        }
+
+        // Special tokens and identifiers need the full tokenizer, whose tokens are all
+        // pieces of the text
+        let buffer = self
+            .tokenize_text(text)
+            .into_iter()
+            .map(|token| TokenRef {
+                text: &text[token.start..token.end],
+                kind: token.kind,
+                start: token.start,
+                end: token.end,
+            })
+            .collect();
+        BorrowedTokens {
+            text,
+            position: text.len(),
+            buffer,
+            next: 0,
+        }
+    }
+
+    /// Cuts a text after its first `max_tokens` tokens
+    ///
+    /// The result is a prefix of the original string (cut at the end of the last kept
+    /// token, using the token offsets), not a detokenization, so the kept part is
+    /// exactly what the user wrote, spacing and all.
+    ///
+    /// # Arguments
+    /// * `text` - The text to shorten
+    /// * `max_tokens` - How many tokens to keep
+    ///
+    /// # Returns
+    /// The whole text if it has at most `max_tokens` tokens, otherwise the prefix that
+    /// ends with token number `max_tokens`
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    ///
+    /// let tokenizer = Tokenizer::new(String::new());
+    /// let prompt = "Summarize  this:\n  \"Don't panic\" (page 42)";
+    /// assert_eq!(tokenizer.truncate_to_tokens(prompt, 6), "Summarize  this:\n  \"Don't panic");
+    /// assert_eq!(tokenizer.truncate_to_tokens(prompt, 100), prompt);
+    /// assert_eq!(tokenizer.truncate_to_tokens(prompt, 0), "");
+    /// ```
+    pub fn truncate_to_tokens<'t>(&self, text: &'t str, max_tokens: usize) -> &'t str {
+        if max_tokens == 0 {
+            return "";
+        }
+        let tokens = self.tokenize_text(text);
+        if tokens.len() > max_tokens {
+            &text[..tokens[max_tokens - 1].end]
+        } else {
+            text
+        }
+    }
+
+    /// Splits a long text into chunks of at most `max_tokens` tokens
+    ///
+    /// Every chunk is a contiguous piece of the text, going from its first to its last
+    /// token, so `chunk.text` is exactly `&text[chunk.start..chunk.end]` with its original
+    /// whitespace. Neighbouring chunks share `overlap` tokens, so text cut
+    /// at the end of one chunk is complete at the start of the next one.
+    ///
+    /// # Arguments
+    /// * `text` - The document to split
+    /// * `max_tokens` - The token budget of one chunk (0 gives no chunks at all)
+    /// * `overlap` - How many tokens neighbouring chunks share (at most `max_tokens - 1` is used)
+    ///
+    /// # Returns
+    /// The chunks, in document order
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    ///
+    /// let tokenizer = Tokenizer::new(String::new());
+    /// let chunks = tokenizer.chunk("One two three,  four five six.", 4, 1);
+    ///
+    /// let texts: Vec<&str> = chunks.iter().map(|chunk| chunk.text.as_str()).collect();
+    /// assert_eq!(texts, vec!["One two three,", ",  four five six", "six."]);
+    /// assert_eq!(chunks[1].first_token, 3);
+    /// assert!(chunks.iter().all(|chunk| chunk.token_count <= 4));
+    /// ```
+    pub fn chunk(&self, text: &str, max_tokens: usize, overlap: usize) -> Vec<Chunk> {
+        let tokens = self.tokenize_text(text);
+        chunk::chunk_tokens(text, &tokens, max_tokens, overlap)
+    }
+
+    /// Splits a long text into chunks of whole sentences with at most `max_tokens` tokens
+    ///
+    /// Like chunk(), but chunks end at sentence boundaries: as many whole sentences as fit
+    /// in the budget go into each chunk. Only a sentence longer than `max_tokens` is cut in
+    /// the middle (into token windows, like chunk() does). The overlap is made of whole
+    /// sentences too: the next chunk repeats the last sentences of the previous one that
+    /// fit in `overlap` tokens.
+    ///
+    /// # Arguments
+    /// * `text` - The document to split
+    /// * `max_tokens` - The token budget of one chunk (0 gives no chunks at all)
+    /// * `overlap` - How many tokens of whole sentences neighbouring chunks can share
+    /// * `splitter` - Finds the sentences of the text
+    ///
+    /// # Returns
+    /// The chunks, in document order
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::sentences::SentenceSplitter;
+    ///
+    /// let tokenizer = Tokenizer::new(String::new());
+    /// let text = "Dr. Who came. He saw. He left again. Bye.";
+    /// let chunks = tokenizer.chunk_sentences(text, 8, 3, &SentenceSplitter::new());
+    ///
+    /// let texts: Vec<&str> = chunks.iter().map(|chunk| chunk.text.as_str()).collect();
+    /// // "He saw." (3 tokens) fits in the overlap and is repeated, "He left again." (4) doesn't
+    /// assert_eq!(texts, vec!["Dr. Who came. He saw.", "He saw. He left again.", "Bye."]);
+    /// ```
+    pub fn chunk_sentences(
+        &self,
+        text: &str,
+        max_tokens: usize,
+        overlap: usize,
+        splitter: &SentenceSplitter,
+    ) -> Vec<Chunk> {
+        let tokens = self.tokenize_text(text);
+        chunk::chunk_sentences(text, &tokens, &splitter.split(text), max_tokens, overlap)
+    }
+
+    /// Tokenizes text from any reader (a file, stdin, a network stream, ...) piece by piece
+    ///
+    /// The input is read in chunks instead of being loaded into memory, so it can be
+    /// much bigger than the available RAM. Tokens that span two chunks are handled
+    /// correctly, and the result is the same as tokenizing the whole text at once.
+    ///
+    /// # Arguments
+    /// * `reader` - Where the text comes from (anything implementing Read, including BufRead types)
+    ///
+    /// # Returns
+    /// An iterator of tokens with byte offsets from the start of the stream.
+    /// Reading errors and invalid UTF-8 come out as `Err` items.
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    ///
+    /// let tokenizer = Tokenizer::new(String::new());
+    /// let input = "Hello, world!\nSecond line.".as_bytes();
+    /// let tokens: Vec<String> = tokenizer
+    ///     .tokenize_reader(input)
+    ///     .with_chunk_size(4)
+    ///     .map(|token| token.unwrap().text)
+    ///     .collect();
+    /// assert_eq!(tokens, vec!["Hello", ",", "world", "!", "Second", "line", "."]);
+    /// ```
+    pub fn tokenize_reader<R: Read>(&self, reader: R) -> TokenReader<'_, R> {
+        TokenReader::new(self, reader)
+    }
+
+    /// Tokenizes several readers (files, sockets, ...) in one stream, one document each
+    ///
+    /// Every token comes with the index of the reader it was read from, so tokens of
+    /// concatenated inputs can always be traced back to their document. Offsets are byte
+    /// offsets from the start of each reader, and the readers are read one after the other.
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    ///
+    /// let tokenizer = Tokenizer::new(String::new());
+    /// let files = ["Hello!".as_bytes(), "".as_bytes(), "Bye now".as_bytes()];
+    /// let tokens: Vec<(usize, String, usize)> = tokenizer
+    ///     .tokenize_readers(files)
+    ///     .map(|token| token.unwrap())
+    ///     .map(|token| (token.document, token.token.text, token.token.start))
+    ///     .collect();
+    /// assert_eq!(tokens, vec![
+    ///     (0, "Hello".to_string(), 0),
+    ///     (0, "!".to_string(), 5),
+    ///     (2, "Bye".to_string(), 0),
+    ///     (2, "now".to_string(), 4),
+    /// ]);
+    /// ```
+    pub fn tokenize_readers<I>(&self, readers: I) -> DocumentTokenReader<'_, I::IntoIter>
+    where
+        I: IntoIterator,
+        I::Item: Read,
+    {
+        DocumentTokenReader::new(self, readers)
+    }
+
+    /// Tokenizes text from an async reader, yielding the tokens as a Stream
+    ///
+    /// This is the async version of tokenize_reader(), for use inside tokio services.
+    /// To move the stream into a spawned task, create it with
+    /// `AsyncTokenStream::new(Arc::clone(&tokenizer), reader)` instead.
+    ///
+    /// # Arguments
+    /// * `reader` - Where the text comes from (a tokio AsyncBufRead, like a BufReader around a file or socket)
+    ///
+    /// # Returns
+    /// A Stream of tokens with byte offsets from the start of the stream
+    ///
+    /// # Example
+    /// ```
+    /// use std::pin::Pin;
+    /// use futures_core::Stream;
+    /// use tokenizer_rust::Tokenizer;
+    ///
+    /// #[tokio::main(flavor = "current_thread")]
+    /// async fn main() {
+    ///     let tokenizer = Tokenizer::new(String::new());
+    ///     let mut stream = tokenizer.tokenize_async("Hello, async world!".as_bytes());
+    ///
+    ///     let mut tokens = Vec::new();
+    ///     while let Some(token) = std::future::poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)).await {
+    ///         tokens.push(token.unwrap().text);
+    ///     }
+    ///     assert_eq!(tokens, vec!["Hello", ",", "async", "world", "!"]);
+    /// }
+    /// ```
+    #[cfg(feature = "tokio")]
+    pub fn tokenize_async<R>(&self, reader: R) -> AsyncTokenStream<&Tokenizer, R>
+    where
+        R: tokio::io::AsyncBufRead + Unpin,
+    {
+        AsyncTokenStream::new(self, reader)
+    }
+
+    /// Tokenizes a file through memory mapping, without reading it into a String
+    ///
+    /// The returned MappedText gives borrowed tokens (TokenRef) whose text points straight
+    /// into the mapped file. The file is tokenized chunk by chunk as the tokens are consumed,
+    /// and the operating system pages it in and out as needed, so it can be larger than RAM.
+    ///
+    /// # Arguments
+    /// * `path` - The file to tokenize (UTF-8 text)
+    ///
+    /// # Returns
+    /// The mapped file, or the error that happened while opening or mapping it
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    ///
+    /// let path = std::env::temp_dir().join("tokenize_file_example.txt");
+    /// std::fs::write(&path, "Hello, mapped world!\n").unwrap();
+    ///
+    /// let tokenizer = Tokenizer::new(String::new());
+    /// let file = tokenizer.tokenize_file(&path).unwrap();
+    /// let tokens: Vec<&str> = file.tokens().map(|token| token.unwrap().text).collect();
+    /// assert_eq!(tokens, vec!["Hello", ",", "mapped", "world", "!"]);
+    /// # std::fs::remove_file(&path).unwrap();
+    /// ```
+    #[cfg(feature = "mmap")]
+    pub fn tokenize_file<P: AsRef<std::path::Path>>(
+        &self,
+        path: P,
+    ) -> std::io::Result<MappedText<'_>> {
+        MappedText::open(self, path)
+    }
+
+    /// Returns true if tokenization is nothing more than the character scanner
+    /// (no special tokens, no added tokens, no identifier detection, no structure tokens)
+    pub(crate) fn is_plain(&self) -> bool {
+        self.special_tokens.is_empty()
+            && self.added_tokens.is_empty()
+            && self.identifiers.is_none()
+            && !self.structure_tokens
+    }
+
+    /// Reconstructs the original text from a list of tokens
+    ///
+    /// This function uses intelligent spacing rules:
+    /// - Punctuation like . , ! ? ; : ) ] } gets NO space before it
+    /// - Opening brackets ( [ { get NO space after them
+    /// - Other words are separated by spaces
+    ///
+    /// Tokens are normalized first (see normalize_token()): whitespace around a token is
+    /// removed, whitespace inside it becomes a single space, and empty tokens are skipped.
+    /// Whatever the tokens look like, the result never contains two spaces in a row, or a
+    /// space at the start or the end.
+    ///
+    /// Re-tokenizing the result and detokenizing again usually gives the same text back,
+    /// but this is not guaranteed: a token that the tokenizer would split differently (like
+    /// ".5" after a word) comes back as other tokens, with other spacing. See the last example.
+    ///
+    /// With structure tokens enabled (see with_structure_tokens()), LineBreak and
+    /// ParagraphBreak tokens are written as "\n" and "\n\n" instead of being skipped.
+    ///
+    /// # Arguments
+    /// * `tokens` - A vector of token strings to recombine
+    ///
+    /// # Returns
+    /// A reconstructed string from the tokens
+    ///
+    /// # Example
+    /// ```
+    /// # use tokenizer_rust::Tokenizer;
+    /// # let tokenizer = Tokenizer::new(String::new());
+    /// let tokens: Vec<String> = vec!["Hello", ",", "world", "!"].into_iter().map(String::from).collect();
+    /// let reconstructed = tokenizer.detokenize(&tokens);
+    /// // Result: "Hello, world!"
+    /// assert_eq!(reconstructed, "Hello, world!");
+    ///
+    /// // Unusual tokens never produce double spaces
+    /// let odd: Vec<String> = vec!["", "Wait", " ", "!", "!", "", " what ", "?"].into_iter().map(String::from).collect();
+    /// let text = tokenizer.detokenize(&odd);
+    /// assert_eq!(text, "Wait!! what?");
+    ///
+    /// // And a second round trip changes nothing
+    /// let again = tokenizer.detokenize(&Tokenizer::new(text.clone()).tokenize());
+    /// assert_eq!(again, text);
+    ///
+    /// // A round trip is not stable for tokens the tokenizer never produces: "x.5" is
+    /// // tokenized as "x", ".", "5", and "." gets a space after it
+    /// let made_up: Vec<String> = vec!["x", ".5"].into_iter().map(String::from).collect();
+    /// let text = tokenizer.detokenize(&made_up);
+    /// assert_eq!(text, "x.5");
+    /// assert_eq!(Tokenizer::new(text.clone()).tokenize(), vec!["x", ".", "5"]);
+    /// assert_eq!(tokenizer.detokenize(&Tokenizer::new(text).tokenize()), "x. 5");
+    /// ```
+    pub fn detokenize(&self, tokens: &[String]) -> String {
+        // Create a string to build the result
+        // Every token plus at most one space after it
+        let mut result = String::with_capacity(tokens.iter().map(|token| token.len() + 1).sum());
+
+        // The previous token that was actually written (empty tokens don't count)
+        let mut prev_token: Option<String> = None;
+
+        for token in tokens {
+            // Structure tokens become newlines, and the next token starts the new line
+            let line_break = self
+                .structure_tokens
+                .then(|| structure::structure_break(token))
+                .flatten();
+            if let Some(line_break) = line_break {
+                let trimmed = result.trim_end_matches([' ', '\t']).len();
+                result.truncate(trimmed);
+                result.push_str(line_break);
+                prev_token = None;
+                continue;
+            }
+
+            // Normalize the token, and skip it if nothing is left
+            let token = Self::normalize_token(token);
+            if token.is_empty() {
+                continue;
+            }
+
+            // For tokens after the first, we need to decide about spacing
+            if let Some(prev_token) = &prev_token {
+                if Self::needs_space(prev_token, &token) {
+                    result.push(' ');
+                }
+            }
+
+            // Add the current token to the result
+            result.push_str(&token);
+            prev_token = Some(token);
+        }
+
+        // Return the reconstructed text
+        result
+    }
+
+    /// Normalizes a token before detokenize() writes it
+    ///
+    /// Whitespace at both ends is removed and every run of whitespace inside the token
+    /// (like in "DE89  3704") becomes a single space. A token made only of whitespace
+    /// becomes empty.
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    ///
+    /// assert_eq!(Tokenizer::normalize_token("  DE89 \t 3704 "), "DE89 3704");
+    /// assert_eq!(Tokenizer::normalize_token(" \n"), "");
+    /// ```
+    pub fn normalize_token(token: &str) -> String {
+        token.split_whitespace().collect::<Vec<&str>>().join(" ")
+    }
+
+    /// Tokenizes the stored text and turns every token into its vocabulary id
+    ///
+    /// Tokens that are not in the vocabulary are handled according to the unknown-token
+    /// policy of the encoder configuration (by default they are skipped).
+    ///
+    /// # Returns
+    /// An Encoding with the ids, token strings, byte offsets, attention mask and special-token mask
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::vocab::Vocabulary;
+    ///
+    /// let tokens = Tokenizer::new("Hello, world!".to_string()).tokenize();
+    /// let tokenizer = Tokenizer::new("Hello, world!".to_string())
+    ///     .with_vocabulary(Vocabulary::from_tokens(&tokens));
+    /// let encoding = tokenizer.encode();
+    /// // ids will be: [0, 1, 2, 3]
+    /// assert_eq!(encoding.ids, vec![0, 1, 2, 3]);
+    /// assert_eq!(encoding.offsets[2], (7, 12));
+    /// ```
+    pub fn encode(&self) -> Encoding {
+        let encoding = self.encode_text(&self.text);
+        self.finish_single(encoding)
+    }
+
+    /// Encodes many texts at once, using all CPU cores
+    ///
+    /// Every text is encoded like encode() would (template, truncation, padding to a fixed
+    /// length), and batch padding is applied to the whole batch at the end.
+    /// The results are in the same order as the texts.
+    ///
+    /// With the `parallel` feature (on by default) the texts are spread over a rayon
+    /// thread pool; without it they are encoded one after the other.
+    ///
+    /// # Arguments
+    /// * `texts` - The texts to encode
+    ///
+    /// # Returns
+    /// One encoding per text
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::vocab::Vocabulary;
+    ///
+    /// let tokens = Tokenizer::new("the cat sat".to_string()).tokenize();
+    /// let tokenizer = Tokenizer::new(String::new()).with_vocabulary(Vocabulary::from_tokens(&tokens));
+    /// let encodings = tokenizer.encode_batch(&["the cat", "sat", "the cat sat"]);
+    /// assert_eq!(encodings[0].ids, vec![0, 1]);
+    /// assert_eq!(encodings[2].ids, vec![0, 1, 2]);
+    /// ```
+    pub fn encode_batch(&self, texts: &[&str]) -> Vec<Encoding> {
+        let mut encodings =
+            self.map_batch(texts, |text| self.finish_single(self.encode_text(text)));
+        self.pad(&mut encodings);
+        encodings
+    }
+
+    /// Tokenizes many texts at once, using all CPU cores
+    ///
+    /// # Arguments
+    /// * `texts` - The texts to tokenize
+    ///
+    /// # Returns
+    /// The tokens of every text, in the same order as the texts
+    pub fn tokenize_batch(&self, texts: &[&str]) -> Vec<Vec<Token>> {
+        self.map_batch(texts, |text| self.tokenize_text(text))
+    }
+
+    /// Tokenizes many texts at once (using all CPU cores) into one list of tokens, each with
+    /// the index of the text it comes from
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    ///
+    /// let files = ["a.txt", "b.txt"];
+    /// let texts = ["Hi there", "Bye"];
+    /// let tokens = Tokenizer::new(String::new()).tokenize_documents(&texts);
+    /// let last = &tokens[2];
+    /// assert_eq!((files[last.document], last.token.text.as_str()), ("b.txt", "Bye"));
+    /// assert_eq!(&texts[last.document][last.token.start..last.token.end], "Bye");
+    /// ```
+    pub fn tokenize_documents(&self, texts: &[&str]) -> Vec<DocumentToken> {
+        self.tokenize_batch(texts)
+            .into_iter()
+            .enumerate()
+            .flat_map(|(document, tokens)| {
+                tokens
+                    .into_iter()
+                    .map(move |token| DocumentToken { document, token })
+            })
+            .collect()
+    }
+
+    /// Tokenizes many texts at once (using all CPU cores) and writes one OutputRecord per
+    /// text, with its text and offsets, to any OutputWriter
+    ///
+    /// The output is not finished, so that several batches can go to the same output: call
+    /// finish() after the last one.
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::output::{JsonlWriter, OutputWriter};
+    ///
+    /// let mut jsonl = JsonlWriter::new(Vec::new());
+    /// Tokenizer::new(String::new()).write_batch(&["Hi!", "Bye"], &mut jsonl).unwrap();
+    /// jsonl.finish().unwrap();
+    /// let output = String::from_utf8(jsonl.into_inner()).unwrap();
+    /// assert_eq!(output.lines().count(), 2);
+    /// assert!(output.starts_with(r#"{"schema_version":1,"text":"Hi!","tokens":["Hi","!"]"#));
+    /// ```
+    pub fn write_batch<O: OutputWriter + ?Sized>(
+        &self,
+        texts: &[&str],
+        output: &mut O,
+    ) -> std::io::Result<()> {
+        for (text, tokens) in texts.iter().zip(self.tokenize_batch(texts)) {
+            output.write_record(&OutputRecord::from_tokens(text, &tokens))?;
+        }
+        Ok(())
+    }
+
+    /// Reads every document of an InputSource, tokenizes them in batches (using all CPU
+    /// cores) and writes one OutputRecord per document to an OutputWriter, in order
+    ///
+    /// The output is not finished, so that several sources can go to the same output.
+    ///
+    /// # Returns
+    /// The number of documents written
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::input::LineSource;
+    /// use tokenizer_rust::output::{CsvWriter, OutputWriter};
+    ///
+    /// let mut source = LineSource::new("One.\nTwo!\n".as_bytes(), "lines");
+    /// let mut csv = CsvWriter::new(Vec::new());
+    /// let tokenizer = Tokenizer::new(String::new());
+    /// assert_eq!(tokenizer.write_source(&mut source, &mut csv).unwrap(), 2);
+    /// csv.finish().unwrap();
+    /// assert_eq!(String::from_utf8(csv.into_inner()).unwrap().lines().count(), 5);
+    /// ```
+    pub fn write_source<S, O>(&self, source: &mut S, output: &mut O) -> std::io::Result<usize>
+    where
+        S: InputSource + ?Sized,
+        O: OutputWriter + ?Sized,
+    {
+        // Documents per tokenize_batch() call: enough to keep every core busy
+        const BATCH_DOCUMENTS: usize = 1024;
+
+        let mut written = 0;
+        let mut batch = Vec::with_capacity(BATCH_DOCUMENTS);
+        loop {
+            let document = source.next_document()?;
+            let end = document.is_none();
+            batch.extend(document.map(|document| document.text));
+            if batch.len() == BATCH_DOCUMENTS || (end && !batch.is_empty()) {
+                let texts: Vec<&str> = batch.iter().map(String::as_str).collect();
+                self.write_batch(&texts, output)?;
+                written += batch.len();
+                batch.clear();
+            }
+            if end {
+                return Ok(written);
+            }
+        }
+    }
+
+    /// Runs a function on every text, in parallel when the `parallel` feature is on
+    #[cfg(feature = "parallel")]
+    fn map_batch<T, F>(&self, texts: &[&str], function: F) -> Vec<T>
+    where
+        T: Send,
+        F: Fn(&str) -> T + Sync + Send,
+    {
+        use rayon::prelude::*;
+
+        // collect() on an indexed parallel iterator keeps the original order
+        texts.par_iter().map(|text| function(text)).collect()
+    }
+
+    /// Runs a function on every text, in parallel when the `parallel` feature is on
+    #[cfg(not(feature = "parallel"))]
+    fn map_batch<T, F>(&self, texts: &[&str], function: F) -> Vec<T>
+    where
+        F: Fn(&str) -> T,
+    {
+        texts.iter().map(|text| function(text)).collect()
+    }
+
+    /// Encodes a text pair for BERT-style models (question/answer, premise/hypothesis, ...)
+    ///
+    /// Both texts are encoded separately, truncated together if the encoder configuration
+    /// has a maximum length (which sequence loses tokens depends on the truncation strategy,
+    /// by default the longer one), and then joined with the pair template of the
+    /// configuration, for example `[CLS] A [SEP] B [SEP]`.
+    /// Without a template the two sequences are simply put one after the other.
+    ///
+    /// The `type_ids` of the result tell which sequence each token belongs to (0 for A, 1 for B,
+    /// or whatever the template says). Offsets always refer to the text the token comes from,
+    /// and template tokens get the empty offsets (0, 0).
+    ///
+    /// # Arguments
+    /// * `text_a` - The first text
+    /// * `text_b` - The second text
+    ///
+    /// # Returns
+    /// The encoding of the pair
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::encoder::{EncoderConfig, Template};
+    /// use tokenizer_rust::vocab::Vocabulary;
+    ///
+    /// let tokenizer = Tokenizer::new(String::new())
+    ///     .with_vocabulary(Vocabulary::from_tokens(&["Who".to_string(), "?".to_string(), "Me".to_string()]))
+    ///     .with_encoder_config(EncoderConfig::new().with_template(Template::bert()));
+    /// let encoding = tokenizer.encode_pair("Who?", "Me");
+    /// assert_eq!(encoding.tokens, vec!["[CLS]", "Who", "?", "[SEP]", "Me", "[SEP]"]);
+    /// assert_eq!(encoding.type_ids, vec![0, 0, 0, 0, 1, 1]);
+    /// assert_eq!(encoding.offsets[4], (0, 2));
+    /// ```
+    pub fn encode_pair(&self, text_a: &str, text_b: &str) -> Encoding {
+        let mut first = self.encode_text(text_a);
+        let mut second = self.encode_text(text_b);
+
+        // Make room for the template tokens, then let the strategy decide what is kept
+        if let Some(max_length) = self.encoder.max_length() {
+            let budget = max_length.saturating_sub(self.encoder.template_length(true));
+
+            if let Some(stride) = self.encoder.overflowing_stride() {
+                if let Some(encoding) = self.overflowing_pair(&first, &second, budget, stride) {
+                    return encoding;
+                }
+            }
+
+            let (length_a, length_b) =
+                self.encoder
+                    .truncation_strategy()
+                    .lengths(first.len(), second.len(), budget);
+            truncate_encoding(&mut first, length_a);
+            truncate_encoding(&mut second, length_b);
+        }
+
+        let encoding = self.apply_template(first, Some(second));
+        self.pad_one(encoding)
+    }
+
+    /// Pads a batch of encodings with the padding settings of the encoder configuration
+    ///
+    /// encode() and encode_pair() already pad to a fixed length on their own; this is
+    /// what pads a batch to the length of its longest encoding. Nothing happens if the
+    /// configuration has no padding.
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::encoder::EncoderConfig;
+    /// use tokenizer_rust::encode_options::{Padding, PaddingStrategy};
+    /// use tokenizer_rust::vocab::Vocabulary;
+    ///
+    /// let tokenizer = Tokenizer::new(String::new())
+    ///     .with_vocabulary(Vocabulary::from_tokens(&["a".to_string(), "b".to_string()]))
+    ///     .with_encoder_config(EncoderConfig::new().with_padding(Padding::new(PaddingStrategy::BatchLongest)));
+    /// let mut batch = vec![tokenizer.encode_text("a"), tokenizer.encode_text("a b a")];
+    /// tokenizer.pad(&mut batch);
+    /// assert_eq!(batch[0].tokens, vec!["a", "[PAD]", "[PAD]"]);
+    /// assert_eq!(batch[0].attention_mask, vec![1, 0, 0]);
+    /// ```
+    pub fn pad(&self, encodings: &mut [Encoding]) {
+        if let Some(padding) = self.encoder.padding() {
+            if let Some(pad_id) = self.vocab.token_to_id(padding.pad_token()) {
+                padding.pad(encodings, pad_id);
+            }
+        }
+    }
+
+    /// Pads a single encoding (only a fixed padding length changes anything here)
+    fn pad_one(&self, encoding: Encoding) -> Encoding {
+        let mut encodings = [encoding];
+        self.pad(&mut encodings);
+        let [encoding] = encodings;
+        encoding
+    }
+
+    /// Applies truncation, the single-sequence template and padding to an encoding
+    fn finish_single(&self, mut encoding: Encoding) -> Encoding {
+        if let Some(max_length) = self.encoder.max_length() {
+            let budget = max_length.saturating_sub(self.encoder.template_length(false));
+
+            // With overflowing windows enabled, nothing is dropped
+            if let (Some(stride), true) =
+                (self.encoder.overflowing_stride(), encoding.len() > budget)
+            {
+                if budget > 0 {
+                    let windows = window_starts(encoding.len(), budget, stride)
+                        .into_iter()
+                        .map(|start| {
+                            let end = (start + budget).min(encoding.len());
+                            self.pad_one(self.apply_template(encoding.slice(start, end), None))
+                        })
+                        .collect();
+                    return join_windows(windows);
+                }
+            }
+
+            truncate_encoding(&mut encoding, budget);
+        }
+
+        let encoding = self.apply_template(encoding, None);
+        self.pad_one(encoding)
+    }
+
+    /// Splits a pair that is too long into overlapping windows
+    ///
+    /// Only one sequence is split into windows (the one the truncation strategy shortens, or the
+    /// longer one for LongestFirst), the other one is repeated whole in every window. This is the
+    /// usual setup for question answering: the question stays, the context slides.
+    ///
+    /// # Returns
+    /// The first window with the others in its `overflowing` field, or None if the pair fits
+    /// or if the repeated sequence alone doesn't leave room for a window (plain truncation is used then)
+    fn overflowing_pair(
+        &self,
+        first: &Encoding,
+        second: &Encoding,
+        budget: usize,
+        stride: usize,
+    ) -> Option<Encoding> {
+        if first.len() + second.len() <= budget {
+            return None;
+        }
+
+        let split_first = match self.encoder.truncation_strategy() {
+            TruncationStrategy::OnlyFirst => true,
+            TruncationStrategy::OnlySecond => false,
+            TruncationStrategy::LongestFirst => first.len() >= second.len(),
+        };
+        let (sliding, fixed) = if split_first {
+            (first, second)
+        } else {
+            (second, first)
+        };
+        if fixed.len() >= budget {
+            return None;
+        }
+
+        let window = budget - fixed.len();
+        let windows = window_starts(sliding.len(), window, stride)
+            .into_iter()
+            .map(|start| {
+                let part = sliding.slice(start, (start + window).min(sliding.len()));
+                let (a, b) = if split_first {
+                    (part, fixed.clone())
+                } else {
+                    (fixed.clone(), part)
+                };
+                self.pad_one(self.apply_template(a, Some(b)))
+            })
+            .collect();
+
+        Some(join_windows(windows))
+    }
+
+    /// Joins one or two encoded sequences using the template of the encoder configuration
+    fn apply_template(&self, first: Encoding, second: Option<Encoding>) -> Encoding {
+        let template = match self.encoder.template() {
+            Some(template) => template,
+            None => {
+                // No template: sequence A gets type 0 and sequence B gets type 1
+                let mut result = Encoding::new();
+                result.append(first, 0);
+                if let Some(second) = second {
+                    result.append(second, 1);
+                }
+                return result;
+            }
+        };
+
+        let pieces = if second.is_some() {
+            template.pair()
+        } else {
+            template.single()
+        };
+
+        let mut result = Encoding::new();
+        let mut first = Some(first);
+        let mut second = second;
+        for piece in pieces {
+            match piece {
+                TemplatePiece::SequenceA(type_id) => {
+                    if let Some(sequence) = first.take() {
+                        result.append(sequence, *type_id);
+                    }
+                }
+                TemplatePiece::SequenceB(type_id) => {
+                    if let Some(sequence) = second.take() {
+                        result.append(sequence, *type_id);
+                    }
+                }
+                TemplatePiece::Special(token, type_id) => {
+                    if let Some(id) = self.vocab.token_to_id(token) {
+                        result.push(id, token, (0, 0), true);
+                        if let Some(last) = result.type_ids.last_mut() {
+                            *last = *type_id;
+                        }
+                    }
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Encodes any text with the settings of this Tokenizer (without template or truncation)
+    ///
+    /// # Arguments
+    /// * `text` - The text to encode
+    ///
+    /// # Returns
+    /// The encoding of the text, with offsets relative to `text`
+    pub fn encode_text(&self, text: &str) -> Encoding {
+        let tokens = self.tokenize_text(text);
+        let mut encoding = Encoding::with_capacity(tokens.len());
+        self.encode_tokens(tokens, &mut encoding, false);
+        encoding
+    }
+
+    /// Pre-encodes the start of texts that all begin the same way (like a system prompt)
+    ///
+    /// The prefix is cut at the same safe point tokenize_reader() cuts a stream at (before the
+    /// whitespace around its last newline, or after its last double space), and the part
+    /// before it is encoded once. encode_with_prefix() then only encodes the rest of the
+    /// prefix together with each suffix, so a token that continues across the end of the
+    /// prefix is still found whole. A prefix without newline or double space has no safe
+    /// point: it is then encoded again with every suffix.
+    ///
+    /// The prefix must be used with the Tokenizer that encoded it.
+    ///
+    /// # Arguments
+    /// * `prefix` - The text every encoded text starts with
+    ///
+    /// # Returns
+    /// The cached encoding of the prefix
+    pub fn encode_prefix(&self, prefix: &str) -> EncodedPrefix {
+        let cut = stream::safe_cut(prefix.as_bytes(), false, &self.protected_tokens()).unwrap_or(0);
+        let tokens = self.tokenize_text(&prefix[..cut]);
+        let mut encoding = Encoding::with_capacity(tokens.len());
+        let ends_with_bytes = self.encode_tokens(tokens, &mut encoding, false);
+        EncodedPrefix {
+            encoding,
+            ends_with_bytes,
+            cut,
+            tail: prefix[cut..].to_string(),
+        }
+    }
+
+    /// Encodes a pre-encoded prefix followed by a suffix
+    ///
+    /// Only the end of the prefix (after its safe cut point) and the suffix are tokenized;
+    /// the tokens before it come from the cache. The result is the encoding encode_batch()
+    /// gives for the whole text (template, truncation and padding included), with offsets
+    /// relative to the start of the prefix.
+    ///
+    /// # Arguments
+    /// * `prefix` - The prefix, from encode_prefix()
+    /// * `suffix` - The text that follows the prefix
+    ///
+    /// # Returns
+    /// The encoding of prefix + suffix
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::vocab::Vocabulary;
+    ///
+    /// let system = "You are a helpful assistant.\nAnswer in one word";
+    /// let tokens = Tokenizer::new(system.to_string()).tokenize();
+    /// let tokenizer = Tokenizer::new(String::new()).with_vocabulary(Vocabulary::from_tokens(&tokens));
+    ///
+    /// let prefix = tokenizer.encode_prefix(system);
+    /// for suffix in [".\nYou are?", "s only", ""] {
+    ///     let text = format!("{system}{suffix}");
+    ///     let encoding = tokenizer.encode_with_prefix(&prefix, suffix);
+    ///     assert_eq!(encoding, tokenizer.encode_batch(&[text.as_str()])[0]);
+    /// }
+    ///
+    /// // "words" continues the last word of the prefix: it is one (unknown, skipped) token
+    /// let encoding = tokenizer.encode_with_prefix(&prefix, "s");
+    /// assert_eq!(encoding.tokens.last().map(String::as_str), Some("one"));
+    /// ```
+    pub fn encode_with_prefix(&self, prefix: &EncodedPrefix, suffix: &str) -> Encoding {
+        let mut text = String::with_capacity(prefix.tail.len() + suffix.len());
+        text.push_str(&prefix.tail);
+        text.push_str(suffix);
+
+        // The tokens after the cut point have offsets relative to it
+        let tokens: Vec<Token> = self
+            .tokenize_text(&text)
+            .into_iter()
+            .map(|mut token| {
+                token.start += prefix.cut;
+                token.end += prefix.cut;
+                token
+            })
+            .collect();
+
+        let mut encoding = prefix.encoding.clone();
+        self.encode_tokens(tokens, &mut encoding, prefix.ends_with_bytes);
+        self.finish_single(encoding)
+    }
+
+    /// Encodes tokens at the end of an encoding
+    ///
+    /// # Arguments
+    /// * `tokens` - The tokens to encode
+    /// * `encoding` - Where the ids are added
+    /// * `previous_was_bytes` - true if the last token of the encoding was encoded byte by byte
+    ///
+    /// # Returns
+    /// true if the last token was encoded byte by byte
+    fn encode_tokens(
+        &self,
+        tokens: Vec<Token>,
+        encoding: &mut Encoding,
+        mut previous_was_bytes: bool,
+    ) -> bool {
+        for token in tokens {
+            let offsets = (token.start, token.end);
+            let special = token.kind == TokenKind::Special;
+
+            // Known tokens are simply looked up
+            if let Some(id) = self.vocab.token_to_id(&token.text) {
+                encoding.push(id, &token.text, offsets, special);
+                previous_was_bytes = false;
+                continue;
+            }
+
+            // Other tokens are split into subword pieces, if the model knows them all
+            if let Some(pieces) = self.subword_ids(&token) {
+                // A piece covers its own bytes, unless the text is not the span (then every
+                // piece covers the whole token)
+                let exact = token.text.len() == token.end - token.start;
+                let mut start = token.start;
+                for (index, (piece, id)) in pieces.iter().enumerate() {
+                    let length = match index {
+                        0 => piece.len(),
+                        _ => piece.len() - subword::CONTINUATION_PREFIX.len(),
+                    };
+                    let piece_offsets = if exact {
+                        (start, start + length)
+                    } else {
+                        offsets
+                    };
+                    encoding.push(*id, piece, piece_offsets, false);
+                    start += length;
+                }
+                previous_was_bytes = false;
+                continue;
+            }
+
+            // Unknown tokens follow the policy of the encoder configuration
+            match self.encoder.unknown_policy() {
+                UnknownPolicy::Skip => {}
+                UnknownPolicy::UnknownToken(unknown) => {
+                    if let Some(id) = self.vocab.token_to_id(unknown) {
+                        encoding.push(id, unknown, offsets, false);
+                    }
+                }
+                UnknownPolicy::ByteFallback => {
+                    // Two byte-encoded tokens in a row are separated by a space byte,
+                    // otherwise decode() could not tell where the first one ends
+                    // (the separator doesn't cover any text, so its offsets are empty)
+                    if previous_was_bytes {
+                        self.push_byte(encoding, b' ', (token.start, token.start));
+                    }
+                    for (index, byte) in token.text.bytes().enumerate() {
+                        let position = token.start + index;
+                        self.push_byte(encoding, byte, (position, position + 1));
+                    }
+                    previous_was_bytes = true;
+                }
+            }
+        }
+
+        previous_was_bytes
+    }
+
+    /// Splits a token with the subword model of the encoder configuration
+    ///
+    /// # Returns
+    /// The pieces with their ids, or None if there is no model, the token is special or a
+    /// piece is not in the vocabulary
+    fn subword_ids(&self, token: &Token) -> Option<Vec<(String, u32)>> {
+        let model = self.encoder.subwords()?;
+        if token.kind == TokenKind::Special {
+            return None;
+        }
+        model
+            .split(&token.text)?
+            .into_iter()
+            .map(|piece| {
+                let id = self.vocab.token_to_id(&piece)?;
+                Some((piece, id))
+            })
+            .collect()
+    }
+
+    /// Adds one byte-fallback piece to an encoding
+    fn push_byte(&self, encoding: &mut Encoding, byte: u8, offsets: (usize, usize)) {
+        let piece = encoder::byte_piece(byte);
+        if let Some(id) = self.vocab.token_to_id(&piece) {
+            encoding.push(id, &piece, offsets, false);
+        }
+    }
+
+    /// Turns a list of ids back into text
+    ///
+    /// Every id is looked up in the vocabulary and the resulting tokens are
+    /// joined with detokenize(). Ids that are not in the vocabulary are skipped.
+    /// Consecutive byte-fallback pieces are put back together into the original token.
+    ///
+    /// # Arguments
+    /// * `ids` - The token ids to decode
+    ///
+    /// # Returns
+    /// The reconstructed text
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::vocab::Vocabulary;
+    ///
+    /// let tokens = Tokenizer::new("Hello, world!".to_string()).tokenize();
+    /// let tokenizer = Tokenizer::new("Hello, world!".to_string())
+    ///     .with_vocabulary(Vocabulary::from_tokens(&tokens));
+    /// let text = tokenizer.decode(&tokenizer.encode().ids);
+    /// assert_eq!(text, "Hello, world!");
+    /// ```
+    pub fn decode(&self, ids: &[u32]) -> String {
+        self.detokenize(&self.ids_to_tokens(ids, false))
+    }
+
+    /// Turns a list of ids back into text, leaving out special tokens
+    ///
+    /// This works like decode(), but tokens such as [CLS] or <pad> are stripped.
+    ///
+    /// # Arguments
+    /// * `ids` - The token ids to decode
+    ///
+    /// # Returns
+    /// The reconstructed text without special tokens
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::special::SpecialTokens;
+    /// use tokenizer_rust::vocab::Vocabulary;
+    ///
+    /// let text = "[CLS] Hello, world! [SEP]";
+    /// let tokenizer = Tokenizer::new(text.to_string()).with_special_tokens(SpecialTokens::bert());
+    /// let vocab = Vocabulary::from_tokens(&tokenizer.tokenize());
+    /// let tokenizer = tokenizer.with_vocabulary(vocab);
+    /// let encoding = tokenizer.encode();
+    /// assert_eq!(tokenizer.decode_skip_special(&encoding.ids), "Hello, world!");
+    /// ```
+    pub fn decode_skip_special(&self, ids: &[u32]) -> String {
+        self.detokenize(&self.ids_to_tokens(ids, true))
+    }
+
+    /// Looks up the tokens of a list of ids (used by decode() and decode_skip_special())
+    ///
+    /// # Arguments
+    /// * `ids` - The token ids to look up
+    /// * `skip_special` - If true, special tokens are left out
+    ///
+    /// # Returns
+    /// The tokens, with runs of byte-fallback pieces merged back into the original tokens
+    /// and subword pieces glued back into words
+    fn ids_to_tokens(&self, ids: &[u32], skip_special: bool) -> Vec<String> {
+        let mut tokens: Vec<String> = Vec::new();
+        let mut pending_bytes: Vec<u8> = Vec::new();
+
+        for &id in ids {
+            let token = match self.vocab.id_to_token(id) {
+                Some(token) => token,
+                None => continue,
+            };
+            if skip_special && self.special_tokens.contains(token) {
+                continue;
+            }
+
+            // Collect byte pieces until the run ends
+            if let Some(byte) = encoder::parse_byte_piece(token) {
+                pending_bytes.push(byte);
+                continue;
+            }
+            flush_bytes(&mut pending_bytes, &mut tokens);
+
+            // Subword pieces that continue a word are glued back onto the piece before
+            if self.encoder.subwords().is_some() {
+                let rest = token
+                    .strip_prefix(subword::CONTINUATION_PREFIX)
+                    .filter(|rest| !rest.is_empty());
+                if let (Some(rest), Some(last)) = (rest, tokens.last_mut()) {
+                    last.push_str(rest);
+                    continue;
+                }
+            }
+
+            tokens.push(token.to_string());
+        }
+        flush_bytes(&mut pending_bytes, &mut tokens);
+
+        tokens
+    }
+
+    /// Decides whether detokenize() puts a space between two neighbouring tokens
+    ///
+    /// Other modules (like the subtitle line breaker) use this so they agree with
+    /// detokenize() about where the "gaps" between tokens are.
+    ///
+    /// # Arguments
+    /// * `prev_token` - The token that comes first
+    /// * `token` - The token that comes right after it
+    ///
+    /// # Returns
+    /// true if a space separates the two tokens in the reconstructed text
+    pub(crate) fn needs_space(prev_token: &str, token: &str) -> bool {
+        // These characters should NOT have a space before them because they attach to the previous word
+        let no_space_before = ['.', ',', '!', '?', ';', ':', ')', ']', '}', '"'];
+
+        // These characters should NOT have a space after them because the next word attaches to them
+        let no_space_after = ['(', '[', '{', '"'];
+
+        // Check if the current token starts with a no-space character
+        let first_char = token.chars().next().unwrap_or(' ');
+        let should_add_space = !no_space_before.contains(&first_char);
+
+        // Also check if the previous token is a no-space-after character
+        let prev_last_char = prev_token.chars().last().unwrap_or(' ');
+        let prev_allows_space = !no_space_after.contains(&prev_last_char);
+
+        // Add space only if both conditions are met
+        should_add_space && prev_allows_space
+    }
+
+    /// Returns the original text that was stored in this Tokenizer
+    ///
+    /// # Returns
+    /// A reference to the original text string
+    pub fn original_text(&self) -> &str {
+        &self.text
+    }
+
+    /// Compares the stored text with its reconstruction from tokens
+    ///
+    /// The reconstruction is detokenize(tokenize()). The returned patch lists every
+    /// insertion and deletion (with offsets in the original text) needed to go from the
+    /// original to the reconstruction, and can be reverted to correct the reconstruction.
+    ///
+    /// # Returns
+    /// The patch from the original text to its reconstruction (empty if they are equal)
+    ///
+    /// # Example
+    /// ```
+    /// # use tokenizer_rust::Tokenizer;
+    /// let tokenizer = Tokenizer::new("This  has   multiple    spaces.".to_string());
+    /// let patch = tokenizer.reconstruction_patch();
+    /// // three runs of extra spaces were removed
+    /// assert_eq!(patch.ops.len(), 3);
+    /// ```
+    pub fn reconstruction_patch(&self) -> Patch {
+        let reconstructed = self.detokenize(&self.tokenize());
+        Patch::between(&self.text, &reconstructed)
+    }
+
+    /// Analyzes and returns statistics about the tokens
+    ///
+    /// # Arguments
+    /// * `tokens` - A vector of tokens to analyze
+    ///
+    /// # Returns
+    /// The number of tokens of every kind, the number of distinct tokens and the average and
+    /// median token lengths (see TokenStats). Empty tokens are ignored, and an empty list gives
+    /// all zeros.
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    ///
+    /// let tokenizer = Tokenizer::new("Hello, world! Hello again.".to_string());
+    /// let stats = tokenizer.analyze_tokens(&tokenizer.tokenize());
+    /// assert_eq!((stats.total, stats.words, stats.punctuation), (7, 4, 3));
+    /// assert_eq!(stats.unique, 6);
+    /// ```
+    pub fn analyze_tokens(&self, tokens: &[String]) -> TokenStats {
+        TokenStats::from_tokens(tokens)
+    }
+}
+
+/// Makes the first window the main encoding and stores the others in its `overflowing` field
+fn join_windows(windows: Vec<Encoding>) -> Encoding {
+    let mut windows = windows.into_iter();
+    let mut first = windows.next().unwrap_or_default();
+    first.overflowing = windows.collect();
+    first
+}
+
+/// Shortens an encoding to at most `length` tokens without splitting a byte-fallback token
+///
+/// All vectors (ids, offsets, masks, ...) are shortened together. If the cut falls in the
+/// middle of a word encoded byte by byte, the partial bytes of that word are dropped too,
+/// so decode() never produces half a character. A trailing byte separator is dropped as well.
+///
+/// # Arguments
+/// * `encoding` - The encoding to shorten
+/// * `length` - The maximum number of tokens to keep
+fn truncate_encoding(encoding: &mut Encoding, length: usize) {
+    if encoding.len() <= length {
+        return;
+    }
+
+    let is_byte = |index: usize| encoder::parse_byte_piece(&encoding.tokens[index]).is_some();
+    let covers_text = |index: usize| encoding.offsets[index].0 < encoding.offsets[index].1;
+
+    // STEP 1: Step back while the last kept piece and the first dropped piece are bytes of the same word
+    let mut length = length;
+    while length > 0
+        && is_byte(length - 1)
+        && is_byte(length)
+        && covers_text(length - 1)
+        && covers_text(length)
+        && encoding.offsets[length - 1].1 == encoding.offsets[length].0
+    {
+        length -= 1;
+    }
+
+    // STEP 2: A separator between two byte-encoded words is useless at the end
+    if length > 0 && is_byte(length - 1) && !covers_text(length - 1) {
+        length -= 1;
+    }
+
+    encoding.truncate(length);
+}
+
+/// Turns a run of byte-fallback bytes back into tokens
+///
+/// The bytes are decoded as UTF-8 and split on the space bytes encode() puts between
+/// consecutive byte-encoded tokens. The byte buffer is emptied afterwards.
+fn flush_bytes(pending_bytes: &mut Vec<u8>, tokens: &mut Vec<String>) {
+    if pending_bytes.is_empty() {
+        return;
+    }
+
+    let text = String::from_utf8_lossy(pending_bytes);
+    tokens.extend(text.split_whitespace().map(|token| token.to_string()));
+    pending_bytes.clear();
+}
+
+/// How many bytes of text BorrowedTokens scans at a time
+const BORROWED_WINDOW: usize = 8 * 1024;
+
+/// An iterator over the tokens of a text, borrowing their text from it, created by
+/// Tokenizer::tokenize_borrowed()
+#[derive(Debug, Clone)]
+pub struct BorrowedTokens<'a> {
+    /// The whole text
+    text: &'a str,
+    /// Where the next window starts (the end of the text once everything was scanned)
+    position: usize,
+    /// The tokens of the current window
+    buffer: Vec<TokenRef<'a>>,
+    /// Index in `buffer` of the next token to return
+    next: usize,
+}
+
+impl<'a> BorrowedTokens<'a> {
+    /// Scans the next window of the text into the buffer
+    ///
+    /// A window ends on ASCII whitespace: no token contains whitespace, so the scanner
+    /// finds the same tokens as on the whole text. A window without whitespace grows until
+    /// the next one, or the end of the text.
+    fn next_window(&mut self) {
+        let rest = &self.text.as_bytes()[self.position..];
+        let is_space = |byte: &u8| *byte < 128 && ASCII_CLASSES[*byte as usize] == SPACE;
+        let length = if rest.len() <= BORROWED_WINDOW {
+            rest.len()
+        } else {
+            rest[..BORROWED_WINDOW]
+                .iter()
+                .rposition(is_space)
+                .or_else(|| {
+                    rest[BORROWED_WINDOW..]
+                        .iter()
+                        .position(is_space)
+                        .map(|space| BORROWED_WINDOW + space)
+                })
+                .map_or(rest.len(), |space| space + 1)
+        };
+
+        let offset = self.position;
+        let window = &self.text[offset..offset + length];
+        self.position += length;
+        self.buffer.clear();
+        self.next = 0;
+        let buffer = &mut self.buffer;
+        scan_spans(window, |start, end, kind| {
+            buffer.push(TokenRef {
+                text: &window[start..end],
+                kind,
+                start: offset + start,
+                end: offset + end,
+            });
+        });
+    }
+}
+
+impl<'a> Iterator for BorrowedTokens<'a> {
+    type Item = TokenRef<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.next == self.buffer.len() {
+            if self.position == self.text.len() {
+                return None;
+            }
+            self.next_window();
+        }
+        self.next += 1;
+        Some(self.buffer[self.next - 1])
+    }
+}
+
+/// Character classes used by scan_segment()
+/// Letters and digits are word characters and so are apostrophes and hyphens ("don't", "e-mail")
+const LETTER: u8 = 0;
+const DIGIT: u8 = 1;
+const JOINER: u8 = 2;
+const SPACE: u8 = 3;
+const PUNCTUATION: u8 = 4;
+
+/// The class of every ASCII character, so the common case is a single table lookup
+static ASCII_CLASSES: [u8; 128] = ascii_classes();
+
+/// Builds the ASCII class table at compile time
+const fn ascii_classes() -> [u8; 128] {
+    let mut classes = [PUNCTUATION; 128];
+    let mut byte = 0;
+    while byte < 128 {
+        let character = byte as u8 as char;
+        classes[byte] = if character.is_ascii_alphabetic() {
+            LETTER
+        } else if character.is_ascii_digit() {
+            DIGIT
+        } else if character == '\'' || character == '-' {
+            JOINER
+        } else if character.is_ascii_whitespace() || byte == 0x0B {
+            // 0x0B (vertical tab) is whitespace for char::is_whitespace() but not for is_ascii_whitespace()
+            SPACE
+        } else {
+            PUNCTUATION
+        };
+        byte += 1;
+    }
+    classes
+}
+
+/// The classes of the Basic Multilingual Plane, filled 256 characters at a time on first use
+///
+/// is_numeric() and is_alphabetic() search big Unicode tables and cost far more than the
+/// rest of the scan, so every block of 256 characters is classified once and then looked up.
+static UNICODE_BLOCKS: [OnceLock<[u8; 256]>; 256] = [const { OnceLock::new() }; 256];
+
+/// Returns the class of a non-ASCII character
+fn unicode_class(character: char) -> u8 {
+    let code = character as u32;
+    if code > 0xFFFF {
+        return classify_unicode(character);
+    }
+
+    let block = UNICODE_BLOCKS[(code >> 8) as usize].get_or_init(|| {
+        let mut classes = [PUNCTUATION; 256];
+        for (low, class) in classes.iter_mut().enumerate() {
+            if let Some(character) = char::from_u32((code & !0xFF) | low as u32) {
+                *class = classify_unicode(character);
+            }
+        }
+        classes
+    });
+    block[(code & 0xFF) as usize]
+}
+
+/// Classifies a character from its Unicode properties (the slow path of unicode_class())
+fn classify_unicode(character: char) -> u8 {
+    if character.is_numeric() {
+        DIGIT
+    } else if character.is_alphabetic() {
+        LETTER
+    } else if character.is_whitespace() {
+        SPACE
+    } else {
+        PUNCTUATION
+    }
+}
+
+/// Tokenizes one piece of text that contains no special tokens
+///
+/// # Arguments
+/// * `segment` - The piece of text to tokenize
+/// * `offset` - Byte offset of the segment in the original text (added to every token offset)
+/// * `tokens` - The vector the new tokens are pushed onto
+fn scan_segment(segment: &str, offset: usize, tokens: &mut Vec<Token>) {
+    scan_spans(segment, |start, end, kind| {
+        tokens.push(Token {
+            text: segment[start..end].to_string(),
+            kind,
+            start: offset + start,
+            end: offset + end,
+        });
+    });
+}
+
+/// Estimates how many tokens a text has, without tokenizing it
+///
+/// Short texts are counted exactly (for plain ASCII text without special tokens or
+/// identifiers), long ones are estimated from their first 4 KiB. This is much cheaper than
+/// tokenizing, and pre-sizes result vectors with Tokenizer::with_estimated_capacity().
+///
+/// # Arguments
+/// * `text` - The text
+///
+/// # Returns
+/// The estimated number of tokens
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::tokenizer::estimate_token_count;
+///
+/// assert_eq!(estimate_token_count("Hello, world!"), 4);
+/// let book = "It was the best of times, it was the worst of times. ".repeat(10_000);
+/// let estimate = estimate_token_count(&book);
+/// assert!(estimate > 130_000 && estimate < 150_000); // the exact count is 140,000
+/// ```
+pub fn estimate_token_count(text: &str) -> usize {
+    const SAMPLE_SIZE: usize = 4096;
+
+    let bytes = text.as_bytes();
+    let sample = &bytes[..bytes.len().min(SAMPLE_SIZE)];
+
+    // Count word starts and punctuation marks; non-ASCII bytes are counted as letters
+    let mut count = 0;
+    let mut in_word = false;
+    for &byte in sample {
+        let class = if byte < 128 {
+            ASCII_CLASSES[byte as usize]
+        } else {
+            LETTER
+        };
+        if class <= JOINER {
+            count += usize::from(!in_word);
+            in_word = true;
+        } else {
+            count += usize::from(class == PUNCTUATION);
+            in_word = false;
+        }
+    }
+
+    // Scale the sample count up to the whole text
+    if sample.len() == bytes.len() {
+        count
+    } else {
+        (count as u64 * bytes.len() as u64 / sample.len() as u64) as usize
+    }
+}
+
+/// Finds the tokens of a piece of text without allocating anything
+///
+/// This is the hot loop of the tokenizer, so it works on bytes: ASCII characters are
+/// classified with a lookup table, runs of ASCII letters and digits are skipped 8 bytes at a
+/// time, and only non-ASCII characters are decoded (and looked up in a cached class table).
+/// The kind of every word is worked out while scanning it, instead of looking at the word
+/// again afterwards.
+///
+/// # Arguments
+/// * `segment` - The piece of text to scan
+/// * `on_token` - Called with the (start, end) byte range and the kind of every token, in order
+pub(crate) fn scan_spans<F: FnMut(usize, usize, TokenKind)>(segment: &str, mut on_token: F) {
+    let bytes = segment.as_bytes();
+
+    // Byte offset where the word we are currently building started (None = no word in progress)
+    let mut word_start: Option<usize> = None;
+    // What we know about the word in progress, to compute its kind without a second pass
+    let mut all_digits = true;
+    let mut any_alphanumeric = false;
+
+    let mut position = 0;
+    while position < bytes.len() {
+        // STEP 1: Classify the character (table lookup for ASCII, decoding only for the rest)
+        let byte = bytes[position];
+        let (class, width) = if byte < 128 {
+            (ASCII_CLASSES[byte as usize], 1)
+        } else {
+            let character = segment[position..].chars().next().unwrap_or(' ');
+            (unicode_class(character), character.len_utf8())
+        };
+
+        // STEP 2: Word characters extend the current word
+        if class <= JOINER {
+            if word_start.is_none() {
+                word_start = Some(position);
+                all_digits = true;
+                any_alphanumeric = false;
+            }
+            all_digits &= class == DIGIT;
+            any_alphanumeric |= class != JOINER;
+
+            // Skip the rest of an ASCII word 8 bytes at a time
+            if class != JOINER {
+                let (length, letters) = ascii_alphanumeric_run(&bytes[position + 1..]);
+                all_digits &= !letters;
+                position += length;
+            }
+        } else {
+            // STEP 3: Whitespace or punctuation ends the current word
+            if let Some(start) = word_start.take() {
+                on_token(start, position, word_kind(all_digits, any_alphanumeric));
+            }
+
+            // Punctuation is its own token (whitespace is simply dropped)
+            if class == PUNCTUATION {
+                on_token(position, position + width, TokenKind::Punctuation);
+            }
+        }
+
+        position += width;
+    }
+
+    // After processing all characters, if there's still a token being built, save it
+    if let Some(start) = word_start {
+        on_token(start, bytes.len(), word_kind(all_digits, any_alphanumeric));
+    }
+}
+
+/// Measures the run of ASCII letters and digits at the start of some bytes, 8 bytes at a time
+///
+/// Eight bytes are loaded into one u64 and compared all at once (SWAR, "SIMD within a
+/// register"), which is much faster than classifying them one by one for long words.
+/// The last few bytes of the text (fewer than 8) are left to the byte loop.
+///
+/// # Returns
+/// The length of the run and whether it contains any letter
+fn ascii_alphanumeric_run(bytes: &[u8]) -> (usize, bool) {
+    const ONES: u64 = 0x0101_0101_0101_0101;
+    const HIGH: u64 = 0x8080_8080_8080_8080;
+
+    // Sets the high bit of every byte between lo and hi (bytes must be below 128)
+    let in_range = |x: u64, lo: u8, hi: u8| {
+        (x + ONES * (128 - lo as u64)) & !(x + ONES * (127 - hi as u64)) & HIGH
+    };
+
+    let mut length = 0;
+    let mut letters = false;
+    while let Some(chunk) = bytes.get(length..length + 8) {
+        let x = u64::from_le_bytes(chunk.try_into().unwrap_or([0; 8]));
+        if x & HIGH != 0 {
+            break;
+        }
+        let letter = in_range(x | (ONES * 0x20), b'a', b'z');
+        let digit = in_range(x, b'0', b'9');
+        let alphanumeric = letter | digit;
+        if alphanumeric != HIGH {
+            // Only keep the letters and digits before the first other byte
+            let run = (!alphanumeric & HIGH).trailing_zeros() as usize / 8;
+            let kept = (1u64 << (run * 8)) - 1;
+            letters |= letter & kept != 0;
+            length += run;
+            break;
+        }
+        letters |= letter != 0;
+        length += 8;
+    }
+
+    (length, letters)
+}
 
-        (total, words, punctuation, avg_length)
+/// Works out the kind of a word from what scan_segment() saw while scanning it
+/// (this gives the same result as TokenKind::of())
+fn word_kind(all_digits: bool, any_alphanumeric: bool) -> TokenKind {
+    if all_digits {
+        TokenKind::Number
+    } else if any_alphanumeric {
+        TokenKind::Word
+    } else {
+        TokenKind::Punctuation
     }
 }
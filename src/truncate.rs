@@ -0,0 +1,173 @@
+// Per-kind token length limits
+// Downstream systems often store tokens in fixed-size fields (a database column, a feature
+// slot of a model), and one pasted URL or base64 blob is enough to overflow them. A
+// LengthLimits filter caps the length of tokens per kind: a token over its limit is cut and
+// ends with a truncation marker, while its start and end offsets still cover the whole
+// original token, so the full text can always be recovered from the input.
+
+use std::collections::HashMap;
+
+use crate::manifest::{escape, ManifestStep};
+use crate::token::{Token, TokenKind};
+use crate::token_filter::TokenFilter;
+
+/// The marker that ends truncated tokens by default
+pub const DEFAULT_MARKER: &str = "…";
+
+/// Caps the length of tokens, in characters, per token kind
+///
+/// Kinds without a limit of their own use the default limit, if one is set.
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::Tokenizer;
+/// use tokenizer_rust::token::TokenKind;
+/// use tokenizer_rust::token_filter::TokenFilter;
+/// use tokenizer_rust::truncate::LengthLimits;
+///
+/// let text = "Key: ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789 okay";
+/// let limits = LengthLimits::new().with_limit(TokenKind::Word, 10);
+/// let tokens = limits.apply(Tokenizer::new(String::new()).tokenize_text(text));
+/// let texts: Vec<&str> = tokens.iter().map(|token| token.text.as_str()).collect();
+/// assert_eq!(texts, vec!["Key", ":", "ABCDEFGHI…", "okay"]);
+///
+/// // The span still covers the whole original token
+/// assert_eq!(&text[tokens[2].start..tokens[2].end], "ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789");
+/// ```
+#[derive(Debug, Clone)]
+pub struct LengthLimits {
+    /// The maximum number of characters of each kind that has a limit of its own
+    limits: HashMap<TokenKind, usize>,
+    /// The maximum number of characters of the other kinds (None = no limit)
+    default_limit: Option<usize>,
+    /// What a truncated token ends with
+    marker: String,
+}
+
+impl Default for LengthLimits {
+    fn default() -> Self {
+        LengthLimits {
+            limits: HashMap::new(),
+            default_limit: None,
+            marker: DEFAULT_MARKER.to_string(),
+        }
+    }
+}
+
+impl LengthLimits {
+    /// Creates a filter without any limit (every token is kept as it is)
+    pub fn new() -> Self {
+        LengthLimits::default()
+    }
+
+    /// Sets the maximum length of the tokens of one kind, in characters (marker included)
+    pub fn with_limit(mut self, kind: TokenKind, max_chars: usize) -> Self {
+        self.limits.insert(kind, max_chars);
+        self
+    }
+
+    /// Sets the maximum length of the tokens of every kind without a limit of its own
+    pub fn with_default_limit(mut self, max_chars: usize) -> Self {
+        self.default_limit = Some(max_chars);
+        self
+    }
+
+    /// Sets what a truncated token ends with ("…" by default, "" for no marker)
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::token::Token;
+    /// use tokenizer_rust::truncate::LengthLimits;
+    ///
+    /// let limits = LengthLimits::new().with_default_limit(8).with_marker("[...]");
+    /// let mut token = Token::new("internationalization", 0);
+    /// assert!(limits.truncate(&mut token));
+    /// assert_eq!(token.text, "int[...]");
+    /// assert_eq!((token.start, token.end), (0, 20));
+    /// ```
+    pub fn with_marker(mut self, marker: &str) -> Self {
+        self.marker = marker.to_string();
+        self
+    }
+
+    /// Returns the maximum length of the tokens of a kind, in characters, if it has one
+    pub fn limit(&self, kind: TokenKind) -> Option<usize> {
+        self.limits.get(&kind).copied().or(self.default_limit)
+    }
+
+    /// Returns the marker that ends truncated tokens
+    pub fn marker(&self) -> &str {
+        &self.marker
+    }
+
+    /// Cuts a token that is over the limit of its kind
+    ///
+    /// The text keeps as many characters as fit before the marker (if the limit is
+    /// shorter than the marker, the marker itself is cut). The kind and the offsets don't
+    /// change.
+    ///
+    /// # Returns
+    /// true if the token was truncated
+    pub fn truncate(&self, token: &mut Token) -> bool {
+        let Some(max_chars) = self.limit(token.kind) else {
+            return false;
+        };
+        // STEP 1: Find the byte where the text goes over the limit (None = it fits)
+        let Some((over, _)) = token.text.char_indices().nth(max_chars) else {
+            return false;
+        };
+
+        // STEP 2: Keep the characters that fit in front of the marker
+        let marker_chars = self.marker.chars().count();
+        let kept = max_chars.saturating_sub(marker_chars);
+        let kept_end = token.text.char_indices().nth(kept).map_or(over, |(i, _)| i);
+        token.text.truncate(kept_end);
+
+        // STEP 3: Add the marker, or as much of it as fits
+        let marker_end = self
+            .marker
+            .char_indices()
+            .nth(max_chars - kept)
+            .map_or(self.marker.len(), |(i, _)| i);
+        token.text.push_str(&self.marker[..marker_end]);
+        true
+    }
+
+    /// Rebuilds a filter from the step written by describe()
+    pub(crate) fn from_step(step: &ManifestStep) -> Option<Self> {
+        let mut limits = LengthLimits::new().with_marker(&step.value("marker")?);
+        for limit in step.values("limit") {
+            let (kind, max_chars) = limit.split_once(' ')?;
+            limits = limits.with_limit(TokenKind::from_name(kind)?, max_chars.parse().ok()?);
+        }
+        if let Some(max_chars) = step.value("default_limit") {
+            limits = limits.with_default_limit(max_chars.parse().ok()?);
+        }
+        Some(limits)
+    }
+}
+
+impl TokenFilter for LengthLimits {
+    fn apply(&self, mut tokens: Vec<Token>) -> Vec<Token> {
+        for token in &mut tokens {
+            self.truncate(token);
+        }
+        tokens
+    }
+
+    /// The limits are written in the order of TokenKind::ALL, so the same limits always
+    /// have the same hash
+    fn describe(&self) -> Option<ManifestStep> {
+        let mut step = ManifestStep::new("length_limits");
+        for kind in TokenKind::ALL {
+            if let Some(max_chars) = self.limits.get(&kind) {
+                step.push("limit", format!("{} {}", kind.name(), max_chars));
+            }
+        }
+        if let Some(max_chars) = self.default_limit {
+            step.push("default_limit", max_chars);
+        }
+        step.push("marker", escape(&self.marker));
+        Some(step)
+    }
+}
@@ -0,0 +1,202 @@
+// Vocabulary: the mapping between tokens and numbers (ids)
+// Machine learning models don't work with strings, so every token gets a unique id
+
+use std::collections::HashMap;
+
+use crate::stats::{FrequencyTable, TokenOrder};
+
+/// A bidirectional mapping between token strings and numeric ids
+///
+/// Ids are handed out in order (0, 1, 2, ...), so the id of a token is also its
+/// position in the vocabulary.
+#[derive(Debug, Clone, Default)]
+pub struct Vocabulary {
+    /// Looks up the id of a token (token -> id)
+    token_to_id: HashMap<String, u32>,
+    /// Looks up the token of an id (id -> token), the index in the vector is the id
+    id_to_token: Vec<String>,
+}
+
+impl Vocabulary {
+    /// Creates a new, empty vocabulary
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::vocab::Vocabulary;
+    ///
+    /// let vocab = Vocabulary::new();
+    /// assert!(vocab.is_empty());
+    /// ```
+    pub fn new() -> Self {
+        Vocabulary::default()
+    }
+
+    /// Builds a vocabulary from a list of tokens
+    ///
+    /// Each distinct token gets an id in the order it is first seen.
+    /// Duplicates are ignored, so you can pass a whole tokenized text.
+    /// Empty tokens are ignored too (tokenize() never produces them).
+    ///
+    /// # Arguments
+    /// * `tokens` - The tokens to put into the vocabulary
+    ///
+    /// # Returns
+    /// A vocabulary containing every distinct token
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::Tokenizer;
+    /// use tokenizer_rust::vocab::Vocabulary;
+    ///
+    /// let tokens = Tokenizer::new("the cat saw the dog".to_string()).tokenize();
+    /// let vocab = Vocabulary::from_tokens(&tokens);
+    /// // "the" is only stored once: ["the", "cat", "saw", "dog"]
+    /// assert_eq!(vocab.len(), 4);
+    /// ```
+    pub fn from_tokens(tokens: &[String]) -> Self {
+        let mut vocab = Vocabulary::new();
+        for token in tokens.iter().filter(|token| !token.is_empty()) {
+            vocab.add_token(token);
+        }
+        vocab
+    }
+
+    /// Adds a token to the vocabulary
+    ///
+    /// If the token is already known, nothing changes and its existing id is returned.
+    ///
+    /// # Arguments
+    /// * `token` - The token to add
+    ///
+    /// # Returns
+    /// The id of the token
+    pub fn add_token(&mut self, token: &str) -> u32 {
+        if let Some(&id) = self.token_to_id.get(token) {
+            return id;
+        }
+
+        // The new id is the next free position in the id -> token vector
+        let id = self.id_to_token.len() as u32;
+        self.id_to_token.push(token.to_string());
+        self.token_to_id.insert(token.to_string(), id);
+        id
+    }
+
+    /// Adds the frequent tokens of a FrequencyTable, most frequent first
+    ///
+    /// Ties are added in lexicographic order, so the same counts always give the same ids.
+    ///
+    /// # Arguments
+    /// * `frequencies` - The token counts of a corpus
+    /// * `min_count` - Tokens seen fewer times are left out
+    /// * `max_tokens` - The maximum number of corpus tokens to add, if limited
+    ///
+    /// # Returns
+    /// The number of tokens that were not in the vocabulary yet
+    ///
+    /// # Example
+    /// ```
+    /// use tokenizer_rust::stats::FrequencyTable;
+    /// use tokenizer_rust::vocab::Vocabulary;
+    ///
+    /// let mut frequencies = FrequencyTable::new();
+    /// frequencies.add_strings(&["to", "be", "or", "not", "to", "be"]);
+    ///
+    /// let mut vocab = Vocabulary::from_tokens(&["[UNK]".to_string()]);
+    /// assert_eq!(vocab.add_frequent(&frequencies, 2, None), 2);
+    /// assert_eq!(vocab.token_to_id("be"), Some(1));
+    /// assert!(!vocab.contains("or"));
+    /// ```
+    pub fn add_frequent(
+        &mut self,
+        frequencies: &FrequencyTable,
+        min_count: usize,
+        max_tokens: Option<usize>,
+    ) -> usize {
+        let before = self.len();
+        let frequent = frequencies
+            .sorted(TokenOrder::Frequency)
+            .into_iter()
+            .take_while(|&(_, count)| count >= min_count)
+            .take(max_tokens.unwrap_or(usize::MAX));
+        for (token, _) in frequent {
+            self.add_token(token);
+        }
+        self.len() - before
+    }
+
+    /// Returns the id of a token, or None if the token is not in the vocabulary
+    pub fn token_to_id(&self, token: &str) -> Option<u32> {
+        self.token_to_id.get(token).copied()
+    }
+
+    /// Returns the token of an id, or None if the id is not in the vocabulary
+    pub fn id_to_token(&self, id: u32) -> Option<&str> {
+        self.id_to_token
+            .get(id as usize)
+            .map(|token| token.as_str())
+    }
+
+    /// Returns true if the token is in the vocabulary
+    pub fn contains(&self, token: &str) -> bool {
+        self.token_to_id.contains_key(token)
+    }
+
+    /// Returns the number of tokens in the vocabulary
+    pub fn len(&self) -> usize {
+        self.id_to_token.len()
+    }
+
+    /// Returns true if the vocabulary has no tokens
+    pub fn is_empty(&self) -> bool {
+        self.id_to_token.is_empty()
+    }
+
+    /// Iterates over all (id, token) pairs in id order
+    pub fn iter(&self) -> impl Iterator<Item = (u32, &str)> {
+        self.id_to_token
+            .iter()
+            .enumerate()
+            .map(|(id, token)| (id as u32, token.as_str()))
+    }
+}
+
+/// A vocabulary is serialized as the list of its tokens in id order (the ids are the positions)
+///
+/// # Example
+/// ```
+/// use tokenizer_rust::vocab::Vocabulary;
+///
+/// let vocab = Vocabulary::from_tokens(&["the".to_string(), "cat".to_string()]);
+/// let json = serde_json::to_string(&vocab).unwrap();
+/// assert_eq!(json, r#"["the","cat"]"#);
+///
+/// let vocab: Vocabulary = serde_json::from_str(&json).unwrap();
+/// assert_eq!(vocab.token_to_id("cat"), Some(1));
+/// assert!(serde_json::from_str::<Vocabulary>(r#"["a","b","a"]"#).is_err());
+/// ```
+#[cfg(feature = "serde")]
+impl serde::Serialize for Vocabulary {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(&self.id_to_token)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Vocabulary {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let tokens = <Vec<String> as serde::Deserialize>::deserialize(deserializer)?;
+        let mut vocab = Vocabulary::new();
+        for token in &tokens {
+            // A repeated token would shift the ids of every token after it
+            if vocab.contains(token) {
+                return Err(serde::de::Error::custom(format!(
+                    "duplicate vocabulary token: {:?}",
+                    token
+                )));
+            }
+            vocab.add_token(token);
+        }
+        Ok(vocab)
+    }
+}
@@ -0,0 +1,126 @@
+// WebAssembly bindings (only compiled with the `wasm` feature)
+// A web page that estimates the length of a prompt before sending it should count exactly
+// what the server will count. This module exposes the tokenizer to JavaScript through
+// wasm-bindgen: the same rules, and with a tokenizer saved by `tokenizer build-vocab` (or
+// Tokenizer::save()) the same vocabulary and ids. Build it with
+//     wasm-pack build --target web --no-default-features --features wasm
+// (without the default features: there are no threads, files or memory maps in a browser)
+// and use it from JavaScript:
+//     import init, { Tokenizer, countTokens } from "./pkg/tokenizer_rust.js";
+//     await init();
+//     countTokens("Hello, world!");                      // 4
+//     const tokenizer = Tokenizer.fromJson(await (await fetch("model.json")).text());
+//     tokenizer.encode("Hello, world!");                 // Uint32Array of ids
+
+use wasm_bindgen::prelude::*;
+
+use crate::Tokenizer;
+
+/// A tokenizer for JavaScript, with the default rules or loaded from a saved configuration
+#[wasm_bindgen(js_name = Tokenizer)]
+pub struct WasmTokenizer {
+    /// The Rust tokenizer
+    tokenizer: Tokenizer,
+}
+
+#[wasm_bindgen(js_class = Tokenizer)]
+impl WasmTokenizer {
+    /// Creates a tokenizer with the default rules and no vocabulary
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> WasmTokenizer {
+        WasmTokenizer {
+            tokenizer: Tokenizer::new(String::new()),
+        }
+    }
+
+    /// Creates a tokenizer from the content of a file written by Tokenizer::save() (or
+    /// `tokenizer build-vocab`), with its special tokens, vocabulary and template
+    ///
+    /// # Returns
+    /// The tokenizer, or an Error with the reason the text isn't a valid configuration
+    #[wasm_bindgen(js_name = fromJson)]
+    pub fn from_json(text: &str) -> Result<WasmTokenizer, JsError> {
+        Tokenizer::from_json(text)
+            .map(|tokenizer| WasmTokenizer { tokenizer })
+            .map_err(|error| JsError::new(&error.to_string()))
+    }
+
+    /// Creates a tokenizer from a manifest saved by an older version
+    ///
+    /// # Returns
+    /// The tokenizer, or an Error with the reason the text isn't a valid manifest
+    #[wasm_bindgen(js_name = fromManifest)]
+    pub fn from_manifest(text: &str) -> Result<WasmTokenizer, JsError> {
+        Tokenizer::from_manifest(text)
+            .map(|tokenizer| WasmTokenizer { tokenizer })
+            .map_err(|error| JsError::new(&error.to_string()))
+    }
+
+    /// Returns the tokens of a text
+    pub fn tokenize(&self, text: &str) -> Vec<String> {
+        self.tokenizer
+            .tokenize_text(text)
+            .into_iter()
+            .map(|token| token.text)
+            .collect()
+    }
+
+    /// Joins tokens back into text
+    pub fn detokenize(&self, tokens: Vec<String>) -> String {
+        self.tokenizer.detokenize(&tokens)
+    }
+
+    /// Returns the ids a text is encoded into, with the special tokens of the template
+    pub fn encode(&self, text: &str) -> Vec<u32> {
+        self.tokenizer
+            .encode_batch(&[text])
+            .pop()
+            .map(|encoding| encoding.ids)
+            .unwrap_or_default()
+    }
+
+    /// Returns the text of a list of ids, without the special tokens
+    pub fn decode(&self, ids: Vec<u32>) -> String {
+        self.tokenizer.decode_skip_special(&ids)
+    }
+
+    /// Returns the number of tokens of a text (the length of tokenize(), computed without
+    /// building the tokens)
+    #[wasm_bindgen(js_name = countTokens)]
+    pub fn count_tokens(&self, text: &str) -> usize {
+        self.tokenizer.count_tokens(text)
+    }
+}
+
+impl Default for WasmTokenizer {
+    fn default() -> Self {
+        WasmTokenizer::new()
+    }
+}
+
+/// Returns the tokens of a text with the default rules
+#[wasm_bindgen]
+pub fn tokenize(text: &str) -> Vec<String> {
+    WasmTokenizer::new().tokenize(text)
+}
+
+/// Joins tokens back into text with the default rules
+#[wasm_bindgen]
+pub fn detokenize(tokens: Vec<String>) -> String {
+    WasmTokenizer::new().detokenize(tokens)
+}
+
+/// Returns the ids of a text in the vocabulary of a saved tokenizer (the content of its
+/// file, JSON or an older manifest), with the special tokens of its template
+#[wasm_bindgen]
+pub fn encode(saved: &str, text: &str) -> Result<Vec<u32>, JsError> {
+    let tokenizer =
+        Tokenizer::from_saved(saved).map_err(|error| JsError::new(&error.to_string()))?;
+    Ok(WasmTokenizer { tokenizer }.encode(text))
+}
+
+/// Returns the number of tokens of a text with the default rules
+#[wasm_bindgen(js_name = countTokens)]
+pub fn count_tokens(text: &str) -> usize {
+    Tokenizer::new(String::new()).count_tokens(text)
+}
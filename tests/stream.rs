@@ -0,0 +1,54 @@
+// Streaming must give the same tokens as tokenizing the whole text at once, even when a
+// gigantic line forces a cut at a single punctuation byte next to special or added tokens
+
+use tokenizer_rust::special::SpecialTokens;
+use tokenizer_rust::Tokenizer;
+
+/// A minified line of about 3.6 MB whose only cut points are punctuation bytes, most of them
+/// inside "<|endoftext|>" and "[SEP]"
+fn corpus() -> String {
+    r#"{"a":"b"}<|endoftext|>[SEP]"#.repeat(150_000)
+}
+
+fn tokenizer() -> Tokenizer {
+    let mut tokenizer = Tokenizer::new(String::new()).with_special_tokens(SpecialTokens::bert());
+    tokenizer.add_tokens(["<|endoftext|>"]);
+    tokenizer
+}
+
+#[test]
+fn reader_keeps_special_and_added_tokens_whole() {
+    let (tokenizer, text) = (tokenizer(), corpus());
+    let expected = tokenizer.tokenize_text(&text);
+    assert_eq!(expected.len(), 1_650_000);
+
+    for chunk_size in [64 * 1024, 4093] {
+        let streamed: Vec<_> = tokenizer
+            .tokenize_reader(text.as_bytes())
+            .with_chunk_size(chunk_size)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(streamed.len(), expected.len(), "chunk size {}", chunk_size);
+        assert!(streamed == expected, "chunk size {}", chunk_size);
+    }
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+fn mapped_file_keeps_special_and_added_tokens_whole() {
+    let (tokenizer, text) = (tokenizer(), corpus());
+    let path = std::env::temp_dir().join(format!("stream-specials-{}.txt", std::process::id()));
+    std::fs::write(&path, &text).unwrap();
+
+    let expected = tokenizer.tokenize_text(&text);
+    let mapped = tokenizer.tokenize_file(&path).unwrap();
+    let tokens: Vec<_> = mapped.tokens().collect::<Result<_, _>>().unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(tokens.len(), expected.len());
+    assert!(tokens
+        .iter()
+        .zip(&expected)
+        .all(|(token, expected)| token.text == expected.text
+            && (token.start, token.end) == (expected.start, expected.end)));
+}
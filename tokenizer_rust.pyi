@@ -0,0 +1,42 @@
+# Type hints of the `tokenizer_rust` Python module (src/python.rs)
+
+class Tokenizer:
+    """A tokenizer: the default rules, the special tokens of a model family, or a tokenizer
+    saved by `tokenizer build-vocab` (or Tokenizer::save()) with its vocabulary"""
+
+    def __init__(self, special_tokens: str | None = None) -> None:
+        """Creates a tokenizer with the default rules, keeping the special tokens of a model
+        family ("bert" or "roberta") whole"""
+    @staticmethod
+    def from_file(path: str) -> Tokenizer:
+        """Loads a tokenizer saved by save() or `tokenizer build-vocab`"""
+    @staticmethod
+    def from_json(text: str) -> Tokenizer:
+        """Loads a tokenizer from the content of a file written by save()"""
+    @staticmethod
+    def from_manifest(text: str) -> Tokenizer:
+        """Loads a tokenizer from a manifest saved by an older version"""
+    def save(self, path: str) -> None:
+        """Writes the tokenizer to a file, to load it again with from_file()"""
+    def tokenize(self, text: str) -> list[str]:
+        """Returns the tokens of a text"""
+    def detokenize(self, tokens: list[str]) -> str:
+        """Joins tokens back into text"""
+    def encode(self, text: str) -> list[int]:
+        """Returns the ids a text is encoded into, with the special tokens of the template"""
+    def decode(self, ids: list[int], skip_special_tokens: bool = True) -> str:
+        """Returns the text of a list of ids (without the special tokens unless asked)"""
+    def count_tokens(self, text: str) -> int:
+        """Returns the number of tokens of a text"""
+    def tokenize_batch(self, texts: list[str]) -> list[list[str]]:
+        """Returns the tokens of every text, without holding the GIL"""
+    def encode_batch(self, texts: list[str]) -> list[list[int]]:
+        """Returns the ids of every text (padded if the tokenizer pads batches), without
+        holding the GIL"""
+    def decode_batch(
+        self, ids: list[list[int]], skip_special_tokens: bool = True
+    ) -> list[str]:
+        """Returns the text of every list of ids, without holding the GIL"""
+    @property
+    def vocab_size(self) -> int:
+        """The number of tokens of the vocabulary"""